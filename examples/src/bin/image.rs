@@ -164,16 +164,11 @@ fn main() {
     });
 
 
-    let pipeline = vulkano::pipeline::GraphicsPipeline::new(&device, vulkano::pipeline::GraphicsPipelineParams {
-        vertex_input: vulkano::pipeline::vertex::SingleBufferDefinition::new(),
-        vertex_shader: vs.main_entry_point(),
-        input_assembly: vulkano::pipeline::input_assembly::InputAssembly {
-            topology: vulkano::pipeline::input_assembly::PrimitiveTopology::TriangleStrip,
-            primitive_restart_enable: false,
-        },
-        tessellation: None,
-        geometry_shader: None,
-        viewport: vulkano::pipeline::viewport::ViewportsState::Fixed {
+    let pipeline = vulkano::pipeline::GraphicsPipeline::start()
+        .vertex_input(vulkano::pipeline::vertex::SingleBufferDefinition::new())
+        .vertex_shader(vs.main_entry_point(), &())
+        .input_assembly(vulkano::pipeline::input_assembly::PrimitiveTopology::TriangleStrip)
+        .viewports(vulkano::pipeline::viewport::ViewportsState::Fixed {
             data: vec![(
                 vulkano::pipeline::viewport::Viewport {
                     origin: [0.0, 0.0],
@@ -182,15 +177,12 @@ fn main() {
                 },
                 vulkano::pipeline::viewport::Scissor::irrelevant()
             )],
-        },
-        raster: Default::default(),
-        multisample: vulkano::pipeline::multisample::Multisample::disabled(),
-        fragment_shader: fs.main_entry_point(),
-        depth_stencil: vulkano::pipeline::depth_stencil::DepthStencil::disabled(),
-        blend: vulkano::pipeline::blend::Blend::pass_through(),
-        layout: &pipeline_layout,
-        render_pass: vulkano::framebuffer::Subpass::from(&renderpass, 0).unwrap(),
-    }).unwrap();
+        })
+        .fragment_shader(fs.main_entry_point(), &())
+        .layout(&pipeline_layout)
+        .render_pass(vulkano::framebuffer::Subpass::from(&renderpass, 0).unwrap())
+        .build(&device)
+        .unwrap();
 
     let framebuffers = images.iter().map(|image| {
         let attachments = renderpass::AList {
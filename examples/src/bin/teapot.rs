@@ -71,7 +71,7 @@ fn main() {
     let cb_pool = vulkano::command_buffer::CommandBufferPool::new(&device, &queue.family());
 
 
-    let depth_buffer = vulkano::image::attachment::AttachmentImage::transient(&device, images[0].dimensions(), vulkano::format::D16Unorm).unwrap();
+    let depth_buffer = vulkano::image::attachment::AttachmentImage::depth_stencil(&device, images[0].dimensions(), vulkano::format::D16Unorm).unwrap();
 
     let vertex_buffer = vulkano::buffer::cpu_access::CpuAccessibleBuffer
                                ::array(&device, examples::VERTICES.len(),
@@ -168,13 +168,11 @@ fn main() {
         uniforms: &uniform_buffer
     });
 
-    let pipeline = vulkano::pipeline::GraphicsPipeline::new(&device, vulkano::pipeline::GraphicsPipelineParams {
-        vertex_input: vulkano::pipeline::vertex::TwoBuffersDefinition::new(),
-        vertex_shader: vs.main_entry_point(),
-        input_assembly: vulkano::pipeline::input_assembly::InputAssembly::triangle_list(),
-        tessellation: None,
-        geometry_shader: None,
-        viewport: vulkano::pipeline::viewport::ViewportsState::Fixed {
+    let pipeline = vulkano::pipeline::GraphicsPipeline::start()
+        .vertex_input(vulkano::pipeline::vertex::TwoBuffersDefinition::new())
+        .vertex_shader(vs.main_entry_point(), &())
+        .input_assembly(vulkano::pipeline::input_assembly::PrimitiveTopology::TriangleList)
+        .viewports(vulkano::pipeline::viewport::ViewportsState::Fixed {
             data: vec![(
                 vulkano::pipeline::viewport::Viewport {
                     origin: [0.0, 0.0],
@@ -183,15 +181,13 @@ fn main() {
                 },
                 vulkano::pipeline::viewport::Scissor::irrelevant()
             )],
-        },
-        raster: Default::default(),
-        multisample: vulkano::pipeline::multisample::Multisample::disabled(),
-        fragment_shader: fs.main_entry_point(),
-        depth_stencil: vulkano::pipeline::depth_stencil::DepthStencil::simple_depth_test(),
-        blend: vulkano::pipeline::blend::Blend::pass_through(),
-        layout: &pipeline_layout,
-        render_pass: vulkano::framebuffer::Subpass::from(&renderpass, 0).unwrap(),
-    }).unwrap();
+        })
+        .fragment_shader(fs.main_entry_point(), &())
+        .depth_stencil(vulkano::pipeline::depth_stencil::DepthStencil::simple_depth_test())
+        .layout(&pipeline_layout)
+        .render_pass(vulkano::framebuffer::Subpass::from(&renderpass, 0).unwrap())
+        .build(&device)
+        .unwrap();
 
     let framebuffers = images.iter().map(|image| {
         let attachments = renderpass::AList {
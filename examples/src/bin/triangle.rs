@@ -44,11 +44,7 @@ use vulkano::framebuffer::Framebuffer;
 use vulkano::framebuffer::Subpass;
 use vulkano::instance::Instance;
 use vulkano::pipeline::GraphicsPipeline;
-use vulkano::pipeline::GraphicsPipelineParams;
-use vulkano::pipeline::blend::Blend;
-use vulkano::pipeline::depth_stencil::DepthStencil;
-use vulkano::pipeline::input_assembly::InputAssembly;
-use vulkano::pipeline::multisample::Multisample;
+use vulkano::pipeline::input_assembly::PrimitiveTopology;
 use vulkano::pipeline::vertex::SingleBufferDefinition;
 use vulkano::pipeline::viewport::ViewportsState;
 use vulkano::pipeline::viewport::Viewport;
@@ -292,29 +288,23 @@ fn main() {
 
     // Before we draw we have to create what is called a pipeline. This is similar to an OpenGL
     // program, but much more specific.
-    let pipeline = GraphicsPipeline::new(&device, GraphicsPipelineParams {
+    let pipeline = GraphicsPipeline::start()
         // We need to indicate the layout of the vertices.
         // The type `SingleBufferDefinition` actually contains a template parameter corresponding
         // to the type of each vertex. But in this code it is automatically inferred.
-        vertex_input: SingleBufferDefinition::new(),
+        .vertex_input(SingleBufferDefinition::new())
 
         // A Vulkan shader can in theory contain multiple entry points, so we have to specify
         // which one. The `main` word of `main_entry_point` actually corresponds to the name of
-        // the entry point.
-        vertex_shader: vs.main_entry_point(),
+        // the entry point. The second parameter contains the values of the specialization
+        // constants of the shader, and our shader doesn't have any.
+        .vertex_shader(vs.main_entry_point(), &())
 
-        // `InputAssembly::triangle_list()` is a shortcut to build a `InputAssembly` struct that
-        // describes a list of triangles.
-        input_assembly: InputAssembly::triangle_list(),
-
-        // No tessellation shader.
-        tessellation: None,
-
-        // No geometry shader.
-        geometry_shader: None,
+        // The content of the vertex buffer describes a list of triangles.
+        .input_assembly(PrimitiveTopology::TriangleList)
 
         // TODO: switch to dynamic viewports and explain how it works
-        viewport: ViewportsState::Fixed {
+        .viewports(ViewportsState::Fixed {
             data: vec![(
                 Viewport {
                     origin: [0.0, 0.0],
@@ -324,36 +314,25 @@ fn main() {
                 },
                 Scissor::irrelevant()
             )],
-        },
-
-        // The `Raster` struct can be used to customize parameters such as polygon mode or backface
-        // culling.
-        raster: Default::default(),
-
-        // If we use multisampling, we can pass additional configuration.
-        multisample: Multisample::disabled(),
+        })
 
         // See `vertex_shader`.
-        fragment_shader: fs.main_entry_point(),
-
-        // `DepthStencil::disabled()` is a shortcut to build a `DepthStencil` struct that describes
-        // the fact that depth and stencil testing are disabled.
-        depth_stencil: DepthStencil::disabled(),
-
-        // `Blend::pass_through()` is a shortcut to build a `Blend` struct that describes the fact
-        // that colors must be directly transferred from the fragment shader output to the
-        // attachments without any change.
-        blend: Blend::pass_through(),
+        .fragment_shader(fs.main_entry_point(), &())
 
         // Shaders can usually access resources such as images or buffers. This parameters is here
         // to indicate the layout of the accessed resources, which is also called the *pipeline
         // layout*. Here we don't access anything, so we just create an `EmptyPipeline` object.
-        layout: &EmptyPipeline::new(&device).unwrap(),
+        .layout(&EmptyPipeline::new(&device).unwrap())
 
         // We have to indicate which subpass of which render pass this pipeline is going to be used
         // in. The pipeline will only be usable from this particular subpass.
-        render_pass: Subpass::from(&render_pass, 0).unwrap(),
-    }).unwrap();
+        .render_pass(Subpass::from(&render_pass, 0).unwrap())
+
+        // All the other parameters of the pipeline, such as the rasterization, the depth and
+        // stencil tests or the blending, keep their default values. They can be customized with
+        // the other methods of the builder.
+        .build(&device)
+        .unwrap();
 
     // The render pass we created above only describes the layout of our framebuffers. Before we
     // can draw we also need to create the actual framebuffers.
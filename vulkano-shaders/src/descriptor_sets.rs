@@ -49,8 +49,18 @@ pub fn write_descriptor_sets(doc: &parse::Spirv) -> String {
             }
         }).next().expect(&format!("Uniform `{}` is missing a binding", name));
 
+        // Find the input attachment index of this descriptor, if any.
+        let input_attachment_index = doc.instructions.iter().filter_map(|i| {
+            match i {
+                &parse::Instruction::Decorate { target_id, decoration: enums::Decoration::DecorationInputAttachmentIndex, ref params } if target_id == variable_id => {
+                    Some(params[0])
+                },
+                _ => None,
+            }
+        }).next();
+
         // Find informations about the kind of binding for this descriptor.
-        let (desc_ty, readonly) = descriptor_infos(doc, pointed_ty, false).expect(&format!("Couldn't find relevant type for uniform `{}` (type {}, maybe unimplemented)", name, pointed_ty));
+        let (desc_ty, readonly) = descriptor_infos(doc, pointed_ty, input_attachment_index, false).expect(&format!("Couldn't find relevant type for uniform `{}` (type {}, maybe unimplemented)", name, pointed_ty));
 
         descriptors.push(Descriptor {
             name: name,
@@ -79,6 +89,7 @@ pub fn write_descriptor_sets(doc: &parse::Spirv) -> String {
                                                 array_count: 1,
                                                 stages: stages.clone(),
                                                 readonly: {readonly},
+                                                immutable_samplers: None,
                                             }}", binding = d.binding, desc_ty = d.desc_ty,
                                                  readonly = if d.readonly { "true" } else { "false" })
                                })
@@ -140,8 +151,12 @@ fn pointer_variable_ty(doc: &parse::Spirv, variable: u32) -> u32 {
 /// Returns a `DescriptorDescTy` constructor and a bool indicating whether the descriptor is
 /// read-only.
 ///
+/// `input_attachment_index` is the value of the `InputAttachmentIndex` decoration of the
+/// variable, if any.
+///
 /// See also section 14.5.2 of the Vulkan specs: Descriptor Set Interface
-fn descriptor_infos(doc: &parse::Spirv, pointed_ty: u32, force_combined_image_sampled: bool)
+fn descriptor_infos(doc: &parse::Spirv, pointed_ty: u32, input_attachment_index: Option<u32>,
+                    force_combined_image_sampled: bool)
                     -> Option<(String, bool)>
 {
     doc.instructions.iter().filter_map(|i| {
@@ -200,11 +215,16 @@ fn descriptor_infos(doc: &parse::Spirv, pointed_ty: u32, force_combined_image_sa
                     assert!(if let &enums::ImageFormat::ImageFormatUnknown = format { true }
                             else { false }, "If Dim is SubpassData, Image Format must be Unknown");
                     assert!(!sampled, "If Dim is SubpassData, Sampled must be 2");
+                    let index = input_attachment_index.expect("Variables whose type is an \
+                                                               OpTypeImage of dimension \
+                                                               SubpassData must have an \
+                                                               InputAttachmentIndex decoration");
 
                     let desc = format!("DescriptorDescTy::InputAttachment {{
                                             multisampled: {},
-                                            array_layers: {}
-                                        }}", ms, arrayed);
+                                            array_layers: {},
+                                            index: Some({}),
+                                        }}", ms, arrayed, index);
 
                     Some((desc, true))
 
@@ -246,7 +266,7 @@ fn descriptor_infos(doc: &parse::Spirv, pointed_ty: u32, force_combined_image_sa
             &parse::Instruction::TypeSampledImage { result_id, image_type_id }
                                                                 if result_id == pointed_ty =>
             {
-                descriptor_infos(doc, image_type_id, true)
+                descriptor_infos(doc, image_type_id, None, true)
             },
 
             &parse::Instruction::TypeSampler { result_id } if result_id == pointed_ty => {
@@ -10,23 +10,16 @@ use vulkano::instance::Instance;
 use vulkano::instance::InstanceExtensions;
 use vulkano::swapchain::Surface;
 use vulkano::swapchain::SurfaceCreationError;
+use vulkano::swapchain::win;
 use winit::WindowBuilder;
 use winit::CreationError as WindowCreationError;
 
+/// Returns the extensions to enable on the instance in order to create windows.
+///
+/// See `vulkano::swapchain::win::required_extensions`.
+#[inline]
 pub fn required_extensions() -> InstanceExtensions {
-    let ideal = InstanceExtensions {
-        khr_surface: true,
-        khr_xlib_surface: true,
-        khr_xcb_surface: true,
-        khr_wayland_surface: true,
-        khr_mir_surface: true,
-        khr_android_surface: true,
-        khr_win32_surface: true,
-        .. InstanceExtensions::none()
-    };
-
-    let supported = InstanceExtensions::supported_by_core();
-    supported.intersection(&ideal)
+    win::required_extensions()
 }
 
 pub trait VkSurfaceBuild {
@@ -12,9 +12,10 @@
 //! The `CpuAccessibleBuffer` is a basic general-purpose buffer. It can be used in any situation
 //! but may not perform as well as other buffer types.
 //! 
-//! Each access from the CPU or from the GPU locks the whole buffer for either reading or writing.
-//! You can read the buffer multiple times simultaneously. Trying to read and write simultaneously,
-//! or write and write simultaneously will block.
+//! Each access from the CPU locks the whole buffer for either reading or writing, while each
+//! access from the GPU only locks the range of the buffer that it uses. You can read the buffer
+//! multiple times simultaneously. Trying to read and write simultaneously, or write and write
+//! simultaneously will block.
 
 use std::marker::PhantomData;
 use std::mem;
@@ -35,6 +36,7 @@ use buffer::sys::SparseLevel;
 use buffer::sys::UnsafeBuffer;
 use buffer::sys::Usage;
 use buffer::traits::AccessRange;
+use buffer::traits::AccessRanges;
 use buffer::traits::Buffer;
 use buffer::traits::GpuAccessResult;
 use buffer::traits::TypedBuffer;
@@ -43,15 +45,15 @@ use device::Device;
 use instance::QueueFamily;
 use memory::Content;
 use memory::CpuAccess as MemCpuAccess;
+use memory::MemoryTypePreference;
 use memory::pool::AllocLayout;
 use memory::pool::MemoryPool;
 use memory::pool::MemoryPoolAlloc;
+use memory::pool::MemoryPoolError;
 use memory::pool::StdMemoryPool;
 use sync::FenceWaitError;
 use sync::Sharing;
 
-use OomError;
-
 /// Buffer whose content is accessible by the CPU.
 #[derive(Debug)]
 pub struct CpuAccessibleBuffer<T: ?Sized, A = StdMemoryPool> where A: MemoryPool {
@@ -74,15 +76,15 @@ pub struct CpuAccessibleBuffer<T: ?Sized, A = StdMemoryPool> where A: MemoryPool
 
 #[derive(Debug)]
 struct LatestSubmission {
-    read_submissions: Mutex<Vec<Weak<Submission>>>,
-    write_submission: Option<Weak<Submission>>,         // TODO: can use `Weak::new()` once it's stabilized
+    // Submissions that use this buffer, with the ranges of bytes that each of them accesses.
+    submissions: Mutex<SmallVec<[(Weak<Submission>, AccessRanges); 4]>>,
 }
 
 impl<T> CpuAccessibleBuffer<T> {
     /// Builds a new buffer. Only allowed for sized data.
     #[inline]
     pub fn new<'a, I>(device: &Arc<Device>, usage: &Usage, queue_families: I)
-                      -> Result<Arc<CpuAccessibleBuffer<T>>, OomError>
+                      -> Result<Arc<CpuAccessibleBuffer<T>>, MemoryPoolError>
         where I: IntoIterator<Item = QueueFamily<'a>>
     {
         unsafe {
@@ -95,7 +97,7 @@ impl<T> CpuAccessibleBuffer<[T]> {
     /// Builds a new buffer. Can be used for arrays.
     #[inline]
     pub fn array<'a, I>(device: &Arc<Device>, len: usize, usage: &Usage, queue_families: I)
-                      -> Result<Arc<CpuAccessibleBuffer<[T]>>, OomError>
+                      -> Result<Arc<CpuAccessibleBuffer<[T]>>, MemoryPoolError>
         where I: IntoIterator<Item = QueueFamily<'a>>
     {
         unsafe {
@@ -112,7 +114,7 @@ impl<T: ?Sized> CpuAccessibleBuffer<T> {
     /// You must ensure that the size that you pass is correct for `T`.
     ///
     pub unsafe fn raw<'a, I>(device: &Arc<Device>, size: usize, usage: &Usage, queue_families: I)
-                             -> Result<Arc<CpuAccessibleBuffer<T>>, OomError>
+                             -> Result<Arc<CpuAccessibleBuffer<T>>, MemoryPoolError>
         where I: IntoIterator<Item = QueueFamily<'a>>
     {
         let queue_families = queue_families.into_iter().map(|f| f.id())
@@ -127,18 +129,17 @@ impl<T: ?Sized> CpuAccessibleBuffer<T> {
 
             match UnsafeBuffer::new(device, size, &usage, sharing, SparseLevel::none()) {
                 Ok(b) => b,
-                Err(BufferCreationError::OomError(err)) => return Err(err),
+                Err(BufferCreationError::OomError(err)) => return Err(err.into()),
                 Err(_) => unreachable!()        // We don't use sparse binding, therefore the other
                                                 // errors can't happen
             }
         };
 
-        let mem_ty = device.physical_device().memory_types()
-                           .filter(|t| (mem_reqs.memory_type_bits & (1 << t.id())) != 0)
-                           .filter(|t| t.is_host_visible())
-                           .next().unwrap();    // Vk specs guarantee that this can't fail
+        let pool = device.standard_pool();
+        let mem_ty = pool.choose_memory_type(&mem_reqs, MemoryTypePreference::HostVisibleOnly)
+                         .unwrap();    // Vk specs guarantee that this can't fail
 
-        let mem = try!(MemoryPool::alloc(&device.standard_pool(), mem_ty,
+        let mem = try!(MemoryPool::alloc(&pool, mem_ty,
                                          mem_reqs.size, mem_reqs.alignment, AllocLayout::Linear));
         debug_assert!((mem.offset() % mem_reqs.alignment) == 0);
         debug_assert!(mem.mapped_memory().is_some());
@@ -149,8 +150,7 @@ impl<T: ?Sized> CpuAccessibleBuffer<T> {
             memory: mem,
             queue_families: queue_families,
             latest_submission: RwLock::new(LatestSubmission {
-                read_submissions: Mutex::new(vec![]),
-                write_submission: None,
+                submissions: Mutex::new(SmallVec::new()),
             }),
             marker: PhantomData,
         }))
@@ -188,9 +188,18 @@ impl<T: ?Sized, A> CpuAccessibleBuffer<T, A> where T: Content + 'static, A: Memo
     pub fn read(&self, timeout: Duration) -> Result<ReadLock<T>, FenceWaitError> {
         let submission = self.latest_submission.read().unwrap();
 
-        // TODO: should that set the write_submission to None?
-        if let Some(submission) = submission.write_submission.clone().and_then(|s| s.upgrade()) {
-            try!(submission.wait(timeout));
+        // TODO: should that remove the submissions from the list?
+        {
+            let submissions = submission.submissions.lock().unwrap();
+            for &(ref weak, ref ranges) in submissions.iter() {
+                if !ranges.has_write() {
+                    continue;
+                }
+
+                if let Some(submission) = weak.upgrade() {
+                    try!(submission.wait(timeout));
+                }
+            }
         }
 
         let offset = self.memory.offset();
@@ -216,18 +225,14 @@ impl<T: ?Sized, A> CpuAccessibleBuffer<T, A> where T: Content + 'static, A: Memo
         let mut submission = self.latest_submission.write().unwrap();
 
         {
-            let mut read_submissions = submission.read_submissions.get_mut().unwrap();
-            for submission in read_submissions.drain(..) {
-                if let Some(submission) = submission.upgrade() {
+            let mut submissions = submission.submissions.get_mut().unwrap();
+            for (weak, _) in submissions.into_iter() {
+                if let Some(submission) = weak.upgrade() {
                     try!(submission.wait(timeout));
                 }
             }
         }
 
-        if let Some(submission) = submission.write_submission.take().and_then(|s| s.upgrade()) {
-            try!(submission.wait(timeout));
-        }
-
         let offset = self.memory.offset();
         let range = offset .. offset + self.inner.size();
 
@@ -275,33 +280,47 @@ unsafe impl<T: ?Sized, A> Buffer for CpuAccessibleBuffer<T, A>
                    queue_id, self.queue_families);
         }
 
-        let is_written = {
-            let mut written = false;
-            while let Some(r) = ranges.next() { if r.write { written = true; break; } }
-            written
+        let mut accesses = AccessRanges::new();
+        while let Some(r) = ranges.next() { accesses.add(r.range, r.write); }
+
+        // Writing requires an exclusive lock so that we don't write while the CPU is reading.
+        let write_lock;
+        let read_lock;
+        let latest: &LatestSubmission = if accesses.has_write() {
+            write_lock = self.latest_submission.write().unwrap();
+            &write_lock
+        } else {
+            read_lock = self.latest_submission.read().unwrap();
+            &read_lock
         };
 
-        let dependencies = if is_written {
-            let mut submissions = self.latest_submission.write().unwrap();
+        let mut submissions = latest.submissions.lock().unwrap();
 
-            let write_dep = mem::replace(&mut submissions.write_submission,
-                                         Some(Arc::downgrade(submission)));
+        let mut dependencies = Vec::new();
+        let mut remaining = SmallVec::new();
 
-            let mut read_submissions = submissions.read_submissions.get_mut().unwrap();
-            let read_submissions = mem::replace(&mut *read_submissions, Vec::new());
-            read_submissions.into_iter()
-                            .chain(write_dep.into_iter())
-                            .filter_map(|s| s.upgrade())
-                            .collect::<Vec<_>>()
+        for (weak, other) in submissions.into_iter() {
+            let other_submission = match weak.upgrade() {
+                Some(s) => s,
+                None => continue,
+            };
 
-        } else {
-            let submissions = self.latest_submission.read().unwrap();
+            if !other.conflicts_with(&accesses) {
+                remaining.push((weak, other));
+                continue;
+            }
 
-            let mut read_submissions = submissions.read_submissions.lock().unwrap();
-            read_submissions.push(Arc::downgrade(submission));
+            // If we overwrite everything that the other submission accesses, then any further
+            // access that conflicts with it also conflicts with us and we can forget about it.
+            if !accesses.covers_writes(&other) {
+                remaining.push((weak, other));
+            }
 
-            submissions.write_submission.clone().and_then(|s| s.upgrade()).into_iter().collect()
-        };
+            dependencies.push(other_submission);
+        }
+
+        remaining.push((Arc::downgrade(submission), accesses));
+        *submissions = remaining;
 
         GpuAccessResult {
             dependencies: dependencies,
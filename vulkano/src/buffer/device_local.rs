@@ -9,9 +9,10 @@
 
 //! Buffer whose content is read-written by the GPU only.
 //!
-//! Each access from the CPU or from the GPU locks the whole buffer for either reading or writing.
+//! Each access from the GPU locks the range of the buffer it uses for either reading or writing.
 //! You can read the buffer multiple times simultaneously from multiple queues. Trying to read and
-//! write simultaneously, or write and write simultaneously will block with a semaphore.
+//! write simultaneously, or write and write simultaneously the same bytes will block with a
+//! semaphore.
 
 use std::marker::PhantomData;
 use std::mem;
@@ -26,20 +27,21 @@ use buffer::sys::SparseLevel;
 use buffer::sys::UnsafeBuffer;
 use buffer::sys::Usage;
 use buffer::traits::AccessRange;
+use buffer::traits::AccessRanges;
 use buffer::traits::Buffer;
 use buffer::traits::GpuAccessResult;
 use buffer::traits::TypedBuffer;
 use command_buffer::Submission;
 use device::Device;
 use instance::QueueFamily;
+use memory::MemoryTypePreference;
 use memory::pool::AllocLayout;
 use memory::pool::MemoryPool;
 use memory::pool::MemoryPoolAlloc;
+use memory::pool::MemoryPoolError;
 use memory::pool::StdMemoryPool;
 use sync::Sharing;
 
-use OomError;
-
 /// Buffer whose content is accessible by the CPU.
 #[derive(Debug)]
 pub struct DeviceLocalBuffer<T: ?Sized, A = StdMemoryPool> where A: MemoryPool {
@@ -52,25 +54,18 @@ pub struct DeviceLocalBuffer<T: ?Sized, A = StdMemoryPool> where A: MemoryPool {
     // Queue families allowed to access this buffer.
     queue_families: SmallVec<[u32; 4]>,
 
-    // Latest submission that uses this buffer.
-    // Also used to block any attempt to submit this buffer while it is accessed by the CPU.
-    latest_submission: Mutex<LatestSubmission>,
+    // Submissions that use this buffer, with the ranges of bytes that each of them accesses.
+    latest_submissions: Mutex<SmallVec<[(Weak<Submission>, AccessRanges); 4]>>,
 
     // Necessary to make it compile.
     marker: PhantomData<Box<T>>,
 }
 
-#[derive(Debug)]
-struct LatestSubmission {
-    read_submissions: SmallVec<[Weak<Submission>; 4]>,
-    write_submission: Option<Weak<Submission>>,         // TODO: can use `Weak::new()` once it's stabilized
-}
-
 impl<T> DeviceLocalBuffer<T> {
     /// Builds a new buffer. Only allowed for sized data.
     #[inline]
     pub fn new<'a, I>(device: &Arc<Device>, usage: &Usage, queue_families: I)
-                      -> Result<Arc<DeviceLocalBuffer<T>>, OomError>
+                      -> Result<Arc<DeviceLocalBuffer<T>>, MemoryPoolError>
         where I: IntoIterator<Item = QueueFamily<'a>>
     {
         unsafe {
@@ -83,7 +78,7 @@ impl<T> DeviceLocalBuffer<[T]> {
     /// Builds a new buffer. Can be used for arrays.
     #[inline]
     pub fn array<'a, I>(device: &Arc<Device>, len: usize, usage: &Usage, queue_families: I)
-                      -> Result<Arc<DeviceLocalBuffer<[T]>>, OomError>
+                      -> Result<Arc<DeviceLocalBuffer<[T]>>, MemoryPoolError>
         where I: IntoIterator<Item = QueueFamily<'a>>
     {
         unsafe {
@@ -100,7 +95,7 @@ impl<T: ?Sized> DeviceLocalBuffer<T> {
     /// You must ensure that the size that you pass is correct for `T`.
     ///
     pub unsafe fn raw<'a, I>(device: &Arc<Device>, size: usize, usage: &Usage, queue_families: I)
-                             -> Result<Arc<DeviceLocalBuffer<T>>, OomError>
+                             -> Result<Arc<DeviceLocalBuffer<T>>, MemoryPoolError>
         where I: IntoIterator<Item = QueueFamily<'a>>
     {
         let queue_families = queue_families.into_iter().map(|f| f.id())
@@ -115,22 +110,17 @@ impl<T: ?Sized> DeviceLocalBuffer<T> {
 
             match UnsafeBuffer::new(device, size, &usage, sharing, SparseLevel::none()) {
                 Ok(b) => b,
-                Err(BufferCreationError::OomError(err)) => return Err(err),
+                Err(BufferCreationError::OomError(err)) => return Err(err.into()),
                 Err(_) => unreachable!()        // We don't use sparse binding, therefore the other
                                                 // errors can't happen
             }
         };
 
-        let mem_ty = {
-            let device_local = device.physical_device().memory_types()
-                                     .filter(|t| (mem_reqs.memory_type_bits & (1 << t.id())) != 0)
-                                     .filter(|t| t.is_device_local());
-            let any = device.physical_device().memory_types()
-                            .filter(|t| (mem_reqs.memory_type_bits & (1 << t.id())) != 0);
-            device_local.chain(any).next().unwrap()
-        };
+        let pool = device.standard_pool();
+        let mem_ty = pool.choose_memory_type(&mem_reqs, MemoryTypePreference::DeviceLocal)
+                         .unwrap();
 
-        let mem = try!(MemoryPool::alloc(&device.standard_pool(), mem_ty,
+        let mem = try!(MemoryPool::alloc(&pool, mem_ty,
                                          mem_reqs.size, mem_reqs.alignment, AllocLayout::Linear));
         debug_assert!((mem.offset() % mem_reqs.alignment) == 0);
         try!(buffer.bind_memory(mem.memory(), mem.offset()));
@@ -139,10 +129,7 @@ impl<T: ?Sized> DeviceLocalBuffer<T> {
             inner: buffer,
             memory: mem,
             queue_families: queue_families,
-            latest_submission: Mutex::new(LatestSubmission {
-                read_submissions: SmallVec::new(),
-                write_submission: None,
-            }),
+            latest_submissions: Mutex::new(SmallVec::new()),
             marker: PhantomData,
         }))
     }
@@ -202,32 +189,36 @@ unsafe impl<T: ?Sized, A> Buffer for DeviceLocalBuffer<T, A>
                    queue_id, self.queue_families);
         }
 
-        let is_written = {
-            let mut written = false;
-            while let Some(r) = ranges.next() { if r.write { written = true; break; } }
-            written
-        };
+        let mut accesses = AccessRanges::new();
+        while let Some(r) = ranges.next() { accesses.add(r.range, r.write); }
 
-        let mut submissions = self.latest_submission.lock().unwrap();
+        let mut submissions = self.latest_submissions.lock().unwrap();
 
-        let dependencies = if is_written {
-            let write_dep = mem::replace(&mut submissions.write_submission,
-                                         Some(Arc::downgrade(submission)));
+        let mut dependencies = Vec::new();
+        let mut remaining = SmallVec::new();
 
-            let mut read_submissions = mem::replace(&mut submissions.read_submissions,
-                                                    SmallVec::new());
+        for (weak, other) in submissions.into_iter() {
+            let other_submission = match weak.upgrade() {
+                Some(s) => s,
+                None => continue,
+            };
 
-            // We use a temporary variable to bypass a lifetime error in rustc.
-            let list = read_submissions.into_iter()
-                                       .chain(write_dep.into_iter())
-                                       .filter_map(|s| s.upgrade())
-                                       .collect::<Vec<_>>();
-            list
+            if !other.conflicts_with(&accesses) {
+                remaining.push((weak, other));
+                continue;
+            }
 
-        } else {
-            submissions.read_submissions.push(Arc::downgrade(submission));
-            submissions.write_submission.clone().and_then(|s| s.upgrade()).into_iter().collect()
-        };
+            // If we overwrite everything that the other submission accesses, then any further
+            // access that conflicts with it also conflicts with us and we can forget about it.
+            if !accesses.covers_writes(&other) {
+                remaining.push((weak, other));
+            }
+
+            dependencies.push(other_submission);
+        }
+
+        remaining.push((Arc::downgrade(submission), accesses));
+        *submissions = remaining;
 
         GpuAccessResult {
             dependencies: dependencies,
@@ -39,14 +39,14 @@ use buffer::traits::TypedBuffer;
 use command_buffer::Submission;
 use device::Device;
 use instance::QueueFamily;
+use memory::MemoryTypePreference;
 use memory::pool::AllocLayout;
 use memory::pool::MemoryPool;
 use memory::pool::MemoryPoolAlloc;
+use memory::pool::MemoryPoolError;
 use memory::pool::StdMemoryPool;
 use sync::Sharing;
 
-use OomError;
-
 /// Buffer that is written once then read for as long as it is alive.
 pub struct ImmutableBuffer<T: ?Sized, A = StdMemoryPool> where A: MemoryPool {
     // Inner content.
@@ -68,7 +68,7 @@ impl<T> ImmutableBuffer<T> {
     /// Builds a new buffer. Only allowed for sized data.
     #[inline]
     pub fn new<'a, I>(device: &Arc<Device>, usage: &Usage, queue_families: I)
-                      -> Result<Arc<ImmutableBuffer<T>>, OomError>
+                      -> Result<Arc<ImmutableBuffer<T>>, MemoryPoolError>
         where I: IntoIterator<Item = QueueFamily<'a>>
     {
         unsafe {
@@ -81,7 +81,7 @@ impl<T> ImmutableBuffer<[T]> {
     /// Builds a new buffer. Can be used for arrays.
     #[inline]
     pub fn array<'a, I>(device: &Arc<Device>, len: usize, usage: &Usage, queue_families: I)
-                      -> Result<Arc<ImmutableBuffer<[T]>>, OomError>
+                      -> Result<Arc<ImmutableBuffer<[T]>>, MemoryPoolError>
         where I: IntoIterator<Item = QueueFamily<'a>>
     {
         unsafe {
@@ -98,7 +98,7 @@ impl<T: ?Sized> ImmutableBuffer<T> {
     /// You must ensure that the size that you pass is correct for `T`.
     ///
     pub unsafe fn raw<'a, I>(device: &Arc<Device>, size: usize, usage: &Usage, queue_families: I)
-                             -> Result<Arc<ImmutableBuffer<T>>, OomError>
+                             -> Result<Arc<ImmutableBuffer<T>>, MemoryPoolError>
         where I: IntoIterator<Item = QueueFamily<'a>>
     {
         let queue_families = queue_families.into_iter().map(|f| f.id())
@@ -113,22 +113,17 @@ impl<T: ?Sized> ImmutableBuffer<T> {
 
             match UnsafeBuffer::new(device, size, &usage, sharing, SparseLevel::none()) {
                 Ok(b) => b,
-                Err(BufferCreationError::OomError(err)) => return Err(err),
+                Err(BufferCreationError::OomError(err)) => return Err(err.into()),
                 Err(_) => unreachable!()        // We don't use sparse binding, therefore the other
                                                 // errors can't happen
             }
         };
 
-        let mem_ty = {
-            let device_local = device.physical_device().memory_types()
-                                     .filter(|t| (mem_reqs.memory_type_bits & (1 << t.id())) != 0)
-                                     .filter(|t| t.is_device_local());
-            let any = device.physical_device().memory_types()
-                            .filter(|t| (mem_reqs.memory_type_bits & (1 << t.id())) != 0);
-            device_local.chain(any).next().unwrap()
-        };
+        let pool = device.standard_pool();
+        let mem_ty = pool.choose_memory_type(&mem_reqs, MemoryTypePreference::DeviceLocal)
+                         .unwrap();
 
-        let mem = try!(MemoryPool::alloc(&device.standard_pool(), mem_ty,
+        let mem = try!(MemoryPool::alloc(&pool, mem_ty,
                                          mem_reqs.size, mem_reqs.alignment, AllocLayout::Linear));
         debug_assert!((mem.offset() % mem_reqs.alignment) == 0);
         try!(buffer.bind_memory(mem.memory(), mem.offset()));
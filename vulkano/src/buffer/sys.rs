@@ -155,14 +155,19 @@ impl UnsafeBuffer {
         let vk = self.device.pointers();
 
         // We check for correctness in debug mode.
-        debug_assert!({
+        if cfg!(debug_assertions) {
             let mut mem_reqs = mem::uninitialized();
             vk.GetBufferMemoryRequirements(self.device.internal_object(), self.buffer,
                                            &mut mem_reqs);
-            mem_reqs.size <= (memory.size() - offset) as u64 &&
-            (offset as u64 % mem_reqs.alignment) == 0 &&
-            mem_reqs.memoryTypeBits & (1 << memory.memory_type().id()) != 0
-        });
+            let mem_reqs = MemoryRequirements::from(mem_reqs);
+            assert!(mem_reqs.size <= memory.size().saturating_sub(offset),
+                    "The memory is too small for the buffer");
+            assert!(offset % mem_reqs.alignment == 0,
+                    "The offset of the buffer in the memory is not aligned: offset {}, \
+                     alignment {}", offset, mem_reqs.alignment);
+            assert!(mem_reqs.prefers(memory.memory_type()),
+                    "The memory type is not allowed for the buffer");
+        }
 
         // Check for alignment correctness.
         {
@@ -453,6 +458,8 @@ pub enum BufferCreationError {
     SparseResidencyBufferFeatureNotEnabled,
     /// Sparse aliasing was requested but the corresponding feature wasn't enabled.
     SparseResidencyAliasedFeatureNotEnabled,
+    /// The Vulkan implementation returned an error that this operation isn't supposed to return.
+    Unexpected(Error),
 }
 
 impl error::Error for BufferCreationError {
@@ -469,6 +476,9 @@ impl error::Error for BufferCreationError {
             BufferCreationError::SparseResidencyAliasedFeatureNotEnabled => {
                 "sparse aliasing was requested but the corresponding feature wasn't enabled"
             },
+            BufferCreationError::Unexpected(_) => {
+                "unexpected error returned by the Vulkan implementation"
+            },
         }
     }
 
@@ -476,6 +486,7 @@ impl error::Error for BufferCreationError {
     fn cause(&self) -> Option<&error::Error> {
         match *self {
             BufferCreationError::OomError(ref err) => Some(err),
+            BufferCreationError::Unexpected(ref err) => Some(err),
             _ => None
         }
     }
@@ -501,7 +512,7 @@ impl From<Error> for BufferCreationError {
         match err {
             err @ Error::OutOfHostMemory => BufferCreationError::OomError(OomError::from(err)),
             err @ Error::OutOfDeviceMemory => BufferCreationError::OomError(OomError::from(err)),
-            _ => panic!("unexpected error: {:?}", err)
+            _ => BufferCreationError::Unexpected(err)
         }
     }
 }
@@ -7,8 +7,12 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
+use std::cmp;
 use std::ops::Range;
+use std::slice;
 use std::sync::Arc;
+use std::usize;
+use smallvec::SmallVec;
 
 use buffer::sys::UnsafeBuffer;
 use command_buffer::Submission;
@@ -45,7 +49,11 @@ pub unsafe trait Buffer: 'static + Send + Sync {
     ///                backing the buffer.
     fn block_memory_range(&self, block: usize) -> Range<usize>;
 
+    /// Called when a command buffer that uses this buffer is being submitted.
     ///
+    /// The `ranges` iterator contains the byte ranges of the buffer that the command buffer
+    /// accesses. The ranges are sorted and don't overlap. Implementations are encouraged to only
+    /// return as dependencies the submissions whose accesses conflict with these ranges.
     ///
     /// If the host is still accessing the buffer, this function implementation should block
     /// until it is no longer the case.
@@ -71,14 +79,294 @@ pub unsafe trait TypedBuffer: Buffer {
     }
 }
 
+/// Access to a range of bytes of a buffer.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct AccessRange {
-    pub block: usize,
+    /// Range of bytes within the buffer.
+    pub range: Range<usize>,
+    /// True if the range is written, false if it is only read.
     pub write: bool,
 }
 
+impl AccessRange {
+    /// Returns true if accessing both ranges simultaneously is a hazard, in other words if the
+    /// ranges overlap and at least one of them is written.
+    #[inline]
+    pub fn conflicts_with(&self, other: &AccessRange) -> bool {
+        (self.write || other.write) && self.range.start < other.range.end &&
+        other.range.start < self.range.end
+    }
+}
+
 pub struct GpuAccessResult {
     pub dependencies: Vec<Arc<Submission>>,
     pub additional_wait_semaphore: Option<Arc<Semaphore>>,
     pub additional_signal_semaphore: Option<Arc<Semaphore>>,
 }
+
+/// List of accesses to the bytes of a buffer.
+///
+/// The ranges are kept sorted and never overlap. Adjacent ranges with the same kind of access are
+/// merged together in order to keep the list small.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessRanges {
+    ranges: SmallVec<[AccessRange; 4]>,
+}
+
+impl AccessRanges {
+    /// Builds an empty list.
+    #[inline]
+    pub fn new() -> AccessRanges {
+        AccessRanges {
+            ranges: SmallVec::new(),
+        }
+    }
+
+    /// Builds a list that contains a single range.
+    #[inline]
+    pub fn from_range(range: Range<usize>, write: bool) -> AccessRanges {
+        let mut list = AccessRanges::new();
+        list.add(range, write);
+        list
+    }
+
+    /// Returns true if the list doesn't contain any range.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Returns true if at least one range of the list is written.
+    #[inline]
+    pub fn has_write(&self) -> bool {
+        self.ranges.iter().any(|r| r.write)
+    }
+
+    /// Returns the list of ranges, sorted by offset.
+    #[inline]
+    pub fn iter(&self) -> slice::Iter<AccessRange> {
+        self.ranges.iter()
+    }
+
+    /// Returns true if accessing `range` would conflict with one of the ranges of this list.
+    #[inline]
+    pub fn conflicts(&self, range: Range<usize>, write: bool) -> bool {
+        let access = AccessRange { range: range, write: write };
+        self.ranges.iter().any(|r| r.conflicts_with(&access))
+    }
+
+    /// Returns true if one of the ranges of `other` conflicts with one of the ranges of this list.
+    pub fn conflicts_with(&self, other: &AccessRanges) -> bool {
+        // Both lists are sorted, so we can walk them simultaneously.
+        let mut a = self.ranges.iter().peekable();
+        let mut b = other.ranges.iter().peekable();
+
+        loop {
+            let (ra, rb) = match (a.peek(), b.peek()) {
+                (Some(ra), Some(rb)) => (*ra, *rb),
+                _ => return false,
+            };
+
+            if ra.conflicts_with(rb) {
+                return true;
+            }
+
+            if ra.range.end <= rb.range.end {
+                a.next();
+            } else {
+                b.next();
+            }
+        }
+    }
+
+    /// Returns true if all the bytes covered by `other` are written by this list.
+    pub fn covers_writes(&self, other: &AccessRanges) -> bool {
+        other.ranges.iter().all(|o| {
+            let mut cursor = o.range.start;
+            for r in self.ranges.iter() {
+                if !r.write || r.range.end <= cursor { continue; }
+                if r.range.start > cursor { break; }
+                cursor = r.range.end;
+                if cursor >= o.range.end { break; }
+            }
+            cursor >= o.range.end
+        })
+    }
+
+    /// Adds an access to the list. Parts that overlap an existing range are considered written
+    /// if either of the two accesses is a write.
+    pub fn add(&mut self, range: Range<usize>, write: bool) {
+        if range.start >= range.end {
+            return;
+        }
+
+        // Fast path for accesses that are after all the existing ranges.
+        let append = self.ranges.last().map_or(true, |last| last.range.end <= range.start);
+        if append {
+            push_coalesce(&mut self.ranges, AccessRange { range: range, write: write });
+            return;
+        }
+
+        let access = [AccessRange { range: range, write: write }];
+        self.ranges = union(&self.ranges, &access);
+    }
+
+    /// Adds all the accesses of `other` to this list.
+    #[inline]
+    pub fn merge(&mut self, other: &AccessRanges) {
+        if other.ranges.is_empty() {
+            return;
+        }
+
+        self.ranges = union(&self.ranges, &other.ranges);
+    }
+}
+
+/// Pushes an access at the end of a sorted list, merging it with the last element if possible.
+#[inline]
+fn push_coalesce(list: &mut SmallVec<[AccessRange; 4]>, access: AccessRange) {
+    if let Some(last) = list.last_mut() {
+        if last.range.end == access.range.start && last.write == access.write {
+            last.range.end = access.range.end;
+            return;
+        }
+    }
+
+    list.push(access);
+}
+
+/// Merges two sorted lists of non-overlapping ranges into one.
+fn union(a: &[AccessRange], b: &[AccessRange]) -> SmallVec<[AccessRange; 4]> {
+    let mut result = SmallVec::new();
+    let mut ia = 0;
+    let mut ib = 0;
+    let mut cursor = 0;
+
+    loop {
+        while ia < a.len() && a[ia].range.end <= cursor { ia += 1; }
+        while ib < b.len() && b[ib].range.end <= cursor { ib += 1; }
+
+        let ra = a.get(ia);
+        let rb = b.get(ib);
+
+        // Start of the next piece of output. At least one range covers this position.
+        let start = match (ra, rb) {
+            (Some(ra), Some(rb)) => cmp::min(ra.range.start, rb.range.start),
+            (Some(r), None) | (None, Some(r)) => r.range.start,
+            (None, None) => break,
+        };
+        let start = cmp::max(start, cursor);
+
+        // The piece ends at the nearest boundary after `start`.
+        let mut end = usize::MAX;
+        let mut write = false;
+        for r in ra.into_iter().chain(rb.into_iter()) {
+            if r.range.start > start {
+                end = cmp::min(end, r.range.start);
+            } else {
+                end = cmp::min(end, r.range.end);
+                write = write || r.write;
+            }
+        }
+
+        push_coalesce(&mut result, AccessRange { range: start .. end, write: write });
+        cursor = end;
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AccessRange;
+    use super::AccessRanges;
+
+    #[test]
+    fn disjoint() {
+        let list = AccessRanges::from_range(0 .. 16, true);
+        assert!(!list.conflicts(32 .. 48, true));
+        assert!(!list.conflicts_with(&AccessRanges::from_range(64 .. 128, true)));
+    }
+
+    #[test]
+    fn touching() {
+        let list = AccessRanges::from_range(0 .. 16, true);
+        assert!(!list.conflicts(16 .. 32, true));
+        assert!(!AccessRanges::from_range(16 .. 32, true).conflicts_with(&list));
+    }
+
+    #[test]
+    fn nested() {
+        let list = AccessRanges::from_range(0 .. 64, false);
+        assert!(list.conflicts(16 .. 32, true));
+        assert!(!list.conflicts(16 .. 32, false));
+        assert!(AccessRanges::from_range(16 .. 32, true).conflicts_with(&list));
+    }
+
+    #[test]
+    fn identical() {
+        let list = AccessRanges::from_range(0 .. 16, false);
+        assert!(!list.conflicts(0 .. 16, false));
+        assert!(list.conflicts(0 .. 16, true));
+        assert!(AccessRanges::from_range(0 .. 16, true).conflicts(0 .. 16, true));
+    }
+
+    #[test]
+    fn coalesce_adjacent() {
+        let mut list = AccessRanges::new();
+        list.add(0 .. 4, true);
+        list.add(8 .. 12, true);
+        list.add(4 .. 8, true);
+        assert_eq!(list.iter().cloned().collect::<Vec<_>>(),
+                   vec![AccessRange { range: 0 .. 12, write: true }]);
+    }
+
+    #[test]
+    fn overlap_splits() {
+        let mut list = AccessRanges::new();
+        list.add(0 .. 16, false);
+        list.add(4 .. 8, true);
+        assert_eq!(list.iter().cloned().collect::<Vec<_>>(),
+                   vec![AccessRange { range: 0 .. 4, write: false },
+                        AccessRange { range: 4 .. 8, write: true },
+                        AccessRange { range: 8 .. 16, write: false }]);
+
+        list.add(2 .. 20, false);
+        assert_eq!(list.iter().cloned().collect::<Vec<_>>(),
+                   vec![AccessRange { range: 0 .. 4, write: false },
+                        AccessRange { range: 4 .. 8, write: true },
+                        AccessRange { range: 8 .. 20, write: false }]);
+    }
+
+    #[test]
+    fn covers_writes() {
+        let mut list = AccessRanges::from_range(0 .. 8, true);
+        list.add(8 .. 16, true);
+        assert!(list.covers_writes(&AccessRanges::from_range(4 .. 12, false)));
+        assert!(!list.covers_writes(&AccessRanges::from_range(4 .. 20, false)));
+        assert!(!AccessRanges::from_range(0 .. 8, false)
+                                .covers_writes(&AccessRanges::from_range(0 .. 8, false)));
+    }
+
+    #[test]
+    fn many_small_ranges() {
+        // Every other 4-bytes chunk of a 64kB buffer is written.
+        let mut written = AccessRanges::new();
+        for i in 0 .. 8192 {
+            written.add(i * 8 .. i * 8 + 4, true);
+        }
+        assert_eq!(written.iter().count(), 8192);
+
+        // The other chunks can be accessed without conflict.
+        let mut other = AccessRanges::new();
+        for i in 0 .. 8192 {
+            other.add(i * 8 + 4 .. i * 8 + 8, true);
+        }
+        assert!(!written.conflicts_with(&other));
+
+        // Filling the holes coalesces everything into a single range.
+        written.merge(&other);
+        assert_eq!(written.iter().cloned().collect::<Vec<_>>(),
+                   vec![AccessRange { range: 0 .. 65536, write: true }]);
+    }
+}
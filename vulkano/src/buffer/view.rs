@@ -94,22 +94,16 @@ impl<F, B> BufferView<F, B> where B: Buffer {
             return Err(BufferViewCreationError::WrongBufferUsage);
         }
 
-        let format_props = {
-            let vk_i = device.instance().pointers();
-            let mut output = mem::uninitialized();
-            vk_i.GetPhysicalDeviceFormatProperties(device.physical_device().internal_object(),
-                                                   format as u32, &mut output);
-            output.bufferFeatures
-        };
+        let format_props = format.properties(&device.physical_device()).buffer_features;
 
         if buffer.buffer().inner_buffer().usage_uniform_texel_buffer() {
-            if (format_props & vk::FORMAT_FEATURE_UNIFORM_TEXEL_BUFFER_BIT) == 0 {
+            if !format_props.uniform_texel_buffer {
                 return Err(BufferViewCreationError::UnsupportedFormat);
             }
         }
 
         if buffer.buffer().inner_buffer().usage_storage_texel_buffer() {
-            if (format_props & vk::FORMAT_FEATURE_STORAGE_TEXEL_BUFFER_BIT) == 0 {
+            if !format_props.storage_texel_buffer {
                 return Err(BufferViewCreationError::UnsupportedFormat);
             }
         }
@@ -136,11 +130,16 @@ impl<F, B> BufferView<F, B> where B: Buffer {
             view: view,
             buffer: buffer.resource.clone(),
             marker: PhantomData,
-            atomic_accesses: (format_props &
-                              vk::FORMAT_FEATURE_STORAGE_TEXEL_BUFFER_ATOMIC_BIT) != 0,
+            atomic_accesses: format_props.storage_texel_buffer_atomic,
         }))
     }
 
+    /// Returns the buffer that this view is a view of.
+    #[inline]
+    pub fn buffer(&self) -> &Arc<B> {
+        &self.buffer
+    }
+
     /// Returns true if the buffer view can be used as a uniform texel buffer.
     #[inline]
     pub fn uniform_texel_buffer(&self) -> bool {
@@ -160,6 +159,8 @@ impl<F, B> BufferView<F, B> where B: Buffer {
     }
 }
 
+impl_debug_handle!(BufferView<F, B> where B: Buffer);
+
 unsafe impl<F, B> VulkanObject for BufferView<F, B> where B: Buffer {
     type Object = vk::BufferView;
 
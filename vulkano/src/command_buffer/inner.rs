@@ -7,11 +7,15 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
+use std::cmp;
 use std::collections::HashMap;
 use std::collections::hash_map::Entry;
+use std::error;
 use std::fmt;
 use std::hash;
 use std::hash::BuildHasherDefault;
+use std::io;
+use std::io::Write;
 use std::mem;
 use std::ops::Range;
 use std::ptr;
@@ -26,23 +30,34 @@ use buffer::Buffer;
 use buffer::BufferSlice;
 use buffer::TypedBuffer;
 use buffer::traits::AccessRange as BufferAccessRange;
+use buffer::traits::AccessRanges as BufferAccessRanges;
 use command_buffer::CommandBufferPool;
 use command_buffer::DrawIndirectCommand;
 use command_buffer::DynamicState;
+use descriptor::descriptor_set::DescriptorSet;
 use descriptor::descriptor_set::DescriptorSetsCollection;
+use descriptor::descriptor_set::UnsafeDescriptorSetLayout;
+use descriptor::descriptor_set::check_dynamic_offsets;
 use descriptor::PipelineLayout;
+use descriptor::pipeline_layout::PipelineLayoutDescPcRange;
+use descriptor::pipeline_layout::UnsafePipelineLayout;
+use descriptor::pipeline_layout::layouts_compatible;
+use descriptor::pipeline_layout::push_constants_updates;
 use device::Queue;
 use format::ClearValue;
+use format::ClearValueMismatch;
 use format::FormatDesc;
 use format::FormatTy;
-use format::PossibleFloatFormatDesc;
 use framebuffer::RenderPass;
 use framebuffer::RenderPassDesc;
 use framebuffer::Framebuffer;
 use framebuffer::Subpass;
 use image::Image;
 use image::ImageView;
+use image::sys::ImageAspects;
 use image::sys::Layout as ImageLayout;
+use image::sys::check_buffer_image_copy;
+use image::sys::check_buffer_image_copy_layout;
 use image::traits::ImageClearValue;
 use image::traits::ImageContent;
 use image::traits::AccessRange as ImageAccessRange;
@@ -51,8 +66,13 @@ use pipeline::GraphicsPipeline;
 use pipeline::input_assembly::Index;
 use pipeline::vertex::Definition as VertexDefinition;
 use pipeline::vertex::Source as VertexSource;
+use query::QueryPool;
+use query::QueryType;
 use sync::Fence;
+use sync::FenceGuard;
+use sync::FencePool;
 use sync::FenceWaitError;
+use sync::PipelineStages;
 use sync::Semaphore;
 
 use device::Device;
@@ -131,6 +151,27 @@ pub struct InnerCommandBufferBuilder {
 
     // Current state of the dynamic state within the command buffer. Includes all staging commands.
     current_dynamic_state: DynamicState,
+
+    // Descriptor sets bound to the graphics bind point. Includes all staging commands.
+    current_graphics_descriptor_sets: BoundDescriptorSets,
+
+    // Descriptor sets bound to the compute bind point. Includes all staging commands.
+    current_compute_descriptor_sets: BoundDescriptorSets,
+
+    // Layout of each pipeline used by this command buffer, as passed to the descriptor sets
+    // bookkeeping. The pipelines are kept alive, so their handles can't be reused.
+    bound_layouts: HashMap<vk::Pipeline, Arc<BoundPipelineLayout>, BuildHasherDefault<FnvHasher>>,
+
+    // Number of layers of the framebuffer of the current render pass, or 1 outside of a render
+    // pass.
+    current_framebuffer_layers: u32,
+
+    // Index of the subpass that is being recorded, or `None` outside of a render pass.
+    current_subpass: Option<u32>,
+
+    // Queries that have been begun but not ended yet, with the type of their pool and the subpass
+    // they were begun in.
+    active_queries: SmallVec<[(vk::QueryPool, u32, vk::QueryType, Option<u32>); 4]>,
 }
 
 impl InnerCommandBufferBuilder {
@@ -226,6 +267,12 @@ impl InnerCommandBufferBuilder {
             current_graphics_pipeline: None,
             current_compute_pipeline: None,
             current_dynamic_state: DynamicState::none(),
+            current_graphics_descriptor_sets: BoundDescriptorSets::new(),
+            current_compute_descriptor_sets: BoundDescriptorSets::new(),
+            bound_layouts: HashMap::with_hasher(BuildHasherDefault::<FnvHasher>::default()),
+            current_framebuffer_layers: secondary_cont_fb.map(|fb| fb.layers()).unwrap_or(1),
+            current_subpass: secondary_cont.as_ref().map(|sp| sp.index()),
+            active_queries: SmallVec::new(),
         })
     }
 
@@ -251,8 +298,8 @@ impl InnerCommandBufferBuilder {
             // Flushing if required.
             let mut conflict = false;
             for (buffer, access) in cb.buffers_state.iter() {
-                if let Some(&entry) = self.staging_required_buffer_accesses.get(&buffer) {
-                    if entry.write || access.write {
+                if let Some(entry) = self.staging_required_buffer_accesses.get(&buffer) {
+                    if entry.ranges.conflicts_with(&access.ranges) {
                         conflict = true;
                         break;
                     }
@@ -260,8 +307,7 @@ impl InnerCommandBufferBuilder {
             }
             for (image, access) in cb.images_state.iter() {
                 if let Some(entry) = self.staging_required_image_accesses.get(&image) {
-                    // TODO: should be reviewed
-                    if entry.write || access.write || entry.new_layout != access.old_layout {
+                    if entry.new_layout != access.old_layout || entry.conflicts_with(access) {
                         conflict = true;
                         break;
                     }
@@ -279,7 +325,7 @@ impl InnerCommandBufferBuilder {
                         let mut entry = entry.get_mut();
                         entry.stages &= access.stages;
                         entry.accesses &= access.stages;
-                        entry.write = entry.write || access.write;
+                        entry.ranges.merge(&access.ranges);
                     }
                 }
             }
@@ -295,6 +341,7 @@ impl InnerCommandBufferBuilder {
                         entry.write = entry.write || access.write;
                         debug_assert_eq!(entry.new_layout, access.old_layout);
                         entry.aspects |= access.aspects;
+                        entry.merge_subresources(access);
                         entry.new_layout = access.new_layout;
                     }
                 }
@@ -337,6 +384,8 @@ impl InnerCommandBufferBuilder {
         self.current_graphics_pipeline = None;
         self.current_compute_pipeline = None;
         self.current_dynamic_state = DynamicState::none();
+        self.current_graphics_descriptor_sets = BoundDescriptorSets::new();
+        self.current_compute_descriptor_sets = BoundDescriptorSets::new();
 
         self
     }
@@ -362,11 +411,18 @@ impl InnerCommandBufferBuilder {
 
         let buffer = buffer.into();
 
-        assert_eq!(buffer.size(), mem::size_of_val(data));
-        assert!(buffer.size() <= 65536);
-        assert!(buffer.offset() % 4 == 0);
-        assert!(buffer.size() % 4 == 0);
-        assert!(buffer.buffer().inner_buffer().usage_transfer_dest());
+        assert_eq!(buffer.size(), mem::size_of_val(data),
+                   "The size of the data doesn't match the size of the buffer slice");
+        assert!(buffer.size() <= 65536,
+                "Can't update more than 65536 bytes at once (tried to update {} bytes)",
+                buffer.size());
+        assert!(buffer.offset() % 4 == 0,
+                "The offset of the updated range must be a multiple of 4 (got {})",
+                buffer.offset());
+        assert!(buffer.size() % 4 == 0,
+                "The size of the updated range must be a multiple of 4 (got {})", buffer.size());
+        assert!(buffer.buffer().inner_buffer().usage_transfer_dest(),
+                "The buffer wasn't created with the transfer_dest usage");
 
         // FIXME: check queue family of the buffer
 
@@ -411,11 +467,17 @@ impl InnerCommandBufferBuilder {
     {
         debug_assert!(self.render_pass_staging_commands.is_empty());
 
-        assert!(self.pool.queue_family().supports_transfers());
-        assert!(offset + size <= buffer.size());
-        assert!(offset % 4 == 0);
-        assert!(size % 4 == 0);
-        assert!(buffer.inner_buffer().usage_transfer_dest());
+        assert!(self.pool.queue_family().supports_transfers(),
+                "The queue family doesn't support transfer operations");
+        assert!(offset.checked_add(size).map_or(false, |end| end <= buffer.size()),
+                "The filled range of {} bytes at offset {} exceeds the size of the buffer \
+                 ({} bytes)", size, offset, buffer.size());
+        assert!(offset % 4 == 0, "The offset of the filled range must be a multiple of 4 (got {})",
+                offset);
+        assert!(size % 4 == 0, "The size of the filled range must be a multiple of 4 (got {})",
+                size);
+        assert!(buffer.inner_buffer().usage_transfer_dest(),
+                "The buffer wasn't created with the transfer_dest usage");
 
         self.add_buffer_resource_outside(buffer.clone() as Arc<_>, true, offset .. offset + size,
                                          vk::PIPELINE_STAGE_TRANSFER_BIT,
@@ -443,6 +505,7 @@ impl InnerCommandBufferBuilder {
     ///
     /// - Panicks if the buffers don't belong to the same device.
     /// - Panicks if one of the buffers wasn't created with the right usage.
+    /// - Panicks if the destination is smaller than the source.
     ///
     /// # Safety
     ///
@@ -458,9 +521,15 @@ impl InnerCommandBufferBuilder {
         debug_assert!(self.render_pass_staging_commands.is_empty());
 
         assert_eq!(&**source.inner_buffer().device() as *const _,
-                   &**destination.inner_buffer().device() as *const _);
-        assert!(source.inner_buffer().usage_transfer_src());
-        assert!(destination.inner_buffer().usage_transfer_dest());
+                   &**destination.inner_buffer().device() as *const _,
+                   "The source and destination buffers don't belong to the same device");
+        assert!(source.inner_buffer().usage_transfer_src(),
+                "The source buffer wasn't created with the transfer_src usage");
+        assert!(destination.inner_buffer().usage_transfer_dest(),
+                "The destination buffer wasn't created with the transfer_dest usage");
+        assert!(destination.size() >= source.size(),
+                "The destination buffer ({} bytes) is smaller than the source buffer ({} bytes)",
+                destination.size(), source.size());
 
         self.add_buffer_resource_outside(source.clone() as Arc<_>, false, 0 .. source.size(),
                                          vk::PIPELINE_STAGE_TRANSFER_BIT,
@@ -470,7 +539,7 @@ impl InnerCommandBufferBuilder {
                                          vk::ACCESS_TRANSFER_WRITE_BIT);
 
         {
-            let source_size = source.size() as u64;     // FIXME: what is destination is too small?
+            let source_size = source.size() as u64;
             let source = source.inner_buffer().internal_object();
             let destination = destination.inner_buffer().internal_object();
 
@@ -501,9 +570,38 @@ impl InnerCommandBufferBuilder {
     {
         debug_assert!(self.render_pass_staging_commands.is_empty());
 
-        assert!(image.format().is_float()); // FIXME: should accept uint and int images too
+        let format = image.format();
+        assert!(!format.has_depth() && !format.has_stencil() &&
+                format.ty() != FormatTy::Compressed);
+        assert!(image.inner_image().usage_transfer_dest());
 
-        let color = image.decode(color).unwrap(); /* FIXME: error */
+        let color = match image.decode(color) {
+            Some(c) => c,
+            None => panic!("The clear value doesn't match the format of the image")
+        };
+
+        // `decode` is implemented by the image, so we can't trust it blindly.
+        if let Err(err) = ClearValueMismatch::check(format, color) {
+            panic!("{}", err);
+        }
+
+        let levels = image.inner_image().mipmap_levels();
+        let layers = image.dimensions().array_layers();
+
+        // Images that are always kept in the `General` layout (like storage images) are cleared
+        // in place instead of being transitioned to `TransferDstOptimal` and back.
+        let layout = {
+            let in_general = image.blocks(0 .. levels, 0 .. layers).into_iter().all(|block| {
+                image.initial_layout(block, ImageLayout::TransferDstOptimal).0 ==
+                                                                            ImageLayout::General
+            });
+
+            if in_general { ImageLayout::General } else { ImageLayout::TransferDstOptimal }
+        };
+
+        self.add_image_resource_outside(image.clone() as Arc<_>, 0 .. levels, 0 .. layers, true,
+                                        layout, vk::PIPELINE_STAGE_TRANSFER_BIT,
+                                        vk::ACCESS_TRANSFER_WRITE_BIT);
 
         {
             let image = image.inner_image().internal_object();
@@ -518,14 +616,87 @@ impl InnerCommandBufferBuilder {
 
                 let range = vk::ImageSubresourceRange {
                     aspectMask: vk::IMAGE_ASPECT_COLOR_BIT,
-                    baseMipLevel: 0,        // FIXME:
-                    levelCount: 1,      // FIXME:
-                    baseArrayLayer: 0,      // FIXME:
-                    layerCount: 1,      // FIXME:
+                    baseMipLevel: 0,
+                    levelCount: levels,
+                    baseArrayLayer: 0,
+                    layerCount: layers,
                 };
 
-                vk.CmdClearColorImage(cmd, image, vk::IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL /* FIXME: */,
-                                      &color, 1, &range);
+                vk.CmdClearColorImage(cmd, image, layout as u32, &color, 1, &range);
+            }));
+        }
+
+        self
+    }
+
+    /// Clears the content of a depth, stencil or depth-stencil image.
+    ///
+    /// This operation can only be performed by queue families that support graphics.
+    ///
+    /// # Safety
+    ///
+    /// - Care must be taken to respect the rules about secondary command buffers.
+    ///
+    pub unsafe fn clear_depth_stencil_image<'a, I, V>(mut self, image: &Arc<I>, value: V)
+                                                      -> InnerCommandBufferBuilder
+        where I: ImageClearValue<V> + 'static
+    {
+        debug_assert!(self.render_pass_staging_commands.is_empty());
+
+        assert!(self.pool.queue_family().supports_graphics(),
+                "The queue family doesn't support graphics operations");
+
+        let format = image.format();
+        assert!(format.has_depth() || format.has_stencil(),
+                "The format of the image doesn't have a depth or a stencil component");
+        assert!(image.inner_image().usage_transfer_dest(),
+                "The image wasn't created with the transfer_dest usage");
+
+        let value = match image.decode(value) {
+            Some(v) => v,
+            None => panic!("The clear value doesn't match the format of the image")
+        };
+
+        if let Err(err) = ClearValueMismatch::check(format, value) {
+            panic!("{}", err);
+        }
+
+        let value = match value {
+            ClearValue::Depth(depth) => vk::ClearDepthStencilValue { depth: depth, stencil: 0 },
+            ClearValue::Stencil(stencil) => vk::ClearDepthStencilValue { depth: 0.0,
+                                                                         stencil: stencil },
+            ClearValue::DepthStencil((depth, stencil)) => {
+                vk::ClearDepthStencilValue { depth: depth, stencil: stencil }
+            },
+            _ => panic!("Wrong clear value")
+        };
+
+        let levels = image.inner_image().mipmap_levels();
+        let layers = image.dimensions().array_layers();
+
+        self.add_image_resource_outside(image.clone() as Arc<_>, 0 .. levels, 0 .. layers, true,
+                                        ImageLayout::TransferDstOptimal,
+                                        vk::PIPELINE_STAGE_TRANSFER_BIT,
+                                        vk::ACCESS_TRANSFER_WRITE_BIT);
+
+        {
+            let image = image.inner_image().internal_object();
+
+            self.staging_commands.push(Box::new(move |vk, cmd| {
+                let mut aspect_mask = 0;
+                if format.has_depth() { aspect_mask |= vk::IMAGE_ASPECT_DEPTH_BIT; }
+                if format.has_stencil() { aspect_mask |= vk::IMAGE_ASPECT_STENCIL_BIT; }
+
+                let range = vk::ImageSubresourceRange {
+                    aspectMask: aspect_mask,
+                    baseMipLevel: 0,
+                    levelCount: levels,
+                    baseArrayLayer: 0,
+                    layerCount: layers,
+                };
+
+                vk.CmdClearDepthStencilImage(cmd, image, vk::IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL,
+                                             &value, 1, &range);
             }));
         }
 
@@ -547,13 +718,15 @@ impl InnerCommandBufferBuilder {
         where S: Into<BufferSlice<'a, [P], Sb>>, Img: ImageContent<P> + Image + 'static,
               Sb: Buffer + 'static
     {
-        // FIXME: check the parameters
-
         debug_assert!(self.render_pass_staging_commands.is_empty());
 
-        //assert!(image.format().is_float_or_compressed());
+        assert!(image.matches_format(), "The pixel type doesn't match the format of the image");
 
         let source = source.into();
+        assert!(source.buffer().inner_buffer().usage_transfer_src());
+        assert!(image.inner_image().usage_transfer_dest());
+        check_copy_region(&**image, mip_level, &array_layers_range, offset, extent,
+                          source.offset(), source.size());
         self.add_buffer_resource_outside(source.buffer().clone() as Arc<_>, false,
                                          source.offset() .. source.offset() + source.size(),
                                          vk::PIPELINE_STAGE_TRANSFER_BIT,
@@ -601,6 +774,141 @@ impl InnerCommandBufferBuilder {
         self
     }
 
+    /// Copies data from a buffer to multiple regions of an image, without checking the type of
+    /// the content of the buffer.
+    ///
+    /// This operation can be performed by any kind of queue.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the buffer and the image don't belong to the same device.
+    /// - Panics if the buffer wasn't created with the `transfer_src` usage or the image with the
+    ///   `transfer_dest` usage.
+    /// - Panics if the image has both a depth and a stencil aspect.
+    /// - Panics if one of the regions is invalid for the image or the buffer.
+    ///
+    /// # Safety
+    ///
+    /// - Care must be taken to respect the rules about secondary command buffers.
+    ///
+    pub unsafe fn copy_buffer_to_image_untyped<B, I, R>(mut self, source: &Arc<B>, image: &Arc<I>,
+                                                        regions: R)
+                                                        -> InnerCommandBufferBuilder
+        where B: Buffer + 'static, I: Image + 'static,
+              R: IntoIterator<Item = BufferImageCopyRegion>
+    {
+        debug_assert!(self.render_pass_staging_commands.is_empty());
+
+        assert_eq!(&**source.inner_buffer().device() as *const _,
+                   &**image.inner_image().device() as *const _,
+                   "The source buffer and the destination image don't belong to the same device");
+        assert!(source.inner_buffer().usage_transfer_src(),
+                "The source buffer wasn't created with the transfer_src usage");
+        assert!(image.inner_image().usage_transfer_dest(),
+                "The destination image wasn't created with the transfer_dest usage");
+
+        let aspect = match image.format().ty() {
+            FormatTy::Float | FormatTy::Uint | FormatTy::Sint | FormatTy::Compressed => {
+                vk::IMAGE_ASPECT_COLOR_BIT
+            },
+            FormatTy::Depth => vk::IMAGE_ASPECT_DEPTH_BIT,
+            FormatTy::Stencil => vk::IMAGE_ASPECT_STENCIL_BIT,
+            FormatTy::DepthStencil => {
+                panic!("Copying to an image with both a depth and a stencil aspect isn't \
+                        supported")
+            },
+        };
+
+        let regions = regions.into_iter().collect::<SmallVec<[_; 4]>>();
+        assert!(!regions.is_empty(), "At least one region must be copied");
+
+        let mipmap_levels = image.inner_image().mipmap_levels();
+        let array_layers = image.dimensions().array_layers();
+        for (num, region) in regions.iter().enumerate() {
+            assert!(region.mip_level < mipmap_levels,
+                    "Region {}: mipmap level {} is out of range (the image has {} levels)",
+                    num, region.mip_level, mipmap_levels);
+            assert!(region.array_layers.start < region.array_layers.end &&
+                    region.array_layers.end <= array_layers,
+                    "Region {}: array layers {:?} are invalid (the image has {} layers)",
+                    num, region.array_layers, array_layers);
+            assert!(region.buffer_offset <= source.size(),
+                    "Region {}: buffer offset {} is out of range (the buffer is {} bytes)",
+                    num, region.buffer_offset, source.size());
+
+            let level_dimensions = image.dimensions().mipmap_dimensions(region.mip_level);
+
+            let num_layers = region.array_layers.end - region.array_layers.start;
+            match check_buffer_image_copy_layout(image.format(), level_dimensions,
+                                                 region.image_offset, region.image_extent,
+                                                 num_layers, region.buffer_offset,
+                                                 region.buffer_row_length,
+                                                 region.buffer_image_height,
+                                                 source.size() - region.buffer_offset)
+            {
+                Ok(()) => (),
+                Err(err) => panic!("Region {}: invalid copy between a buffer and an image \
+                                    (offset {:?}, extent {:?}, mipmap level of {:?}): {:?}",
+                                   num, region.image_offset, region.image_extent,
+                                   level_dimensions, err)
+            }
+        }
+
+        // The resources are added once for all the regions, otherwise two regions that overlap
+        // would be considered as conflicting with each other.
+        let min_offset = regions.iter().map(|r| r.buffer_offset).min().unwrap();
+        let levels = regions.iter().map(|r| r.mip_level).min().unwrap() ..
+                     regions.iter().map(|r| r.mip_level + 1).max().unwrap();
+        let layers = regions.iter().map(|r| r.array_layers.start).min().unwrap() ..
+                     regions.iter().map(|r| r.array_layers.end).max().unwrap();
+
+        self.add_buffer_resource_outside(source.clone() as Arc<_>, false,
+                                         min_offset .. source.size(),
+                                         vk::PIPELINE_STAGE_TRANSFER_BIT,
+                                         vk::ACCESS_TRANSFER_READ_BIT);
+        self.add_image_resource_outside(image.clone() as Arc<_>, levels, layers, true,
+                                        ImageLayout::TransferDstOptimal,
+                                        vk::PIPELINE_STAGE_TRANSFER_BIT,
+                                        vk::ACCESS_TRANSFER_WRITE_BIT);
+
+        {
+            let regions = regions.iter().map(|region| {
+                vk::BufferImageCopy {
+                    bufferOffset: region.buffer_offset as vk::DeviceSize,
+                    bufferRowLength: region.buffer_row_length,
+                    bufferImageHeight: region.buffer_image_height,
+                    imageSubresource: vk::ImageSubresourceLayers {
+                        aspectMask: aspect,
+                        mipLevel: region.mip_level,
+                        baseArrayLayer: region.array_layers.start,
+                        layerCount: region.array_layers.end - region.array_layers.start,
+                    },
+                    imageOffset: vk::Offset3D {
+                        x: region.image_offset[0] as i32,
+                        y: region.image_offset[1] as i32,
+                        z: region.image_offset[2] as i32,
+                    },
+                    imageExtent: vk::Extent3D {
+                        width: region.image_extent[0],
+                        height: region.image_extent[1],
+                        depth: region.image_extent[2],
+                    },
+                }
+            }).collect::<SmallVec<[_; 4]>>();
+
+            let source = source.inner_buffer().internal_object();
+            let image = image.inner_image().internal_object();
+
+            self.staging_commands.push(Box::new(move |vk, cmd| {
+                vk.CmdCopyBufferToImage(cmd, source, image,
+                                        vk::IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL,
+                                        regions.len() as u32, regions.as_ptr());
+            }));
+        }
+
+        self
+    }
+
     /// Copies data from a color image to a buffer.
     ///
     /// This operation can be performed by any kind of queue.
@@ -616,13 +924,15 @@ impl InnerCommandBufferBuilder {
         where S: Into<BufferSlice<'a, [P], Sb>>, Img: ImageContent<P> + Image + 'static,
               Sb: Buffer + 'static
     {
-        // FIXME: check the parameters
-
         debug_assert!(self.render_pass_staging_commands.is_empty());
 
-        //assert!(image.format().is_float_or_compressed());
+        assert!(image.matches_format(), "The pixel type doesn't match the format of the image");
 
         let dest = dest.into();
+        assert!(dest.buffer().inner_buffer().usage_transfer_dest());
+        assert!(image.inner_image().usage_transfer_src());
+        check_copy_region(&**image, mip_level, &array_layers_range, offset, extent,
+                          dest.offset(), dest.size());
         self.add_buffer_resource_outside(dest.buffer().clone() as Arc<_>, true,
                                          dest.offset() .. dest.offset() + dest.size(),
                                          vk::PIPELINE_STAGE_TRANSFER_BIT,
@@ -683,6 +993,8 @@ impl InnerCommandBufferBuilder {
 
         assert!(source.supports_blit_source());
         assert!(destination.supports_blit_destination());
+        assert!(source.inner_image().usage_transfer_src());
+        assert!(destination.inner_image().usage_transfer_dest());
 
         self.add_image_resource_outside(source.clone() as Arc<_>,
                                         source_mip_level .. source_mip_level + 1,
@@ -920,7 +1232,12 @@ impl InnerCommandBufferBuilder {
               Pc: 'static + Clone + Send + Sync
     {
         unsafe {
-            //assert!(sets.is_compatible_with(pipeline.layout()));
+            {
+                let layout = PipelineLayout::inner_pipeline_layout(&**pipeline.layout());
+                if let Err(err) = layout.ensure_sets_compatible(sets.layouts().iter()) {
+                    panic!("Descriptor sets are not compatible with the pipeline layout: {}", err);
+                }
+            }
 
             if self.current_compute_pipeline != Some(pipeline.internal_object()) {
                 self.keep_alive.push(pipeline.clone());
@@ -934,9 +1251,18 @@ impl InnerCommandBufferBuilder {
 
             let mut descriptor_sets = DescriptorSetsCollection::list(&sets).collect::<SmallVec<[_; 32]>>();
 
+            let dynamic_offsets = sets.dynamic_offsets();
+            if let Err(err) = check_dynamic_offsets(descriptor_sets.iter().map(|s| s.inner_descriptor_set()),
+                                                    dynamic_offsets)
+            {
+                panic!("Invalid dynamic offsets: {}", err);
+            }
+            let dynamic_offsets = split_dynamic_offsets(&descriptor_sets, dynamic_offsets);
+
             for set in descriptor_sets.iter() {
                 for &(ref img, block, layout) in set.inner_descriptor_set().images_list().iter() {
-                    self.add_image_resource_outside(img.clone(), 0 .. 1 /* FIXME */, 0 .. 1 /* FIXME */,
+                    self.add_image_resource_outside(img.clone(), img.block_mipmap_levels_range(block),
+                                                   img.block_array_layers_range(block),
                                                    false, layout, vk::PIPELINE_STAGE_ALL_COMMANDS_BIT /* FIXME */,
                                                    vk::ACCESS_SHADER_READ_BIT | vk::ACCESS_UNIFORM_READ_BIT /* TODO */);
                 }
@@ -948,28 +1274,54 @@ impl InnerCommandBufferBuilder {
             }
 
             for d in descriptor_sets.iter() { self.keep_alive.push(mem::transmute(d.clone()) /* FIXME: */); }
-            let mut descriptor_sets = Some(descriptor_sets.into_iter().map(|set| set.inner_descriptor_set().internal_object()).collect::<SmallVec<[_; 32]>>());
-
-            // TODO: shouldn't rebind everything every time
-            if !descriptor_sets.as_ref().unwrap().is_empty() {
-                let pipeline = PipelineLayout::inner_pipeline_layout(&**pipeline.layout()).internal_object();
+            let descriptor_sets = descriptor_sets.into_iter().map(|set| set.inner_descriptor_set().internal_object()).collect::<SmallVec<[_; 32]>>();
+
+            // Only the sets that aren't already bound with a compatible layout are bound.
+            let layout = PipelineLayout::inner_pipeline_layout(&**pipeline.layout());
+            let bound_layout = self.bound_layouts.entry(pipeline.internal_object())
+                                   .or_insert_with(|| Arc::new(BoundPipelineLayout::new(layout)))
+                                   .clone();
+            let first_set = self.current_compute_descriptor_sets.bind(&bound_layout, &descriptor_sets,
+                                                                       &dynamic_offsets);
+            if let Err(err) = self.current_compute_descriptor_sets.check(&bound_layout) {
+                panic!("Bound descriptor sets don't match the pipeline layout: {}", err);
+            }
+            if first_set < descriptor_sets.len() {
+                let pipeline = layout.internal_object();
+                let mut descriptor_sets = Some(descriptor_sets);
+                let dynamic_offsets = dynamic_offsets[first_set ..].iter()
+                                                                   .flat_map(|o| o.iter().cloned())
+                                                                   .collect::<SmallVec<[u32; 32]>>();
                 self.staging_commands.push(Box::new(move |vk, cmd| {
                     let descriptor_sets = descriptor_sets.take().unwrap();
                     vk.CmdBindDescriptorSets(cmd, vk::PIPELINE_BIND_POINT_COMPUTE,
-                                             pipeline, 0, descriptor_sets.len() as u32,
-                                             descriptor_sets.as_ptr(), 0, ptr::null());   // FIXME: dynamic offsets
+                                             pipeline, first_set as u32,
+                                             (descriptor_sets.len() - first_set) as u32,
+                                             descriptor_sets[first_set ..].as_ptr(),
+                                             dynamic_offsets.len() as u32,
+                                             dynamic_offsets.as_ptr());
                 }));
             }
 
             if mem::size_of_val(push_constants) >= 1 {
-                let pipeline = PipelineLayout::inner_pipeline_layout(&**pipeline.layout()).internal_object();
-                let size = mem::size_of_val(push_constants);
+                let layout = PipelineLayout::inner_pipeline_layout(&**pipeline.layout());
+                let updates = match push_constants_updates(layout.push_constants_ranges(),
+                                                           mem::size_of_val(push_constants))
+                {
+                    Ok(u) => u,
+                    Err(err) => panic!("Push constants don't match the pipeline layout: {}", err)
+                };
+
+                let pipeline = layout.internal_object();
                 let push_constants = push_constants.clone();
-                assert!((size % 4) == 0);
 
                 self.staging_commands.push(Box::new(move |vk, cmd| {
-                    vk.CmdPushConstants(cmd, pipeline, 0x7fffffff, 0, size as u32,
-                                        &push_constants as *const Pc as *const _);
+                    let data = &push_constants as *const Pc as *const u8;
+                    for update in updates.iter() {
+                        vk.CmdPushConstants(cmd, pipeline, update.stages.into(),
+                                            update.offset as u32, update.size as u32,
+                                            data.offset(update.offset as isize) as *const _);
+                    }
                 }));
             }
         }
@@ -981,19 +1333,50 @@ impl InnerCommandBufferBuilder {
               Pl: 'static + PipelineLayout + Send + Sync, Rp: 'static + Send + Sync, Pc: 'static + Clone + Send + Sync
     {
         unsafe {
-            //assert!(sets.is_compatible_with(pipeline.layout()));
+            {
+                let layout = PipelineLayout::inner_pipeline_layout(&**pipeline.layout());
+                if let Err(err) = layout.ensure_sets_compatible(sets.layouts().iter()) {
+                    panic!("Descriptor sets are not compatible with the pipeline layout: {}", err);
+                }
+            }
+
+            // Vulkano doesn't support any other way than a geometry shader to choose the layer to
+            // render to, so rendering to a framebuffer with multiple layers without one would
+            // always render to the first layer.
+            assert!(self.current_framebuffer_layers <= 1 || pipeline.has_geometry_shader(),
+                    "Drawing to a framebuffer with multiple layers requires a pipeline with a \
+                     geometry shader");
 
             if self.current_graphics_pipeline != Some(pipeline.internal_object()) {
                 self.keep_alive.push(pipeline.clone());
-                let pipeline = pipeline.internal_object();
+                let pipeline_obj = pipeline.internal_object();
                 self.render_pass_staging_commands.push(Box::new(move |vk, cmd| {
-                    vk.CmdBindPipeline(cmd, vk::PIPELINE_BIND_POINT_GRAPHICS, pipeline);
+                    vk.CmdBindPipeline(cmd, vk::PIPELINE_BIND_POINT_GRAPHICS, pipeline_obj);
                 }));
-                self.current_graphics_pipeline = Some(pipeline);
+                self.current_graphics_pipeline = Some(pipeline_obj);
+
+                // Binding a pipeline where a state is fixed overwrites the dynamic value of
+                // that state.
+                let flags = pipeline.dynamic_states();
+                let current = &mut self.current_dynamic_state;
+                if !flags.line_width { current.line_width = None; }
+                if !flags.viewports { current.viewports = None; }
+                if !flags.scissors { current.scissors = None; }
+                if !flags.depth_bias { current.depth_bias = None; }
+                if !flags.blend_constants { current.blend_constants = None; }
+                if !flags.depth_bounds { current.depth_bounds = None; }
+                if !flags.stencil_compare_mask { current.stencil_compare_mask = None; }
+                if !flags.stencil_write_mask { current.stencil_write_mask = None; }
+                if !flags.stencil_reference { current.stencil_reference = None; }
+            }
+
+            if let Err(err) = dynamic.check(pipeline.dynamic_states(), pipeline.num_viewports(),
+                                            &self.current_dynamic_state)
+            {
+                panic!("{}", err);
             }
 
             if let Some(line_width) = dynamic.line_width {
-                assert!(pipeline.has_dynamic_line_width());
                 // TODO: check limits
                 if self.current_dynamic_state.line_width != Some(line_width) {
                     self.render_pass_staging_commands.push(Box::new(move |vk, cmd| {
@@ -1001,43 +1384,89 @@ impl InnerCommandBufferBuilder {
                     }));
                     self.current_dynamic_state.line_width = Some(line_width);
                 }
-            } else {
-                assert!(!pipeline.has_dynamic_line_width());
             }
 
             if let Some(ref viewports) = dynamic.viewports {
-                assert!(pipeline.has_dynamic_viewports());
-                assert_eq!(viewports.len(), pipeline.num_viewports() as usize);
                 // TODO: check limits
                 // TODO: cache state?
-                let mut viewports = Some(viewports.iter().map(|v| v.clone().into()).collect::<SmallVec<[_; 16]>>());
+                let mut viewports_vk = Some(viewports.iter().map(|v| v.clone().into()).collect::<SmallVec<[_; 16]>>());
                 self.render_pass_staging_commands.push(Box::new(move |vk, cmd| {
-                    let viewports = viewports.take().unwrap();
+                    let viewports = viewports_vk.take().unwrap();
                     vk.CmdSetViewport(cmd, 0, viewports.len() as u32, viewports.as_ptr());
                 }));
-            } else {
-                assert!(!pipeline.has_dynamic_viewports());
+                self.current_dynamic_state.viewports = Some(viewports.clone());
             }
 
             if let Some(ref scissors) = dynamic.scissors {
-                assert!(pipeline.has_dynamic_scissors());
-                assert_eq!(scissors.len(), pipeline.num_viewports() as usize);
                 // TODO: check limits
                 // TODO: cache state?
                 // TODO: allocate on stack instead (https://github.com/rust-lang/rfcs/issues/618)
-                let mut scissors = Some(scissors.iter().map(|v| v.clone().into()).collect::<SmallVec<[_; 16]>>());
+                let mut scissors_vk = Some(scissors.iter().map(|v| v.clone().into()).collect::<SmallVec<[_; 16]>>());
                 self.render_pass_staging_commands.push(Box::new(move |vk, cmd| {
-                    let scissors = scissors.take().unwrap();
+                    let scissors = scissors_vk.take().unwrap();
                     vk.CmdSetScissor(cmd, 0, scissors.len() as u32, scissors.as_ptr());
                 }));
-            } else {
-                assert!(!pipeline.has_dynamic_scissors());
+                self.current_dynamic_state.scissors = Some(scissors.clone());
+            }
+
+            if let Some(bias) = dynamic.depth_bias {
+                self.render_pass_staging_commands.push(Box::new(move |vk, cmd| {
+                    vk.CmdSetDepthBias(cmd, bias.constant_factor, bias.clamp, bias.slope_factor);
+                }));
+                self.current_dynamic_state.depth_bias = Some(bias);
+            }
+
+            if let Some(constants) = dynamic.blend_constants {
+                self.render_pass_staging_commands.push(Box::new(move |vk, cmd| {
+                    vk.CmdSetBlendConstants(cmd, constants);
+                }));
+                self.current_dynamic_state.blend_constants = Some(constants);
+            }
+
+            if let Some((min, max)) = dynamic.depth_bounds {
+                self.render_pass_staging_commands.push(Box::new(move |vk, cmd| {
+                    vk.CmdSetDepthBounds(cmd, min, max);
+                }));
+                self.current_dynamic_state.depth_bounds = Some((min, max));
+            }
+
+            if let Some(mask) = dynamic.stencil_compare_mask {
+                self.render_pass_staging_commands.push(Box::new(move |vk, cmd| {
+                    vk.CmdSetStencilCompareMask(cmd, vk::STENCIL_FACE_FRONT_BIT, mask.front);
+                    vk.CmdSetStencilCompareMask(cmd, vk::STENCIL_FACE_BACK_BIT, mask.back);
+                }));
+                self.current_dynamic_state.stencil_compare_mask = Some(mask);
+            }
+
+            if let Some(mask) = dynamic.stencil_write_mask {
+                self.render_pass_staging_commands.push(Box::new(move |vk, cmd| {
+                    vk.CmdSetStencilWriteMask(cmd, vk::STENCIL_FACE_FRONT_BIT, mask.front);
+                    vk.CmdSetStencilWriteMask(cmd, vk::STENCIL_FACE_BACK_BIT, mask.back);
+                }));
+                self.current_dynamic_state.stencil_write_mask = Some(mask);
+            }
+
+            if let Some(reference) = dynamic.stencil_reference {
+                self.render_pass_staging_commands.push(Box::new(move |vk, cmd| {
+                    vk.CmdSetStencilReference(cmd, vk::STENCIL_FACE_FRONT_BIT, reference.front);
+                    vk.CmdSetStencilReference(cmd, vk::STENCIL_FACE_BACK_BIT, reference.back);
+                }));
+                self.current_dynamic_state.stencil_reference = Some(reference);
             }
 
             let mut descriptor_sets = DescriptorSetsCollection::list(&sets).collect::<SmallVec<[_; 32]>>();
+
+            let dynamic_offsets = sets.dynamic_offsets();
+            if let Err(err) = check_dynamic_offsets(descriptor_sets.iter().map(|s| s.inner_descriptor_set()),
+                                                    dynamic_offsets)
+            {
+                panic!("Invalid dynamic offsets: {}", err);
+            }
+            let dynamic_offsets = split_dynamic_offsets(&descriptor_sets, dynamic_offsets);
             for set in descriptor_sets.iter() {
                 for &(ref img, block, layout) in set.inner_descriptor_set().images_list().iter() {
-                    self.add_image_resource_inside(img.clone(), 0 .. 1 /* FIXME */, 0 .. 1 /* FIXME */,
+                    self.add_image_resource_inside(img.clone(), img.block_mipmap_levels_range(block),
+                                                   img.block_array_layers_range(block),
                                                    false, layout, layout, vk::PIPELINE_STAGE_ALL_COMMANDS_BIT /* FIXME */,
                                                    vk::ACCESS_SHADER_READ_BIT | vk::ACCESS_UNIFORM_READ_BIT /* TODO */);
                 }
@@ -1048,31 +1477,57 @@ impl InnerCommandBufferBuilder {
                 }
             }
             for d in descriptor_sets.iter() { self.keep_alive.push(mem::transmute(d.clone()) /* FIXME: */); }
-            let mut descriptor_sets = Some(descriptor_sets.into_iter().map(|set| set.inner_descriptor_set().internal_object()).collect::<SmallVec<[_; 32]>>());
+            let descriptor_sets = descriptor_sets.into_iter().map(|set| set.inner_descriptor_set().internal_object()).collect::<SmallVec<[_; 32]>>();
 
             if mem::size_of_val(push_constants) >= 1 {
-                let pipeline = PipelineLayout::inner_pipeline_layout(&**pipeline.layout()).internal_object();
-                let size = mem::size_of_val(push_constants);
+                let layout = PipelineLayout::inner_pipeline_layout(&**pipeline.layout());
+                let updates = match push_constants_updates(layout.push_constants_ranges(),
+                                                           mem::size_of_val(push_constants))
+                {
+                    Ok(u) => u,
+                    Err(err) => panic!("Push constants don't match the pipeline layout: {}", err)
+                };
+
+                let pipeline = layout.internal_object();
                 let push_constants = push_constants.clone();
-                assert!((size % 4) == 0);
 
                 self.render_pass_staging_commands.push(Box::new(move |vk, cmd| {
-                    vk.CmdPushConstants(cmd, pipeline, 0x7fffffff, 0, size as u32,
-                                        &push_constants as *const Pc as *const _);
+                    let data = &push_constants as *const Pc as *const u8;
+                    for update in updates.iter() {
+                        vk.CmdPushConstants(cmd, pipeline, update.stages.into(),
+                                            update.offset as u32, update.size as u32,
+                                            data.offset(update.offset as isize) as *const _);
+                    }
                 }));
             }
 
             // FIXME: input attachments of descriptor sets have to be checked against input
             //        attachments of the render pass
 
-            // TODO: shouldn't rebind everything every time
-            if !descriptor_sets.as_ref().unwrap().is_empty() {
-                let pipeline = PipelineLayout::inner_pipeline_layout(&**pipeline.layout()).internal_object();
+            // Only the sets that aren't already bound with a compatible layout are bound.
+            let layout = PipelineLayout::inner_pipeline_layout(&**pipeline.layout());
+            let bound_layout = self.bound_layouts.entry(pipeline.internal_object())
+                                   .or_insert_with(|| Arc::new(BoundPipelineLayout::new(layout)))
+                                   .clone();
+            let first_set = self.current_graphics_descriptor_sets.bind(&bound_layout, &descriptor_sets,
+                                                                        &dynamic_offsets);
+            if let Err(err) = self.current_graphics_descriptor_sets.check(&bound_layout) {
+                panic!("Bound descriptor sets don't match the pipeline layout: {}", err);
+            }
+            if first_set < descriptor_sets.len() {
+                let pipeline = layout.internal_object();
+                let mut descriptor_sets = Some(descriptor_sets);
+                let dynamic_offsets = dynamic_offsets[first_set ..].iter()
+                                                                   .flat_map(|o| o.iter().cloned())
+                                                                   .collect::<SmallVec<[u32; 32]>>();
                 self.render_pass_staging_commands.push(Box::new(move |vk, cmd| {
                     let descriptor_sets = descriptor_sets.take().unwrap();
                     vk.CmdBindDescriptorSets(cmd, vk::PIPELINE_BIND_POINT_GRAPHICS, pipeline,
-                                             0, descriptor_sets.len() as u32,
-                                             descriptor_sets.as_ptr(), 0, ptr::null());   // FIXME: dynamic offsets
+                                             first_set as u32,
+                                             (descriptor_sets.len() - first_set) as u32,
+                                             descriptor_sets[first_set ..].as_ptr(),
+                                             dynamic_offsets.len() as u32,
+                                             dynamic_offsets.as_ptr());
                 }));
             }
         }
@@ -1093,7 +1548,7 @@ impl InnerCommandBufferBuilder {
                                          framebuffer: &Arc<Framebuffer<F>>,
                                          secondary_cmd_buffers: bool,
                                          clear_values: &[ClearValue]) -> InnerCommandBufferBuilder
-        where R: RenderPass + 'static, F: RenderPass + 'static
+        where R: RenderPass + RenderPassDesc + 'static, F: RenderPass + RenderPassDesc + 'static
     {
         debug_assert!(self.render_pass_staging_commands.is_empty());
         debug_assert!(self.render_pass_staging_required_buffer_accesses.is_empty());
@@ -1101,6 +1556,8 @@ impl InnerCommandBufferBuilder {
 
         assert!(framebuffer.is_compatible_with(render_pass));
 
+        self.current_framebuffer_layers = framebuffer.layers();
+        self.current_subpass = Some(0);
         self.keep_alive.push(framebuffer.clone() as Arc<_>);
         self.keep_alive.push(render_pass.clone() as Arc<_>);
 
@@ -1139,7 +1596,8 @@ impl InnerCommandBufferBuilder {
                            vk::ACCESS_INPUT_ATTACHMENT_READ_BIT;       // FIXME:
 
             // FIXME: parameters
-            self.add_image_resource_inside(image.clone(), 0 .. 1, 0 .. 1, true,
+            self.add_image_resource_inside(image.clone(), attachment.inner_view().mipmap_levels(),
+                                           attachment.inner_view().array_layers(), true,
                                            initial_layout, final_layout, stages, accesses);
         }
 
@@ -1181,9 +1639,15 @@ impl InnerCommandBufferBuilder {
         self
     }
 
+    /// # Panic
+    ///
+    /// - Panicks if a query begun in the current subpass hasn't been ended.
+    ///
     #[inline]
     pub unsafe fn next_subpass(mut self, secondary_cmd_buffers: bool) -> InnerCommandBufferBuilder {
         debug_assert!(!self.render_pass_staging_commands.is_empty());
+        self.check_subpass_queries_ended();
+        self.current_subpass = self.current_subpass.map(|sp| sp + 1);
 
         let content = if secondary_cmd_buffers {
             vk::SUBPASS_CONTENTS_SECONDARY_COMMAND_BUFFERS
@@ -1204,13 +1668,214 @@ impl InnerCommandBufferBuilder {
     ///
     /// Assumes that you're inside a render pass and that all subpasses have been processed.
     ///
+    /// # Panic
+    ///
+    /// - Panicks if a query begun in the current subpass hasn't been ended.
+    ///
     #[inline]
     pub unsafe fn end_renderpass(mut self) -> InnerCommandBufferBuilder {
         debug_assert!(!self.render_pass_staging_commands.is_empty());
+        self.check_subpass_queries_ended();
         self.flush_render_pass();
         self.staging_commands.push(Box::new(move |vk, cmd| {
             vk.CmdEndRenderPass(cmd);
         }));
+        self.current_framebuffer_layers = 1;
+        self.current_subpass = None;
+        self
+    }
+
+    // Panics if a query that was begun in the current subpass is still active.
+    fn check_subpass_queries_ended(&self) {
+        let current = self.current_subpass;
+        assert!(self.active_queries.iter().all(|&(_, _, _, sp)| sp != current),
+                "All the queries begun in a subpass must be ended before the end of the subpass");
+    }
+
+    /// Calls `vkCmdResetQueryPool` on the `count` queries of the pool starting at `first`.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the range of queries is empty or out of the pool.
+    /// - Panicks if the pool wasn't created with the same device as the command buffer.
+    ///
+    /// # Safety
+    ///
+    /// - Care must be taken to respect the rules about secondary command buffers.
+    ///
+    pub unsafe fn reset_query_pool<P>(mut self, pool: &Arc<P>, first: u32, count: u32)
+                                      -> InnerCommandBufferBuilder
+        where P: QueryPool
+    {
+        debug_assert!(self.render_pass_staging_commands.is_empty());
+
+        let raw_pool = {
+            let inner = pool.inner_query_pool();
+            assert_eq!(inner.device().internal_object(), self.device.internal_object(),
+                       "The query pool doesn't belong to the device of the command buffer");
+            assert!(inner.queries_range(first, count).is_some(),
+                    "The range of queries is empty or out of the pool");
+            inner.internal_object()
+        };
+
+        pool.queries_reset(first, count);
+        self.keep_alive.push(pool.clone() as Arc<_>);
+
+        self.staging_commands.push(Box::new(move |vk, cmd| {
+            vk.CmdResetQueryPool(cmd, raw_pool, first, count);
+        }));
+
+        self
+    }
+
+    /// Calls `vkCmdBeginQuery` on a query of the pool.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the query is out of the pool.
+    /// - Panicks if the pool wasn't created with the same device as the command buffer.
+    /// - Panicks if the pool contains timestamp queries.
+    /// - Panicks if a query of the same type is already active in this command buffer.
+    /// - Panicks if `precise` is true and the query isn't an occlusion query or the
+    ///   `occlusion_query_precise` feature isn't enabled.
+    ///
+    /// # Safety
+    ///
+    /// - Care must be taken to respect the rules about secondary command buffers.
+    ///
+    pub unsafe fn begin_query<P>(mut self, pool: &Arc<P>, query: u32, precise: bool)
+                                 -> InnerCommandBufferBuilder
+        where P: QueryPool
+    {
+        let (raw_pool, ty) = {
+            let inner = pool.inner_query_pool();
+            assert_eq!(inner.device().internal_object(), self.device.internal_object(),
+                       "The query pool doesn't belong to the device of the command buffer");
+            assert!(query < inner.count(), "The query is out of the pool");
+            (inner.internal_object(), inner.ty())
+        };
+
+        assert!(ty != QueryType::Timestamp, "Timestamp queries can't be begun");
+        if precise {
+            assert!(ty == QueryType::Occlusion, "Only occlusion queries can be precise");
+            assert!(self.device.enabled_features().occlusion_query_precise,
+                    "The occlusion_query_precise feature must be enabled for precise queries");
+        }
+
+        let vk_ty: vk::QueryType = ty.into();
+        assert!(self.active_queries.iter().all(|&(_, _, t, _)| t != vk_ty),
+                "A query of the same type is already active in this command buffer");
+        self.active_queries.push((raw_pool, query, vk_ty, self.current_subpass));
+
+        pool.query_begun(query);
+        self.keep_alive.push(pool.clone() as Arc<_>);
+
+        let flags = if precise { vk::QUERY_CONTROL_PRECISE_BIT } else { 0 };
+        let command = Box::new(move |vk: &vk::DevicePointers, cmd| {
+            vk.CmdBeginQuery(cmd, raw_pool, query, flags);
+        });
+
+        if self.render_pass_staging_commands.is_empty() {
+            self.staging_commands.push(command);
+        } else {
+            self.render_pass_staging_commands.push(command);
+        }
+
+        self
+    }
+
+    /// Calls `vkCmdWriteTimestamp` on a query of the pool.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the query is out of the pool.
+    /// - Panicks if the pool wasn't created with the same device as the command buffer.
+    /// - Panicks if the pool doesn't contain timestamp queries.
+    /// - Panicks if the queue family of the command buffer doesn't support timestamps.
+    /// - Panicks if `stage` doesn't contain exactly one stage.
+    ///
+    /// # Safety
+    ///
+    /// - Care must be taken to respect the rules about secondary command buffers.
+    ///
+    pub unsafe fn write_timestamp<P>(mut self, pool: &Arc<P>, stage: PipelineStages, query: u32)
+                                     -> InnerCommandBufferBuilder
+        where P: QueryPool
+    {
+        let raw_pool = {
+            let inner = pool.inner_query_pool();
+            assert_eq!(inner.device().internal_object(), self.device.internal_object(),
+                       "The query pool doesn't belong to the device of the command buffer");
+            assert!(query < inner.count(), "The query is out of the pool");
+            assert!(inner.ty() == QueryType::Timestamp,
+                    "Timestamps can only be written to timestamp queries");
+            inner.internal_object()
+        };
+
+        assert!(self.pool.queue_family().timestamp_valid_bits().is_some(),
+                "The queue family of the command buffer doesn't support timestamps");
+
+        let stage: vk::PipelineStageFlagBits = stage.into();
+        assert_eq!(stage.count_ones(), 1, "A timestamp must be written at exactly one stage");
+
+        pool.query_written(query);
+        self.keep_alive.push(pool.clone() as Arc<_>);
+
+        let command = Box::new(move |vk: &vk::DevicePointers, cmd| {
+            vk.CmdWriteTimestamp(cmd, stage, raw_pool, query);
+        });
+
+        if self.render_pass_staging_commands.is_empty() {
+            self.staging_commands.push(command);
+        } else {
+            self.render_pass_staging_commands.push(command);
+        }
+
+        self
+    }
+
+    /// Returns the identifier of the queue family of the command buffer.
+    #[inline]
+    pub fn queue_family_id(&self) -> u32 {
+        self.pool.queue_family().id()
+    }
+
+    /// Calls `vkCmdEndQuery` on a query of the pool.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the query wasn't begun in this command buffer.
+    /// - Panicks if the query was begun in another subpass, or outside of the render pass while
+    ///   being ended inside of it or the other way around.
+    ///
+    /// # Safety
+    ///
+    /// - Care must be taken to respect the rules about secondary command buffers.
+    ///
+    pub unsafe fn end_query<P>(mut self, pool: &Arc<P>, query: u32) -> InnerCommandBufferBuilder
+        where P: QueryPool
+    {
+        let raw_pool = pool.inner_query_pool().internal_object();
+
+        let position = self.active_queries.iter().position(|&(p, q, _, _)| {
+            p == raw_pool && q == query
+        }).expect("The query wasn't begun in this command buffer");
+        assert!(self.active_queries[position].3 == self.current_subpass,
+                "The query must be ended in the subpass it was begun in");
+        self.active_queries.remove(position);
+
+        pool.query_written(query);
+
+        let command = Box::new(move |vk: &vk::DevicePointers, cmd| {
+            vk.CmdEndQuery(cmd, raw_pool, query);
+        });
+
+        if self.render_pass_staging_commands.is_empty() {
+            self.staging_commands.push(command);
+        } else {
+            self.render_pass_staging_commands.push(command);
+        }
+
         self
     }
 
@@ -1223,8 +1888,8 @@ impl InnerCommandBufferBuilder {
         let mut conflict = false;
         for block in buffer.blocks(range.clone()) {
             let key = (BufferKey(buffer.clone()), block);
-            if let Some(&entry) = self.staging_required_buffer_accesses.get(&key) {
-                if entry.write || write {
+            if let Some(entry) = self.staging_required_buffer_accesses.get(&key) {
+                if entry.ranges.conflicts(range.clone(), write) {
                     conflict = true;
                     break;
                 }
@@ -1242,14 +1907,14 @@ impl InnerCommandBufferBuilder {
                     e.insert(InternalBufferBlockAccess {
                         stages: stages,
                         accesses: accesses,
-                        write: write,
+                        ranges: BufferAccessRanges::from_range(range.clone(), write),
                     });
                 },
                 Entry::Occupied(mut entry) => {
                     let mut entry = entry.get_mut();
                     entry.stages &= stages;
                     entry.accesses &= stages;
-                    entry.write = entry.write || write;
+                    entry.ranges.add(range.clone(), write);
                 }
             }
         }
@@ -1260,13 +1925,15 @@ impl InnerCommandBufferBuilder {
                                   array_layers_range: Range<u32>, write: bool, layout: ImageLayout,
                                   stages: vk::PipelineStageFlagBits, accesses: vk::AccessFlagBits)
     {
+        let new_accesses = image_block_accesses(&image, mipmap_levels_range, array_layers_range,
+                                                write, layout, layout, stages, accesses);
+
         // Flushing if required.
         let mut conflict = false;
-        for block in image.blocks(mipmap_levels_range.clone(), array_layers_range.clone()) {
+        for &(block, ref access) in new_accesses.iter() {
             let key = (ImageKey(image.clone()), block);
             if let Some(entry) = self.staging_required_image_accesses.get(&key) {
-                // TODO: should be reviewed
-                if entry.write || write || entry.new_layout != layout {
+                if entry.new_layout != layout || entry.conflicts_with(access) {
                     conflict = true;
                     break;
                 }
@@ -1277,27 +1944,11 @@ impl InnerCommandBufferBuilder {
         }
 
         // Inserting in `staging_required_image_accesses`.
-        for block in image.blocks(mipmap_levels_range.clone(), array_layers_range.clone()) {
+        for (block, access) in new_accesses.into_iter() {
             let key = (ImageKey(image.clone()), block);
-            let aspect_mask = match image.format().ty() {
-                FormatTy::Float | FormatTy::Uint | FormatTy::Sint | FormatTy::Compressed => {
-                    vk::IMAGE_ASPECT_COLOR_BIT
-                },
-                FormatTy::Depth => vk::IMAGE_ASPECT_DEPTH_BIT,
-                FormatTy::Stencil => vk::IMAGE_ASPECT_STENCIL_BIT,
-                FormatTy::DepthStencil => vk::IMAGE_ASPECT_DEPTH_BIT | vk::IMAGE_ASPECT_STENCIL_BIT,
-            };
-
             match self.staging_required_image_accesses.entry(key) {
                 Entry::Vacant(e) => {
-                    e.insert(InternalImageBlockAccess {
-                        stages: stages,
-                        accesses: accesses,
-                        write: write,
-                        aspects: aspect_mask,
-                        old_layout: layout,
-                        new_layout: layout,
-                    });
+                    e.insert(access);
                 },
                 Entry::Occupied(mut entry) => {
                     let mut entry = entry.get_mut();
@@ -1305,7 +1956,8 @@ impl InnerCommandBufferBuilder {
                     entry.accesses &= stages;
                     entry.write = entry.write || write;
                     debug_assert_eq!(entry.new_layout, layout);
-                    entry.aspects |= aspect_mask;
+                    entry.aspects |= access.aspects;
+                    entry.merge_subresources(&access);
                 }
             }
         }
@@ -1319,11 +1971,21 @@ impl InnerCommandBufferBuilder {
         // TODO: check for collisions
         for block in buffer.blocks(range.clone()) {
             let key = (BufferKey(buffer.clone()), block);
-            self.render_pass_staging_required_buffer_accesses.insert(key, InternalBufferBlockAccess {
-                stages: stages,
-                accesses: accesses,
-                write: write,
-            });
+            match self.render_pass_staging_required_buffer_accesses.entry(key) {
+                Entry::Vacant(e) => {
+                    e.insert(InternalBufferBlockAccess {
+                        stages: stages,
+                        accesses: accesses,
+                        ranges: BufferAccessRanges::from_range(range.clone(), write),
+                    });
+                },
+                Entry::Occupied(mut entry) => {
+                    let mut entry = entry.get_mut();
+                    entry.stages |= stages;
+                    entry.accesses |= accesses;
+                    entry.ranges.add(range.clone(), write);
+                }
+            }
         }
     }
 
@@ -1334,25 +1996,13 @@ impl InnerCommandBufferBuilder {
                                  stages: vk::PipelineStageFlagBits, accesses: vk::AccessFlagBits)
     {
         // TODO: check for collisions
-        for block in image.blocks(mipmap_levels_range.clone(), array_layers_range.clone()) {
-            let key = (ImageKey(image.clone()), block);
-            let aspect_mask = match image.format().ty() {
-                FormatTy::Float | FormatTy::Uint | FormatTy::Sint | FormatTy::Compressed => {
-                    vk::IMAGE_ASPECT_COLOR_BIT
-                },
-                FormatTy::Depth => vk::IMAGE_ASPECT_DEPTH_BIT,
-                FormatTy::Stencil => vk::IMAGE_ASPECT_STENCIL_BIT,
-                FormatTy::DepthStencil => vk::IMAGE_ASPECT_DEPTH_BIT | vk::IMAGE_ASPECT_STENCIL_BIT,
-            };
+        let new_accesses = image_block_accesses(&image, mipmap_levels_range, array_layers_range,
+                                                write, initial_layout, final_layout, stages,
+                                                accesses);
 
-            self.render_pass_staging_required_image_accesses.insert(key, InternalImageBlockAccess {
-                stages: stages,
-                accesses: accesses,
-                write: write,
-                aspects: aspect_mask,
-                old_layout: initial_layout,
-                new_layout: final_layout,
-            });
+        for (block, access) in new_accesses.into_iter() {
+            let key = (ImageKey(image.clone()), block);
+            self.render_pass_staging_required_image_accesses.insert(key, access);
         }
     }
 
@@ -1364,7 +2014,7 @@ impl InnerCommandBufferBuilder {
         let mut conflict = false;
         for (key, access) in self.render_pass_staging_required_buffer_accesses.iter() {
             if let Some(ex_acc) = self.staging_required_buffer_accesses.get(&key) {
-                if access.write || ex_acc.write {
+                if access.ranges.conflicts_with(&ex_acc.ranges) {
                     conflict = true;
                     break;
                 }
@@ -1373,7 +2023,7 @@ impl InnerCommandBufferBuilder {
         if !conflict {
             for (key, access) in self.render_pass_staging_required_image_accesses.iter() {
                 if let Some(ex_acc) = self.staging_required_image_accesses.get(&key) {
-                    if access.write || ex_acc.write ||
+                    if access.conflicts_with(ex_acc) ||
                        (ex_acc.aspects & access.aspects) != ex_acc.aspects ||
                        access.old_layout != ex_acc.new_layout
                     {
@@ -1398,9 +2048,10 @@ impl InnerCommandBufferBuilder {
                 Entry::Vacant(e) => { e.insert(access); },
                 Entry::Occupied(mut entry) => {
                     let mut entry = entry.get_mut();
-                    debug_assert!(!entry.write && !access.write);
+                    debug_assert!(!entry.ranges.conflicts_with(&access.ranges));
                     entry.stages |= access.stages;
                     entry.accesses |= access.accesses;
+                    entry.ranges.merge(&access.ranges);
                 }
             }
         }
@@ -1409,10 +2060,12 @@ impl InnerCommandBufferBuilder {
                 Entry::Vacant(e) => { e.insert(access); },
                 Entry::Occupied(mut entry) => {
                     let mut entry = entry.get_mut();
-                    debug_assert!(!entry.write && !access.write);
+                    debug_assert!(!entry.conflicts_with(&access));
                     debug_assert_eq!(entry.new_layout, access.old_layout);
                     entry.stages |= access.stages;
                     entry.accesses |= access.accesses;
+                    entry.write = entry.write || access.write;
+                    entry.merge_subresources(&access);
                     entry.new_layout = access.new_layout;
                 }
             }
@@ -1480,7 +2133,7 @@ impl InnerCommandBufferBuilder {
                 Entry::Occupied(mut entry) => {
                     let entry = entry.get_mut();
 
-                    if entry.write || access.write {
+                    if entry.ranges.conflicts_with(&access.ranges) {
                         src_stages |= entry.stages;
                         dst_stages |= access.stages;
 
@@ -1501,9 +2154,12 @@ impl InnerCommandBufferBuilder {
 
                         entry.stages = access.stages;
                         entry.accesses = access.accesses;
+                    } else {
+                        entry.stages |= access.stages;
+                        entry.accesses |= access.accesses;
                     }
 
-                    entry.write = entry.write || access.write;
+                    entry.ranges.merge(&access.ranges);
                 },
             }
         }
@@ -1559,6 +2215,8 @@ impl InnerCommandBufferBuilder {
                         accesses: access.accesses,
                         write: access.write,
                         aspects: access.aspects,
+                        mipmap_levels: access.mipmap_levels,
+                        array_layers: access.array_layers,
                         old_layout: extern_layout,
                         new_layout: access.new_layout,
                     });
@@ -1597,6 +2255,9 @@ impl InnerCommandBufferBuilder {
                     // TODO: incomplete
                     entry.stages = access.stages;
                     entry.accesses = access.accesses;
+                    entry.write = entry.write || access.write;
+                    entry.aspects |= access.aspects;
+                    entry.merge_subresources(&access);
                     entry.new_layout = access.new_layout;
                 },
             };
@@ -1629,6 +2290,9 @@ impl InnerCommandBufferBuilder {
 
     /// Finishes building the command buffer.
     pub fn build(mut self) -> Result<InnerCommandBuffer, OomError> {
+        assert!(self.active_queries.is_empty(),
+                "All the queries must be ended before building the command buffer");
+
         unsafe {
             self.flush_render_pass();
             self.flush(true);
@@ -1651,6 +2315,8 @@ impl InnerCommandBufferBuilder {
                             accesses: accesses,
                             write: false,
                             aspects: access.aspects,
+                            mipmap_levels: access.mipmap_levels.clone(),
+                            array_layers: access.array_layers.clone(),
                             old_layout: final_layout,
                             new_layout: final_layout,
                         });
@@ -1663,10 +2329,14 @@ impl InnerCommandBufferBuilder {
                         continue;
                     }
 
+                    // The host reads everything that the command buffer accessed.
+                    let mut ranges = BufferAccessRanges::new();
+                    for r in access.ranges.iter() { ranges.add(r.range.clone(), false); }
+
                     self.staging_required_buffer_accesses.insert(buffer.clone(), InternalBufferBlockAccess {
                         stages: vk::PIPELINE_STAGE_HOST_BIT,
                         accesses: vk::ACCESS_HOST_READ_BIT | vk::ACCESS_HOST_WRITE_BIT,
-                        write: false,
+                        ranges: ranges,
                     });
                 }
 
@@ -1691,31 +2361,25 @@ impl InnerCommandBufferBuilder {
                 images_state: self.images_state.clone(),        // TODO: meh
                 extern_buffers_sync: {
                     let mut map = HashMap::with_hasher(BuildHasherDefault::<FnvHasher>::default());
-                    for ((buf, bl), access) in self.buffers_state.drain() {
-                        let value = BufferAccessRange {
-                            block: bl,
-                            write: access.write,
-                        };
-
+                    for ((buf, _), access) in self.buffers_state.drain() {
                         match map.entry(buf) {
-                            Entry::Vacant(e) => {
-                                let mut v = SmallVec::new();
-                                v.push(value);
-                                e.insert(v);
-                            },
-                            Entry::Occupied(mut e) => {
-                                e.get_mut().push(value);
-                            },
+                            Entry::Vacant(e) => { e.insert(access.ranges); },
+                            Entry::Occupied(mut e) => { e.get_mut().merge(&access.ranges); },
                         }
                     }
 
-                    map.into_iter().map(|(buf, val)| (buf.0, val)).collect()
+                    map.into_iter().map(|(buf, ranges): (_, BufferAccessRanges)| {
+                        (buf.0, ranges.iter().cloned().collect())
+                    }).collect()
                 },
                 extern_images_sync: {
                     let mut map = HashMap::with_hasher(BuildHasherDefault::<FnvHasher>::default());
                     for ((img, bl), access) in self.images_state.drain() {
                         let value = ImageAccessRange {
                             block: bl,
+                            mipmap_levels: access.mipmap_levels,
+                            array_layers: access.array_layers,
+                            aspects: ImageAspects::from_bits(access.aspects),
                             write: access.write,
                             initial_layout: access.old_layout,
                             final_layout: access.new_layout,
@@ -1779,6 +2443,19 @@ pub struct InnerCommandBuffer {
 ///
 pub fn submit(me: &InnerCommandBuffer, me_arc: Arc<KeepAlive>,
               queue: &Arc<Queue>) -> Result<Arc<Submission>, OomError>   // TODO: wrong error type
+{
+    let fence = try!(FencePool::get(&queue.device().fence_pool()));
+    submit_with(me, me_arc, queue, fence, &[], &[])
+}
+
+/// Same as `submit`, but signals `fence` instead of a fence taken from the pool of the device.
+///
+/// The submission also waits upon each semaphore of `wait_semaphores` at the given stages, and
+/// signals each semaphore of `signal_semaphores` once it is finished.
+pub fn submit_with(me: &InnerCommandBuffer, me_arc: Arc<KeepAlive>, queue: &Arc<Queue>,
+                   fence: FenceGuard, wait_semaphores: &[(Arc<Semaphore>, PipelineStages)],
+                   signal_semaphores: &[Arc<Semaphore>])
+                   -> Result<Arc<Submission>, OomError>   // TODO: wrong error type
 {
     // TODO: see comment of GLOBAL_MUTEX
     let _global_lock = GLOBAL_MUTEX.lock().unwrap();
@@ -1788,10 +2465,6 @@ pub fn submit(me: &InnerCommandBuffer, me_arc: Arc<KeepAlive>,
     assert_eq!(queue.device().internal_object(), me.pool.device().internal_object());
     assert_eq!(queue.family().id(), me.pool.queue_family().id());
 
-    // TODO: check if this change is okay (maybe the Arc can be omitted?) - Mixthos
-    //let fence = try!(Fence::new(queue.device()));
-    let fence = Arc::new(try!(Fence::raw(queue.device())));
-
     let mut keep_alive_semaphores = SmallVec::<[_; 8]>::new();
     let mut post_semaphores_ids = SmallVec::<[_; 8]>::new();
     let mut pre_semaphores_ids = SmallVec::<[_; 8]>::new();
@@ -1815,6 +2488,17 @@ pub fn submit(me: &InnerCommandBuffer, me_arc: Arc<KeepAlive>,
         keep_alive_semaphores.push(signalled);
     }
 
+    // Semaphores requested by the caller.
+    for &(ref semaphore, stages) in wait_semaphores {
+        pre_semaphores_ids.push(semaphore.internal_object());
+        pre_semaphores_stages.push(stages.into());
+        keep_alive_semaphores.push(semaphore.clone());
+    }
+    for semaphore in signal_semaphores {
+        post_semaphores_ids.push(semaphore.internal_object());
+        keep_alive_semaphores.push(semaphore.clone());
+    }
+
     // Creating additional semaphores, one for each queue transition.
     let queue_transitions_hint: u32 = 2;        // TODO: get as function parameter
     // TODO: use a pool
@@ -1834,7 +2518,7 @@ pub fn submit(me: &InnerCommandBuffer, me_arc: Arc<KeepAlive>,
     // We can now create the `Submission` object.
     // We need to create it early because we pass it when calling `gpu_access`.
     let submission = Arc::new(Submission {
-        fence: fence.clone(),
+        fence: fence,
         queue: queue.clone(),
         guarded: Mutex::new(SubmissionGuarded {
             signalled_semaphores: semaphores_to_signal,
@@ -2020,9 +2704,9 @@ pub fn submit(me: &InnerCommandBuffer, me_arc: Arc<KeepAlive>,
                 });
             }
 
-            let fence = fence.internal_object();
             try!(check_errors(vk.QueueSubmit(*queue.internal_object_guard(), infos.len() as u32,
-                                             infos.as_ptr(), fence)));
+                                             infos.as_ptr(), submission.fence.internal_object())));
+            submission.fence.mark_submitted();
         }
 
         // Don't forget to add all the semaphores in the list of semaphores that must be kept alive.
@@ -2036,6 +2720,15 @@ pub fn submit(me: &InnerCommandBuffer, me_arc: Arc<KeepAlive>,
     Ok(submission)
 }
 
+unsafe impl VulkanObject for InnerCommandBuffer {
+    type Object = vk::CommandBuffer;
+
+    #[inline]
+    fn internal_object(&self) -> vk::CommandBuffer {
+        self.cmd
+    }
+}
+
 impl Drop for InnerCommandBuffer {
     #[inline]
     fn drop(&mut self) {
@@ -2049,7 +2742,7 @@ impl Drop for InnerCommandBuffer {
 
 #[must_use]
 pub struct Submission {
-    fence: Arc<Fence>,
+    fence: FenceGuard,
 
     // The queue on which this was submitted.
     queue: Arc<Queue>,
@@ -2120,23 +2813,51 @@ impl Submission {
     pub fn queue(&self) -> &Arc<Queue> {
         &self.queue
     }
+
+    /// Returns the fence that is signaled when the submission has finished.
+    ///
+    /// The fence belongs to the submission and is reused once the submission is destroyed, so
+    /// it isn't exposed outside of the crate.
+    #[inline]
+    pub(crate) fn fence(&self) -> &Arc<Fence> {
+        self.fence.fence()
+    }
 }
 
 impl Drop for Submission {
     #[inline]
     fn drop(&mut self) {
-        let timeout = Duration::new(u64::MAX / 1_000_000_000, (u64::MAX % 1_000_000_000) as u32);
-        match self.fence.wait(timeout) {
+        match self.fence.wait_forever() {
             Ok(_) => (),
             Err(FenceWaitError::DeviceLostError) => (),
             Err(FenceWaitError::Timeout) => panic!(),       // The driver has some sort of problem.
             Err(FenceWaitError::OomError(_)) => panic!(),   // What else to do here?
+            Err(FenceWaitError::Unexpected(err)) => {
+                // We don't know whether the GPU is still using the command buffers and the
+                // semaphores, so they are leaked instead of being destroyed.
+                let _ = writeln!(io::stderr(), "vulkano: failed to wait for the end of a \
+                                                submission, leaking its resources: {}", err);
+                let cbs = mem::replace(lock_mut(&mut self.keep_alive_cb), SmallVec::new());
+                mem::forget(cbs);
+                let semaphores = mem::replace(lock_mut(&mut self.keep_alive_semaphores),
+                                              SmallVec::new());
+                mem::forget(semaphores);
+            },
         }
 
         // TODO: return `signalled_semaphores` to the semaphore pools
     }
 }
 
+// Returns the content of a mutex, even if it has been poisoned.
+#[inline]
+fn lock_mut<T>(mutex: &mut Mutex<T>) -> &mut T {
+    match mutex.get_mut() {
+        Ok(content) => content,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
 pub trait KeepAlive: 'static + Send + Sync {}
 impl<T> KeepAlive for T where T: 'static + Send + Sync {}
 
@@ -2160,7 +2881,7 @@ impl hash::Hash for BufferKey {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 struct InternalBufferBlockAccess {
     // Stages in which the resource is used.
     // Note that this field can have different semantics depending on where this struct is used.
@@ -2171,7 +2892,9 @@ struct InternalBufferBlockAccess {
     // Just like `stages`, this has different semantics depending on the usage of this struct.
     accesses: vk::AccessFlagBits,
 
-    write: bool,
+    // Ranges of bytes of the buffer that are read or written. Two accesses only conflict if
+    // their ranges overlap and at least one of them is a write.
+    ranges: BufferAccessRanges,
 }
 
 #[derive(Clone)]
@@ -2194,7 +2917,7 @@ impl hash::Hash for ImageKey {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 struct InternalImageBlockAccess {
     // Stages in which the resource is used.
     // Note that this field can have different semantics depending on where this struct is used.
@@ -2207,10 +2930,307 @@ struct InternalImageBlockAccess {
 
     write: bool,
     aspects: vk::ImageAspectFlags,
+
+    // Mipmap levels and array layers of the block that are read or written. Two accesses only
+    // conflict if their subresources overlap and at least one of them is a write.
+    mipmap_levels: Range<u32>,
+    array_layers: Range<u32>,
+
     old_layout: ImageLayout,
     new_layout: ImageLayout,
 }
 
+impl InternalImageBlockAccess {
+    // Returns true if both accesses cover a common subresource and at least one of them is a
+    // write.
+    #[inline]
+    fn conflicts_with(&self, other: &InternalImageBlockAccess) -> bool {
+        (self.write || other.write) && (self.aspects & other.aspects) != 0 &&
+        self.mipmap_levels.start < other.mipmap_levels.end &&
+        other.mipmap_levels.start < self.mipmap_levels.end &&
+        self.array_layers.start < other.array_layers.end &&
+        other.array_layers.start < self.array_layers.end
+    }
+
+    // Extends the subresources covered by this access so that they include those of `other`.
+    #[inline]
+    fn merge_subresources(&mut self, other: &InternalImageBlockAccess) {
+        self.mipmap_levels = cmp::min(self.mipmap_levels.start, other.mipmap_levels.start) ..
+                             cmp::max(self.mipmap_levels.end, other.mipmap_levels.end);
+        self.array_layers = cmp::min(self.array_layers.start, other.array_layers.start) ..
+                            cmp::max(self.array_layers.end, other.array_layers.end);
+    }
+}
+
+// Descriptor sets bound to a bind point of a command buffer, and the layout of the pipeline
+// each of them was bound with.
+//
+// The layout is generic so that the bookkeeping can be tested without a device.
+struct BoundDescriptorSets<L = BoundPipelineLayout> {
+    sets: SmallVec<[Option<BoundSet<L>>; 8]>,
+}
+
+// A descriptor set bound at a given index.
+struct BoundSet<L> {
+    layout: Arc<L>,
+    set: vk::DescriptorSet,
+    dynamic_offsets: SmallVec<[u32; 4]>,
+}
+
+impl<L> BoundDescriptorSets<L> where L: SetsLayout {
+    #[inline]
+    fn new() -> BoundDescriptorSets<L> {
+        BoundDescriptorSets {
+            sets: SmallVec::new(),
+        }
+    }
+
+    // Records that `sets` are now bound starting at index 0 with `layout` and the dynamic
+    // offsets of each set, and returns the index of the first set that has to be bound again.
+    //
+    // The sets before this index are already bound and, according to the compatibility rules
+    // of pipeline layouts, stay valid with `layout`. The sets after the last one of `sets` are
+    // disturbed, and thus considered as unbound, unless the set that was previously bound at
+    // the index of the last one of `sets` was bound with a layout compatible with `layout`.
+    fn bind(&mut self, layout: &Arc<L>, sets: &[vk::DescriptorSet], dynamic_offsets: &[&[u32]])
+            -> usize
+    {
+        debug_assert_eq!(sets.len(), dynamic_offsets.len());
+
+        if sets.is_empty() {
+            return 0;
+        }
+
+        let mut first_set = 0;
+        while first_set < sets.len() {
+            match self.sets.get(first_set) {
+                Some(&Some(ref bound)) if bound.set == sets[first_set] &&
+                                          &bound.dynamic_offsets[..] == dynamic_offsets[first_set] &&
+                                          bound.layout.compatible_for_set(layout, first_set) => (),
+                _ => break,
+            }
+
+            first_set += 1;
+        }
+
+        let last_set = sets.len() - 1;
+        let keep_higher = match self.sets.get(last_set) {
+            Some(&Some(ref bound)) => bound.layout.compatible_for_set(layout, last_set),
+            _ => false,
+        };
+
+        let mut new_sets = SmallVec::new();
+        for (set, offsets) in sets.iter().zip(dynamic_offsets.iter()) {
+            new_sets.push(Some(BoundSet {
+                layout: layout.clone(),
+                set: *set,
+                dynamic_offsets: offsets.iter().cloned().collect(),
+            }));
+        }
+        if keep_higher {
+            for (num, bound) in self.sets.iter_mut().enumerate() {
+                if num > last_set {
+                    new_sets.push(bound.take());
+                }
+            }
+        }
+
+        self.sets = new_sets;
+        first_set
+    }
+
+    // Checks that all the sets of `layout` are bound with layouts compatible with it.
+    fn check(&self, layout: &L) -> Result<(), BoundDescriptorSetsError> {
+        for set_num in 0 .. layout.num_sets() {
+            match self.sets.get(set_num) {
+                Some(&Some(ref bound)) => {
+                    if !bound.layout.compatible_for_set(layout, set_num) {
+                        return Err(BoundDescriptorSetsError::IncompatibleSet { set_num: set_num });
+                    }
+                },
+                _ => return Err(BoundDescriptorSetsError::MissingSet { set_num: set_num }),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Description of a pipeline layout, as far as the compatibility rules of pipeline layouts are
+// concerned.
+trait SetsLayout {
+    // Returns the number of descriptor sets of the layout.
+    fn num_sets(&self) -> usize;
+
+    // Returns true if the two layouts are compatible for set `set_num`, in other words if they
+    // have the same push constants and identical set layouts up to `set_num` included.
+    fn compatible_for_set(&self, other: &Self, set_num: usize) -> bool;
+}
+
+// Layout of the pipeline that descriptor sets were bound with.
+struct BoundPipelineLayout {
+    set_layouts: SmallVec<[Arc<UnsafeDescriptorSetLayout>; 16]>,
+    push_constants: SmallVec<[PipelineLayoutDescPcRange; 8]>,
+}
+
+impl BoundPipelineLayout {
+    #[inline]
+    fn new(layout: &UnsafePipelineLayout) -> BoundPipelineLayout {
+        BoundPipelineLayout {
+            set_layouts: layout.descriptor_set_layouts().iter().cloned().collect(),
+            push_constants: layout.push_constants_ranges().iter().cloned().collect(),
+        }
+    }
+}
+
+impl SetsLayout for BoundPipelineLayout {
+    #[inline]
+    fn num_sets(&self) -> usize {
+        self.set_layouts.len()
+    }
+
+    #[inline]
+    fn compatible_for_set(&self, other: &BoundPipelineLayout, set_num: usize) -> bool {
+        set_num < self.set_layouts.len() && set_num < other.set_layouts.len() &&
+        layouts_compatible(&self.set_layouts, &self.push_constants,
+                           &other.set_layouts, &other.push_constants, set_num + 1)
+    }
+}
+
+/// Error that can happen when the descriptor sets bound to a command buffer don't match the
+/// layout of the pipeline used by a draw or dispatch command.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BoundDescriptorSetsError {
+    /// The pipeline layout uses a descriptor set that isn't bound, or that was disturbed by a
+    /// later binding of an incompatible layout.
+    MissingSet {
+        /// Index of the set.
+        set_num: usize,
+    },
+
+    /// The descriptor set was bound with a pipeline layout that isn't compatible with the
+    /// layout of the pipeline for this set.
+    IncompatibleSet {
+        /// Index of the set.
+        set_num: usize,
+    },
+}
+
+impl error::Error for BoundDescriptorSetsError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            BoundDescriptorSetsError::MissingSet { .. } => {
+                "a descriptor set used by the pipeline layout isn't bound"
+            },
+            BoundDescriptorSetsError::IncompatibleSet { .. } => {
+                "a descriptor set was bound with a pipeline layout that isn't compatible with \
+                 the layout of the pipeline"
+            },
+        }
+    }
+}
+
+impl fmt::Display for BoundDescriptorSetsError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            BoundDescriptorSetsError::MissingSet { set_num } |
+            BoundDescriptorSetsError::IncompatibleSet { set_num } => {
+                write!(fmt, "{} (set {})", error::Error::description(self), set_num)
+            },
+        }
+    }
+}
+
+// Splits the dynamic offsets of a list of descriptor sets into the offsets of each set.
+//
+// The offsets must have been checked with `check_dynamic_offsets` first.
+/// Describes a region of a copy between a buffer and an image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BufferImageCopyRegion {
+    /// Offset in bytes of the data of the region within the buffer.
+    pub buffer_offset: usize,
+    /// Number of texels of a row of the data in the buffer, or 0 if the rows are tightly packed.
+    pub buffer_row_length: u32,
+    /// Number of rows of an image of the data in the buffer, or 0 if the images are tightly
+    /// packed.
+    pub buffer_image_height: u32,
+    /// Mipmap level of the image.
+    pub mip_level: u32,
+    /// Array layers of the image.
+    pub array_layers: Range<u32>,
+    /// Offset in texels of the region within the mipmap level.
+    pub image_offset: [u32; 3],
+    /// Dimensions in texels of the region.
+    pub image_extent: [u32; 3],
+}
+
+fn split_dynamic_offsets<'a>(sets: &[Arc<DescriptorSet>], mut offsets: &'a [u32])
+                             -> SmallVec<[&'a [u32]; 32]>
+{
+    sets.iter().map(|set| {
+        let (set_offsets, rest) = offsets.split_at(set.inner_descriptor_set().layout()
+                                                      .num_dynamic_offsets());
+        offsets = rest;
+        set_offsets
+    }).collect()
+}
+
+// Splits an access to a range of subresources of an image into one access per block of the
+// image. Each access only covers the part of the range that is inside its block.
+fn image_block_accesses(image: &Arc<Image>, mipmap_levels: Range<u32>, array_layers: Range<u32>,
+                        write: bool, initial_layout: ImageLayout, final_layout: ImageLayout,
+                        stages: vk::PipelineStageFlagBits, accesses: vk::AccessFlagBits)
+                        -> Vec<((u32, u32), InternalImageBlockAccess)>
+{
+    let aspect_mask = match image.format().ty() {
+        FormatTy::Float | FormatTy::Uint | FormatTy::Sint | FormatTy::Compressed => {
+            vk::IMAGE_ASPECT_COLOR_BIT
+        },
+        FormatTy::Depth => vk::IMAGE_ASPECT_DEPTH_BIT,
+        FormatTy::Stencil => vk::IMAGE_ASPECT_STENCIL_BIT,
+        FormatTy::DepthStencil => vk::IMAGE_ASPECT_DEPTH_BIT | vk::IMAGE_ASPECT_STENCIL_BIT,
+    };
+
+    image.blocks(mipmap_levels.clone(), array_layers.clone()).into_iter().map(|block| {
+        let block_levels = image.block_mipmap_levels_range(block);
+        let block_layers = image.block_array_layers_range(block);
+
+        let access = InternalImageBlockAccess {
+            stages: stages,
+            accesses: accesses,
+            write: write,
+            aspects: aspect_mask,
+            mipmap_levels: cmp::max(mipmap_levels.start, block_levels.start) ..
+                           cmp::min(mipmap_levels.end, block_levels.end),
+            array_layers: cmp::max(array_layers.start, block_layers.start) ..
+                          cmp::min(array_layers.end, block_layers.end),
+            old_layout: initial_layout,
+            new_layout: final_layout,
+        };
+
+        (block, access)
+    }).collect()
+}
+
+// Panics if a copy between a buffer and a region of an image is invalid.
+fn check_copy_region(image: &Image, mip_level: u32, array_layers: &Range<u32>, offset: [u32; 3],
+                     extent: [u32; 3], buffer_offset: usize, buffer_size: usize)
+{
+    assert!(mip_level < image.inner_image().mipmap_levels());
+
+    let level_dimensions = image.dimensions().mipmap_dimensions(mip_level);
+
+    let num_layers = array_layers.end - array_layers.start;
+    match check_buffer_image_copy(image.format(), level_dimensions, offset, extent, num_layers,
+                                  buffer_offset, buffer_size)
+    {
+        Ok(()) => (),
+        Err(err) => panic!("invalid copy between a buffer and an image: {}", err)
+    }
+}
+
 /// Builds an `InnerCommandBuffer` whose only purpose is to transition an image between two
 /// layouts.
 fn transition_cb(pool: &Arc<CommandBufferPool>, image: Arc<Image>, block: (u32, u32),
@@ -2296,3 +3316,97 @@ fn transition_cb(pool: &Arc<CommandBufferPool>, image: Arc<Image>, block: (u32,
         keep_alive: Vec::new(),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use command_buffer::inner::BoundDescriptorSets;
+    use command_buffer::inner::BoundDescriptorSetsError;
+    use command_buffer::inner::SetsLayout;
+
+    // Pipeline layout whose set layouts and push constants are identified by numbers.
+    struct MockLayout {
+        sets: Vec<u32>,
+        push_constants: u32,
+    }
+
+    impl SetsLayout for MockLayout {
+        fn num_sets(&self) -> usize {
+            self.sets.len()
+        }
+
+        fn compatible_for_set(&self, other: &MockLayout, set_num: usize) -> bool {
+            set_num < self.sets.len() && set_num < other.sets.len() &&
+            self.push_constants == other.push_constants &&
+            self.sets[.. set_num + 1] == other.sets[.. set_num + 1]
+        }
+    }
+
+    fn layout(sets: &[u32], push_constants: u32) -> Arc<MockLayout> {
+        Arc::new(MockLayout { sets: sets.to_vec(), push_constants: push_constants })
+    }
+
+    #[test]
+    fn missing_sets() {
+        let bound = BoundDescriptorSets::<MockLayout>::new();
+        assert_eq!(bound.check(&layout(&[], 0)), Ok(()));
+        assert_eq!(bound.check(&layout(&[1, 2], 0)),
+                   Err(BoundDescriptorSetsError::MissingSet { set_num: 0 }));
+    }
+
+    #[test]
+    fn rebind_skips_bound_sets() {
+        let layout_a = layout(&[1, 2], 0);
+        let mut bound = BoundDescriptorSets::new();
+
+        assert_eq!(bound.bind(&layout_a, &[10, 11], &[&[], &[]]), 0);
+        assert_eq!(bound.bind(&layout_a, &[10, 11], &[&[], &[]]), 2);
+        assert_eq!(bound.bind(&layout_a, &[10, 12], &[&[], &[]]), 1);
+        assert_eq!(bound.bind(&layout_a, &[10, 12], &[&[], &[64]]), 1);
+        assert_eq!(bound.check(&layout_a), Ok(()));
+    }
+
+    #[test]
+    fn compatible_binding_keeps_higher_sets() {
+        let layout_a = layout(&[1, 2], 0);
+        let layout_b = layout(&[1, 3], 0);
+        let mut bound = BoundDescriptorSets::new();
+
+        bound.bind(&layout_a, &[10, 11], &[&[], &[]]);
+        bound.bind(&layout_b, &[12], &[&[]]);
+
+        assert_eq!(bound.check(&layout_a), Ok(()));
+        assert_eq!(bound.check(&layout(&[1], 0)), Ok(()));
+        assert_eq!(bound.check(&layout_b),
+                   Err(BoundDescriptorSetsError::IncompatibleSet { set_num: 1 }));
+    }
+
+    #[test]
+    fn incompatible_binding_disturbs_higher_sets() {
+        let layout_a = layout(&[1, 2], 0);
+        let layout_c = layout(&[5, 2], 0);
+        let mut bound = BoundDescriptorSets::new();
+
+        bound.bind(&layout_a, &[10, 11], &[&[], &[]]);
+        bound.bind(&layout_c, &[12], &[&[]]);
+
+        assert_eq!(bound.check(&layout_a),
+                   Err(BoundDescriptorSetsError::IncompatibleSet { set_num: 0 }));
+        assert_eq!(bound.check(&layout_c),
+                   Err(BoundDescriptorSetsError::MissingSet { set_num: 1 }));
+    }
+
+    #[test]
+    fn push_constants_disturb_higher_sets() {
+        let layout_a = layout(&[1, 2], 0);
+        let layout_d = layout(&[1, 2], 1);
+        let mut bound = BoundDescriptorSets::new();
+
+        bound.bind(&layout_a, &[10, 11], &[&[], &[]]);
+        assert_eq!(bound.bind(&layout_d, &[10], &[&[]]), 0);
+
+        assert_eq!(bound.check(&layout_d),
+                   Err(BoundDescriptorSetsError::MissingSet { set_num: 1 }));
+    }
+}
@@ -41,9 +41,17 @@
 // API has several different command buffer wrappers, but they all use the same internal
 // struct. The restrictions are enforced only in the public types.
 
+pub use self::inner::BoundDescriptorSetsError;
+pub use self::inner::BufferImageCopyRegion;
 pub use self::inner::Submission;
 pub use self::outer::submit;
+#[doc(hidden)]
+pub use self::outer::submit_with;
 pub use self::outer::DynamicState;
+pub use self::outer::DynamicStateError;
+pub use self::outer::DynamicStateKind;
+pub use self::outer::DynamicStencilValue;
+pub use self::outer::MipmapGenerationError;
 pub use self::outer::PrimaryCommandBufferBuilder;
 pub use self::outer::PrimaryCommandBufferBuilderInlineDraw;
 pub use self::outer::PrimaryCommandBufferBuilderSecondaryDraw;
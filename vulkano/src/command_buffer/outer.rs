@@ -7,6 +7,8 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
+use std::error;
+use std::fmt;
 use std::ops::Range;
 use std::sync::Arc;
 use smallvec::SmallVec;
@@ -16,13 +18,16 @@ use buffer::BufferSlice;
 use buffer::TypedBuffer;
 use command_buffer::CommandBufferPool;
 use command_buffer::DrawIndirectCommand;
+use command_buffer::inner::BufferImageCopyRegion;
 use command_buffer::inner::InnerCommandBufferBuilder;
 use command_buffer::inner::InnerCommandBuffer;
 use command_buffer::inner::Submission;
 use command_buffer::inner::submit as inner_submit;
+use command_buffer::inner::submit_with as inner_submit_with;
 use descriptor::descriptor_set::DescriptorSetsCollection;
 use descriptor::PipelineLayout;
 use device::Queue;
+use format::ClearValue;
 use framebuffer::Framebuffer;
 use framebuffer::UnsafeRenderPass;
 use framebuffer::RenderPassCompatible;
@@ -34,14 +39,23 @@ use image::traits::Image;
 use image::traits::ImageClearValue;
 use image::traits::ImageContent;
 use pipeline::ComputePipeline;
+use pipeline::DynamicStateFlags;
 use pipeline::GraphicsPipeline;
 use pipeline::input_assembly::Index;
+use pipeline::raster::DepthBias;
 use pipeline::vertex::Definition as VertexDefinition;
 use pipeline::vertex::Source as VertexSource;
 use pipeline::viewport::Viewport;
 use pipeline::viewport::Scissor;
+use query::QueryPool;
+use query::TimestampQueryPool;
+use sync::FenceGuard;
+use sync::PipelineStages;
+use sync::Semaphore;
 
 use OomError;
+use VulkanObject;
+use vk;
 
 /// A prototype of a primary command buffer.
 ///
@@ -158,6 +172,30 @@ impl PrimaryCommandBufferBuilder {
         }
     }
 
+    /// Copies raw data from a buffer to one or more regions of an image.
+    ///
+    /// Contrary to `copy_buffer_to_color_image`, the content of the buffer isn't checked against
+    /// the format of the image, and the rows and images of the data in the buffer can be padded.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the buffer wasn't created with the `transfer_src` usage or the image with the
+    ///   `transfer_dest` usage.
+    /// - Panics if the image has both a depth and a stencil aspect.
+    /// - Panics if one of the regions is invalid for the image or the buffer.
+    ///
+    pub fn copy_buffer_to_image_untyped<B, I, R>(self, source: &Arc<B>, destination: &Arc<I>,
+                                                 regions: R) -> PrimaryCommandBufferBuilder
+        where B: Buffer + 'static, I: Image + 'static,
+              R: IntoIterator<Item = BufferImageCopyRegion>
+    {
+        unsafe {
+            PrimaryCommandBufferBuilder {
+                inner: self.inner.copy_buffer_to_image_untyped(source, destination, regions),
+            }
+        }
+    }
+
     pub fn copy_color_image_to_buffer<'a, P, S, Img, Sb>(self, dest: S, destination: &Arc<Img>, mip_level: u32, array_layers_range: Range<u32>,
                                                          offset: [u32; 3], extent: [u32; 3])
                                                     -> PrimaryCommandBufferBuilder
@@ -187,6 +225,53 @@ impl PrimaryCommandBufferBuilder {
         }
     }
 
+    /// Generates the content of all the mipmap levels of an image except the first one, by
+    /// successively blitting each level to the next one with linear filtering.
+    ///
+    /// All the array layers of the image are processed. The layout transitions between the
+    /// levels are handled automatically, and the image is returned to the layout it wants at the
+    /// end of the command buffer.
+    ///
+    /// Returns an error if the image has only one mipmap level, or if its format doesn't
+    /// support blitting with linear filtering.
+    pub fn generate_mipmaps<I>(self, image: &Arc<I>)
+                               -> Result<PrimaryCommandBufferBuilder, MipmapGenerationError>
+        where I: Image + 'static
+    {
+        let inner_image = image.inner_image();
+
+        let num_mipmaps = inner_image.mipmap_levels();
+        if num_mipmaps < 2 {
+            return Err(MipmapGenerationError::NotEnoughMipmapLevels);
+        }
+
+        if !inner_image.supports_blit_source() || !inner_image.supports_blit_destination() {
+            return Err(MipmapGenerationError::BlitNotSupported);
+        }
+
+        if !inner_image.supports_linear_filtering() {
+            return Err(MipmapGenerationError::LinearFilteringNotSupported);
+        }
+
+        let dimensions = inner_image.dimensions();
+        let layers = 0 .. dimensions.array_layers();
+        let level_dims = |level: u32| {
+            let dims = dimensions.mipmap_dimensions(level);
+            [dims[0] as i32, dims[1] as i32, dims[2] as i32]
+        };
+
+        let mut cb = self;
+        for level in 1 .. num_mipmaps {
+            let src = level_dims(level - 1);
+            let dest = level_dims(level);
+
+            cb = cb.blit(image, level - 1, layers.clone(), [0 .. src[0], 0 .. src[1], 0 .. src[2]],
+                         image, level, layers.clone(), [0 .. dest[0], 0 .. dest[1], 0 .. dest[2]]);
+        }
+
+        Ok(cb)
+    }
+
     ///
     /// Note that compressed formats are not supported.
     pub fn clear_color_image<'a, I, V>(self, image: &Arc<I>, color: V)
@@ -200,6 +285,26 @@ impl PrimaryCommandBufferBuilder {
         }
     }
 
+    /// Clears the content of an image whose format has a depth and/or a stencil component.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the queue family doesn't support graphics operations.
+    /// - Panicks if the format of the image doesn't have a depth or a stencil component.
+    /// - Panicks if the image wasn't created with the `transfer_dest` usage.
+    /// - Panicks if the clear value doesn't match the format of the image.
+    ///
+    pub fn clear_depth_stencil_image<I, V>(self, image: &Arc<I>, value: V)
+                                           -> PrimaryCommandBufferBuilder
+        where I: ImageClearValue<V> + 'static
+    {
+        unsafe {
+            PrimaryCommandBufferBuilder {
+                inner: self.inner.clear_depth_stencil_image(image, value),
+            }
+        }
+    }
+
     /// Executes secondary compute command buffers within this primary command buffer.
     #[inline]
     pub fn execute_commands(self, cb: &Arc<SecondaryComputeCommandBuffer>)
@@ -227,6 +332,96 @@ impl PrimaryCommandBufferBuilder {
         }
     }
 
+    /// Resets the queries of `range` in a query pool.
+    ///
+    /// Queries must be reset before they can be begun or written.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if `range` is empty or out of the pool.
+    /// - Panicks if the pool wasn't created with the same device as the command buffer.
+    ///
+    #[inline]
+    pub fn reset_query_pool<P>(self, pool: &Arc<P>, range: Range<u32>)
+                               -> PrimaryCommandBufferBuilder
+        where P: QueryPool
+    {
+        unsafe {
+            let count = range.end.saturating_sub(range.start);
+            PrimaryCommandBufferBuilder {
+                inner: self.inner.reset_query_pool(pool, range.start, count),
+            }
+        }
+    }
+
+    /// Begins a query of a query pool.
+    ///
+    /// If `precise` is true, an occlusion query returns the exact number of samples that passed
+    /// the tests instead of just a non-zero value.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the query is out of the pool.
+    /// - Panicks if the pool wasn't created with the same device as the command buffer.
+    /// - Panicks if the pool contains timestamp queries.
+    /// - Panicks if a query of the same type is already active in this command buffer.
+    /// - Panicks if `precise` is true and the query isn't an occlusion query or the
+    ///   `occlusion_query_precise` feature isn't enabled.
+    ///
+    #[inline]
+    pub fn begin_query<P>(self, pool: &Arc<P>, query: u32, precise: bool)
+                          -> PrimaryCommandBufferBuilder
+        where P: QueryPool
+    {
+        unsafe {
+            PrimaryCommandBufferBuilder {
+                inner: self.inner.begin_query(pool, query, precise),
+            }
+        }
+    }
+
+    /// Ends a query that was begun with `begin_query`.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the query wasn't begun in this command buffer.
+    ///
+    #[inline]
+    pub fn end_query<P>(self, pool: &Arc<P>, query: u32) -> PrimaryCommandBufferBuilder
+        where P: QueryPool
+    {
+        unsafe {
+            PrimaryCommandBufferBuilder {
+                inner: self.inner.end_query(pool, query),
+            }
+        }
+    }
+
+    /// Writes the current time in a timestamp query once all the previous commands have reached
+    /// `stage`.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the query is out of the pool.
+    /// - Panicks if the pool wasn't created with the same device as the command buffer.
+    /// - Panicks if the pool was created for another queue family than the one of the command
+    ///   buffer.
+    /// - Panicks if `stage` doesn't contain exactly one stage.
+    ///
+    #[inline]
+    pub fn write_timestamp(self, pool: &Arc<TimestampQueryPool>, stage: PipelineStages,
+                           query: u32) -> PrimaryCommandBufferBuilder
+    {
+        assert_eq!(pool.queue_family().id(), self.inner.queue_family_id(),
+                   "The query pool was created for another queue family");
+
+        unsafe {
+            PrimaryCommandBufferBuilder {
+                inner: self.inner.write_timestamp(pool, stage, query),
+            }
+        }
+    }
+
     /// Start drawing on a framebuffer.
     //
     /// This function returns an object that can be used to submit draw commands on the first
@@ -235,6 +430,8 @@ impl PrimaryCommandBufferBuilder {
     /// # Panic
     ///
     /// - Panicks if the framebuffer is not compatible with the renderpass.
+    /// - Panicks if the clear values don't match the attachments of the render pass. See
+    ///   `RenderPassDesc::check_clear_values`.
     ///
     // FIXME: rest of the parameters (render area and clear attachment values)
     #[inline]
@@ -249,6 +446,7 @@ impl PrimaryCommandBufferBuilder {
         // TODO: allocate on stack instead (https://github.com/rust-lang/rfcs/issues/618)
         let clear_values = framebuffer.render_pass().convert_clear_values(clear_values)
                                       .collect::<SmallVec<[_; 16]>>();
+        check_clear_values(&**framebuffer.render_pass(), &clear_values);
 
         unsafe {
             let inner = self.inner.begin_renderpass(renderpass, framebuffer, false, &clear_values);
@@ -269,6 +467,8 @@ impl PrimaryCommandBufferBuilder {
     /// # Panic
     ///
     /// - Panicks if the framebuffer is not compatible with the renderpass.
+    /// - Panicks if the clear values don't match the attachments of the render pass. See
+    ///   `RenderPassDesc::check_clear_values`.
     ///
     // FIXME: rest of the parameters (render area and clear attachment values)
     #[inline]
@@ -282,6 +482,7 @@ impl PrimaryCommandBufferBuilder {
 
         let clear_values = framebuffer.render_pass().convert_clear_values(clear_values)
                                       .collect::<SmallVec<[_; 16]>>();
+        check_clear_values(&**framebuffer.render_pass(), &clear_values);
 
         unsafe {
             let inner = self.inner.begin_renderpass(renderpass, framebuffer, true, &clear_values);
@@ -359,6 +560,63 @@ impl PrimaryCommandBufferBuilderInlineDraw {
         }
     }
 
+    /// Begins a query of a query pool.
+    ///
+    /// See `PrimaryCommandBufferBuilder::begin_query`. The query must be ended before the end of
+    /// the current subpass.
+    #[inline]
+    pub fn begin_query<P>(self, pool: &Arc<P>, query: u32, precise: bool)
+                          -> PrimaryCommandBufferBuilderInlineDraw
+        where P: QueryPool
+    {
+        unsafe {
+            PrimaryCommandBufferBuilderInlineDraw {
+                inner: self.inner.begin_query(pool, query, precise),
+                num_subpasses: self.num_subpasses,
+                current_subpass: self.current_subpass,
+            }
+        }
+    }
+
+    /// Ends a query that was begun with `begin_query`.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the query wasn't begun in this command buffer.
+    /// - Panicks if the query wasn't begun in the current subpass.
+    ///
+    #[inline]
+    pub fn end_query<P>(self, pool: &Arc<P>, query: u32) -> PrimaryCommandBufferBuilderInlineDraw
+        where P: QueryPool
+    {
+        unsafe {
+            PrimaryCommandBufferBuilderInlineDraw {
+                inner: self.inner.end_query(pool, query),
+                num_subpasses: self.num_subpasses,
+                current_subpass: self.current_subpass,
+            }
+        }
+    }
+
+    /// Writes the current time in a timestamp query.
+    ///
+    /// See `PrimaryCommandBufferBuilder::write_timestamp`.
+    #[inline]
+    pub fn write_timestamp(self, pool: &Arc<TimestampQueryPool>, stage: PipelineStages,
+                           query: u32) -> PrimaryCommandBufferBuilderInlineDraw
+    {
+        assert_eq!(pool.queue_family().id(), self.inner.queue_family_id(),
+                   "The query pool was created for another queue family");
+
+        unsafe {
+            PrimaryCommandBufferBuilderInlineDraw {
+                inner: self.inner.write_timestamp(pool, stage, query),
+                num_subpasses: self.num_subpasses,
+                current_subpass: self.current_subpass,
+            }
+        }
+    }
+
     /// Switches to the next subpass of the current renderpass.
     ///
     /// This function is similar to `draw_inline` on the builder.
@@ -366,6 +624,7 @@ impl PrimaryCommandBufferBuilderInlineDraw {
     /// # Panic
     ///
     /// - Panicks if no more subpasses remain.
+    /// - Panicks if a query begun in the current subpass hasn't been ended.
     ///
     #[inline]
     pub fn next_subpass_inline(self) -> PrimaryCommandBufferBuilderInlineDraw {
@@ -389,6 +648,7 @@ impl PrimaryCommandBufferBuilderInlineDraw {
     /// # Panic
     ///
     /// - Panicks if no more subpasses remain.
+    /// - Panicks if a query begun in the current subpass hasn't been ended.
     ///
     #[inline]
     pub fn next_subpass_secondary(self) -> PrimaryCommandBufferBuilderSecondaryDraw {
@@ -410,6 +670,7 @@ impl PrimaryCommandBufferBuilderInlineDraw {
     /// # Panic
     ///
     /// - Panicks if not at the last subpass.
+    /// - Panicks if a query begun in the current subpass hasn't been ended.
     ///
     #[inline]
     pub fn draw_end(self) -> PrimaryCommandBufferBuilder {
@@ -523,6 +784,17 @@ pub struct PrimaryCommandBuffer {
     inner: InnerCommandBuffer,
 }
 
+impl_debug_handle!(PrimaryCommandBuffer);
+
+unsafe impl VulkanObject for PrimaryCommandBuffer {
+    type Object = vk::CommandBuffer;
+
+    #[inline]
+    fn internal_object(&self) -> vk::CommandBuffer {
+        self.inner.internal_object()
+    }
+}
+
 /// Submits the command buffer to a queue so that it is executed.
 ///
 /// Fences and semaphores are automatically handled.
@@ -539,6 +811,27 @@ pub fn submit(cmd: &Arc<PrimaryCommandBuffer>, queue: &Arc<Queue>)
     inner_submit(&cmd.inner, cmd.clone() as Arc<_>, queue)
 }
 
+/// Same as `submit`, but signals `fence` and waits upon and signals additional semaphores.
+#[doc(hidden)]
+pub fn submit_with(cmd: &Arc<PrimaryCommandBuffer>, queue: &Arc<Queue>, fence: FenceGuard,
+                   wait_semaphores: &[(Arc<Semaphore>, PipelineStages)],
+                   signal_semaphores: &[Arc<Semaphore>])
+                   -> Result<Arc<Submission>, OomError>
+{
+    inner_submit_with(&cmd.inner, cmd.clone() as Arc<_>, queue, fence, wait_semaphores,
+                      signal_semaphores)
+}
+
+// Checks the clear values against the attachments of the render pass. Panics if they don't
+// match.
+fn check_clear_values<R>(render_pass: &R, clear_values: &[ClearValue])
+    where R: RenderPassDesc
+{
+    if let Err(err) = render_pass.check_clear_values(clear_values) {
+        panic!("{}", err);
+    }
+}
+
 /// A prototype of a secondary compute command buffer.
 pub struct SecondaryGraphicsCommandBufferBuilder<R> {
     inner: InnerCommandBufferBuilder,
@@ -686,6 +979,17 @@ pub struct SecondaryGraphicsCommandBuffer<R> {
     render_pass_subpass: u32,
 }
 
+impl_debug_handle!(SecondaryGraphicsCommandBuffer<R>);
+
+unsafe impl<R> VulkanObject for SecondaryGraphicsCommandBuffer<R> {
+    type Object = vk::CommandBuffer;
+
+    #[inline]
+    fn internal_object(&self) -> vk::CommandBuffer {
+        self.inner.internal_object()
+    }
+}
+
 /// A prototype of a secondary compute command buffer.
 pub struct SecondaryComputeCommandBufferBuilder {
     inner: InnerCommandBufferBuilder,
@@ -790,12 +1094,35 @@ pub struct SecondaryComputeCommandBuffer {
     inner: InnerCommandBuffer,
 }
 
+impl_debug_handle!(SecondaryComputeCommandBuffer);
+
+unsafe impl VulkanObject for SecondaryComputeCommandBuffer {
+    type Object = vk::CommandBuffer;
+
+    #[inline]
+    fn internal_object(&self) -> vk::CommandBuffer {
+        self.inner.internal_object()
+    }
+}
+
 /// The dynamic state to use for a draw command.
+///
+/// Each field must be `Some` only if the corresponding state of the pipeline is dynamic. If a
+/// state is dynamic and the field is `None`, then the value set by a previous draw command of
+/// the same command buffer is used. Binding a pipeline where a state is fixed forgets the
+/// previous value of that state.
 #[derive(Debug, Clone)]
 pub struct DynamicState {
     pub line_width: Option<f32>,
     pub viewports: Option<Vec<Viewport>>,
     pub scissors: Option<Vec<Scissor>>,
+    pub depth_bias: Option<DepthBias>,
+    pub blend_constants: Option<[f32; 4]>,
+    /// Minimum and maximum depth values of the depth bounds test.
+    pub depth_bounds: Option<(f32, f32)>,
+    pub stencil_compare_mask: Option<DynamicStencilValue>,
+    pub stencil_write_mask: Option<DynamicStencilValue>,
+    pub stencil_reference: Option<DynamicStencilValue>,
 }
 
 impl DynamicState {
@@ -805,8 +1132,63 @@ impl DynamicState {
             line_width: None,
             viewports: None,
             scissors: None,
+            depth_bias: None,
+            blend_constants: None,
+            depth_bounds: None,
+            stencil_compare_mask: None,
+            stencil_write_mask: None,
+            stencil_reference: None,
         }
     }
+
+    /// Checks whether this dynamic state can be used to draw with a pipeline whose dynamic
+    /// states are `flags`, given the state that has already been set in the command buffer.
+    ///
+    /// Returns an error if this object sets a state that is fixed in the pipeline, or if a
+    /// dynamic state of the pipeline is set neither by this object nor by `current`.
+    #[doc(hidden)]
+    pub fn check(&self, flags: &DynamicStateFlags, num_viewports: u32, current: &DynamicState)
+                 -> Result<(), DynamicStateError>
+    {
+        macro_rules! check {
+            ($field:ident, $kind:ident) => (
+                match (self.$field.is_some(), flags.$field, current.$field.is_some()) {
+                    (true, false, _) => {
+                        return Err(DynamicStateError::NotDynamic(DynamicStateKind::$kind));
+                    },
+                    (false, true, false) => {
+                        return Err(DynamicStateError::Missing(DynamicStateKind::$kind));
+                    },
+                    _ => ()
+                }
+            );
+        }
+
+        check!(line_width, LineWidth);
+        check!(viewports, Viewports);
+        check!(scissors, Scissors);
+        check!(depth_bias, DepthBias);
+        check!(blend_constants, BlendConstants);
+        check!(depth_bounds, DepthBounds);
+        check!(stencil_compare_mask, StencilCompareMask);
+        check!(stencil_write_mask, StencilWriteMask);
+        check!(stencil_reference, StencilReference);
+
+        // The viewports and scissors that were set before must also match the pipeline.
+        let num_viewports = num_viewports as usize;
+        let viewports = self.viewports.as_ref().or(current.viewports.as_ref()).map(|v| v.len());
+        let scissors = self.scissors.as_ref().or(current.scissors.as_ref()).map(|s| s.len());
+        for &len in viewports.iter().chain(scissors.iter()) {
+            if len != num_viewports {
+                return Err(DynamicStateError::WrongViewportsCount {
+                    expected: num_viewports,
+                    obtained: len,
+                });
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl Default for DynamicState {
@@ -815,3 +1197,319 @@ impl Default for DynamicState {
         DynamicState::none()
     }
 }
+
+/// Value of a stencil state for the front and the back faces.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DynamicStencilValue {
+    pub front: u32,
+    pub back: u32,
+}
+
+/// One of the states of a graphics pipeline that can be dynamic.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DynamicStateKind {
+    LineWidth,
+    Viewports,
+    Scissors,
+    DepthBias,
+    BlendConstants,
+    DepthBounds,
+    StencilCompareMask,
+    StencilWriteMask,
+    StencilReference,
+}
+
+/// Error that can happen when the dynamic state of a draw command doesn't match the pipeline.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DynamicStateError {
+    /// The dynamic state sets a state that is fixed in the pipeline.
+    NotDynamic(DynamicStateKind),
+    /// A state is dynamic in the pipeline but hasn't been set.
+    Missing(DynamicStateKind),
+    /// The number of viewports or scissors doesn't match the number of viewports of the pipeline.
+    WrongViewportsCount {
+        /// Number of viewports of the pipeline.
+        expected: usize,
+        /// Number of viewports or scissors that were passed.
+        obtained: usize,
+    },
+}
+
+impl error::Error for DynamicStateError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            DynamicStateError::NotDynamic(_) => {
+                "tried to set a state that is fixed in the pipeline"
+            },
+            DynamicStateError::Missing(_) => {
+                "a dynamic state of the pipeline has not been set"
+            },
+            DynamicStateError::WrongViewportsCount { .. } => {
+                "the number of viewports or scissors doesn't match the pipeline"
+            },
+        }
+    }
+}
+
+impl fmt::Display for DynamicStateError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            DynamicStateError::NotDynamic(kind) | DynamicStateError::Missing(kind) => {
+                write!(fmt, "{} ({:?})", error::Error::description(self), kind)
+            },
+            DynamicStateError::WrongViewportsCount { expected, obtained } => {
+                write!(fmt, "{} (expected {}, obtained {})", error::Error::description(self),
+                       expected, obtained)
+            },
+        }
+    }
+}
+
+/// Error that can happen when generating the mipmaps of an image.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MipmapGenerationError {
+    /// The image has only one mipmap level.
+    NotEnoughMipmapLevels,
+    /// The format of the image can't be used as the source or the destination of a blit.
+    BlitNotSupported,
+    /// The format of the image doesn't support linear filtering.
+    LinearFilteringNotSupported,
+}
+
+impl error::Error for MipmapGenerationError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            MipmapGenerationError::NotEnoughMipmapLevels => {
+                "the image has only one mipmap level"
+            },
+            MipmapGenerationError::BlitNotSupported => {
+                "the format of the image doesn't support blits"
+            },
+            MipmapGenerationError::LinearFilteringNotSupported => {
+                "the format of the image doesn't support linear filtering"
+            },
+        }
+    }
+}
+
+impl fmt::Display for MipmapGenerationError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::usize;
+
+    use buffer::BufferUsage;
+    use buffer::CpuAccessibleBuffer;
+    use command_buffer::BufferImageCopyRegion;
+    use command_buffer::CommandBufferPool;
+    use command_buffer::DynamicState;
+    use command_buffer::DynamicStateError;
+    use command_buffer::DynamicStateKind;
+    use command_buffer::DynamicStencilValue;
+    use command_buffer::PrimaryCommandBufferBuilder;
+    use format::R8G8B8A8Unorm;
+    use image::storage::StorageImage;
+    use image::sys::Dimensions;
+    use pipeline::DynamicStateFlags;
+    use pipeline::viewport::Scissor;
+    use pipeline::viewport::Viewport;
+
+    fn viewport() -> Viewport {
+        Viewport {
+            origin: [0.0, 0.0],
+            dimensions: [1024.0, 768.0],
+            depth_range: 0.0 .. 1.0,
+        }
+    }
+
+    #[test]
+    fn fixed_state_overridden() {
+        let dynamic = DynamicState { line_width: Some(2.0), .. DynamicState::none() };
+        let result = dynamic.check(&DynamicStateFlags::none(), 1, &DynamicState::none());
+        assert_eq!(result, Err(DynamicStateError::NotDynamic(DynamicStateKind::LineWidth)));
+    }
+
+    #[test]
+    fn dynamic_state_missing() {
+        let flags = DynamicStateFlags { viewports: true, .. DynamicStateFlags::none() };
+        let result = DynamicState::none().check(&flags, 1, &DynamicState::none());
+        assert_eq!(result, Err(DynamicStateError::Missing(DynamicStateKind::Viewports)));
+    }
+
+    #[test]
+    fn dynamic_state_previously_set() {
+        let flags = DynamicStateFlags { viewports: true, .. DynamicStateFlags::none() };
+        let current = DynamicState { viewports: Some(vec![viewport()]), .. DynamicState::none() };
+        assert_eq!(DynamicState::none().check(&flags, 1, &current), Ok(()));
+    }
+
+    #[test]
+    fn all_dynamic_states_set() {
+        let flags = DynamicStateFlags {
+            viewports: true,
+            stencil_reference: true,
+            blend_constants: true,
+            .. DynamicStateFlags::none()
+        };
+
+        let dynamic = DynamicState {
+            viewports: Some(vec![viewport(), viewport()]),
+            stencil_reference: Some(DynamicStencilValue { front: 1, back: 2 }),
+            blend_constants: Some([0.0, 0.5, 0.5, 1.0]),
+            .. DynamicState::none()
+        };
+
+        assert_eq!(dynamic.check(&flags, 2, &DynamicState::none()), Ok(()));
+    }
+
+    #[test]
+    fn wrong_viewports_count() {
+        let flags = DynamicStateFlags { viewports: true, .. DynamicStateFlags::none() };
+        let dynamic = DynamicState { viewports: Some(vec![viewport()]), .. DynamicState::none() };
+
+        match dynamic.check(&flags, 2, &DynamicState::none()) {
+            Err(DynamicStateError::WrongViewportsCount { expected: 2, obtained: 1 }) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn wrong_scissors_count() {
+        let flags = DynamicStateFlags { scissors: true, .. DynamicStateFlags::none() };
+        let dynamic = DynamicState {
+            scissors: Some(vec![Scissor::irrelevant(); 3]),
+            .. DynamicState::none()
+        };
+
+        match dynamic.check(&flags, 2, &DynamicState::none()) {
+            Err(DynamicStateError::WrongViewportsCount { expected: 2, obtained: 3 }) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn previous_viewports_count_mismatch() {
+        // Viewports set for a pipeline with one viewport, then drawing with a pipeline that
+        // has two viewports without setting them again.
+        let flags = DynamicStateFlags { viewports: true, .. DynamicStateFlags::none() };
+        let current = DynamicState { viewports: Some(vec![viewport()]), .. DynamicState::none() };
+
+        match DynamicState::none().check(&flags, 2, &current) {
+            Err(DynamicStateError::WrongViewportsCount { expected: 2, obtained: 1 }) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn wrong_viewports_count_message() {
+        let err = DynamicStateError::WrongViewportsCount { expected: 2, obtained: 1 };
+        let message = format!("{}", err);
+        assert!(message.ends_with("(expected 2, obtained 1)"), "wrong message: {}", message);
+    }
+
+    #[test]
+    #[should_panic(expected = "The filled range of 16 bytes at offset 8 exceeds the size of the \
+                               buffer (16 bytes)")]
+    fn fill_buffer_out_of_range() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let usage = BufferUsage { transfer_dest: true, .. BufferUsage::none() };
+        let buffer = CpuAccessibleBuffer::<[u32]>::array(&device, 4, &usage,
+                                                         Some(queue.family())).unwrap();
+
+        let pool = CommandBufferPool::new(&device, &queue.family());
+        let _ = unsafe { PrimaryCommandBufferBuilder::new(&pool).fill_buffer(&buffer, 8, 16, 0) };
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds the size of the buffer (16 bytes)")]
+    fn fill_buffer_range_overflow() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let usage = BufferUsage { transfer_dest: true, .. BufferUsage::none() };
+        let buffer = CpuAccessibleBuffer::<[u32]>::array(&device, 4, &usage,
+                                                         Some(queue.family())).unwrap();
+
+        let pool = CommandBufferPool::new(&device, &queue.family());
+        let size = usize::MAX - 3;
+        let _ = unsafe { PrimaryCommandBufferBuilder::new(&pool).fill_buffer(&buffer, 8, size, 0) };
+    }
+
+    #[test]
+    #[should_panic(expected = "The destination buffer (8 bytes) is smaller than the source buffer \
+                               (16 bytes)")]
+    fn copy_buffer_destination_too_small() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let usage = BufferUsage { transfer_source: true, transfer_dest: true,
+                                  .. BufferUsage::none() };
+        let source = CpuAccessibleBuffer::<[u32]>::array(&device, 4, &usage,
+                                                         Some(queue.family())).unwrap();
+        let destination = CpuAccessibleBuffer::<[u32]>::array(&device, 2, &usage,
+                                                              Some(queue.family())).unwrap();
+
+        let pool = CommandBufferPool::new(&device, &queue.family());
+        let _ = PrimaryCommandBufferBuilder::new(&pool).copy_buffer(&source, &destination);
+    }
+
+    fn copy_region(buffer_offset: usize, buffer_row_length: u32, image_offset: [u32; 3])
+                   -> BufferImageCopyRegion
+    {
+        BufferImageCopyRegion {
+            buffer_offset: buffer_offset,
+            buffer_row_length: buffer_row_length,
+            buffer_image_height: 0,
+            mip_level: 0,
+            array_layers: 0 .. 1,
+            image_offset: image_offset,
+            image_extent: [16, 16, 1],
+        }
+    }
+
+    #[test]
+    fn copy_buffer_to_image_untyped() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let usage = BufferUsage { transfer_source: true, .. BufferUsage::none() };
+        let buffer = CpuAccessibleBuffer::<[u8]>::array(&device, 4096, &usage,
+                                                        Some(queue.family())).unwrap();
+        let image = StorageImage::new(&device, Dimensions::Dim2d { width: 32, height: 32 },
+                                      R8G8B8A8Unorm, Some(queue.family())).unwrap();
+
+        // The left half of the buffer is copied to the top-left corner of the image, and its
+        // right half to the top-right corner.
+        let regions = vec![copy_region(0, 32, [0, 0, 0]), copy_region(64, 32, [16, 0, 0])];
+
+        let pool = CommandBufferPool::new(&device, &queue.family());
+        let _ = PrimaryCommandBufferBuilder::new(&pool)
+                    .copy_buffer_to_image_untyped(&buffer, &image, regions)
+                    .build();
+    }
+
+    #[test]
+    #[should_panic(expected = "Region 1: invalid copy between a buffer and an image")]
+    fn copy_buffer_to_image_untyped_buffer_too_small() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let usage = BufferUsage { transfer_source: true, .. BufferUsage::none() };
+        let buffer = CpuAccessibleBuffer::<[u8]>::array(&device, 2048, &usage,
+                                                        Some(queue.family())).unwrap();
+        let image = StorageImage::new(&device, Dimensions::Dim2d { width: 32, height: 32 },
+                                      R8G8B8A8Unorm, Some(queue.family())).unwrap();
+
+        // The second region needs 15 rows of 128 bytes plus 64 bytes after its offset.
+        let regions = vec![copy_region(0, 32, [0, 0, 0]), copy_region(128, 32, [0, 16, 0])];
+
+        let pool = CommandBufferPool::new(&device, &queue.family());
+        let _ = PrimaryCommandBufferBuilder::new(&pool)
+                    .copy_buffer_to_image_untyped(&buffer, &image, regions);
+    }
+}
@@ -7,11 +7,14 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
+use std::sync::Arc;
+
 use format::Format;
+use sampler::Sampler;
 use vk;
 
 /// Describes a single descriptor.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone)]
 pub struct DescriptorDesc {
     /// Offset of the binding within the descriptor.
     pub binding: u32,
@@ -27,6 +30,12 @@ pub struct DescriptorDesc {
 
     /// True if the attachment is only ever read by the shader. False if it is also written.
     pub readonly: bool,
+
+    /// Samplers that are embedded in the layout of the descriptor set, one per array element.
+    ///
+    /// Only valid for sampler and combined image sampler descriptors. If `Some`, the samplers
+    /// are never written to the descriptor set and using the layout keeps them alive.
+    pub immutable_samplers: Option<Vec<Arc<Sampler>>>,
 }
 
 impl DescriptorDesc {
@@ -40,6 +49,35 @@ impl DescriptorDesc {
         self.array_count >= other.array_count && self.stages.is_superset_of(&other.stages) &&
         (!self.readonly || other.readonly)
     }
+
+    /// Checks whether the two descriptors are defined identically in a descriptor set layout.
+    ///
+    /// This compares the binding, the type of descriptor, the array elements count, the
+    /// shader stages and the immutable samplers. Contrary to `is_superset_of`, a larger array or
+    /// more shader stages make the descriptors different.
+    #[inline]
+    pub fn is_identical_to(&self, other: &DescriptorDesc) -> bool {
+        let samplers_identical = match (&self.immutable_samplers, &other.immutable_samplers) {
+            (&Some(ref a), &Some(ref b)) => {
+                a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| &**a as *const Sampler ==
+                                                                          &**b as *const Sampler)
+            },
+            (&None, &None) => true,
+            _ => false,
+        };
+
+        self.binding == other.binding && self.ty.ty() == other.ty.ty() &&
+        self.array_count == other.array_count && self.stages == other.stages &&
+        samplers_identical
+    }
+}
+
+/// Checks whether two lists of descriptors describe identical descriptor set layouts. The order
+/// of the descriptors doesn't matter.
+#[doc(hidden)]
+pub fn descriptors_identical(a: &[DescriptorDesc], b: &[DescriptorDesc]) -> bool {
+    a.len() == b.len() &&
+    a.iter().all(|a| b.iter().any(|b| a.is_identical_to(b)))
 }
 
 /// Describes the content and layout of each array element of a descriptor.
@@ -49,7 +87,11 @@ pub enum DescriptorDescTy {
     CombinedImageSampler(DescriptorImageDesc),               // TODO: the sampler has some restrictions as well
     Image(DescriptorImageDesc),
     TexelBuffer { sampled: bool, format: Option<Format> },
-    InputAttachment { multisampled: bool, array_layers: DescriptorImageDescArray },
+    /// An input attachment. `index` is the index of the attachment within the input attachments
+    /// of the subpass, if known. It corresponds to the `InputAttachmentIndex` decoration of the
+    /// shader and is ignored when comparing descriptors.
+    InputAttachment { multisampled: bool, array_layers: DescriptorImageDescArray,
+                      index: Option<u32> },
     Buffer(DescriptorBufferDesc),
 }
 
@@ -95,9 +137,9 @@ impl DescriptorDescTy {
              DescriptorDescTy::Image(ref other)) => me.is_superset_of(other),
 
             (DescriptorDescTy::InputAttachment { multisampled: me_multisampled,
-                                                 array_layers: me_array_layers },
+                                                 array_layers: me_array_layers, .. },
              DescriptorDescTy::InputAttachment { multisampled: other_multisampled,
-                                                 array_layers: other_array_layers }) =>
+                                                 array_layers: other_array_layers, .. }) =>
             {
                 me_multisampled == other_multisampled && me_array_layers == other_array_layers
             },
@@ -302,6 +344,30 @@ impl ShaderStages {
         (self.fragment || !other.fragment) &&
         (self.compute || !other.compute)
     }
+
+    /// Checks whether any of the stages in `self` are also present in `other`.
+    #[inline]
+    pub fn intersects(&self, other: &ShaderStages) -> bool {
+        (self.vertex && other.vertex) ||
+        (self.tessellation_control && other.tessellation_control) ||
+        (self.tessellation_evaluation && other.tessellation_evaluation) ||
+        (self.geometry && other.geometry) ||
+        (self.fragment && other.fragment) ||
+        (self.compute && other.compute)
+    }
+
+    /// Returns the stages that are enabled in `self`, in `other`, or in both.
+    #[inline]
+    pub fn union(&self, other: &ShaderStages) -> ShaderStages {
+        ShaderStages {
+            vertex: self.vertex || other.vertex,
+            tessellation_control: self.tessellation_control || other.tessellation_control,
+            tessellation_evaluation: self.tessellation_evaluation || other.tessellation_evaluation,
+            geometry: self.geometry || other.geometry,
+            fragment: self.fragment || other.fragment,
+            compute: self.compute || other.compute,
+        }
+    }
 }
 
 #[doc(hidden)]
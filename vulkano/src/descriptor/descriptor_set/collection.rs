@@ -16,6 +16,7 @@ use std::vec::IntoIter as VecIntoIter;
 use descriptor::descriptor::DescriptorDesc;
 use descriptor::descriptor_set::DescriptorSet;
 use descriptor::descriptor_set::DescriptorSetDesc;
+use descriptor::descriptor_set::UnsafeDescriptorSetLayout;
 
 /// A collection of descriptor set objects.
 pub unsafe trait DescriptorSetsCollection {
@@ -33,6 +34,78 @@ pub unsafe trait DescriptorSetsCollection {
 
     /// Produces a description of the sets, as if it was a layout.
     fn description(&self) -> Self::SetsIter;
+
+    /// Returns the layouts of the descriptor sets of this collection, in order.
+    ///
+    /// This is used to check the sets one by one against the layout of a pipeline.
+    #[inline]
+    fn layouts(&self) -> Vec<Arc<UnsafeDescriptorSetLayout>> {
+        self.list().map(|set| set.inner_descriptor_set().layout().clone()).collect()
+    }
+
+    /// Returns the offsets to apply to the dynamic buffer descriptors of the sets.
+    ///
+    /// There must be one offset per array element of each dynamic uniform or storage buffer
+    /// descriptor, ordered by set, then by binding, then by array element.
+    #[inline]
+    fn dynamic_offsets(&self) -> &[u32] {
+        &[]
+    }
+}
+
+/// Wraps around a collection of descriptor sets and provides the offsets of their dynamic buffer
+/// descriptors.
+///
+/// The offsets are checked against the sets when they are bound to a command buffer.
+#[derive(Debug, Clone)]
+pub struct DynamicOffsets<L> {
+    sets: L,
+    offsets: Vec<u32>,
+}
+
+impl<L> DynamicOffsets<L> where L: DescriptorSetsCollection {
+    /// Builds a new `DynamicOffsets` from a collection of sets and the list of their offsets.
+    #[inline]
+    pub fn new<O>(sets: L, offsets: O) -> DynamicOffsets<L>
+        where O: IntoIterator<Item = u32>
+    {
+        DynamicOffsets {
+            sets: sets,
+            offsets: offsets.into_iter().collect(),
+        }
+    }
+
+    /// Returns the wrapped collection of sets.
+    #[inline]
+    pub fn sets(&self) -> &L {
+        &self.sets
+    }
+}
+
+unsafe impl<L> DescriptorSetsCollection for DynamicOffsets<L> where L: DescriptorSetsCollection {
+    type ListIter = L::ListIter;
+    type SetsIter = L::SetsIter;
+    type DescIter = L::DescIter;
+
+    #[inline]
+    fn list(&self) -> Self::ListIter {
+        self.sets.list()
+    }
+
+    #[inline]
+    fn description(&self) -> Self::SetsIter {
+        self.sets.description()
+    }
+
+    #[inline]
+    fn layouts(&self) -> Vec<Arc<UnsafeDescriptorSetLayout>> {
+        self.sets.layouts()
+    }
+
+    #[inline]
+    fn dynamic_offsets(&self) -> &[u32] {
+        &self.offsets
+    }
 }
 
 unsafe impl DescriptorSetsCollection for () {
@@ -87,6 +160,25 @@ unsafe impl<'a, T> DescriptorSetsCollection for &'a Arc<T>
     }
 }
 
+unsafe impl DescriptorSetsCollection for Vec<Arc<DescriptorSet>> {
+    type ListIter = VecIntoIter<Arc<DescriptorSet>>;
+    type SetsIter = VecIntoIter<Self::DescIter>;
+    type DescIter = VecIntoIter<DescriptorDesc>;
+
+    #[inline]
+    fn list(&self) -> Self::ListIter {
+        self.clone().into_iter()
+    }
+
+    #[inline]
+    fn description(&self) -> Self::SetsIter {
+        // The sets are type-erased, so their description is taken from their layout.
+        self.iter().map(|set| {
+            set.inner_descriptor_set().layout().descriptors().to_vec().into_iter()
+        }).collect::<Vec<_>>().into_iter()
+    }
+}
+
 macro_rules! impl_collection {
     ($first:ident $(, $others:ident)*) => (
         unsafe impl<'a, $first$(, $others)*> DescriptorSetsCollection for
@@ -130,3 +222,120 @@ macro_rules! impl_collection {
 }
 
 impl_collection!(Z, Y, X, W, V, U, T, S, R, Q, P, O, N, M, L, K, J, I, H, G, F, E, D, C, B, A);
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::vec::IntoIter as VecIntoIter;
+
+    use descriptor::descriptor::DescriptorBufferDesc;
+    use descriptor::descriptor::DescriptorDesc;
+    use descriptor::descriptor::DescriptorDescTy;
+    use descriptor::descriptor::ShaderStages;
+    use descriptor::descriptor_set::DescriptorSet;
+    use descriptor::descriptor_set::DescriptorSetDesc;
+    use descriptor::descriptor_set::DescriptorSetsCollection;
+    use descriptor::descriptor_set::UnsafeDescriptorPool;
+    use descriptor::descriptor_set::UnsafeDescriptorSet;
+    use descriptor::descriptor_set::UnsafeDescriptorSetLayout;
+    use descriptor::pipeline_layout::DescriptorSetsIncompatibleError;
+    use descriptor::pipeline_layout::UnsafePipelineLayout;
+    use device::Device;
+
+    // Minimal descriptor set that wraps around an `UnsafeDescriptorSet`.
+    struct TestSet(UnsafeDescriptorSet);
+
+    unsafe impl DescriptorSet for TestSet {
+        #[inline]
+        fn inner_descriptor_set(&self) -> &UnsafeDescriptorSet {
+            &self.0
+        }
+    }
+
+    unsafe impl DescriptorSetDesc for TestSet {
+        type Iter = VecIntoIter<DescriptorDesc>;
+
+        #[inline]
+        fn desc(&self) -> Self::Iter {
+            self.0.layout().descriptors().to_vec().into_iter()
+        }
+    }
+
+    // Builds a layout with a single descriptor at binding 0.
+    fn layout(device: &Arc<Device>, ty: DescriptorDescTy) -> Arc<UnsafeDescriptorSetLayout> {
+        UnsafeDescriptorSetLayout::new(device, Some(Some(DescriptorDesc {
+            binding: 0,
+            ty: ty,
+            array_count: 1,
+            stages: ShaderStages::all(),
+            readonly: true,
+            immutable_samplers: None,
+        })))
+    }
+
+    #[test]
+    fn five_sets_tuple() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let buffer = layout(&device, DescriptorDescTy::Buffer(DescriptorBufferDesc {
+            dynamic: Some(false),
+            storage: false,
+        }));
+        let sampler = layout(&device, DescriptorDescTy::Sampler);
+
+        let pipeline_layout = UnsafePipelineLayout::new(&device, vec![&buffer, &buffer, &buffer,
+                                                                      &buffer, &sampler],
+                                                        None).unwrap();
+
+        let count = buffer.descriptors_count() * 4 + sampler.descriptors_count();
+        let mut pool = UnsafeDescriptorPool::new(&device, 5, &count, false).unwrap();
+        let sets = unsafe {
+            pool.alloc(vec![&buffer, &buffer, &buffer, &buffer, &sampler]).unwrap()
+                .map(|set| Arc::new(TestSet(set))).collect::<Vec<_>>()
+        };
+
+        let collection = (&sets[0], &sets[1], &sets[2], &sets[3], &sets[4]);
+        assert_eq!(collection.list().len(), 5);
+        assert_eq!(collection.description().len(), 5);
+        assert_eq!(pipeline_layout.ensure_sets_compatible(collection.layouts().iter()), Ok(()));
+
+        let collection = (&sets[0], &sets[1], &sets[4], &sets[3], &sets[2]);
+        assert_eq!(pipeline_layout.ensure_sets_compatible(collection.layouts().iter()),
+                   Err(DescriptorSetsIncompatibleError::IncompatibleSet { set_num: 2 }));
+    }
+
+    #[test]
+    fn vec_collection() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let buffer = layout(&device, DescriptorDescTy::Buffer(DescriptorBufferDesc {
+            dynamic: Some(false),
+            storage: false,
+        }));
+
+        let pipeline_layout = UnsafePipelineLayout::new(&device, vec![&buffer, &buffer],
+                                                        None).unwrap();
+
+        let mut pool = UnsafeDescriptorPool::new(&device, 3, &(buffer.descriptors_count() * 3),
+                                                 false).unwrap();
+        let sets = unsafe {
+            pool.alloc(vec![&buffer, &buffer, &buffer]).unwrap()
+                .map(|set| Arc::new(TestSet(set)) as Arc<DescriptorSet>).collect::<Vec<_>>()
+        };
+
+        let collection = sets[.. 2].to_vec();
+        assert_eq!(collection.list().len(), 2);
+        let bindings = collection.description().map(|set| {
+            set.map(|d| d.binding).collect::<Vec<_>>()
+        }).collect::<Vec<_>>();
+        assert_eq!(bindings, vec![vec![0], vec![0]]);
+        assert_eq!(pipeline_layout.ensure_sets_compatible(collection.layouts().iter()), Ok(()));
+
+        // Binding fewer sets than the layout has is allowed.
+        let collection = sets[.. 1].to_vec();
+        assert_eq!(pipeline_layout.ensure_sets_compatible(collection.layouts().iter()), Ok(()));
+
+        assert_eq!(pipeline_layout.ensure_sets_compatible(sets.layouts().iter()),
+                   Err(DescriptorSetsIncompatibleError::TooManySets { expected: 2, obtained: 3 }));
+    }
+}
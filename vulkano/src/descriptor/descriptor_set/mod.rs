@@ -10,15 +10,38 @@
 use descriptor::descriptor::DescriptorDesc;
 
 pub use self::collection::DescriptorSetsCollection;
+pub use self::collection::DynamicOffsets;
 pub use self::pool::DescriptorPool;
+pub use self::std_pool::FrameDescriptorPool;
+pub use self::std_pool::FrameDescriptorSet;
+pub use self::std_pool::PooledDescriptorSet;
+pub use self::std_pool::StdDescriptorPool;
 pub use self::sys::UnsafeDescriptorSet;
+pub use self::sys::DescriptorCopy;
+pub use self::sys::DynamicOffsetsError;
+#[doc(hidden)] pub use self::sys::check_dynamic_offsets;
 pub use self::sys::DescriptorWrite;
+pub use self::sys::DescriptorWriteBatch;
+pub use self::sys::DescriptorWriteResources;
+pub use self::unsafe_layout::DescriptorSetLayoutCreationError;
+#[doc(hidden)] pub use self::unsafe_layout::DescriptorLimitExceeded;
+#[doc(hidden)] pub use self::unsafe_layout::DescriptorLimits;
+#[doc(hidden)] pub use self::unsafe_layout::check_descriptor_limits;
+pub use self::unsafe_layout::DynamicDescriptor;
 pub use self::unsafe_layout::UnsafeDescriptorSetLayout;
+pub use self::unsafe_pool::DescriptorPoolAllocError;
+pub use self::unsafe_pool::DescriptorPoolFreeError;
+pub use self::unsafe_pool::DescriptorsCount;
+pub use self::unsafe_pool::TrackedDescriptorPool;
+pub use self::unsafe_pool::UnsafeDescriptorPool;
+pub use self::unsafe_pool::UnsafeDescriptorPoolAllocIter;
 
 mod collection;
 mod pool;
+mod std_pool;
 mod sys;
 mod unsafe_layout;
+mod unsafe_pool;
 
 /// Trait for objects that contain a collection of resources that will be accessible by shaders.
 ///
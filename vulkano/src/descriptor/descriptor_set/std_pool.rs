@@ -0,0 +1,428 @@
+// Copyright (c) 2016 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::collections::HashMap;
+use std::hash::BuildHasherDefault;
+use std::iter;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::vec::IntoIter as VecIntoIter;
+use fnv::FnvHasher;
+
+use descriptor::descriptor::DescriptorDesc;
+use descriptor::descriptor_set::DescriptorPoolAllocError;
+use descriptor::descriptor_set::DescriptorSet;
+use descriptor::descriptor_set::DescriptorSetDesc;
+use descriptor::descriptor_set::DescriptorsCount;
+use descriptor::descriptor_set::UnsafeDescriptorPool;
+use descriptor::descriptor_set::UnsafeDescriptorSet;
+use descriptor::descriptor_set::UnsafeDescriptorSetLayout;
+use device::Device;
+use sync::Fence;
+use sync::FenceWaitError;
+
+use OomError;
+
+// Number of descriptor sets that each of the `UnsafeDescriptorPool`s of a pool can hold.
+const SETS_PER_CHUNK: u32 = 32;
+
+// For each layout signature, the list of pools that sets with this signature are allocated from.
+// Two layouts have the same signature if they contain the same number of descriptors of each
+// type, in which case their sets can be allocated from the same pools.
+type Chunks = HashMap<DescriptorsCount, Vec<Arc<Mutex<UnsafeDescriptorPool>>>,
+                      BuildHasherDefault<FnvHasher>>;
+
+/// Standard implementation of a descriptor pool.
+///
+/// Allocates descriptor sets from a list of `UnsafeDescriptorPool`s, and creates a new
+/// `UnsafeDescriptorPool` whenever all the existing ones are full. Sets are returned to their
+/// pool when they are destroyed.
+///
+/// Each device owns a `StdDescriptorPool`, that you can get with
+/// `Device::standard_descriptor_pool()`.
+pub struct StdDescriptorPool {
+    device: Arc<Device>,
+    chunks: Mutex<Chunks>,
+}
+
+impl StdDescriptorPool {
+    /// Creates a new empty pool.
+    #[inline]
+    pub fn new(device: &Arc<Device>) -> Arc<StdDescriptorPool> {
+        Arc::new(StdDescriptorPool {
+            device: device.clone(),
+            chunks: Mutex::new(HashMap::default()),
+        })
+    }
+
+    /// Returns the device this pool belongs to.
+    #[inline]
+    pub fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+
+    /// Allocates a descriptor set with the given layout.
+    ///
+    /// The content of the descriptor set is uninitialized. Use `inner_mut()` to write to it.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the layout was not created with the same device as the pool.
+    ///
+    pub fn alloc(&self, layout: &Arc<UnsafeDescriptorSetLayout>)
+                 -> Result<PooledDescriptorSet, OomError>
+    {
+        let mut chunks = self.chunks.lock().unwrap();
+        let (set, pool) = try!(alloc_from_chunks(&self.device, &mut chunks, layout, true));
+
+        Ok(PooledDescriptorSet {
+            inner: Some(set),
+            pool: pool,
+        })
+    }
+
+    /// Returns the number of `UnsafeDescriptorPool`s that this pool has created.
+    #[inline]
+    pub fn num_chunks(&self) -> usize {
+        self.chunks.lock().unwrap().values().fold(0, |acc, c| acc + c.len())
+    }
+}
+
+/// Descriptor set allocated from a `StdDescriptorPool`.
+///
+/// The set is returned to its pool when destroyed.
+pub struct PooledDescriptorSet {
+    // Always `Some`, except during the destructor.
+    inner: Option<UnsafeDescriptorSet>,
+    pool: Arc<Mutex<UnsafeDescriptorPool>>,
+}
+
+impl PooledDescriptorSet {
+    /// Returns the inner `UnsafeDescriptorSet`, in order to write to it.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut UnsafeDescriptorSet {
+        self.inner.as_mut().unwrap()
+    }
+}
+
+unsafe impl DescriptorSet for PooledDescriptorSet {
+    #[inline]
+    fn inner_descriptor_set(&self) -> &UnsafeDescriptorSet {
+        self.inner.as_ref().unwrap()
+    }
+}
+
+unsafe impl DescriptorSetDesc for PooledDescriptorSet {
+    type Iter = VecIntoIter<DescriptorDesc>;
+
+    #[inline]
+    fn desc(&self) -> Self::Iter {
+        self.inner_descriptor_set().layout().descriptors().to_vec().into_iter()
+    }
+}
+
+impl Drop for PooledDescriptorSet {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe {
+            let set = self.inner.take().unwrap();
+            // The pools of a `StdDescriptorPool` are always created with the possibility to free
+            // individual sets. If freeing fails anyway, the set is leaked until its pool is
+            // destroyed, which is better than panicking in a destructor.
+            let _ = self.pool.lock().unwrap().free(iter::once(set));
+        }
+    }
+}
+
+/// Descriptor pool whose sets are all reclaimed at once.
+///
+/// This is meant for sets that are only used during a single frame. Instead of freeing the sets
+/// one by one, all the sets allocated since the last reset are reclaimed by `reset()`, which is
+/// much cheaper. The `UnsafeDescriptorPool`s are kept and reused by the next frames.
+pub struct FrameDescriptorPool {
+    device: Arc<Device>,
+    chunks: Chunks,
+    // Incremented at each reset. Sets allocated before a reset can't be used anymore.
+    generation: Arc<AtomicUsize>,
+}
+
+impl FrameDescriptorPool {
+    /// Creates a new empty pool.
+    #[inline]
+    pub fn new(device: &Arc<Device>) -> FrameDescriptorPool {
+        FrameDescriptorPool {
+            device: device.clone(),
+            chunks: HashMap::default(),
+            generation: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Returns the device this pool belongs to.
+    #[inline]
+    pub fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+
+    /// Allocates a descriptor set with the given layout.
+    ///
+    /// The content of the descriptor set is uninitialized. Use `inner_mut()` to write to it.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the layout was not created with the same device as the pool.
+    ///
+    pub fn alloc(&mut self, layout: &Arc<UnsafeDescriptorSetLayout>)
+                 -> Result<FrameDescriptorSet, OomError>
+    {
+        let (set, pool) = try!(alloc_from_chunks(&self.device, &mut self.chunks, layout, false));
+
+        Ok(FrameDescriptorSet {
+            inner: set,
+            _pool: pool,
+            generation: self.generation.load(Ordering::SeqCst),
+            pool_generation: self.generation.clone(),
+        })
+    }
+
+    /// Reclaims all the sets that have been allocated since the last reset.
+    ///
+    /// Waits for `fence` to be signaled before resetting the pool. The fence must be signaled
+    /// once the device has finished executing all the command buffers that use the sets. The sets
+    /// that have been allocated before the reset can't be used anymore afterwards, and trying to
+    /// bind them panics.
+    pub fn reset(&mut self, fence: &Fence) -> Result<(), FenceWaitError> {
+        try!(fence.wait_forever());
+
+        self.generation.fetch_add(1, Ordering::SeqCst);
+
+        for pool in self.chunks.values().flat_map(|c| c.iter()) {
+            unsafe {
+                try!(pool.lock().unwrap().reset());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of `UnsafeDescriptorPool`s that this pool has created.
+    #[inline]
+    pub fn num_chunks(&self) -> usize {
+        self.chunks.values().fold(0, |acc, c| acc + c.len())
+    }
+}
+
+/// Descriptor set allocated from a `FrameDescriptorPool`.
+///
+/// The set is reclaimed when its pool is reset, and not when it is destroyed.
+pub struct FrameDescriptorSet {
+    inner: UnsafeDescriptorSet,
+    // Keeps the pool alive for as long as the set exists.
+    _pool: Arc<Mutex<UnsafeDescriptorPool>>,
+    // Value of the generation of the pool when the set was allocated.
+    generation: usize,
+    pool_generation: Arc<AtomicUsize>,
+}
+
+impl FrameDescriptorSet {
+    /// Returns the inner `UnsafeDescriptorSet`, in order to write to it.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the pool of the set has been reset since the set was allocated.
+    ///
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut UnsafeDescriptorSet {
+        self.check_generation();
+        &mut self.inner
+    }
+
+    #[inline]
+    fn check_generation(&self) {
+        assert_eq!(self.generation, self.pool_generation.load(Ordering::SeqCst),
+                   "This descriptor set was reclaimed by a reset of its pool");
+    }
+}
+
+unsafe impl DescriptorSet for FrameDescriptorSet {
+    #[inline]
+    fn inner_descriptor_set(&self) -> &UnsafeDescriptorSet {
+        self.check_generation();
+        &self.inner
+    }
+}
+
+unsafe impl DescriptorSetDesc for FrameDescriptorSet {
+    type Iter = VecIntoIter<DescriptorDesc>;
+
+    #[inline]
+    fn desc(&self) -> Self::Iter {
+        self.inner.layout().descriptors().to_vec().into_iter()
+    }
+}
+
+// Allocates a set from the first pool of `chunks` that has room for it, or from a new pool if
+// they are all full.
+fn alloc_from_chunks(device: &Arc<Device>, chunks: &mut Chunks,
+                     layout: &Arc<UnsafeDescriptorSetLayout>, free_individual_sets: bool)
+                     -> Result<(UnsafeDescriptorSet, Arc<Mutex<UnsafeDescriptorPool>>), OomError>
+{
+    assert_eq!(&**layout.device() as *const Device, &**device as *const Device,
+               "The layout doesn't belong to the same device as the pool");
+
+    let signature = layout.descriptors_count();
+    let pools = chunks.entry(signature).or_insert_with(Vec::new);
+
+    for pool in pools.iter() {
+        if let Some(set) = try!(try_alloc(&mut pool.lock().unwrap(), layout)) {
+            return Ok((set, pool.clone()));
+        }
+    }
+
+    let mut count = signature * SETS_PER_CHUNK;
+    // A pool can't be created without descriptors, even if the sets are empty.
+    if count == DescriptorsCount::zero() {
+        count.sampler = 1;
+    }
+
+    let mut pool = try!(UnsafeDescriptorPool::new(device, SETS_PER_CHUNK, &count,
+                                                  free_individual_sets));
+    let set = match try!(try_alloc(&mut pool, layout)) {
+        Some(set) => set,
+        None => unreachable!()
+    };
+
+    let pool = Arc::new(Mutex::new(pool));
+    pools.push(pool.clone());
+    Ok((set, pool))
+}
+
+// Allocates a set from `pool`. Returns `None` if the pool is full.
+fn try_alloc(pool: &mut UnsafeDescriptorPool, layout: &Arc<UnsafeDescriptorSetLayout>)
+             -> Result<Option<UnsafeDescriptorSet>, OomError>
+{
+    unsafe {
+        match pool.alloc(iter::once(layout)) {
+            Ok(mut sets) => Ok(sets.next()),
+            Err(DescriptorPoolAllocError::OutOfHostMemory) => Err(OomError::OutOfHostMemory),
+            Err(DescriptorPoolAllocError::OutOfDeviceMemory) => Err(OomError::OutOfDeviceMemory),
+            Err(DescriptorPoolAllocError::FragmentedPool) |
+            Err(DescriptorPoolAllocError::OutOfPoolMemory) => Ok(None),
+            Err(DescriptorPoolAllocError::Unexpected(err)) => Err(OomError::Unexpected(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::iter;
+    use std::sync::Arc;
+
+    use descriptor::descriptor::DescriptorBufferDesc;
+    use descriptor::descriptor::DescriptorDesc;
+    use descriptor::descriptor::DescriptorDescTy;
+    use descriptor::descriptor::ShaderStages;
+    use descriptor::descriptor_set::DescriptorSet;
+    use descriptor::descriptor_set::DescriptorSetsCollection;
+    use descriptor::descriptor_set::FrameDescriptorPool;
+    use descriptor::descriptor_set::StdDescriptorPool;
+    use descriptor::descriptor_set::UnsafeDescriptorSetLayout;
+    use device::Device;
+    use sync::Fence;
+
+    use VulkanObject;
+
+    fn uniform_buffer_layout(device: &Arc<Device>) -> Arc<UnsafeDescriptorSetLayout> {
+        UnsafeDescriptorSetLayout::new(device, iter::once(Some(DescriptorDesc {
+            binding: 0,
+            ty: DescriptorDescTy::Buffer(DescriptorBufferDesc {
+                dynamic: Some(false),
+                storage: false,
+            }),
+            array_count: 1,
+            stages: ShaderStages::all(),
+            readonly: true,
+            immutable_samplers: None,
+        })))
+    }
+
+    #[test]
+    fn std_pool_reuses_freed_sets() {
+        let (device, _) = gfx_dev_and_queue!();
+        let layout = uniform_buffer_layout(&device);
+        let pool = StdDescriptorPool::new(&device);
+
+        let sets = (0 .. 200).map(|_| pool.alloc(&layout).unwrap()).collect::<Vec<_>>();
+        let num_chunks = pool.num_chunks();
+        assert!(num_chunks >= 2, "200 sets should need several chunks");
+        drop(sets);
+
+        let _sets = (0 .. 200).map(|_| pool.alloc(&layout).unwrap()).collect::<Vec<_>>();
+        assert_eq!(pool.num_chunks(), num_chunks);
+    }
+
+    #[test]
+    fn std_pool_empty_layout() {
+        let (device, _) = gfx_dev_and_queue!();
+        let layout = UnsafeDescriptorSetLayout::new(&device, iter::empty());
+        let _set = Device::standard_descriptor_pool(&device).alloc(&layout).unwrap();
+    }
+
+    #[test]
+    fn sets_are_collections() {
+        let (device, _) = gfx_dev_and_queue!();
+        let layout = uniform_buffer_layout(&device);
+
+        let pooled = Arc::new(StdDescriptorPool::new(&device).alloc(&layout).unwrap());
+        assert_eq!(pooled.list().len(), 1);
+        assert_eq!(pooled.layouts()[0].internal_object(), layout.internal_object());
+        assert_eq!(pooled.description().next().unwrap().len(), 1);
+
+        let mut frame_pool = FrameDescriptorPool::new(&device);
+        let frame = Arc::new(frame_pool.alloc(&layout).unwrap());
+        assert_eq!(frame.list().len(), 1);
+        assert_eq!(frame.description().next().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn frame_pool_stabilizes() {
+        let (device, _) = gfx_dev_and_queue!();
+        let layout = uniform_buffer_layout(&device);
+        let mut pool = FrameDescriptorPool::new(&device);
+        let fence = Fence::signaled(&device);
+
+        let mut num_chunks = None;
+
+        for _ in 0 .. 5 {
+            let sets = (0 .. 300).map(|_| pool.alloc(&layout).unwrap()).collect::<Vec<_>>();
+            drop(sets);
+
+            match num_chunks {
+                Some(n) => assert_eq!(pool.num_chunks(), n),
+                None => num_chunks = Some(pool.num_chunks()),
+            }
+
+            pool.reset(&fence).unwrap();
+        }
+    }
+
+    #[test]
+    #[should_panic = "This descriptor set was reclaimed by a reset of its pool"]
+    fn frame_pool_stale_set() {
+        let (device, _) = gfx_dev_and_queue!();
+        let layout = uniform_buffer_layout(&device);
+        let mut pool = FrameDescriptorPool::new(&device);
+        let fence = Fence::signaled(&device);
+
+        let set = pool.alloc(&layout).unwrap();
+        pool.reset(&fence).unwrap();
+        set.inner_descriptor_set();
+    }
+}
@@ -7,6 +7,10 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
+use std::any::Any;
+use std::error;
+use std::fmt;
+use std::iter;
 use std::mem;
 use std::ops::Range;
 use std::ptr;
@@ -22,6 +26,7 @@ use vk;
 
 use buffer::Buffer;
 use buffer::BufferSlice;
+use buffer::BufferView;
 use descriptor::descriptor::DescriptorType;
 use descriptor::descriptor_set::UnsafeDescriptorSetLayout;
 use descriptor::descriptor_set::DescriptorPool;
@@ -32,9 +37,15 @@ use image::Layout as ImageLayout;
 use sampler::Sampler;
 
 /// Low-level descriptor set.
+///
+/// A descriptor set allocated with `uninitialized()` is freed when it is destroyed. A descriptor
+/// set allocated from an `UnsafeDescriptorPool` is not, and must be freed or reset through its
+/// pool instead.
 pub struct UnsafeDescriptorSet {
     set: vk::DescriptorSet,
-    pool: Arc<DescriptorPool>,
+    device: Arc<Device>,
+    // The pool to free the set from when it is destroyed, if any.
+    pool: Option<Arc<DescriptorPool>>,
     layout: Arc<UnsafeDescriptorSetLayout>,
 
     // Here we store the resources used by the descriptor set.
@@ -43,6 +54,21 @@ pub struct UnsafeDescriptorSet {
     resources_images: Vec<(Arc<Image>, (u32, u32), ImageLayout)>,
     resources_image_views: Vec<Arc<ImageView>>,
     resources_buffers: Vec<Arc<Buffer>>,
+    resources_buffer_views: Vec<Arc<Any + Send + Sync>>,
+
+    // The buffer ranges written to the dynamic buffer descriptors, used to check the dynamic
+    // offsets when the set is bound.
+    dynamic_buffers: SmallVec<[DynamicBufferWrite; 4]>,
+}
+
+// Buffer range written to an array element of a dynamic buffer descriptor.
+#[derive(Debug, Copy, Clone)]
+struct DynamicBufferWrite {
+    binding: u32,
+    array_element: u32,
+    offset: usize,
+    range: usize,
+    buffer_size: usize,
 }
 
 impl UnsafeDescriptorSet {
@@ -75,13 +101,16 @@ impl UnsafeDescriptorSet {
 
         Ok(UnsafeDescriptorSet {
             set: set,
-            pool: pool.clone(),
+            device: pool.device().clone(),
+            pool: Some(pool.clone()),
             layout: layout.clone(),
 
             resources_samplers: Vec::new(),
             resources_images: Vec::new(),
             resources_image_views: Vec::new(),
             resources_buffers: Vec::new(),
+            resources_buffer_views: Vec::new(),
+            dynamic_buffers: SmallVec::new(),
         })
     }
     
@@ -101,210 +130,750 @@ impl UnsafeDescriptorSet {
         UnsafeDescriptorSet::uninitialized_raw(pool, layout).unwrap()
     }
 
+    /// Builds an `UnsafeDescriptorSet` from a descriptor set allocated from an
+    /// `UnsafeDescriptorPool`. The descriptor set won't be freed when the object is destroyed.
+    #[doc(hidden)]
+    #[inline]
+    pub unsafe fn from_unsafe_pool(set: vk::DescriptorSet,
+                                   layout: Arc<UnsafeDescriptorSetLayout>) -> UnsafeDescriptorSet
+    {
+        UnsafeDescriptorSet {
+            set: set,
+            device: layout.device().clone(),
+            pool: None,
+            layout: layout,
+
+            resources_samplers: Vec::new(),
+            resources_images: Vec::new(),
+            resources_image_views: Vec::new(),
+            resources_buffers: Vec::new(),
+            resources_buffer_views: Vec::new(),
+            dynamic_buffers: SmallVec::new(),
+        }
+    }
+
     /// Modifies a descriptor set without checking that the writes are correct.
     ///
+    /// All the writes are performed with a single call to `vkUpdateDescriptorSets`. Writes that
+    /// target consecutive array elements of the same binding are merged together.
+    ///
     /// # Safety
     ///
     /// - Doesn't verify that the things you write in the descriptor set match its layout.
+    /// - The descriptor set must not be in use by the GPU.
     ///
-    pub unsafe fn write(&mut self, write: Vec<DescriptorWrite>) {
-        let vk = self.pool.device().pointers();
+    #[inline]
+    pub unsafe fn write<I>(&mut self, writes: I)
+        where I: IntoIterator<Item = DescriptorWrite>
+    {
+        self.update(writes, iter::empty())
+    }
 
+    /// Modifies a descriptor set by writing descriptors and copying descriptors from other sets.
+    ///
+    /// Everything is performed with a single call to `vkUpdateDescriptorSets`. The writes are
+    /// performed before the copies.
+    ///
+    /// The resources referenced by the source sets of the copies are kept alive by this set.
+    ///
+    /// # Safety
+    ///
+    /// - Doesn't verify that the things you write in the descriptor set match its layout.
+    /// - The descriptor set and the source sets of the copies must not be in use by the GPU.
+    /// - The copied descriptors must have been written in the source sets.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if a copy is between bindings that don't exist or that don't have the same
+    ///   descriptor type.
+    /// - Panicks if a copy reads or writes outside of the array of a binding.
+    ///
+    pub unsafe fn update<'a, W, C>(&mut self, writes: W, copies: C)
+        where W: IntoIterator<Item = DescriptorWrite>,
+              C: IntoIterator<Item = DescriptorCopy<'a>>
+    {
         // TODO: how do we remove the existing resources that are overwritten?
 
-        // This function uses multiple closures which all borrow `self`. In order to satisfy the
-        // borrow checker, we extract references to the members here.
-        let ref mut self_resources_buffers = self.resources_buffers;
-        let ref mut self_resources_samplers = self.resources_samplers;
-        let ref mut self_resources_images = self.resources_images;
-        let ref mut self_resources_image_views = self.resources_image_views;
-        let self_set = self.set;
+        let mut batch = DescriptorWriteBatch::new();
+        batch.add(self, writes);
+
+        let mut vk_copies: SmallVec<[vk::CopyDescriptorSet; 16]> = SmallVec::new();
+
+        for copy in copies {
+            assert_eq!(&**copy.src_set.device() as *const Device, &*self.device as *const Device,
+                       "The source set of a copy doesn't belong to the same device");
+
+            let src_desc = copy.src_set.layout().descriptors().iter()
+                               .find(|d| d.binding == copy.src_binding)
+                               .expect("The source binding of a copy doesn't exist");
+            let dst_desc = self.layout.descriptors().iter()
+                               .find(|d| d.binding == copy.dst_binding)
+                               .expect("The destination binding of a copy doesn't exist");
+
+            assert_eq!(src_desc.ty.ty(), dst_desc.ty.ty(),
+                       "The source and destination bindings of a copy don't have the same \
+                        descriptor type");
+            assert!(copy.src_array_element.checked_add(copy.count)
+                        .map_or(false, |end| end <= src_desc.array_count),
+                    "A copy reads outside of the array of its source binding");
+            assert!(copy.dst_array_element.checked_add(copy.count)
+                        .map_or(false, |end| end <= dst_desc.array_count),
+                    "A copy writes outside of the array of its destination binding");
+
+            for i in 0 .. copy.count {
+                let src = copy.src_set.dynamic_buffers.iter().find(|w| {
+                    w.binding == copy.src_binding && w.array_element == copy.src_array_element + i
+                }).cloned();
+
+                if let Some(src) = src {
+                    set_dynamic_buffer(&mut self.dynamic_buffers, DynamicBufferWrite {
+                        binding: copy.dst_binding,
+                        array_element: copy.dst_array_element + i,
+                        .. src
+                    });
+                }
+            }
+
+            // We don't know which resources are referenced by the copied descriptors, so we keep
+            // all the resources of the source set alive.
+            self.resources_samplers.extend(copy.src_set.resources_samplers.iter().cloned());
+            self.resources_images.extend(copy.src_set.resources_images.iter().cloned());
+            self.resources_image_views.extend(copy.src_set.resources_image_views.iter().cloned());
+            self.resources_buffers.extend(copy.src_set.resources_buffers.iter().cloned());
+            self.resources_buffer_views.extend(copy.src_set.resources_buffer_views.iter()
+                                                                                .cloned());
+
+            vk_copies.push(vk::CopyDescriptorSet {
+                sType: vk::STRUCTURE_TYPE_COPY_DESCRIPTOR_SET,
+                pNext: ptr::null(),
+                srcSet: copy.src_set.set,
+                srcBinding: copy.src_binding,
+                srcArrayElement: copy.src_array_element,
+                dstSet: self.set,
+                dstBinding: copy.dst_binding,
+                dstArrayElement: copy.dst_array_element,
+                descriptorCount: copy.count,
+            });
+        }
+
+        batch.flush(&self.device, &vk_copies);
+    }
+
+    // Checks a write against the layout of the set before it is performed, and records the
+    // buffer range of dynamic buffer writes.
+    fn prepare_write(&mut self, write: &DescriptorWrite) {
+        let immutable_samplers = self.layout.descriptor(write.binding)
+                                     .map_or(false, |d| d.immutable_samplers.is_some());
+
+        match write.inner {
+            DescriptorWriteInner::Sampler(_) => {
+                assert!(!immutable_samplers,
+                        "Can't write a sampler to a binding that has immutable samplers");
+            },
+            DescriptorWriteInner::CombinedImageSampler(ref sampler, _, _, _) => {
+                if immutable_samplers {
+                    assert!(sampler.is_none(),
+                            "Can't write a sampler to a binding that has immutable samplers");
+                } else {
+                    assert!(sampler.is_some(),
+                            "Writing only the image of a combined image sampler requires a \
+                             binding with immutable samplers");
+                }
+            },
+            DescriptorWriteInner::DynamicUniformBuffer { ref buffer, offset, size } |
+            DescriptorWriteInner::DynamicStorageBuffer { ref buffer, offset, size } => {
+                set_dynamic_buffer(&mut self.dynamic_buffers, DynamicBufferWrite {
+                    binding: write.binding,
+                    array_element: write.first_array_element,
+                    offset: offset,
+                    range: size,
+                    buffer_size: buffer.size(),
+                });
+            },
+            _ => ()
+        }
+    }
+
+    /// Returns the device this descriptor set belongs to.
+    #[inline]
+    pub fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+
+    /// Returns the layout used to create this descriptor set.
+    #[inline]
+    pub fn layout(&self) -> &Arc<UnsafeDescriptorSetLayout> {
+        &self.layout
+    }
+
+
+    // TODO: hacky
+    #[doc(hidden)]
+    #[inline]
+    pub fn images_list(&self) -> &[(Arc<Image>, (u32, u32), ImageLayout)] {
+        &self.resources_images
+    }
+
+    // TODO: hacky
+    #[doc(hidden)]
+    #[inline]
+    pub fn buffers_list(&self) -> &[Arc<Buffer>] {
+        &self.resources_buffers
+    }
+}
+
+impl_debug_handle!(UnsafeDescriptorSet);
+
+unsafe impl VulkanObject for UnsafeDescriptorSet {
+    type Object = vk::DescriptorSet;
+
+    #[inline]
+    fn internal_object(&self) -> vk::DescriptorSet {
+        self.set
+    }
+}
+
+impl Drop for UnsafeDescriptorSet {
+    #[inline]
+    fn drop(&mut self) {
+        if let Some(ref pool) = self.pool {
+            unsafe {
+                let vk = self.device.pointers();
+                vk.FreeDescriptorSets(self.device.internal_object(),
+                                      *pool.internal_object_guard(), 1, &self.set);
+            }
+        }
+    }
+}
+
+// Records the buffer range written to an array element of a dynamic buffer descriptor, replacing
+// the previous one.
+fn set_dynamic_buffer(list: &mut SmallVec<[DynamicBufferWrite; 4]>, write: DynamicBufferWrite) {
+    let existing = list.iter_mut().find(|w| {
+        w.binding == write.binding && w.array_element == write.array_element
+    });
+
+    match existing {
+        Some(existing) => *existing = write,
+        None => list.push(write),
+    }
+}
+
+/// Accumulates writes that target multiple descriptor sets, in order to perform all of them with
+/// a single call to `vkUpdateDescriptorSets`.
+///
+/// The infos of the writes are stored in lists that are only read when the batch is committed,
+/// so that the pointers passed to the driver stay valid during the call. The resources referenced
+/// by the writes are kept alive by the batch until then.
+pub struct DescriptorWriteBatch {
+    device: Option<Arc<Device>>,
+    writes: Vec<PendingWrite>,
+    buffer_descriptors: Vec<vk::DescriptorBufferInfo>,
+    image_descriptors: Vec<vk::DescriptorImageInfo>,
+    texel_buffer_descriptors: Vec<vk::BufferView>,
+    resources: DescriptorWriteResources,
+}
+
+// Write of consecutive array elements of a binding. Its infos start at `first_info` in the list
+// that corresponds to its category.
+struct PendingWrite {
+    set: vk::DescriptorSet,
+    binding: u32,
+    first_array_element: u32,
+    count: u32,
+    ty: DescriptorType,
+    category: WriteCategory,
+    first_info: usize,
+}
+
+impl DescriptorWriteBatch {
+    /// Builds a new empty batch.
+    #[inline]
+    pub fn new() -> DescriptorWriteBatch {
+        DescriptorWriteBatch {
+            device: None,
+            writes: Vec::new(),
+            buffer_descriptors: Vec::new(),
+            image_descriptors: Vec::new(),
+            texel_buffer_descriptors: Vec::new(),
+            resources: DescriptorWriteResources::empty(),
+        }
+    }
+
+    /// Returns the number of writes in the batch.
+    ///
+    /// Writes that target consecutive array elements of the same binding of the same set are
+    /// merged together and count as one.
+    #[inline]
+    pub fn num_writes(&self) -> usize {
+        self.writes.len()
+    }
+
+    /// Adds writes to `set` to the batch.
+    ///
+    /// The resources of the writes are immediately added to the list of resources used by
+    /// `set`.
+    ///
+    /// # Safety
+    ///
+    /// - Doesn't verify that the things you write in the descriptor set match its layout.
+    /// - `set` must stay alive until the batch is committed, and must not be in use by the GPU
+    ///   when it is.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if `set` doesn't belong to the same device as the sets that were previously
+    ///   added to the batch.
+    ///
+    pub unsafe fn add<I>(&mut self, set: &mut UnsafeDescriptorSet, writes: I)
+        where I: IntoIterator<Item = DescriptorWrite>
+    {
+        match self.device {
+            Some(ref device) => {
+                assert_eq!(&**device as *const Device, &*set.device as *const Device,
+                           "All the descriptor sets of a batch must belong to the same device");
+            },
+            None => self.device = Some(set.device.clone()),
+        }
+
+        let mut resources = DescriptorWriteResources::empty();
+
+        for write in writes {
+            set.prepare_write(&write);
+
+            let ty = write.ty();
+            let category = write.inner.category();
+            let first_info = match category {
+                WriteCategory::Buffer => self.buffer_descriptors.len(),
+                WriteCategory::Image => self.image_descriptors.len(),
+                WriteCategory::TexelBuffer => self.texel_buffer_descriptors.len(),
+            };
 
-        let buffer_descriptors = write.iter().filter_map(|write| {
             match write.inner {
                 DescriptorWriteInner::UniformBuffer { ref buffer, offset, size } |
                 DescriptorWriteInner::DynamicUniformBuffer { ref buffer, offset, size } => {
                     assert!(buffer.inner_buffer().usage_uniform_buffer());
-                    self_resources_buffers.push(buffer.clone());
-                    Some(vk::DescriptorBufferInfo {
+                    resources.buffers.push(buffer.clone());
+                    self.buffer_descriptors.push(vk::DescriptorBufferInfo {
                         buffer: buffer.inner_buffer().internal_object(),
                         offset: offset as u64,
                         range: size as u64,
-                    })
+                    });
                 },
                 DescriptorWriteInner::StorageBuffer { ref buffer, offset, size } |
                 DescriptorWriteInner::DynamicStorageBuffer { ref buffer, offset, size } => {
                     assert!(buffer.inner_buffer().usage_storage_buffer());
-                    self_resources_buffers.push(buffer.clone());
-                    Some(vk::DescriptorBufferInfo {
+                    resources.buffers.push(buffer.clone());
+                    self.buffer_descriptors.push(vk::DescriptorBufferInfo {
                         buffer: buffer.inner_buffer().internal_object(),
                         offset: offset as u64,
                         range: size as u64,
-                    })
+                    });
+                },
+                DescriptorWriteInner::UniformTexelBuffer { view, ref buffer, ref keep_alive } => {
+                    assert!(buffer.inner_buffer().usage_uniform_texel_buffer());
+                    resources.buffers.push(buffer.clone());
+                    resources.buffer_views.push(keep_alive.clone());
+                    self.texel_buffer_descriptors.push(view);
+                },
+                DescriptorWriteInner::StorageTexelBuffer { view, ref buffer, ref keep_alive } => {
+                    assert!(buffer.inner_buffer().usage_storage_texel_buffer());
+                    resources.buffers.push(buffer.clone());
+                    resources.buffer_views.push(keep_alive.clone());
+                    self.texel_buffer_descriptors.push(view);
                 },
-                _ => None
-            }
-        }).collect::<SmallVec<[_; 64]>>();
-
-        let image_descriptors = write.iter().filter_map(|write| {
-            match write.inner {
                 DescriptorWriteInner::Sampler(ref sampler) => {
-                    self_resources_samplers.push(sampler.clone());
-                    Some(vk::DescriptorImageInfo {
+                    resources.samplers.push(sampler.clone());
+                    self.image_descriptors.push(vk::DescriptorImageInfo {
                         sampler: sampler.internal_object(),
                         imageView: 0,
                         imageLayout: 0,
-                    })
+                    });
                 },
                 DescriptorWriteInner::CombinedImageSampler(ref sampler, ref view, ref image, ref blocks) => {
                     assert!(view.inner_view().usage_sampled());
+                    // Sampling requires choosing between the depth and the stencil aspects.
+                    assert!(!view.inner_view().aspects().depth ||
+                            !view.inner_view().aspects().stencil);
                     let layout = view.descriptor_set_combined_image_sampler_layout();
-                    self_resources_samplers.push(sampler.clone());
-                    self_resources_image_views.push(view.clone());
+                    if let Some(ref sampler) = *sampler {
+                        resources.samplers.push(sampler.clone());
+                    }
+                    resources.image_views.push(view.clone());
                     for &block in blocks.iter() {
-                        self_resources_images.push((image.clone(), block, layout));       // TODO: check for collisions
+                        resources.images.push((image.clone(), block, layout));       // TODO: check for collisions
                     }
-                    Some(vk::DescriptorImageInfo {
-                        sampler: sampler.internal_object(),
+                    self.image_descriptors.push(vk::DescriptorImageInfo {
+                        // Ignored by the implementation if the sampler is immutable.
+                        sampler: sampler.as_ref().map_or(0, |s| s.internal_object()),
                         imageView: view.inner_view().internal_object(),
                         imageLayout: layout as u32,
-                    })
+                    });
                 },
                 DescriptorWriteInner::StorageImage(ref view, ref image, ref blocks) => {
                     assert!(view.inner_view().usage_storage());
                     assert!(view.identity_swizzle());
                     let layout = view.descriptor_set_storage_image_layout();
-                    self_resources_image_views.push(view.clone());
+                    resources.image_views.push(view.clone());
                     for &block in blocks.iter() {
-                        self_resources_images.push((image.clone(), block, layout));       // TODO: check for collisions
+                        resources.images.push((image.clone(), block, layout));       // TODO: check for collisions
                     }
-                    Some(vk::DescriptorImageInfo {
+                    self.image_descriptors.push(vk::DescriptorImageInfo {
                         sampler: 0,
                         imageView: view.inner_view().internal_object(),
                         imageLayout: layout as u32,
-                    })
+                    });
                 },
                 DescriptorWriteInner::SampledImage(ref view, ref image, ref blocks) => {
                     assert!(view.inner_view().usage_sampled());
+                    // Sampling requires choosing between the depth and the stencil aspects.
+                    assert!(!view.inner_view().aspects().depth ||
+                            !view.inner_view().aspects().stencil);
                     let layout = view.descriptor_set_sampled_image_layout();
-                    self_resources_image_views.push(view.clone());
+                    resources.image_views.push(view.clone());
                     for &block in blocks.iter() {
-                        self_resources_images.push((image.clone(), block, layout));       // TODO: check for collisions
+                        resources.images.push((image.clone(), block, layout));       // TODO: check for collisions
                     }
-                    Some(vk::DescriptorImageInfo {
+                    self.image_descriptors.push(vk::DescriptorImageInfo {
                         sampler: 0,
                         imageView: view.inner_view().internal_object(),
                         imageLayout: layout as u32,
-                    })
+                    });
                 },
                 DescriptorWriteInner::InputAttachment(ref view, ref image, ref blocks) => {
                     assert!(view.inner_view().usage_input_attachment());
                     assert!(view.identity_swizzle());
                     let layout = view.descriptor_set_input_attachment_layout();
-                    self_resources_image_views.push(view.clone());
+                    resources.image_views.push(view.clone());
                     for &block in blocks.iter() {
-                        self_resources_images.push((image.clone(), block, layout));       // TODO: check for collisions
+                        resources.images.push((image.clone(), block, layout));       // TODO: check for collisions
                     }
-                    Some(vk::DescriptorImageInfo {
+                    self.image_descriptors.push(vk::DescriptorImageInfo {
                         sampler: 0,
                         imageView: view.inner_view().internal_object(),
                         imageLayout: layout as u32,
-                    })
+                    });
                 },
-                _ => None
             }
-        }).collect::<SmallVec<[_; 64]>>();
 
+            // If this write targets the array element that follows the previous write, we simply
+            // increase the number of descriptors of the previous write. This works because the
+            // infos of both writes are next to each other in their list.
+            if let Some(last) = self.writes.last_mut() {
+                if last.set == set.set && last.binding == write.binding && last.ty == ty &&
+                   last.first_array_element + last.count == write.first_array_element
+                {
+                    last.count += 1;
+                    continue;
+                }
+            }
+
+            self.writes.push(PendingWrite {
+                set: set.set,
+                binding: write.binding,
+                first_array_element: write.first_array_element,
+                count: 1,
+                ty: ty,
+                category: category,
+                first_info: first_info,
+            });
+        }
+
+        set.resources_samplers.extend(resources.samplers.iter().cloned());
+        set.resources_images.extend(resources.images.iter().cloned());
+        set.resources_image_views.extend(resources.image_views.iter().cloned());
+        set.resources_buffers.extend(resources.buffers.iter().cloned());
+        set.resources_buffer_views.extend(resources.buffer_views.iter().cloned());
+        self.resources.append(resources);
+    }
+
+    /// Performs all the writes of the batch with a single call to `vkUpdateDescriptorSets`,
+    /// and returns the resources referenced by the writes.
+    ///
+    /// # Safety
+    ///
+    /// - The descriptor sets that were added to the batch must still be alive and must not be in
+    ///   use by the GPU.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the descriptor sets of the batch don't belong to `device`.
+    ///
+    #[inline]
+    pub unsafe fn commit(self, device: &Arc<Device>) -> DescriptorWriteResources {
+        self.flush(device, &[])
+    }
 
-        let mut next_buffer_desc = 0;
-        let mut next_image_desc = 0;
+    // Performs the writes of the batch and `copies` with a single call to
+    // `vkUpdateDescriptorSets`.
+    unsafe fn flush(self, device: &Arc<Device>, copies: &[vk::CopyDescriptorSet])
+                    -> DescriptorWriteResources
+    {
+        if let Some(ref batch_device) = self.device {
+            assert_eq!(&**batch_device as *const Device, &**device as *const Device,
+                       "The descriptor sets of the batch don't belong to this device");
+        }
 
-        let vk_writes = write.iter().map(|write| {
-            let (buffer_info, image_info) = match write.inner {
-                DescriptorWriteInner::Sampler(_) | DescriptorWriteInner::CombinedImageSampler(_, _, _, _) |
-                DescriptorWriteInner::SampledImage(_, _, _) | DescriptorWriteInner::StorageImage(_, _, _) |
-                DescriptorWriteInner::InputAttachment(_, _, _) => {
-                    let img = image_descriptors.as_ptr().offset(next_image_desc as isize);
-                    next_image_desc += 1;
-                    (ptr::null(), img)
+        // FIXME: the descriptor sets must be synchronized
+        let vk_writes = self.writes.iter().map(|write| {
+            let (buffer_info, image_info, texel_buffer_view) = match write.category {
+                WriteCategory::Buffer => {
+                    (self.buffer_descriptors.as_ptr().offset(write.first_info as isize),
+                     ptr::null(), ptr::null())
+                },
+                WriteCategory::Image => {
+                    (ptr::null(), self.image_descriptors.as_ptr().offset(write.first_info as isize),
+                     ptr::null())
                 },
-                //DescriptorWriteInner::UniformTexelBuffer(_) | DescriptorWriteInner::StorageTexelBuffer(_) =>
-                DescriptorWriteInner::UniformBuffer { .. } | DescriptorWriteInner::StorageBuffer { .. } |
-                DescriptorWriteInner::DynamicUniformBuffer { .. } |
-                DescriptorWriteInner::DynamicStorageBuffer { .. } => {
-                    let buf = buffer_descriptors.as_ptr().offset(next_buffer_desc as isize);
-                    next_buffer_desc += 1;
-                    (buf, ptr::null())
+                WriteCategory::TexelBuffer => {
+                    (ptr::null(), ptr::null(),
+                     self.texel_buffer_descriptors.as_ptr().offset(write.first_info as isize))
                 },
             };
 
-            // FIXME: the descriptor set must be synchronized
             vk::WriteDescriptorSet {
                 sType: vk::STRUCTURE_TYPE_WRITE_DESCRIPTOR_SET,
                 pNext: ptr::null(),
-                dstSet: self_set,
+                dstSet: write.set,
                 dstBinding: write.binding,
                 dstArrayElement: write.first_array_element,
-                descriptorCount: 1,
-                descriptorType: write.ty() as u32,
+                descriptorCount: write.count,
+                descriptorType: write.ty as u32,
                 pImageInfo: image_info,
                 pBufferInfo: buffer_info,
-                pTexelBufferView: ptr::null(),      // TODO:
+                pTexelBufferView: texel_buffer_view,
             }
-        }).collect::<SmallVec<[_; 64]>>();
+        }).collect::<Vec<_>>();
 
-        debug_assert_eq!(next_buffer_desc, buffer_descriptors.len());
-        debug_assert_eq!(next_image_desc, image_descriptors.len());
+        if !vk_writes.is_empty() || !copies.is_empty() {
+            let vk = device.pointers();
+            vk.UpdateDescriptorSets(device.internal_object(),
+                                    vk_writes.len() as u32, vk_writes.as_ptr(),
+                                    copies.len() as u32, copies.as_ptr());
+        }
+
+        self.resources
+    }
+}
+
+/// Resources referenced by the writes of a `DescriptorWriteBatch`.
+///
+/// Returned when the batch is committed, so that they can be kept alive for as long as the
+/// descriptor sets use them.
+pub struct DescriptorWriteResources {
+    samplers: Vec<Arc<Sampler>>,
+    images: Vec<(Arc<Image>, (u32, u32), ImageLayout)>,
+    image_views: Vec<Arc<ImageView>>,
+    buffers: Vec<Arc<Buffer>>,
+    buffer_views: Vec<Arc<Any + Send + Sync>>,
+}
 
-        if !vk_writes.is_empty() {
-            vk.UpdateDescriptorSets(self.pool.device().internal_object(),
-                                    vk_writes.len() as u32, vk_writes.as_ptr(), 0, ptr::null());
+impl DescriptorWriteResources {
+    #[inline]
+    fn empty() -> DescriptorWriteResources {
+        DescriptorWriteResources {
+            samplers: Vec::new(),
+            images: Vec::new(),
+            image_views: Vec::new(),
+            buffers: Vec::new(),
+            buffer_views: Vec::new(),
         }
     }
 
-    /// Returns the layout used to create this descriptor set.
     #[inline]
-    pub fn layout(&self) -> &Arc<UnsafeDescriptorSetLayout> {
-        &self.layout
+    fn append(&mut self, mut other: DescriptorWriteResources) {
+        self.samplers.append(&mut other.samplers);
+        self.images.append(&mut other.images);
+        self.image_views.append(&mut other.image_views);
+        self.buffers.append(&mut other.buffers);
+        self.buffer_views.append(&mut other.buffer_views);
     }
 
+    /// Returns the samplers referenced by the writes.
+    #[inline]
+    pub fn samplers(&self) -> &[Arc<Sampler>] {
+        &self.samplers
+    }
 
-    // TODO: hacky
-    #[doc(hidden)]
+    /// Returns the blocks of images referenced by the writes, and the layout they are used in.
     #[inline]
-    pub fn images_list(&self) -> &[(Arc<Image>, (u32, u32), ImageLayout)] {
-        &self.resources_images
+    pub fn images(&self) -> &[(Arc<Image>, (u32, u32), ImageLayout)] {
+        &self.images
     }
 
-    // TODO: hacky
-    #[doc(hidden)]
+    /// Returns the image views referenced by the writes.
     #[inline]
-    pub fn buffers_list(&self) -> &[Arc<Buffer>] {
-        &self.resources_buffers
+    pub fn image_views(&self) -> &[Arc<ImageView>] {
+        &self.image_views
     }
-}
 
-unsafe impl VulkanObject for UnsafeDescriptorSet {
-    type Object = vk::DescriptorSet;
+    /// Returns the buffers referenced by the writes.
+    #[inline]
+    pub fn buffers(&self) -> &[Arc<Buffer>] {
+        &self.buffers
+    }
 
+    /// Returns the buffer views referenced by the writes.
     #[inline]
-    fn internal_object(&self) -> vk::DescriptorSet {
-        self.set
+    pub fn buffer_views(&self) -> &[Arc<Any + Send + Sync>] {
+        &self.buffer_views
     }
 }
 
-impl Drop for UnsafeDescriptorSet {
+/// Checks whether `offsets` are valid dynamic offsets for binding `sets` together.
+///
+/// The offsets must be ordered by set, then by binding, then by array element. Each offset must
+/// be a multiple of the `min_uniform_buffer_offset_alignment` or
+/// `min_storage_buffer_offset_alignment` limit, and the range of the buffer that was written to
+/// the descriptor, moved by the offset, must stay inside the buffer.
+#[doc(hidden)]
+pub fn check_dynamic_offsets<'a, I>(sets: I, offsets: &[u32]) -> Result<(), DynamicOffsetsError>
+    where I: IntoIterator<Item = &'a UnsafeDescriptorSet>
+{
+    let sets = sets.into_iter().collect::<SmallVec<[_; 8]>>();
+
+    let expected = sets.iter().fold(0, |acc, set| acc + set.layout.num_dynamic_offsets());
+    if expected != offsets.len() {
+        return Err(DynamicOffsetsError::WrongCount {
+            expected: expected,
+            obtained: offsets.len(),
+        });
+    }
+
+    let mut offsets = offsets.iter();
+
+    for (set_num, set) in sets.iter().enumerate() {
+        let limits = set.device.physical_device().limits();
+
+        for desc in set.layout.dynamic_descriptors() {
+            let alignment = if desc.storage {
+                limits.min_storage_buffer_offset_alignment()
+            } else {
+                limits.min_uniform_buffer_offset_alignment()
+            };
+
+            for array_element in 0 .. desc.array_count {
+                let offset = *offsets.next().unwrap();
+
+                if offset as u64 % alignment != 0 {
+                    return Err(DynamicOffsetsError::UnalignedOffset {
+                        set_num: set_num,
+                        binding: desc.binding,
+                        offset: offset,
+                        alignment: alignment,
+                    });
+                }
+
+                let write = set.dynamic_buffers.iter().find(|w| {
+                    w.binding == desc.binding && w.array_element == array_element
+                });
+
+                // We can't check the range of a descriptor that hasn't been written.
+                if let Some(write) = write {
+                    if write.offset + offset as usize + write.range > write.buffer_size {
+                        return Err(DynamicOffsetsError::OutOfBufferRange {
+                            set_num: set_num,
+                            binding: desc.binding,
+                            offset: offset,
+                            range: write.offset .. write.offset + write.range,
+                            buffer_size: write.buffer_size,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Error that can happen when checking the dynamic offsets of descriptor sets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DynamicOffsetsError {
+    /// The number of dynamic offsets doesn't match the number of dynamic descriptors of the sets.
+    WrongCount {
+        /// Number of dynamic descriptors in the sets.
+        expected: usize,
+        /// Number of dynamic offsets that were passed.
+        obtained: usize,
+    },
+
+    /// A dynamic offset isn't a multiple of the `min_uniform_buffer_offset_alignment` or
+    /// `min_storage_buffer_offset_alignment` limit.
+    UnalignedOffset {
+        /// Index of the descriptor set.
+        set_num: usize,
+        /// Binding of the descriptor.
+        binding: u32,
+        /// The offset that was passed.
+        offset: u32,
+        /// The required alignment.
+        alignment: u64,
+    },
+
+    /// A dynamic offset moves the range of a buffer descriptor outside of its buffer.
+    OutOfBufferRange {
+        /// Index of the descriptor set.
+        set_num: usize,
+        /// Binding of the descriptor.
+        binding: u32,
+        /// The offset that was passed.
+        offset: u32,
+        /// The range of the buffer that was written to the descriptor.
+        range: Range<usize>,
+        /// Size of the buffer.
+        buffer_size: usize,
+    },
+}
+
+impl error::Error for DynamicOffsetsError {
     #[inline]
-    fn drop(&mut self) {
-        unsafe {
-            let vk = self.pool.device().pointers();
-            vk.FreeDescriptorSets(self.pool.device().internal_object(),
-                                  *self.pool.internal_object_guard(), 1, &self.set);
+    fn description(&self) -> &str {
+        match *self {
+            DynamicOffsetsError::WrongCount { .. } => {
+                "the number of dynamic offsets doesn't match the number of dynamic descriptors"
+            },
+            DynamicOffsetsError::UnalignedOffset { .. } => {
+                "a dynamic offset isn't properly aligned"
+            },
+            DynamicOffsetsError::OutOfBufferRange { .. } => {
+                "a dynamic offset moves the range of a descriptor outside of its buffer"
+            },
         }
     }
 }
 
+impl fmt::Display for DynamicOffsetsError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+/// Copy of descriptors from a descriptor set to the set that is being updated.
+///
+/// Copies `count` consecutive array elements of `src_binding` in `src_set`, starting at
+/// `src_array_element`, to the array elements of `dst_binding` starting at `dst_array_element`.
+#[derive(Copy, Clone)]
+pub struct DescriptorCopy<'a> {
+    /// The descriptor set to copy from.
+    pub src_set: &'a UnsafeDescriptorSet,
+    /// Binding to copy from.
+    pub src_binding: u32,
+    /// First array element to copy from.
+    pub src_array_element: u32,
+    /// Binding to copy to.
+    pub dst_binding: u32,
+    /// First array element to copy to.
+    pub dst_array_element: u32,
+    /// Number of array elements to copy.
+    pub count: u32,
+}
+
 /// Represents a single write entry to a descriptor set.
+///
+/// By default a write modifies the first array element of its binding. Use `array_element()` to
+/// write another element of an arrayed binding.
 pub struct DescriptorWrite {
     binding: u32,
     first_array_element: u32,
@@ -317,9 +886,9 @@ enum DescriptorWriteInner {
     StorageImage(Arc<ImageView>, Arc<Image>, Vec<(u32, u32)>),
     Sampler(Arc<Sampler>),
     SampledImage(Arc<ImageView>, Arc<Image>, Vec<(u32, u32)>),
-    CombinedImageSampler(Arc<Sampler>, Arc<ImageView>, Arc<Image>, Vec<(u32, u32)>),
-    //UniformTexelBuffer(Arc<Buffer>),      // FIXME: requires buffer views
-    //StorageTexelBuffer(Arc<Buffer>),      // FIXME: requires buffer views
+    CombinedImageSampler(Option<Arc<Sampler>>, Arc<ImageView>, Arc<Image>, Vec<(u32, u32)>),
+    UniformTexelBuffer { view: vk::BufferView, buffer: Arc<Buffer>, keep_alive: Arc<Any + Send + Sync> },
+    StorageTexelBuffer { view: vk::BufferView, buffer: Arc<Buffer>, keep_alive: Arc<Any + Send + Sync> },
     UniformBuffer { buffer: Arc<Buffer>, offset: usize, size: usize },
     StorageBuffer { buffer: Arc<Buffer>, offset: usize, size: usize },
     DynamicUniformBuffer { buffer: Arc<Buffer>, offset: usize, size: usize },
@@ -327,7 +896,50 @@ enum DescriptorWriteInner {
     InputAttachment(Arc<ImageView>, Arc<Image>, Vec<(u32, u32)>),
 }
 
+// Which list of infos a write uses in `vkUpdateDescriptorSets`.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum WriteCategory {
+    Buffer,
+    Image,
+    TexelBuffer,
+}
+
+impl DescriptorWriteInner {
+    #[inline]
+    fn category(&self) -> WriteCategory {
+        match *self {
+            DescriptorWriteInner::Sampler(_) | DescriptorWriteInner::CombinedImageSampler(_, _, _, _) |
+            DescriptorWriteInner::SampledImage(_, _, _) | DescriptorWriteInner::StorageImage(_, _, _) |
+            DescriptorWriteInner::InputAttachment(_, _, _) => WriteCategory::Image,
+            DescriptorWriteInner::UniformTexelBuffer { .. } |
+            DescriptorWriteInner::StorageTexelBuffer { .. } => WriteCategory::TexelBuffer,
+            DescriptorWriteInner::UniformBuffer { .. } | DescriptorWriteInner::StorageBuffer { .. } |
+            DescriptorWriteInner::DynamicUniformBuffer { .. } |
+            DescriptorWriteInner::DynamicStorageBuffer { .. } => WriteCategory::Buffer,
+        }
+    }
+}
+
 impl DescriptorWrite {
+    /// Modifies the array element of the binding that this write targets.
+    #[inline]
+    pub fn array_element(mut self, element: u32) -> DescriptorWrite {
+        self.first_array_element = element;
+        self
+    }
+
+    /// Returns the binding that this write targets.
+    #[inline]
+    pub fn binding(&self) -> u32 {
+        self.binding
+    }
+
+    /// Returns the array element of the binding that this write targets.
+    #[inline]
+    pub fn first_array_element(&self) -> u32 {
+        self.first_array_element
+    }
+
     #[inline]
     pub fn storage_image<I>(binding: u32, image: &Arc<I>) -> DescriptorWrite
         where I: ImageView + 'static
@@ -359,14 +971,68 @@ impl DescriptorWrite {
         }
     }
 
+    /// # Panic
+    ///
+    /// - Panicks if the sampler can't be used with the format of the image view.
+    ///
     #[inline]
     pub fn combined_image_sampler<I>(binding: u32, sampler: &Arc<Sampler>, image: &Arc<I>) -> DescriptorWrite
         where I: ImageView + 'static
     {
+        assert!(image.can_be_sampled(sampler), "The sampler can't be used with the format of \
+                                                the image view");
+
+        DescriptorWrite {
+            binding: binding,
+            first_array_element: 0,
+            inner: DescriptorWriteInner::CombinedImageSampler(Some(sampler.clone()), image.clone(), ImageView::parent_arc(image), image.blocks())
+        }
+    }
+
+    /// Writes only the image of a combined image sampler descriptor whose binding has immutable
+    /// samplers.
+    #[inline]
+    pub fn combined_image_immutable_sampler<I>(binding: u32, image: &Arc<I>) -> DescriptorWrite
+        where I: ImageView + 'static
+    {
+        DescriptorWrite {
+            binding: binding,
+            first_array_element: 0,
+            inner: DescriptorWriteInner::CombinedImageSampler(None, image.clone(), ImageView::parent_arc(image), image.blocks())
+        }
+    }
+
+    #[inline]
+    pub fn uniform_texel_buffer<F, B>(binding: u32, view: &Arc<BufferView<F, B>>) -> DescriptorWrite
+        where F: Send + Sync + 'static, B: Buffer + 'static
+    {
+        assert!(view.uniform_texel_buffer());
+
+        DescriptorWrite {
+            binding: binding,
+            first_array_element: 0,
+            inner: DescriptorWriteInner::UniformTexelBuffer {
+                view: view.internal_object(),
+                buffer: view.buffer().clone(),
+                keep_alive: view.clone(),
+            },
+        }
+    }
+
+    #[inline]
+    pub fn storage_texel_buffer<F, B>(binding: u32, view: &Arc<BufferView<F, B>>) -> DescriptorWrite
+        where F: Send + Sync + 'static, B: Buffer + 'static
+    {
+        assert!(view.storage_texel_buffer());
+
         DescriptorWrite {
             binding: binding,
             first_array_element: 0,
-            inner: DescriptorWriteInner::CombinedImageSampler(sampler.clone(), image.clone(), ImageView::parent_arc(image), image.blocks())
+            inner: DescriptorWriteInner::StorageTexelBuffer {
+                view: view.internal_object(),
+                buffer: view.buffer().clone(),
+                keep_alive: view.clone(),
+            },
         }
     }
 
@@ -521,8 +1187,8 @@ impl DescriptorWrite {
             DescriptorWriteInner::CombinedImageSampler(_, _, _, _) => DescriptorType::CombinedImageSampler,
             DescriptorWriteInner::SampledImage(_, _, _) => DescriptorType::SampledImage,
             DescriptorWriteInner::StorageImage(_, _, _) => DescriptorType::StorageImage,
-            //DescriptorWriteInner::UniformTexelBuffer(_) => DescriptorType::UniformTexelBuffer,
-            //DescriptorWriteInner::StorageTexelBuffer(_) => DescriptorType::StorageTexelBuffer,
+            DescriptorWriteInner::UniformTexelBuffer { .. } => DescriptorType::UniformTexelBuffer,
+            DescriptorWriteInner::StorageTexelBuffer { .. } => DescriptorType::StorageTexelBuffer,
             DescriptorWriteInner::UniformBuffer { .. } => DescriptorType::UniformBuffer,
             DescriptorWriteInner::StorageBuffer { .. } => DescriptorType::StorageBuffer,
             DescriptorWriteInner::DynamicUniformBuffer { .. } => DescriptorType::UniformBufferDynamic,
@@ -531,3 +1197,522 @@ impl DescriptorWrite {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cmp;
+    use std::iter;
+    use std::sync::Arc;
+    use std::u32;
+
+    use buffer::BufferSlice;
+    use buffer::BufferUsage;
+    use buffer::BufferView;
+    use buffer::CpuAccessibleBuffer;
+    use descriptor::descriptor::DescriptorBufferDesc;
+    use descriptor::descriptor::DescriptorDesc;
+    use descriptor::descriptor::DescriptorDescTy;
+    use descriptor::descriptor::DescriptorImageDesc;
+    use descriptor::descriptor::DescriptorImageDescArray;
+    use descriptor::descriptor::DescriptorImageDescDimensions;
+    use descriptor::descriptor::ShaderStages;
+    use descriptor::descriptor_set::DescriptorCopy;
+    use descriptor::descriptor_set::DescriptorWrite;
+    use descriptor::descriptor_set::DescriptorWriteBatch;
+    use descriptor::descriptor_set::DynamicDescriptor;
+    use descriptor::descriptor_set::DynamicOffsetsError;
+    use descriptor::descriptor_set::check_dynamic_offsets;
+    use descriptor::descriptor_set::UnsafeDescriptorPool;
+    use descriptor::descriptor_set::UnsafeDescriptorSetLayout;
+    use device::Device;
+    use format;
+    use image::ImageUsage;
+    use image::storage::StorageImage;
+    use image::sys::Dimensions;
+    use sampler;
+
+    #[test]
+    fn write_all_types() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let image_desc = |sampled| DescriptorDescTy::Image(DescriptorImageDesc {
+            sampled: sampled,
+            dimensions: DescriptorImageDescDimensions::TwoDimensional,
+            format: None,
+            multisampled: false,
+            array_layers: DescriptorImageDescArray::NonArrayed,
+        });
+
+        let buffer_desc = |storage, dynamic| DescriptorDescTy::Buffer(DescriptorBufferDesc {
+            dynamic: Some(dynamic),
+            storage: storage,
+        });
+
+        let types = vec![
+            DescriptorDescTy::Sampler,
+            DescriptorDescTy::CombinedImageSampler(match image_desc(true) {
+                DescriptorDescTy::Image(desc) => desc,
+                _ => unreachable!()
+            }),
+            image_desc(true),
+            image_desc(false),
+            DescriptorDescTy::TexelBuffer { sampled: true, format: None },
+            DescriptorDescTy::TexelBuffer { sampled: false, format: None },
+            buffer_desc(false, false),
+            buffer_desc(true, false),
+            buffer_desc(false, true),
+            buffer_desc(true, true),
+        ];
+
+        let num_types = types.len() as u32;
+        let descriptors = types.into_iter().enumerate().map(|(binding, ty)| {
+            DescriptorDesc {
+                binding: binding as u32,
+                ty: ty,
+                array_count: 1,
+                stages: ShaderStages::all(),
+                readonly: false,
+                immutable_samplers: None,
+            }
+        }).chain(iter::once(DescriptorDesc {
+            binding: num_types,
+            ty: image_desc(true),
+            array_count: 3,
+            stages: ShaderStages::all(),
+            readonly: true,
+            immutable_samplers: None,
+        }));
+
+        let layout = UnsafeDescriptorSetLayout::new(&device, descriptors.map(Some));
+        let mut pool = UnsafeDescriptorPool::new(&device, 1, &layout.descriptors_count(),
+                                                 false).unwrap();
+
+        let sampler = sampler::Sampler::new(&device, sampler::Filter::Linear,
+                                            sampler::Filter::Linear,
+                                            sampler::MipmapMode::Nearest,
+                                            sampler::SamplerAddressMode::Repeat,
+                                            sampler::SamplerAddressMode::Repeat,
+                                            sampler::SamplerAddressMode::Repeat, 0.0, 1.0,
+                                            0.0, 1.0).unwrap();
+
+        let image = StorageImage::with_usage(&device, Dimensions::Dim2d { width: 4, height: 4 },
+                                             format::R8G8B8A8Unorm,
+                                             ImageUsage { sampled: true, .. ImageUsage::none() },
+                                             Some(queue.family())).unwrap();
+
+        let usage = BufferUsage {
+            uniform_texel_buffer: true,
+            storage_texel_buffer: true,
+            uniform_buffer: true,
+            storage_buffer: true,
+            .. BufferUsage::none()
+        };
+        let buffer = CpuAccessibleBuffer::<[[u8; 4]]>::array(&device, 64, &usage,
+                                                             Some(queue.family())).unwrap();
+        let view = BufferView::new(&buffer, format::R8G8B8A8Unorm).unwrap();
+
+        unsafe {
+            let mut set = pool.alloc(iter::once(&layout)).unwrap().next().unwrap();
+
+            set.write(vec![
+                DescriptorWrite::sampler(0, &sampler),
+                DescriptorWrite::combined_image_sampler(1, &sampler, &image),
+                DescriptorWrite::sampled_image(2, &image),
+                DescriptorWrite::storage_image(3, &image),
+                DescriptorWrite::uniform_texel_buffer(4, &view),
+                DescriptorWrite::storage_texel_buffer(5, &view),
+                DescriptorWrite::uniform_buffer(6, &buffer),
+                DescriptorWrite::storage_buffer(7, &buffer),
+                DescriptorWrite::dynamic_uniform_buffer(8, &buffer),
+                DescriptorWrite::dynamic_storage_buffer(9, &buffer),
+                DescriptorWrite::sampled_image(10, &image),
+                DescriptorWrite::sampled_image(10, &image).array_element(1),
+                DescriptorWrite::sampled_image(10, &image).array_element(2),
+            ]);
+
+            assert_eq!(set.buffers_list().len(), 6);
+        }
+    }
+
+    #[test]
+    fn array_element() {
+        let (device, _) = gfx_dev_and_queue!();
+        let sampler = sampler::Sampler::new(&device, sampler::Filter::Linear,
+                                            sampler::Filter::Linear,
+                                            sampler::MipmapMode::Nearest,
+                                            sampler::SamplerAddressMode::Repeat,
+                                            sampler::SamplerAddressMode::Repeat,
+                                            sampler::SamplerAddressMode::Repeat, 0.0, 1.0,
+                                            0.0, 1.0).unwrap();
+
+        let write = DescriptorWrite::sampler(3, &sampler);
+        assert_eq!(write.binding(), 3);
+        assert_eq!(write.first_array_element(), 0);
+
+        let write = write.array_element(2);
+        assert_eq!(write.binding(), 3);
+        assert_eq!(write.first_array_element(), 2);
+    }
+
+    #[test]
+    fn copy_then_overwrite() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let layout = copy_layout(&device);
+        let mut pool = UnsafeDescriptorPool::new(&device, 2, &(layout.descriptors_count() * 2),
+                                                 false).unwrap();
+
+        let usage = BufferUsage { uniform_buffer: true, .. BufferUsage::none() };
+        let buffer_a = CpuAccessibleBuffer::<[u32; 4]>::new(&device, &usage,
+                                                            Some(queue.family())).unwrap();
+        let buffer_b = CpuAccessibleBuffer::<[u32; 4]>::new(&device, &usage,
+                                                            Some(queue.family())).unwrap();
+
+        unsafe {
+            let mut sets = pool.alloc(vec![&layout, &layout]).unwrap();
+            let mut src = sets.next().unwrap();
+            let mut dst = sets.next().unwrap();
+
+            src.write(vec![
+                DescriptorWrite::uniform_buffer(0, &buffer_a),
+                DescriptorWrite::uniform_buffer(1, &buffer_a),
+                DescriptorWrite::uniform_buffer(1, &buffer_a).array_element(1),
+            ]);
+
+            dst.update(vec![], vec![
+                DescriptorCopy {
+                    src_set: &src,
+                    src_binding: 0,
+                    src_array_element: 0,
+                    dst_binding: 0,
+                    dst_array_element: 0,
+                    count: 1,
+                },
+                DescriptorCopy {
+                    src_set: &src,
+                    src_binding: 1,
+                    src_array_element: 0,
+                    dst_binding: 1,
+                    dst_array_element: 0,
+                    count: 2,
+                },
+            ]);
+
+            // The destination keeps the resources of the source alive.
+            assert_eq!(dst.buffers_list().len(), src.buffers_list().len());
+
+            dst.write(vec![DescriptorWrite::uniform_buffer(0, &buffer_b)]);
+            assert_eq!(dst.buffers_list().len(), src.buffers_list().len() + 1);
+        }
+    }
+
+    #[test]
+    fn batch_three_sets() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let layout = copy_layout(&device);
+        let mut pool = UnsafeDescriptorPool::new(&device, 4, &(layout.descriptors_count() * 4),
+                                                 false).unwrap();
+
+        let uniform = BufferUsage { uniform_buffer: true, .. BufferUsage::none() };
+        let storage = BufferUsage { storage_buffer: true, .. BufferUsage::none() };
+        let buffers = (0 .. 3).map(|_| {
+            CpuAccessibleBuffer::<[u32; 4]>::new(&device, &uniform, Some(queue.family())).unwrap()
+        }).collect::<Vec<_>>();
+        let storage_buffer = CpuAccessibleBuffer::<[u32; 4]>::new(&device, &storage,
+                                                                  Some(queue.family())).unwrap();
+
+        unsafe {
+            let mut sets = pool.alloc(vec![&layout, &layout, &layout, &layout]).unwrap()
+                               .collect::<Vec<_>>();
+            let mut dst = sets.pop().unwrap();
+
+            let mut batch = DescriptorWriteBatch::new();
+            for (set, buffer) in sets.iter_mut().zip(buffers.iter()) {
+                batch.add(set, vec![
+                    DescriptorWrite::uniform_buffer(0, buffer),
+                    DescriptorWrite::uniform_buffer(1, buffer),
+                    DescriptorWrite::uniform_buffer(1, buffer).array_element(1),
+                    DescriptorWrite::storage_buffer(2, &storage_buffer),
+                ]);
+            }
+
+            // The two writes to the array of binding 1 of each set are merged.
+            assert_eq!(batch.num_writes(), 9);
+
+            let resources = batch.commit(&device);
+            assert_eq!(resources.buffers().len(), 12);
+            for set in sets.iter() {
+                assert_eq!(set.buffers_list().len(), 4);
+            }
+
+            // Copying from the sets only works if they were written.
+            for set in sets.iter() {
+                dst.update(vec![], vec![
+                    DescriptorCopy {
+                        src_set: set,
+                        src_binding: 1,
+                        src_array_element: 0,
+                        dst_binding: 1,
+                        dst_array_element: 0,
+                        count: 2,
+                    },
+                    DescriptorCopy {
+                        src_set: set,
+                        src_binding: 2,
+                        src_array_element: 0,
+                        dst_binding: 2,
+                        dst_array_element: 0,
+                        count: 1,
+                    },
+                ]);
+            }
+
+            assert_eq!(dst.buffers_list().len(), 12);
+        }
+    }
+
+    #[test]
+    #[should_panic = "A copy reads outside of the array of its source binding"]
+    fn copy_out_of_range() {
+        let (device, _) = gfx_dev_and_queue!();
+        let layout = copy_layout(&device);
+        let mut pool = UnsafeDescriptorPool::new(&device, 2, &(layout.descriptors_count() * 2),
+                                                 false).unwrap();
+
+        unsafe {
+            let mut sets = pool.alloc(vec![&layout, &layout]).unwrap();
+            let src = sets.next().unwrap();
+            let mut dst = sets.next().unwrap();
+
+            dst.update(vec![], iter::once(DescriptorCopy {
+                src_set: &src,
+                src_binding: 1,
+                src_array_element: 1,
+                dst_binding: 1,
+                dst_array_element: 0,
+                count: 2,
+            }));
+        }
+    }
+
+    #[test]
+    #[should_panic = "A copy reads outside of the array of its source binding"]
+    fn copy_range_overflow() {
+        let (device, _) = gfx_dev_and_queue!();
+        let layout = copy_layout(&device);
+        let mut pool = UnsafeDescriptorPool::new(&device, 2, &(layout.descriptors_count() * 2),
+                                                 false).unwrap();
+
+        unsafe {
+            let mut sets = pool.alloc(vec![&layout, &layout]).unwrap();
+            let src = sets.next().unwrap();
+            let mut dst = sets.next().unwrap();
+
+            dst.update(vec![], iter::once(DescriptorCopy {
+                src_set: &src,
+                src_binding: 1,
+                src_array_element: 1,
+                dst_binding: 1,
+                dst_array_element: 0,
+                count: u32::MAX,
+            }));
+        }
+    }
+
+    #[test]
+    #[should_panic = "The source and destination bindings of a copy don't have the same \
+                      descriptor type"]
+    fn copy_type_mismatch() {
+        let (device, _) = gfx_dev_and_queue!();
+        let layout = copy_layout(&device);
+        let mut pool = UnsafeDescriptorPool::new(&device, 2, &(layout.descriptors_count() * 2),
+                                                 false).unwrap();
+
+        unsafe {
+            let mut sets = pool.alloc(vec![&layout, &layout]).unwrap();
+            let src = sets.next().unwrap();
+            let mut dst = sets.next().unwrap();
+
+            dst.update(vec![], iter::once(DescriptorCopy {
+                src_set: &src,
+                src_binding: 0,
+                src_array_element: 0,
+                dst_binding: 2,
+                dst_array_element: 0,
+                count: 1,
+            }));
+        }
+    }
+
+    #[test]
+    fn dynamic_offsets() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let layout = dynamic_layout(&device);
+        let mut pool = UnsafeDescriptorPool::new(&device, 1, &layout.descriptors_count(),
+                                                 false).unwrap();
+
+        assert_eq!(layout.num_dynamic_offsets(), 3);
+        assert_eq!(layout.dynamic_descriptors(), &[
+            DynamicDescriptor { binding: 0, array_count: 1, storage: false },
+            DynamicDescriptor { binding: 1, array_count: 2, storage: true },
+        ]);
+
+        let limits = device.physical_device().limits();
+        let uniform_alignment = limits.min_uniform_buffer_offset_alignment();
+        let alignment = cmp::max(uniform_alignment,
+                                 limits.min_storage_buffer_offset_alignment()) as usize;
+
+        let usage = BufferUsage { uniform_buffer: true, storage_buffer: true,
+                                  .. BufferUsage::none() };
+        let buffer = CpuAccessibleBuffer::<[u8]>::array(&device, alignment * 4, &usage,
+                                                        Some(queue.family())).unwrap();
+        let slice = || BufferSlice::from(&buffer).slice(0 .. alignment).unwrap();
+
+        unsafe {
+            let mut set = pool.alloc(iter::once(&layout)).unwrap().next().unwrap();
+
+            set.write(vec![
+                DescriptorWrite::dynamic_uniform_buffer(0, slice()),
+                DescriptorWrite::dynamic_storage_buffer(1, slice()),
+                DescriptorWrite::dynamic_storage_buffer(1, slice()).array_element(1),
+            ]);
+
+            let alignment = alignment as u32;
+
+            assert_eq!(check_dynamic_offsets(iter::once(&set), &[0, alignment, alignment * 3]),
+                       Ok(()));
+
+            assert_eq!(check_dynamic_offsets(iter::once(&set), &[0, 0]),
+                       Err(DynamicOffsetsError::WrongCount { expected: 3, obtained: 2 }));
+
+            match check_dynamic_offsets(iter::once(&set), &[0, 0, alignment * 4]) {
+                Err(DynamicOffsetsError::OutOfBufferRange { set_num: 0, binding: 1, .. }) => (),
+                r => panic!("{:?}", r)
+            };
+
+            if uniform_alignment > 1 {
+                assert_eq!(check_dynamic_offsets(iter::once(&set), &[1, 0, 0]),
+                           Err(DynamicOffsetsError::UnalignedOffset {
+                               set_num: 0,
+                               binding: 0,
+                               offset: 1,
+                               alignment: uniform_alignment,
+                           }));
+            }
+        }
+    }
+
+    #[test]
+    fn immutable_sampler_image_write() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let sampler = linear_sampler(&device);
+        let layout = immutable_sampler_layout(&device, &sampler);
+        let mut pool = UnsafeDescriptorPool::new(&device, 1, &layout.descriptors_count(),
+                                                 false).unwrap();
+
+        let image = StorageImage::with_usage(&device, Dimensions::Dim2d { width: 4, height: 4 },
+                                             format::R8G8B8A8Unorm,
+                                             ImageUsage { sampled: true, .. ImageUsage::none() },
+                                             Some(queue.family())).unwrap();
+
+        unsafe {
+            let mut set = pool.alloc(iter::once(&layout)).unwrap().next().unwrap();
+            set.write(iter::once(DescriptorWrite::combined_image_immutable_sampler(0, &image)));
+            assert_eq!(set.images_list().len(), 1);
+        }
+    }
+
+    #[test]
+    #[should_panic = "Can't write a sampler to a binding that has immutable samplers"]
+    fn immutable_sampler_rejects_sampler_write() {
+        let (device, _) = gfx_dev_and_queue!();
+        let sampler = linear_sampler(&device);
+        let layout = immutable_sampler_layout(&device, &sampler);
+        let mut pool = UnsafeDescriptorPool::new(&device, 1, &layout.descriptors_count(),
+                                                 false).unwrap();
+
+        unsafe {
+            let mut set = pool.alloc(iter::once(&layout)).unwrap().next().unwrap();
+            set.write(iter::once(DescriptorWrite::sampler(1, &sampler)));
+        }
+    }
+
+    fn linear_sampler(device: &Arc<Device>) -> Arc<sampler::Sampler> {
+        sampler::Sampler::new(device, sampler::Filter::Linear, sampler::Filter::Linear,
+                              sampler::MipmapMode::Nearest,
+                              sampler::SamplerAddressMode::Repeat,
+                              sampler::SamplerAddressMode::Repeat,
+                              sampler::SamplerAddressMode::Repeat, 0.0, 1.0, 0.0, 1.0).unwrap()
+    }
+
+    // Layout with a combined image sampler at binding 0 and a sampler at binding 1, both using
+    // `sampler` as immutable sampler.
+    fn immutable_sampler_layout(device: &Arc<Device>, sampler: &Arc<sampler::Sampler>)
+                                -> Arc<UnsafeDescriptorSetLayout>
+    {
+        let combined = DescriptorDesc {
+            binding: 0,
+            ty: DescriptorDescTy::CombinedImageSampler(DescriptorImageDesc {
+                sampled: true,
+                dimensions: DescriptorImageDescDimensions::TwoDimensional,
+                format: None,
+                multisampled: false,
+                array_layers: DescriptorImageDescArray::NonArrayed,
+            }),
+            array_count: 1,
+            stages: ShaderStages::all(),
+            readonly: true,
+            immutable_samplers: Some(vec![sampler.clone()]),
+        };
+
+        let sampler = DescriptorDesc {
+            binding: 1,
+            ty: DescriptorDescTy::Sampler,
+            array_count: 1,
+            stages: ShaderStages::all(),
+            readonly: true,
+            immutable_samplers: Some(vec![sampler.clone()]),
+        };
+
+        UnsafeDescriptorSetLayout::new(device, vec![Some(combined), Some(sampler)])
+    }
+
+    // Layout with a dynamic uniform buffer at binding 0, an array of two dynamic storage buffers
+    // at binding 1, and a uniform buffer at binding 2.
+    fn dynamic_layout(device: &Arc<Device>) -> Arc<UnsafeDescriptorSetLayout> {
+        let desc = |binding, storage, dynamic, array_count| DescriptorDesc {
+            binding: binding,
+            ty: DescriptorDescTy::Buffer(DescriptorBufferDesc {
+                dynamic: Some(dynamic),
+                storage: storage,
+            }),
+            array_count: array_count,
+            stages: ShaderStages::all(),
+            readonly: true,
+            immutable_samplers: None,
+        };
+
+        UnsafeDescriptorSetLayout::new(device, vec![Some(desc(0, false, true, 1)),
+                                                    Some(desc(1, true, true, 2)),
+                                                    Some(desc(2, false, false, 1))])
+    }
+
+    // Layout with a uniform buffer at binding 0, an array of two uniform buffers at binding 1,
+    // and a storage buffer at binding 2.
+    fn copy_layout(device: &Arc<Device>) -> Arc<UnsafeDescriptorSetLayout> {
+        let desc = |binding, storage, array_count| DescriptorDesc {
+            binding: binding,
+            ty: DescriptorDescTy::Buffer(DescriptorBufferDesc {
+                dynamic: Some(false),
+                storage: storage,
+            }),
+            array_count: array_count,
+            stages: ShaderStages::all(),
+            readonly: true,
+            immutable_samplers: None,
+        };
+
+        UnsafeDescriptorSetLayout::new(device, vec![Some(desc(0, false, 1)),
+                                                    Some(desc(1, false, 2)),
+                                                    Some(desc(2, true, 1))])
+    }
+}
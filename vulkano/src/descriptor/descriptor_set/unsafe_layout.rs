@@ -7,18 +7,26 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
+use std::error;
+use std::fmt;
 use std::mem;
 use std::ptr;
 use std::sync::Arc;
 use smallvec::SmallVec;
 
 use check_errors;
+use Error;
 use OomError;
 use VulkanObject;
 use VulkanPointers;
 use vk;
 
+use descriptor::descriptor::DescriptorBufferDesc;
 use descriptor::descriptor::DescriptorDesc;
+use descriptor::descriptor::DescriptorDescTy;
+use descriptor::descriptor::DescriptorType;
+use descriptor::descriptor::descriptors_identical;
+use descriptor::descriptor_set::DescriptorsCount;
 use device::Device;
 
 /// Describes to the Vulkan implementation the layout of all descriptors within a descriptor set.
@@ -28,23 +36,75 @@ use device::Device;
 pub struct UnsafeDescriptorSetLayout {
     layout: vk::DescriptorSetLayout,
     device: Arc<Device>,
+    descriptors: SmallVec<[DescriptorDesc; 32]>,
+    // The dynamic buffer descriptors of the layout, ordered by binding.
+    dynamic_descriptors: SmallVec<[DynamicDescriptor; 4]>,
+}
+
+/// Dynamic buffer descriptor of a descriptor set layout.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DynamicDescriptor {
+    /// Binding of the descriptor.
+    pub binding: u32,
+    /// Number of array elements of the descriptor. Each element requires one dynamic offset.
+    pub array_count: u32,
+    /// True for a storage buffer, false for a uniform buffer.
+    pub storage: bool,
 }
 
 impl UnsafeDescriptorSetLayout {
     /// See the docs of new().
-    pub fn raw<I>(device: &Arc<Device>, descriptors: I)
-                  -> Result<UnsafeDescriptorSetLayout, OomError>
-        where I: IntoIterator<Item = DescriptorDesc>
+    pub fn raw<I>(device: &Arc<Device>, bindings: I)
+                  -> Result<UnsafeDescriptorSetLayout, DescriptorSetLayoutCreationError>
+        where I: IntoIterator<Item = Option<DescriptorDesc>>
     {
         let vk = device.pointers();
 
-        let bindings = descriptors.into_iter().map(|desc| {
+        let descriptors = bindings.into_iter().enumerate().filter_map(|(binding, desc)| {
+            desc.map(|desc| {
+                assert_eq!(desc.binding, binding as u32,
+                           "The binding of a descriptor doesn't match its position in the list");
+                desc
+            })
+        }).collect::<SmallVec<[_; 32]>>();
+
+        let limits = DescriptorLimits::from_device(device);
+        try!(check_descriptor_limits(&limits, descriptors.iter()));
+
+        // The raw handles of the immutable samplers of each descriptor. This list must not be
+        // modified once we start taking pointers to its content below.
+        let immutable_samplers = descriptors.iter().map(|desc| {
+            let samplers = match desc.immutable_samplers {
+                Some(ref s) => s,
+                None => return None
+            };
+
+            match desc.ty.ty() {
+                Some(DescriptorType::Sampler) | Some(DescriptorType::CombinedImageSampler) => (),
+                _ => panic!("Immutable samplers can only be used with sampler and combined image \
+                             sampler descriptors")
+            };
+            assert_eq!(samplers.len(), desc.array_count as usize,
+                       "The number of immutable samplers doesn't match the array count of the \
+                        descriptor");
+
+            Some(samplers.iter().map(|sampler| {
+                assert_eq!(&**sampler.device() as *const Device, &**device as *const Device,
+                           "An immutable sampler doesn't belong to the same device as the layout");
+                sampler.internal_object()
+            }).collect::<Vec<_>>())
+        }).collect::<SmallVec<[_; 32]>>();
+
+        let bindings = descriptors.iter().zip(immutable_samplers.iter()).map(|(desc, samplers)| {
             vk::DescriptorSetLayoutBinding {
                 binding: desc.binding,
                 descriptorType: desc.ty.ty().unwrap() /* TODO: shouldn't panic */ as u32,
                 descriptorCount: desc.array_count,
                 stageFlags: desc.stages.into(),
-                pImmutableSamplers: ptr::null(),        // FIXME: not yet implemented
+                pImmutableSamplers: match *samplers {
+                    Some(ref s) => s.as_ptr(),
+                    None => ptr::null()
+                },
             }
         }).collect::<SmallVec<[_; 32]>>();
 
@@ -63,23 +123,47 @@ impl UnsafeDescriptorSetLayout {
             output
         };
 
+        let mut dynamic_descriptors = descriptors.iter().filter_map(|desc| {
+            let storage = match desc.ty.ty() {
+                Some(DescriptorType::UniformBufferDynamic) => false,
+                Some(DescriptorType::StorageBufferDynamic) => true,
+                _ => return None
+            };
+
+            Some(DynamicDescriptor {
+                binding: desc.binding,
+                array_count: desc.array_count,
+                storage: storage,
+            })
+        }).collect::<SmallVec<[_; 4]>>();
+        dynamic_descriptors.sort_by_key(|d| d.binding);
+
         Ok(UnsafeDescriptorSetLayout {
             layout: layout,
             device: device.clone(),
+            descriptors: descriptors,
+            dynamic_descriptors: dynamic_descriptors,
         })
     }
     
-    /// Builds a new `UnsafeDescriptorSetLayout` with the given descriptors.
+    /// Builds a new `UnsafeDescriptorSetLayout` with the given bindings.
+    ///
+    /// The element at index `n` of `bindings` describes binding `n` of the layout. A `None`
+    /// element means that there is no descriptor at this binding, which allows leaving gaps
+    /// between binding numbers.
     ///
     /// # Panic
     ///
+    /// - Panicks if the `binding` of a descriptor doesn't match its index in `bindings`.
     /// - Panicks if the device or host ran out of memory.
+    /// - Panicks if the number of descriptors exceeds one of the limits of the device. Use
+    ///   `raw()` to get an error instead.
     ///
     #[inline]
-    pub fn new<I>(device: &Arc<Device>, descriptors: I) -> Arc<UnsafeDescriptorSetLayout>
-        where I: IntoIterator<Item = DescriptorDesc>
+    pub fn new<I>(device: &Arc<Device>, bindings: I) -> Arc<UnsafeDescriptorSetLayout>
+        where I: IntoIterator<Item = Option<DescriptorDesc>>
     {
-        Arc::new(UnsafeDescriptorSetLayout::raw(device, descriptors).unwrap())
+        Arc::new(UnsafeDescriptorSetLayout::raw(device, bindings).unwrap())
     }
 
     /// Returns the device used to create this layout.
@@ -87,8 +171,59 @@ impl UnsafeDescriptorSetLayout {
     pub fn device(&self) -> &Arc<Device> {
         &self.device
     }
+
+    /// Returns the descriptors this layout was created with.
+    #[inline]
+    pub fn descriptors(&self) -> &[DescriptorDesc] {
+        &self.descriptors
+    }
+
+    /// Returns the descriptor at the given binding, or `None` if there is no descriptor at this
+    /// binding.
+    #[inline]
+    pub fn descriptor(&self, binding: u32) -> Option<&DescriptorDesc> {
+        self.descriptors.iter().find(|d| d.binding == binding)
+    }
+
+    /// Returns the dynamic uniform and storage buffer descriptors of this layout, ordered by
+    /// binding.
+    ///
+    /// This is the order in which the dynamic offsets have to be passed when binding a
+    /// descriptor set with this layout.
+    #[inline]
+    pub fn dynamic_descriptors(&self) -> &[DynamicDescriptor] {
+        &self.dynamic_descriptors
+    }
+
+    /// Returns the number of dynamic offsets that must be passed when binding a descriptor set
+    /// with this layout.
+    #[inline]
+    pub fn num_dynamic_offsets(&self) -> usize {
+        self.dynamic_descriptors.iter().fold(0, |acc, d| acc + d.array_count as usize)
+    }
+
+    /// Returns the number of descriptors of each type that a descriptor set with this layout
+    /// contains.
+    ///
+    /// This can be used to compute the size of a descriptor pool.
+    pub fn descriptors_count(&self) -> DescriptorsCount {
+        let mut count = DescriptorsCount::zero();
+        for desc in self.descriptors.iter() {
+            count.add_num(desc.ty.ty().unwrap(), desc.array_count);
+        }
+        count
+    }
+
+    /// Returns true if the two layouts are defined identically, in which case a descriptor set
+    /// created with one of them can be used where the other one is expected.
+    #[inline]
+    pub fn is_identical_to(&self, other: &UnsafeDescriptorSetLayout) -> bool {
+        self.layout == other.layout || descriptors_identical(&self.descriptors, &other.descriptors)
+    }
 }
 
+impl_debug_handle!(UnsafeDescriptorSetLayout);
+
 unsafe impl VulkanObject for UnsafeDescriptorSetLayout {
     type Object = vk::DescriptorSetLayout;
 
@@ -109,14 +244,391 @@ impl Drop for UnsafeDescriptorSetLayout {
     }
 }
 
+/// Limits of a device that restrict the number of descriptors of descriptor set layouts and
+/// pipeline layouts. Each field has the same meaning as the method of `Limits` of the same name.
+#[doc(hidden)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DescriptorLimits {
+    pub max_bound_descriptor_sets: u32,
+    pub max_per_stage_descriptor_samplers: u32,
+    pub max_per_stage_descriptor_uniform_buffers: u32,
+    pub max_per_stage_descriptor_storage_buffers: u32,
+    pub max_per_stage_descriptor_sampled_images: u32,
+    pub max_per_stage_descriptor_storage_images: u32,
+    pub max_per_stage_descriptor_input_attachments: u32,
+    pub max_per_stage_resources: u32,
+    pub max_descriptor_set_samplers: u32,
+    pub max_descriptor_set_uniform_buffers: u32,
+    pub max_descriptor_set_uniform_buffers_dynamic: u32,
+    pub max_descriptor_set_storage_buffers: u32,
+    pub max_descriptor_set_storage_buffers_dynamic: u32,
+    pub max_descriptor_set_sampled_images: u32,
+    pub max_descriptor_set_storage_images: u32,
+    pub max_descriptor_set_input_attachments: u32,
+}
+
+impl DescriptorLimits {
+    /// Reads the limits of the physical device of `device`.
+    pub fn from_device(device: &Device) -> DescriptorLimits {
+        let limits = device.physical_device().limits();
+
+        DescriptorLimits {
+            max_bound_descriptor_sets: limits.max_bound_descriptor_sets(),
+            max_per_stage_descriptor_samplers: limits.max_per_stage_descriptor_samplers(),
+            max_per_stage_descriptor_uniform_buffers:
+                limits.max_per_stage_descriptor_uniform_buffers(),
+            max_per_stage_descriptor_storage_buffers:
+                limits.max_per_stage_descriptor_storage_buffers(),
+            max_per_stage_descriptor_sampled_images:
+                limits.max_per_stage_descriptor_sampled_images(),
+            max_per_stage_descriptor_storage_images:
+                limits.max_per_stage_descriptor_storage_images(),
+            max_per_stage_descriptor_input_attachments:
+                limits.max_per_stage_descriptor_input_attachments(),
+            max_per_stage_resources: limits.max_per_stage_resources(),
+            max_descriptor_set_samplers: limits.max_descriptor_set_samplers(),
+            max_descriptor_set_uniform_buffers: limits.max_descriptor_set_uniform_buffers(),
+            max_descriptor_set_uniform_buffers_dynamic:
+                limits.max_descriptor_set_uniform_buffers_dynamic(),
+            max_descriptor_set_storage_buffers: limits.max_descriptor_set_storage_buffers(),
+            max_descriptor_set_storage_buffers_dynamic:
+                limits.max_descriptor_set_storage_buffers_dynamic(),
+            max_descriptor_set_sampled_images: limits.max_descriptor_set_sampled_images(),
+            max_descriptor_set_storage_images: limits.max_descriptor_set_storage_images(),
+            max_descriptor_set_input_attachments: limits.max_descriptor_set_input_attachments(),
+        }
+    }
+}
+
+/// A limit of `DescriptorLimits` that is exceeded.
+#[doc(hidden)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DescriptorLimitExceeded {
+    /// Name of the limit, as found in `Limits`.
+    pub limit: &'static str,
+    /// Value of the limit.
+    pub max: u32,
+    /// Number of descriptors that were requested.
+    pub requested: u32,
+}
+
+/// Checks the number of descriptors of `descriptors` against the `max_descriptor_set_*` limits,
+/// and the number of descriptors of each shader stage against the `max_per_stage_*` limits.
+///
+/// These limits apply to the descriptors of all the sets of a pipeline layout, and thus also to
+/// the descriptors of each set layout.
+#[doc(hidden)]
+pub fn check_descriptor_limits<'a, I>(limits: &DescriptorLimits, descriptors: I)
+                                      -> Result<(), DescriptorLimitExceeded>
+    where I: IntoIterator<Item = &'a DescriptorDesc>
+{
+    let mut total = DescriptorsCount::zero();
+    // One count for each of vertex, tessellation control, tessellation evaluation, geometry,
+    // fragment and compute.
+    let mut per_stage = [DescriptorsCount::zero(); 6];
+
+    for desc in descriptors {
+        let ty = match desc.ty.ty() {
+            Some(ty) => ty,
+            // A buffer that isn't known to be dynamic yet counts as a non-dynamic buffer. Both
+            // kinds count towards the limits on all buffers, and the dynamic limits are checked
+            // once the type is known.
+            None => match desc.ty {
+                DescriptorDescTy::Buffer(DescriptorBufferDesc { storage: true, .. }) => {
+                    DescriptorType::StorageBuffer
+                },
+                _ => DescriptorType::UniformBuffer,
+            },
+        };
+        total.add_num(ty, desc.array_count);
+
+        let stages = [desc.stages.vertex, desc.stages.tessellation_control,
+                      desc.stages.tessellation_evaluation, desc.stages.geometry,
+                      desc.stages.fragment, desc.stages.compute];
+        for (count, &enabled) in per_stage.iter_mut().zip(stages.iter()) {
+            if enabled {
+                count.add_num(ty, desc.array_count);
+            }
+        }
+    }
+
+    let set_limits = [
+        ("max_descriptor_set_samplers", limits.max_descriptor_set_samplers,
+         total.sampler + total.combined_image_sampler),
+        ("max_descriptor_set_uniform_buffers", limits.max_descriptor_set_uniform_buffers,
+         total.uniform_buffer + total.uniform_buffer_dynamic),
+        ("max_descriptor_set_uniform_buffers_dynamic",
+         limits.max_descriptor_set_uniform_buffers_dynamic, total.uniform_buffer_dynamic),
+        ("max_descriptor_set_storage_buffers", limits.max_descriptor_set_storage_buffers,
+         total.storage_buffer + total.storage_buffer_dynamic),
+        ("max_descriptor_set_storage_buffers_dynamic",
+         limits.max_descriptor_set_storage_buffers_dynamic, total.storage_buffer_dynamic),
+        ("max_descriptor_set_sampled_images", limits.max_descriptor_set_sampled_images,
+         total.sampled_image + total.combined_image_sampler + total.uniform_texel_buffer),
+        ("max_descriptor_set_storage_images", limits.max_descriptor_set_storage_images,
+         total.storage_image + total.storage_texel_buffer),
+        ("max_descriptor_set_input_attachments", limits.max_descriptor_set_input_attachments,
+         total.input_attachment),
+    ];
+
+    for &(limit, max, requested) in set_limits.iter() {
+        if requested > max {
+            return Err(DescriptorLimitExceeded {
+                limit: limit,
+                max: max,
+                requested: requested,
+            });
+        }
+    }
+
+    for count in per_stage.iter() {
+        let stage_limits = [
+            ("max_per_stage_descriptor_samplers", limits.max_per_stage_descriptor_samplers,
+             count.sampler + count.combined_image_sampler),
+            ("max_per_stage_descriptor_uniform_buffers",
+             limits.max_per_stage_descriptor_uniform_buffers,
+             count.uniform_buffer + count.uniform_buffer_dynamic),
+            ("max_per_stage_descriptor_storage_buffers",
+             limits.max_per_stage_descriptor_storage_buffers,
+             count.storage_buffer + count.storage_buffer_dynamic),
+            ("max_per_stage_descriptor_sampled_images",
+             limits.max_per_stage_descriptor_sampled_images,
+             count.sampled_image + count.combined_image_sampler + count.uniform_texel_buffer),
+            ("max_per_stage_descriptor_storage_images",
+             limits.max_per_stage_descriptor_storage_images,
+             count.storage_image + count.storage_texel_buffer),
+            ("max_per_stage_descriptor_input_attachments",
+             limits.max_per_stage_descriptor_input_attachments, count.input_attachment),
+            ("max_per_stage_resources", limits.max_per_stage_resources,
+             count.uniform_buffer + count.uniform_buffer_dynamic + count.storage_buffer +
+             count.storage_buffer_dynamic + count.sampled_image + count.combined_image_sampler +
+             count.uniform_texel_buffer + count.storage_image + count.storage_texel_buffer +
+             count.input_attachment),
+        ];
+
+        for &(limit, max, requested) in stage_limits.iter() {
+            if requested > max {
+                return Err(DescriptorLimitExceeded {
+                    limit: limit,
+                    max: max,
+                    requested: requested,
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Error that can happen when creating a descriptor set layout.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DescriptorSetLayoutCreationError {
+    /// Not enough memory.
+    OomError(OomError),
+
+    /// The number of descriptors exceeds one of the limits of the device.
+    MaxDescriptorsLimitExceeded {
+        /// Name of the limit, as found in `Limits`.
+        limit: &'static str,
+        /// Value of the limit.
+        max: u32,
+        /// Number of descriptors that were requested.
+        requested: u32,
+    },
+
+    /// The Vulkan implementation returned an error that this operation isn't supposed to return.
+    Unexpected(Error),
+}
+
+impl error::Error for DescriptorSetLayoutCreationError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            DescriptorSetLayoutCreationError::OomError(_) => {
+                "not enough memory available"
+            },
+            DescriptorSetLayoutCreationError::MaxDescriptorsLimitExceeded { .. } => {
+                "the number of descriptors exceeds one of the limits of the device"
+            },
+            DescriptorSetLayoutCreationError::Unexpected(_) => {
+                "unexpected error returned by the Vulkan implementation"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            DescriptorSetLayoutCreationError::OomError(ref err) => Some(err),
+            DescriptorSetLayoutCreationError::Unexpected(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for DescriptorSetLayoutCreationError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<DescriptorLimitExceeded> for DescriptorSetLayoutCreationError {
+    #[inline]
+    fn from(err: DescriptorLimitExceeded) -> DescriptorSetLayoutCreationError {
+        DescriptorSetLayoutCreationError::MaxDescriptorsLimitExceeded {
+            limit: err.limit,
+            max: err.max,
+            requested: err.requested,
+        }
+    }
+}
+
+impl From<OomError> for DescriptorSetLayoutCreationError {
+    #[inline]
+    fn from(err: OomError) -> DescriptorSetLayoutCreationError {
+        DescriptorSetLayoutCreationError::OomError(err)
+    }
+}
+
+impl From<Error> for DescriptorSetLayoutCreationError {
+    #[inline]
+    fn from(err: Error) -> DescriptorSetLayoutCreationError {
+        match err {
+            err @ Error::OutOfHostMemory => {
+                DescriptorSetLayoutCreationError::OomError(OomError::from(err))
+            },
+            err @ Error::OutOfDeviceMemory => {
+                DescriptorSetLayoutCreationError::OomError(OomError::from(err))
+            },
+            _ => DescriptorSetLayoutCreationError::Unexpected(err)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::iter;
+    use descriptor::descriptor::DescriptorBufferDesc;
+    use descriptor::descriptor::DescriptorDesc;
+    use descriptor::descriptor::DescriptorDescTy;
+    use descriptor::descriptor::DescriptorType;
+    use descriptor::descriptor::ShaderStages;
+    use descriptor::descriptor_set::DescriptorSetLayoutCreationError;
+    use descriptor::descriptor_set::unsafe_layout::DescriptorLimits;
     use descriptor::descriptor_set::unsafe_layout::UnsafeDescriptorSetLayout;
+    use descriptor::descriptor_set::unsafe_layout::check_descriptor_limits;
+    use sampler::Filter;
+    use sampler::MipmapMode;
+    use sampler::Sampler;
+    use sampler::SamplerAddressMode;
 
     #[test]
     fn empty() {
         let (device, _) = gfx_dev_and_queue!();
         let _layout = UnsafeDescriptorSetLayout::new(&device, iter::empty());
     }
+
+    #[test]
+    fn gap_and_array() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let sampler = |binding, array_count| DescriptorDesc {
+            binding: binding,
+            ty: DescriptorDescTy::Sampler,
+            array_count: array_count,
+            stages: ShaderStages::all(),
+            readonly: true,
+            immutable_samplers: None,
+        };
+
+        let layout = UnsafeDescriptorSetLayout::new(&device, vec![Some(sampler(0, 1)), None,
+                                                                  Some(sampler(2, 4))]);
+
+        let bindings = layout.descriptors().iter().map(|d| (d.binding, d.array_count))
+                             .collect::<Vec<_>>();
+        assert_eq!(bindings, vec![(0, 1), (2, 4)]);
+
+        assert!(layout.descriptor(1).is_none(), "Binding 1 should be a gap");
+        let desc = layout.descriptor(2).unwrap();
+        assert_eq!(desc.ty.ty(), Some(DescriptorType::Sampler));
+        assert_eq!(desc.array_count, 4);
+        assert_eq!(layout.descriptors_count().sampler, 5);
+    }
+
+    #[test]
+    #[should_panic = "The binding of a descriptor doesn't match its position in the list"]
+    fn wrong_binding() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let _ = UnsafeDescriptorSetLayout::raw(&device, vec![None, Some(DescriptorDesc {
+            binding: 0,
+            ty: DescriptorDescTy::Sampler,
+            array_count: 1,
+            stages: ShaderStages::all(),
+            readonly: true,
+            immutable_samplers: None,
+        })]);
+    }
+
+    #[test]
+    #[should_panic = "The number of immutable samplers doesn't match the array count of the \
+                      descriptor"]
+    fn immutable_samplers_count_mismatch() {
+        let (device, _) = gfx_dev_and_queue!();
+        let sampler = Sampler::new(&device, Filter::Linear, Filter::Linear, MipmapMode::Nearest,
+                                   SamplerAddressMode::Repeat, SamplerAddressMode::Repeat,
+                                   SamplerAddressMode::Repeat, 0.0, 1.0, 0.0, 1.0).unwrap();
+
+        let _ = UnsafeDescriptorSetLayout::raw(&device, iter::once(Some(DescriptorDesc {
+            binding: 0,
+            ty: DescriptorDescTy::Sampler,
+            array_count: 2,
+            stages: ShaderStages::all(),
+            readonly: true,
+            immutable_samplers: Some(vec![sampler]),
+        })));
+    }
+
+    #[test]
+    fn too_many_samplers() {
+        let (device, _) = gfx_dev_and_queue!();
+        let max = device.physical_device().limits().max_descriptor_set_samplers();
+
+        let desc = DescriptorDesc {
+            binding: 0,
+            ty: DescriptorDescTy::Sampler,
+            array_count: max + 1,
+            stages: ShaderStages::compute(),
+            readonly: true,
+            immutable_samplers: None,
+        };
+
+        match UnsafeDescriptorSetLayout::raw(&device, iter::once(Some(desc))) {
+            Err(DescriptorSetLayoutCreationError::MaxDescriptorsLimitExceeded {
+                limit: "max_descriptor_set_samplers", requested, ..
+            }) => assert_eq!(requested, max + 1),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn limits_unknown_dynamic_buffer() {
+        let (device, _) = gfx_dev_and_queue!();
+        let limits = DescriptorLimits::from_device(&device);
+        let max = limits.max_descriptor_set_storage_buffers;
+
+        let desc = DescriptorDesc {
+            binding: 0,
+            ty: DescriptorDescTy::Buffer(DescriptorBufferDesc { dynamic: None, storage: true }),
+            array_count: max + 1,
+            stages: ShaderStages::compute(),
+            readonly: true,
+            immutable_samplers: None,
+        };
+
+        let err = check_descriptor_limits(&limits, iter::once(&desc)).unwrap_err();
+        assert_eq!(err.limit, "max_descriptor_set_storage_buffers");
+        assert_eq!(err.requested, max + 1);
+    }
 }
@@ -0,0 +1,861 @@
+// Copyright (c) 2016 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::error;
+use std::cmp;
+use std::fmt;
+use std::mem;
+use std::ops;
+use std::ptr;
+use std::sync::Arc;
+use std::vec::IntoIter as VecIntoIter;
+use smallvec::SmallVec;
+
+use check_errors;
+use Error;
+use OomError;
+use VulkanObject;
+use VulkanPointers;
+use vk;
+
+use descriptor::descriptor::DescriptorType;
+use descriptor::descriptor_set::UnsafeDescriptorSet;
+use descriptor::descriptor_set::UnsafeDescriptorSetLayout;
+use device::Device;
+
+/// Number of available descriptors slots in a pool, one value per descriptor type.
+///
+/// The number of descriptors required by a descriptor set layout can be obtained with
+/// `UnsafeDescriptorSetLayout::descriptors_count()`. These values can then be added together and
+/// multiplied in order to size a pool:
+///
+/// ```ignore
+/// let count = layout_a.descriptors_count() * 100 + layout_b.descriptors_count() * 10;
+/// let pool = UnsafeDescriptorPool::new(&device, 110, &count, false).unwrap();
+/// ```
+///
+/// The additions and multiplications saturate at `u32::MAX` instead of overflowing.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct DescriptorsCount {
+    pub uniform_buffer: u32,
+    pub storage_buffer: u32,
+    pub uniform_buffer_dynamic: u32,
+    pub storage_buffer_dynamic: u32,
+    pub uniform_texel_buffer: u32,
+    pub storage_texel_buffer: u32,
+    pub sampled_image: u32,
+    pub storage_image: u32,
+    pub sampler: u32,
+    pub combined_image_sampler: u32,
+    pub input_attachment: u32,
+}
+
+impl DescriptorsCount {
+    /// Returns a `DescriptorsCount` object with all fields set to 0.
+    #[inline]
+    pub fn zero() -> DescriptorsCount {
+        DescriptorsCount {
+            uniform_buffer: 0,
+            storage_buffer: 0,
+            uniform_buffer_dynamic: 0,
+            storage_buffer_dynamic: 0,
+            uniform_texel_buffer: 0,
+            storage_texel_buffer: 0,
+            sampled_image: 0,
+            storage_image: 0,
+            sampler: 0,
+            combined_image_sampler: 0,
+            input_attachment: 0,
+        }
+    }
+
+    /// Returns the number of descriptors of the given type.
+    #[inline]
+    pub fn get(&self, ty: DescriptorType) -> u32 {
+        match ty {
+            DescriptorType::UniformBuffer => self.uniform_buffer,
+            DescriptorType::StorageBuffer => self.storage_buffer,
+            DescriptorType::UniformBufferDynamic => self.uniform_buffer_dynamic,
+            DescriptorType::StorageBufferDynamic => self.storage_buffer_dynamic,
+            DescriptorType::UniformTexelBuffer => self.uniform_texel_buffer,
+            DescriptorType::StorageTexelBuffer => self.storage_texel_buffer,
+            DescriptorType::SampledImage => self.sampled_image,
+            DescriptorType::StorageImage => self.storage_image,
+            DescriptorType::Sampler => self.sampler,
+            DescriptorType::CombinedImageSampler => self.combined_image_sampler,
+            DescriptorType::InputAttachment => self.input_attachment,
+        }
+    }
+
+    /// Adds `num` descriptors of the given type.
+    #[inline]
+    pub fn add_num(&mut self, ty: DescriptorType, num: u32) {
+        let count = match ty {
+            DescriptorType::UniformBuffer => &mut self.uniform_buffer,
+            DescriptorType::StorageBuffer => &mut self.storage_buffer,
+            DescriptorType::UniformBufferDynamic => &mut self.uniform_buffer_dynamic,
+            DescriptorType::StorageBufferDynamic => &mut self.storage_buffer_dynamic,
+            DescriptorType::UniformTexelBuffer => &mut self.uniform_texel_buffer,
+            DescriptorType::StorageTexelBuffer => &mut self.storage_texel_buffer,
+            DescriptorType::SampledImage => &mut self.sampled_image,
+            DescriptorType::StorageImage => &mut self.storage_image,
+            DescriptorType::Sampler => &mut self.sampler,
+            DescriptorType::CombinedImageSampler => &mut self.combined_image_sampler,
+            DescriptorType::InputAttachment => &mut self.input_attachment,
+        };
+
+        *count = count.saturating_add(num);
+    }
+
+    /// Subtracts `other` from `self`. Returns `None` if one of the values of `other` is superior
+    /// to the corresponding value of `self`.
+    #[inline]
+    pub fn checked_sub(&self, other: &DescriptorsCount) -> Option<DescriptorsCount> {
+        if self.combine(other, cmp::min) != *other {
+            return None;
+        }
+
+        Some(self.combine(other, |a, b| a - b))
+    }
+
+    // Applies `f` to each pair of values of `self` and `other`.
+    #[inline]
+    fn combine<F>(&self, other: &DescriptorsCount, f: F) -> DescriptorsCount
+        where F: Fn(u32, u32) -> u32
+    {
+        DescriptorsCount {
+            uniform_buffer: f(self.uniform_buffer, other.uniform_buffer),
+            storage_buffer: f(self.storage_buffer, other.storage_buffer),
+            uniform_buffer_dynamic: f(self.uniform_buffer_dynamic, other.uniform_buffer_dynamic),
+            storage_buffer_dynamic: f(self.storage_buffer_dynamic, other.storage_buffer_dynamic),
+            uniform_texel_buffer: f(self.uniform_texel_buffer, other.uniform_texel_buffer),
+            storage_texel_buffer: f(self.storage_texel_buffer, other.storage_texel_buffer),
+            sampled_image: f(self.sampled_image, other.sampled_image),
+            storage_image: f(self.storage_image, other.storage_image),
+            sampler: f(self.sampler, other.sampler),
+            combined_image_sampler: f(self.combined_image_sampler, other.combined_image_sampler),
+            input_attachment: f(self.input_attachment, other.input_attachment),
+        }
+    }
+
+    // Builds the list of pool sizes to pass to `vkCreateDescriptorPool`. Types with a count of 0
+    // are skipped, as the specs don't allow them.
+    fn pool_sizes(&self) -> SmallVec<[vk::DescriptorPoolSize; 11]> {
+        const TYPES: [DescriptorType; 11] = [
+            DescriptorType::UniformBuffer, DescriptorType::StorageBuffer,
+            DescriptorType::UniformBufferDynamic, DescriptorType::StorageBufferDynamic,
+            DescriptorType::UniformTexelBuffer, DescriptorType::StorageTexelBuffer,
+            DescriptorType::SampledImage, DescriptorType::StorageImage, DescriptorType::Sampler,
+            DescriptorType::CombinedImageSampler, DescriptorType::InputAttachment,
+        ];
+
+        TYPES.iter().filter(|&&ty| self.get(ty) != 0).map(|&ty| {
+            vk::DescriptorPoolSize {
+                ty: ty as u32,
+                descriptorCount: self.get(ty),
+            }
+        }).collect()
+    }
+}
+
+impl ops::Add for DescriptorsCount {
+    type Output = DescriptorsCount;
+
+    #[inline]
+    fn add(self, rhs: DescriptorsCount) -> DescriptorsCount {
+        self.combine(&rhs, |a, b| a.saturating_add(b))
+    }
+}
+
+impl ops::AddAssign for DescriptorsCount {
+    #[inline]
+    fn add_assign(&mut self, rhs: DescriptorsCount) {
+        *self = *self + rhs;
+    }
+}
+
+impl ops::Mul<u32> for DescriptorsCount {
+    type Output = DescriptorsCount;
+
+    #[inline]
+    fn mul(self, rhs: u32) -> DescriptorsCount {
+        self.combine(&DescriptorsCount::zero(), |a, _| a.saturating_mul(rhs))
+    }
+}
+
+impl ops::MulAssign<u32> for DescriptorsCount {
+    #[inline]
+    fn mul_assign(&mut self, rhs: u32) {
+        *self = *self * rhs;
+    }
+}
+
+/// Low-level pool of descriptor sets.
+///
+/// Contrary to `DescriptorPool`, the descriptor sets allocated from this pool are not freed
+/// automatically when they are destroyed. Instead you must either free them with `free()`, which
+/// is only possible if the pool was created with `free_individual_sets` set to true, or reset
+/// the whole pool with `reset()`.
+pub struct UnsafeDescriptorPool {
+    pool: vk::DescriptorPool,
+    device: Arc<Device>,
+    max_sets: u32,
+    count: DescriptorsCount,
+    free_individual_sets: bool,
+    // Number of descriptor sets that are currently allocated from the pool.
+    num_allocated_sets: u32,
+}
+
+impl UnsafeDescriptorPool {
+    /// Initializes a new pool.
+    ///
+    /// Up to `max_sets` descriptor sets can be allocated from the pool at once, and the total
+    /// number of descriptors of each type in these sets can't exceed the values in `count`.
+    ///
+    /// If `free_individual_sets` is true, individual descriptor sets can be freed with `free()`.
+    /// Otherwise the only way to free descriptor sets is to reset the whole pool.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if `max_sets` is 0.
+    /// - Panicks if all the values of `count` are 0.
+    ///
+    pub fn new(device: &Arc<Device>, max_sets: u32, count: &DescriptorsCount,
+               free_individual_sets: bool) -> Result<UnsafeDescriptorPool, OomError>
+    {
+        let vk = device.pointers();
+
+        assert!(max_sets != 0, "The maximum number of sets can't be 0");
+
+        let pool_sizes = count.pool_sizes();
+        assert!(!pool_sizes.is_empty(), "All the descriptors count of a pool can't be 0");
+
+        let pool = unsafe {
+            let infos = vk::DescriptorPoolCreateInfo {
+                sType: vk::STRUCTURE_TYPE_DESCRIPTOR_POOL_CREATE_INFO,
+                pNext: ptr::null(),
+                flags: if free_individual_sets {
+                    vk::DESCRIPTOR_POOL_CREATE_FREE_DESCRIPTOR_SET_BIT
+                } else {
+                    0
+                },
+                maxSets: max_sets,
+                poolSizeCount: pool_sizes.len() as u32,
+                pPoolSizes: pool_sizes.as_ptr(),
+            };
+
+            let mut output = mem::uninitialized();
+            try!(check_errors(vk.CreateDescriptorPool(device.internal_object(), &infos,
+                                                      ptr::null(), &mut output)));
+            output
+        };
+
+        Ok(UnsafeDescriptorPool {
+            pool: pool,
+            device: device.clone(),
+            max_sets: max_sets,
+            count: *count,
+            free_individual_sets: free_individual_sets,
+            num_allocated_sets: 0,
+        })
+    }
+
+    /// Returns the device this pool was created from.
+    #[inline]
+    pub fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+
+    /// Returns the maximum number of descriptor sets that can be allocated from the pool.
+    #[inline]
+    pub fn max_sets(&self) -> u32 {
+        self.max_sets
+    }
+
+    /// Returns the number of descriptors of each type the pool was created with.
+    #[inline]
+    pub fn descriptors_count(&self) -> &DescriptorsCount {
+        &self.count
+    }
+
+    /// Returns true if individual descriptor sets can be freed with `free()`.
+    #[inline]
+    pub fn free_individual_sets(&self) -> bool {
+        self.free_individual_sets
+    }
+
+    /// Allocates one descriptor set for each layout of `layouts`.
+    ///
+    /// Either all the descriptor sets are allocated, or an error is returned and none of them
+    /// is.
+    ///
+    /// # Safety
+    ///
+    /// - The descriptor sets must not be used after they have been freed, after the pool has
+    ///   been reset, or after the pool has been destroyed.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if one of the layouts wasn't created with the same device as the pool.
+    ///
+    pub unsafe fn alloc<'l, I>(&mut self, layouts: I)
+                               -> Result<UnsafeDescriptorPoolAllocIter, DescriptorPoolAllocError>
+        where I: IntoIterator<Item = &'l Arc<UnsafeDescriptorSetLayout>>
+    {
+        let layouts = layouts.into_iter().map(|layout| {
+            assert_eq!(&**layout.device() as *const Device, &*self.device as *const Device,
+                       "The layout and the pool were not created with the same device");
+            layout.clone()
+        }).collect::<Vec<_>>();
+
+        if layouts.is_empty() {
+            return Ok(UnsafeDescriptorPoolAllocIter { sets: Vec::new().into_iter() });
+        }
+
+        // Exceeding the maximum number of sets isn't reported by all implementations, so we
+        // check it ourselves.
+        let num_sets = (self.num_allocated_sets as usize).checked_add(layouts.len());
+        if num_sets.map_or(true, |n| n > self.max_sets as usize) {
+            return Err(DescriptorPoolAllocError::OutOfPoolMemory);
+        }
+
+        let raw_layouts = layouts.iter().map(|l| l.internal_object())
+                                 .collect::<SmallVec<[_; 8]>>();
+
+        let infos = vk::DescriptorSetAllocateInfo {
+            sType: vk::STRUCTURE_TYPE_DESCRIPTOR_SET_ALLOCATE_INFO,
+            pNext: ptr::null(),
+            descriptorPool: self.pool,
+            descriptorSetCount: raw_layouts.len() as u32,
+            pSetLayouts: raw_layouts.as_ptr(),
+        };
+
+        let vk = self.device.pointers();
+        let mut output = Vec::with_capacity(raw_layouts.len());
+        let ret = vk.AllocateDescriptorSets(self.device.internal_object(), &infos,
+                                            output.as_mut_ptr());
+
+        match check_errors(ret) {
+            Ok(_) => (),
+            Err(Error::OutOfHostMemory) => {
+                return Err(DescriptorPoolAllocError::OutOfHostMemory);
+            },
+            Err(Error::OutOfDeviceMemory) => {
+                return Err(DescriptorPoolAllocError::OutOfDeviceMemory);
+            },
+            Err(Error::FragmentedPool) => {
+                return Err(DescriptorPoolAllocError::FragmentedPool);
+            },
+            Err(Error::OutOfPoolMemory) => {
+                return Err(DescriptorPoolAllocError::OutOfPoolMemory);
+            },
+            Err(err) => return Err(DescriptorPoolAllocError::Unexpected(err))
+        }
+
+        output.set_len(raw_layouts.len());
+        self.num_allocated_sets += layouts.len() as u32;
+
+        let sets = output.into_iter().zip(layouts.into_iter()).map(|(set, layout)| {
+            UnsafeDescriptorSet::from_unsafe_pool(set, layout)
+        }).collect::<Vec<_>>();
+
+        Ok(UnsafeDescriptorPoolAllocIter { sets: sets.into_iter() })
+    }
+
+    /// Frees descriptor sets that were allocated from this pool.
+    ///
+    /// Returns an error if the pool wasn't created with `free_individual_sets` set to true. The
+    /// descriptor sets are then still allocated, and can be reclaimed by resetting the pool.
+    ///
+    /// # Safety
+    ///
+    /// - The descriptor sets must have been allocated from this pool.
+    /// - The descriptor sets must not be in use by the GPU.
+    ///
+    pub unsafe fn free<I>(&mut self, descriptor_sets: I) -> Result<(), DescriptorPoolFreeError>
+        where I: IntoIterator<Item = UnsafeDescriptorSet>
+    {
+        if !self.free_individual_sets {
+            return Err(DescriptorPoolFreeError::IndividualFreeNotAllowed);
+        }
+
+        let sets = descriptor_sets.into_iter().map(|s| s.internal_object())
+                                  .collect::<SmallVec<[_; 8]>>();
+        if sets.is_empty() {
+            return Ok(());
+        }
+
+        let vk = self.device.pointers();
+        try!(check_errors(vk.FreeDescriptorSets(self.device.internal_object(), self.pool,
+                                                sets.len() as u32, sets.as_ptr())));
+
+        debug_assert!(self.num_allocated_sets >= sets.len() as u32);
+        self.num_allocated_sets -= sets.len() as u32;
+        Ok(())
+    }
+
+    /// Frees all the descriptor sets that were allocated from this pool.
+    ///
+    /// # Safety
+    ///
+    /// - None of the descriptor sets of the pool must be in use by the GPU.
+    /// - The `UnsafeDescriptorSet` objects that were allocated from the pool must no longer be
+    ///   used.
+    ///
+    pub unsafe fn reset(&mut self) -> Result<(), OomError> {
+        let vk = self.device.pointers();
+        try!(check_errors(vk.ResetDescriptorPool(self.device.internal_object(), self.pool,
+                                                 0 /* reserved */)));
+        self.num_allocated_sets = 0;
+        Ok(())
+    }
+}
+
+impl_debug_handle!(UnsafeDescriptorPool);
+
+unsafe impl VulkanObject for UnsafeDescriptorPool {
+    type Object = vk::DescriptorPool;
+
+    #[inline]
+    fn internal_object(&self) -> vk::DescriptorPool {
+        self.pool
+    }
+}
+
+impl Drop for UnsafeDescriptorPool {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe {
+            let vk = self.device.pointers();
+            vk.DestroyDescriptorPool(self.device.internal_object(), self.pool, ptr::null());
+        }
+    }
+}
+
+/// Wrapper around an `UnsafeDescriptorPool` that keeps track of the number of descriptors that
+/// are still available.
+///
+/// Allocations that are known not to fit in the pool are refused with
+/// `DescriptorPoolAllocError::OutOfPoolMemory` without calling the Vulkan implementation.
+pub struct TrackedDescriptorPool {
+    inner: UnsafeDescriptorPool,
+    // Number of descriptors that haven't been allocated yet.
+    remaining: DescriptorsCount,
+}
+
+impl TrackedDescriptorPool {
+    /// Initializes a new pool. See the docs of `UnsafeDescriptorPool::new()`.
+    #[inline]
+    pub fn new(device: &Arc<Device>, max_sets: u32, count: &DescriptorsCount,
+               free_individual_sets: bool) -> Result<TrackedDescriptorPool, OomError>
+    {
+        Ok(TrackedDescriptorPool {
+            inner: try!(UnsafeDescriptorPool::new(device, max_sets, count,
+                                                  free_individual_sets)),
+            remaining: *count,
+        })
+    }
+
+    /// Returns the wrapped pool.
+    #[inline]
+    pub fn inner(&self) -> &UnsafeDescriptorPool {
+        &self.inner
+    }
+
+    /// Returns the number of descriptors of each type that are still available.
+    #[inline]
+    pub fn remaining(&self) -> &DescriptorsCount {
+        &self.remaining
+    }
+
+    /// Allocates one descriptor set for each layout of `layouts`.
+    ///
+    /// Returns `DescriptorPoolAllocError::OutOfPoolMemory` if the pool doesn't have enough
+    /// descriptors left for all the layouts.
+    ///
+    /// # Safety
+    ///
+    /// Same as `UnsafeDescriptorPool::alloc()`.
+    ///
+    pub unsafe fn alloc<'l, I>(&mut self, layouts: I)
+                               -> Result<UnsafeDescriptorPoolAllocIter, DescriptorPoolAllocError>
+        where I: IntoIterator<Item = &'l Arc<UnsafeDescriptorSetLayout>>
+    {
+        let layouts = layouts.into_iter().collect::<Vec<_>>();
+
+        let needed = layouts.iter().fold(DescriptorsCount::zero(), |acc, layout| {
+            acc + layout.descriptors_count()
+        });
+
+        let remaining = match self.remaining.checked_sub(&needed) {
+            Some(r) => r,
+            None => return Err(DescriptorPoolAllocError::OutOfPoolMemory),
+        };
+
+        let sets = try!(self.inner.alloc(layouts.into_iter()));
+        self.remaining = remaining;
+        Ok(sets)
+    }
+
+    /// Frees descriptor sets that were allocated from this pool, and makes their descriptors
+    /// available again.
+    ///
+    /// # Safety
+    ///
+    /// Same as `UnsafeDescriptorPool::free()`.
+    ///
+    pub unsafe fn free<I>(&mut self, descriptor_sets: I) -> Result<(), DescriptorPoolFreeError>
+        where I: IntoIterator<Item = UnsafeDescriptorSet>
+    {
+        let sets = descriptor_sets.into_iter().collect::<Vec<_>>();
+
+        let freed = sets.iter().fold(DescriptorsCount::zero(), |acc, set| {
+            acc + set.layout().descriptors_count()
+        });
+
+        try!(self.inner.free(sets.into_iter()));
+        self.remaining += freed;
+        Ok(())
+    }
+
+    /// Frees all the descriptor sets that were allocated from this pool.
+    ///
+    /// # Safety
+    ///
+    /// Same as `UnsafeDescriptorPool::reset()`.
+    ///
+    #[inline]
+    pub unsafe fn reset(&mut self) -> Result<(), OomError> {
+        try!(self.inner.reset());
+        self.remaining = *self.inner.descriptors_count();
+        Ok(())
+    }
+}
+
+/// Iterator to the descriptor sets allocated from an `UnsafeDescriptorPool`.
+pub struct UnsafeDescriptorPoolAllocIter {
+    sets: VecIntoIter<UnsafeDescriptorSet>,
+}
+
+impl Iterator for UnsafeDescriptorPoolAllocIter {
+    type Item = UnsafeDescriptorSet;
+
+    #[inline]
+    fn next(&mut self) -> Option<UnsafeDescriptorSet> {
+        self.sets.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.sets.size_hint()
+    }
+}
+
+impl ExactSizeIterator for UnsafeDescriptorPoolAllocIter {
+}
+
+/// Error that can happen when allocating descriptor sets from a pool.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DescriptorPoolAllocError {
+    /// There is no memory available on the host (ie. the CPU, RAM, etc.).
+    OutOfHostMemory,
+    /// There is no memory available on the device (ie. video memory).
+    OutOfDeviceMemory,
+    /// Allocation has failed because the pool is too fragmented.
+    FragmentedPool,
+    /// There is no more space available in the descriptor pool.
+    OutOfPoolMemory,
+    /// The Vulkan implementation returned an error that this operation isn't supposed to return.
+    Unexpected(Error),
+}
+
+impl error::Error for DescriptorPoolAllocError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            DescriptorPoolAllocError::OutOfHostMemory => {
+                "no memory available on the host"
+            },
+            DescriptorPoolAllocError::OutOfDeviceMemory => {
+                "no memory available on the graphical device"
+            },
+            DescriptorPoolAllocError::FragmentedPool => {
+                "allocation has failed because the pool is too fragmented"
+            },
+            DescriptorPoolAllocError::OutOfPoolMemory => {
+                "there is no more space available in the descriptor pool"
+            },
+            DescriptorPoolAllocError::Unexpected(_) => {
+                "unexpected error returned by the Vulkan implementation"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            DescriptorPoolAllocError::Unexpected(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for DescriptorPoolAllocError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+/// Error that can happen when freeing descriptor sets.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DescriptorPoolFreeError {
+    /// Not enough memory.
+    OomError(OomError),
+    /// The pool wasn't created with `free_individual_sets` set to true.
+    IndividualFreeNotAllowed,
+}
+
+impl error::Error for DescriptorPoolFreeError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            DescriptorPoolFreeError::OomError(_) => "not enough memory available",
+            DescriptorPoolFreeError::IndividualFreeNotAllowed => {
+                "the pool doesn't allow freeing individual descriptor sets"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            DescriptorPoolFreeError::OomError(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for DescriptorPoolFreeError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<OomError> for DescriptorPoolFreeError {
+    #[inline]
+    fn from(err: OomError) -> DescriptorPoolFreeError {
+        DescriptorPoolFreeError::OomError(err)
+    }
+}
+
+impl From<Error> for DescriptorPoolFreeError {
+    #[inline]
+    fn from(err: Error) -> DescriptorPoolFreeError {
+        DescriptorPoolFreeError::OomError(OomError::from(err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::iter;
+    use std::sync::Arc;
+
+    use descriptor::descriptor::DescriptorBufferDesc;
+    use descriptor::descriptor::DescriptorDesc;
+    use descriptor::descriptor::DescriptorDescTy;
+    use descriptor::descriptor::DescriptorType;
+    use descriptor::descriptor::ShaderStages;
+    use descriptor::descriptor_set::DescriptorPoolAllocError;
+    use descriptor::descriptor_set::DescriptorPoolFreeError;
+    use descriptor::descriptor_set::DescriptorsCount;
+    use descriptor::descriptor_set::TrackedDescriptorPool;
+    use descriptor::descriptor_set::UnsafeDescriptorPool;
+    use descriptor::descriptor_set::UnsafeDescriptorSetLayout;
+    use device::Device;
+
+    #[test]
+    fn count_arithmetic() {
+        let a = DescriptorsCount { uniform_buffer: 1, sampler: 2, .. DescriptorsCount::zero() };
+        let b = DescriptorsCount { uniform_buffer: 3, storage_image: 1,
+                                   .. DescriptorsCount::zero() };
+
+        assert_eq!(a * 100 + b * 10, DescriptorsCount {
+            uniform_buffer: 130,
+            sampler: 200,
+            storage_image: 10,
+            .. DescriptorsCount::zero()
+        });
+
+        let mut c = a;
+        c += b;
+        c *= 2;
+        assert_eq!(c, DescriptorsCount {
+            uniform_buffer: 8,
+            sampler: 4,
+            storage_image: 2,
+            .. DescriptorsCount::zero()
+        });
+
+        assert_eq!(c.checked_sub(&a), Some(DescriptorsCount {
+            uniform_buffer: 7,
+            sampler: 2,
+            storage_image: 2,
+            .. DescriptorsCount::zero()
+        }));
+        assert_eq!(c.checked_sub(&c), Some(DescriptorsCount::zero()));
+        assert_eq!(a.checked_sub(&b), None);
+        assert_eq!(DescriptorsCount::zero().checked_sub(&a), None);
+    }
+
+    #[test]
+    fn count_arithmetic_saturates() {
+        let a = DescriptorsCount { sampler: u32::MAX - 1, .. DescriptorsCount::zero() };
+        assert_eq!((a + a).sampler, u32::MAX);
+        assert_eq!((a * 2).sampler, u32::MAX);
+
+        let mut b = a;
+        b.add_num(DescriptorType::Sampler, 2);
+        assert_eq!(b.sampler, u32::MAX);
+    }
+
+    #[test]
+    fn layout_descriptors_count() {
+        let (device, _) = gfx_dev_and_queue!();
+        let layout = uniform_buffer_layout(&device);
+        assert_eq!(layout.descriptors_count(),
+                   DescriptorsCount { uniform_buffer: 1, .. DescriptorsCount::zero() });
+    }
+
+    #[test]
+    fn tracked_pool() {
+        let (device, _) = gfx_dev_and_queue!();
+        let layout = uniform_buffer_layout(&device);
+        let count = layout.descriptors_count() * 2;
+        let mut pool = TrackedDescriptorPool::new(&device, 10, &count, true).unwrap();
+        assert_eq!(pool.remaining(), &count);
+
+        unsafe {
+            let sets = pool.alloc(vec![&layout, &layout]).unwrap().collect::<Vec<_>>();
+            assert_eq!(pool.remaining(), &DescriptorsCount::zero());
+
+            // The pool still has room for more sets, but not for more descriptors.
+            match pool.alloc(iter::once(&layout)) {
+                Err(DescriptorPoolAllocError::OutOfPoolMemory) => (),
+                _ => panic!()
+            }
+            assert_eq!(pool.remaining(), &DescriptorsCount::zero());
+
+            pool.free(sets).unwrap();
+            assert_eq!(pool.remaining(), &count);
+
+            let _ = pool.alloc(iter::once(&layout)).unwrap();
+            assert_eq!(pool.remaining(), &layout.descriptors_count());
+
+            pool.reset().unwrap();
+            assert_eq!(pool.remaining(), &count);
+        }
+    }
+
+    #[test]
+    fn create() {
+        let (device, _) = gfx_dev_and_queue!();
+        let count = DescriptorsCount { uniform_buffer: 1, .. DescriptorsCount::zero() };
+        let pool = UnsafeDescriptorPool::new(&device, 10, &count, false).unwrap();
+        assert_eq!(pool.max_sets(), 10);
+        assert_eq!(pool.descriptors_count(), &count);
+        assert!(!pool.free_individual_sets());
+    }
+
+    #[test]
+    #[should_panic = "The maximum number of sets can't be 0"]
+    fn zero_max_sets() {
+        let (device, _) = gfx_dev_and_queue!();
+        let count = DescriptorsCount { uniform_buffer: 1, .. DescriptorsCount::zero() };
+        let _ = UnsafeDescriptorPool::new(&device, 0, &count, false);
+    }
+
+    #[test]
+    #[should_panic = "All the descriptors count of a pool can't be 0"]
+    fn zero_descriptors() {
+        let (device, _) = gfx_dev_and_queue!();
+        let _ = UnsafeDescriptorPool::new(&device, 10, &DescriptorsCount::zero(), false);
+    }
+
+    #[test]
+    fn alloc_until_exhausted() {
+        let (device, _) = gfx_dev_and_queue!();
+        let layout = uniform_buffer_layout(&device);
+        let count = DescriptorsCount { uniform_buffer: 4, .. DescriptorsCount::zero() };
+        let mut pool = UnsafeDescriptorPool::new(&device, 4, &count, false).unwrap();
+
+        unsafe {
+            for _ in 0 .. 4 {
+                let sets = pool.alloc(iter::once(&layout)).unwrap();
+                assert_eq!(sets.len(), 1);
+            }
+
+            match pool.alloc(iter::once(&layout)) {
+                Err(DescriptorPoolAllocError::OutOfPoolMemory) => (),
+                _ => panic!()
+            }
+
+            pool.reset().unwrap();
+
+            let sets = pool.alloc(vec![&layout, &layout, &layout, &layout]).unwrap();
+            assert_eq!(sets.len(), 4);
+        }
+    }
+
+    #[test]
+    fn free() {
+        let (device, _) = gfx_dev_and_queue!();
+        let layout = uniform_buffer_layout(&device);
+        let count = DescriptorsCount { uniform_buffer: 2, .. DescriptorsCount::zero() };
+        let mut pool = UnsafeDescriptorPool::new(&device, 2, &count, true).unwrap();
+
+        unsafe {
+            let sets = pool.alloc(vec![&layout, &layout]).unwrap();
+            pool.free(sets).unwrap();
+            let sets = pool.alloc(vec![&layout, &layout]).unwrap();
+            assert_eq!(sets.len(), 2);
+        }
+    }
+
+    #[test]
+    fn free_not_allowed() {
+        let (device, _) = gfx_dev_and_queue!();
+        let layout = uniform_buffer_layout(&device);
+        let count = DescriptorsCount { uniform_buffer: 1, .. DescriptorsCount::zero() };
+        let mut pool = UnsafeDescriptorPool::new(&device, 1, &count, false).unwrap();
+
+        unsafe {
+            let sets = pool.alloc(iter::once(&layout)).unwrap();
+            match pool.free(sets) {
+                Err(DescriptorPoolFreeError::IndividualFreeNotAllowed) => (),
+                _ => panic!()
+            }
+        }
+    }
+
+    fn uniform_buffer_layout(device: &Arc<Device>) -> Arc<UnsafeDescriptorSetLayout> {
+        UnsafeDescriptorSetLayout::new(device, iter::once(Some(DescriptorDesc {
+            binding: 0,
+            ty: DescriptorDescTy::Buffer(DescriptorBufferDesc {
+                dynamic: Some(false),
+                storage: false,
+            }),
+            array_count: 1,
+            stages: ShaderStages::all(),
+            readonly: true,
+            immutable_samplers: None,
+        })))
+    }
+}
@@ -34,6 +34,7 @@ macro_rules! pipeline_layout {
         use $crate::descriptor::descriptor::ShaderStages;
         use $crate::descriptor::pipeline_layout::PipelineLayout;
         use $crate::descriptor::pipeline_layout::PipelineLayoutDesc;
+        use $crate::descriptor::pipeline_layout::PipelineLayoutDescPcRange;
         use $crate::descriptor::pipeline_layout::UnsafePipelineLayout;
         use $crate::descriptor::pipeline_layout::UnsafePipelineLayoutCreationError;
 
@@ -58,7 +59,11 @@ macro_rules! pipeline_layout {
                 ];
 
                 let push_constants = if mem::size_of::<PushConstants>() >= 1 {
-                    Some((0, mem::size_of::<PushConstants>(), ShaderStages::all()))
+                    Some(PipelineLayoutDescPcRange {
+                        offset: 0,
+                        size: mem::size_of::<PushConstants>(),
+                        stages: ShaderStages::all(),
+                    })
                 } else {
                     None
                 };
@@ -90,6 +95,16 @@ macro_rules! pipeline_layout {
                 // FIXME:
                 vec![].into_iter()
             }
+
+            #[inline]
+            fn num_push_constants_ranges(&self) -> usize {
+                self.inner.push_constants_ranges().len()
+            }
+
+            #[inline]
+            fn push_constants_range(&self, num: usize) -> Option<PipelineLayoutDescPcRange> {
+                self.inner.push_constants_ranges().get(num).cloned()
+            }
         }
 
         /* TODO: uncomment when specialization lands
@@ -122,6 +137,7 @@ macro_rules! pipeline_layout {
             use $crate::descriptor::descriptor_set::DescriptorPool;
             use $crate::descriptor::descriptor_set::DescriptorSet;
             use $crate::descriptor::descriptor_set::DescriptorSetDesc;
+            use $crate::descriptor::descriptor_set::DescriptorSetLayoutCreationError;
             use $crate::descriptor::descriptor_set::UnsafeDescriptorSet;
             use $crate::descriptor::descriptor_set::UnsafeDescriptorSetLayout;
             use $crate::descriptor::descriptor_set::DescriptorWrite;
@@ -212,19 +228,21 @@ macro_rules! pipeline_layout {
 
             #[allow(unused_assignments)]
             pub fn build_set_layout_raw(device: &Arc<Device>)
-                                        -> Result<UnsafeDescriptorSetLayout, OomError>
+                                        -> Result<UnsafeDescriptorSetLayout,
+                                                  DescriptorSetLayoutCreationError>
             {
                 let mut descriptors = Vec::new();
                 let mut binding = 0;
 
                 $(
-                    descriptors.push(DescriptorDesc {
+                    descriptors.push(Some(DescriptorDesc {
                         binding: binding,
                         ty: <$ty as DescriptorMarker>::descriptor_type(),
                         array_count: 1,                     // TODO:
                         stages: ShaderStages::all(),        // TODO:
                         readonly: false,                    // TODO:
-                    });
+                        immutable_samplers: None,
+                    }));
 
                     binding += 1;
                 )*
@@ -347,7 +365,7 @@ unsafe impl DescriptorMarker for InputAttachment {
     #[inline]
     fn descriptor_type() -> DescriptorDescTy {
         // FIXME: correct values
-        DescriptorDescTy::InputAttachment { multisampled: false, array_layers: DescriptorImageDescArray::NonArrayed }
+        DescriptorDescTy::InputAttachment { multisampled: false, array_layers: DescriptorImageDescArray::NonArrayed, index: None }
     }
 }
 
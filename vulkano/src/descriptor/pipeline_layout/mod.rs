@@ -20,16 +20,27 @@
 
 pub use self::empty::EmptyPipeline;
 pub use self::empty::EmptyPipelineDesc;
+pub use self::runtime::RuntimePipelineLayout;
+pub use self::sys::DescriptorSetsIncompatibleError;
 pub use self::sys::UnsafePipelineLayout;
 pub use self::sys::UnsafePipelineLayoutCreationError;
+pub use self::sys::PushConstantsError;
+#[doc(hidden)]
+pub use self::sys::push_constants_updates;
+#[doc(hidden)]
+pub use self::sys::layouts_compatible;
 pub use self::traits::PipelineLayout;
+pub use self::traits::PipelineLayoutCompatible;
 pub use self::traits::PipelineLayoutDesc;
+pub use self::traits::PipelineLayoutDescPcRange;
 pub use self::traits::PipelineLayoutSuperset;
+pub use self::traits::PipelineLayoutNotSupersetError;
 pub use self::traits::PipelineLayoutSetsCompatible;
 pub use self::traits::PipelineLayoutPushConstantsCompatible;
 
 pub mod custom_pipeline_macro;
 
 mod empty;
+mod runtime;
 mod sys;
 mod traits;
@@ -0,0 +1,127 @@
+// Copyright (c) 2016 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::sync::Arc;
+use std::vec::IntoIter as VecIntoIter;
+
+use device::Device;
+use descriptor::descriptor::DescriptorDesc;
+use descriptor::descriptor_set::UnsafeDescriptorSetLayout;
+use descriptor::pipeline_layout::PipelineLayout;
+use descriptor::pipeline_layout::PipelineLayoutDesc;
+use descriptor::pipeline_layout::PipelineLayoutDescPcRange;
+use descriptor::pipeline_layout::UnsafePipelineLayout;
+use descriptor::pipeline_layout::UnsafePipelineLayoutCreationError;
+
+/// Implementation of `PipelineLayout` whose layout is only known at runtime.
+///
+/// The description of the layout is built from the `UnsafeDescriptorSetLayout`s it is made of,
+/// which makes it possible to create pipeline layouts from data instead of from shaders.
+pub struct RuntimePipelineLayout {
+    inner: UnsafePipelineLayout,
+}
+
+impl RuntimePipelineLayout {
+    /// Builds a new pipeline layout from a list of descriptor set layouts and a list of push
+    /// constant ranges.
+    ///
+    /// # Panic
+    ///
+    /// Panicks if one of the `UnsafeDescriptorSetLayout` was not created with `device`.
+    #[inline]
+    pub fn new<'a, I, P>(device: &Arc<Device>, layouts: I, push_constants: P)
+                         -> Result<Arc<RuntimePipelineLayout>, UnsafePipelineLayoutCreationError>
+        where I: IntoIterator<Item = &'a Arc<UnsafeDescriptorSetLayout>>,
+              P: IntoIterator<Item = PipelineLayoutDescPcRange>,
+    {
+        let inner = try!(UnsafePipelineLayout::new(device, layouts, push_constants));
+
+        Ok(Arc::new(RuntimePipelineLayout {
+            inner: inner
+        }))
+    }
+}
+
+unsafe impl PipelineLayout for RuntimePipelineLayout {
+    #[inline]
+    fn inner_pipeline_layout(&self) -> &UnsafePipelineLayout {
+        &self.inner
+    }
+}
+
+unsafe impl PipelineLayoutDesc for RuntimePipelineLayout {
+    type SetsIter = VecIntoIter<Self::DescIter>;
+    type DescIter = VecIntoIter<DescriptorDesc>;
+
+    fn descriptors_desc(&self) -> Self::SetsIter {
+        self.inner.descriptor_set_layouts().iter().map(|layout| {
+            layout.descriptors().to_vec().into_iter()
+        }).collect::<Vec<_>>().into_iter()
+    }
+
+    #[inline]
+    fn num_push_constants_ranges(&self) -> usize {
+        self.inner.push_constants_ranges().len()
+    }
+
+    #[inline]
+    fn push_constants_range(&self, num: usize) -> Option<PipelineLayoutDescPcRange> {
+        self.inner.push_constants_ranges().get(num).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::iter;
+
+    use descriptor::descriptor::DescriptorBufferDesc;
+    use descriptor::descriptor::DescriptorDesc;
+    use descriptor::descriptor::DescriptorDescTy;
+    use descriptor::descriptor::ShaderStages;
+    use descriptor::descriptor_set::UnsafeDescriptorSetLayout;
+    use descriptor::pipeline_layout::PipelineLayoutDesc;
+    use descriptor::pipeline_layout::PipelineLayoutDescPcRange;
+    use descriptor::pipeline_layout::runtime::RuntimePipelineLayout;
+
+    #[test]
+    fn description() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let buffer = |binding| DescriptorDesc {
+            binding: binding,
+            ty: DescriptorDescTy::Buffer(DescriptorBufferDesc {
+                dynamic: Some(false),
+                storage: false,
+            }),
+            array_count: 1,
+            stages: ShaderStages::all(),
+            readonly: true,
+            immutable_samplers: None,
+        };
+
+        let set0 = UnsafeDescriptorSetLayout::new(&device, vec![Some(buffer(0))]);
+        let set1 = UnsafeDescriptorSetLayout::new(&device, vec![None, Some(buffer(1))]);
+        let push_constants = PipelineLayoutDescPcRange {
+            offset: 0,
+            size: 16,
+            stages: ShaderStages::all(),
+        };
+
+        let layout = RuntimePipelineLayout::new(&device, vec![&set0, &set1],
+                                                iter::once(push_constants)).unwrap();
+
+        let sets = layout.descriptors_desc().map(|set| {
+            set.map(|d| d.binding).collect::<Vec<_>>()
+        }).collect::<Vec<_>>();
+        assert_eq!(sets, vec![vec![0], vec![1]]);
+
+        assert_eq!(layout.num_push_constants_ranges(), 1);
+        assert_eq!(layout.push_constants_range(0), Some(push_constants));
+    }
+}
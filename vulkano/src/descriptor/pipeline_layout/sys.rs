@@ -21,8 +21,14 @@ use VulkanObject;
 use VulkanPointers;
 use vk;
 
+use descriptor::descriptor::DescriptorDesc;
 use descriptor::descriptor::ShaderStages;
+use descriptor::descriptor_set::DescriptorLimitExceeded;
+use descriptor::descriptor_set::DescriptorLimits;
+use descriptor::descriptor_set::DescriptorSetLayoutCreationError;
+use descriptor::descriptor_set::check_descriptor_limits;
 use descriptor::descriptor_set::UnsafeDescriptorSetLayout;
+use descriptor::pipeline_layout::PipelineLayoutDescPcRange;
 use device::Device;
 
 /// Low-level struct that represents the layout of the resources available to your shaders.
@@ -33,6 +39,7 @@ pub struct UnsafePipelineLayout {
     device: Arc<Device>,
     layout: vk::PipelineLayout,
     layouts: SmallVec<[Arc<UnsafeDescriptorSetLayout>; 16]>,
+    push_constants: SmallVec<[PipelineLayoutDescPcRange; 8]>,
 }
 
 impl UnsafePipelineLayout {
@@ -45,7 +52,7 @@ impl UnsafePipelineLayout {
     pub fn new<'a, I, P>(device: &Arc<Device>, layouts: I, push_constants: P)
                          -> Result<UnsafePipelineLayout, UnsafePipelineLayoutCreationError>
         where I: IntoIterator<Item = &'a Arc<UnsafeDescriptorSetLayout>>,
-              P: IntoIterator<Item = (usize, usize, ShaderStages)>,
+              P: IntoIterator<Item = PipelineLayoutDescPcRange>,
     {
         UnsafePipelineLayout::new_inner(device, layouts.into_iter().map(|e| e.clone()).collect(),
                                         push_constants.into_iter().collect())
@@ -53,7 +60,7 @@ impl UnsafePipelineLayout {
 
     /// Same as `new` but won't be inlined.
     fn new_inner(device: &Arc<Device>, layouts: SmallVec<[Arc<UnsafeDescriptorSetLayout>; 16]>,
-                 push_constants: SmallVec<[(usize, usize, ShaderStages); 8]>)
+                 push_constants: SmallVec<[PipelineLayoutDescPcRange; 8]>)
                  -> Result<UnsafePipelineLayout, UnsafePipelineLayoutCreationError>
     {
         let vk = device.pointers();
@@ -65,28 +72,37 @@ impl UnsafePipelineLayout {
                                     l.internal_object()
                                  }).collect::<SmallVec<[_; 16]>>();
 
-        // FIXME: must also check per-descriptor-type limits (eg. max uniform buffer descriptors)
+        try!(check_layout_limits(&DescriptorLimits::from_device(device),
+                                 layouts.iter().map(|l| l.descriptors())));
 
-        if layouts_ids.len() > limits.max_bound_descriptor_sets() as usize {
-            return Err(UnsafePipelineLayoutCreationError::MaxDescriptorSetsLimitExceeded);
-        }
-
-        let push_constants = {
+        let push_constants_raw = {
             let mut out: SmallVec<[_; 8]> = SmallVec::new();
 
-            for pc in push_constants.iter() {
-                if pc.2 == ShaderStages::none() || pc.1 == 0 || (pc.1 % 4) != 0 {
+            for (num, pc) in push_constants.iter().enumerate() {
+                if pc.stages == ShaderStages::none() || pc.size == 0 || (pc.size % 4) != 0 ||
+                   (pc.offset % 4) != 0
+                {
                     return Err(UnsafePipelineLayoutCreationError::InvalidPushConstant);
                 }
 
-                if pc.0 + pc.1 > limits.max_push_constants_size() as usize {
+                if pc.offset + pc.size > limits.max_push_constants_size() as usize {
                     return Err(UnsafePipelineLayoutCreationError::MaxPushConstantsSizeExceeded);
                 }
 
+                // Each stage can only be part of one range.
+                for (prev_num, prev) in push_constants.iter().take(num).enumerate() {
+                    if prev.stages.intersects(&pc.stages) {
+                        return Err(UnsafePipelineLayoutCreationError::PushConstantsConflict {
+                            first_range: prev_num,
+                            second_range: num,
+                        });
+                    }
+                }
+
                 out.push(vk::PushConstantRange {
-                    stageFlags: pc.2.into(),
-                    offset: pc.0 as u32,
-                    size: pc.1 as u32,
+                    stageFlags: pc.stages.into(),
+                    offset: pc.offset as u32,
+                    size: pc.size as u32,
                 });
             }
 
@@ -100,8 +116,8 @@ impl UnsafePipelineLayout {
                 flags: 0,   // reserved
                 setLayoutCount: layouts_ids.len() as u32,
                 pSetLayouts: layouts_ids.as_ptr(),
-                pushConstantRangeCount: push_constants.len() as u32,
-                pPushConstantRanges: push_constants.as_ptr(),
+                pushConstantRangeCount: push_constants_raw.len() as u32,
+                pPushConstantRanges: push_constants_raw.as_ptr(),
             };
 
             let mut output = mem::uninitialized();
@@ -114,6 +130,7 @@ impl UnsafePipelineLayout {
             device: device.clone(),
             layout: layout,
             layouts: layouts,
+            push_constants: push_constants,
         })
     }
 
@@ -125,6 +142,66 @@ impl UnsafePipelineLayout {
         self.layouts.get(index)
     }
 
+    /// Returns the layouts of all the descriptor sets of this pipeline layout.
+    #[inline]
+    pub fn descriptor_set_layouts(&self) -> &[Arc<UnsafeDescriptorSetLayout>] {
+        &self.layouts
+    }
+
+    /// Returns true if `self` and `other` are compatible for their first `num_sets` descriptor
+    /// sets.
+    ///
+    /// When binding a pipeline, the descriptor sets that were bound with the previous pipeline
+    /// stay valid for all the sets for which the two layouts are compatible. See also
+    /// `PipelineLayoutCompatible`.
+    #[inline]
+    pub fn is_compatible_with(&self, other: &UnsafePipelineLayout, num_sets: usize) -> bool {
+        layouts_compatible(&self.layouts, &self.push_constants, &other.layouts,
+                           &other.push_constants, num_sets)
+    }
+
+    /// Checks whether descriptor sets with the given layouts can be bound, starting from set 0,
+    /// to a pipeline that uses this layout.
+    ///
+    /// Each set must have been created with a layout identical to the layout of the pipeline at
+    /// the same index. Binding fewer sets than the pipeline layout has is allowed.
+    pub fn ensure_sets_compatible<'a, I>(&self, sets: I)
+                                         -> Result<(), DescriptorSetsIncompatibleError>
+        where I: IntoIterator<Item = &'a Arc<UnsafeDescriptorSetLayout>>
+    {
+        let mut num_sets = 0;
+
+        for (set_num, set) in sets.into_iter().enumerate() {
+            num_sets += 1;
+
+            match self.layouts.get(set_num) {
+                Some(expected) => {
+                    if !expected.is_identical_to(set) {
+                        return Err(DescriptorSetsIncompatibleError::IncompatibleSet {
+                            set_num: set_num,
+                        });
+                    }
+                },
+                None => (),
+            }
+        }
+
+        if num_sets > self.layouts.len() {
+            return Err(DescriptorSetsIncompatibleError::TooManySets {
+                expected: self.layouts.len(),
+                obtained: num_sets,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Returns the push constant ranges of this pipeline layout.
+    #[inline]
+    pub fn push_constants_ranges(&self) -> &[PipelineLayoutDescPcRange] {
+        &self.push_constants
+    }
+
     /// Returns the device used to create this pipeline layout.
     #[inline]
     pub fn device(&self) -> &Arc<Device> {
@@ -132,6 +209,8 @@ impl UnsafePipelineLayout {
     }
 }
 
+impl_debug_handle!(UnsafePipelineLayout);
+
 unsafe impl VulkanObject for UnsafePipelineLayout {
     type Object = vk::PipelineLayout;
 
@@ -151,18 +230,61 @@ impl Drop for UnsafePipelineLayout {
     }
 }
 
-/// Error that can happen when creating an instance.
+// Checks the number of descriptor sets of a pipeline layout against the
+// `max_bound_descriptor_sets` limit, and the descriptors of all its sets against the descriptor
+// limits.
+fn check_layout_limits<'a, I>(limits: &DescriptorLimits, sets: I)
+                              -> Result<(), UnsafePipelineLayoutCreationError>
+    where I: ExactSizeIterator<Item = &'a [DescriptorDesc]>
+{
+    if sets.len() > limits.max_bound_descriptor_sets as usize {
+        return Err(UnsafePipelineLayoutCreationError::MaxDescriptorSetsLimitExceeded {
+            max: limits.max_bound_descriptor_sets,
+            requested: sets.len() as u32,
+        });
+    }
+
+    try!(check_descriptor_limits(limits, sets.flat_map(|set| set.iter())));
+    Ok(())
+}
+
+/// Error that can happen when creating a pipeline layout.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum UnsafePipelineLayoutCreationError {
     /// Not enough memory.
     OomError(OomError),
+    /// Error while creating one of the descriptor set layouts.
+    SetLayoutCreationError(DescriptorSetLayoutCreationError),
     /// The maximum number of descriptor sets has been exceeded.
-    MaxDescriptorSetsLimitExceeded,
+    MaxDescriptorSetsLimitExceeded {
+        /// Value of the `max_bound_descriptor_sets` limit.
+        max: u32,
+        /// Number of descriptor sets that were requested.
+        requested: u32,
+    },
+    /// The number of descriptors of all the sets exceeds one of the limits of the device.
+    MaxDescriptorsLimitExceeded {
+        /// Name of the limit, as found in `Limits`.
+        limit: &'static str,
+        /// Value of the limit.
+        max: u32,
+        /// Number of descriptors that were requested.
+        requested: u32,
+    },
     /// The maximum size of push constants has been exceeded.
     MaxPushConstantsSizeExceeded,
     /// One of the push constants range didn't obey the rules. The list of stages must not be
-    /// empty, the size must not be 0, and the size must be a multiple or 4.
+    /// empty, the size must not be 0, and the offset and the size must be multiples of 4.
     InvalidPushConstant,
+    /// Two push constant ranges have a shader stage in common.
+    PushConstantsConflict {
+        /// Index of the first range.
+        first_range: usize,
+        /// Index of the second range.
+        second_range: usize,
+    },
+    /// The Vulkan implementation returned an error that this operation isn't supposed to return.
+    Unexpected(Error),
 }
 
 impl error::Error for UnsafePipelineLayoutCreationError {
@@ -172,15 +294,27 @@ impl error::Error for UnsafePipelineLayoutCreationError {
             UnsafePipelineLayoutCreationError::OomError(_) => {
                 "not enough memory available"
             },
-            UnsafePipelineLayoutCreationError::MaxDescriptorSetsLimitExceeded => {
+            UnsafePipelineLayoutCreationError::SetLayoutCreationError(_) => {
+                "error while creating one of the descriptor set layouts"
+            },
+            UnsafePipelineLayoutCreationError::MaxDescriptorSetsLimitExceeded { .. } => {
                 "the maximum number of descriptor sets has been exceeded"
             },
+            UnsafePipelineLayoutCreationError::MaxDescriptorsLimitExceeded { .. } => {
+                "the number of descriptors exceeds one of the limits of the device"
+            },
             UnsafePipelineLayoutCreationError::MaxPushConstantsSizeExceeded => {
                 "the maximum size of push constants has been exceeded"
             },
             UnsafePipelineLayoutCreationError::InvalidPushConstant => {
                 "one of the push constants range didn't obey the rules"
             },
+            UnsafePipelineLayoutCreationError::PushConstantsConflict { .. } => {
+                "two push constant ranges have a shader stage in common"
+            },
+            UnsafePipelineLayoutCreationError::Unexpected(_) => {
+                "unexpected error returned by the Vulkan implementation"
+            },
         }
     }
 
@@ -188,6 +322,8 @@ impl error::Error for UnsafePipelineLayoutCreationError {
     fn cause(&self) -> Option<&error::Error> {
         match *self {
             UnsafePipelineLayoutCreationError::OomError(ref err) => Some(err),
+            UnsafePipelineLayoutCreationError::SetLayoutCreationError(ref err) => Some(err),
+            UnsafePipelineLayoutCreationError::Unexpected(ref err) => Some(err),
             _ => None
         }
     }
@@ -207,6 +343,24 @@ impl From<OomError> for UnsafePipelineLayoutCreationError {
     }
 }
 
+impl From<DescriptorLimitExceeded> for UnsafePipelineLayoutCreationError {
+    #[inline]
+    fn from(err: DescriptorLimitExceeded) -> UnsafePipelineLayoutCreationError {
+        UnsafePipelineLayoutCreationError::MaxDescriptorsLimitExceeded {
+            limit: err.limit,
+            max: err.max,
+            requested: err.requested,
+        }
+    }
+}
+
+impl From<DescriptorSetLayoutCreationError> for UnsafePipelineLayoutCreationError {
+    #[inline]
+    fn from(err: DescriptorSetLayoutCreationError) -> UnsafePipelineLayoutCreationError {
+        UnsafePipelineLayoutCreationError::SetLayoutCreationError(err)
+    }
+}
+
 impl From<Error> for UnsafePipelineLayoutCreationError {
     #[inline]
     fn from(err: Error) -> UnsafePipelineLayoutCreationError {
@@ -217,19 +371,187 @@ impl From<Error> for UnsafePipelineLayoutCreationError {
             err @ Error::OutOfDeviceMemory => {
                 UnsafePipelineLayoutCreationError::OomError(OomError::from(err))
             },
-            _ => panic!("unexpected error: {:?}", err)
+            _ => UnsafePipelineLayoutCreationError::Unexpected(err)
         }
     }
 }
 
+/// Error that can happen when descriptor sets are not compatible with a pipeline layout.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DescriptorSetsIncompatibleError {
+    /// More descriptor sets were passed than the pipeline layout has.
+    TooManySets {
+        /// Number of descriptor sets of the pipeline layout.
+        expected: usize,
+        /// Number of descriptor sets that were passed.
+        obtained: usize,
+    },
+
+    /// The layout of a descriptor set isn't identical to the layout of the pipeline at the same
+    /// index.
+    IncompatibleSet {
+        /// Index of the first incompatible set.
+        set_num: usize,
+    },
+}
+
+impl error::Error for DescriptorSetsIncompatibleError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            DescriptorSetsIncompatibleError::TooManySets { .. } => {
+                "more descriptor sets were passed than the pipeline layout has"
+            },
+            DescriptorSetsIncompatibleError::IncompatibleSet { .. } => {
+                "the layout of a descriptor set isn't compatible with the pipeline layout"
+            },
+        }
+    }
+}
+
+impl fmt::Display for DescriptorSetsIncompatibleError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+/// Checks whether two pipeline layouts, given as their list of descriptor set layouts and their
+/// push constant ranges, are compatible for their first `num_sets` descriptor sets.
+#[doc(hidden)]
+pub fn layouts_compatible(sets1: &[Arc<UnsafeDescriptorSetLayout>],
+                          push_constants1: &[PipelineLayoutDescPcRange],
+                          sets2: &[Arc<UnsafeDescriptorSetLayout>],
+                          push_constants2: &[PipelineLayoutDescPcRange], num_sets: usize) -> bool
+{
+    if push_constants1.len() != push_constants2.len() ||
+       !push_constants1.iter().all(|r| push_constants2.contains(r))
+    {
+        return false;
+    }
+
+    for num in 0 .. num_sets {
+        match (sets1.get(num), sets2.get(num)) {
+            (Some(set1), Some(set2)) => {
+                if !set1.is_identical_to(set2) {
+                    return false;
+                }
+            },
+            (None, None) => break,
+            _ => return false,
+        }
+    }
+
+    true
+}
+
+/// Splits the update of `size` bytes of push constants, starting at offset 0, into updates
+/// that each obey the rules of `vkCmdPushConstants` for a layout with the given ranges.
+///
+/// Every updated byte must be accessible by all the stages that have it in one of their ranges,
+/// and each update must only target the stages whose range contains all of its bytes.
+#[doc(hidden)]
+pub fn push_constants_updates(ranges: &[PipelineLayoutDescPcRange], size: usize)
+                              -> Result<SmallVec<[PipelineLayoutDescPcRange; 8]>, PushConstantsError>
+{
+    if (size % 4) != 0 {
+        return Err(PushConstantsError::SizeNotMultipleOfFour { size: size });
+    }
+
+    // Offsets where the list of stages that can access a byte may change.
+    let mut bounds = vec![0, size];
+    for range in ranges.iter() {
+        for &bound in [range.offset, range.offset + range.size].iter() {
+            if bound < size { bounds.push(bound); }
+        }
+    }
+    bounds.sort();
+    bounds.dedup();
+
+    let mut out: SmallVec<[PipelineLayoutDescPcRange; 8]> = SmallVec::new();
+
+    for (&start, &end) in bounds.iter().zip(bounds.iter().skip(1)) {
+        let stages = ranges.iter()
+                           .filter(|r| r.offset <= start && r.offset + r.size >= end)
+                           .fold(ShaderStages::none(), |s, r| s.union(&r.stages));
+
+        if stages == ShaderStages::none() {
+            return Err(PushConstantsError::OutOfRange { offset: start });
+        }
+
+        if let Some(last) = out.last_mut() {
+            if last.stages == stages {
+                last.size = end - last.offset;
+                continue;
+            }
+        }
+
+        out.push(PipelineLayoutDescPcRange {
+            offset: start,
+            size: end - start,
+            stages: stages,
+        });
+    }
+
+    Ok(out)
+}
+
+/// Error that can happen when pushing constants with a pipeline layout.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PushConstantsError {
+    /// The size of the push constants must be a multiple of 4.
+    SizeNotMultipleOfFour {
+        /// Size of the push constants.
+        size: usize,
+    },
+    /// A byte of the push constants isn't part of any push constants range of the layout.
+    OutOfRange {
+        /// Offset of the first byte that isn't part of a range.
+        offset: usize,
+    },
+}
+
+impl error::Error for PushConstantsError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            PushConstantsError::SizeNotMultipleOfFour { .. } => {
+                "the size of the push constants must be a multiple of 4"
+            },
+            PushConstantsError::OutOfRange { .. } => {
+                "a byte of the push constants isn't part of any push constants range of the \
+                 layout"
+            },
+        }
+    }
+}
+
+impl fmt::Display for PushConstantsError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::iter;
     use std::sync::Arc;
+    use descriptor::descriptor::DescriptorBufferDesc;
+    use descriptor::descriptor::DescriptorDesc;
+    use descriptor::descriptor::DescriptorDescTy;
+    use descriptor::descriptor::DescriptorImageDesc;
+    use descriptor::descriptor::DescriptorImageDescArray;
+    use descriptor::descriptor::DescriptorImageDescDimensions;
     use descriptor::descriptor::ShaderStages;
+    use descriptor::descriptor_set::DescriptorLimits;
     use descriptor::descriptor_set::UnsafeDescriptorSetLayout;
+    use descriptor::pipeline_layout::PipelineLayoutDescPcRange;
+    use descriptor::pipeline_layout::sys::PushConstantsError;
     use descriptor::pipeline_layout::sys::UnsafePipelineLayout;
     use descriptor::pipeline_layout::sys::UnsafePipelineLayoutCreationError;
+    use descriptor::pipeline_layout::sys::check_layout_limits;
+    use descriptor::pipeline_layout::sys::push_constants_updates;
 
     #[test]
     fn empty() {
@@ -255,7 +577,11 @@ mod tests {
     fn invalid_push_constant_stages() {
         let (device, _) = gfx_dev_and_queue!();
 
-        let push_constant = (0, 8, ShaderStages::none());
+        let push_constant = PipelineLayoutDescPcRange {
+            offset: 0,
+            size: 8,
+            stages: ShaderStages::none(),
+        };
 
         match UnsafePipelineLayout::new(&device, iter::empty(), Some(push_constant)) {
             Err(UnsafePipelineLayoutCreationError::InvalidPushConstant) => (),
@@ -267,7 +593,11 @@ mod tests {
     fn invalid_push_constant_size1() {
         let (device, _) = gfx_dev_and_queue!();
 
-        let push_constant = (0, 0, ShaderStages::all_graphics());
+        let push_constant = PipelineLayoutDescPcRange {
+            offset: 0,
+            size: 0,
+            stages: ShaderStages::all_graphics(),
+        };
 
         match UnsafePipelineLayout::new(&device, iter::empty(), Some(push_constant)) {
             Err(UnsafePipelineLayoutCreationError::InvalidPushConstant) => (),
@@ -279,11 +609,242 @@ mod tests {
     fn invalid_push_constant_size2() {
         let (device, _) = gfx_dev_and_queue!();
 
-        let push_constant = (0, 11, ShaderStages::all_graphics());
+        let push_constant = PipelineLayoutDescPcRange {
+            offset: 0,
+            size: 11,
+            stages: ShaderStages::all_graphics(),
+        };
+
+        match UnsafePipelineLayout::new(&device, iter::empty(), Some(push_constant)) {
+            Err(UnsafePipelineLayoutCreationError::InvalidPushConstant) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn invalid_push_constant_offset() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let push_constant = PipelineLayoutDescPcRange {
+            offset: 2,
+            size: 8,
+            stages: ShaderStages::all_graphics(),
+        };
 
         match UnsafePipelineLayout::new(&device, iter::empty(), Some(push_constant)) {
             Err(UnsafePipelineLayoutCreationError::InvalidPushConstant) => (),
             _ => panic!()
         }
     }
+
+    #[test]
+    fn push_constants_size_limit() {
+        let (device, _) = gfx_dev_and_queue!();
+        let max = device.physical_device().limits().max_push_constants_size() as usize;
+
+        let push_constant = PipelineLayoutDescPcRange {
+            offset: max - 4,
+            size: 8,
+            stages: ShaderStages::all_graphics(),
+        };
+
+        match UnsafePipelineLayout::new(&device, iter::empty(), Some(push_constant)) {
+            Err(UnsafePipelineLayoutCreationError::MaxPushConstantsSizeExceeded) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn push_constants_conflict() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let push_constants = vec![
+            PipelineLayoutDescPcRange {
+                offset: 0,
+                size: 16,
+                stages: ShaderStages { vertex: true, .. ShaderStages::none() },
+            },
+            PipelineLayoutDescPcRange {
+                offset: 16,
+                size: 16,
+                stages: ShaderStages { vertex: true, fragment: true, .. ShaderStages::none() },
+            },
+        ];
+
+        match UnsafePipelineLayout::new(&device, iter::empty(), push_constants) {
+            Err(UnsafePipelineLayoutCreationError::PushConstantsConflict {
+                first_range: 0, second_range: 1
+            }) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn separate_vertex_fragment_ranges() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let push_constants = vec![
+            PipelineLayoutDescPcRange {
+                offset: 0,
+                size: 16,
+                stages: ShaderStages { vertex: true, .. ShaderStages::none() },
+            },
+            PipelineLayoutDescPcRange {
+                offset: 8,
+                size: 16,
+                stages: ShaderStages { fragment: true, .. ShaderStages::none() },
+            },
+        ];
+
+        let layout = UnsafePipelineLayout::new(&device, iter::empty(), push_constants.clone())
+                                                                                    .unwrap();
+        assert_eq!(layout.push_constants_ranges(), &push_constants[..]);
+    }
+
+    #[test]
+    fn push_constants_updates_split() {
+        let vertex = ShaderStages { vertex: true, .. ShaderStages::none() };
+        let fragment = ShaderStages { fragment: true, .. ShaderStages::none() };
+
+        let ranges = [
+            PipelineLayoutDescPcRange { offset: 0, size: 16, stages: vertex },
+            PipelineLayoutDescPcRange { offset: 8, size: 16, stages: fragment },
+        ];
+
+        let updates = push_constants_updates(&ranges, 24).unwrap();
+        assert_eq!(&updates[..], &[
+            PipelineLayoutDescPcRange { offset: 0, size: 8, stages: vertex },
+            PipelineLayoutDescPcRange { offset: 8, size: 8, stages: vertex.union(&fragment) },
+            PipelineLayoutDescPcRange { offset: 16, size: 8, stages: fragment },
+        ][..]);
+
+        let updates = push_constants_updates(&ranges, 8).unwrap();
+        assert_eq!(&updates[..], &[
+            PipelineLayoutDescPcRange { offset: 0, size: 8, stages: vertex },
+        ][..]);
+    }
+
+    #[test]
+    fn push_constants_updates_errors() {
+        let ranges = [
+            PipelineLayoutDescPcRange { offset: 4, size: 8, stages: ShaderStages::all() },
+        ];
+
+        assert_eq!(push_constants_updates(&ranges, 6).unwrap_err(),
+                   PushConstantsError::SizeNotMultipleOfFour { size: 6 });
+        assert_eq!(push_constants_updates(&ranges, 8).unwrap_err(),
+                   PushConstantsError::OutOfRange { offset: 0 });
+        assert_eq!(push_constants_updates(&ranges[..], 0).unwrap().len(), 0);
+    }
+
+    // Limits of 4 descriptors per stage, 6 descriptors for all the sets, 7 resources per stage and
+    // 2 descriptor sets.
+    fn test_limits() -> DescriptorLimits {
+        DescriptorLimits {
+            max_bound_descriptor_sets: 2,
+            max_per_stage_descriptor_samplers: 4,
+            max_per_stage_descriptor_uniform_buffers: 4,
+            max_per_stage_descriptor_storage_buffers: 4,
+            max_per_stage_descriptor_sampled_images: 4,
+            max_per_stage_descriptor_storage_images: 4,
+            max_per_stage_descriptor_input_attachments: 4,
+            max_per_stage_resources: 7,
+            max_descriptor_set_samplers: 6,
+            max_descriptor_set_uniform_buffers: 6,
+            max_descriptor_set_uniform_buffers_dynamic: 2,
+            max_descriptor_set_storage_buffers: 6,
+            max_descriptor_set_storage_buffers_dynamic: 2,
+            max_descriptor_set_sampled_images: 6,
+            max_descriptor_set_storage_images: 6,
+            max_descriptor_set_input_attachments: 6,
+        }
+    }
+
+    fn limit_exceeded(limit: &'static str, max: u32, requested: u32)
+                      -> Result<(), UnsafePipelineLayoutCreationError>
+    {
+        Err(UnsafePipelineLayoutCreationError::MaxDescriptorsLimitExceeded {
+            limit: limit,
+            max: max,
+            requested: requested,
+        })
+    }
+
+    #[test]
+    fn layout_limits() {
+        let vertex = ShaderStages { vertex: true, .. ShaderStages::none() };
+        let fragment = ShaderStages { fragment: true, .. ShaderStages::none() };
+
+        let desc = |ty, array_count, stages| DescriptorDesc {
+            binding: 0,
+            ty: ty,
+            array_count: array_count,
+            stages: stages,
+            readonly: true,
+            immutable_samplers: None,
+        };
+        let buffer = |storage, dynamic| DescriptorDescTy::Buffer(DescriptorBufferDesc {
+            dynamic: Some(dynamic),
+            storage: storage,
+        });
+        let image = |sampled| DescriptorDescTy::Image(DescriptorImageDesc {
+            sampled: sampled,
+            dimensions: DescriptorImageDescDimensions::TwoDimensional,
+            format: None,
+            multisampled: false,
+            array_layers: DescriptorImageDescArray::NonArrayed,
+        });
+        let input_attachment = DescriptorDescTy::InputAttachment {
+            multisampled: false,
+            array_layers: DescriptorImageDescArray::NonArrayed,
+            index: None,
+        };
+
+        let types = [
+            (DescriptorDescTy::Sampler, "max_descriptor_set_samplers",
+             "max_per_stage_descriptor_samplers"),
+            (buffer(false, false), "max_descriptor_set_uniform_buffers",
+             "max_per_stage_descriptor_uniform_buffers"),
+            (buffer(true, false), "max_descriptor_set_storage_buffers",
+             "max_per_stage_descriptor_storage_buffers"),
+            (image(true), "max_descriptor_set_sampled_images",
+             "max_per_stage_descriptor_sampled_images"),
+            (image(false), "max_descriptor_set_storage_images",
+             "max_per_stage_descriptor_storage_images"),
+            (input_attachment, "max_descriptor_set_input_attachments",
+             "max_per_stage_descriptor_input_attachments"),
+        ];
+
+        let limits = test_limits();
+        let check = |sets: &[&[DescriptorDesc]]| {
+            check_layout_limits(&limits, sets.iter().cloned())
+        };
+
+        for &(ref ty, max_set, max_stage) in types.iter() {
+            // Sets within all the limits.
+            assert_eq!(check(&[&[desc(ty.clone(), 4, vertex)], &[desc(ty.clone(), 2, fragment)]]),
+                       Ok(()));
+
+            // The descriptors of all the sets are counted together.
+            assert_eq!(check(&[&[desc(ty.clone(), 4, vertex)], &[desc(ty.clone(), 4, fragment)]]),
+                       limit_exceeded(max_set, 6, 8));
+
+            // So are the descriptors of each stage.
+            assert_eq!(check(&[&[desc(ty.clone(), 3, vertex)], &[desc(ty.clone(), 2, vertex)]]),
+                       limit_exceeded(max_stage, 4, 5));
+        }
+
+        assert_eq!(check(&[&[desc(buffer(false, true), 3, vertex)]]),
+                   limit_exceeded("max_descriptor_set_uniform_buffers_dynamic", 2, 3));
+        assert_eq!(check(&[&[desc(buffer(true, true), 3, vertex)]]),
+                   limit_exceeded("max_descriptor_set_storage_buffers_dynamic", 2, 3));
+        assert_eq!(check(&[&[desc(buffer(false, false), 4, vertex)],
+                           &[desc(buffer(true, false), 4, vertex)]]),
+                   limit_exceeded("max_per_stage_resources", 7, 8));
+        assert_eq!(check(&[&[], &[], &[]]),
+                   Err(UnsafePipelineLayoutCreationError::MaxDescriptorSetsLimitExceeded {
+                       max: 2,
+                       requested: 3,
+                   }));
+    }
 }
@@ -7,7 +7,12 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
+use std::error;
+use std::fmt;
+
 use descriptor::descriptor::DescriptorDesc;
+use descriptor::descriptor::descriptors_identical;
+use descriptor::descriptor::ShaderStages;
 use descriptor::descriptor_set::DescriptorSetsCollection;
 use descriptor::pipeline_layout::UnsafePipelineLayout;
 
@@ -28,7 +33,32 @@ pub unsafe trait PipelineLayoutDesc {
     /// Describes the layout of the descriptors of the pipeline.
     fn descriptors_desc(&self) -> Self::SetsIter;
 
-    // TODO: describe push constants
+    /// Returns the number of push constant ranges of the layout.
+    #[inline]
+    fn num_push_constants_ranges(&self) -> usize {
+        0
+    }
+
+    /// Describes a push constant range of the layout.
+    ///
+    /// Returns `None` if out of range.
+    #[inline]
+    fn push_constants_range(&self, _num: usize) -> Option<PipelineLayoutDescPcRange> {
+        None
+    }
+}
+
+/// Description of a range of the push constants of a pipeline layout.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PipelineLayoutDescPcRange {
+    /// Offset in bytes from the start of the push constants to this range. Must be a multiple
+    /// of 4.
+    pub offset: usize,
+    /// Size in bytes of the range. Must be a multiple of 4 and not 0.
+    pub size: usize,
+    /// The stages which can access this range. Two ranges of the same layout must not have a
+    /// stage in common.
+    pub stages: ShaderStages,
 }
 
 /// Traits that allow determining whether a pipeline layout is a superset of another one.
@@ -39,37 +69,151 @@ pub unsafe trait PipelineLayoutSuperset<Other>: PipelineLayoutDesc
     where Other: PipelineLayoutDesc
 {
     /// Returns true if `self` is a superset of `Other`.
-    fn is_superset_of(&self, &Other) -> bool;
+    #[inline]
+    fn is_superset_of(&self, other: &Other) -> bool {
+        self.ensure_superset_of(other).is_ok()
+    }
+
+    /// Checks that `self` is a superset of `Other`, and returns the first descriptor of `Other`
+    /// that isn't in `self` otherwise.
+    fn ensure_superset_of(&self, &Other) -> Result<(), PipelineLayoutNotSupersetError>;
 }
 
 unsafe impl<T, U> PipelineLayoutSuperset<U> for T
     where T: PipelineLayoutDesc, U: PipelineLayoutDesc
 {
-    fn is_superset_of(&self, other: &U) -> bool {
-        let mut other_descriptor_sets = other.descriptors_desc();
+    fn ensure_superset_of(&self, other: &U) -> Result<(), PipelineLayoutNotSupersetError> {
+        let mut my_descriptor_sets = self.descriptors_desc();
 
-        for my_set in self.descriptors_desc() {
-            let mut other_set = match other_descriptor_sets.next() {
-                None => return false,
-                Some(s) => s,
-            };
+        for (set_num, other_set) in other.descriptors_desc().enumerate() {
+            let set_num = set_num as u32;
+            let my_set = my_descriptor_sets.next().map(|s| s.collect::<Vec<_>>())
+                                           .unwrap_or(Vec::new());
 
-            for my_desc in my_set {
-                let other_desc = match other_set.next() {
-                    None => return false,
+            for other_desc in other_set {
+                let my_desc = match my_set.iter().find(|d| d.binding == other_desc.binding) {
                     Some(d) => d,
+                    None => return Err(PipelineLayoutNotSupersetError::MissingDescriptor {
+                        set_num: set_num,
+                        binding_num: other_desc.binding,
+                    }),
                 };
 
                 if !my_desc.is_superset_of(&other_desc) {
-                    return false;
+                    return Err(PipelineLayoutNotSupersetError::IncompatibleDescriptors {
+                        set_num: set_num,
+                        binding_num: other_desc.binding,
+                    });
                 }
             }
         }
 
+        Ok(())
+    }
+}
+
+/// Traits that allow determining whether two pipeline layouts are compatible for their first
+/// descriptor sets.
+///
+/// Descriptor sets that have been bound with a pipeline layout stay bound when a pipeline with
+/// a compatible layout is bound. Two layouts are compatible for the first `num_sets` sets if
+/// they have identical push constant ranges and if their first `num_sets` descriptor sets are
+/// defined identically.
+///
+/// This trait is automatically implemented on all types that implement `PipelineLayoutDesc`.
+pub unsafe trait PipelineLayoutCompatible<Other>: PipelineLayoutDesc
+    where Other: PipelineLayoutDesc
+{
+    /// Returns true if `self` and `other` are compatible for their first `num_sets` descriptor
+    /// sets.
+    fn is_compatible_with(&self, other: &Other, num_sets: usize) -> bool;
+}
+
+unsafe impl<T, U> PipelineLayoutCompatible<U> for T
+    where T: PipelineLayoutDesc, U: PipelineLayoutDesc
+{
+    fn is_compatible_with(&self, other: &U, num_sets: usize) -> bool {
+        if self.num_push_constants_ranges() != other.num_push_constants_ranges() {
+            return false;
+        }
+
+        for num in 0 .. self.num_push_constants_ranges() {
+            let my_range = self.push_constants_range(num);
+            let found = (0 .. other.num_push_constants_ranges())
+                            .any(|n| other.push_constants_range(n) == my_range);
+            if !found {
+                return false;
+            }
+        }
+
+        let mut my_sets = self.descriptors_desc();
+        let mut other_sets = other.descriptors_desc();
+
+        for _ in 0 .. num_sets {
+            match (my_sets.next(), other_sets.next()) {
+                (Some(my_set), Some(other_set)) => {
+                    let my_set = my_set.collect::<Vec<_>>();
+                    let other_set = other_set.collect::<Vec<_>>();
+                    if !descriptors_identical(&my_set, &other_set) {
+                        return false;
+                    }
+                },
+                (None, None) => break,
+                _ => return false,
+            }
+        }
+
         true
     }
 }
 
+/// Error that can happen when a pipeline layout is not a superset of another one.
+///
+/// Returned by `PipelineLayoutSuperset::ensure_superset_of`. The set and binding numbers refer to
+/// the first descriptor of the other layout (usually the layout expected by a shader) that the
+/// pipeline layout doesn't provide.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PipelineLayoutNotSupersetError {
+    /// The other layout has a descriptor at a set and binding where the pipeline layout has
+    /// none.
+    MissingDescriptor {
+        /// Index of the descriptor set.
+        set_num: u32,
+        /// Binding of the descriptor within the set.
+        binding_num: u32,
+    },
+
+    /// The pipeline layout has a descriptor at the same set and binding as the other layout, but
+    /// with a different type, a smaller array or fewer shader stages.
+    IncompatibleDescriptors {
+        /// Index of the descriptor set.
+        set_num: u32,
+        /// Binding of the descriptor within the set.
+        binding_num: u32,
+    },
+}
+
+impl error::Error for PipelineLayoutNotSupersetError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            PipelineLayoutNotSupersetError::MissingDescriptor { .. } => {
+                "a descriptor is missing in the pipeline layout"
+            },
+            PipelineLayoutNotSupersetError::IncompatibleDescriptors { .. } => {
+                "a descriptor of the pipeline layout is not compatible with what is expected"
+            },
+        }
+    }
+}
+
+impl fmt::Display for PipelineLayoutNotSupersetError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
 /// Traits that allow determining whether 
 pub unsafe trait PipelineLayoutSetsCompatible<Other>: PipelineLayoutDesc
     where Other: DescriptorSetsCollection
@@ -119,3 +263,167 @@ unsafe impl<T, U> PipelineLayoutPushConstantsCompatible<U> for T where T: Pipeli
         true
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::vec::IntoIter as VecIntoIter;
+
+    use descriptor::descriptor::DescriptorBufferDesc;
+    use descriptor::descriptor::DescriptorDesc;
+    use descriptor::descriptor::DescriptorDescTy;
+    use descriptor::descriptor::ShaderStages;
+    use descriptor::pipeline_layout::PipelineLayoutCompatible;
+    use descriptor::pipeline_layout::PipelineLayoutDesc;
+    use descriptor::pipeline_layout::PipelineLayoutDescPcRange;
+    use descriptor::pipeline_layout::PipelineLayoutNotSupersetError;
+    use descriptor::pipeline_layout::PipelineLayoutSuperset;
+
+    // Layout description made of a list of descriptor sets.
+    struct Desc(Vec<Vec<DescriptorDesc>>);
+
+    unsafe impl PipelineLayoutDesc for Desc {
+        type SetsIter = VecIntoIter<VecIntoIter<DescriptorDesc>>;
+        type DescIter = VecIntoIter<DescriptorDesc>;
+
+        fn descriptors_desc(&self) -> Self::SetsIter {
+            self.0.iter().map(|s| s.clone().into_iter()).collect::<Vec<_>>().into_iter()
+        }
+    }
+
+    // Layout description made of a list of descriptor sets and push constant ranges.
+    struct PcDesc(Vec<Vec<DescriptorDesc>>, Vec<PipelineLayoutDescPcRange>);
+
+    unsafe impl PipelineLayoutDesc for PcDesc {
+        type SetsIter = VecIntoIter<VecIntoIter<DescriptorDesc>>;
+        type DescIter = VecIntoIter<DescriptorDesc>;
+
+        fn descriptors_desc(&self) -> Self::SetsIter {
+            self.0.iter().map(|s| s.clone().into_iter()).collect::<Vec<_>>().into_iter()
+        }
+
+        fn num_push_constants_ranges(&self) -> usize {
+            self.1.len()
+        }
+
+        fn push_constants_range(&self, num: usize) -> Option<PipelineLayoutDescPcRange> {
+            self.1.get(num).cloned()
+        }
+    }
+
+    fn pc_range(offset: usize, size: usize, stages: ShaderStages) -> PipelineLayoutDescPcRange {
+        PipelineLayoutDescPcRange {
+            offset: offset,
+            size: size,
+            stages: stages,
+        }
+    }
+
+    fn buffer(binding: u32, storage: bool, stages: ShaderStages) -> DescriptorDesc {
+        DescriptorDesc {
+            binding: binding,
+            ty: DescriptorDescTy::Buffer(DescriptorBufferDesc {
+                dynamic: Some(false),
+                storage: storage,
+            }),
+            array_count: 1,
+            stages: stages,
+            readonly: true,
+            immutable_samplers: None,
+        }
+    }
+
+    #[test]
+    fn superset() {
+        let layout = Desc(vec![
+            vec![buffer(0, false, ShaderStages::all()), buffer(3, true, ShaderStages::all())],
+            vec![buffer(1, true, ShaderStages::all())],
+        ]);
+        let shader = Desc(vec![
+            vec![buffer(3, true, ShaderStages::compute())],
+            vec![buffer(1, true, ShaderStages::compute())],
+        ]);
+
+        assert_eq!(layout.ensure_superset_of(&shader), Ok(()));
+        assert!(layout.is_superset_of(&shader));
+        assert!(!shader.is_superset_of(&layout));
+    }
+
+    #[test]
+    fn missing_descriptor() {
+        let layout = Desc(vec![vec![buffer(0, true, ShaderStages::all())]]);
+        let shader = Desc(vec![vec![], vec![buffer(0, true, ShaderStages::compute())]]);
+
+        assert_eq!(layout.ensure_superset_of(&shader),
+                   Err(PipelineLayoutNotSupersetError::MissingDescriptor {
+                       set_num: 1,
+                       binding_num: 0,
+                   }));
+    }
+
+    #[test]
+    fn incompatible_descriptors() {
+        let layout = Desc(vec![vec![buffer(0, false, ShaderStages::all())]]);
+        let shader = Desc(vec![vec![buffer(0, true, ShaderStages::compute())]]);
+
+        assert_eq!(layout.ensure_superset_of(&shader),
+                   Err(PipelineLayoutNotSupersetError::IncompatibleDescriptors {
+                       set_num: 0,
+                       binding_num: 0,
+                   }));
+    }
+
+    #[test]
+    fn missing_stage() {
+        let layout = Desc(vec![vec![buffer(0, true, ShaderStages::compute())]]);
+        let shader = Desc(vec![vec![buffer(0, true, ShaderStages::all())]]);
+
+        match layout.ensure_superset_of(&shader) {
+            Err(PipelineLayoutNotSupersetError::IncompatibleDescriptors { .. }) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn compatible() {
+        let first = Desc(vec![
+            vec![buffer(0, false, ShaderStages::all()), buffer(1, true, ShaderStages::all())],
+            vec![buffer(0, true, ShaderStages::compute())],
+        ]);
+        let second = Desc(vec![
+            vec![buffer(1, true, ShaderStages::all()), buffer(0, false, ShaderStages::all())],
+            vec![buffer(0, false, ShaderStages::compute())],
+        ]);
+
+        assert!(first.is_compatible_with(&second, 0));
+        assert!(first.is_compatible_with(&second, 1));
+        assert!(second.is_compatible_with(&first, 1));
+        assert!(!first.is_compatible_with(&second, 2));
+    }
+
+    #[test]
+    fn incompatible_sets() {
+        let first = Desc(vec![vec![buffer(0, false, ShaderStages::all())]]);
+        let more_stages = Desc(vec![vec![buffer(0, false, ShaderStages::compute())]]);
+        let more_sets = Desc(vec![vec![buffer(0, false, ShaderStages::all())], vec![]]);
+
+        assert!(!first.is_compatible_with(&more_stages, 1));
+        assert!(first.is_compatible_with(&more_sets, 1));
+        assert!(!first.is_compatible_with(&more_sets, 2));
+    }
+
+    #[test]
+    fn push_constants_compatibility() {
+        let vertex = ShaderStages { vertex: true, .. ShaderStages::none() };
+        let fragment = ShaderStages { fragment: true, .. ShaderStages::none() };
+        let sets = vec![vec![buffer(0, false, ShaderStages::all())]];
+
+        let first = PcDesc(sets.clone(), vec![pc_range(0, 16, vertex), pc_range(16, 8, fragment)]);
+        let reordered = PcDesc(sets.clone(), vec![pc_range(16, 8, fragment),
+                                                  pc_range(0, 16, vertex)]);
+        let different = PcDesc(sets.clone(), vec![pc_range(0, 16, vertex)]);
+
+        assert!(first.is_compatible_with(&reordered, 1));
+        assert!(!first.is_compatible_with(&different, 0));
+        assert!(!first.is_compatible_with(&Desc(sets), 1));
+    }
+}
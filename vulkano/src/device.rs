@@ -26,8 +26,15 @@ use instance::Features;
 use instance::Instance;
 use instance::PhysicalDevice;
 use instance::QueueFamily;
+use descriptor::descriptor_set::StdDescriptorPool;
+use memory::AllocationError;
 use memory::pool::StdMemoryPool;
+use memory::sparse::SparseBindError;
+use memory::sparse::SparseBindSubmission;
+use memory::sparse::SparseBinds;
+use sync::FencePool;
 use sync::Semaphore;
+use sync::SemaphorePool;
 
 use Error;
 use OomError;
@@ -46,10 +53,28 @@ pub struct Device {
     device: vk::Device,
     vk: vk::DevicePointers,
     standard_pool: Mutex<Option<Arc<StdMemoryPool>>>,
+    standard_descriptor_pool: Mutex<Option<Arc<StdDescriptorPool>>>,
+    semaphore_pool: Mutex<Option<Arc<SemaphorePool>>>,
+    fence_pool: Mutex<Option<Arc<FencePool>>>,
+    memory_usage: Mutex<MemoryUsage>,
+    non_coherent_atom_size: usize,
     features: Features,
     extensions: DeviceExtensions,
 }
 
+// Live device memory allocations of a device, compared to the limits of the physical device.
+#[derive(Debug)]
+struct MemoryUsage {
+    // Number of allocations that are alive.
+    allocation_count: u32,
+    // Maximum number of allocations that can be alive at the same time.
+    max_allocation_count: u32,
+    // Number of bytes allocated from each heap.
+    heap_usage: Vec<usize>,
+    // Size of each heap.
+    heap_sizes: Vec<usize>,
+}
+
 impl Device {
     /// Builds a new Vulkan device for the given physical device.
     ///
@@ -185,6 +210,16 @@ impl Device {
             device: device,
             vk: vk,
             standard_pool: Mutex::new(None),
+            standard_descriptor_pool: Mutex::new(None),
+            semaphore_pool: Mutex::new(None),
+            fence_pool: Mutex::new(None),
+            memory_usage: Mutex::new(MemoryUsage {
+                allocation_count: 0,
+                max_allocation_count: phys.limits().max_memory_allocation_count(),
+                heap_usage: vec![0; phys.memory_heaps().len()],
+                heap_sizes: phys.memory_heaps().map(|h| h.size()).collect(),
+            }),
+            non_coherent_atom_size: phys.limits().non_coherent_atom_size() as usize,
             features: requested_features.clone(),
             extensions: extensions.clone(),
         });
@@ -195,6 +230,12 @@ impl Device {
             *pool_dest = Some(StdMemoryPool::new(&device));
         }
 
+        // Creating the fence pool. Fences are only created when they are requested.
+        {
+            let mut pool_dest = device.fence_pool.lock().unwrap();
+            *pool_dest = Some(FencePool::new(&device));
+        }
+
         // Iterator for the produced queues.
         let output_queues = QueuesIter {
             next_queue: 0,
@@ -259,15 +300,142 @@ impl Device {
     pub fn standard_pool(&self) -> Arc<StdMemoryPool> {
         self.standard_pool.lock().unwrap().clone().unwrap()
     }
-}
 
-impl fmt::Debug for Device {
+    /// Returns the standard descriptor pool of this device.
+    ///
+    /// Use this pool if you don't want to manage descriptor pools yourself. The pool is created
+    /// the first time this function is called.
     #[inline]
-    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        write!(fmt, "<Vulkan device>")
+    pub fn standard_descriptor_pool(me: &Arc<Self>) -> Arc<StdDescriptorPool> {
+        let mut pool = me.standard_descriptor_pool.lock().unwrap();
+
+        if let Some(ref pool) = *pool {
+            return pool.clone();
+        }
+
+        let new_pool = StdDescriptorPool::new(me);
+        *pool = Some(new_pool.clone());
+        new_pool
+    }
+
+    /// Returns the pool of semaphores of this device.
+    ///
+    /// Use this pool to avoid creating and destroying semaphores every frame. The pool is created
+    /// the first time this function is called, and semaphores are only created when they are
+    /// requested.
+    #[inline]
+    pub fn semaphore_pool(me: &Arc<Self>) -> Arc<SemaphorePool> {
+        let mut pool = me.semaphore_pool.lock().unwrap();
+
+        if let Some(ref pool) = *pool {
+            return pool.clone();
+        }
+
+        let new_pool = SemaphorePool::new(me);
+        *pool = Some(new_pool.clone());
+        new_pool
+    }
+
+    /// Returns the pool of fences of this device.
+    ///
+    /// Submissions take their fence from this pool, so that fences are reused instead of being
+    /// created and destroyed every time.
+    #[inline]
+    pub fn fence_pool(&self) -> Arc<FencePool> {
+        self.fence_pool.lock().unwrap().clone().unwrap()
+    }
+
+    /// Returns the `non_coherent_atom_size` limit of the physical device.
+    ///
+    /// Flushing or invalidating ranges of non-coherent memory must be done with offsets and
+    /// sizes that are multiples of this value.
+    #[inline]
+    pub fn non_coherent_atom_size(&self) -> usize {
+        self.non_coherent_atom_size
+    }
+
+    /// Returns the number of device memory allocations of this device that are alive.
+    ///
+    /// The number of allocations is limited by the `max_memory_allocation_count` limit of the
+    /// physical device.
+    #[inline]
+    pub fn memory_allocation_count(&self) -> u32 {
+        self.memory_usage.lock().unwrap().allocation_count
+    }
+
+    /// Returns the number of bytes of the given heap that are used by the device memory
+    /// allocations of this device that are alive.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if `heap_id` is out of range.
+    ///
+    #[inline]
+    pub fn heap_usage(&self, heap_id: u32) -> usize {
+        self.memory_usage.lock().unwrap().heap_usage[heap_id as usize]
+    }
+
+    /// Records a new device memory allocation of `size` bytes from a heap. Returns an error if
+    /// the limit on the number of allocations would be exceeded or if the heap doesn't have
+    /// enough space left.
+    ///
+    /// Must be followed by a call to `release_memory` once the allocation has been freed or
+    /// has failed.
+    ///
+    /// Only the crate may call this, otherwise the counters would get out of sync with the real
+    /// allocations.
+    pub(crate) fn reserve_memory(&self, heap_id: u32, size: usize) -> Result<(), AllocationError> {
+        let mut usage = self.memory_usage.lock().unwrap();
+
+        if usage.allocation_count >= usage.max_allocation_count {
+            return Err(AllocationError::TooManyObjects);
+        }
+
+        let heap = heap_id as usize;
+        let remaining = usage.heap_sizes[heap].saturating_sub(usage.heap_usage[heap]);
+        if size > remaining {
+            return Err(AllocationError::OutOfHeapMemory {
+                heap: heap_id,
+                requested: size,
+                remaining: remaining,
+            });
+        }
+
+        usage.allocation_count += 1;
+        usage.heap_usage[heap] += size;
+        Ok(())
+    }
+
+    /// Records that a device memory allocation recorded with `reserve_memory` has been freed.
+    pub(crate) fn release_memory(&self, heap_id: u32, size: usize) {
+        let mut usage = self.memory_usage.lock().unwrap();
+        debug_assert!(usage.allocation_count >= 1, "No memory allocation to release");
+        debug_assert!(usage.heap_usage[heap_id as usize] >= size, "Released more than allocated");
+        usage.allocation_count -= 1;
+        usage.heap_usage[heap_id as usize] -= size;
+    }
+
+    /// Returns the number of bytes of the given heap that can still be allocated.
+    #[doc(hidden)]
+    pub fn remaining_heap_memory(&self, heap_id: u32) -> usize {
+        let usage = self.memory_usage.lock().unwrap();
+        let heap = heap_id as usize;
+        usage.heap_sizes[heap].saturating_sub(usage.heap_usage[heap])
+    }
+
+    // Replaces the limits on the memory allocations, so that tests can reach them.
+    #[cfg(test)]
+    #[doc(hidden)]
+    pub fn override_memory_limits(&self, max_allocation_count: u32, heap_sizes: Vec<usize>) {
+        let mut usage = self.memory_usage.lock().unwrap();
+        assert_eq!(heap_sizes.len(), usage.heap_sizes.len(), "Wrong number of heaps");
+        usage.max_allocation_count = max_allocation_count;
+        usage.heap_sizes = heap_sizes;
     }
 }
 
+impl_debug_handle!(Device);
+
 unsafe impl VulkanObject for Device {
     type Object = vk::Device;
 
@@ -290,6 +458,7 @@ impl Drop for Device {
     #[inline]
     fn drop(&mut self) {
         let _ = self.standard_pool.lock().unwrap().take();
+        let _ = self.standard_descriptor_pool.lock().unwrap().take();
 
         unsafe {
             self.vk.DeviceWaitIdle(self.device);
@@ -347,6 +516,8 @@ pub enum DeviceCreationError {
     /// There is no memory available on the device (ie. video memory).
     OutOfDeviceMemory,
     // FIXME: other values
+    /// The Vulkan implementation returned an error that this operation isn't supposed to return.
+    Unexpected(Error),
 }
 
 impl error::Error for DeviceCreationError {
@@ -355,6 +526,17 @@ impl error::Error for DeviceCreationError {
         match *self {
             DeviceCreationError::OutOfHostMemory => "no memory available on the host",
             DeviceCreationError::OutOfDeviceMemory => "no memory available on the graphical device",
+            DeviceCreationError::Unexpected(_) => {
+                "unexpected error returned by the Vulkan implementation"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            DeviceCreationError::Unexpected(ref err) => Some(err),
+            _ => None
         }
     }
 }
@@ -372,7 +554,7 @@ impl From<Error> for DeviceCreationError {
         match err {
             Error::OutOfHostMemory => DeviceCreationError::OutOfHostMemory,
             Error::OutOfDeviceMemory => DeviceCreationError::OutOfDeviceMemory,
-            _ => panic!("Unexpected error value: {}", err as i32)
+            _ => DeviceCreationError::Unexpected(err)
         }
     }
 }
@@ -438,6 +620,30 @@ impl Queue {
         self.wait_raw().unwrap();
     }
 
+    /// Submits a list of sparse binds to the queue.
+    ///
+    /// The buffers, images and memory of the binds are kept alive by the returned object until
+    /// the operation has finished.
+    ///
+    /// # Safety
+    ///
+    /// - The binding operation isn't synchronized with the command buffers submitted to the
+    ///   queue. You must wait for it to finish before using the resources, and the bound ranges
+    ///   must not be in use by the GPU.
+    /// - The ranges, offsets and memory types of the binds must respect the memory requirements
+    ///   of the resources.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if a resource or a memory doesn't belong to the same device as the queue.
+    ///
+    #[inline]
+    pub unsafe fn bind_sparse(&self, binds: SparseBinds)
+                              -> Result<SparseBindSubmission, SparseBindError>
+    {
+        binds.submit(self)
+    }
+
     // TODO: the design of this functions depends on https://github.com/KhronosGroup/Vulkan-Docs/issues/155
     /*// TODO: document
     #[doc(hidden)]
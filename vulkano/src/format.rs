@@ -101,9 +101,16 @@
 //!
 //! // TODO: storage formats
 //!
+use std::error;
+use std::fmt;
+use std::i32;
+use std::u32;
 use std::vec::IntoIter as VecIntoIter;
 use vk;
 
+use features::Features;
+use instance::PhysicalDevice;
+
 // TODO: add enumerations for color, depth, stencil and depthstencil formats
 
 /// Some data whose type must be known by the library.
@@ -176,6 +183,109 @@ macro_rules! formats {
                     )+
                 }
             }
+
+            /// Returns true if the features required in order to use this format are enabled in
+            /// `features`.
+            ///
+            /// Only compressed formats require a feature: `texture_compression_bc`,
+            /// `texture_compression_etc2` or `texture_compression_astc_ldr`.
+            #[inline]
+            pub fn features_enabled(&self, features: &Features) -> bool {
+                match *self {
+                    $(
+                        Format::$name => formats!(__inner_features__ features $($f_ty)*),
+                    )+
+                }
+            }
+
+            /// Returns the width and height in texels of a block of this format.
+            ///
+            /// Compressed formats store texels by blocks. Uncompressed formats have blocks of one
+            /// texel.
+            #[inline]
+            pub fn block_dimensions(&self) -> [u32; 2] {
+                match *self {
+                    $(
+                        Format::$name => formats!(__inner_block_dims__ $($f_ty)*),
+                    )+
+                }
+            }
+
+            /// Returns the size in bytes of a block of this format. For uncompressed formats,
+            /// this is the same as `size()`.
+            #[inline]
+            pub fn block_size(&self) -> Option<usize> {
+                match *self {
+                    $(
+                        Format::$name => formats!(__inner_block_size__ $sz; $($f_ty)*),
+                    )+
+                }
+            }
+
+            /// Returns the number of components of this format.
+            ///
+            /// For example `R8G8B8A8Unorm` has four components, and `D24Unorm_S8Uint` has two
+            /// (depth and stencil).
+            #[inline]
+            pub fn components(&self) -> u32 {
+                match *self {
+                    $(
+                        Format::$name => formats!(__inner_components__ $($f_ty)*),
+                    )+
+                }
+            }
+
+            /// Returns true if this is an sRGB format.
+            ///
+            /// sRGB formats have a UNORM twin with the same layout, see `unorm_equivalent`.
+            #[inline]
+            pub fn is_srgb(&self) -> bool {
+                self.unorm_equivalent().is_some()
+            }
+
+            /// Returns the UNORM format that has the same layout as this sRGB format.
+            ///
+            /// Returns `None` if this isn't an sRGB format.
+            #[inline]
+            pub fn unorm_equivalent(&self) -> Option<Format> {
+                match *self {
+                    $(
+                        Format::$name => formats!(__inner_unorm__ $($f_ty)*),
+                    )+
+                }
+            }
+
+            /// Returns the sRGB format that has the same layout as this UNORM format.
+            ///
+            /// Returns `None` if this format doesn't have an sRGB twin, which includes sRGB
+            /// formats themselves.
+            pub fn srgb_equivalent(&self) -> Option<Format> {
+                $(
+                    if formats!(__inner_unorm__ $($f_ty)*) == Some(*self) {
+                        return Some(Format::$name);
+                    }
+                )+
+
+                None
+            }
+
+            /// Returns true if the format has a depth component.
+            #[inline]
+            pub fn has_depth(&self) -> bool {
+                match self.ty() {
+                    FormatTy::Depth | FormatTy::DepthStencil => true,
+                    _ => false
+                }
+            }
+
+            /// Returns true if the format has a stencil component.
+            #[inline]
+            pub fn has_stencil(&self) -> bool {
+                match self.ty() {
+                    FormatTy::Stencil | FormatTy::DepthStencil => true,
+                    _ => false
+                }
+            }
         }
 
         $(
@@ -189,7 +299,7 @@ macro_rules! formats {
         )+
     );
 
-    (__inner_impl__ $name:ident float=$num:expr) => {
+    (__inner_impl__ $name:ident float=$num:tt $($rest:tt)*) => {
         unsafe impl FormatDesc for $name {
             type ClearValue = [f32; $num];
 
@@ -274,6 +384,11 @@ macro_rules! formats {
             #[inline(always)]
             fn is_depth(&self) -> bool { true }
         }
+
+        unsafe impl PossibleDepthOrStencilFormatDesc for $name {
+            #[inline(always)]
+            fn is_depth_or_stencil(&self) -> bool { true }
+        }
     };
 
     (__inner_impl__ $name:ident stencil) => {
@@ -295,6 +410,11 @@ macro_rules! formats {
             #[inline(always)]
             fn is_stencil(&self) -> bool { true }
         }
+
+        unsafe impl PossibleDepthOrStencilFormatDesc for $name {
+            #[inline(always)]
+            fn is_depth_or_stencil(&self) -> bool { true }
+        }
     };
 
     (__inner_impl__ $name:ident depthstencil) => {
@@ -316,9 +436,14 @@ macro_rules! formats {
             #[inline(always)]
             fn is_depth_stencil(&self) -> bool { true }
         }
+
+        unsafe impl PossibleDepthOrStencilFormatDesc for $name {
+            #[inline(always)]
+            fn is_depth_or_stencil(&self) -> bool { true }
+        }
     };
 
-    (__inner_impl__ $name:ident compressed = $feature:ident) => {
+    (__inner_impl__ $name:ident compressed = $feature:ident; $($rest:tt)*) => {
         unsafe impl FormatDesc for $name {
             type ClearValue = [f32; 4];
 
@@ -343,13 +468,36 @@ macro_rules! formats {
         }
     };
 
-    (__inner_ty__ $name:ident float=$num:tt) => { FormatTy::Float };
+    (__inner_ty__ $name:ident float=$num:tt $($rest:tt)*) => { FormatTy::Float };
     (__inner_ty__ $name:ident uint=$num:tt) => { FormatTy::Uint };
     (__inner_ty__ $name:ident sint=$num:tt) => { FormatTy::Sint };
     (__inner_ty__ $name:ident depth) => { FormatTy::Depth };
     (__inner_ty__ $name:ident stencil) => { FormatTy::Stencil };
     (__inner_ty__ $name:ident depthstencil) => { FormatTy::DepthStencil };
-    (__inner_ty__ $name:ident compressed=$f:tt) => { FormatTy::Compressed };
+    (__inner_ty__ $name:ident compressed=$f:tt; $($rest:tt)*) => { FormatTy::Compressed };
+
+    (__inner_features__ $features:ident compressed=$f:ident; $($rest:tt)*) => { $features.$f };
+    (__inner_features__ $features:ident $($f_ty:tt)*) => { true };
+
+    (__inner_block_dims__ compressed=$f:ident; block=$dims:expr; $($rest:tt)*) => { $dims };
+    (__inner_block_dims__ $($f_ty:tt)*) => { [1, 1] };
+
+    (__inner_block_size__ $sz:expr; compressed=$f:ident; block=$dims:expr; size=$bsz:expr;
+                          $($rest:tt)*) => { Some($bsz) };
+    (__inner_block_size__ $sz:expr; $($f_ty:tt)*) => { $sz };
+
+    (__inner_components__ float=$num:tt $($rest:tt)*) => { $num };
+    (__inner_components__ uint=$num:tt) => { $num };
+    (__inner_components__ sint=$num:tt) => { $num };
+    (__inner_components__ depth) => { 1 };
+    (__inner_components__ stencil) => { 1 };
+    (__inner_components__ depthstencil) => { 2 };
+    (__inner_components__ compressed=$f:ident; block=$dims:expr; size=$bsz:expr;
+                          components=$num:tt $($rest:tt)*) => { $num };
+
+    (__inner_unorm__ unorm=$unorm:ident $($rest:tt)*) => { Some(Format::$unorm) };
+    (__inner_unorm__ $first:tt $($rest:tt)*) => { formats!(__inner_unorm__ $($rest)*) };
+    (__inner_unorm__) => { None };
 
 
     (__inner_strongstorage__ $name:ident $ty:ty) => {
@@ -375,49 +523,49 @@ formats! {
     R8Sscaled => FORMAT_R8_SSCALED [Some(1)] [float=1] {i8},
     R8Uint => FORMAT_R8_UINT [Some(1)] [uint=1] {u8},
     R8Sint => FORMAT_R8_SINT [Some(1)] [sint=1] {i8},
-    R8Srgb => FORMAT_R8_SRGB [Some(1)] [float=1] {u8},
+    R8Srgb => FORMAT_R8_SRGB [Some(1)] [float=1; unorm=R8Unorm] {u8},
     R8G8Unorm => FORMAT_R8G8_UNORM [Some(2)] [float=2] {[u8; 2]},
     R8G8Snorm => FORMAT_R8G8_SNORM [Some(2)] [float=2] {[i8; 2]},
     R8G8Uscaled => FORMAT_R8G8_USCALED [Some(2)] [float=2] {[u8; 2]},
     R8G8Sscaled => FORMAT_R8G8_SSCALED [Some(2)] [float=2] {[i8; 2]},
     R8G8Uint => FORMAT_R8G8_UINT [Some(2)] [uint=2] {[u8; 2]},
     R8G8Sint => FORMAT_R8G8_SINT [Some(2)] [sint=2] {[i8; 2]},
-    R8G8Srgb => FORMAT_R8G8_SRGB [Some(2)] [float=2] {[u8; 2]},
+    R8G8Srgb => FORMAT_R8G8_SRGB [Some(2)] [float=2; unorm=R8G8Unorm] {[u8; 2]},
     R8G8B8Unorm => FORMAT_R8G8B8_UNORM [Some(3)] [float=3] {[u8; 3]},
     R8G8B8Snorm => FORMAT_R8G8B8_SNORM [Some(3)] [float=3] {[i8; 3]},
     R8G8B8Uscaled => FORMAT_R8G8B8_USCALED [Some(3)] [float=3] {[u8; 3]},
     R8G8B8Sscaled => FORMAT_R8G8B8_SSCALED [Some(3)] [float=3] {[i8; 3]},
     R8G8B8Uint => FORMAT_R8G8B8_UINT [Some(3)] [uint=3] {[u8; 3]},
     R8G8B8Sint => FORMAT_R8G8B8_SINT [Some(3)] [sint=3] {[i8; 3]},
-    R8G8B8Srgb => FORMAT_R8G8B8_SRGB [Some(3)] [float=3] {[u8; 3]},
+    R8G8B8Srgb => FORMAT_R8G8B8_SRGB [Some(3)] [float=3; unorm=R8G8B8Unorm] {[u8; 3]},
     B8G8R8Unorm => FORMAT_B8G8R8_UNORM [Some(3)] [float=3] {[u8; 3]},
     B8G8R8Snorm => FORMAT_B8G8R8_SNORM [Some(3)] [float=3] {[i8; 3]},
     B8G8R8Uscaled => FORMAT_B8G8R8_USCALED [Some(3)] [float=3] {[u8; 3]},
     B8G8R8Sscaled => FORMAT_B8G8R8_SSCALED [Some(3)] [float=3] {[i8; 3]},
     B8G8R8Uint => FORMAT_B8G8R8_UINT [Some(3)] [uint=3] {[u8; 3]},
     B8G8R8Sint => FORMAT_B8G8R8_SINT [Some(3)] [sint=3] {[i8; 3]},
-    B8G8R8Srgb => FORMAT_B8G8R8_SRGB [Some(3)] [float=3] {[u8; 3]},
+    B8G8R8Srgb => FORMAT_B8G8R8_SRGB [Some(3)] [float=3; unorm=B8G8R8Unorm] {[u8; 3]},
     R8G8B8A8Unorm => FORMAT_R8G8B8A8_UNORM [Some(4)] [float=4] {[u8; 4]},
     R8G8B8A8Snorm => FORMAT_R8G8B8A8_SNORM [Some(4)] [float=4] {[i8; 4]},
     R8G8B8A8Uscaled => FORMAT_R8G8B8A8_USCALED [Some(4)] [float=4] {[u8; 4]},
     R8G8B8A8Sscaled => FORMAT_R8G8B8A8_SSCALED [Some(4)] [float=4] {[i8; 4]},
     R8G8B8A8Uint => FORMAT_R8G8B8A8_UINT [Some(4)] [uint=4] {[u8; 4]},
     R8G8B8A8Sint => FORMAT_R8G8B8A8_SINT [Some(4)] [sint=4] {[i8; 4]},
-    R8G8B8A8Srgb => FORMAT_R8G8B8A8_SRGB [Some(4)] [float=4] {[u8; 4]},
+    R8G8B8A8Srgb => FORMAT_R8G8B8A8_SRGB [Some(4)] [float=4; unorm=R8G8B8A8Unorm] {[u8; 4]},
     B8G8R8A8Unorm => FORMAT_B8G8R8A8_UNORM [Some(4)] [float=4] {[u8; 4]},
     B8G8R8A8Snorm => FORMAT_B8G8R8A8_SNORM [Some(4)] [float=4] {[i8; 4]},
     B8G8R8A8Uscaled => FORMAT_B8G8R8A8_USCALED [Some(4)] [float=4] {[u8; 4]},
     B8G8R8A8Sscaled => FORMAT_B8G8R8A8_SSCALED [Some(4)] [float=4] {[i8; 4]},
     B8G8R8A8Uint => FORMAT_B8G8R8A8_UINT [Some(4)] [uint=4] {[u8; 4]},
     B8G8R8A8Sint => FORMAT_B8G8R8A8_SINT [Some(4)] [sint=4] {[i8; 4]},
-    B8G8R8A8Srgb => FORMAT_B8G8R8A8_SRGB [Some(4)] [float=4] {[u8; 4]},
+    B8G8R8A8Srgb => FORMAT_B8G8R8A8_SRGB [Some(4)] [float=4; unorm=B8G8R8A8Unorm] {[u8; 4]},
     A8B8G8R8UnormPack32 => FORMAT_A8B8G8R8_UNORM_PACK32 [Some(4)] [float=4] {[u8; 4]},
     A8B8G8R8SnormPack32 => FORMAT_A8B8G8R8_SNORM_PACK32 [Some(4)] [float=4] {[i8; 4]},
     A8B8G8R8UscaledPack32 => FORMAT_A8B8G8R8_USCALED_PACK32 [Some(4)] [float=4] {[u8; 4]},
     A8B8G8R8SscaledPack32 => FORMAT_A8B8G8R8_SSCALED_PACK32 [Some(4)] [float=4] {[i8; 4]},
     A8B8G8R8UintPack32 => FORMAT_A8B8G8R8_UINT_PACK32 [Some(4)] [uint=4] {[u8; 4]},
     A8B8G8R8SintPack32 => FORMAT_A8B8G8R8_SINT_PACK32 [Some(4)] [sint=4] {[i8; 4]},
-    A8B8G8R8SrgbPack32 => FORMAT_A8B8G8R8_SRGB_PACK32 [Some(4)] [float=4] {[u8; 4]},
+    A8B8G8R8SrgbPack32 => FORMAT_A8B8G8R8_SRGB_PACK32 [Some(4)] [float=4; unorm=A8B8G8R8UnormPack32] {[u8; 4]},
     A2R10G10B10UnormPack32 => FORMAT_A2R10G10B10_UNORM_PACK32 [Some(4)] [float=4] {u32},
     A2R10G10B10SnormPack32 => FORMAT_A2R10G10B10_SNORM_PACK32 [Some(4)] [float=4] {u32},
     A2R10G10B10UscaledPack32 => FORMAT_A2R10G10B10_USCALED_PACK32 [Some(4)] [float=4] {u32},
@@ -491,60 +639,548 @@ formats! {
     D16Unorm_S8Uint => FORMAT_D16_UNORM_S8_UINT [None] [depthstencil] {},
     D24Unorm_S8Uint => FORMAT_D24_UNORM_S8_UINT [None] [depthstencil] {},
     D32Sfloat_S8Uint => FORMAT_D32_SFLOAT_S8_UINT [None] [depthstencil] {},
-    BC1_RGBUnormBlock => FORMAT_BC1_RGB_UNORM_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC1_RGBSrgbBlock => FORMAT_BC1_RGB_SRGB_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC1_RGBAUnormBlock => FORMAT_BC1_RGBA_UNORM_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC1_RGBASrgbBlock => FORMAT_BC1_RGBA_SRGB_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC2UnormBlock => FORMAT_BC2_UNORM_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC2SrgbBlock => FORMAT_BC2_SRGB_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC3UnormBlock => FORMAT_BC3_UNORM_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC3SrgbBlock => FORMAT_BC3_SRGB_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC4UnormBlock => FORMAT_BC4_UNORM_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC4SnormBlock => FORMAT_BC4_SNORM_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC5UnormBlock => FORMAT_BC5_UNORM_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC5SnormBlock => FORMAT_BC5_SNORM_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC6HUfloatBlock => FORMAT_BC6H_UFLOAT_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC6HSfloatBlock => FORMAT_BC6H_SFLOAT_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC7UnormBlock => FORMAT_BC7_UNORM_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC7SrgbBlock => FORMAT_BC7_SRGB_BLOCK [None] [compressed=texture_compression_bc] {},
-    ETC2_R8G8B8UnormBlock => FORMAT_ETC2_R8G8B8_UNORM_BLOCK [None] [compressed=texture_compression_etc2] {},
-    ETC2_R8G8B8SrgbBlock => FORMAT_ETC2_R8G8B8_SRGB_BLOCK [None] [compressed=texture_compression_etc2] {},
-    ETC2_R8G8B8A1UnormBlock => FORMAT_ETC2_R8G8B8A1_UNORM_BLOCK [None] [compressed=texture_compression_etc2] {},
-    ETC2_R8G8B8A1SrgbBlock => FORMAT_ETC2_R8G8B8A1_SRGB_BLOCK [None] [compressed=texture_compression_etc2] {},
-    ETC2_R8G8B8A8UnormBlock => FORMAT_ETC2_R8G8B8A8_UNORM_BLOCK [None] [compressed=texture_compression_etc2] {},
-    ETC2_R8G8B8A8SrgbBlock => FORMAT_ETC2_R8G8B8A8_SRGB_BLOCK [None] [compressed=texture_compression_etc2] {},
-    EAC_R11UnormBlock => FORMAT_EAC_R11_UNORM_BLOCK [None] [compressed=texture_compression_etc2] {},
-    EAC_R11SnormBlock => FORMAT_EAC_R11_SNORM_BLOCK [None] [compressed=texture_compression_etc2] {},
-    EAC_R11G11UnormBlock => FORMAT_EAC_R11G11_UNORM_BLOCK [None] [compressed=texture_compression_etc2] {},
-    EAC_R11G11SnormBlock => FORMAT_EAC_R11G11_SNORM_BLOCK [None] [compressed=texture_compression_etc2] {},
-    ASTC_4x4UnormBlock => FORMAT_ASTC_4x4_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_4x4SrgbBlock => FORMAT_ASTC_4x4_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_5x4UnormBlock => FORMAT_ASTC_5x4_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_5x4SrgbBlock => FORMAT_ASTC_5x4_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_5x5UnormBlock => FORMAT_ASTC_5x5_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_5x5SrgbBlock => FORMAT_ASTC_5x5_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_6x5UnormBlock => FORMAT_ASTC_6x5_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_6x5SrgbBlock => FORMAT_ASTC_6x5_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_6x6UnormBlock => FORMAT_ASTC_6x6_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_6x6SrgbBlock => FORMAT_ASTC_6x6_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_8x5UnormBlock => FORMAT_ASTC_8x5_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_8x5SrgbBlock => FORMAT_ASTC_8x5_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_8x6UnormBlock => FORMAT_ASTC_8x6_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_8x6SrgbBlock => FORMAT_ASTC_8x6_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_8x8UnormBlock => FORMAT_ASTC_8x8_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_8x8SrgbBlock => FORMAT_ASTC_8x8_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_10x5UnormBlock => FORMAT_ASTC_10x5_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_10x5SrgbBlock => FORMAT_ASTC_10x5_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_10x6UnormBlock => FORMAT_ASTC_10x6_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_10x6SrgbBlock => FORMAT_ASTC_10x6_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_10x8UnormBlock => FORMAT_ASTC_10x8_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_10x8SrgbBlock => FORMAT_ASTC_10x8_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_10x10UnormBlock => FORMAT_ASTC_10x10_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_10x10SrgbBlock => FORMAT_ASTC_10x10_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_12x10UnormBlock => FORMAT_ASTC_12x10_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_12x10SrgbBlock => FORMAT_ASTC_12x10_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_12x12UnormBlock => FORMAT_ASTC_12x12_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_12x12SrgbBlock => FORMAT_ASTC_12x12_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
+    BC1_RGBUnormBlock => FORMAT_BC1_RGB_UNORM_BLOCK [None] [compressed=texture_compression_bc; block=[4, 4]; size=8; components=3] {},
+    BC1_RGBSrgbBlock => FORMAT_BC1_RGB_SRGB_BLOCK [None] [compressed=texture_compression_bc; block=[4, 4]; size=8; components=3; unorm=BC1_RGBUnormBlock] {},
+    BC1_RGBAUnormBlock => FORMAT_BC1_RGBA_UNORM_BLOCK [None] [compressed=texture_compression_bc; block=[4, 4]; size=8; components=4] {},
+    BC1_RGBASrgbBlock => FORMAT_BC1_RGBA_SRGB_BLOCK [None] [compressed=texture_compression_bc; block=[4, 4]; size=8; components=4; unorm=BC1_RGBAUnormBlock] {},
+    BC2UnormBlock => FORMAT_BC2_UNORM_BLOCK [None] [compressed=texture_compression_bc; block=[4, 4]; size=16; components=4] {},
+    BC2SrgbBlock => FORMAT_BC2_SRGB_BLOCK [None] [compressed=texture_compression_bc; block=[4, 4]; size=16; components=4; unorm=BC2UnormBlock] {},
+    BC3UnormBlock => FORMAT_BC3_UNORM_BLOCK [None] [compressed=texture_compression_bc; block=[4, 4]; size=16; components=4] {},
+    BC3SrgbBlock => FORMAT_BC3_SRGB_BLOCK [None] [compressed=texture_compression_bc; block=[4, 4]; size=16; components=4; unorm=BC3UnormBlock] {},
+    BC4UnormBlock => FORMAT_BC4_UNORM_BLOCK [None] [compressed=texture_compression_bc; block=[4, 4]; size=8; components=1] {},
+    BC4SnormBlock => FORMAT_BC4_SNORM_BLOCK [None] [compressed=texture_compression_bc; block=[4, 4]; size=8; components=1] {},
+    BC5UnormBlock => FORMAT_BC5_UNORM_BLOCK [None] [compressed=texture_compression_bc; block=[4, 4]; size=16; components=2] {},
+    BC5SnormBlock => FORMAT_BC5_SNORM_BLOCK [None] [compressed=texture_compression_bc; block=[4, 4]; size=16; components=2] {},
+    BC6HUfloatBlock => FORMAT_BC6H_UFLOAT_BLOCK [None] [compressed=texture_compression_bc; block=[4, 4]; size=16; components=3] {},
+    BC6HSfloatBlock => FORMAT_BC6H_SFLOAT_BLOCK [None] [compressed=texture_compression_bc; block=[4, 4]; size=16; components=3] {},
+    BC7UnormBlock => FORMAT_BC7_UNORM_BLOCK [None] [compressed=texture_compression_bc; block=[4, 4]; size=16; components=4] {},
+    BC7SrgbBlock => FORMAT_BC7_SRGB_BLOCK [None] [compressed=texture_compression_bc; block=[4, 4]; size=16; components=4; unorm=BC7UnormBlock] {},
+    ETC2_R8G8B8UnormBlock => FORMAT_ETC2_R8G8B8_UNORM_BLOCK [None] [compressed=texture_compression_etc2; block=[4, 4]; size=8; components=3] {},
+    ETC2_R8G8B8SrgbBlock => FORMAT_ETC2_R8G8B8_SRGB_BLOCK [None] [compressed=texture_compression_etc2; block=[4, 4]; size=8; components=3; unorm=ETC2_R8G8B8UnormBlock] {},
+    ETC2_R8G8B8A1UnormBlock => FORMAT_ETC2_R8G8B8A1_UNORM_BLOCK [None] [compressed=texture_compression_etc2; block=[4, 4]; size=8; components=4] {},
+    ETC2_R8G8B8A1SrgbBlock => FORMAT_ETC2_R8G8B8A1_SRGB_BLOCK [None] [compressed=texture_compression_etc2; block=[4, 4]; size=8; components=4; unorm=ETC2_R8G8B8A1UnormBlock] {},
+    ETC2_R8G8B8A8UnormBlock => FORMAT_ETC2_R8G8B8A8_UNORM_BLOCK [None] [compressed=texture_compression_etc2; block=[4, 4]; size=16; components=4] {},
+    ETC2_R8G8B8A8SrgbBlock => FORMAT_ETC2_R8G8B8A8_SRGB_BLOCK [None] [compressed=texture_compression_etc2; block=[4, 4]; size=16; components=4; unorm=ETC2_R8G8B8A8UnormBlock] {},
+    EAC_R11UnormBlock => FORMAT_EAC_R11_UNORM_BLOCK [None] [compressed=texture_compression_etc2; block=[4, 4]; size=8; components=1] {},
+    EAC_R11SnormBlock => FORMAT_EAC_R11_SNORM_BLOCK [None] [compressed=texture_compression_etc2; block=[4, 4]; size=8; components=1] {},
+    EAC_R11G11UnormBlock => FORMAT_EAC_R11G11_UNORM_BLOCK [None] [compressed=texture_compression_etc2; block=[4, 4]; size=16; components=2] {},
+    EAC_R11G11SnormBlock => FORMAT_EAC_R11G11_SNORM_BLOCK [None] [compressed=texture_compression_etc2; block=[4, 4]; size=16; components=2] {},
+    ASTC_4x4UnormBlock => FORMAT_ASTC_4x4_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr; block=[4, 4]; size=16; components=4] {},
+    ASTC_4x4SrgbBlock => FORMAT_ASTC_4x4_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr; block=[4, 4]; size=16; components=4; unorm=ASTC_4x4UnormBlock] {},
+    ASTC_5x4UnormBlock => FORMAT_ASTC_5x4_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr; block=[5, 4]; size=16; components=4] {},
+    ASTC_5x4SrgbBlock => FORMAT_ASTC_5x4_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr; block=[5, 4]; size=16; components=4; unorm=ASTC_5x4UnormBlock] {},
+    ASTC_5x5UnormBlock => FORMAT_ASTC_5x5_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr; block=[5, 5]; size=16; components=4] {},
+    ASTC_5x5SrgbBlock => FORMAT_ASTC_5x5_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr; block=[5, 5]; size=16; components=4; unorm=ASTC_5x5UnormBlock] {},
+    ASTC_6x5UnormBlock => FORMAT_ASTC_6x5_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr; block=[6, 5]; size=16; components=4] {},
+    ASTC_6x5SrgbBlock => FORMAT_ASTC_6x5_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr; block=[6, 5]; size=16; components=4; unorm=ASTC_6x5UnormBlock] {},
+    ASTC_6x6UnormBlock => FORMAT_ASTC_6x6_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr; block=[6, 6]; size=16; components=4] {},
+    ASTC_6x6SrgbBlock => FORMAT_ASTC_6x6_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr; block=[6, 6]; size=16; components=4; unorm=ASTC_6x6UnormBlock] {},
+    ASTC_8x5UnormBlock => FORMAT_ASTC_8x5_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr; block=[8, 5]; size=16; components=4] {},
+    ASTC_8x5SrgbBlock => FORMAT_ASTC_8x5_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr; block=[8, 5]; size=16; components=4; unorm=ASTC_8x5UnormBlock] {},
+    ASTC_8x6UnormBlock => FORMAT_ASTC_8x6_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr; block=[8, 6]; size=16; components=4] {},
+    ASTC_8x6SrgbBlock => FORMAT_ASTC_8x6_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr; block=[8, 6]; size=16; components=4; unorm=ASTC_8x6UnormBlock] {},
+    ASTC_8x8UnormBlock => FORMAT_ASTC_8x8_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr; block=[8, 8]; size=16; components=4] {},
+    ASTC_8x8SrgbBlock => FORMAT_ASTC_8x8_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr; block=[8, 8]; size=16; components=4; unorm=ASTC_8x8UnormBlock] {},
+    ASTC_10x5UnormBlock => FORMAT_ASTC_10x5_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr; block=[10, 5]; size=16; components=4] {},
+    ASTC_10x5SrgbBlock => FORMAT_ASTC_10x5_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr; block=[10, 5]; size=16; components=4; unorm=ASTC_10x5UnormBlock] {},
+    ASTC_10x6UnormBlock => FORMAT_ASTC_10x6_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr; block=[10, 6]; size=16; components=4] {},
+    ASTC_10x6SrgbBlock => FORMAT_ASTC_10x6_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr; block=[10, 6]; size=16; components=4; unorm=ASTC_10x6UnormBlock] {},
+    ASTC_10x8UnormBlock => FORMAT_ASTC_10x8_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr; block=[10, 8]; size=16; components=4] {},
+    ASTC_10x8SrgbBlock => FORMAT_ASTC_10x8_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr; block=[10, 8]; size=16; components=4; unorm=ASTC_10x8UnormBlock] {},
+    ASTC_10x10UnormBlock => FORMAT_ASTC_10x10_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr; block=[10, 10]; size=16; components=4] {},
+    ASTC_10x10SrgbBlock => FORMAT_ASTC_10x10_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr; block=[10, 10]; size=16; components=4; unorm=ASTC_10x10UnormBlock] {},
+    ASTC_12x10UnormBlock => FORMAT_ASTC_12x10_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr; block=[12, 10]; size=16; components=4] {},
+    ASTC_12x10SrgbBlock => FORMAT_ASTC_12x10_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr; block=[12, 10]; size=16; components=4; unorm=ASTC_12x10UnormBlock] {},
+    ASTC_12x12UnormBlock => FORMAT_ASTC_12x12_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr; block=[12, 12]; size=16; components=4] {},
+    ASTC_12x12SrgbBlock => FORMAT_ASTC_12x12_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr; block=[12, 12]; size=16; components=4; unorm=ASTC_12x12UnormBlock] {},
+}
+
+impl Format {
+    /// Returns the number of bytes required to store a region of texels of the given extent in
+    /// this format, in other words the number of blocks covering the region multiplied by the
+    /// size of a block.
+    ///
+    /// Returns `None` if the size of the format is irrelevant.
+    pub fn data_size(&self, extent: [u32; 3]) -> Option<usize> {
+        let block_size = match self.block_size() {
+            Some(s) => s,
+            None => return None,
+        };
+
+        let block_dims = self.block_dimensions();
+        let blocks_x = (extent[0] + block_dims[0] - 1) / block_dims[0];
+        let blocks_y = (extent[1] + block_dims[1] - 1) / block_dims[1];
+        Some(blocks_x as usize * blocks_y as usize * extent[2] as usize * block_size)
+    }
+
+    /// Returns true if pixels of `size` bytes can be used to describe the content of an image of
+    /// this format.
+    ///
+    /// For uncompressed formats, the size must match the size of a texel. For compressed formats,
+    /// a block must be made of a whole number of pixels of this size.
+    pub fn accepts_pixel_size(&self, size: usize) -> bool {
+        if size == 0 {
+            return false;
+        }
+
+        if self.ty() == FormatTy::Compressed {
+            match self.block_size() {
+                Some(block_size) => block_size % size == 0,
+                None => false,
+            }
+        } else {
+            self.size() == Some(size)
+        }
+    }
+
+    /// Returns true if this is a block-compressed format.
+    ///
+    /// Compressed formats are the only ones whose blocks are larger than one texel.
+    #[inline]
+    pub fn is_compressed(&self) -> bool {
+        self.ty() == FormatTy::Compressed
+    }
+
+    /// Returns true if this format only has a depth aspect.
+    #[inline]
+    pub fn is_depth(&self) -> bool {
+        self.ty() == FormatTy::Depth
+    }
+
+    /// Returns true if this format only has a stencil aspect.
+    #[inline]
+    pub fn is_stencil(&self) -> bool {
+        self.ty() == FormatTy::Stencil
+    }
+
+    /// Returns true if this format has both a depth and a stencil aspect.
+    #[inline]
+    pub fn is_depth_stencil(&self) -> bool {
+        self.ty() == FormatTy::DepthStencil
+    }
+
+    /// Returns true if this format can be used for a depth-stencil attachment, in other words if
+    /// it has a depth aspect, a stencil aspect, or both.
+    #[inline]
+    pub fn is_depth_or_stencil(&self) -> bool {
+        self.is_depth() || self.is_stencil() || self.is_depth_stencil()
+    }
+
+    /// Returns true if `value` can be used to clear an image or attachment of this format.
+    ///
+    /// Floating-point and normalized formats expect `ClearValue::Float`, integer formats expect
+    /// `ClearValue::Int` or `ClearValue::Uint` depending on their signedness, and depth and/or
+    /// stencil formats expect the corresponding depth and/or stencil value. Compressed formats
+    /// can't be cleared. `ClearValue::None` is never valid.
+    pub fn validate_clear_value(&self, value: &ClearValue) -> bool {
+        match (self.ty(), *value) {
+            (FormatTy::Float, ClearValue::Float(_)) => true,
+            (FormatTy::Sint, ClearValue::Int(_)) => true,
+            (FormatTy::Uint, ClearValue::Uint(_)) => true,
+            (FormatTy::Depth, ClearValue::Depth(_)) => true,
+            (FormatTy::Stencil, ClearValue::Stencil(_)) => true,
+            (FormatTy::DepthStencil, ClearValue::DepthStencil(_)) => true,
+            _ => false
+        }
+    }
+
+    /// Builds the clear value of this format from four floating-point components.
+    ///
+    /// This is useful to clear attachments whose format isn't known at compile time.
+    ///
+    /// - Float, UNORM, SNORM and sRGB formats use the components as they are.
+    /// - Integer formats truncate the components towards zero and clamp them to the range of
+    ///   the integer type.
+    /// - Depth, stencil and depth-stencil formats take the first component as the depth value
+    ///   and the second component as the stencil value, converted like an unsigned integer.
+    ///
+    /// Use `try_decode_float_clear_value` to get an error instead of a lossy conversion.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the format is compressed, as compressed images can't be cleared.
+    ///
+    pub fn decode_float_clear_value(&self, value: [f32; 4]) -> ClearValue {
+        match self.ty() {
+            FormatTy::Float => ClearValue::Float(value),
+            FormatTy::Uint => {
+                ClearValue::Uint([float_to_u32(value[0]), float_to_u32(value[1]),
+                                  float_to_u32(value[2]), float_to_u32(value[3])])
+            },
+            FormatTy::Sint => {
+                ClearValue::Int([float_to_i32(value[0]), float_to_i32(value[1]),
+                                 float_to_i32(value[2]), float_to_i32(value[3])])
+            },
+            FormatTy::Depth => ClearValue::Depth(value[0]),
+            FormatTy::Stencil => ClearValue::Stencil(float_to_u32(value[1])),
+            FormatTy::DepthStencil => ClearValue::DepthStencil((value[0],
+                                                                float_to_u32(value[1]))),
+            FormatTy::Compressed => panic!("Compressed formats can't be cleared"),
+        }
+    }
+
+    /// Same as `decode_float_clear_value`, but returns an error if the format is compressed or
+    /// if a component used by the format can't be represented exactly.
+    ///
+    /// For integer formats, all four components must be whole numbers within the range of the
+    /// integer type. The stencil component must be a whole non-negative number as well.
+    pub fn try_decode_float_clear_value(&self, value: [f32; 4])
+                                        -> Result<ClearValue, ClearValueConversionError>
+    {
+        let check = |component: usize, exact: bool| {
+            if exact {
+                Ok(())
+            } else {
+                Err(ClearValueConversionError::NotRepresentable {
+                    format: *self,
+                    component: component,
+                    value: value[component],
+                })
+            }
+        };
+
+        match self.ty() {
+            FormatTy::Uint => {
+                for (n, &v) in value.iter().enumerate() {
+                    try!(check(n, float_is_u32(v)));
+                }
+            },
+            FormatTy::Sint => {
+                for (n, &v) in value.iter().enumerate() {
+                    try!(check(n, float_is_i32(v)));
+                }
+            },
+            FormatTy::Stencil | FormatTy::DepthStencil => {
+                try!(check(1, float_is_u32(value[1])));
+            },
+            FormatTy::Compressed => {
+                return Err(ClearValueConversionError::CompressedFormat { format: *self });
+            },
+            FormatTy::Float | FormatTy::Depth => (),
+        }
+
+        Ok(self.decode_float_clear_value(value))
+    }
+
+    /// Queries the features that the physical device supports for this format.
+    ///
+    /// Same as `PhysicalDevice::format_properties`.
+    #[inline]
+    pub fn properties(&self, physical: &PhysicalDevice) -> FormatProperties {
+        physical.format_properties(*self)
+    }
+
+    /// Returns true if images of this format with the optimal tiling can be sampled with linear
+    /// filtering on the given physical device.
+    #[inline]
+    pub fn can_be_sampled_linear(&self, physical: &PhysicalDevice) -> bool {
+        self.properties(physical).can_be_sampled_linear()
+    }
+
+    /// Returns true if images of this format with the optimal tiling can be used as color
+    /// attachments on the given physical device.
+    #[inline]
+    pub fn can_be_color_attachment(&self, physical: &PhysicalDevice) -> bool {
+        self.properties(physical).can_be_color_attachment()
+    }
+
+    /// Returns true if images of this format with the optimal tiling can be used as the source
+    /// of a blit on the given physical device.
+    #[inline]
+    pub fn can_be_blit_src(&self, physical: &PhysicalDevice) -> bool {
+        self.properties(physical).can_be_blit_src()
+    }
+
+    /// Returns true if images of this format with the optimal tiling can be used as the
+    /// destination of a blit on the given physical device.
+    #[inline]
+    pub fn can_be_blit_dst(&self, physical: &PhysicalDevice) -> bool {
+        self.properties(physical).can_be_blit_dst()
+    }
+
+    /// Returns true if images of this format with the optimal tiling can be used as storage
+    /// images on the given physical device.
+    #[inline]
+    pub fn can_be_storage_image(&self, physical: &PhysicalDevice) -> bool {
+        self.properties(physical).can_be_storage_image()
+    }
+
+    /// Returns the features that every Vulkan implementation must support for images of this
+    /// format with the optimal tiling, according to the specifications.
+    ///
+    /// Only the requirements that don't depend on any device feature are included. Returns
+    /// `FormatFeatures::none()` for formats that have no such requirement.
+    pub fn required_optimal_features(&self) -> FormatFeatures {
+        let filterable = FormatFeatures {
+            sampled_image: true,
+            blit_src: true,
+            sampled_image_filter_linear: true,
+            .. FormatFeatures::none()
+        };
+
+        let color = FormatFeatures {
+            sampled_image: true,
+            blit_src: true,
+            color_attachment: true,
+            blit_dst: true,
+            .. FormatFeatures::none()
+        };
+
+        let color_filterable = FormatFeatures {
+            sampled_image_filter_linear: true,
+            color_attachment_blend: true,
+            .. color
+        };
+
+        match *self {
+            Format::B4G4R4A4UnormPack16 | Format::R8Snorm | Format::R8G8Snorm |
+            Format::B10G11R11UfloatPack32 | Format::E5B9G9R9UfloatPack32 => filterable,
+
+            Format::R8G8B8A8Snorm | Format::A8B8G8R8SnormPack32 => {
+                FormatFeatures { storage_image: true, .. filterable }
+            },
+
+            Format::R5G6B5UnormPack16 | Format::A1R5G5B5UnormPack16 | Format::R8Unorm |
+            Format::R8G8Unorm | Format::R8G8B8A8Srgb | Format::B8G8R8A8Unorm |
+            Format::B8G8R8A8Srgb | Format::A8B8G8R8UnormPack32 | Format::A8B8G8R8SrgbPack32 |
+            Format::A2B10G10R10UnormPack32 | Format::R16Sfloat |
+            Format::R16G16Sfloat => color_filterable,
+
+            Format::R8G8B8A8Unorm | Format::R16G16B16A16Sfloat => {
+                FormatFeatures { storage_image: true, .. color_filterable }
+            },
+
+            Format::R8Uint | Format::R8Sint | Format::R8G8Uint | Format::R8G8Sint |
+            Format::A8B8G8R8UintPack32 | Format::A8B8G8R8SintPack32 |
+            Format::A2B10G10R10UintPack32 | Format::R16Uint | Format::R16Sint |
+            Format::R16G16Uint | Format::R16G16Sint => color,
+
+            Format::R8G8B8A8Uint | Format::R8G8B8A8Sint | Format::R16G16B16A16Uint |
+            Format::R16G16B16A16Sint | Format::R32Sfloat | Format::R32G32Uint |
+            Format::R32G32Sint | Format::R32G32Sfloat | Format::R32G32B32A32Uint |
+            Format::R32G32B32A32Sint | Format::R32G32B32A32Sfloat => {
+                FormatFeatures { storage_image: true, .. color }
+            },
+
+            Format::R32Uint | Format::R32Sint => {
+                FormatFeatures { storage_image: true, storage_image_atomic: true, .. color }
+            },
+
+            Format::D16Unorm => {
+                FormatFeatures {
+                    sampled_image: true,
+                    blit_src: true,
+                    depth_stencil_attachment: true,
+                    .. FormatFeatures::none()
+                }
+            },
+
+            Format::D32Sfloat => {
+                FormatFeatures { sampled_image: true, blit_src: true, .. FormatFeatures::none() }
+            },
+
+            _ => FormatFeatures::none(),
+        }
+    }
+
+    /// Returns the compatibility class of this format.
+    ///
+    /// An image created with the mutable format flag can be viewed with any format of the same
+    /// compatibility class as its own.
+    pub fn compatibility_class(&self) -> CompatibilityClass {
+        match *self {
+            Format::BC1_RGBUnormBlock | Format::BC1_RGBSrgbBlock => CompatibilityClass::Bc1Rgb,
+            Format::BC1_RGBAUnormBlock | Format::BC1_RGBASrgbBlock => CompatibilityClass::Bc1Rgba,
+            Format::BC2UnormBlock | Format::BC2SrgbBlock => CompatibilityClass::Bc2,
+            Format::BC3UnormBlock | Format::BC3SrgbBlock => CompatibilityClass::Bc3,
+            Format::BC4UnormBlock | Format::BC4SnormBlock => CompatibilityClass::Bc4,
+            Format::BC5UnormBlock | Format::BC5SnormBlock => CompatibilityClass::Bc5,
+            Format::BC6HUfloatBlock | Format::BC6HSfloatBlock => CompatibilityClass::Bc6h,
+            Format::BC7UnormBlock | Format::BC7SrgbBlock => CompatibilityClass::Bc7,
+            Format::ETC2_R8G8B8UnormBlock | Format::ETC2_R8G8B8SrgbBlock => {
+                CompatibilityClass::Etc2Rgb
+            },
+            Format::ETC2_R8G8B8A1UnormBlock | Format::ETC2_R8G8B8A1SrgbBlock => {
+                CompatibilityClass::Etc2Rgba
+            },
+            Format::ETC2_R8G8B8A8UnormBlock | Format::ETC2_R8G8B8A8SrgbBlock => {
+                CompatibilityClass::Etc2EacRgba
+            },
+            Format::EAC_R11UnormBlock | Format::EAC_R11SnormBlock => CompatibilityClass::EacR,
+            Format::EAC_R11G11UnormBlock | Format::EAC_R11G11SnormBlock => {
+                CompatibilityClass::EacRg
+            },
+            _ if self.ty() == FormatTy::Compressed => {
+                // All the remaining compressed formats are ASTC formats.
+                CompatibilityClass::Astc(self.block_dimensions())
+            },
+            _ => match self.ty() {
+                FormatTy::Depth | FormatTy::Stencil | FormatTy::DepthStencil => {
+                    CompatibilityClass::DepthStencil(*self)
+                },
+                _ => {
+                    // All uncompressed color formats have a size.
+                    CompatibilityClass::Bits(self.size().unwrap() as u32 * 8)
+                },
+            },
+        }
+    }
+}
+
+macro_rules! format_features {
+    ($($(#[$attr:meta])* $field:ident => $bit:ident,)+) => (
+        /// List of features that a physical device supports for a format.
+        ///
+        /// See `FormatProperties`.
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+        pub struct FormatFeatures {
+            $(
+                $(#[$attr])*
+                pub $field: bool,
+            )+
+        }
+
+        impl FormatFeatures {
+            /// Builds a `FormatFeatures` with all values set to false.
+            #[inline]
+            pub fn none() -> FormatFeatures {
+                FormatFeatures {
+                    $(
+                        $field: false,
+                    )+
+                }
+            }
+
+            /// Returns true if all the features of `other` are also in `self`.
+            #[inline]
+            pub fn is_superset_of(&self, other: &FormatFeatures) -> bool {
+                $(
+                    (self.$field || !other.$field) &&
+                )+ true
+            }
+
+            #[inline]
+            #[doc(hidden)]
+            pub fn from_bits(val: vk::FormatFeatureFlagBits) -> FormatFeatures {
+                FormatFeatures {
+                    $(
+                        $field: (val & vk::$bit) != 0,
+                    )+
+                }
+            }
+        }
+    );
+}
+
+format_features! {
+    /// Images can be sampled from a shader.
+    sampled_image => FORMAT_FEATURE_SAMPLED_IMAGE_BIT,
+    /// Images can be used as storage images.
+    storage_image => FORMAT_FEATURE_STORAGE_IMAGE_BIT,
+    /// Storage images support atomic operations.
+    storage_image_atomic => FORMAT_FEATURE_STORAGE_IMAGE_ATOMIC_BIT,
+    /// Buffer views can be used as uniform texel buffers.
+    uniform_texel_buffer => FORMAT_FEATURE_UNIFORM_TEXEL_BUFFER_BIT,
+    /// Buffer views can be used as storage texel buffers.
+    storage_texel_buffer => FORMAT_FEATURE_STORAGE_TEXEL_BUFFER_BIT,
+    /// Storage texel buffers support atomic operations.
+    storage_texel_buffer_atomic => FORMAT_FEATURE_STORAGE_TEXEL_BUFFER_ATOMIC_BIT,
+    /// Buffers can be used as vertex buffers with this format.
+    vertex_buffer => FORMAT_FEATURE_VERTEX_BUFFER_BIT,
+    /// Images can be used as color attachments.
+    color_attachment => FORMAT_FEATURE_COLOR_ATTACHMENT_BIT,
+    /// Color attachments support blending.
+    color_attachment_blend => FORMAT_FEATURE_COLOR_ATTACHMENT_BLEND_BIT,
+    /// Images can be used as depth-stencil attachments.
+    depth_stencil_attachment => FORMAT_FEATURE_DEPTH_STENCIL_ATTACHMENT_BIT,
+    /// Images can be used as the source of a blit.
+    blit_src => FORMAT_FEATURE_BLIT_SRC_BIT,
+    /// Images can be used as the destination of a blit.
+    blit_dst => FORMAT_FEATURE_BLIT_DST_BIT,
+    /// Images can be sampled or blitted with linear filtering.
+    sampled_image_filter_linear => FORMAT_FEATURE_SAMPLED_IMAGE_FILTER_LINEAR_BIT,
+}
+
+/// Features that a physical device supports for a format.
+///
+/// Obtained with `PhysicalDevice::format_properties` or `Format::properties`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FormatProperties {
+    /// Features supported by images with the linear tiling.
+    pub linear_tiling_features: FormatFeatures,
+    /// Features supported by images with the optimal tiling.
+    pub optimal_tiling_features: FormatFeatures,
+    /// Features supported by buffers.
+    pub buffer_features: FormatFeatures,
+}
+
+impl FormatProperties {
+    /// Returns true if images with the optimal tiling can be sampled with linear filtering.
+    #[inline]
+    pub fn can_be_sampled_linear(&self) -> bool {
+        self.optimal_tiling_features.sampled_image &&
+        self.optimal_tiling_features.sampled_image_filter_linear
+    }
+
+    /// Returns true if images with the optimal tiling can be used as color attachments.
+    #[inline]
+    pub fn can_be_color_attachment(&self) -> bool {
+        self.optimal_tiling_features.color_attachment
+    }
+
+    /// Returns true if images with the optimal tiling can be used as the source of a blit.
+    #[inline]
+    pub fn can_be_blit_src(&self) -> bool {
+        self.optimal_tiling_features.blit_src
+    }
+
+    /// Returns true if images with the optimal tiling can be used as the destination of a blit.
+    #[inline]
+    pub fn can_be_blit_dst(&self) -> bool {
+        self.optimal_tiling_features.blit_dst
+    }
+
+    /// Returns true if images with the optimal tiling can be used as storage images.
+    #[inline]
+    pub fn can_be_storage_image(&self) -> bool {
+        self.optimal_tiling_features.storage_image
+    }
+
+    /// Returns the features supported by images with the given tiling.
+    #[inline]
+    pub fn image_features(&self, linear_tiling: bool) -> FormatFeatures {
+        if linear_tiling {
+            self.linear_tiling_features
+        } else {
+            self.optimal_tiling_features
+        }
+    }
+}
+
+/// Compatibility class of a format, as defined by the Vulkan specifications.
+///
+/// Two formats are compatible if they belong to the same class. See `Format::compatibility_class`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CompatibilityClass {
+    /// Uncompressed color formats whose texels have the given size in bits.
+    Bits(u32),
+    /// Depth, stencil and depth-stencil formats are only compatible with themselves.
+    DepthStencil(Format),
+    Bc1Rgb,
+    Bc1Rgba,
+    Bc2,
+    Bc3,
+    Bc4,
+    Bc5,
+    Bc6h,
+    Bc7,
+    Etc2Rgb,
+    Etc2Rgba,
+    Etc2EacRgba,
+    EacR,
+    EacRg,
+    /// ASTC formats with the given block dimensions.
+    Astc([u32; 2]),
 }
 
 pub unsafe trait FormatDesc {
@@ -553,6 +1189,43 @@ pub unsafe trait FormatDesc {
     fn format(&self) -> Format;
 
     fn decode_clear_value(&self, Self::ClearValue) -> ClearValue;
+
+    /// Same as `decode_clear_value`, but returns `None` instead of panicking if the value doesn't
+    /// match the format.
+    #[inline]
+    fn try_decode_clear_value(&self, value: Self::ClearValue) -> Option<ClearValue> {
+        Some(self.decode_clear_value(value))
+    }
+
+    /// Returns the width and height in texels of a block. See `Format::block_dimensions`.
+    #[inline]
+    fn block_dimensions(&self) -> [u32; 2] {
+        self.format().block_dimensions()
+    }
+
+    /// Returns the size in bytes of a block. See `Format::block_size`.
+    #[inline]
+    fn block_size(&self) -> Option<usize> {
+        self.format().block_size()
+    }
+
+    /// Returns the number of components. See `Format::components`.
+    #[inline]
+    fn components(&self) -> u32 {
+        self.format().components()
+    }
+
+    /// Returns true if the format has a depth component.
+    #[inline]
+    fn has_depth(&self) -> bool {
+        self.format().has_depth()
+    }
+
+    /// Returns true if the format has a stencil component.
+    #[inline]
+    fn has_stencil(&self) -> bool {
+        self.format().has_stencil()
+    }
 }
 
 unsafe impl FormatDesc for Format {
@@ -563,16 +1236,19 @@ unsafe impl FormatDesc for Format {
         *self
     }
 
+    #[inline]
     fn decode_clear_value(&self, value: Self::ClearValue) -> ClearValue {
-        match (self.ty(), value) {
-            (FormatTy::Float, f @ ClearValue::Float(_)) => f,
-            (FormatTy::Compressed, f @ ClearValue::Float(_)) => f,
-            (FormatTy::Sint, f @ ClearValue::Int(_)) => f,
-            (FormatTy::Uint, f @ ClearValue::Uint(_)) => f,
-            (FormatTy::Depth, f @ ClearValue::Depth(_)) => f,
-            (FormatTy::Stencil, f @ ClearValue::Stencil(_)) => f,
-            (FormatTy::DepthStencil, f @ ClearValue::DepthStencil(_)) => f,
-            _ => panic!("Wrong clear value")
+        match self.try_decode_clear_value(value) {
+            Some(v) => v,
+            None => panic!("Wrong clear value")
+        }
+    }
+
+    fn try_decode_clear_value(&self, value: Self::ClearValue) -> Option<ClearValue> {
+        if self.validate_clear_value(&value) {
+            Some(value)
+        } else {
+            None
         }
     }
 }
@@ -606,7 +1282,9 @@ unsafe impl PossibleSintFormatDesc for Format {
     fn is_sint(&self) -> bool { self.ty() == FormatTy::Sint }
 }
 
+/// Trait for types that can possibly describe a depth-only attachment.
 pub unsafe trait PossibleDepthFormatDesc: FormatDesc {
+    /// Returns true if the format only has a depth aspect.
     fn is_depth(&self) -> bool;
 }
 
@@ -615,7 +1293,9 @@ unsafe impl PossibleDepthFormatDesc for Format {
     fn is_depth(&self) -> bool { self.ty() == FormatTy::Depth }
 }
 
+/// Trait for types that can possibly describe a stencil-only attachment.
 pub unsafe trait PossibleStencilFormatDesc: FormatDesc {
+    /// Returns true if the format only has a stencil aspect.
     fn is_stencil(&self) -> bool;
 }
 
@@ -624,7 +1304,10 @@ unsafe impl PossibleStencilFormatDesc for Format {
     fn is_stencil(&self) -> bool { self.ty() == FormatTy::Stencil }
 }
 
+/// Trait for types that can possibly describe an attachment with both a depth and a stencil
+/// aspect.
 pub unsafe trait PossibleDepthStencilFormatDesc: FormatDesc {
+    /// Returns true if the format has both a depth and a stencil aspect.
     fn is_depth_stencil(&self) -> bool;
 }
 
@@ -633,6 +1316,29 @@ unsafe impl PossibleDepthStencilFormatDesc for Format {
     fn is_depth_stencil(&self) -> bool { self.ty() == FormatTy::DepthStencil }
 }
 
+/// Trait for types that can possibly describe the depth-stencil attachment of a subpass.
+///
+/// This is implemented on all the depth, stencil and depth-stencil formats, and on `Format`
+/// which checks the format at runtime. Render passes and `AttachmentImage::depth_stencil` use
+/// it to reject color formats at compile time.
+///
+/// ```compile_fail
+/// use vulkano::format::PossibleDepthOrStencilFormatDesc;
+/// use vulkano::format::R8G8B8A8Unorm;
+///
+/// fn depth_attachment<F: PossibleDepthOrStencilFormatDesc>(_: F) {}
+/// depth_attachment(R8G8B8A8Unorm);
+/// ```
+pub unsafe trait PossibleDepthOrStencilFormatDesc: FormatDesc {
+    /// Returns true if the format has a depth aspect, a stencil aspect, or both.
+    fn is_depth_or_stencil(&self) -> bool;
+}
+
+unsafe impl PossibleDepthOrStencilFormatDesc for Format {
+    #[inline]
+    fn is_depth_or_stencil(&self) -> bool { Format::is_depth_or_stencil(self) }
+}
+
 pub unsafe trait PossibleCompressedFormatDesc: FormatDesc {
     fn is_compressed(&self) -> bool;
 }
@@ -659,14 +1365,27 @@ pub unsafe trait StrongStorage: FormatDesc {
     type Pixel: Copy;
 }
 
+/// General category of a format.
+///
+/// Compressed formats have their own category instead of being considered as floating-point
+/// formats, even though they are sampled as floating-point values. This is because most
+/// operations that work with floating-point formats (clearing, rendering, blitting) aren't
+/// allowed with compressed formats.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum FormatTy {
+    /// Floating-point, normalized or scaled color format.
     Float,
+    /// Unsigned integer color format.
     Uint,
+    /// Signed integer color format.
     Sint,
+    /// Depth format.
     Depth,
+    /// Stencil format.
     Stencil,
+    /// Format with both a depth and a stencil component.
     DepthStencil,
+    /// Block-compressed format (BC, ETC2, EAC or ASTC).
     Compressed,
 }
 
@@ -690,6 +1409,121 @@ pub enum ClearValue {
     DepthStencil((f32, u32)),
 }
 
+/// Error that happens when a clear value doesn't match the format it's used with.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ClearValueMismatch {
+    /// The format of the image or attachment to clear.
+    pub format: Format,
+    /// The clear value that was provided.
+    pub value: ClearValue,
+}
+
+impl ClearValueMismatch {
+    /// Checks `value` against `format` with `Format::validate_clear_value`.
+    #[inline]
+    pub fn check(format: Format, value: ClearValue) -> Result<(), ClearValueMismatch> {
+        if format.validate_clear_value(&value) {
+            Ok(())
+        } else {
+            Err(ClearValueMismatch { format: format, value: value })
+        }
+    }
+}
+
+impl error::Error for ClearValueMismatch {
+    #[inline]
+    fn description(&self) -> &str {
+        "the clear value doesn't match the format"
+    }
+}
+
+impl fmt::Display for ClearValueMismatch {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}: {:?} can't be cleared with {:?}", error::Error::description(self),
+               self.format, self.value)
+    }
+}
+
+/// Error that happens when building a clear value from floating-point components.
+///
+/// See `Format::try_decode_float_clear_value`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ClearValueConversionError {
+    /// The format is compressed, and compressed images can't be cleared.
+    CompressedFormat { format: Format },
+    /// A component can't be represented exactly by the format, for example `1.5` for an integer
+    /// format.
+    NotRepresentable { format: Format, component: usize, value: f32 },
+}
+
+impl error::Error for ClearValueConversionError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            ClearValueConversionError::CompressedFormat { .. } => {
+                "compressed formats can't be cleared"
+            },
+            ClearValueConversionError::NotRepresentable { .. } => {
+                "a component of the clear value can't be represented exactly by the format"
+            },
+        }
+    }
+}
+
+impl fmt::Display for ClearValueConversionError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            ClearValueConversionError::CompressedFormat { format } => {
+                write!(fmt, "{}: {:?}", error::Error::description(self), format)
+            },
+            ClearValueConversionError::NotRepresentable { format, component, value } => {
+                write!(fmt, "{}: component {} of a {:?} clear value is {}",
+                       error::Error::description(self), component, format, value)
+            },
+        }
+    }
+}
+
+// Truncates towards zero and clamps to the range of `u32`. NaN becomes 0.
+#[inline]
+fn float_to_u32(value: f32) -> u32 {
+    if !(value > 0.0) {
+        0
+    } else if value >= 4294967296.0 {
+        u32::MAX
+    } else {
+        value as u32
+    }
+}
+
+// Truncates towards zero and clamps to the range of `i32`. NaN becomes 0.
+#[inline]
+fn float_to_i32(value: f32) -> i32 {
+    if value != value {
+        0
+    } else if value <= -2147483648.0 {
+        i32::MIN
+    } else if value >= 2147483648.0 {
+        i32::MAX
+    } else {
+        value as i32
+    }
+}
+
+// Returns true if `value` is a whole number that fits in a `u32`.
+#[inline]
+fn float_is_u32(value: f32) -> bool {
+    value >= 0.0 && value < 4294967296.0 && value.fract() == 0.0
+}
+
+// Returns true if `value` is a whole number that fits in an `i32`.
+#[inline]
+fn float_is_i32(value: f32) -> bool {
+    value >= -2147483648.0 && value < 2147483648.0 && value.fract() == 0.0
+}
+
 // TODO: remove all these From implementations once they are no longer needed
 
 impl From<[f32; 1]> for ClearValue {
@@ -837,3 +1671,477 @@ macro_rules! impl_clear_values_tuple {
 }
 
 impl_clear_values_tuple!(A B C D E F G H I J K L M N O P Q R S T U V W X Y Z);
+
+#[cfg(test)]
+mod tests {
+    use std::i32;
+    use std::u32;
+
+    use features::Features;
+    use format::ClearValue;
+    use format::ClearValueConversionError;
+    use format::ClearValueMismatch;
+    use format::CompatibilityClass;
+    use format::Format;
+    use format::FormatDesc;
+    use format::FormatFeatures;
+    use format::FormatProperties;
+    use vk;
+
+    #[test]
+    fn uncompressed_block() {
+        assert_eq!(Format::R8G8B8A8Unorm.block_dimensions(), [1, 1]);
+        assert_eq!(Format::R8G8B8A8Unorm.block_size(), Some(4));
+        assert_eq!(Format::R8G8B8A8Unorm.data_size([3, 5, 2]), Some(3 * 5 * 2 * 4));
+    }
+
+    #[test]
+    fn compressed_blocks() {
+        assert_eq!(Format::BC1_RGBAUnormBlock.block_dimensions(), [4, 4]);
+        assert_eq!(Format::BC1_RGBAUnormBlock.block_size(), Some(8));
+        assert_eq!(Format::BC7UnormBlock.block_size(), Some(16));
+        assert_eq!(Format::ETC2_R8G8B8A8UnormBlock.block_size(), Some(16));
+        assert_eq!(Format::ASTC_10x8UnormBlock.block_dimensions(), [10, 8]);
+        assert_eq!(Format::ASTC_10x8UnormBlock.block_size(), Some(16));
+    }
+
+    #[test]
+    fn texel_metadata() {
+        assert_eq!(Format::R8Unorm.block_size(), Some(1));
+        assert_eq!(Format::R8Unorm.components(), 1);
+        assert_eq!(Format::R32G32B32A32Sfloat.block_size(), Some(16));
+        assert_eq!(Format::R32G32B32A32Sfloat.components(), 4);
+        assert_eq!(Format::B10G11R11UfloatPack32.components(), 3);
+        assert_eq!(Format::BC1_RGBUnormBlock.components(), 3);
+        assert_eq!(Format::BC5UnormBlock.components(), 2);
+        assert_eq!(Format::ASTC_12x12SrgbBlock.components(), 4);
+    }
+
+    #[test]
+    fn depth_stencil_aspects() {
+        assert!(Format::D24Unorm_S8Uint.has_depth());
+        assert!(Format::D24Unorm_S8Uint.has_stencil());
+        assert_eq!(Format::D24Unorm_S8Uint.components(), 2);
+        assert!(Format::D32Sfloat.has_depth());
+        assert!(!Format::D32Sfloat.has_stencil());
+        assert!(!Format::S8Uint.has_depth());
+        assert!(Format::S8Uint.has_stencil());
+        assert!(!Format::R8Unorm.has_depth());
+        assert!(!Format::R8Unorm.has_stencil());
+    }
+
+    #[test]
+    fn depth_stencil_predicates() {
+        assert!(Format::D16Unorm.is_depth());
+        assert!(!Format::D16Unorm.is_depth_stencil());
+        assert!(Format::S8Uint.is_stencil());
+        assert!(Format::D32Sfloat_S8Uint.is_depth_stencil());
+        assert!(!Format::D32Sfloat_S8Uint.is_depth());
+        assert!(!Format::R8G8B8A8Unorm.is_depth());
+        assert!(!Format::R8G8B8A8Unorm.is_stencil());
+        assert!(!Format::R8G8B8A8Unorm.is_depth_stencil());
+        assert!(!Format::R8G8B8A8Unorm.is_depth_or_stencil());
+        assert!(Format::D24Unorm_S8Uint.is_depth_or_stencil());
+    }
+
+    #[test]
+    fn depth_format_traits() {
+        use format::D16Unorm;
+        use format::D24Unorm_S8Uint;
+        use format::PossibleDepthFormatDesc;
+        use format::PossibleDepthOrStencilFormatDesc;
+        use format::PossibleDepthStencilFormatDesc;
+        use format::PossibleStencilFormatDesc;
+        use format::S8Uint;
+
+        fn depth<F: PossibleDepthFormatDesc>(f: F) -> bool { f.is_depth() }
+        fn stencil<F: PossibleStencilFormatDesc>(f: F) -> bool { f.is_stencil() }
+        fn depth_stencil<F: PossibleDepthStencilFormatDesc>(f: F) -> bool { f.is_depth_stencil() }
+        fn attachment<F: PossibleDepthOrStencilFormatDesc>(f: F) -> bool {
+            f.is_depth_or_stencil()
+        }
+
+        assert!(depth(D16Unorm));
+        assert!(stencil(S8Uint));
+        assert!(depth_stencil(D24Unorm_S8Uint));
+        assert!(attachment(D16Unorm));
+        assert!(attachment(S8Uint));
+        assert!(attachment(D24Unorm_S8Uint));
+
+        // `Format` implements the traits too, but checks at runtime.
+        assert!(!depth(Format::R8G8B8A8Unorm));
+        assert!(!attachment(Format::R8G8B8A8Unorm));
+    }
+
+    #[test]
+    fn format_desc_metadata() {
+        use format::BC1_RGBUnormBlock;
+        use format::D24Unorm_S8Uint;
+        use format::R8G8B8A8Unorm;
+
+        assert_eq!(FormatDesc::block_dimensions(&BC1_RGBUnormBlock), [4, 4]);
+        assert_eq!(FormatDesc::block_size(&BC1_RGBUnormBlock), Some(8));
+        assert_eq!(FormatDesc::components(&R8G8B8A8Unorm), 4);
+        assert!(FormatDesc::has_depth(&D24Unorm_S8Uint));
+        assert!(FormatDesc::has_stencil(&D24Unorm_S8Uint));
+    }
+
+    #[test]
+    fn compressed_vk_values() {
+        assert_eq!(Format::BC1_RGBUnormBlock as u32, vk::FORMAT_BC1_RGB_UNORM_BLOCK);
+        assert_eq!(Format::BC7SrgbBlock as u32, vk::FORMAT_BC7_SRGB_BLOCK);
+        assert_eq!(Format::ETC2_R8G8B8A8UnormBlock as u32, vk::FORMAT_ETC2_R8G8B8A8_UNORM_BLOCK);
+        assert_eq!(Format::EAC_R11G11SnormBlock as u32, vk::FORMAT_EAC_R11G11_SNORM_BLOCK);
+        assert_eq!(Format::ASTC_12x12SrgbBlock as u32, vk::FORMAT_ASTC_12x12_SRGB_BLOCK);
+        assert_eq!(Format::from_num(vk::FORMAT_BC5_UNORM_BLOCK), Some(Format::BC5UnormBlock));
+        assert_eq!(Format::from_num(vk::FORMAT_ASTC_8x5_SRGB_BLOCK),
+                   Some(Format::ASTC_8x5SrgbBlock));
+    }
+
+    #[test]
+    fn is_compressed_consistent() {
+        for num in 0 .. 200 {
+            let format = match Format::from_num(num) {
+                Some(f) => f,
+                None => continue,
+            };
+
+            assert_eq!(format.is_compressed(), format.size().is_none() && !format.has_depth());
+            if format.is_compressed() {
+                assert!(format.block_size().is_some());
+                assert!(!format.validate_clear_value(&ClearValue::Float([0.0; 4])));
+            } else {
+                assert_eq!(format.block_dimensions(), [1, 1]);
+                assert_eq!(format.block_size(), format.size());
+            }
+        }
+
+        assert!(Format::BC1_RGBUnormBlock.is_compressed());
+        assert!(!Format::R8G8B8A8Unorm.is_compressed());
+    }
+
+    #[test]
+    fn compressed_data_size() {
+        // The extents are rounded up to whole blocks.
+        assert_eq!(Format::BC1_RGBAUnormBlock.data_size([16, 16, 1]), Some(4 * 4 * 8));
+        assert_eq!(Format::BC1_RGBAUnormBlock.data_size([5, 5, 1]), Some(2 * 2 * 8));
+        assert_eq!(Format::BC1_RGBAUnormBlock.data_size([1, 1, 1]), Some(8));
+        assert_eq!(Format::BC7UnormBlock.data_size([2, 1, 1]), Some(16));
+        assert_eq!(Format::ASTC_10x8UnormBlock.data_size([32, 32, 1]), Some(4 * 4 * 16));
+    }
+
+    #[test]
+    fn compression_features() {
+        let bc = Features { texture_compression_bc: true, .. Features::none() };
+        assert!(Format::R8G8B8A8Unorm.features_enabled(&Features::none()));
+        assert!(!Format::BC7UnormBlock.features_enabled(&Features::none()));
+        assert!(Format::BC7UnormBlock.features_enabled(&bc));
+        assert!(!Format::ASTC_4x4UnormBlock.features_enabled(&bc));
+    }
+
+    #[test]
+    fn validate_clear_value() {
+        assert!(Format::R8G8B8A8Unorm.validate_clear_value(&ClearValue::Float([0.0; 4])));
+        assert!(!Format::BC7UnormBlock.validate_clear_value(&ClearValue::Float([0.0; 4])));
+        assert!(Format::R32Uint.validate_clear_value(&ClearValue::Uint([0; 4])));
+        assert!(Format::R32Sint.validate_clear_value(&ClearValue::Int([0; 4])));
+        assert!(Format::D32Sfloat.validate_clear_value(&ClearValue::Depth(1.0)));
+        assert!(Format::S8Uint.validate_clear_value(&ClearValue::Stencil(0)));
+        assert!(Format::D24Unorm_S8Uint.validate_clear_value(&ClearValue::DepthStencil((1.0, 0))));
+    }
+
+    #[test]
+    fn clear_value_mismatches() {
+        let values = [
+            ClearValue::None, ClearValue::Float([0.0; 4]), ClearValue::Int([0; 4]),
+            ClearValue::Uint([0; 4]), ClearValue::Depth(1.0), ClearValue::Stencil(0),
+            ClearValue::DepthStencil((1.0, 0)),
+        ];
+
+        // For each format, the index in `values` of the only valid clear value.
+        let formats = [
+            (Format::R8G8B8A8Unorm, 1), (Format::R32Sint, 2), (Format::R32Uint, 3),
+            (Format::D16Unorm, 4), (Format::S8Uint, 5), (Format::D32Sfloat_S8Uint, 6),
+        ];
+
+        for &(format, valid) in formats.iter() {
+            for (num, value) in values.iter().enumerate() {
+                assert_eq!(format.validate_clear_value(value), num == valid);
+                assert_eq!(ClearValueMismatch::check(format, *value).is_ok(), num == valid);
+            }
+        }
+    }
+
+    #[test]
+    fn float_clear_value_per_class() {
+        assert_eq!(Format::R8G8B8A8Srgb.decode_float_clear_value([0.0, 0.5, 1.0, 1.0]),
+                   ClearValue::Float([0.0, 0.5, 1.0, 1.0]));
+        assert_eq!(Format::R16G16Sfloat.decode_float_clear_value([2.5, -1.0, 0.0, 0.0]),
+                   ClearValue::Float([2.5, -1.0, 0.0, 0.0]));
+        assert_eq!(Format::R8G8B8A8Uint.decode_float_clear_value([1.0, 2.0, 3.0, 4.0]),
+                   ClearValue::Uint([1, 2, 3, 4]));
+        assert_eq!(Format::R32Sint.decode_float_clear_value([-3.0, 0.0, 0.0, 0.0]),
+                   ClearValue::Int([-3, 0, 0, 0]));
+        assert_eq!(Format::D16Unorm.decode_float_clear_value([1.0, 0.0, 0.0, 0.0]),
+                   ClearValue::Depth(1.0));
+        assert_eq!(Format::S8Uint.decode_float_clear_value([0.0, 7.0, 0.0, 0.0]),
+                   ClearValue::Stencil(7));
+        assert_eq!(Format::D24Unorm_S8Uint.decode_float_clear_value([0.5, 3.0, 0.0, 0.0]),
+                   ClearValue::DepthStencil((0.5, 3)));
+
+        for &format in [Format::R8G8B8A8Unorm, Format::R32G32Uint, Format::R16Sint,
+                        Format::D32Sfloat, Format::S8Uint, Format::D32Sfloat_S8Uint].iter()
+        {
+            let value = format.decode_float_clear_value([1.0, 1.0, 1.0, 1.0]);
+            assert!(format.validate_clear_value(&value));
+        }
+    }
+
+    #[test]
+    fn float_clear_value_lossy() {
+        assert_eq!(Format::R32Uint.decode_float_clear_value([1.5, -2.0, 1e20, 0.0]),
+                   ClearValue::Uint([1, 0, u32::MAX, 0]));
+        assert_eq!(Format::R32Sint.decode_float_clear_value([-1.5, -1e20, 1e20, 0.0]),
+                   ClearValue::Int([-1, i32::MIN, i32::MAX, 0]));
+        assert_eq!(Format::S8Uint.decode_float_clear_value([0.0, 2.9, 0.0, 0.0]),
+                   ClearValue::Stencil(2));
+    }
+
+    #[test]
+    fn float_clear_value_exact() {
+        assert_eq!(Format::R32Uint.try_decode_float_clear_value([1.5, 0.0, 0.0, 0.0]),
+                   Err(ClearValueConversionError::NotRepresentable {
+                       format: Format::R32Uint,
+                       component: 0,
+                       value: 1.5,
+                   }));
+        assert!(Format::R32Uint.try_decode_float_clear_value([0.0, -1.0, 0.0, 0.0]).is_err());
+        assert!(Format::R32Sint.try_decode_float_clear_value([0.0, 0.0, 0.0, 3e9]).is_err());
+        assert_eq!(Format::R32Sint.try_decode_float_clear_value([-1.0, 0.0, 0.0, 0.0]),
+                   Ok(ClearValue::Int([-1, 0, 0, 0])));
+        assert!(Format::D24Unorm_S8Uint.try_decode_float_clear_value([1.0, 0.5, 0.0, 0.0])
+                                        .is_err());
+        // The unused components of a depth-only format are ignored.
+        assert_eq!(Format::D16Unorm.try_decode_float_clear_value([1.0, 0.5, 0.0, 0.0]),
+                   Ok(ClearValue::Depth(1.0)));
+        assert_eq!(Format::R8G8B8A8Unorm.try_decode_float_clear_value([0.25; 4]),
+                   Ok(ClearValue::Float([0.25; 4])));
+        assert_eq!(Format::BC1_RGBUnormBlock.try_decode_float_clear_value([0.0; 4]),
+                   Err(ClearValueConversionError::CompressedFormat {
+                       format: Format::BC1_RGBUnormBlock,
+                   }));
+    }
+
+    #[test]
+    #[should_panic = "Compressed formats can't be cleared"]
+    fn float_clear_value_compressed() {
+        Format::BC7UnormBlock.decode_float_clear_value([0.0; 4]);
+    }
+
+    #[test]
+    fn srgb_pairs() {
+        let pairs = [
+            (Format::R8Srgb, Format::R8Unorm),
+            (Format::R8G8Srgb, Format::R8G8Unorm),
+            (Format::R8G8B8A8Srgb, Format::R8G8B8A8Unorm),
+            (Format::B8G8R8A8Srgb, Format::B8G8R8A8Unorm),
+            (Format::A8B8G8R8SrgbPack32, Format::A8B8G8R8UnormPack32),
+            (Format::BC1_RGBASrgbBlock, Format::BC1_RGBAUnormBlock),
+            (Format::BC7SrgbBlock, Format::BC7UnormBlock),
+            (Format::ETC2_R8G8B8A8SrgbBlock, Format::ETC2_R8G8B8A8UnormBlock),
+            (Format::ASTC_12x12SrgbBlock, Format::ASTC_12x12UnormBlock),
+        ];
+
+        for &(srgb, unorm) in pairs.iter() {
+            assert!(srgb.is_srgb());
+            assert!(!unorm.is_srgb());
+            assert_eq!(srgb.unorm_equivalent(), Some(unorm));
+            assert_eq!(unorm.srgb_equivalent(), Some(srgb));
+            assert_eq!(srgb.srgb_equivalent(), None);
+            assert_eq!(unorm.unorm_equivalent(), None);
+        }
+    }
+
+    #[test]
+    fn srgb_pairs_consistent() {
+        for num in 0 .. 200 {
+            let format = match Format::from_num(num) {
+                Some(f) => f,
+                None => continue,
+            };
+
+            if let Some(unorm) = format.unorm_equivalent() {
+                assert_eq!(unorm.srgb_equivalent(), Some(format));
+                assert_eq!(unorm.compatibility_class(), format.compatibility_class());
+            }
+
+            if let Some(srgb) = format.srgb_equivalent() {
+                assert_eq!(srgb.unorm_equivalent(), Some(format));
+            }
+        }
+    }
+
+    #[test]
+    fn no_srgb_twin() {
+        for &format in [Format::R8Snorm, Format::R16G16B16A16Sfloat, Format::R32Uint,
+                        Format::D24Unorm_S8Uint, Format::BC4UnormBlock,
+                        Format::EAC_R11UnormBlock, Format::A2B10G10R10UnormPack32].iter()
+        {
+            assert!(!format.is_srgb());
+            assert_eq!(format.srgb_equivalent(), None);
+            assert_eq!(format.unorm_equivalent(), None);
+        }
+    }
+
+    #[test]
+    fn format_features_from_bits() {
+        let features = FormatFeatures::from_bits(vk::FORMAT_FEATURE_BLIT_SRC_BIT |
+                                                 vk::FORMAT_FEATURE_STORAGE_IMAGE_BIT);
+        assert_eq!(features, FormatFeatures {
+            blit_src: true,
+            storage_image: true,
+            .. FormatFeatures::none()
+        });
+    }
+
+    #[test]
+    fn format_properties_predicates() {
+        let properties = FormatProperties {
+            linear_tiling_features: FormatFeatures {
+                sampled_image: true,
+                sampled_image_filter_linear: true,
+                color_attachment: true,
+                blit_src: true,
+                blit_dst: true,
+                storage_image: true,
+                .. FormatFeatures::none()
+            },
+            optimal_tiling_features: FormatFeatures {
+                sampled_image: true,
+                blit_src: true,
+                .. FormatFeatures::none()
+            },
+            buffer_features: FormatFeatures::none(),
+        };
+
+        // Only the optimal tiling features are consulted.
+        assert!(!properties.can_be_sampled_linear());
+        assert!(!properties.can_be_color_attachment());
+        assert!(properties.can_be_blit_src());
+        assert!(!properties.can_be_blit_dst());
+        assert!(!properties.can_be_storage_image());
+        assert!(properties.image_features(true).color_attachment);
+
+        let properties = FormatProperties {
+            optimal_tiling_features: FormatFeatures {
+                sampled_image: true,
+                sampled_image_filter_linear: true,
+                color_attachment: true,
+                blit_dst: true,
+                storage_image: true,
+                .. FormatFeatures::none()
+            },
+            .. properties
+        };
+
+        assert!(properties.can_be_sampled_linear());
+        assert!(properties.can_be_color_attachment());
+        assert!(properties.can_be_blit_dst());
+        assert!(properties.can_be_storage_image());
+    }
+
+    #[test]
+    fn required_optimal_features() {
+        let rgba = Format::R8G8B8A8Unorm.required_optimal_features();
+        assert!(rgba.color_attachment && rgba.color_attachment_blend && rgba.storage_image);
+        assert!(rgba.sampled_image_filter_linear);
+        assert!(Format::D16Unorm.required_optimal_features().depth_stencil_attachment);
+        assert!(Format::R32Uint.required_optimal_features().storage_image_atomic);
+        assert_eq!(Format::BC1_RGBUnormBlock.required_optimal_features(),
+                   FormatFeatures::none());
+
+        // Depth formats never need to be color attachments and vice versa.
+        for num in 0 .. 200 {
+            let format = match Format::from_num(num) {
+                Some(f) => f,
+                None => continue,
+            };
+
+            let required = format.required_optimal_features();
+            if format.is_depth_or_stencil() {
+                assert!(!required.color_attachment);
+            } else {
+                assert!(!required.depth_stencil_attachment);
+            }
+        }
+    }
+
+    #[test]
+    fn features_superset() {
+        let all = FormatFeatures::from_bits(0xffffffff);
+        let blit = FormatFeatures { blit_src: true, blit_dst: true, .. FormatFeatures::none() };
+        assert!(all.is_superset_of(&blit));
+        assert!(blit.is_superset_of(&blit));
+        assert!(blit.is_superset_of(&FormatFeatures::none()));
+        assert!(!blit.is_superset_of(&all));
+        assert!(!FormatFeatures::none().is_superset_of(&blit));
+    }
+
+    #[test]
+    fn device_format_properties() {
+        let (device, _) = gfx_dev_and_queue!();
+        let physical = device.physical_device();
+
+        let properties = Format::R8G8B8A8Unorm.properties(&physical);
+        assert!(properties.optimal_tiling_features
+                          .is_superset_of(&Format::R8G8B8A8Unorm.required_optimal_features()));
+        assert!(Format::R8G8B8A8Unorm.can_be_color_attachment(&physical));
+        assert!(Format::R8G8B8A8Unorm.can_be_sampled_linear(&physical));
+        assert!(Format::R8G8B8A8Unorm.can_be_blit_src(&physical));
+        assert!(Format::R8G8B8A8Unorm.can_be_blit_dst(&physical));
+        assert!(Format::R8G8B8A8Unorm.can_be_storage_image(&physical));
+        assert!(!Format::D16Unorm.can_be_color_attachment(&physical));
+    }
+
+    #[test]
+    fn compatibility_classes() {
+        assert_eq!(Format::R8G8B8A8Unorm.compatibility_class(),
+                   Format::R8G8B8A8Srgb.compatibility_class());
+        assert_eq!(Format::R8G8B8A8Unorm.compatibility_class(),
+                   Format::R32Sfloat.compatibility_class());
+        assert_eq!(Format::R8G8B8A8Unorm.compatibility_class(), CompatibilityClass::Bits(32));
+        assert!(Format::R8G8B8A8Unorm.compatibility_class() !=
+                Format::R16G16B16A16Sfloat.compatibility_class());
+        assert_eq!(Format::BC1_RGBAUnormBlock.compatibility_class(),
+                   Format::BC1_RGBASrgbBlock.compatibility_class());
+        assert!(Format::BC1_RGBAUnormBlock.compatibility_class() !=
+                Format::BC1_RGBUnormBlock.compatibility_class());
+        assert_eq!(Format::ASTC_8x8SrgbBlock.compatibility_class(),
+                   CompatibilityClass::Astc([8, 8]));
+        assert!(Format::D32Sfloat.compatibility_class() != Format::R32Sfloat.compatibility_class());
+    }
+
+    #[test]
+    fn pixel_size() {
+        assert!(Format::R8G8B8A8Unorm.accepts_pixel_size(4));
+        assert!(!Format::R8G8B8A8Unorm.accepts_pixel_size(1));
+        assert!(!Format::R8G8B8A8Unorm.accepts_pixel_size(0));
+        assert!(Format::BC1_RGBAUnormBlock.accepts_pixel_size(1));
+        assert!(Format::BC1_RGBAUnormBlock.accepts_pixel_size(8));
+        assert!(!Format::BC1_RGBAUnormBlock.accepts_pixel_size(16));
+    }
+
+    #[test]
+    fn try_decode_clear_value() {
+        let format = Format::R8G8B8A8Unorm;
+        assert!(format.try_decode_clear_value(ClearValue::Float([0.0; 4])).is_some());
+        assert!(format.try_decode_clear_value(ClearValue::Uint([0; 4])).is_none());
+        assert!(Format::D16Unorm.try_decode_clear_value(ClearValue::Depth(1.0)).is_some());
+    }
+
+    #[test]
+    #[should_panic]
+    fn decode_wrong_clear_value() {
+        Format::R8G8B8A8Unorm.decode_clear_value(ClearValue::Depth(1.0));
+    }
+}
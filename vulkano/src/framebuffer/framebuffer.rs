@@ -18,8 +18,11 @@ use device::Device;
 use framebuffer::RenderPass;
 use framebuffer::RenderPassAttachmentsList;
 use framebuffer::RenderPassCompatible;
+use framebuffer::RenderPassDesc;
+use format::Format;
 use framebuffer::UnsafeRenderPass;
 use image::Layout as ImageLayout;
+use image::Usage as ImageUsage;
 use image::traits::Image;
 use image::traits::ImageView;
 
@@ -49,10 +52,14 @@ pub struct Framebuffer<L> {
 impl<L> Framebuffer<L> {
     /// Builds a new framebuffer.
     ///
+    /// The `dimensions` are the width, height and number of layers of the framebuffer. Use 1
+    /// layer unless you render to multiple layers at once with a geometry shader, in which case
+    /// each attachment must have at least that many array layers.
+    ///
     /// The `attachments` parameter depends on which `RenderPass` implementation is used.
     pub fn new<A>(render_pass: &Arc<L>, dimensions: [u32; 3],
                   attachments: A) -> Result<Arc<Framebuffer<L>>, FramebufferCreationError>
-        where L: RenderPass + RenderPassAttachmentsList<A>
+        where L: RenderPass + RenderPassDesc + RenderPassAttachmentsList<A>
     {
         let vk = render_pass.render_pass().device().pointers();
         let device = render_pass.render_pass().device().clone();
@@ -72,26 +79,121 @@ impl<L> Framebuffer<L> {
             }
         }
 
+        let num_attachments = render_pass.attachments().len();
+        if attachments.len() != num_attachments {
+            return Err(FramebufferCreationError::AttachmentsCountMismatch {
+                expected: num_attachments,
+                obtained: attachments.len(),
+            });
+        }
+
+        // The usage that each attachment needs, depending on how the subpasses use it.
+        let mut required_usages = vec![ImageUsage::none(); num_attachments];
+        for pass in render_pass.passes() {
+            for &(num, _) in pass.color_attachments.iter()
+                                 .chain(pass.resolve_attachments.iter().flat_map(|a| a.iter()))
+            {
+                required_usages[num].color_attachment = true;
+            }
+            for &(num, _) in pass.depth_stencil.iter() {
+                required_usages[num].depth_stencil_attachment = true;
+            }
+            for &(num, _) in pass.input_attachments.iter() {
+                required_usages[num].input_attachment = true;
+            }
+        }
+
         let ids = {
             let mut ids = SmallVec::<[_; 8]>::new();
 
-            for &(ref a, _, _, _) in attachments.iter() {
-                debug_assert!(a.identity_swizzle());
-                // TODO: add more checks with debug_assert!
+            for (num, (&(ref a, _, _, _), desc)) in attachments.iter()
+                                                              .zip(render_pass.attachments())
+                                                              .enumerate()
+            {
+                let view = a.inner_view();
 
-                let atch_dims = a.parent().dimensions();
-                if atch_dims.width() < dimensions[0] || atch_dims.height() < dimensions[1] ||
-                   atch_dims.array_layers() < dimensions[2]      // TODO: wrong, since it must be the array layers of the view and not of the image
-                {
-                    return Err(FramebufferCreationError::AttachmentTooSmall);
+                if !view.identity_swizzle() {
+                    return Err(FramebufferCreationError::AttachmentNotIdentitySwizzled);
                 }
 
-                ids.push(a.inner_view().internal_object());
+                if view.format() != desc.format {
+                    return Err(FramebufferCreationError::AttachmentFormatMismatch {
+                        attachment: num,
+                        expected: desc.format,
+                        obtained: view.format(),
+                    });
+                }
+
+                if a.samples() != desc.samples {
+                    return Err(FramebufferCreationError::AttachmentSamplesMismatch {
+                        attachment: num,
+                        expected: desc.samples,
+                        obtained: a.samples(),
+                    });
+                }
+
+                let atch_dims = view.dimensions();
+                if atch_dims.width() < dimensions[0] || atch_dims.height() < dimensions[1] {
+                    return Err(FramebufferCreationError::AttachmentTooSmall {
+                        attachment: num,
+                        dimensions: [atch_dims.width(), atch_dims.height()],
+                        min: [dimensions[0], dimensions[1]],
+                    });
+                }
+
+                if atch_dims.array_layers() < dimensions[2] {
+                    return Err(FramebufferCreationError::AttachmentNotEnoughLayers {
+                        attachment: num,
+                        layers: atch_dims.array_layers(),
+                        min: dimensions[2],
+                    });
+                }
+
+                let required = required_usages[num];
+                let missing = ImageUsage {
+                    color_attachment: required.color_attachment &&
+                                      !view.usage_color_attachment(),
+                    depth_stencil_attachment: required.depth_stencil_attachment &&
+                                              !view.usage_depth_stencil_attachment(),
+                    input_attachment: required.input_attachment &&
+                                      !view.usage_input_attachment(),
+                    .. ImageUsage::none()
+                };
+                if missing != ImageUsage::none() {
+                    return Err(FramebufferCreationError::AttachmentMissingUsage {
+                        attachment: num,
+                        missing: missing,
+                    });
+                }
+
+                ids.push(view.internal_object());
             }
 
             ids
         };
 
+        // Checking that each resolve attachment has the same dimensions as the color attachment
+        // it resolves.
+        for pass in render_pass.passes() {
+            for (resolve, &(color, _)) in pass.resolve_attachments.iter()
+                                              .zip(pass.color_attachments.iter())
+            {
+                let resolve = match *resolve {
+                    Some((resolve, _)) => resolve,
+                    None => continue,
+                };
+
+                let resolve_dims = attachments[resolve].0.inner_view().dimensions();
+                let color_dims = attachments[color].0.inner_view().dimensions();
+                if resolve_dims.width() != color_dims.width() ||
+                   resolve_dims.height() != color_dims.height() ||
+                   resolve_dims.array_layers() != color_dims.array_layers()
+                {
+                    return Err(FramebufferCreationError::ResolveAttachmentDimensionsMismatch);
+                }
+            }
+        }
+
         let framebuffer = unsafe {
             let infos = vk::FramebufferCreateInfo {
                 sType: vk::STRUCTURE_TYPE_FRAMEBUFFER_CREATE_INFO,
@@ -120,11 +222,51 @@ impl<L> Framebuffer<L> {
         }))
     }
 
+    /// Builds one framebuffer for each image of a swapchain.
+    ///
+    /// `attachments` is called with each swapchain image and must return the list of
+    /// attachments of the corresponding framebuffer, in the same format as for `new`. This lets
+    /// the caller put the swapchain image at any position and add other attachments, typically
+    /// a depth buffer shared by all the framebuffers. The framebuffers have the dimensions of
+    /// the images and one layer, and are returned in the same order as the images so that the
+    /// index returned by `acquire_next_image` can be used to pick one of them.
+    ///
+    /// All the images must have the same dimensions and format. Any image type can be passed,
+    /// which makes it possible to render to other images in the same way as to a swapchain.
+    pub fn from_swapchain<'a, I, A, F>(render_pass: &Arc<L>, swapchain_images: &'a [Arc<I>],
+                                       mut attachments: F)
+                                       -> Result<Vec<Arc<Framebuffer<L>>>, FramebufferCreationError>
+        where I: ImageView + Image + 'static,
+              F: FnMut(&'a Arc<I>) -> A,
+              L: RenderPass + RenderPassDesc + RenderPassAttachmentsList<A>
+    {
+        let (dimensions, format) = match swapchain_images.first() {
+            Some(first) => {
+                let view = first.inner_view();
+                ([view.dimensions().width(), view.dimensions().height(), 1], view.format())
+            },
+            None => return Ok(Vec::new()),
+        };
+
+        for (num, image) in swapchain_images.iter().enumerate() {
+            let view = image.inner_view();
+            if view.dimensions().width() != dimensions[0] ||
+               view.dimensions().height() != dimensions[1] || view.format() != format
+            {
+                return Err(FramebufferCreationError::SwapchainImagesMismatch { image: num });
+            }
+        }
+
+        swapchain_images.iter().map(|image| {
+            Framebuffer::new(render_pass, dimensions, attachments(image))
+        }).collect()
+    }
+
     /// Returns true if this framebuffer can be used with the specified renderpass.
     #[inline]
     pub fn is_compatible_with<R>(&self, render_pass: &Arc<R>) -> bool
-        where R: RenderPass,
-              L: RenderPass + RenderPassCompatible<R>
+        where R: RenderPass + RenderPassDesc,
+              L: RenderPass + RenderPassDesc + RenderPassCompatible<R>
     {
         (&*self.render_pass.render_pass() as *const UnsafeRenderPass as usize ==
          &*render_pass.render_pass() as *const UnsafeRenderPass as usize) ||
@@ -175,6 +317,8 @@ impl<L> Framebuffer<L> {
     }
 }
 
+impl_debug_handle!(Framebuffer<L>);
+
 unsafe impl<L> VulkanObject for Framebuffer<L> {
     type Object = vk::Framebuffer;
 
@@ -204,8 +348,65 @@ pub enum FramebufferCreationError {
     DimensionsTooLarge,
     /// One of the attachments has a component swizzle that is different from identity.
     AttachmentNotIdentitySwizzled,
+    /// The number of attachments doesn't match the number of attachments of the render pass.
+    AttachmentsCountMismatch {
+        /// Number of attachments of the render pass.
+        expected: usize,
+        /// Number of attachments that were passed.
+        obtained: usize,
+    },
+    /// The format of an attachment doesn't match the format expected by the render pass.
+    AttachmentFormatMismatch {
+        /// Index of the attachment.
+        attachment: usize,
+        /// Format expected by the render pass.
+        expected: Format,
+        /// Format of the attachment.
+        obtained: Format,
+    },
+    /// The number of samples of an attachment doesn't match the render pass.
+    AttachmentSamplesMismatch {
+        /// Index of the attachment.
+        attachment: usize,
+        /// Number of samples expected by the render pass.
+        expected: u32,
+        /// Number of samples of the attachment.
+        obtained: u32,
+    },
     /// One of the attachments is too small compared to the requested framebuffer dimensions.
-    AttachmentTooSmall,
+    AttachmentTooSmall {
+        /// Index of the attachment.
+        attachment: usize,
+        /// Width and height of the attachment.
+        dimensions: [u32; 2],
+        /// Width and height of the framebuffer.
+        min: [u32; 2],
+    },
+    /// One of the attachments has fewer array layers than the framebuffer.
+    AttachmentNotEnoughLayers {
+        /// Index of the attachment.
+        attachment: usize,
+        /// Number of array layers of the attachment.
+        layers: u32,
+        /// Number of layers of the framebuffer.
+        min: u32,
+    },
+    /// One of the attachments was not created with a usage required by the render pass.
+    AttachmentMissingUsage {
+        /// Index of the attachment.
+        attachment: usize,
+        /// The usages that are required but missing.
+        missing: ImageUsage,
+    },
+    /// A resolve attachment doesn't have the same dimensions as the color attachment it
+    /// resolves.
+    ResolveAttachmentDimensionsMismatch,
+    /// One of the images passed to `from_swapchain` doesn't have the same dimensions or format
+    /// as the first one.
+    SwapchainImagesMismatch {
+        /// Index of the image.
+        image: usize,
+    },
 }
 
 impl From<OomError> for FramebufferCreationError {
@@ -225,10 +426,32 @@ impl error::Error for FramebufferCreationError {
             FramebufferCreationError::AttachmentNotIdentitySwizzled => {
                 "one of the attachments has a component swizzle that is different from identity"
             },
-            FramebufferCreationError::AttachmentTooSmall => {
+            FramebufferCreationError::AttachmentsCountMismatch { .. } => {
+                "the number of attachments doesn't match the render pass"
+            },
+            FramebufferCreationError::AttachmentFormatMismatch { .. } => {
+                "the format of one of the attachments doesn't match the render pass"
+            },
+            FramebufferCreationError::AttachmentSamplesMismatch { .. } => {
+                "the number of samples of one of the attachments doesn't match the render pass"
+            },
+            FramebufferCreationError::AttachmentTooSmall { .. } => {
                 "one of the attachments is too small compared to the requested framebuffer \
                  dimensions"
             },
+            FramebufferCreationError::AttachmentNotEnoughLayers { .. } => {
+                "one of the attachments has fewer array layers than the framebuffer"
+            },
+            FramebufferCreationError::AttachmentMissingUsage { .. } => {
+                "one of the attachments was not created with a usage required by the render pass"
+            },
+            FramebufferCreationError::ResolveAttachmentDimensionsMismatch => {
+                "a resolve attachment doesn't have the same dimensions as the color attachment \
+                 it resolves"
+            },
+            FramebufferCreationError::SwapchainImagesMismatch { .. } => {
+                "the swapchain images don't all have the same dimensions and format"
+            },
         }
     }
 
@@ -257,10 +480,24 @@ impl From<Error> for FramebufferCreationError {
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
+    use format::ClearValue;
+    use format::D16Unorm;
+    use format::Format;
     use format::R8G8B8A8Unorm;
     use framebuffer::Framebuffer;
     use framebuffer::FramebufferCreationError;
+    use framebuffer::RenderPassClearValues;
+    use framebuffer::RuntimeRenderPass;
+    use image::ImageUsage;
     use image::attachment::AttachmentImage;
+    use image::storage::StorageImage;
+    use image::sys::Dimensions;
+    use image::traits::Image;
+    use image::traits::ImageView;
+
+    use VulkanObject;
 
     mod example {
         use format::R8G8B8A8Unorm;
@@ -324,8 +561,237 @@ mod tests {
 
         let alist = example::AList { color: &image };
         match Framebuffer::new(&render_pass, [600, 600, 1], alist) {
-            Err(FramebufferCreationError::AttachmentTooSmall) => (),
+            Err(FramebufferCreationError::AttachmentTooSmall {
+                attachment: 0,
+                dimensions: [512, 512],
+                min: [600, 600],
+            }) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn attachment_not_enough_layers() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let render_pass = example::CustomRenderPass::new(&device, &example::Formats {
+            color: (R8G8B8A8Unorm, 1)
+        }).unwrap();
+
+        let image = AttachmentImage::new(&device, [512, 512], R8G8B8A8Unorm).unwrap();
+
+        let alist = example::AList { color: &image };
+        match Framebuffer::new(&render_pass, [512, 512, 2], alist) {
+            Err(FramebufferCreationError::AttachmentNotEnoughLayers {
+                attachment: 0,
+                layers: 1,
+                min: 2,
+            }) => (),
+            _ => panic!()
+        }
+    }
+
+    mod dynamic {
+        use format::Format;
+
+        single_pass_renderpass! {
+            attachments: {
+                color: {
+                    load: Clear,
+                    store: DontCare,
+                    format: Format,
+                }
+            },
+            pass: {
+                color: [color],
+                depth_stencil: {}
+            }
+        }
+    }
+
+    #[test]
+    fn attachment_format_mismatch() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let render_pass = dynamic::CustomRenderPass::new(&device, &dynamic::Formats {
+            color: (Format::B8G8R8A8Unorm, 1)
+        }).unwrap();
+
+        let image = AttachmentImage::new(&device, [512, 512], R8G8B8A8Unorm).unwrap();
+
+        let alist = dynamic::AList { color: &image };
+        match Framebuffer::new(&render_pass, [512, 512, 1], alist) {
+            Err(FramebufferCreationError::AttachmentFormatMismatch {
+                attachment: 0,
+                expected: Format::B8G8R8A8Unorm,
+                obtained: Format::R8G8B8A8Unorm,
+            }) => (),
             _ => panic!()
         }
     }
+
+    #[test]
+    fn attachment_samples_mismatch() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let render_pass = example::CustomRenderPass::new(&device, &example::Formats {
+            color: (R8G8B8A8Unorm, 4)
+        }).unwrap();
+
+        let image = AttachmentImage::new(&device, [512, 512], R8G8B8A8Unorm).unwrap();
+
+        let alist = example::AList { color: &image };
+        match Framebuffer::new(&render_pass, [512, 512, 1], alist) {
+            Err(FramebufferCreationError::AttachmentSamplesMismatch {
+                attachment: 0,
+                expected: 4,
+                obtained: 1,
+            }) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn attachment_missing_usage() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let render_pass = example::CustomRenderPass::new(&device, &example::Formats {
+            color: (R8G8B8A8Unorm, 1)
+        }).unwrap();
+
+        let image = StorageImage::new(&device, Dimensions::Dim2d { width: 512, height: 512 },
+                                      R8G8B8A8Unorm, Some(queue.family())).unwrap();
+
+        let alist = example::AList { color: &image };
+        match Framebuffer::new(&render_pass, [512, 512, 1], alist) {
+            Err(FramebufferCreationError::AttachmentMissingUsage { attachment: 0, missing }) => {
+                assert_eq!(missing, ImageUsage { color_attachment: true, .. ImageUsage::none() });
+            },
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn float_clear_values() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let render_pass = example::CustomRenderPass::new(&device, &example::Formats {
+            color: (R8G8B8A8Unorm, 1)
+        }).unwrap();
+
+        let values = render_pass.convert_clear_values(vec![[0.0, 0.5, 1.0, 1.0]]);
+        assert_eq!(values.collect::<Vec<_>>(), vec![ClearValue::Float([0.0, 0.5, 1.0, 1.0])]);
+    }
+
+    #[test]
+    #[should_panic = "The number of clear values must match the number of attachments"]
+    fn float_clear_values_wrong_count() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let render_pass = example::CustomRenderPass::new(&device, &example::Formats {
+            color: (R8G8B8A8Unorm, 1)
+        }).unwrap();
+
+        let _ = render_pass.convert_clear_values(vec![[0.0; 4], [0.0; 4]]);
+    }
+
+    #[test]
+    fn from_swapchain() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let render_pass = RuntimeRenderPass::simple(&device, Format::R8G8B8A8Unorm, 1,
+                                                    Some(Format::D16Unorm)).unwrap();
+
+        let images = (0 .. 3).map(|_| {
+            AttachmentImage::new(&device, [512, 256], Format::R8G8B8A8Unorm).unwrap()
+        }).collect::<Vec<_>>();
+        let depth = AttachmentImage::new(&device, [512, 256], Format::D16Unorm).unwrap();
+
+        let framebuffers = Framebuffer::from_swapchain(&render_pass, &images, |image| {
+            vec![
+                (image.clone() as Arc<ImageView>, image.clone() as Arc<Image>),
+                (depth.clone() as Arc<ImageView>, depth.clone() as Arc<Image>),
+            ]
+        }).unwrap();
+        assert_eq!(framebuffers.len(), 3);
+
+        for (framebuffer, image) in framebuffers.iter().zip(images.iter()) {
+            assert_eq!(framebuffer.dimensions(), [512, 256, 1]);
+            assert_eq!(framebuffer.attachments().len(), 2);
+            assert_eq!(framebuffer.attachments()[0].0.inner_view().internal_object(),
+                       image.inner_view().internal_object());
+        }
+    }
+
+    #[test]
+    fn from_swapchain_mismatch() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let render_pass = RuntimeRenderPass::simple(&device, Format::R8G8B8A8Unorm, 1, None)
+                                                   .unwrap();
+
+        let images = vec![
+            AttachmentImage::new(&device, [512, 256], Format::R8G8B8A8Unorm).unwrap(),
+            AttachmentImage::new(&device, [256, 256], Format::R8G8B8A8Unorm).unwrap(),
+        ];
+
+        let attachments = |image: &Arc<AttachmentImage<Format>>| {
+            vec![(image.clone() as Arc<ImageView>, image.clone() as Arc<Image>)]
+        };
+
+        match Framebuffer::from_swapchain(&render_pass, &images, attachments) {
+            Err(FramebufferCreationError::SwapchainImagesMismatch { image: 1 }) => (),
+            _ => panic!()
+        }
+    }
+
+    mod depth_first {
+        use format::D16Unorm;
+        use format::R8G8B8A8Unorm;
+
+        single_pass_renderpass! {
+            attachments: {
+                depth: {
+                    load: Clear,
+                    store: DontCare,
+                    format: D16Unorm,
+                },
+                color: {
+                    load: Clear,
+                    store: DontCare,
+                    format: R8G8B8A8Unorm,
+                }
+            },
+            pass: {
+                color: [color],
+                depth_stencil: {depth}
+            }
+        }
+    }
+
+    #[test]
+    fn from_swapchain_render_pass_macro() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let render_pass = depth_first::CustomRenderPass::new(&device, &depth_first::Formats {
+            depth: (D16Unorm, 1),
+            color: (R8G8B8A8Unorm, 1),
+        }).unwrap();
+
+        let images = (0 .. 2).map(|_| {
+            AttachmentImage::new(&device, [512, 256], R8G8B8A8Unorm).unwrap()
+        }).collect::<Vec<_>>();
+        let depth = AttachmentImage::new(&device, [512, 256], D16Unorm).unwrap();
+
+        let framebuffers = Framebuffer::from_swapchain(&render_pass, &images, |image| {
+            depth_first::AList { depth: &depth, color: image }
+        }).unwrap();
+
+        for (framebuffer, image) in framebuffers.iter().zip(images.iter()) {
+            assert_eq!(framebuffer.attachments()[0].0.inner_view().internal_object(),
+                       depth.inner_view().internal_object());
+            assert_eq!(framebuffer.attachments()[1].0.inner_view().internal_object(),
+                       image.inner_view().internal_object());
+        }
+    }
 }
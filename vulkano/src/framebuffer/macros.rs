@@ -8,6 +8,11 @@
 // according to those terms.
 
 /// Builds a `CustomRenderPass` object that provides a safe wrapper around `UnsafeRenderPass`.
+///
+/// The pass can optionally contain a `resolve: [...]` entry after `depth_stencil`, with one
+/// entry per color attachment. Like `depth_stencil`, each entry is either `{}` or the name of a
+/// single-sampled attachment between braces. At the end of the subpass, each multisampled color
+/// attachment is resolved into the corresponding resolve attachment, unless the entry is `{}`.
 #[macro_export]
 macro_rules! single_pass_renderpass {
     (
@@ -15,6 +20,7 @@ macro_rules! single_pass_renderpass {
         pass: {
             color: [$($color_atch:ident),*],
             depth_stencil: {$($depth_atch:ident)*}
+            $(, resolve: [$({$($resolve_atch:ident)*}),*])?
         }
     ) => {
         ordered_passes_renderpass!{
@@ -24,6 +30,7 @@ macro_rules! single_pass_renderpass {
                     color: [$($color_atch),*],
                     depth_stencil: {$($depth_atch)*},
                     input: []
+                    $(, resolve: [$({$($resolve_atch)*}),*])?
                 }
             ]
         }
@@ -31,6 +38,9 @@ macro_rules! single_pass_renderpass {
 }
 
 /// Builds a `CustomRenderPass` object that provides a safe wrapper around `UnsafeRenderPass`.
+///
+/// Each pass can optionally contain a `resolve: [...]` entry after `input`. See
+/// `single_pass_renderpass!`.
 #[macro_export]
 macro_rules! ordered_passes_renderpass {
     (
@@ -51,6 +61,7 @@ macro_rules! ordered_passes_renderpass {
                     color: [$($color_atch:ident),*],
                     depth_stencil: {$($depth_atch:ident)*},
                     input: [$($input_atch:ident),*]
+                    $(, resolve: [$({$($resolve_atch:ident)*}),*])?
                 }
             ),*
         ]
@@ -93,6 +104,19 @@ macro_rules! ordered_passes_renderpass {
             {
                 #![allow(unsafe_code)]
 
+                $($(
+                    $crate::framebuffer::traits::ensure_depth_stencil_attachment(
+                                                                &formats.$depth_atch.0);
+                )*)*
+
+                {
+                    let attachments = AttachmentsIter(formats.clone(), 0).collect::<Vec<_>>();
+                    for (num, pass) in PassesIter(0).enumerate() {
+                        try!($crate::framebuffer::check_resolve_attachments(&attachments, num,
+                                                                             &pass));
+                    }
+                }
+
                 let rp = try!(unsafe {
                     UnsafeRenderPass::new(device, AttachmentsIter(formats.clone(), 0),
                                           PassesIter(0), DependenciesIter(0, 0))
@@ -226,11 +250,21 @@ macro_rules! ordered_passes_renderpass {
                                     ($input_atch, Layout::ShaderReadOnlyOptimal)
                                 ),*
                             ],
-                            resolve_attachments: vec![],
+                            resolve_attachments: vec![
+                                $($({
+                                    let mut resolve = None;
+                                    $(
+                                        resolve = Some(($resolve_atch,
+                                                        Layout::ColorAttachmentOptimal));
+                                    )*
+                                    resolve
+                                },)*)?
+                            ],
                             preserve_attachments: (0 .. attachment_num).filter(|&a| {
                                 $(if a == $color_atch { return false; })*
                                 $(if a == $depth_atch { return false; })*
                                 $(if a == $input_atch { return false; })*
+                                $($($(if a == $resolve_atch { return false; })*)*)?
                                 true
                             }).collect()
                         });
@@ -338,6 +372,15 @@ macro_rules! ordered_passes_renderpass {
                         final_layout = Some(Layout::ShaderReadOnlyOptimal);
                     }
                 )*
+
+                $($($(
+                    if $resolve_atch == num {
+                        if initial_layout.is_none() {
+                            initial_layout = Some(Layout::ColorAttachmentOptimal);
+                        }
+                        final_layout = Some(Layout::ColorAttachmentOptimal);
+                    }
+                )*)*)?
             })*
 
             $(if $atch_name == num {
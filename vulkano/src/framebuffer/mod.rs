@@ -35,6 +35,8 @@
 //! - Creating an instance of an `EmptySinglePassRenderPass`, which describes a render pass with no
 //!   attachment and with one subpass.
 //! - Using the `single_pass_renderpass!` macro. See the documentation of this macro.
+//! - Building a `RuntimeRenderPassDesc` when the attachments and subpasses are only known at
+//!   runtime.
 //!
 //! Render passes have three characteristics:
 //!
@@ -69,8 +71,13 @@
 pub use self::empty::EmptySinglePassRenderPass;
 pub use self::framebuffer::Framebuffer;
 pub use self::framebuffer::FramebufferCreationError;
+pub use self::runtime::RenderPassDescError;
+#[doc(hidden)] pub use self::runtime::check_resolve_attachments;
+pub use self::runtime::RuntimeRenderPass;
+pub use self::runtime::RuntimeRenderPassDesc;
 pub use self::sys::UnsafeRenderPass;
 pub use self::sys::RenderPassCreationError;
+pub use self::traits::ClearValuesError;
 pub use self::traits::RenderPass;
 pub use self::traits::RenderPassDesc;
 pub use self::traits::RenderPassAttachmentsList;
@@ -83,10 +90,12 @@ pub use self::traits::LayoutPassDependencyDescription;
 pub use self::traits::StoreOp;
 pub use self::traits::LoadOp;
 pub use self::traits::Subpass;
+pub use self::traits::SUBPASS_EXTERNAL;
 
 #[macro_use]
 mod macros;
 mod empty;
 mod framebuffer;
+mod runtime;
 mod sys;
 #[doc(hidden)] pub mod traits;      // TODO: pub-hidden because of that trait visibility bug
@@ -0,0 +1,1352 @@
+// Copyright (c) 2016 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::error;
+use std::fmt;
+use std::sync::Arc;
+use std::vec::IntoIter as VecIntoIter;
+
+use device::Device;
+use format::Format;
+use framebuffer::FramebufferCreationError;
+use framebuffer::LayoutAttachmentDescription;
+use framebuffer::LayoutPassDependencyDescription;
+use framebuffer::LayoutPassDescription;
+use framebuffer::LoadOp;
+use framebuffer::RenderPass;
+use framebuffer::RenderPassAttachmentsList;
+use framebuffer::RenderPassCreationError;
+use framebuffer::RenderPassDesc;
+use framebuffer::StoreOp;
+use framebuffer::SUBPASS_EXTERNAL;
+use framebuffer::UnsafeRenderPass;
+use image::Layout as ImageLayout;
+use image::traits::Image;
+use image::traits::ImageView;
+use sync::PipelineStages;
+use vk;
+
+/// Description of a render pass whose attachments, subpasses and dependencies are only known at
+/// runtime.
+///
+/// Attachments, subpasses and dependencies are added one by one, then the description is turned
+/// into a `RuntimeRenderPass` with `build`. The description is checked when it is built, which
+/// makes this a safe alternative to `UnsafeRenderPass` for render passes that can't be described
+/// with the render pass macros.
+///
+/// # Example
+///
+/// ```no_run
+/// use vulkano::format::Format;
+/// use vulkano::framebuffer::LayoutAttachmentDescription;
+/// use vulkano::framebuffer::LayoutPassDescription;
+/// use vulkano::framebuffer::LoadOp;
+/// use vulkano::framebuffer::RuntimeRenderPassDesc;
+/// use vulkano::framebuffer::StoreOp;
+/// use vulkano::image::Layout;
+///
+/// # use vulkano::device::Device;
+/// # use vulkano::instance::DeviceExtensions;
+/// # use vulkano::instance::Instance;
+/// # use vulkano::instance::InstanceExtensions;
+/// # use vulkano::instance::PhysicalDevice;
+/// # let instance = Instance::new(None, &InstanceExtensions::none(), None).unwrap();
+/// # let physical = PhysicalDevice::enumerate(&instance).next().unwrap();
+/// # let queue = physical.queue_families().next().unwrap();
+/// # let (device, _) = Device::new(&physical, physical.supported_features(),
+/// #                               &DeviceExtensions::none(), None,
+/// #                               [(queue, 0.5)].iter().cloned()).unwrap();
+/// let mut desc = RuntimeRenderPassDesc::new();
+///
+/// let color = desc.add_attachment(LayoutAttachmentDescription {
+///     format: Format::R8G8B8A8Unorm,
+///     samples: 1,
+///     load: LoadOp::Clear,
+///     store: StoreOp::Store,
+///     initial_layout: Layout::Undefined,
+///     final_layout: Layout::ColorAttachmentOptimal,
+/// });
+///
+/// desc.add_pass(LayoutPassDescription {
+///     color_attachments: vec![(color, Layout::ColorAttachmentOptimal)],
+///     depth_stencil: None,
+///     input_attachments: vec![],
+///     resolve_attachments: vec![],
+///     preserve_attachments: vec![],
+/// });
+///
+/// let render_pass = desc.build(&device).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct RuntimeRenderPassDesc {
+    attachments: Vec<LayoutAttachmentDescription>,
+    passes: Vec<LayoutPassDescription>,
+    dependencies: Vec<LayoutPassDependencyDescription>,
+}
+
+impl RuntimeRenderPassDesc {
+    /// Builds a new description with no attachment, no subpass and no dependency.
+    #[inline]
+    pub fn new() -> RuntimeRenderPassDesc {
+        RuntimeRenderPassDesc {
+            attachments: Vec::new(),
+            passes: Vec::new(),
+            dependencies: Vec::new(),
+        }
+    }
+
+    /// Adds an attachment to the description and returns its index.
+    #[inline]
+    pub fn add_attachment(&mut self, attachment: LayoutAttachmentDescription) -> usize {
+        self.attachments.push(attachment);
+        self.attachments.len() - 1
+    }
+
+    /// Adds a subpass to the description and returns its index.
+    #[inline]
+    pub fn add_pass(&mut self, pass: LayoutPassDescription) -> usize {
+        self.passes.push(pass);
+        self.passes.len() - 1
+    }
+
+    /// Adds a dependency between two subpasses to the description.
+    ///
+    /// The source or the destination subpass can be `SUBPASS_EXTERNAL` to describe a dependency
+    /// with the commands that are outside of the render pass.
+    #[inline]
+    pub fn add_dependency(&mut self, dependency: LayoutPassDependencyDescription) {
+        self.dependencies.push(dependency);
+    }
+
+    /// Adds to the preserve attachments of each subpass the attachments whose content must be
+    /// kept.
+    ///
+    /// An attachment is preserved by each subpass that doesn't use it and that comes between the
+    /// first and the last subpasses that use it. The preserve attachments that are already in
+    /// the description are kept.
+    pub fn compute_preserve_attachments(&mut self) {
+        for attachment in 0 .. self.attachments.len() {
+            let first = self.passes.iter().position(|p| pass_uses(p, attachment));
+            let last = self.passes.iter().rposition(|p| pass_uses(p, attachment));
+
+            let (first, last) = match (first, last) {
+                (Some(f), Some(l)) if l > f => (f, l),
+                _ => continue,
+            };
+
+            for pass in self.passes[first + 1 .. last].iter_mut() {
+                if !pass_uses(pass, attachment) &&
+                   !pass.preserve_attachments.contains(&attachment)
+                {
+                    pass.preserve_attachments.push(attachment);
+                }
+            }
+        }
+    }
+
+    /// Checks whether the description is valid.
+    ///
+    /// In addition to the validity of each subpass, this checks that the content of each input
+    /// attachment is produced by an earlier subpass (and preserved until it is read, with a
+    /// dependency between the two subpasses) or loaded at the start of the render pass.
+    ///
+    /// This is automatically called by `build`.
+    pub fn check(&self) -> Result<(), RenderPassDescError> {
+        if self.passes.is_empty() {
+            return Err(RenderPassDescError::NoPass);
+        }
+
+        for (num, attachment) in self.attachments.iter().enumerate() {
+            match attachment.final_layout {
+                ImageLayout::Undefined | ImageLayout::Preinitialized => {
+                    return Err(RenderPassDescError::InvalidFinalLayout { attachment: num });
+                },
+                _ => ()
+            }
+        }
+
+        for (pass_num, pass) in self.passes.iter().enumerate() {
+            let kinds = pass.color_attachments.iter().map(|&a| (a, ReferenceKind::Color))
+                .chain(pass.depth_stencil.iter().map(|&a| (a, ReferenceKind::DepthStencil)))
+                .chain(pass.input_attachments.iter().map(|&a| (a, ReferenceKind::Input)))
+                .chain(pass.resolve_attachments.iter().filter_map(|&a| a)
+                                                    .map(|a| (a, ReferenceKind::Resolve)));
+
+            for ((attachment, layout), kind) in kinds {
+                if attachment >= self.attachments.len() {
+                    return Err(RenderPassDescError::AttachmentOutOfRange {
+                        pass: pass_num,
+                        attachment: attachment,
+                    });
+                }
+
+                if !kind.accepts(layout) {
+                    return Err(RenderPassDescError::InvalidReferenceLayout {
+                        pass: pass_num,
+                        attachment: attachment,
+                        layout: layout,
+                    });
+                }
+
+                let format = self.attachments[attachment].format;
+                match kind {
+                    ReferenceKind::Color | ReferenceKind::Resolve => {
+                        if format.is_depth_or_stencil() || format.is_compressed() {
+                            return Err(RenderPassDescError::InvalidColorFormat {
+                                attachment: attachment,
+                            });
+                        }
+                    },
+                    ReferenceKind::DepthStencil => {
+                        if !format.is_depth_or_stencil() {
+                            return Err(RenderPassDescError::InvalidDepthStencilFormat {
+                                attachment: attachment,
+                            });
+                        }
+                    },
+                    ReferenceKind::Input => (),
+                }
+            }
+
+            for &attachment in pass.preserve_attachments.iter() {
+                if attachment >= self.attachments.len() {
+                    return Err(RenderPassDescError::AttachmentOutOfRange {
+                        pass: pass_num,
+                        attachment: attachment,
+                    });
+                }
+
+                if pass_uses(pass, attachment) {
+                    return Err(RenderPassDescError::PreservedAttachmentUsed {
+                        pass: pass_num,
+                        attachment: attachment,
+                    });
+                }
+            }
+
+            try!(check_resolve_attachments(&self.attachments, pass_num, pass));
+        }
+
+        for (num, dependency) in self.dependencies.iter().enumerate() {
+            for &subpass in [dependency.source_subpass, dependency.destination_subpass].iter() {
+                if subpass != SUBPASS_EXTERNAL && subpass >= self.passes.len() {
+                    return Err(RenderPassDescError::DependencySubpassOutOfRange {
+                        dependency: num,
+                        subpass: subpass,
+                    });
+                }
+            }
+
+            // A dependency must go from an earlier subpass to a later one, and can't be external
+            // on both sides.
+            let valid_order = match (dependency.source_subpass, dependency.destination_subpass) {
+                (SUBPASS_EXTERNAL, SUBPASS_EXTERNAL) => false,
+                (SUBPASS_EXTERNAL, _) | (_, SUBPASS_EXTERNAL) => true,
+                (src, dst) => src <= dst,
+            };
+
+            if !valid_order {
+                return Err(RenderPassDescError::DependencyWrongOrder { dependency: num });
+            }
+
+            // A self-dependency can only synchronize framebuffer-space stages, and only within
+            // each region of the framebuffer.
+            if dependency.source_subpass == dependency.destination_subpass {
+                if !is_framebuffer_space(dependency.src_stages) ||
+                   !is_framebuffer_space(dependency.dst_stages)
+                {
+                    return Err(RenderPassDescError::SelfDependencyNotFramebufferSpace {
+                        dependency: num,
+                    });
+                }
+
+                if !dependency.by_region {
+                    return Err(RenderPassDescError::SelfDependencyNotByRegion {
+                        dependency: num,
+                    });
+                }
+            }
+        }
+
+        for (pass_num, pass) in self.passes.iter().enumerate() {
+            for &(attachment, _) in pass.input_attachments.iter() {
+                try!(self.check_input_attachment(pass_num, attachment));
+            }
+        }
+
+        Ok(())
+    }
+
+    // Checks that the content of an input attachment of a subpass has been correctly produced by
+    // the previous subpasses, or loaded at the start of the render pass.
+    fn check_input_attachment(&self, pass_num: usize, attachment: usize)
+                              -> Result<(), RenderPassDescError>
+    {
+        let uses = |pass: &LayoutPassDescription| pass_uses(pass, attachment);
+        let writes = |pass: &LayoutPassDescription| {
+            pass.color_attachments.iter().chain(pass.depth_stencil.iter())
+                .chain(pass.resolve_attachments.iter().flat_map(|a| a.iter()))
+                .any(|&(a, _)| a == attachment)
+        };
+
+        let producer = match self.passes[.. pass_num].iter().rposition(|p| writes(p)) {
+            Some(p) => p,
+            None => {
+                // The first use of an attachment as an input attachment reads the content it had
+                // at the start of the render pass, unless the subpass also writes it.
+                let first_use = !self.passes[.. pass_num].iter().any(|p| uses(p));
+                if first_use && !writes(&self.passes[pass_num]) &&
+                   self.attachments[attachment].load == LoadOp::Clear
+                {
+                    return Err(RenderPassDescError::InputAttachmentCleared {
+                        pass: pass_num,
+                        attachment: attachment,
+                    });
+                }
+
+                return Ok(());
+            },
+        };
+
+        // The subpasses between the producer and this subpass must preserve the attachment.
+        for (num, pass) in self.passes.iter().enumerate().take(pass_num).skip(producer + 1) {
+            if !uses(pass) && !pass.preserve_attachments.contains(&attachment) {
+                return Err(RenderPassDescError::AttachmentNotPreserved {
+                    pass: num,
+                    attachment: attachment,
+                });
+            }
+        }
+
+        // There must be a chain of dependencies from the producer to this subpass. Since
+        // dependencies always go forward, walking the subpasses in order is enough.
+        let mut reachable = vec![false; pass_num + 1];
+        reachable[producer] = true;
+        for subpass in producer .. pass_num {
+            if !reachable[subpass] {
+                continue;
+            }
+
+            for dependency in self.dependencies.iter() {
+                if dependency.source_subpass == subpass &&
+                   dependency.destination_subpass <= pass_num
+                {
+                    reachable[dependency.destination_subpass] = true;
+                }
+            }
+        }
+
+        if !reachable[pass_num] {
+            return Err(RenderPassDescError::MissingDependency {
+                source: producer,
+                destination: pass_num,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Checks the description and builds a render pass from it.
+    pub fn build(self, device: &Arc<Device>)
+                 -> Result<Arc<RuntimeRenderPass>, RenderPassCreationError>
+    {
+        try!(self.check());
+
+        let render_pass = try!(unsafe {
+            UnsafeRenderPass::new(device, self.attachments.clone().into_iter(),
+                                  self.passes.clone().into_iter(),
+                                  self.dependencies.clone().into_iter())
+        });
+
+        Ok(Arc::new(RuntimeRenderPass {
+            render_pass: render_pass,
+            desc: self,
+        }))
+    }
+}
+
+unsafe impl RenderPassDesc for RuntimeRenderPassDesc {
+    type AttachmentsIter = VecIntoIter<LayoutAttachmentDescription>;
+    type PassesIter = VecIntoIter<LayoutPassDescription>;
+    type DependenciesIter = VecIntoIter<LayoutPassDependencyDescription>;
+
+    #[inline]
+    fn attachments(&self) -> Self::AttachmentsIter {
+        self.attachments.clone().into_iter()
+    }
+
+    #[inline]
+    fn passes(&self) -> Self::PassesIter {
+        self.passes.clone().into_iter()
+    }
+
+    #[inline]
+    fn dependencies(&self) -> Self::DependenciesIter {
+        self.dependencies.clone().into_iter()
+    }
+
+    #[inline]
+    fn num_subpasses(&self) -> u32 {
+        self.passes.len() as u32
+    }
+}
+
+// The ways a subpass can refer to an attachment, each of them allowing different layouts.
+#[derive(Copy, Clone)]
+enum ReferenceKind {
+    Color,
+    DepthStencil,
+    Input,
+    Resolve,
+}
+
+impl ReferenceKind {
+    // Returns true if an attachment can be referred to in this way with the given layout.
+    #[inline]
+    fn accepts(&self, layout: ImageLayout) -> bool {
+        match (*self, layout) {
+            (_, ImageLayout::General) => true,
+            (ReferenceKind::Color, ImageLayout::ColorAttachmentOptimal) => true,
+            (ReferenceKind::Resolve, ImageLayout::ColorAttachmentOptimal) => true,
+            (ReferenceKind::DepthStencil, ImageLayout::DepthStencilAttachmentOptimal) => true,
+            (ReferenceKind::DepthStencil, ImageLayout::DepthStencilReadOnlyOptimal) => true,
+            (ReferenceKind::Input, ImageLayout::ShaderReadOnlyOptimal) => true,
+            (ReferenceKind::Input, ImageLayout::DepthStencilReadOnlyOptimal) => true,
+            _ => false
+        }
+    }
+}
+
+/// Implementation of `RenderPass` built from a `RuntimeRenderPassDesc`.
+///
+/// The attachments of a framebuffer are passed as a list of views and their images, in the
+/// same order as the attachments of the description.
+pub struct RuntimeRenderPass {
+    render_pass: UnsafeRenderPass,
+    desc: RuntimeRenderPassDesc,
+}
+
+impl RuntimeRenderPass {
+    /// Builds a render pass with a single subpass that draws to one color attachment and
+    /// optionally to one depth attachment.
+    ///
+    /// This is the runtime equivalent of a simple `single_pass_renderpass!`, and is useful when
+    /// the format of the color attachment is only known at runtime, for example when it comes
+    /// from a swapchain. The color attachment is cleared at the start of the render pass and
+    /// stored at the end. The depth attachment, if any, has the same number of samples as the
+    /// color attachment and is cleared and then discarded. The attachments are in that order.
+    ///
+    /// Returns an error if `color_format` isn't a color format, or if `depth_format` doesn't have
+    /// a depth aspect.
+    pub fn simple(device: &Arc<Device>, color_format: Format, color_samples: u32,
+                  depth_format: Option<Format>)
+                  -> Result<Arc<RuntimeRenderPass>, RenderPassCreationError>
+    {
+        let mut desc = RuntimeRenderPassDesc::new();
+
+        let color = desc.add_attachment(LayoutAttachmentDescription {
+            format: color_format,
+            samples: color_samples,
+            load: LoadOp::Clear,
+            store: StoreOp::Store,
+            initial_layout: ImageLayout::ColorAttachmentOptimal,
+            final_layout: ImageLayout::ColorAttachmentOptimal,
+        });
+
+        let depth = match depth_format {
+            Some(format) => {
+                if !format.has_depth() {
+                    return Err(RenderPassDescError::InvalidDepthStencilFormat {
+                        attachment: 1,
+                    }.into());
+                }
+
+                let depth = desc.add_attachment(LayoutAttachmentDescription {
+                    format: format,
+                    samples: color_samples,
+                    load: LoadOp::Clear,
+                    store: StoreOp::DontCare,
+                    initial_layout: ImageLayout::DepthStencilAttachmentOptimal,
+                    final_layout: ImageLayout::DepthStencilAttachmentOptimal,
+                });
+
+                Some((depth, ImageLayout::DepthStencilAttachmentOptimal))
+            },
+            None => None,
+        };
+
+        desc.add_pass(LayoutPassDescription {
+            color_attachments: vec![(color, ImageLayout::ColorAttachmentOptimal)],
+            depth_stencil: depth,
+            input_attachments: vec![],
+            resolve_attachments: vec![],
+            preserve_attachments: vec![],
+        });
+
+        desc.build(device)
+    }
+
+    /// Returns the description this render pass was built from.
+    #[inline]
+    pub fn desc(&self) -> &RuntimeRenderPassDesc {
+        &self.desc
+    }
+}
+
+unsafe impl RenderPass for RuntimeRenderPass {
+    #[inline]
+    fn render_pass(&self) -> &UnsafeRenderPass {
+        &self.render_pass
+    }
+}
+
+unsafe impl RenderPassDesc for RuntimeRenderPass {
+    type AttachmentsIter = VecIntoIter<LayoutAttachmentDescription>;
+    type PassesIter = VecIntoIter<LayoutPassDescription>;
+    type DependenciesIter = VecIntoIter<LayoutPassDependencyDescription>;
+
+    #[inline]
+    fn attachments(&self) -> Self::AttachmentsIter {
+        self.desc.attachments()
+    }
+
+    #[inline]
+    fn passes(&self) -> Self::PassesIter {
+        self.desc.passes()
+    }
+
+    #[inline]
+    fn dependencies(&self) -> Self::DependenciesIter {
+        self.desc.dependencies()
+    }
+
+    #[inline]
+    fn num_subpasses(&self) -> u32 {
+        self.desc.num_subpasses()
+    }
+}
+
+/// The attachments of a framebuffer can be passed as a list of views, each with the image it was
+/// created from. The views are checked against the description when creating the framebuffer.
+unsafe impl RenderPassAttachmentsList<Vec<(Arc<ImageView>, Arc<Image>)>> for RuntimeRenderPass {
+    type AttachmentsIter = VecIntoIter<(Arc<ImageView>, Arc<Image>, ImageLayout, ImageLayout)>;
+
+    fn convert_attachments_list(&self, list: Vec<(Arc<ImageView>, Arc<Image>)>)
+                                -> Result<Self::AttachmentsIter, FramebufferCreationError>
+    {
+        if list.len() != self.desc.attachments.len() {
+            return Err(FramebufferCreationError::AttachmentsCountMismatch {
+                expected: self.desc.attachments.len(),
+                obtained: list.len(),
+            });
+        }
+
+        let mut result = Vec::with_capacity(list.len());
+
+        for ((view, image), desc) in list.into_iter().zip(self.desc.attachments.iter()) {
+            if !view.identity_swizzle() {
+                return Err(FramebufferCreationError::AttachmentNotIdentitySwizzled);
+            }
+
+            result.push((view, image, desc.initial_layout, desc.final_layout));
+        }
+
+        Ok(result.into_iter())
+    }
+}
+
+// Returns true if all the stages are framebuffer-space stages, in other words stages that operate
+// on individual fragments.
+fn is_framebuffer_space(stages: PipelineStages) -> bool {
+    let framebuffer_space: vk::PipelineStageFlagBits = PipelineStages {
+        fragment_shader: true,
+        early_fragment_tests: true,
+        late_fragment_tests: true,
+        color_attachment_output: true,
+        .. PipelineStages::none()
+    }.into();
+
+    let stages: vk::PipelineStageFlagBits = stages.into();
+    stages & !framebuffer_space == 0
+}
+
+// Returns true if the subpass refers to the attachment, not counting the preserve attachments.
+fn pass_uses(pass: &LayoutPassDescription, attachment: usize) -> bool {
+    pass.color_attachments.iter().chain(pass.depth_stencil.iter())
+        .chain(pass.input_attachments.iter())
+        .chain(pass.resolve_attachments.iter().flat_map(|a| a.iter()))
+        .any(|&(a, _)| a == attachment)
+}
+
+/// Checks the resolve attachments of a subpass against the description of the attachments.
+///
+/// The attachments referenced by the subpass must be in range.
+#[doc(hidden)]
+pub fn check_resolve_attachments(attachments: &[LayoutAttachmentDescription], pass_num: usize,
+                                 pass: &LayoutPassDescription) -> Result<(), RenderPassDescError>
+{
+    if pass.resolve_attachments.is_empty() {
+        return Ok(());
+    }
+
+    if pass.resolve_attachments.len() != pass.color_attachments.len() {
+        return Err(RenderPassDescError::ResolveAttachmentsCountMismatch { pass: pass_num });
+    }
+
+    for (resolve, &(color, _)) in pass.resolve_attachments.iter()
+                                      .zip(pass.color_attachments.iter())
+    {
+        let resolve = match *resolve {
+            Some((resolve, _)) => resolve,
+            None => continue,
+        };
+
+        if attachments[resolve].samples != 1 {
+            return Err(RenderPassDescError::ResolveAttachmentMultisampled {
+                pass: pass_num,
+                attachment: resolve,
+            });
+        }
+
+        if attachments[color].samples == 1 {
+            return Err(RenderPassDescError::ResolvedAttachmentNotMultisampled {
+                pass: pass_num,
+                attachment: color,
+            });
+        }
+
+        if attachments[resolve].format != attachments[color].format {
+            return Err(RenderPassDescError::ResolveAttachmentFormatMismatch {
+                pass: pass_num,
+                attachment: resolve,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Error that can happen when checking a `RuntimeRenderPassDesc`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenderPassDescError {
+    /// The description has no subpass.
+    NoPass,
+
+    /// The final layout of an attachment is `Undefined` or `Preinitialized`.
+    InvalidFinalLayout {
+        /// Index of the attachment.
+        attachment: usize,
+    },
+
+    /// A subpass refers to an attachment that doesn't exist.
+    AttachmentOutOfRange {
+        /// Index of the subpass.
+        pass: usize,
+        /// Index of the attachment.
+        attachment: usize,
+    },
+
+    /// A subpass refers to an attachment with a layout that can't be used for this kind of
+    /// reference.
+    InvalidReferenceLayout {
+        /// Index of the subpass.
+        pass: usize,
+        /// Index of the attachment.
+        attachment: usize,
+        /// The layout of the reference.
+        layout: ImageLayout,
+    },
+
+    /// The resolve attachments of a subpass are not empty and not as many as its color
+    /// attachments.
+    ResolveAttachmentsCountMismatch {
+        /// Index of the subpass.
+        pass: usize,
+    },
+
+    /// A resolve attachment has more than one sample.
+    ResolveAttachmentMultisampled {
+        /// Index of the subpass.
+        pass: usize,
+        /// Index of the attachment.
+        attachment: usize,
+    },
+
+    /// A color attachment that is resolved has only one sample.
+    ResolvedAttachmentNotMultisampled {
+        /// Index of the subpass.
+        pass: usize,
+        /// Index of the attachment.
+        attachment: usize,
+    },
+
+    /// A color or resolve attachment doesn't have a color format.
+    InvalidColorFormat {
+        /// Index of the attachment.
+        attachment: usize,
+    },
+
+    /// A depth-stencil attachment doesn't have a depth and/or stencil format, or an attachment
+    /// that must have a depth aspect doesn't have one.
+    InvalidDepthStencilFormat {
+        /// Index of the attachment.
+        attachment: usize,
+    },
+
+    /// A subpass preserves an attachment that it also uses.
+    PreservedAttachmentUsed {
+        /// Index of the subpass.
+        pass: usize,
+        /// Index of the attachment.
+        attachment: usize,
+    },
+
+    /// A resolve attachment doesn't have the same format as the color attachment it resolves.
+    ResolveAttachmentFormatMismatch {
+        /// Index of the subpass.
+        pass: usize,
+        /// Index of the resolve attachment.
+        attachment: usize,
+    },
+
+    /// A dependency refers to a subpass that doesn't exist and that isn't `SUBPASS_EXTERNAL`.
+    DependencySubpassOutOfRange {
+        /// Index of the dependency.
+        dependency: usize,
+        /// Index of the subpass.
+        subpass: usize,
+    },
+
+    /// The source subpass of a dependency comes after its destination subpass, or both are
+    /// `SUBPASS_EXTERNAL`.
+    DependencyWrongOrder {
+        /// Index of the dependency.
+        dependency: usize,
+    },
+
+    /// A dependency of a subpass with itself has stages that are not framebuffer-space stages.
+    SelfDependencyNotFramebufferSpace {
+        /// Index of the dependency.
+        dependency: usize,
+    },
+
+    /// A dependency of a subpass with itself doesn't have `by_region` set.
+    SelfDependencyNotByRegion {
+        /// Index of the dependency.
+        dependency: usize,
+    },
+
+    /// An attachment is first used as an input attachment, but its content is cleared at the
+    /// start of the render pass.
+    InputAttachmentCleared {
+        /// Index of the subpass.
+        pass: usize,
+        /// Index of the attachment.
+        attachment: usize,
+    },
+
+    /// A subpass between the subpass that writes an attachment and the subpass that reads it as
+    /// an input attachment neither uses nor preserves it.
+    AttachmentNotPreserved {
+        /// Index of the subpass that doesn't preserve the attachment.
+        pass: usize,
+        /// Index of the attachment.
+        attachment: usize,
+    },
+
+    /// A subpass reads an input attachment written by an earlier subpass, but there is no
+    /// dependency between the two.
+    MissingDependency {
+        /// Index of the subpass that writes the attachment.
+        source: usize,
+        /// Index of the subpass that reads the attachment.
+        destination: usize,
+    },
+}
+
+impl error::Error for RenderPassDescError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            RenderPassDescError::NoPass => {
+                "the render pass has no subpass"
+            },
+            RenderPassDescError::InvalidFinalLayout { .. } => {
+                "the final layout of an attachment is `Undefined` or `Preinitialized`"
+            },
+            RenderPassDescError::AttachmentOutOfRange { .. } => {
+                "a subpass refers to an attachment that doesn't exist"
+            },
+            RenderPassDescError::InvalidReferenceLayout { .. } => {
+                "a subpass refers to an attachment with a layout that can't be used for this \
+                 kind of reference"
+            },
+            RenderPassDescError::ResolveAttachmentsCountMismatch { .. } => {
+                "the number of resolve attachments of a subpass doesn't match its number of \
+                 color attachments"
+            },
+            RenderPassDescError::ResolveAttachmentMultisampled { .. } => {
+                "a resolve attachment has more than one sample"
+            },
+            RenderPassDescError::ResolvedAttachmentNotMultisampled { .. } => {
+                "a color attachment that is resolved has only one sample"
+            },
+            RenderPassDescError::InvalidColorFormat { .. } => {
+                "a color or resolve attachment doesn't have a color format"
+            },
+            RenderPassDescError::InvalidDepthStencilFormat { .. } => {
+                "a depth-stencil attachment doesn't have a depth and/or stencil format"
+            },
+            RenderPassDescError::PreservedAttachmentUsed { .. } => {
+                "a subpass preserves an attachment that it also uses"
+            },
+            RenderPassDescError::ResolveAttachmentFormatMismatch { .. } => {
+                "a resolve attachment doesn't have the same format as the color attachment it \
+                 resolves"
+            },
+            RenderPassDescError::DependencySubpassOutOfRange { .. } => {
+                "a dependency refers to a subpass that doesn't exist"
+            },
+            RenderPassDescError::DependencyWrongOrder { .. } => {
+                "the source subpass of a dependency comes after its destination subpass"
+            },
+            RenderPassDescError::SelfDependencyNotFramebufferSpace { .. } => {
+                "a dependency of a subpass with itself has stages that are not framebuffer-space \
+                 stages"
+            },
+            RenderPassDescError::SelfDependencyNotByRegion { .. } => {
+                "a dependency of a subpass with itself is not by region"
+            },
+            RenderPassDescError::InputAttachmentCleared { .. } => {
+                "an attachment is first used as an input attachment but its content is cleared \
+                 at the start of the render pass"
+            },
+            RenderPassDescError::AttachmentNotPreserved { .. } => {
+                "a subpass between the subpass that writes an attachment and the subpass that \
+                 reads it as an input attachment doesn't preserve it"
+            },
+            RenderPassDescError::MissingDependency { .. } => {
+                "a subpass reads an input attachment written by an earlier subpass without a \
+                 dependency between the two"
+            },
+        }
+    }
+}
+
+impl fmt::Display for RenderPassDescError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use format::ClearValue;
+    use format::Format;
+    use framebuffer::ClearValuesError;
+    use framebuffer::Framebuffer;
+    use framebuffer::LayoutAttachmentDescription;
+    use framebuffer::LayoutPassDependencyDescription;
+    use framebuffer::LayoutPassDescription;
+    use framebuffer::LoadOp;
+    use framebuffer::RenderPassCreationError;
+    use framebuffer::RenderPassDesc;
+    use framebuffer::RenderPassDescError;
+    use framebuffer::RuntimeRenderPass;
+    use framebuffer::RuntimeRenderPassDesc;
+    use framebuffer::StoreOp;
+    use framebuffer::SUBPASS_EXTERNAL;
+    use framebuffer::Subpass;
+    use image::Layout;
+    use image::attachment::AttachmentImage;
+    use image::traits::Image;
+    use image::traits::ImageView;
+    use sync::AccessFlagBits;
+    use sync::PipelineStages;
+
+    fn attachment(format: Format, samples: u32, final_layout: Layout)
+                  -> LayoutAttachmentDescription
+    {
+        LayoutAttachmentDescription {
+            format: format,
+            samples: samples,
+            load: LoadOp::Clear,
+            store: StoreOp::Store,
+            initial_layout: Layout::Undefined,
+            final_layout: final_layout,
+        }
+    }
+
+    fn pass(color: Vec<(usize, Layout)>, depth_stencil: Option<(usize, Layout)>,
+            input: Vec<(usize, Layout)>) -> LayoutPassDescription
+    {
+        LayoutPassDescription {
+            color_attachments: color,
+            depth_stencil: depth_stencil,
+            input_attachments: input,
+            resolve_attachments: vec![],
+            preserve_attachments: vec![],
+        }
+    }
+
+    fn dependency(src: usize, dst: usize) -> LayoutPassDependencyDescription {
+        LayoutPassDependencyDescription {
+            source_subpass: src,
+            destination_subpass: dst,
+            src_stages: PipelineStages { color_attachment_output: true, .. PipelineStages::none() },
+            dst_stages: PipelineStages { fragment_shader: true, .. PipelineStages::none() },
+            src_access: AccessFlagBits { color_attachment_write: true, .. AccessFlagBits::none() },
+            dst_access: AccessFlagBits { input_attachment_read: true, .. AccessFlagBits::none() },
+            by_region: true,
+        }
+    }
+
+    // A first subpass that writes an albedo, a normal and a depth attachment, and a second
+    // subpass that reads them as input attachments to write the final color.
+    fn gbuffer() -> RuntimeRenderPassDesc {
+        let mut desc = RuntimeRenderPassDesc::new();
+
+        let albedo = desc.add_attachment(attachment(Format::R8G8B8A8Unorm, 1,
+                                                    Layout::ShaderReadOnlyOptimal));
+        let normal = desc.add_attachment(attachment(Format::R16G16B16A16Sfloat, 1,
+                                                    Layout::ShaderReadOnlyOptimal));
+        let depth = desc.add_attachment(attachment(Format::D16Unorm, 1,
+                                                   Layout::DepthStencilReadOnlyOptimal));
+        let output = desc.add_attachment(attachment(Format::R8G8B8A8Unorm, 1,
+                                                    Layout::ColorAttachmentOptimal));
+
+        let geometry = desc.add_pass(pass(vec![(albedo, Layout::ColorAttachmentOptimal),
+                                               (normal, Layout::ColorAttachmentOptimal)],
+                                          Some((depth, Layout::DepthStencilAttachmentOptimal)),
+                                          vec![]));
+        let lighting = desc.add_pass(pass(vec![(output, Layout::ColorAttachmentOptimal)], None,
+                                          vec![(albedo, Layout::ShaderReadOnlyOptimal),
+                                               (normal, Layout::ShaderReadOnlyOptimal),
+                                               (depth, Layout::DepthStencilReadOnlyOptimal)]));
+        desc.add_dependency(dependency(geometry, lighting));
+
+        desc
+    }
+
+    #[test]
+    fn gbuffer_check() {
+        let desc = gbuffer();
+        assert_eq!(desc.check(), Ok(()));
+        assert_eq!(desc.num_subpasses(), 2);
+        assert_eq!(desc.num_color_attachments(0), Some(2));
+        assert_eq!(desc.num_color_attachments(1), Some(1));
+        assert_eq!(desc.has_depth(0), Some(true));
+        assert_eq!(desc.has_depth(1), Some(false));
+        assert_eq!(desc.num_input_attachments(0), Some(0));
+        assert_eq!(desc.num_input_attachments(1), Some(3));
+    }
+
+    #[test]
+    fn gbuffer_build() {
+        let (device, _) = gfx_dev_and_queue!();
+        let render_pass = gbuffer().build(&device).unwrap();
+        assert_eq!(render_pass.num_subpasses(), 2);
+        assert_eq!(render_pass.dependencies().len(), 1);
+
+        let lighting = Subpass::from(&render_pass, 1).unwrap();
+        assert_eq!(lighting.num_color_attachments(), 1);
+        assert_eq!(lighting.num_input_attachments(), 3);
+        assert!(Subpass::from(&render_pass, 2).is_none());
+    }
+
+    #[test]
+    fn no_pass() {
+        assert_eq!(RuntimeRenderPassDesc::new().check(), Err(RenderPassDescError::NoPass));
+    }
+
+    #[test]
+    fn attachment_out_of_range() {
+        let mut desc = gbuffer();
+        desc.add_pass(pass(vec![(4, Layout::ColorAttachmentOptimal)], None, vec![]));
+        assert_eq!(desc.check(), Err(RenderPassDescError::AttachmentOutOfRange {
+            pass: 2,
+            attachment: 4,
+        }));
+    }
+
+    #[test]
+    fn invalid_reference_layout() {
+        let mut desc = gbuffer();
+        desc.add_pass(pass(vec![], None, vec![(0, Layout::ColorAttachmentOptimal)]));
+        assert_eq!(desc.check(), Err(RenderPassDescError::InvalidReferenceLayout {
+            pass: 2,
+            attachment: 0,
+            layout: Layout::ColorAttachmentOptimal,
+        }));
+
+        let mut desc = gbuffer();
+        desc.add_pass(pass(vec![], Some((2, Layout::ShaderReadOnlyOptimal)), vec![]));
+        assert_eq!(desc.check(), Err(RenderPassDescError::InvalidReferenceLayout {
+            pass: 2,
+            attachment: 2,
+            layout: Layout::ShaderReadOnlyOptimal,
+        }));
+    }
+
+    #[test]
+    fn resolve_attachment_multisampled() {
+        let mut desc = RuntimeRenderPassDesc::new();
+        let color = desc.add_attachment(attachment(Format::R8G8B8A8Unorm, 4,
+                                                   Layout::ColorAttachmentOptimal));
+        let resolve = desc.add_attachment(attachment(Format::R8G8B8A8Unorm, 4,
+                                                     Layout::ColorAttachmentOptimal));
+        desc.add_pass(LayoutPassDescription {
+            resolve_attachments: vec![Some((resolve, Layout::ColorAttachmentOptimal))],
+            .. pass(vec![(color, Layout::ColorAttachmentOptimal)], None, vec![])
+        });
+
+        assert_eq!(desc.check(), Err(RenderPassDescError::ResolveAttachmentMultisampled {
+            pass: 0,
+            attachment: resolve,
+        }));
+    }
+
+    #[test]
+    fn resolve_attachment_format_mismatch() {
+        let mut desc = RuntimeRenderPassDesc::new();
+        let color = desc.add_attachment(attachment(Format::R8G8B8A8Unorm, 4,
+                                                   Layout::ColorAttachmentOptimal));
+        let resolve = desc.add_attachment(attachment(Format::B8G8R8A8Unorm, 1,
+                                                     Layout::ColorAttachmentOptimal));
+        desc.add_pass(LayoutPassDescription {
+            resolve_attachments: vec![Some((resolve, Layout::ColorAttachmentOptimal))],
+            .. pass(vec![(color, Layout::ColorAttachmentOptimal)], None, vec![])
+        });
+
+        assert_eq!(desc.check(), Err(RenderPassDescError::ResolveAttachmentFormatMismatch {
+            pass: 0,
+            attachment: resolve,
+        }));
+    }
+
+    #[test]
+    fn unused_resolve_attachment() {
+        let mut desc = RuntimeRenderPassDesc::new();
+        let color1 = desc.add_attachment(attachment(Format::R8G8B8A8Unorm, 4,
+                                                    Layout::ColorAttachmentOptimal));
+        let color2 = desc.add_attachment(attachment(Format::R8G8B8A8Unorm, 4,
+                                                    Layout::ColorAttachmentOptimal));
+        let resolve = desc.add_attachment(attachment(Format::R8G8B8A8Unorm, 1,
+                                                     Layout::ColorAttachmentOptimal));
+        desc.add_pass(LayoutPassDescription {
+            resolve_attachments: vec![Some((resolve, Layout::ColorAttachmentOptimal)), None],
+            .. pass(vec![(color1, Layout::ColorAttachmentOptimal),
+                         (color2, Layout::ColorAttachmentOptimal)], None, vec![])
+        });
+
+        assert_eq!(desc.check(), Ok(()));
+    }
+
+    #[test]
+    fn dependency_subpass_out_of_range() {
+        let mut desc = gbuffer();
+        desc.add_dependency(dependency(1, 2));
+        assert_eq!(desc.check(), Err(RenderPassDescError::DependencySubpassOutOfRange {
+            dependency: 1,
+            subpass: 2,
+        }));
+
+        let mut desc = gbuffer();
+        desc.add_dependency(dependency(1, SUBPASS_EXTERNAL));
+        desc.add_dependency(dependency(SUBPASS_EXTERNAL, 0));
+        assert_eq!(desc.check(), Ok(()));
+    }
+
+    #[test]
+    fn dependency_wrong_order() {
+        let mut desc = gbuffer();
+        desc.add_dependency(dependency(1, 0));
+        assert_eq!(desc.check(), Err(RenderPassDescError::DependencyWrongOrder {
+            dependency: 1,
+        }));
+    }
+
+    #[test]
+    fn input_attachment_cleared() {
+        let mut desc = RuntimeRenderPassDesc::new();
+        let input = desc.add_attachment(attachment(Format::R8G8B8A8Unorm, 1,
+                                                   Layout::ShaderReadOnlyOptimal));
+        desc.add_pass(pass(vec![], None, vec![(input, Layout::ShaderReadOnlyOptimal)]));
+        assert_eq!(desc.check(), Err(RenderPassDescError::InputAttachmentCleared {
+            pass: 0,
+            attachment: input,
+        }));
+
+        let mut desc = RuntimeRenderPassDesc::new();
+        desc.add_attachment(LayoutAttachmentDescription {
+            load: LoadOp::Load,
+            initial_layout: Layout::ShaderReadOnlyOptimal,
+            .. attachment(Format::R8G8B8A8Unorm, 1, Layout::ShaderReadOnlyOptimal)
+        });
+        desc.add_pass(pass(vec![], None, vec![(0, Layout::ShaderReadOnlyOptimal)]));
+        assert_eq!(desc.check(), Ok(()));
+    }
+
+    #[test]
+    fn input_attachment_not_preserved() {
+        let mut desc = gbuffer();
+        let extra = desc.add_attachment(attachment(Format::R8G8B8A8Unorm, 1,
+                                                   Layout::ColorAttachmentOptimal));
+        // Moves the lighting pass to the end, with an unrelated pass in between.
+        let lighting = desc.passes.pop().unwrap();
+        desc.dependencies.clear();
+        desc.add_pass(pass(vec![(extra, Layout::ColorAttachmentOptimal)], None, vec![]));
+        desc.add_pass(lighting);
+        desc.add_dependency(dependency(0, 2));
+        assert_eq!(desc.check(), Err(RenderPassDescError::AttachmentNotPreserved {
+            pass: 1,
+            attachment: 0,
+        }));
+
+        desc.passes[1].preserve_attachments = vec![0, 1, 2];
+        assert_eq!(desc.check(), Ok(()));
+    }
+
+    #[test]
+    fn input_attachment_missing_dependency() {
+        let mut desc = gbuffer();
+        desc.dependencies.clear();
+        assert_eq!(desc.check(), Err(RenderPassDescError::MissingDependency {
+            source: 0,
+            destination: 1,
+        }));
+
+        desc.add_dependency(dependency(0, 1));
+        assert_eq!(desc.check(), Ok(()));
+    }
+
+    #[test]
+    fn compute_preserve_attachments() {
+        // The first subpass writes `albedo` and `normal`, the second one only writes `output`,
+        // and the third one reads `albedo`.
+        let mut desc = RuntimeRenderPassDesc::new();
+        let albedo = desc.add_attachment(attachment(Format::R8G8B8A8Unorm, 1,
+                                                    Layout::ShaderReadOnlyOptimal));
+        let normal = desc.add_attachment(attachment(Format::R8G8B8A8Unorm, 1,
+                                                    Layout::ColorAttachmentOptimal));
+        let output = desc.add_attachment(attachment(Format::R8G8B8A8Unorm, 1,
+                                                    Layout::ColorAttachmentOptimal));
+        desc.add_pass(pass(vec![(albedo, Layout::ColorAttachmentOptimal),
+                                (normal, Layout::ColorAttachmentOptimal)], None, vec![]));
+        desc.add_pass(pass(vec![(output, Layout::ColorAttachmentOptimal)], None, vec![]));
+        desc.add_pass(pass(vec![(output, Layout::ColorAttachmentOptimal)], None,
+                           vec![(albedo, Layout::ShaderReadOnlyOptimal)]));
+        desc.add_dependency(dependency(0, 2));
+
+        assert_eq!(desc.check(), Err(RenderPassDescError::AttachmentNotPreserved {
+            pass: 1,
+            attachment: albedo,
+        }));
+
+        desc.compute_preserve_attachments();
+        let preserve = desc.passes().map(|p| p.preserve_attachments).collect::<Vec<_>>();
+        assert_eq!(preserve, vec![vec![], vec![albedo], vec![]]);
+        assert_eq!(desc.check(), Ok(()));
+
+        // Computing the lists a second time doesn't add duplicates.
+        desc.compute_preserve_attachments();
+        assert_eq!(desc.passes[1].preserve_attachments, vec![albedo]);
+    }
+
+    #[test]
+    fn preserved_attachment_used() {
+        let mut desc = gbuffer();
+        desc.passes[1].preserve_attachments = vec![3];
+        assert_eq!(desc.check(), Err(RenderPassDescError::PreservedAttachmentUsed {
+            pass: 1,
+            attachment: 3,
+        }));
+    }
+
+    #[test]
+    fn color_attachment_depth_format() {
+        let mut desc = gbuffer();
+        desc.attachments[3].format = Format::D16Unorm;
+        assert_eq!(desc.check(), Err(RenderPassDescError::InvalidColorFormat {
+            attachment: 3,
+        }));
+    }
+
+    #[test]
+    fn depth_attachment_color_format() {
+        let mut desc = gbuffer();
+        desc.attachments[2].format = Format::R8G8B8A8Unorm;
+        assert_eq!(desc.check(), Err(RenderPassDescError::InvalidDepthStencilFormat {
+            attachment: 2,
+        }));
+    }
+
+    #[test]
+    fn simple_without_depth() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let render_pass = RuntimeRenderPass::simple(&device, Format::R8G8B8A8Unorm, 1, None)
+                                                   .unwrap();
+        assert_eq!(render_pass.attachments().len(), 1);
+        assert_eq!(Subpass::from(&render_pass, 0).unwrap().has_depth(), false);
+
+        let color = AttachmentImage::new(&device, [512, 512], Format::R8G8B8A8Unorm).unwrap();
+        let attachments = vec![(color.clone() as Arc<ImageView>, color as Arc<Image>)];
+        let _ = Framebuffer::new(&render_pass, [512, 512, 1], attachments).unwrap();
+    }
+
+    #[test]
+    fn simple_with_depth() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let render_pass = RuntimeRenderPass::simple(&device, Format::R8G8B8A8Unorm, 1,
+                                                    Some(Format::D16Unorm)).unwrap();
+        assert_eq!(render_pass.attachments().len(), 2);
+        assert!(Subpass::from(&render_pass, 0).unwrap().has_depth());
+
+        let color = AttachmentImage::new(&device, [512, 512], Format::R8G8B8A8Unorm).unwrap();
+        let depth = AttachmentImage::new(&device, [512, 512], Format::D16Unorm).unwrap();
+        let attachments = vec![(color.clone() as Arc<ImageView>, color as Arc<Image>),
+                               (depth.clone() as Arc<ImageView>, depth as Arc<Image>)];
+        let _ = Framebuffer::new(&render_pass, [512, 512, 1], attachments).unwrap();
+    }
+
+    #[test]
+    fn simple_wrong_formats() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        match RuntimeRenderPass::simple(&device, Format::D16Unorm, 1, None) {
+            Err(RenderPassCreationError::InvalidDesc(
+                RenderPassDescError::InvalidColorFormat { attachment: 0 })) => (),
+            _ => panic!()
+        }
+
+        match RuntimeRenderPass::simple(&device, Format::R8G8B8A8Unorm, 1, Some(Format::S8Uint)) {
+            Err(RenderPassCreationError::InvalidDesc(
+                RenderPassDescError::InvalidDepthStencilFormat { attachment: 1 })) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn self_dependency() {
+        let mut desc = gbuffer();
+        assert!(!desc.allows_pipeline_barrier(1, PipelineStages::none(), PipelineStages::none(),
+                                              AccessFlagBits::none(), AccessFlagBits::none()));
+
+        desc.add_dependency(dependency(1, 1));
+        assert_eq!(desc.check(), Ok(()));
+
+        let dep = dependency(1, 1);
+        let (src_stages, dst_stages) = (dep.src_stages, dep.dst_stages);
+        let (src_access, dst_access) = (dep.src_access, dep.dst_access);
+        assert!(desc.allows_pipeline_barrier(1, src_stages, dst_stages, src_access, dst_access));
+        assert!(!desc.allows_pipeline_barrier(0, src_stages, dst_stages, src_access, dst_access));
+
+        let dst_access = AccessFlagBits { shader_read: true, .. AccessFlagBits::none() };
+        assert!(!desc.allows_pipeline_barrier(1, src_stages, dst_stages, src_access, dst_access));
+    }
+
+    #[test]
+    fn self_dependency_not_framebuffer_space() {
+        let mut desc = gbuffer();
+        let mut dep = dependency(1, 1);
+        dep.dst_stages = PipelineStages { vertex_shader: true, .. PipelineStages::none() };
+        desc.add_dependency(dep);
+        assert_eq!(desc.check(), Err(RenderPassDescError::SelfDependencyNotFramebufferSpace {
+            dependency: 1,
+        }));
+    }
+
+    #[test]
+    fn self_dependency_not_by_region() {
+        let mut desc = gbuffer();
+        let mut dep = dependency(1, 1);
+        dep.by_region = false;
+        desc.add_dependency(dep);
+        assert_eq!(desc.check(), Err(RenderPassDescError::SelfDependencyNotByRegion {
+            dependency: 1,
+        }));
+    }
+
+    #[test]
+    fn clear_values() {
+        let mut desc = gbuffer();
+        let values = [ClearValue::Float([0.0; 4]), ClearValue::Float([0.0; 4]),
+                      ClearValue::Depth(1.0), ClearValue::Float([0.0; 4])];
+        assert_eq!(desc.check_clear_values(&values), Ok(()));
+
+        desc.attachments[3].load = LoadOp::DontCare;
+        let values = [ClearValue::Float([0.0; 4]), ClearValue::Float([0.0; 4]),
+                      ClearValue::Depth(1.0), ClearValue::None];
+        assert_eq!(desc.check_clear_values(&values), Ok(()));
+    }
+
+    #[test]
+    fn clear_values_too_few() {
+        let values = [ClearValue::Float([0.0; 4]), ClearValue::Float([0.0; 4]),
+                      ClearValue::Depth(1.0)];
+        assert_eq!(gbuffer().check_clear_values(&values), Err(ClearValuesError::CountMismatch {
+            expected: 4,
+            obtained: 3,
+        }));
+    }
+
+    #[test]
+    fn clear_values_float_for_depth() {
+        let values = [ClearValue::Float([0.0; 4]), ClearValue::Float([0.0; 4]),
+                      ClearValue::Float([1.0; 4]), ClearValue::Float([0.0; 4])];
+        assert_eq!(gbuffer().check_clear_values(&values), Err(ClearValuesError::FormatMismatch {
+            attachment: 2,
+            format: Format::D16Unorm,
+            value: ClearValue::Float([1.0; 4]),
+        }));
+    }
+
+    #[test]
+    fn clear_values_placeholders() {
+        let mut desc = gbuffer();
+        let values = [ClearValue::Float([0.0; 4]), ClearValue::None,
+                      ClearValue::Depth(1.0), ClearValue::Float([0.0; 4])];
+        assert_eq!(desc.check_clear_values(&values),
+                   Err(ClearValuesError::MissingClearValue { attachment: 1 }));
+
+        desc.attachments[1].load = LoadOp::Load;
+        let values = [ClearValue::Float([0.0; 4]), ClearValue::Float([0.0; 4]),
+                      ClearValue::Depth(1.0), ClearValue::Float([0.0; 4])];
+        assert_eq!(desc.check_clear_values(&values),
+                   Err(ClearValuesError::AttachmentNotCleared { attachment: 1 }));
+    }
+
+    #[test]
+    fn compatible_different_ops() {
+        let mut other = gbuffer();
+        other.attachments[0].load = LoadOp::DontCare;
+        other.attachments[3].store = StoreOp::DontCare;
+        other.attachments[3].final_layout = Layout::PresentSrc;
+        assert!(gbuffer().is_compatible_with_desc(&other));
+    }
+
+    #[test]
+    fn incompatible_different_format() {
+        let mut other = gbuffer();
+        other.attachments[1].format = Format::R8G8B8A8Unorm;
+        assert!(!gbuffer().is_compatible_with_desc(&other));
+    }
+
+    #[test]
+    fn incompatible_different_passes() {
+        let mut other = gbuffer();
+        other.passes[1].input_attachments.pop();
+        assert!(!gbuffer().is_compatible_with_desc(&other));
+    }
+}
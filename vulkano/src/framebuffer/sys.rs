@@ -21,6 +21,8 @@ use framebuffer::LayoutAttachmentDescription;
 use framebuffer::LayoutPassDescription;
 use framebuffer::LayoutPassDependencyDescription;
 use framebuffer::LoadOp;
+use framebuffer::RenderPassDescError;
+use framebuffer::SUBPASS_EXTERNAL;
 use image::traits::Image;
 use image::traits::ImageView;
 
@@ -105,22 +107,23 @@ impl UnsafeRenderPass {
             // Performing some validation with debug asserts.
             debug_assert!(pass.resolve_attachments.is_empty() ||
                           pass.resolve_attachments.len() == pass.color_attachments.len());
-            debug_assert!(pass.resolve_attachments.iter().all(|a| {
+            debug_assert!(pass.resolve_attachments.iter().flat_map(|a| a.iter()).all(|a| {
                               attachments[a.0].samples == 1
                           }));
-            debug_assert!(pass.resolve_attachments.is_empty() ||
-                          pass.color_attachments.iter().all(|a| {
-                              attachments[a.0].samples > 1
-                          }));
-            debug_assert!(pass.resolve_attachments.is_empty() ||
-                          pass.resolve_attachments.iter().zip(pass.color_attachments.iter())
+            debug_assert!(pass.resolve_attachments.iter().zip(pass.color_attachments.iter())
+                              .all(|(r, c)| {
+                                  r.is_none() || attachments[c.0].samples > 1
+                              }));
+            debug_assert!(pass.resolve_attachments.iter().zip(pass.color_attachments.iter())
                               .all(|(r, c)| {
-                                  attachments[r.0].format == attachments[c.0].format
+                                  r.map_or(true, |r| {
+                                      attachments[r.0].format == attachments[c.0].format
+                                  })
                               }));
             debug_assert!(pass.color_attachments.iter().cloned()
                               .chain(pass.depth_stencil.clone().into_iter())
                               .chain(pass.input_attachments.iter().cloned())
-                              .chain(pass.resolve_attachments.iter().cloned())
+                              .chain(pass.resolve_attachments.iter().filter_map(|a| *a))
                               .all(|(a, _)| {
                                   pass.preserve_attachments.iter().find(|&&b| a == b).is_none()
                               }));
@@ -136,9 +139,18 @@ impl UnsafeRenderPass {
                                   }
                               }));
 
-            let resolve = pass.resolve_attachments.into_iter().map(|(offset, img_la)| {
-                debug_assert!(offset < attachments.len());
-                vk::AttachmentReference { attachment: offset as u32, layout: img_la as u32, }
+            let resolve = pass.resolve_attachments.into_iter().map(|a| {
+                match a {
+                    Some((offset, img_la)) => {
+                        debug_assert!(offset < attachments.len());
+                        vk::AttachmentReference { attachment: offset as u32,
+                                                  layout: img_la as u32, }
+                    },
+                    None => {
+                        vk::AttachmentReference { attachment: vk::ATTACHMENT_UNUSED,
+                                                  layout: vk::IMAGE_LAYOUT_UNDEFINED, }
+                    },
+                }
             });
 
             let color = pass.color_attachments.into_iter().map(|(offset, img_la)| {
@@ -228,12 +240,17 @@ impl UnsafeRenderPass {
         };
 
         let dependencies = pass_dependencies.map(|dependency| {
-            debug_assert!(dependency.source_subpass < passes.len());
-            debug_assert!(dependency.destination_subpass < passes.len());
+            debug_assert!(dependency.source_subpass == SUBPASS_EXTERNAL ||
+                          dependency.source_subpass < passes.len());
+            debug_assert!(dependency.destination_subpass == SUBPASS_EXTERNAL ||
+                          dependency.destination_subpass < passes.len());
+
+            let subpass = |num| if num == SUBPASS_EXTERNAL { vk::SUBPASS_EXTERNAL }
+                                else { num as u32 };
 
             vk::SubpassDependency {
-                srcSubpass: dependency.source_subpass as u32,
-                dstSubpass: dependency.destination_subpass as u32,
+                srcSubpass: subpass(dependency.source_subpass),
+                dstSubpass: subpass(dependency.destination_subpass),
                 srcStageMask: dependency.src_stages.into(),
                 dstStageMask: dependency.dst_stages.into(),
                 srcAccessMask: dependency.src_access.into(),
@@ -276,6 +293,8 @@ impl UnsafeRenderPass {
     }
 }
 
+impl_debug_handle!(UnsafeRenderPass);
+
 unsafe impl VulkanObject for UnsafeRenderPass {
     type Object = vk::RenderPass;
 
@@ -309,6 +328,10 @@ pub enum RenderPassCreationError {
     OomError(OomError),
     /// The maximum number of color attachments has been exceeded.
     ColorAttachmentsLimitExceeded,
+    /// The description of the render pass is invalid.
+    InvalidDesc(RenderPassDescError),
+    /// The Vulkan implementation returned an error that this operation isn't supposed to return.
+    Unexpected(Error),
 }
 
 impl error::Error for RenderPassCreationError {
@@ -319,6 +342,12 @@ impl error::Error for RenderPassCreationError {
             RenderPassCreationError::ColorAttachmentsLimitExceeded => {
                 "the maximum number of color attachments has been exceeded"
             },
+            RenderPassCreationError::InvalidDesc(_) => {
+                "the description of the render pass is invalid"
+            },
+            RenderPassCreationError::Unexpected(_) => {
+                "unexpected error returned by the Vulkan implementation"
+            },
         }
     }
 
@@ -326,6 +355,8 @@ impl error::Error for RenderPassCreationError {
     fn cause(&self) -> Option<&error::Error> {
         match *self {
             RenderPassCreationError::OomError(ref err) => Some(err),
+            RenderPassCreationError::InvalidDesc(ref err) => Some(err),
+            RenderPassCreationError::Unexpected(ref err) => Some(err),
             _ => None
         }
     }
@@ -345,6 +376,13 @@ impl From<OomError> for RenderPassCreationError {
     }
 }
 
+impl From<RenderPassDescError> for RenderPassCreationError {
+    #[inline]
+    fn from(err: RenderPassDescError) -> RenderPassCreationError {
+        RenderPassCreationError::InvalidDesc(err)
+    }
+}
+
 impl From<Error> for RenderPassCreationError {
     #[inline]
     fn from(err: Error) -> RenderPassCreationError {
@@ -355,7 +393,7 @@ impl From<Error> for RenderPassCreationError {
             err @ Error::OutOfDeviceMemory => {
                 RenderPassCreationError::OomError(OomError::from(err))
             },
-            _ => panic!("unexpected error: {:?}", err)
+            _ => RenderPassCreationError::Unexpected(err)
         }
     }
 }
@@ -7,12 +7,16 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
+use std::error;
+use std::fmt;
 use std::sync::Arc;
+use std::vec::IntoIter as VecIntoIter;
 
 use format::ClearValue;
 use format::Format;
 use format::FormatDesc;
 use format::FormatTy;
+use format::PossibleDepthOrStencilFormatDesc;
 use framebuffer::UnsafeRenderPass;
 use framebuffer::FramebufferCreationError;
 use image::Layout as ImageLayout;
@@ -68,6 +72,12 @@ pub unsafe trait RenderPassDesc {
         self.passes().skip(subpass as usize).next().map(|p| p.color_attachments.len() as u32)
     }
 
+    /// Returns the number of input attachments in a subpass. Returns `None` if out of range.
+    #[inline]
+    fn num_input_attachments(&self, subpass: u32) -> Option<u32> {
+        self.passes().skip(subpass as usize).next().map(|p| p.input_attachments.len() as u32)
+    }
+
     /// Returns the number of samples of the attachments of a subpass. Returns `None` if out of
     /// range or if the subpass has no attachment. TODO: return an enum instead?
     #[inline]
@@ -178,6 +188,223 @@ pub unsafe trait RenderPassDesc {
             }
         })
     }
+
+    /// Returns true if a subpass declares a dependency with itself that covers a pipeline
+    /// barrier with the given scopes. Returns `false` if out of range.
+    ///
+    /// Pipeline barriers are only allowed inside a subpass if their stages and accesses are a
+    /// subset of the ones of a self-dependency of that subpass.
+    fn allows_pipeline_barrier(&self, subpass: u32, src_stages: PipelineStages,
+                               dst_stages: PipelineStages, src_access: AccessFlagBits,
+                               dst_access: AccessFlagBits) -> bool
+    {
+        let includes = |a: u32, b: u32| a & b == b;
+        let src_stages: vk::PipelineStageFlagBits = src_stages.into();
+        let dst_stages: vk::PipelineStageFlagBits = dst_stages.into();
+        let src_access: vk::AccessFlagBits = src_access.into();
+        let dst_access: vk::AccessFlagBits = dst_access.into();
+
+        self.dependencies().any(|dep| {
+            dep.source_subpass == subpass as usize && dep.destination_subpass == subpass as usize &&
+            includes(dep.src_stages.into(), src_stages) &&
+            includes(dep.dst_stages.into(), dst_stages) &&
+            includes(dep.src_access.into(), src_access) &&
+            includes(dep.dst_access.into(), dst_access)
+        })
+    }
+
+    /// Returns true if this description is compatible with another one, as defined in the
+    /// `Render Pass Compatibility` section of the Vulkan specs.
+    ///
+    /// Two descriptions are compatible if their corresponding attachments have the same format
+    /// and number of samples, and if they are otherwise identical except for the load and store
+    /// operations and the layouts.
+    fn is_compatible_with_desc<T>(&self, other: &T) -> bool
+        where Self: Sized, T: RenderPassDesc
+    {
+        let my_attachments = self.attachments().collect::<Vec<_>>();
+        let other_attachments = other.attachments().collect::<Vec<_>>();
+        if my_attachments.len() != other_attachments.len() ||
+           !my_attachments.iter().zip(other_attachments.iter())
+                          .all(|(a, b)| a.is_compatible_with(b))
+        {
+            return false;
+        }
+
+        // Unused references are only compatible with other unused references.
+        let references_compatible = |a: &[Option<(usize, ImageLayout)>],
+                                     b: &[Option<(usize, ImageLayout)>]|
+        {
+            a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| {
+                match (*a, *b) {
+                    (Some((a, _)), Some((b, _))) => {
+                        match (my_attachments.get(a), other_attachments.get(b)) {
+                            (Some(a), Some(b)) => a.is_compatible_with(b),
+                            _ => false,
+                        }
+                    },
+                    (None, None) => true,
+                    _ => false,
+                }
+            })
+        };
+        let used = |r: &[(usize, ImageLayout)]| r.iter().cloned().map(Some).collect::<Vec<_>>();
+
+        if self.passes().len() != other.passes().len() {
+            return false;
+        }
+
+        for (a, b) in self.passes().zip(other.passes()) {
+            if !references_compatible(&used(&a.color_attachments), &used(&b.color_attachments)) ||
+               !references_compatible(&[a.depth_stencil], &[b.depth_stencil]) ||
+               !references_compatible(&used(&a.input_attachments), &used(&b.input_attachments)) ||
+               !references_compatible(&a.resolve_attachments, &b.resolve_attachments)
+            {
+                return false;
+            }
+
+            let mut a_preserve = a.preserve_attachments;
+            let mut b_preserve = b.preserve_attachments;
+            a_preserve.sort();
+            b_preserve.sort();
+            if a_preserve != b_preserve {
+                return false;
+            }
+        }
+
+        if self.dependencies().len() != other.dependencies().len() {
+            return false;
+        }
+
+        self.dependencies().zip(other.dependencies()).all(|(a, b)| {
+            let a_stages: (vk::PipelineStageFlagBits, vk::PipelineStageFlagBits) =
+                (a.src_stages.into(), a.dst_stages.into());
+            let b_stages: (vk::PipelineStageFlagBits, vk::PipelineStageFlagBits) =
+                (b.src_stages.into(), b.dst_stages.into());
+            let a_access: (vk::AccessFlagBits, vk::AccessFlagBits) =
+                (a.src_access.into(), a.dst_access.into());
+            let b_access: (vk::AccessFlagBits, vk::AccessFlagBits) =
+                (b.src_access.into(), b.dst_access.into());
+
+            a.source_subpass == b.source_subpass &&
+            a.destination_subpass == b.destination_subpass &&
+            a_stages == b_stages && a_access == b_access && a.by_region == b.by_region
+        })
+    }
+
+    /// Checks a list of clear values that is going to be passed when beginning this render pass.
+    ///
+    /// There must be exactly one value per attachment, in the order of the attachments. The
+    /// attachments that are loaded with `LoadOp::Clear` must have a value that matches their
+    /// format, and the other attachments must have a `ClearValue::None` placeholder.
+    fn check_clear_values(&self, values: &[ClearValue]) -> Result<(), ClearValuesError> {
+        let num_attachments = self.attachments().len();
+        if values.len() != num_attachments {
+            return Err(ClearValuesError::CountMismatch {
+                expected: num_attachments,
+                obtained: values.len(),
+            });
+        }
+
+        for (num, (attachment, value)) in self.attachments().zip(values.iter()).enumerate() {
+            match (attachment.load, *value) {
+                (LoadOp::Clear, ClearValue::None) => {
+                    return Err(ClearValuesError::MissingClearValue { attachment: num });
+                },
+                (LoadOp::Clear, value) => {
+                    if !attachment.format.validate_clear_value(&value) {
+                        return Err(ClearValuesError::FormatMismatch {
+                            attachment: num,
+                            format: attachment.format,
+                            value: value,
+                        });
+                    }
+                },
+                (_, ClearValue::None) => (),
+                (_, _) => {
+                    return Err(ClearValuesError::AttachmentNotCleared { attachment: num });
+                },
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Error that can happen when checking the clear values of a render pass.
+///
+/// See `RenderPassDesc::check_clear_values`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ClearValuesError {
+    /// The number of clear values is not the same as the number of attachments.
+    CountMismatch {
+        /// Number of attachments of the render pass.
+        expected: usize,
+        /// Number of clear values that were passed.
+        obtained: usize,
+    },
+
+    /// An attachment is loaded with `LoadOp::Clear` but its clear value is `ClearValue::None`.
+    MissingClearValue {
+        /// Index of the attachment.
+        attachment: usize,
+    },
+
+    /// A clear value was passed for an attachment that isn't loaded with `LoadOp::Clear`.
+    AttachmentNotCleared {
+        /// Index of the attachment.
+        attachment: usize,
+    },
+
+    /// A clear value doesn't match the format of its attachment.
+    FormatMismatch {
+        /// Index of the attachment.
+        attachment: usize,
+        /// Format of the attachment.
+        format: Format,
+        /// The clear value that was passed.
+        value: ClearValue,
+    },
+}
+
+impl error::Error for ClearValuesError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            ClearValuesError::CountMismatch { .. } => {
+                "the number of clear values doesn't match the number of attachments"
+            },
+            ClearValuesError::MissingClearValue { .. } => {
+                "an attachment that is cleared has no clear value"
+            },
+            ClearValuesError::AttachmentNotCleared { .. } => {
+                "a clear value was passed for an attachment that isn't cleared"
+            },
+            ClearValuesError::FormatMismatch { .. } => {
+                "a clear value doesn't match the format of its attachment"
+            },
+        }
+    }
+}
+
+impl fmt::Display for ClearValuesError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            ClearValuesError::CountMismatch { expected, obtained } => {
+                write!(fmt, "{} (expected {}, obtained {})", error::Error::description(self),
+                       expected, obtained)
+            },
+            ClearValuesError::MissingClearValue { attachment } |
+            ClearValuesError::AttachmentNotCleared { attachment } => {
+                write!(fmt, "{} (attachment {})", error::Error::description(self), attachment)
+            },
+            ClearValuesError::FormatMismatch { attachment, format, value } => {
+                write!(fmt, "{} (attachment {}: {:?} can't be cleared with {:?})",
+                       error::Error::description(self), attachment, format, value)
+            },
+        }
+    }
 }
 
 /// Extension trait for `RenderPass`. Defines which types are allowed as an attachments list.
@@ -225,6 +452,35 @@ pub unsafe trait RenderPassClearValues<C>: RenderPass {
     fn convert_clear_values(&self, C) -> Self::ClearValuesIter;
 }
 
+/// Clear values can be passed as a list of floating-point arrays, one per attachment, to any
+/// render pass that implements `RenderPassDesc`.
+///
+/// Each array is converted with `Format::decode_float_clear_value` according to the format of
+/// its attachment. The entries of attachments that aren't loaded with `LoadOp::Clear` are
+/// ignored.
+///
+/// # Panic
+///
+/// - Panicks if the number of arrays is not the same as the number of attachments.
+/// - Panicks if an attachment that is cleared has a compressed format.
+///
+unsafe impl<R> RenderPassClearValues<Vec<[f32; 4]>> for R where R: RenderPass + RenderPassDesc {
+    type ClearValuesIter = VecIntoIter<ClearValue>;
+
+    fn convert_clear_values(&self, values: Vec<[f32; 4]>) -> VecIntoIter<ClearValue> {
+        assert_eq!(values.len(), self.attachments().len(),
+                   "The number of clear values must match the number of attachments");
+
+        self.attachments().zip(values.into_iter()).map(|(attachment, value)| {
+            if attachment.load == LoadOp::Clear {
+                attachment.format.decode_float_clear_value(value)
+            } else {
+                ClearValue::None
+            }
+        }).collect::<Vec<_>>().into_iter()
+    }
+}
+
 /// Extension trait for `RenderPass` that checks whether a subpass of this render pass accepts
 /// the output of a fragment shader.
 ///
@@ -271,7 +527,8 @@ unsafe impl<A, B> RenderPassSubpassInterface<B> for A
 /// Trait implemented on render pass objects to check whether they are compatible
 /// with another render pass.
 ///
-/// The trait is automatically implemented for all type that implement `RenderPass`.
+/// The trait is automatically implemented for all types that implement `RenderPass` and
+/// `RenderPassDesc`.
 // TODO: once specialization lands, this trait can be specialized for pairs that are known to
 //       always be compatible
 // TODO: maybe this can be unimplemented on some pairs, to provide compile-time checks?
@@ -283,19 +540,11 @@ pub unsafe trait RenderPassCompatible<Other>: RenderPass where Other: RenderPass
 }
 
 unsafe impl<A, B> RenderPassCompatible<B> for A
-    where A: RenderPass, B: RenderPass
+    where A: RenderPass + RenderPassDesc, B: RenderPass + RenderPassDesc
 {
+    #[inline]
     fn is_compatible_with(&self, other: &Arc<B>) -> bool {
-        // FIXME:
-        /*for (atch1, atch2) in self.attachments().zip(other.attachments()) {
-            if !atch1.is_compatible_with(&atch2) {
-                return false;
-            }
-        }*/
-
-        return true;
-
-        // FIXME: finish
+        self.is_compatible_with_desc(&**other)
     }
 }
 
@@ -344,10 +593,11 @@ impl LayoutAttachmentDescription {
 /// - If any attachment is used as both an input attachment and a color or
 ///   depth/stencil attachment, then each use must use the same layout.
 /// - Elements of `preserve_attachments` must not be used in any of the other members.
-/// - If `resolve_attachments` is not empty, then all the resolve attachments must be attachments
-///   with 1 sample and all the color attachments must have more than 1 sample.
-/// - If `resolve_attachments` is not empty, all the resolve attachments must have the same format
-///   as the color attachments.
+/// - If `resolve_attachments` is not empty, then all the used resolve attachments must be
+///   attachments with 1 sample and the color attachments they resolve must have more than 1
+///   sample.
+/// - If `resolve_attachments` is not empty, all the used resolve attachments must have the same
+///   format as the color attachments they resolve.
 /// - If the first use of an attachment in this renderpass is as an input attachment and the
 ///   attachment is not also used as a color or depth/stencil attachment in the same subpass,
 ///   then the loading operation must not be `Clear`.
@@ -368,13 +618,18 @@ pub struct LayoutPassDescription {
     /// If not empty, each color attachment will be resolved into each corresponding entry of
     /// this list.
     ///
-    /// If this value is not empty, it **must** be the same length as `color_attachments`.
-    pub resolve_attachments: Vec<(usize, ImageLayout)>,      // TODO: Vec is slow
+    /// If this value is not empty, it **must** be the same length as `color_attachments`. A
+    /// `None` entry means that the corresponding color attachment isn't resolved.
+    pub resolve_attachments: Vec<Option<(usize, ImageLayout)>>,      // TODO: Vec is slow
 
     /// Indices of attachments that will be preserved during this pass.
     pub preserve_attachments: Vec<usize>,      // TODO: Vec is slow
 }
 
+/// Value of `source_subpass` or `destination_subpass` in a `LayoutPassDependencyDescription`
+/// that designates the commands that are outside of the render pass.
+pub const SUBPASS_EXTERNAL: usize = !0;
+
 /// Describes a dependency between two passes of a render pass.
 ///
 /// The implementation is allowed to change the order of the passes within a render pass, unless
@@ -384,9 +639,13 @@ pub struct LayoutPassDescription {
 #[derive(Debug, Clone)]
 pub struct LayoutPassDependencyDescription {
     /// Index of the subpass that writes the data that `destination_subpass` is going to use.
+    ///
+    /// Can be `SUBPASS_EXTERNAL` for the commands that are before the render pass.
     pub source_subpass: usize,
 
     /// Index of the subpass that reads the data that `source_subpass` wrote.
+    ///
+    /// Can be `SUBPASS_EXTERNAL` for the commands that are after the render pass.
     pub destination_subpass: usize,
 
     pub src_stages: PipelineStages,
@@ -476,6 +735,22 @@ impl<'a, L: 'a> Subpass<'a, L> where L: RenderPass + RenderPassDesc {
         self.render_pass.num_color_attachments(self.subpass_id).unwrap()
     }
 
+    /// Returns the number of input attachments in this subpass.
+    #[inline]
+    pub fn num_input_attachments(&self) -> u32 {
+        self.render_pass.num_input_attachments(self.subpass_id).unwrap()
+    }
+
+    /// Returns true if a pipeline barrier with the given scopes is allowed inside this subpass,
+    /// because the subpass declares a dependency with itself that covers them.
+    #[inline]
+    pub fn allows_pipeline_barrier(&self, src_stages: PipelineStages, dst_stages: PipelineStages,
+                                   src_access: AccessFlagBits, dst_access: AccessFlagBits) -> bool
+    {
+        self.render_pass.allows_pipeline_barrier(self.subpass_id, src_stages, dst_stages,
+                                                 src_access, dst_access)
+    }
+
     /// Returns true if the subpass has a depth attachment or a depth-stencil attachment.
     #[inline]
     pub fn has_depth(&self) -> bool {
@@ -540,3 +815,21 @@ impl<'a, L: 'a> Clone for Subpass<'a, L> {
         Subpass { render_pass: self.render_pass, subpass_id: self.subpass_id }
     }
 }
+
+/// Checks that `format` can be used as the depth-stencil attachment of a subpass.
+///
+/// This is used by the render pass macros. Formats that are known at compile time are checked
+/// by the trait bound, while `Format` is checked at runtime.
+///
+/// # Panic
+///
+/// - Panicks if the format has neither a depth nor a stencil aspect.
+///
+#[doc(hidden)]
+#[inline]
+pub fn ensure_depth_stencil_attachment<F>(format: &F)
+    where F: PossibleDepthOrStencilFormatDesc
+{
+    assert!(format.is_depth_or_stencil(), "The depth-stencil attachment of a subpass must have \
+                                           a depth and/or stencil format");
+}
@@ -16,6 +16,9 @@
 //! format. Trying to use a format that the backend doesn't support for rendering will result in
 //! an error being returned when creating the image. Once you have an `AttachmentImage`, you are
 //! guaranteed that you will be able to draw on it.
+//!
+//! The image can have multiple array layers, in which case each layer is tracked separately and
+//! a view of a single layer can be used as an attachment.
 //! 
 //! The template parameter of `AttachmentImage` is a type that describes the format of the image.
 //! 
@@ -34,31 +37,46 @@
 //! image. If you don't need to read from it (for example if it's some kind of intermediary color,
 //! or a depth buffer that is only used once) then use a transient image.
 //!
+//! If the implementation provides a lazily-allocated memory type, transient images are
+//! allocated from it.
+//!
+//! # Multisampling
+//!
+//! Calling `AttachmentImage::multisampled` will create an image with multiple samples per pixel.
+//! The number of samples must be supported by the implementation for framebuffer attachments of
+//! the given format, otherwise an error is returned. `AttachmentImage::supported_sample_counts`
+//! returns the list of supported values.
+//!
 use std::mem;
 use std::iter::Empty;
 use std::ops::Range;
 use std::sync::Arc;
 use std::sync::Mutex;
-use std::sync::Weak;
 
 use command_buffer::Submission;
 use device::Device;
 use format::ClearValue;
+use format::Format;
 use format::FormatDesc;
-use format::FormatTy;
+use format::PossibleDepthOrStencilFormatDesc;
+use image::layout::LayoutTracker;
 use image::sys::Dimensions;
+use image::sys::ImageCreateFlags;
 use image::sys::ImageCreationError;
 use image::sys::Layout;
+use image::sys::SupportedSampleCounts;
 use image::sys::UnsafeImage;
 use image::sys::UnsafeImageView;
 use image::sys::Usage;
 use image::traits::AccessRange;
+use image::traits::AccessTracker;
 use image::traits::GpuAccessResult;
 use image::traits::Image;
 use image::traits::ImageClearValue;
 use image::traits::ImageContent;
 use image::traits::ImageView;
 use image::traits::Transition;
+use memory::MemoryTypePreference;
 use memory::pool::AllocLayout;
 use memory::pool::MemoryPool;
 use memory::pool::MemoryPoolAlloc;
@@ -90,11 +108,12 @@ pub struct AttachmentImage<F, A = StdMemoryPool> where A: MemoryPool {
 
 #[derive(Debug)]
 struct Guarded {
-    // If false, the image is still in the undefined layout.
-    correct_layout: bool,
+    // Layout of each array layer. Layers start in the undefined layout.
+    layouts: LayoutTracker,
 
-    // The latest submission that used the image. Used for synchronization purposes.
-    latest_submission: Option<Weak<Submission>>,    // TODO: can use `Weak::new()` once it's stabilized
+    // Subresources accessed by the submissions that use the image. Used for synchronization
+    // purposes.
+    accesses: AccessTracker,
 }
 
 impl<F> AttachmentImage<F> {
@@ -113,7 +132,31 @@ impl<F> AttachmentImage<F> {
             .. Usage::none()
         };
 
-        AttachmentImage::new_impl(device, dimensions, format, usage)
+        AttachmentImage::new_impl(device, dimensions, 1, false, format, usage, 1)
+    }
+
+    /// Same as `new`, except that you can choose the usage of the image.
+    ///
+    /// The usages required to use the image as a framebuffer attachment are always added to
+    /// `usage`. Use this constructor if you want for example to sample from the image or to use
+    /// it as a transfer source, and nothing else.
+    pub fn with_usage(device: &Arc<Device>, dimensions: [u32; 2], format: F, usage: Usage)
+                      -> Result<Arc<AttachmentImage<F>>, ImageCreationError>
+        where F: FormatDesc
+    {
+        AttachmentImage::new_impl(device, dimensions, 1, false, format, usage, 1)
+    }
+
+    /// Same as `new`, except that the image will have multiple samples per pixel.
+    ///
+    /// The image can only be used as a framebuffer attachment. Returns an error if the number of
+    /// samples is not supported for attachments of this format. Use `supported_sample_counts`
+    /// to know which values are supported.
+    pub fn multisampled(device: &Arc<Device>, dimensions: [u32; 2], samples: u32, format: F)
+                        -> Result<Arc<AttachmentImage<F>>, ImageCreationError>
+        where F: FormatDesc
+    {
+        AttachmentImage::new_impl(device, dimensions, 1, false, format, Usage::none(), samples)
     }
 
     /// Same as `new`, except that the image will be transient.
@@ -129,66 +172,168 @@ impl<F> AttachmentImage<F> {
             .. Usage::none()
         };
 
-        AttachmentImage::new_impl(device, dimensions, format, usage)
+        AttachmentImage::new_impl(device, dimensions, 1, false, format, usage, 1)
     }
 
-    fn new_impl(device: &Arc<Device>, dimensions: [u32; 2], format: F, usage: Usage)
-                -> Result<Arc<AttachmentImage<F>>, ImageCreationError>
+    /// Creates a new transient image to be used as the depth-stencil attachment of a render
+    /// pass.
+    ///
+    /// Same as `transient`, except that only depth, stencil and depth-stencil formats are
+    /// accepted.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if `format` is a `Format` that has neither a depth nor a stencil aspect.
+    ///
+    pub fn depth_stencil(device: &Arc<Device>, dimensions: [u32; 2], format: F)
+                         -> Result<Arc<AttachmentImage<F>>, ImageCreationError>
+        where F: PossibleDepthOrStencilFormatDesc
+    {
+        assert!(format.is_depth_or_stencil(), "The format of a depth-stencil attachment image \
+                                               must have a depth and/or stencil aspect");
+        AttachmentImage::transient(device, dimensions, format)
+    }
+
+    /// Same as `new`, except that the image will have multiple array layers.
+    ///
+    /// Use `CustomImageView::layer` to obtain a view of a single layer, for example to render to
+    /// it. Rendering to different layers is not considered a conflict.
+    pub fn array(device: &Arc<Device>, dimensions: [u32; 2], array_layers: u32, format: F)
+                 -> Result<Arc<AttachmentImage<F>>, ImageCreationError>
         where F: FormatDesc
     {
-        let is_depth = match format.format().ty() {
-            FormatTy::Depth => true,
-            FormatTy::DepthStencil => true,
-            FormatTy::Compressed => panic!(),
-            _ => false
+        let usage = Usage {
+            transfer_source: true,
+            transfer_dest: true,
+            sampled: true,
+            .. Usage::none()
         };
 
+        AttachmentImage::new_impl(device, dimensions, array_layers, false, format, usage, 1)
+    }
+
+    /// Same as `array`, except that the image is cube-compatible and has 6 square array layers.
+    ///
+    /// Rendering to all the faces at once can be done with a framebuffer of 6 layers and a
+    /// geometry shader that writes `gl_Layer`. Use `CustomImageView::cube` to sample the result
+    /// as a cube map.
+    pub fn cubemap(device: &Arc<Device>, size: u32, format: F)
+                   -> Result<Arc<AttachmentImage<F>>, ImageCreationError>
+        where F: FormatDesc
+    {
         let usage = Usage {
-            color_attachment: !is_depth,
-            depth_stencil_attachment: is_depth,
-            input_attachment: true,
-            .. usage
+            transfer_source: true,
+            transfer_dest: true,
+            sampled: true,
+            .. Usage::none()
+        };
+
+        AttachmentImage::new_impl(device, [size, size], 6, true, format, usage, 1)
+    }
+
+    /// Same as `multisampled`, except that the image will be transient.
+    pub fn transient_multisampled(device: &Arc<Device>, dimensions: [u32; 2], samples: u32,
+                                  format: F)
+                                  -> Result<Arc<AttachmentImage<F>>, ImageCreationError>
+        where F: FormatDesc
+    {
+        let usage = Usage {
+            transient_attachment: true,
+            .. Usage::none()
+        };
+
+        AttachmentImage::new_impl(device, dimensions, 1, false, format, usage, samples)
+    }
+
+    /// Returns the sample counts that are supported when creating a multisampled attachment
+    /// image of the given format.
+    ///
+    /// Use `max()` on the returned value to obtain the best available sample count. The set is
+    /// empty for compressed formats, which can't be attachments.
+    #[inline]
+    pub fn supported_sample_counts(device: &Arc<Device>, format: &F) -> SupportedSampleCounts
+        where F: FormatDesc
+    {
+        if format.format().is_compressed() {
+            return SupportedSampleCounts::none();
+        }
+
+        let usage = attachment_usage(format.format(), Usage::none());
+        SupportedSampleCounts::for_image(&device.physical_device(), format.format(), &usage)
+    }
+
+    fn new_impl(device: &Arc<Device>, dimensions: [u32; 2], array_layers: u32,
+                cube_compatible: bool, format: F, usage: Usage, samples: u32)
+                -> Result<Arc<AttachmentImage<F>>, ImageCreationError>
+        where F: FormatDesc
+    {
+        let usage = attachment_usage(format.format(), usage);
+
+        let dimensions = if array_layers == 1 {
+            Dimensions::Dim2d { width: dimensions[0], height: dimensions[1] }
+        } else {
+            Dimensions::Dim2dArray { width: dimensions[0], height: dimensions[1],
+                                     array_layers: array_layers }
         };
 
         let (image, mem_reqs) = unsafe {
-            try!(UnsafeImage::new(device, &usage, format.format(),
-                                  Dimensions::Dim2d { width: dimensions[0], height: dimensions[1] },
-                                  1, 1, Sharing::Exclusive::<Empty<u32>>, false, false))
+            try!(UnsafeImage::new_checked(device, &usage, format.format(), dimensions, samples,
+                                          1, Sharing::Exclusive::<Empty<u32>>, false, false,
+                                          ImageCreateFlags {
+                                              cube_compatible: cube_compatible,
+                                              .. ImageCreateFlags::none()
+                                          }))
         };
 
+        let pool = device.standard_pool();
         let mem_ty = {
-            let device_local = device.physical_device().memory_types()
-                                     .filter(|t| (mem_reqs.memory_type_bits & (1 << t.id())) != 0)
-                                     .filter(|t| t.is_device_local());
-            let any = device.physical_device().memory_types()
-                            .filter(|t| (mem_reqs.memory_type_bits & (1 << t.id())) != 0);
-            device_local.chain(any).next().unwrap()
+            // Transient images can live in lazily-allocated memory, if there is any.
+            let preference = if usage.transient_attachment {
+                MemoryTypePreference::Transient
+            } else {
+                MemoryTypePreference::DeviceLocal
+            };
+
+            pool.choose_memory_type(&mem_reqs, preference).unwrap()
         };
 
-        let mem = try!(MemoryPool::alloc(&device.standard_pool(), mem_ty,
+        let mem = try!(MemoryPool::alloc(&pool, mem_ty,
                                          mem_reqs.size, mem_reqs.alignment, AllocLayout::Optimal));
         debug_assert!((mem.offset() % mem_reqs.alignment) == 0);
-        unsafe { try!(image.bind_memory(mem.memory(), mem.offset())); }
+        unsafe { try!(image.bind_allocated_memory(mem.memory(), mem.offset())); }
 
-        let view = unsafe {
-            try!(UnsafeImageView::raw(&image, 0 .. 1, 0 .. 1))
-        };
+        let view = unsafe { try!(UnsafeImageView::whole(&image)) };
 
         Ok(Arc::new(AttachmentImage {
             image: image,
             view: view,
             memory: mem,
             format: format,
-            attachment_layout: if is_depth { Layout::DepthStencilAttachmentOptimal }
-                               else { Layout::ColorAttachmentOptimal },
+            attachment_layout: if usage.depth_stencil_attachment {
+                Layout::DepthStencilAttachmentOptimal
+            } else {
+                Layout::ColorAttachmentOptimal
+            },
             guarded: Mutex::new(Guarded {
-                correct_layout: false,
-                latest_submission: None,
+                layouts: LayoutTracker::new(1, dimensions.array_layers(), Layout::Undefined),
+                accesses: AccessTracker::new(),
             }),
         }))
     }
 }
 
+// Adds the usages required by an attachment image of the given format to `usage`.
+fn attachment_usage(format: Format, usage: Usage) -> Usage {
+    let is_depth = format.is_depth_or_stencil();
+
+    Usage {
+        color_attachment: !is_depth,
+        depth_stencil_attachment: is_depth,
+        input_attachment: true,
+        .. usage
+    }
+}
+
 impl<F, A> AttachmentImage<F, A> where A: MemoryPool {
     /// Returns the dimensions of the image.
     #[inline]
@@ -196,6 +341,18 @@ impl<F, A> AttachmentImage<F, A> where A: MemoryPool {
         let dims = self.image.dimensions();
         [dims.width(), dims.height()]
     }
+
+    /// Returns the number of array layers of the image.
+    #[inline]
+    pub fn array_layers(&self) -> u32 {
+        self.image.dimensions().array_layers()
+    }
+
+    /// Returns the number of samples per pixel of the image.
+    #[inline]
+    pub fn samples(&self) -> u32 {
+        self.image.samples()
+    }
 }
 
 unsafe impl<F, A> Image for AttachmentImage<F, A> where F: 'static + Send + Sync, A: MemoryPool {
@@ -205,18 +362,19 @@ unsafe impl<F, A> Image for AttachmentImage<F, A> where F: 'static + Send + Sync
     }
 
     #[inline]
-    fn blocks(&self, _: Range<u32>, _: Range<u32>) -> Vec<(u32, u32)> {
-        vec![(0, 0)]
+    fn blocks(&self, _: Range<u32>, array_layers: Range<u32>) -> Vec<(u32, u32)> {
+        // Each array layer is a separate block.
+        array_layers.map(|layer| (0, layer)).collect()
     }
 
     #[inline]
-    fn block_mipmap_levels_range(&self, block: (u32, u32)) -> Range<u32> {
+    fn block_mipmap_levels_range(&self, _: (u32, u32)) -> Range<u32> {
         0 .. 1
     }
 
     #[inline]
     fn block_array_layers_range(&self, block: (u32, u32)) -> Range<u32> {
-        0 .. 1
+        block.1 .. block.1 + 1
     }
 
     #[inline]
@@ -233,35 +391,43 @@ unsafe impl<F, A> Image for AttachmentImage<F, A> where F: 'static + Send + Sync
         Some(false)
     }
 
-    unsafe fn gpu_access(&self, _: &mut Iterator<Item = AccessRange>,
+    unsafe fn gpu_access(&self, access: &mut Iterator<Item = AccessRange>,
                          submission: &Arc<Submission>) -> GpuAccessResult
     {
         let mut guarded = self.guarded.lock().unwrap();
 
-        let dependency = mem::replace(&mut guarded.latest_submission, Some(Arc::downgrade(submission)));
-        let dependency = dependency.and_then(|d| d.upgrade());
+        let mut accesses = Vec::new();
+        let mut transitions = Vec::new();
 
-        let transition = if !guarded.correct_layout {
-            vec![Transition {
-                block: (0, 0),
-                from: Layout::Undefined,
-                to: self.attachment_layout,
-            }]
-        } else {
-            vec![]
-        };
+        for mut access in access {
+            let layer = access.block.1;
+
+            let layer_transitions = guarded.layouts.transition(0 .. 1, layer .. layer + 1,
+                                                               self.attachment_layout);
 
-        guarded.correct_layout = true;
+            // A layout transition modifies the content of the layer.
+            if !layer_transitions.is_empty() {
+                access.write = true;
+            }
+
+            for t in layer_transitions {
+                transitions.push(Transition {
+                    block: access.block,
+                    from: t.from,
+                    to: t.to,
+                });
+            }
+
+            accesses.push(access);
+        }
+
+        let dependencies = guarded.accesses.register(accesses, submission);
 
         GpuAccessResult {
-            dependencies: if let Some(dependency) = dependency {
-                vec![dependency]
-            } else {
-                vec![]
-            },
+            dependencies: dependencies,
             additional_wait_semaphore: None,
             additional_signal_semaphore: None,
-            before_transitions: transition,
+            before_transitions: transitions,
             after_transitions: vec![],
         }
     }
@@ -272,7 +438,7 @@ unsafe impl<F, A> ImageClearValue<F::ClearValue> for AttachmentImage<F, A>
 {
     #[inline]
     fn decode(&self, value: F::ClearValue) -> Option<ClearValue> {
-        Some(self.format.decode_clear_value(value))
+        self.format.try_decode_clear_value(value)
     }
 }
 
@@ -281,7 +447,7 @@ unsafe impl<P, F, A> ImageContent<P> for AttachmentImage<F, A>
 {
     #[inline]
     fn matches_format(&self) -> bool {
-        true        // FIXME:
+        Image::format(self).accepts_pixel_size(mem::size_of::<P>())
     }
 }
 
@@ -300,7 +466,7 @@ unsafe impl<F, A> ImageView for AttachmentImage<F, A>
 
     #[inline]
     fn blocks(&self) -> Vec<(u32, u32)> {
-        vec![(0, 0)]
+        Image::blocks(self, 0 .. 1, 0 .. self.image.dimensions().array_layers())
     }
 
     #[inline]
@@ -336,8 +502,28 @@ unsafe impl<F, A> ImageView for AttachmentImage<F, A>
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use super::AttachmentImage;
+    use buffer::BufferUsage;
+    use buffer::CpuAccessibleBuffer;
+    use command_buffer::CommandBufferPool;
+    use command_buffer::PrimaryCommandBufferBuilder;
+    use command_buffer::submit;
+    use format::ClearValue;
+    use format::D16Unorm;
     use format::Format;
+    use format::R8G8B8A8Unorm;
+    use framebuffer::Framebuffer;
+    use framebuffer::FramebufferCreationError;
+    use framebuffer::RenderPassCreationError;
+    use framebuffer::RenderPassDescError;
+    use image::CustomImageView;
+    use image::ImageView;
+    use image::Swizzle;
+    use image::traits::ImageClearValue;
+    use image::traits::ImageContent;
+    use image::Usage;
 
     #[test]
     fn create_regular() {
@@ -350,4 +536,351 @@ mod tests {
         let (device, _) = gfx_dev_and_queue!();
         let _img = AttachmentImage::transient(&device, [32, 32], Format::R8G8B8A8Unorm).unwrap();
     }
+
+    #[test]
+    fn create_depth_stencil() {
+        let (device, _) = gfx_dev_and_queue!();
+        let _img = AttachmentImage::depth_stencil(&device, [32, 32], D16Unorm).unwrap();
+    }
+
+    #[test]
+    #[should_panic = "The format of a depth-stencil attachment image must have a depth and/or \
+                      stencil aspect"]
+    fn depth_stencil_color_format() {
+        let (device, _) = gfx_dev_and_queue!();
+        let _ = AttachmentImage::depth_stencil(&device, [32, 32], Format::R8G8B8A8Unorm);
+    }
+
+    #[test]
+    fn create_with_usage() {
+        let (device, _) = gfx_dev_and_queue!();
+        let usage = Usage { sampled: true, .. Usage::none() };
+        let img = AttachmentImage::with_usage(&device, [32, 32], Format::R8G8B8A8Unorm,
+                                              usage).unwrap();
+        assert!(img.inner_view().usage_sampled());
+        assert!(!img.inner_view().usage_transfer_src());
+    }
+
+    #[test]
+    fn create_multisampled() {
+        let (device, _) = gfx_dev_and_queue!();
+        // 4 samples are always supported for color attachments.
+        let img = AttachmentImage::multisampled(&device, [32, 32], 4,
+                                                Format::R8G8B8A8Unorm).unwrap();
+        assert_eq!(img.samples(), 4);
+    }
+
+    #[test]
+    fn best_sample_count() {
+        let (device, _) = gfx_dev_and_queue!();
+        let supported = AttachmentImage::supported_sample_counts(&device, &Format::R8G8B8A8Unorm);
+        let samples = supported.max().unwrap().count();
+        assert!(samples >= 4);
+
+        let img = AttachmentImage::multisampled(&device, [32, 32], samples,
+                                                Format::R8G8B8A8Unorm).unwrap();
+        assert_eq!(img.samples(), samples);
+    }
+
+    #[test]
+    fn compressed_sample_counts() {
+        let (device, _) = gfx_dev_and_queue!();
+        let supported = AttachmentImage::supported_sample_counts(&device,
+                                                                 &Format::BC1_RGBUnormBlock);
+        assert_eq!(supported.max(), None);
+    }
+
+    #[test]
+    fn multisampled_invalid_samples() {
+        let (device, _) = gfx_dev_and_queue!();
+        assert!(AttachmentImage::multisampled(&device, [32, 32], 3,
+                                              Format::R8G8B8A8Unorm).is_err());
+    }
+
+    #[test]
+    fn clear_depth() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let depth = AttachmentImage::new(&device, [32, 32], D16Unorm).unwrap();
+        assert_eq!(depth.decode(0.5), Some(ClearValue::Depth(0.5)));
+
+        let pool = CommandBufferPool::new(&device, &queue.family());
+        let cb = PrimaryCommandBufferBuilder::new(&pool)
+                    .clear_depth_stencil_image(&depth, 1.0)
+                    .build();
+        submit(&cb, &queue).unwrap();
+    }
+
+    #[test]
+    fn clear_color_runtime_format() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let image = AttachmentImage::new(&device, [4, 4], Format::R8G8B8A8Unorm).unwrap();
+        assert_eq!(image.decode(ClearValue::Uint([0; 4])), None);
+        assert_eq!(image.decode(ClearValue::Float([0.0, 1.0, 0.0, 1.0])),
+                   Some(ClearValue::Float([0.0, 1.0, 0.0, 1.0])));
+
+        let usage = BufferUsage { transfer_dest: true, .. BufferUsage::none() };
+        let dest = CpuAccessibleBuffer::<[[u8; 4]]>::array(&device, 16, &usage,
+                                                           Some(queue.family())).unwrap();
+
+        let pool = CommandBufferPool::new(&device, &queue.family());
+        let cb = PrimaryCommandBufferBuilder::new(&pool)
+                    .clear_color_image(&image, ClearValue::Float([0.0, 1.0, 0.0, 1.0]))
+                    .copy_color_image_to_buffer(&dest, &image, 0, 0 .. 1, [0, 0, 0], [4, 4, 1])
+                    .build();
+        submit(&cb, &queue).unwrap();
+
+        let content = dest.read(Duration::new(1, 0)).unwrap();
+        assert!(content.iter().all(|p| *p == [0, 255, 0, 255]));
+    }
+
+    #[test]
+    fn content_matches_format() {
+        let (device, _) = gfx_dev_and_queue!();
+        let image = AttachmentImage::new(&device, [4, 4], Format::R8G8B8A8Unorm).unwrap();
+        assert!(ImageContent::<[u8; 4]>::matches_format(&*image));
+        assert!(ImageContent::<u32>::matches_format(&*image));
+        assert!(!ImageContent::<u8>::matches_format(&*image));
+        assert!(!ImageContent::<[f32; 4]>::matches_format(&*image));
+    }
+
+    mod example {
+        use format::D16Unorm;
+        use format::R8G8B8A8Unorm;
+
+        single_pass_renderpass! {
+            attachments: {
+                color: {
+                    load: Clear,
+                    store: Store,
+                    format: R8G8B8A8Unorm,
+                },
+                depth: {
+                    load: Clear,
+                    store: DontCare,
+                    format: D16Unorm,
+                }
+            },
+            pass: {
+                color: [color],
+                depth_stencil: {depth}
+            }
+        }
+    }
+
+    #[test]
+    fn color_and_depth_framebuffer() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let render_pass = example::CustomRenderPass::new(&device, &example::Formats {
+            color: (R8G8B8A8Unorm, 1),
+            depth: (D16Unorm, 1),
+        }).unwrap();
+
+        let color = AttachmentImage::new(&device, [256, 256], R8G8B8A8Unorm).unwrap();
+        let depth = AttachmentImage::depth_stencil(&device, [256, 256], D16Unorm).unwrap();
+
+        let _ = Framebuffer::new(&render_pass, [256, 256, 1], example::AList {
+            color: &color,
+            depth: &depth,
+        }).unwrap();
+    }
+
+    mod layered {
+        use format::R8G8B8A8Unorm;
+
+        single_pass_renderpass! {
+            attachments: {
+                color: {
+                    load: Clear,
+                    store: Store,
+                    format: R8G8B8A8Unorm,
+                }
+            },
+            pass: {
+                color: [color],
+                depth_stencil: {}
+            }
+        }
+    }
+
+    #[test]
+    fn render_to_array_layers() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let render_pass = layered::CustomRenderPass::new(&device, &layered::Formats {
+            color: (R8G8B8A8Unorm, 1)
+        }).unwrap();
+
+        let image = AttachmentImage::array(&device, [4, 4], 2, R8G8B8A8Unorm).unwrap();
+        assert_eq!(image.array_layers(), 2);
+
+        let layer0 = CustomImageView::layer(&image, 0).unwrap();
+        let layer1 = CustomImageView::layer(&image, 1).unwrap();
+        let fb0 = Framebuffer::new(&render_pass, [4, 4, 1], layered::AList {
+            color: &layer0
+        }).unwrap();
+        let fb1 = Framebuffer::new(&render_pass, [4, 4, 1], layered::AList {
+            color: &layer1
+        }).unwrap();
+
+        let usage = BufferUsage { transfer_dest: true, .. BufferUsage::none() };
+        let dest0 = CpuAccessibleBuffer::<[[u8; 4]]>::array(&device, 16, &usage,
+                                                             Some(queue.family())).unwrap();
+        let dest1 = CpuAccessibleBuffer::<[[u8; 4]]>::array(&device, 16, &usage,
+                                                             Some(queue.family())).unwrap();
+
+        let pool = CommandBufferPool::new(&device, &queue.family());
+
+        let cb = PrimaryCommandBufferBuilder::new(&pool)
+                    .draw_inline(&render_pass, &fb0, layered::ClearValues {
+                        color: [1.0, 0.0, 0.0, 1.0]
+                    })
+                    .draw_end()
+                    .build();
+        submit(&cb, &queue).unwrap();
+
+        let cb = PrimaryCommandBufferBuilder::new(&pool)
+                    .draw_inline(&render_pass, &fb1, layered::ClearValues {
+                        color: [0.0, 0.0, 1.0, 1.0]
+                    })
+                    .draw_end()
+                    .build();
+        submit(&cb, &queue).unwrap();
+
+        let cb = PrimaryCommandBufferBuilder::new(&pool)
+                    .copy_color_image_to_buffer(&dest0, &image, 0, 0 .. 1, [0, 0, 0], [4, 4, 1])
+                    .copy_color_image_to_buffer(&dest1, &image, 0, 1 .. 2, [0, 0, 0], [4, 4, 1])
+                    .build();
+        submit(&cb, &queue).unwrap();
+
+        let content0 = dest0.read(Duration::new(1, 0)).unwrap();
+        let content1 = dest1.read(Duration::new(1, 0)).unwrap();
+        assert!(content0.iter().all(|p| *p == [255, 0, 0, 255]));
+        assert!(content1.iter().all(|p| *p == [0, 0, 255, 255]));
+    }
+
+    mod msaa {
+        use format::R8G8B8A8Unorm;
+
+        single_pass_renderpass! {
+            attachments: {
+                color: {
+                    load: Clear,
+                    store: DontCare,
+                    format: R8G8B8A8Unorm,
+                },
+                resolved: {
+                    load: DontCare,
+                    store: Store,
+                    format: R8G8B8A8Unorm,
+                }
+            },
+            pass: {
+                color: [color],
+                depth_stencil: {},
+                resolve: [{resolved}]
+            }
+        }
+    }
+
+    #[test]
+    fn msaa_resolve() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let render_pass = msaa::CustomRenderPass::new(&device, &msaa::Formats {
+            color: (R8G8B8A8Unorm, 4),
+            resolved: (R8G8B8A8Unorm, 1),
+        }).unwrap();
+
+        let color = AttachmentImage::transient_multisampled(&device, [4, 4], 4,
+                                                            R8G8B8A8Unorm).unwrap();
+        let resolved = AttachmentImage::new(&device, [4, 4], R8G8B8A8Unorm).unwrap();
+        let framebuffer = Framebuffer::new(&render_pass, [4, 4, 1], msaa::AList {
+            color: &color,
+            resolved: &resolved,
+        }).unwrap();
+
+        let usage = BufferUsage { transfer_dest: true, .. BufferUsage::none() };
+        let dest = CpuAccessibleBuffer::<[[u8; 4]]>::array(&device, 16, &usage,
+                                                           Some(queue.family())).unwrap();
+
+        let pool = CommandBufferPool::new(&device, &queue.family());
+        let cb = PrimaryCommandBufferBuilder::new(&pool)
+                    .draw_inline(&render_pass, &framebuffer, msaa::ClearValues {
+                        color: [0.0, 1.0, 0.0, 1.0]
+                    })
+                    .draw_end()
+                    .copy_color_image_to_buffer(&dest, &resolved, 0, 0 .. 1, [0, 0, 0], [4, 4, 1])
+                    .build();
+        submit(&cb, &queue).unwrap();
+
+        let content = dest.read(Duration::new(1, 0)).unwrap();
+        assert!(content.iter().all(|p| *p == [0, 255, 0, 255]));
+    }
+
+    #[test]
+    fn msaa_resolve_invalid_samples() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        match msaa::CustomRenderPass::new(&device, &msaa::Formats {
+            color: (R8G8B8A8Unorm, 4),
+            resolved: (R8G8B8A8Unorm, 4),
+        }) {
+            Err(RenderPassCreationError::InvalidDesc(
+                RenderPassDescError::ResolveAttachmentMultisampled { pass: 0, attachment: 1 }
+            )) => (),
+            _ => panic!("Creating a render pass with a multisampled resolve attachment succeeded")
+        }
+    }
+
+    #[test]
+    fn msaa_resolve_dimensions_mismatch() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let render_pass = msaa::CustomRenderPass::new(&device, &msaa::Formats {
+            color: (R8G8B8A8Unorm, 4),
+            resolved: (R8G8B8A8Unorm, 1),
+        }).unwrap();
+
+        let color = AttachmentImage::transient_multisampled(&device, [8, 8], 4,
+                                                            R8G8B8A8Unorm).unwrap();
+        let resolved = AttachmentImage::new(&device, [4, 4], R8G8B8A8Unorm).unwrap();
+        match Framebuffer::new(&render_pass, [4, 4, 1], msaa::AList {
+            color: &color,
+            resolved: &resolved,
+        }) {
+            Err(FramebufferCreationError::ResolveAttachmentDimensionsMismatch) => (),
+            _ => panic!("Creating a framebuffer with mismatched resolve dimensions succeeded")
+        }
+    }
+
+    #[test]
+    fn cubemap_layered_framebuffer() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let render_pass = layered::CustomRenderPass::new(&device, &layered::Formats {
+            color: (R8G8B8A8Unorm, 1)
+        }).unwrap();
+
+        let image = AttachmentImage::cubemap(&device, 64, R8G8B8A8Unorm).unwrap();
+        assert_eq!(image.array_layers(), 6);
+
+        let faces = CustomImageView::new(&image, 0 .. 1, 0 .. 6, Swizzle::default()).unwrap();
+        let framebuffer = Framebuffer::new(&render_pass, [64, 64, 6], layered::AList {
+            color: &faces
+        }).unwrap();
+        assert_eq!(framebuffer.layers(), 6);
+
+        let face = CustomImageView::layer(&image, 0).unwrap();
+        match Framebuffer::new(&render_pass, [64, 64, 6], layered::AList { color: &face }) {
+            Err(FramebufferCreationError::AttachmentNotEnoughLayers {
+                attachment: 0,
+                layers: 1,
+                min: 6,
+            }) => (),
+            _ => panic!("Creating a framebuffer with too few layers succeeded")
+        }
+    }
 }
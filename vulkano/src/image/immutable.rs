@@ -7,37 +7,55 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
+use std::error;
+use std::fmt;
 use std::mem;
 use std::iter::Empty;
 use std::ops::Range;
 use std::sync::Arc;
 use std::sync::Mutex;
-use std::sync::Weak;
-use std::sync::atomic::AtomicBool;
-use std::sync::atomic::Ordering;
+use std::time::Duration;
 use smallvec::SmallVec;
 
+use buffer::BufferSlice;
+use buffer::BufferUsage;
+use buffer::CpuAccessibleBuffer;
+use command_buffer::CommandBufferPool;
+use command_buffer::PrimaryCommandBufferBuilder;
 use command_buffer::Submission;
+use command_buffer::submit;
 use device::Device;
+use device::Queue;
 use format::FormatDesc;
+use format::FormatTy;
+use image::MipmapsCount;
+use image::layout::LayoutTracker;
 use image::sys::Dimensions;
+use image::sys::ImageCreateFlags;
 use image::sys::ImageCreationError;
 use image::sys::Layout;
 use image::sys::UnsafeImage;
 use image::sys::UnsafeImageView;
 use image::sys::Usage;
 use image::traits::AccessRange;
+use image::traits::AccessTracker;
 use image::traits::GpuAccessResult;
 use image::traits::Image;
 use image::traits::ImageContent;
 use image::traits::ImageView;
+use image::traits::Transition;
 use instance::QueueFamily;
+use memory::MemoryTypePreference;
 use memory::pool::AllocLayout;
 use memory::pool::MemoryPool;
 use memory::pool::MemoryPoolAlloc;
+use memory::pool::MemoryPoolError;
 use memory::pool::StdMemoryPool;
+use sync::FenceWaitError;
 use sync::Sharing;
 
+use OomError;
+
 /// Image whose purpose is to be used for read-only purposes. You can write to the image once,
 /// but then you must only ever read from it. TODO: clarify because of blit operations
 // TODO: type (2D, 3D, array, etc.) as template parameter
@@ -47,27 +65,86 @@ pub struct ImmutableImage<F, A = StdMemoryPool> where A: MemoryPool {
     view: UnsafeImageView,
     memory: A::Alloc,
     format: F,
-    per_layer: SmallVec<[PerLayer; 1]>,
+    guarded: Mutex<Guarded>,
 }
 
 #[derive(Debug)]
-struct PerLayer {
-    latest_write_submission: Mutex<Option<Weak<Submission>>>,        // TODO: can use `Weak::new()` once it's stabilized
-    started_reading: AtomicBool,
+struct Guarded {
+    // Layout of each mipmap level of each array layer. They start in the undefined layout.
+    layouts: LayoutTracker,
+
+    // Subresources accessed by the submissions that use the image.
+    accesses: AccessTracker,
 }
 
 impl<F> ImmutableImage<F> {
+    /// Builds a new immutable image with only one mipmap level.
+    ///
+    /// The content of the image is undefined. You are expected to fill it with a transfer
+    /// operation before reading from it.
+    #[inline]
     pub fn new<'a, I>(device: &Arc<Device>, dimensions: Dimensions, format: F, queue_families: I)
                       -> Result<Arc<ImmutableImage<F>>, ImageCreationError>
         where F: FormatDesc, I: IntoIterator<Item = QueueFamily<'a>>
+    {
+        ImmutableImage::with_mipmaps(device, dimensions, format, MipmapsCount::One,
+                                     queue_families)
+    }
+
+    /// Builds a new immutable image with the given number of mipmap levels.
+    ///
+    /// The content of the image is undefined. You are expected to fill it with a transfer
+    /// operation before reading from it.
+    #[inline]
+    pub fn with_mipmaps<'a, I, M>(device: &Arc<Device>, dimensions: Dimensions, format: F,
+                                  mipmaps: M, queue_families: I)
+                                  -> Result<Arc<ImmutableImage<F>>, ImageCreationError>
+        where F: FormatDesc, I: IntoIterator<Item = QueueFamily<'a>>, M: Into<MipmapsCount>
+    {
+        ImmutableImage::new_impl(device, dimensions, format, mipmaps.into(), false,
+                                 default_usage(), queue_families)
+    }
+
+    /// Same as `with_mipmaps`, but with a specific usage.
+    ///
+    /// The `transfer_dest` usage is always enabled, as it is required to fill the image.
+    #[inline]
+    pub fn with_usage<'a, I, M>(device: &Arc<Device>, dimensions: Dimensions, format: F,
+                                mipmaps: M, usage: Usage, queue_families: I)
+                                -> Result<Arc<ImmutableImage<F>>, ImageCreationError>
+        where F: FormatDesc, I: IntoIterator<Item = QueueFamily<'a>>, M: Into<MipmapsCount>
     {
         let usage = Usage {
-            transfer_source: true,  // for blits
             transfer_dest: true,
-            sampled: true,
-            .. Usage::none()
+            .. usage
         };
 
+        ImmutableImage::new_impl(device, dimensions, format, mipmaps.into(), false, usage,
+                                 queue_families)
+    }
+
+    /// Builds a new cube-compatible immutable image, in other words an image with 6 square array
+    /// layers that can be viewed as a cube map.
+    ///
+    /// The content of the image is undefined. You are expected to fill it with a transfer
+    /// operation before reading from it.
+    #[inline]
+    pub fn cubemap<'a, I, M>(device: &Arc<Device>, size: u32, format: F, mipmaps: M,
+                             queue_families: I)
+                             -> Result<Arc<ImmutableImage<F>>, ImageCreationError>
+        where F: FormatDesc, I: IntoIterator<Item = QueueFamily<'a>>, M: Into<MipmapsCount>
+    {
+        let dimensions = Dimensions::Dim2dArray { width: size, height: size, array_layers: 6 };
+        ImmutableImage::new_impl(device, dimensions, format, mipmaps.into(), true,
+                                 default_usage(), queue_families)
+    }
+
+    fn new_impl<'a, I>(device: &Arc<Device>, dimensions: Dimensions, format: F,
+                       mipmaps: MipmapsCount, cube_compatible: bool, usage: Usage,
+                       queue_families: I)
+                       -> Result<Arc<ImmutableImage<F>>, ImageCreationError>
+        where F: FormatDesc, I: IntoIterator<Item = QueueFamily<'a>>
+    {
         let queue_families = queue_families.into_iter().map(|f| f.id())
                                            .collect::<SmallVec<[u32; 4]>>();
 
@@ -78,46 +155,175 @@ impl<F> ImmutableImage<F> {
                 Sharing::Exclusive
             };
 
-            try!(UnsafeImage::new(device, &usage, format.format(), dimensions,
-                                  1, 1, Sharing::Exclusive::<Empty<u32>>, false, false))
+            try!(UnsafeImage::new_checked(device, &usage, format.format(), dimensions,
+                                          1, mipmaps, Sharing::Exclusive::<Empty<u32>>, false,
+                                          false, ImageCreateFlags {
+                                              cube_compatible: cube_compatible,
+                                              .. ImageCreateFlags::none()
+                                          }))
         };
 
-        let mem_ty = {
-            let device_local = device.physical_device().memory_types()
-                                     .filter(|t| (mem_reqs.memory_type_bits & (1 << t.id())) != 0)
-                                     .filter(|t| t.is_device_local());
-            let any = device.physical_device().memory_types()
-                            .filter(|t| (mem_reqs.memory_type_bits & (1 << t.id())) != 0);
-            device_local.chain(any).next().unwrap()
-        };
+        let pool = device.standard_pool();
+        let mem_ty = pool.choose_memory_type(&mem_reqs, MemoryTypePreference::DeviceLocal)
+                         .unwrap();
 
-        let mem = try!(MemoryPool::alloc(&device.standard_pool(), mem_ty,
+        let mem = try!(MemoryPool::alloc(&pool, mem_ty,
                                          mem_reqs.size, mem_reqs.alignment, AllocLayout::Optimal));
         debug_assert!((mem.offset() % mem_reqs.alignment) == 0);
-        unsafe { try!(image.bind_memory(mem.memory(), mem.offset())); }
+        unsafe { try!(image.bind_allocated_memory(mem.memory(), mem.offset())); }
 
-        let view = unsafe {
-            try!(UnsafeImageView::raw(&image, 0 .. image.mipmap_levels(),
-                                      0 .. image.dimensions().array_layers()))
-        };
+        let view = unsafe { try!(UnsafeImageView::whole(&image)) };
+        let layouts = LayoutTracker::new(image.mipmap_levels(), image.dimensions().array_layers(),
+                                         Layout::Undefined);
 
         Ok(Arc::new(ImmutableImage {
             image: image,
             view: view,
             memory: mem,
             format: format,
-            per_layer: {
-                let mut v = SmallVec::new();
-                for _ in 0 .. dimensions.array_layers() {
-                    v.push(PerLayer {
-                        latest_write_submission: Mutex::new(None),
-                        started_reading: AtomicBool::new(false),
-                    });
-                }
-                v
-            },
+            guarded: Mutex::new(Guarded {
+                layouts: layouts,
+                accesses: AccessTracker::new(),
+            }),
         }))
     }
+
+    /// Builds a new immutable image and fills it with the content of an iterator.
+    ///
+    /// The data is first written to a staging buffer, then copied to the first mipmap level of
+    /// the image. If more than one mipmap level is requested, the other levels are generated by
+    /// successively blitting each level to the next one. The iterator must produce exactly one
+    /// element per texel of the first mipmap level of all the array layers, layer after layer.
+    ///
+    /// The operations are submitted to `queue`. The returned `Submission` can be used to wait
+    /// for the upload to be finished, but you don't need to: any command buffer that uses the
+    /// image will automatically wait for the upload.
+    ///
+    /// # Compressed formats
+    ///
+    /// Compressed images can't be the destination of a blit, therefore their mipmaps can't be
+    /// generated. If the format is compressed, the iterator must instead produce the
+    /// pre-compressed data of all the requested mipmap levels, level after level and layer after
+    /// layer within each level. Each level must be made of `ceil(width / block_width) *
+    /// ceil(height / block_height) * block_size` bytes per layer. The elements of the iterator
+    /// are usually bytes or whole blocks. `InvalidDataSize` is returned if the size of the data
+    /// doesn't match.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the format is not compressed and the number of elements produced by the
+    ///   iterator doesn't match the dimensions of the image.
+    ///
+    pub fn from_iter<P, I, M>(iter: I, dimensions: Dimensions, format: F, mipmaps: M,
+                              queue: &Arc<Queue>)
+                              -> Result<(Arc<ImmutableImage<F>>, Arc<Submission>),
+                                        ImageUploadError>
+        where F: FormatDesc + 'static + Send + Sync, P: 'static + Send + Sync,
+              I: IntoIterator<Item = P>, I::IntoIter: ExactSizeIterator,
+              M: Into<MipmapsCount>
+    {
+        let device = queue.device();
+        let iter = iter.into_iter();
+        let num_elements = iter.len();
+
+        let compressed = format.format().ty() == FormatTy::Compressed;
+        let raw_format = format.format();
+
+        if !compressed {
+            let num_texels = dimensions.width() as usize * dimensions.height() as usize *
+                             dimensions.depth() as usize * dimensions.array_layers() as usize;
+            assert_eq!(num_elements, num_texels);
+        }
+
+        let image = try!(ImmutableImage::with_mipmaps(device, dimensions, format, mipmaps,
+                                                      Some(queue.family())));
+
+        let num_mipmaps = image.image.mipmap_levels();
+        let layers = 0 .. dimensions.array_layers();
+        let width = dimensions.width();
+        let height = dimensions.height();
+        let depth = dimensions.depth();
+
+        // Size in bytes of each mipmap level of the data, for compressed formats.
+        let levels_sizes = if compressed {
+            let levels_sizes = (0 .. num_mipmaps).map(|level| {
+                let dims = dimensions.mipmap_dimensions(level);
+                raw_format.data_size(dims).unwrap() * layers.end as usize
+            }).collect::<Vec<_>>();
+
+            let expected = levels_sizes.iter().fold(0, |a, b| a + b);
+            let obtained = num_elements * mem::size_of::<P>();
+            let elem_size = mem::size_of::<P>();
+            if expected != obtained || levels_sizes.iter().any(|s| s % elem_size != 0) {
+                return Err(ImageUploadError::InvalidDataSize {
+                    expected: expected,
+                    obtained: obtained,
+                });
+            }
+
+            levels_sizes
+
+        } else {
+            // Blits are required in order to generate the mipmaps.
+            if num_mipmaps >= 2 && (!image.image.supports_blit_source() ||
+                                    !image.image.supports_blit_destination())
+            {
+                return Err(ImageUploadError::MipmapsGenerationNotSupported);
+            }
+
+            Vec::new()
+        };
+
+        let staging = try!(CpuAccessibleBuffer::<[P]>::array(device, num_elements,
+                                                              &BufferUsage::transfer_source(),
+                                                              Some(queue.family())));
+        {
+            // The buffer has just been created, therefore locking it can't block.
+            let mut mapping = try!(staging.write(Duration::new(0, 0)));
+            for (dest, texel) in mapping.iter_mut().zip(iter) {
+                *dest = texel;
+            }
+        }
+
+        let pool = try!(CommandBufferPool::raw(device, &queue.family()));
+        let pool = Arc::new(pool);
+
+        let mut cb = try!(PrimaryCommandBufferBuilder::raw(&pool));
+
+        if compressed {
+            // Each mipmap level is copied from its own part of the staging buffer.
+            let elem_size = mem::size_of::<P>();
+            let mut offset = 0;
+            for (level, &size) in levels_sizes.iter().enumerate() {
+                let level = level as u32;
+                let dims = dimensions.mipmap_dimensions(level);
+                let slice = BufferSlice::from(&staging)
+                                .slice(offset / elem_size .. (offset + size) / elem_size)
+                                .unwrap();
+                cb = cb.copy_buffer_to_color_image(slice, &image, level, layers.clone(),
+                                                   [0, 0, 0], dims);
+                offset += size;
+            }
+
+        } else {
+            cb = cb.copy_buffer_to_color_image(&staging, &image, 0, layers.clone(), [0, 0, 0],
+                                               [width, height, depth]);
+
+            for level in 1 .. num_mipmaps {
+                let src = dimensions.mipmap_dimensions(level - 1);
+                let dest = dimensions.mipmap_dimensions(level);
+
+                cb = cb.blit(&image, level - 1, layers.clone(),
+                             [0 .. src[0] as i32, 0 .. src[1] as i32, 0 .. src[2] as i32],
+                             &image, level, layers.clone(),
+                             [0 .. dest[0] as i32, 0 .. dest[1] as i32, 0 .. dest[2] as i32]);
+            }
+        }
+
+        let cb = Arc::new(try!(cb.build_raw()));
+        let submission = try!(submit(&cb, queue));
+        Ok((image, submission))
+    }
 }
 
 impl<F, A> ImmutableImage<F, A> where A: MemoryPool {
@@ -125,6 +331,21 @@ impl<F, A> ImmutableImage<F, A> where A: MemoryPool {
     pub fn dimensions(&self) -> Dimensions {
         self.image.dimensions()
     }
+
+    /// Returns the number of mipmap levels of the image.
+    #[inline]
+    pub fn mipmap_levels(&self) -> u32 {
+        self.image.mipmap_levels()
+    }
+}
+
+// Usage of immutable images that are created without a specific usage.
+#[inline]
+fn default_usage() -> Usage {
+    Usage {
+        transfer_source: true,  // for blits
+        .. Usage::sampled_transfer_dest()
+    }
 }
 
 unsafe impl<F, A> Image for ImmutableImage<F, A> where F: 'static + Send + Sync, A: MemoryPool {
@@ -134,18 +355,27 @@ unsafe impl<F, A> Image for ImmutableImage<F, A> where F: 'static + Send + Sync,
     }
 
     #[inline]
-    fn blocks(&self, _: Range<u32>, array_layers: Range<u32>) -> Vec<(u32, u32)> {
-        array_layers.map(|l| (0, l)).collect()
+    fn blocks(&self, mipmap_levels: Range<u32>, array_layers: Range<u32>) -> Vec<(u32, u32)> {
+        // Each mipmap level of each array layer is a separate block, so that generating the
+        // mipmaps can read from one level while writing to the next one.
+        let mut blocks = Vec::with_capacity((mipmap_levels.end - mipmap_levels.start) as usize *
+                                            (array_layers.end - array_layers.start) as usize);
+        for level in mipmap_levels {
+            for layer in array_layers.clone() {
+                blocks.push((level, layer));
+            }
+        }
+        blocks
     }
 
     #[inline]
     fn block_mipmap_levels_range(&self, block: (u32, u32)) -> Range<u32> {
-        0 .. 1
+        block.0 .. block.0 + 1
     }
 
     #[inline]
     fn block_array_layers_range(&self, block: (u32, u32)) -> Range<u32> {
-        0 .. 1
+        block.1 .. block.1 + 1
     }
 
     #[inline]
@@ -174,32 +404,51 @@ unsafe impl<F, A> Image for ImmutableImage<F, A> where F: 'static + Send + Sync,
     {
         // FIXME: check queue family
 
-        let mut dependencies = Vec::with_capacity(access.size_hint().1.unwrap_or(0));
+        let mut guarded = self.guarded.lock().unwrap();
 
-        while let Some(access) = access.next() {
-            let per_layer = &self.per_layer[access.block.1 as usize];
+        let mut accesses = Vec::new();
+        let mut transitions = Vec::new();
 
-            if access.write {
-                assert!(per_layer.started_reading.load(Ordering::Acquire) == false);
-            }
+        for mut access in access {
+            let (level, layer) = access.block;
 
-            let mut latest_submission = per_layer.latest_write_submission.lock().unwrap();
-            let dependency = if access.write {
-                mem::replace(&mut *latest_submission, Some(Arc::downgrade(submission)))
-            } else {
-                latest_submission.clone()
-            };
+            // The command buffer expects the block to be in its initial layout, unless it
+            // discards the content. This is the case for example if the image is sampled before
+            // anything was uploaded to it.
+            if access.initial_layout != Layout::Undefined {
+                let block_transitions = guarded.layouts.transition(level .. level + 1,
+                                                                   layer .. layer + 1,
+                                                                   access.initial_layout);
+
+                // A layout transition modifies the content of the block.
+                if !block_transitions.is_empty() {
+                    access.write = true;
+                }
 
-            if let Some(dep) = dependency.and_then(|d| d.upgrade()) {
-                dependencies.push(dep);
+                for t in block_transitions {
+                    transitions.push(Transition {
+                        block: access.block,
+                        from: t.from,
+                        to: t.to,
+                    });
+                }
             }
+
+            guarded.layouts.force_layout(level .. level + 1, layer .. layer + 1,
+                                         access.final_layout);
+            accesses.push(access);
         }
 
+        // Only the submissions that write to subresources we access, or that access the
+        // subresources we write to, are dependencies. Uploading a mipmap level while another
+        // level is being sampled doesn't require any synchronization.
+        let dependencies = guarded.accesses.register(accesses, submission);
+
         GpuAccessResult {
             dependencies: dependencies,
             additional_wait_semaphore: None,
             additional_signal_semaphore: None,
-            before_transitions: vec![],
+            before_transitions: transitions,
             after_transitions: vec![],
         }
     }
@@ -210,7 +459,7 @@ unsafe impl<P, F, A> ImageContent<P> for ImmutableImage<F, A>
 {
     #[inline]
     fn matches_format(&self) -> bool {
-        true        // FIXME:
+        Image::format(self).accepts_pixel_size(mem::size_of::<P>())
     }
 }
 
@@ -229,7 +478,8 @@ unsafe impl<F: 'static, A> ImageView for ImmutableImage<F, A>
 
     #[inline]
     fn blocks(&self) -> Vec<(u32, u32)> {
-        vec![(0, 0)]
+        Image::blocks(self, 0 .. self.image.mipmap_levels(),
+                      0 .. self.image.dimensions().array_layers())
     }
 
     #[inline]
@@ -262,3 +512,245 @@ unsafe impl<F: 'static, A> ImageView for ImmutableImage<F, A>
         true
     }
 }
+
+/// Error that can happen when creating an immutable image from some data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageUploadError {
+    /// Error while creating the image.
+    ImageCreationError(ImageCreationError),
+    /// Not enough memory for the staging buffer or the command buffer.
+    OomError(OomError),
+    /// Error while locking the staging buffer.
+    FenceWaitError(FenceWaitError),
+    /// Multiple mipmaps were requested but the format doesn't support being used as both the
+    /// source and the destination of a blit.
+    MipmapsGenerationNotSupported,
+    /// The size of the compressed data doesn't match the dimensions and the number of mipmap
+    /// levels of the image.
+    InvalidDataSize { expected: usize, obtained: usize },
+    /// Allocating the memory of the staging buffer would make the memory used in a heap exceed
+    /// the budget of that heap.
+    BudgetExceeded {
+        /// Index of the heap.
+        heap: u32,
+        /// Number of bytes that were requested.
+        requested: usize,
+        /// Budget of the heap, in bytes.
+        budget: usize,
+        /// Number of bytes of the heap that were already in use.
+        used: usize,
+    },
+}
+
+impl error::Error for ImageUploadError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            ImageUploadError::ImageCreationError(_) => "error while creating the image",
+            ImageUploadError::OomError(_) => "not enough memory available",
+            ImageUploadError::FenceWaitError(_) => "error while locking the staging buffer",
+            ImageUploadError::MipmapsGenerationNotSupported => {
+                "the format doesn't support the blits required to generate the mipmaps"
+            },
+            ImageUploadError::InvalidDataSize { .. } => {
+                "the size of the compressed data doesn't match the dimensions of the image"
+            },
+            ImageUploadError::BudgetExceeded { .. } => {
+                "allocating the memory of the staging buffer would exceed the budget of the heap"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            ImageUploadError::ImageCreationError(ref err) => Some(err),
+            ImageUploadError::OomError(ref err) => Some(err),
+            ImageUploadError::FenceWaitError(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for ImageUploadError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<ImageCreationError> for ImageUploadError {
+    #[inline]
+    fn from(err: ImageCreationError) -> ImageUploadError {
+        ImageUploadError::ImageCreationError(err)
+    }
+}
+
+impl From<OomError> for ImageUploadError {
+    #[inline]
+    fn from(err: OomError) -> ImageUploadError {
+        ImageUploadError::OomError(err)
+    }
+}
+
+impl From<MemoryPoolError> for ImageUploadError {
+    #[inline]
+    fn from(err: MemoryPoolError) -> ImageUploadError {
+        match err {
+            MemoryPoolError::OomError(err) => ImageUploadError::OomError(err),
+            MemoryPoolError::BudgetExceeded { heap, requested, budget, used } => {
+                ImageUploadError::BudgetExceeded {
+                    heap: heap,
+                    requested: requested,
+                    budget: budget,
+                    used: used,
+                }
+            },
+        }
+    }
+}
+
+impl From<FenceWaitError> for ImageUploadError {
+    #[inline]
+    fn from(err: FenceWaitError) -> ImageUploadError {
+        ImageUploadError::FenceWaitError(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use buffer::BufferUsage;
+    use buffer::CpuAccessibleBuffer;
+    use command_buffer::CommandBufferPool;
+    use command_buffer::MipmapGenerationError;
+    use command_buffer::PrimaryCommandBufferBuilder;
+    use command_buffer::submit;
+    use format::Format;
+    use format::R8G8B8A8Unorm;
+    use image::MipmapsCount;
+    use image::sys::Dimensions;
+    use super::ImmutableImage;
+
+    #[test]
+    fn upload_and_read_back() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let pixels = (0 .. 16u8).map(|n| [n, n * 2, n * 3, 255]).collect::<Vec<_>>();
+        let (image, _) = ImmutableImage::from_iter(pixels.iter().cloned(),
+                                                   Dimensions::Dim2d { width: 4, height: 4 },
+                                                   R8G8B8A8Unorm, MipmapsCount::One,
+                                                   &queue).unwrap();
+
+        let usage = BufferUsage { transfer_dest: true, .. BufferUsage::none() };
+        let dest = CpuAccessibleBuffer::<[[u8; 4]]>::array(&device, 16, &usage,
+                                                            Some(queue.family())).unwrap();
+
+        let pool = CommandBufferPool::new(&device, &queue.family());
+        let cb = PrimaryCommandBufferBuilder::new(&pool)
+                    .copy_color_image_to_buffer(&dest, &image, 0, 0 .. 1, [0, 0, 0], [4, 4, 1])
+                    .build();
+        submit(&cb, &queue).unwrap();
+
+        let content = dest.read(Duration::new(1, 0)).unwrap();
+        assert_eq!(content[5], [5, 10, 15, 255]);
+    }
+
+    #[test]
+    fn upload_with_mipmaps() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let pixels = (0 .. 16 * 16).map(|_| [255u8, 0, 0, 255]);
+        let result = ImmutableImage::from_iter(pixels, Dimensions::Dim2d { width: 16, height: 16 },
+                                               R8G8B8A8Unorm, MipmapsCount::Log2, &queue);
+
+        match result {
+            Ok((image, _)) => assert_eq!(image.mipmap_levels(), 5),
+            Err(super::ImageUploadError::MipmapsGenerationNotSupported) => (),
+            Err(err) => panic!("{:?}", err)
+        }
+    }
+
+    #[test]
+    fn log2_mipmaps_non_square() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let dimensions = Dimensions::Dim2d { width: 256, height: 16 };
+        let image = ImmutableImage::with_mipmaps(&device, dimensions, R8G8B8A8Unorm,
+                                                 MipmapsCount::Log2, Some(queue.family())).unwrap();
+        assert_eq!(image.mipmap_levels(), 9);
+    }
+
+    #[test]
+    fn upload_compressed() {
+        let (device, queue) = gfx_dev_and_queue!(texture_compression_bc);
+
+        // 4 blocks for the first level, then one block for each of the 3 remaining levels.
+        let data = (0 .. (4 + 3) * 8).map(|n| n as u8);
+        let (image, _) = ImmutableImage::from_iter(data, Dimensions::Dim2d { width: 8, height: 8 },
+                                                   Format::BC1_RGBAUnormBlock, MipmapsCount::Log2,
+                                                   &queue).unwrap();
+        assert_eq!(image.mipmap_levels(), 4);
+    }
+
+    #[test]
+    fn upload_compressed_wrong_size() {
+        let (device, queue) = gfx_dev_and_queue!(texture_compression_bc);
+
+        let data = (0 .. 4 * 8).map(|n| n as u8);
+        let result = ImmutableImage::from_iter(data, Dimensions::Dim2d { width: 8, height: 8 },
+                                               Format::BC1_RGBAUnormBlock, MipmapsCount::Log2,
+                                               &queue);
+
+        match result {
+            Err(super::ImageUploadError::InvalidDataSize { expected: 56, obtained: 32 }) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn generate_mipmaps() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let image = ImmutableImage::with_mipmaps(&device, Dimensions::Dim2d { width: 8, height: 8 },
+                                                 R8G8B8A8Unorm, MipmapsCount::Log2,
+                                                 Some(queue.family())).unwrap();
+        assert_eq!(image.mipmap_levels(), 4);
+
+        let source = CpuAccessibleBuffer::<[[u8; 4]]>::array(&device, 64,
+                                                              &BufferUsage::transfer_source(),
+                                                              Some(queue.family())).unwrap();
+        for texel in source.write(Duration::new(0, 0)).unwrap().iter_mut() {
+            *texel = [0, 255, 0, 255];
+        }
+        let usage = BufferUsage { transfer_dest: true, .. BufferUsage::none() };
+        let dest = CpuAccessibleBuffer::<[[u8; 4]]>::array(&device, 4, &usage,
+                                                            Some(queue.family())).unwrap();
+
+        let pool = CommandBufferPool::new(&device, &queue.family());
+        let cb = PrimaryCommandBufferBuilder::new(&pool)
+                    .copy_buffer_to_color_image(&source, &image, 0, 0 .. 1, [0, 0, 0], [8, 8, 1])
+                    .generate_mipmaps(&image).unwrap()
+                    .copy_color_image_to_buffer(&dest, &image, 2, 0 .. 1, [0, 0, 0], [2, 2, 1])
+                    .build();
+        submit(&cb, &queue).unwrap();
+
+        let content = dest.read(Duration::new(1, 0)).unwrap();
+        assert!(content.iter().all(|p| *p == [0, 255, 0, 255]));
+    }
+
+    #[test]
+    fn generate_mipmaps_single_level() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let image = ImmutableImage::new(&device, Dimensions::Dim2d { width: 8, height: 8 },
+                                        R8G8B8A8Unorm, Some(queue.family())).unwrap();
+
+        let pool = CommandBufferPool::new(&device, &queue.family());
+        match PrimaryCommandBufferBuilder::new(&pool).generate_mipmaps(&image) {
+            Err(MipmapGenerationError::NotEnoughMipmapLevels) => (),
+            _ => panic!()
+        }
+    }
+}
@@ -0,0 +1,360 @@
+// Copyright (c) 2016 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+//! Tracking of the layouts of the subresources of an image.
+//!
+//! Each mipmap level and each array layer of an image can be in a different layout. When
+//! submitting a pipeline barrier that transitions a subresource, the old layout must match the
+//! layout the subresource is really in, otherwise the behavior is undefined.
+//!
+//! A `LayoutTracker` keeps track of the layout of every subresource of an image and computes
+//! the transitions that are needed in order to put a range of subresources in a new layout.
+
+use std::cmp;
+use std::mem;
+use std::ops::Range;
+use smallvec::SmallVec;
+
+use image::sys::Layout;
+
+/// Tracks the current layout of each mipmap level and array layer of an image.
+#[derive(Debug, Clone)]
+pub struct LayoutTracker {
+    // Number of array layers of the image.
+    array_layers: u32,
+
+    // For each mipmap level, the list of ranges of array layers and their layout. The ranges are
+    // sorted, never overlap and cover all the array layers. Adjacent ranges always have a
+    // different layout.
+    levels: SmallVec<[SmallVec<[(Range<u32>, Layout); 2]>; 4]>,
+}
+
+/// A layout transition computed by a `LayoutTracker`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutTransition {
+    /// Mipmap levels affected by the transition.
+    pub mipmap_levels: Range<u32>,
+    /// Array layers affected by the transition.
+    pub array_layers: Range<u32>,
+    /// Layout the subresources are currently in.
+    pub from: Layout,
+    /// Layout the subresources must be transitioned to.
+    pub to: Layout,
+}
+
+impl LayoutTracker {
+    /// Builds a new tracker for an image with the given number of mipmap levels and array
+    /// layers, all of them being in the given layout.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `mipmap_levels` or `array_layers` is 0.
+    ///
+    pub fn new(mipmap_levels: u32, array_layers: u32, layout: Layout) -> LayoutTracker {
+        assert!(mipmap_levels >= 1);
+        assert!(array_layers >= 1);
+
+        LayoutTracker {
+            array_layers: array_layers,
+            levels: (0 .. mipmap_levels).map(|_| {
+                let mut level = SmallVec::new();
+                level.push((0 .. array_layers, layout));
+                level
+            }).collect(),
+        }
+    }
+
+    /// Returns the number of mipmap levels tracked by this tracker.
+    #[inline]
+    pub fn mipmap_levels(&self) -> u32 {
+        self.levels.len() as u32
+    }
+
+    /// Returns the number of array layers tracked by this tracker.
+    #[inline]
+    pub fn array_layers(&self) -> u32 {
+        self.array_layers
+    }
+
+    /// Returns the layout of the given subresources, or `None` if they are not all in the same
+    /// layout.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if a range is empty or out of range.
+    ///
+    pub fn current_layout(&self, mipmap_levels: Range<u32>, array_layers: Range<u32>)
+                          -> Option<Layout>
+    {
+        self.check_ranges(&mipmap_levels, &array_layers);
+
+        let mut result = None;
+
+        for level in mipmap_levels {
+            for &(ref range, layout) in self.levels[level as usize].iter() {
+                if range.end <= array_layers.start || range.start >= array_layers.end {
+                    continue;
+                }
+
+                match result {
+                    None => result = Some(layout),
+                    Some(l) if l == layout => (),
+                    Some(_) => return None,
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Marks the given subresources as being in `new_layout` and returns the list of transitions
+    /// that must be performed to do so.
+    ///
+    /// Subresources that are already in `new_layout` don't produce any transition. Transitions
+    /// that cover consecutive mipmap levels with the same array layers and the same old layout
+    /// are merged together.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if a range is empty or out of range.
+    ///
+    pub fn transition(&mut self, mipmap_levels: Range<u32>, array_layers: Range<u32>,
+                      new_layout: Layout) -> Vec<LayoutTransition>
+    {
+        self.check_ranges(&mipmap_levels, &array_layers);
+
+        let mut transitions: Vec<LayoutTransition> = Vec::new();
+
+        for level in mipmap_levels {
+            for &(ref range, layout) in self.levels[level as usize].iter() {
+                if layout == new_layout {
+                    continue;
+                }
+
+                let start = cmp::max(range.start, array_layers.start);
+                let end = cmp::min(range.end, array_layers.end);
+                if start >= end {
+                    continue;
+                }
+
+                // Try to extend a transition of the previous mipmap level.
+                if let Some(t) = transitions.iter_mut().find(|t| {
+                    t.mipmap_levels.end == level && t.array_layers == (start .. end) &&
+                    t.from == layout
+                }) {
+                    t.mipmap_levels.end += 1;
+                    continue;
+                }
+
+                transitions.push(LayoutTransition {
+                    mipmap_levels: level .. level + 1,
+                    array_layers: start .. end,
+                    from: layout,
+                    to: new_layout,
+                });
+            }
+
+            self.set(level, array_layers.clone(), new_layout);
+        }
+
+        transitions
+    }
+
+    /// Marks the given subresources as being in `layout` without producing any transition.
+    ///
+    /// This is meant to be used when the subresources have been transitioned outside of the
+    /// control of this tracker, for example by the presentation engine for swapchain images.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if a range is empty or out of range.
+    ///
+    pub fn force_layout(&mut self, mipmap_levels: Range<u32>, array_layers: Range<u32>,
+                        layout: Layout)
+    {
+        self.check_ranges(&mipmap_levels, &array_layers);
+
+        for level in mipmap_levels {
+            self.set(level, array_layers.clone(), layout);
+        }
+    }
+
+    // Panics if the ranges are empty or out of range.
+    fn check_ranges(&self, mipmap_levels: &Range<u32>, array_layers: &Range<u32>) {
+        assert!(mipmap_levels.start < mipmap_levels.end);
+        assert!(mipmap_levels.end <= self.mipmap_levels());
+        assert!(array_layers.start < array_layers.end);
+        assert!(array_layers.end <= self.array_layers);
+    }
+
+    // Sets the layout of some array layers of a mipmap level, splitting the existing ranges and
+    // merging the adjacent ones that end up in the same layout.
+    fn set(&mut self, level: u32, array_layers: Range<u32>, layout: Layout) {
+        let old = mem::replace(&mut self.levels[level as usize], SmallVec::new());
+        let new = &mut self.levels[level as usize];
+
+        // Adds a range at the end of the list, merging it with the last one if possible.
+        fn push(list: &mut SmallVec<[(Range<u32>, Layout); 2]>, range: Range<u32>,
+                layout: Layout)
+        {
+            if let Some(last) = list.last_mut() {
+                if last.1 == layout {
+                    debug_assert_eq!(last.0.end, range.start);
+                    last.0.end = range.end;
+                    return;
+                }
+            }
+
+            list.push((range, layout));
+        }
+
+        for &(ref range, l) in old.iter() {
+            if range.start < array_layers.start {
+                push(new, range.start .. cmp::min(range.end, array_layers.start), l);
+            }
+        }
+
+        push(new, array_layers.clone(), layout);
+
+        for &(ref range, l) in old.iter() {
+            if range.end > array_layers.end {
+                push(new, cmp::max(range.start, array_layers.end) .. range.end, l);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::sys::Layout;
+    use super::LayoutTracker;
+    use super::LayoutTransition;
+
+    #[test]
+    fn initial_state() {
+        let tracker = LayoutTracker::new(4, 6, Layout::Undefined);
+        assert_eq!(tracker.mipmap_levels(), 4);
+        assert_eq!(tracker.array_layers(), 6);
+        assert_eq!(tracker.current_layout(0 .. 4, 0 .. 6), Some(Layout::Undefined));
+    }
+
+    #[test]
+    fn whole_transition() {
+        let mut tracker = LayoutTracker::new(3, 2, Layout::Undefined);
+
+        let transitions = tracker.transition(0 .. 3, 0 .. 2, Layout::General);
+        assert_eq!(transitions, vec![LayoutTransition {
+            mipmap_levels: 0 .. 3,
+            array_layers: 0 .. 2,
+            from: Layout::Undefined,
+            to: Layout::General,
+        }]);
+
+        assert_eq!(tracker.current_layout(0 .. 3, 0 .. 2), Some(Layout::General));
+        assert!(tracker.transition(0 .. 3, 0 .. 2, Layout::General).is_empty());
+    }
+
+    #[test]
+    fn split_layers() {
+        let mut tracker = LayoutTracker::new(1, 8, Layout::Undefined);
+
+        tracker.transition(0 .. 1, 2 .. 5, Layout::TransferDstOptimal);
+        assert_eq!(tracker.current_layout(0 .. 1, 0 .. 2), Some(Layout::Undefined));
+        assert_eq!(tracker.current_layout(0 .. 1, 2 .. 5), Some(Layout::TransferDstOptimal));
+        assert_eq!(tracker.current_layout(0 .. 1, 5 .. 8), Some(Layout::Undefined));
+        assert_eq!(tracker.current_layout(0 .. 1, 1 .. 3), None);
+        assert_eq!(tracker.current_layout(0 .. 1, 0 .. 8), None);
+    }
+
+    #[test]
+    fn partial_overlap() {
+        let mut tracker = LayoutTracker::new(1, 8, Layout::Undefined);
+        tracker.transition(0 .. 1, 0 .. 4, Layout::TransferDstOptimal);
+
+        // Layers 0 to 3 are transitioned from `TransferDstOptimal`, and 4 to 5 from `Undefined`.
+        let transitions = tracker.transition(0 .. 1, 2 .. 6, Layout::ShaderReadOnlyOptimal);
+        assert_eq!(transitions, vec![
+            LayoutTransition {
+                mipmap_levels: 0 .. 1,
+                array_layers: 2 .. 4,
+                from: Layout::TransferDstOptimal,
+                to: Layout::ShaderReadOnlyOptimal,
+            },
+            LayoutTransition {
+                mipmap_levels: 0 .. 1,
+                array_layers: 4 .. 6,
+                from: Layout::Undefined,
+                to: Layout::ShaderReadOnlyOptimal,
+            },
+        ]);
+
+        assert_eq!(tracker.current_layout(0 .. 1, 0 .. 2), Some(Layout::TransferDstOptimal));
+        assert_eq!(tracker.current_layout(0 .. 1, 2 .. 6), Some(Layout::ShaderReadOnlyOptimal));
+        assert_eq!(tracker.current_layout(0 .. 1, 6 .. 8), Some(Layout::Undefined));
+    }
+
+    #[test]
+    fn merge_adjacent() {
+        let mut tracker = LayoutTracker::new(1, 8, Layout::Undefined);
+        tracker.transition(0 .. 1, 0 .. 3, Layout::General);
+        tracker.transition(0 .. 1, 5 .. 8, Layout::General);
+        tracker.transition(0 .. 1, 3 .. 5, Layout::General);
+
+        assert_eq!(tracker.current_layout(0 .. 1, 0 .. 8), Some(Layout::General));
+        assert_eq!(tracker.levels[0].len(), 1);
+    }
+
+    #[test]
+    fn per_mipmap_level() {
+        let mut tracker = LayoutTracker::new(4, 1, Layout::TransferDstOptimal);
+
+        // Typical mipmaps generation: each level is read after having been written.
+        for level in 1 .. 4 {
+            let transitions = tracker.transition(level - 1 .. level, 0 .. 1,
+                                                 Layout::TransferSrcOptimal);
+            assert_eq!(transitions.len(), 1);
+            assert_eq!(transitions[0].from, Layout::TransferDstOptimal);
+        }
+
+        assert_eq!(tracker.current_layout(0 .. 3, 0 .. 1), Some(Layout::TransferSrcOptimal));
+        assert_eq!(tracker.current_layout(3 .. 4, 0 .. 1), Some(Layout::TransferDstOptimal));
+
+        // The last transition contains two different old layouts.
+        let transitions = tracker.transition(0 .. 4, 0 .. 1, Layout::ShaderReadOnlyOptimal);
+        assert_eq!(transitions, vec![
+            LayoutTransition {
+                mipmap_levels: 0 .. 3,
+                array_layers: 0 .. 1,
+                from: Layout::TransferSrcOptimal,
+                to: Layout::ShaderReadOnlyOptimal,
+            },
+            LayoutTransition {
+                mipmap_levels: 3 .. 4,
+                array_layers: 0 .. 1,
+                from: Layout::TransferDstOptimal,
+                to: Layout::ShaderReadOnlyOptimal,
+            },
+        ]);
+    }
+
+    #[test]
+    fn force_layout() {
+        let mut tracker = LayoutTracker::new(1, 1, Layout::Undefined);
+        tracker.force_layout(0 .. 1, 0 .. 1, Layout::PresentSrc);
+        assert_eq!(tracker.current_layout(0 .. 1, 0 .. 1), Some(Layout::PresentSrc));
+        assert!(tracker.transition(0 .. 1, 0 .. 1, Layout::PresentSrc).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn out_of_range() {
+        let tracker = LayoutTracker::new(2, 2, Layout::Undefined);
+        tracker.current_layout(0 .. 3, 0 .. 1);
+    }
+}
@@ -21,6 +21,7 @@
 //! - An `AttachmentImage` can be used when you want to draw to an image.
 //! - An `ImmutableImage` stores data which never need be changed after the initial upload,
 //!   like a texture.
+//! - A `StorageImage` can be read and written by shaders, for example in a compute pipeline.
 //! 
 //! If are a beginner, you are strongly encouraged to use one of these wrappers.
 //! 
@@ -29,17 +30,42 @@
 //! To be written.
 //!
 
+pub use self::layout::LayoutTracker;
+pub use self::layout::LayoutTransition;
+pub use self::sys::BufferImageCopyError;
+pub use self::sys::ImageAspect;
+pub use self::sys::ImageAspects;
+pub use self::sys::ImageCreateFlags;
 pub use self::sys::ImageCreationError;
+pub use self::sys::ImageFormatProperties;
+pub use self::sys::ImageMemoryBindError;
+pub use self::sys::ImageType;
+pub use self::sys::ImageViewCreationError;
 pub use self::sys::Layout;
+pub use self::sys::SampleCount;
+pub use self::sys::SparseImageMemoryRequirements;
+pub use self::sys::SparseLevel;
+pub use self::sys::SubresourceLayout;
+pub use self::sys::SubresourceLayoutError;
+pub use self::sys::SupportedSampleCounts;
 pub use self::sys::Usage;
+pub use self::sys::Usage as ImageUsage;
+pub use self::sys::ViewType;
+pub use self::traits::AccessTracker;
 pub use self::traits::Image;
 pub use self::traits::ImageView;
+pub use self::view::CustomImageView;
+
+use vk;
 
 pub mod attachment;
 pub mod immutable;
+pub mod layout;
+pub mod storage;
 pub mod swapchain;
 pub mod sys;
 pub mod traits;
+pub mod view;
 
 /// Specifies how many mipmaps must be allocated.
 ///
@@ -67,6 +93,10 @@ impl From<u32> for MipmapsCount {
     }
 }
 
+/// Describes how the components of an image view are mapped to the components of the image.
+///
+/// The default value is the identity mapping. Note that views used as framebuffer attachments
+/// must use the identity mapping.
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 pub struct Swizzle {
     pub r: ComponentSwizzle,
@@ -75,15 +105,35 @@ pub struct Swizzle {
     pub a: ComponentSwizzle,
 }
 
+impl Swizzle {
+    /// Returns true if the swizzle doesn't modify any component.
+    #[inline]
+    pub fn is_identity(&self) -> bool {
+        (self.r == ComponentSwizzle::Identity || self.r == ComponentSwizzle::Red) &&
+        (self.g == ComponentSwizzle::Identity || self.g == ComponentSwizzle::Green) &&
+        (self.b == ComponentSwizzle::Identity || self.b == ComponentSwizzle::Blue) &&
+        (self.a == ComponentSwizzle::Identity || self.a == ComponentSwizzle::Alpha)
+    }
+}
+
+/// Value of a single component of an image view.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u32)]
 pub enum ComponentSwizzle {
-    Identity,
-    Zero,
-    One,
-    Red,
-    Green,
-    Blue,
-    Alpha,
+    /// The component keeps its value.
+    Identity = vk::COMPONENT_SWIZZLE_IDENTITY,
+    /// The component is always 0.
+    Zero = vk::COMPONENT_SWIZZLE_ZERO,
+    /// The component is always 1.
+    One = vk::COMPONENT_SWIZZLE_ONE,
+    /// The component takes the value of the red component of the image.
+    Red = vk::COMPONENT_SWIZZLE_R,
+    /// The component takes the value of the green component of the image.
+    Green = vk::COMPONENT_SWIZZLE_G,
+    /// The component takes the value of the blue component of the image.
+    Blue = vk::COMPONENT_SWIZZLE_B,
+    /// The component takes the value of the alpha component of the image.
+    Alpha = vk::COMPONENT_SWIZZLE_A,
 }
 
 impl Default for ComponentSwizzle {
@@ -0,0 +1,382 @@
+// Copyright (c) 2016 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+//! General-purpose image that can be read and written by shaders.
+//!
+//! This module declares the `StorageImage` type. It is a safe wrapper around `UnsafeImage`
+//! and implements all the relevant image traits.
+//!
+//! A storage image can have any number of dimensions and array layers, but only one mipmap. It
+//! is always in the `General` layout, which means that it can be bound as a storage image in a
+//! descriptor set, and be used as the source or the destination of a transfer operation.
+//!
+//! Trying to use a format that the implementation doesn't support for storage images will result
+//! in an error being returned when creating the image.
+
+use std::mem;
+use std::ops::Range;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::Weak;
+use smallvec::SmallVec;
+
+use command_buffer::Submission;
+use device::Device;
+use format::ClearValue;
+use format::FormatDesc;
+use image::layout::LayoutTracker;
+use image::sys::Dimensions;
+use image::sys::ImageCreateFlags;
+use image::sys::ImageCreationError;
+use image::sys::Layout;
+use image::sys::UnsafeImage;
+use image::sys::UnsafeImageView;
+use image::sys::Usage;
+use image::traits::AccessRange;
+use image::traits::GpuAccessResult;
+use image::traits::Image;
+use image::traits::ImageClearValue;
+use image::traits::ImageContent;
+use image::traits::ImageView;
+use image::traits::Transition;
+use instance::QueueFamily;
+use memory::MemoryTypePreference;
+use memory::pool::AllocLayout;
+use memory::pool::MemoryPool;
+use memory::pool::MemoryPoolAlloc;
+use memory::pool::StdMemoryPool;
+use sync::Sharing;
+
+/// General-purpose image in device memory. Can be used for any usage, but will be slower than a
+/// specialized image.
+#[derive(Debug)]
+pub struct StorageImage<F, A = StdMemoryPool> where A: MemoryPool {
+    // Inner implementation.
+    image: UnsafeImage,
+
+    // We maintain a view of the whole image.
+    view: UnsafeImageView,
+
+    // Memory used to back the image.
+    memory: A::Alloc,
+
+    // Format.
+    format: F,
+
+    // Queue families allowed to access this image.
+    queue_families: SmallVec<[u32; 4]>,
+
+    // Additional info behind a mutex.
+    guarded: Mutex<Guarded>,
+}
+
+#[derive(Debug)]
+struct Guarded {
+    // Layout of the image, which starts in the undefined layout.
+    layouts: LayoutTracker,
+
+    // The latest submission that used the image. Used for synchronization purposes.
+    latest_submission: Option<Weak<Submission>>,    // TODO: can use `Weak::new()` once it's stabilized
+}
+
+impl<F> StorageImage<F> {
+    /// Creates a new image with the given dimensions and format.
+    ///
+    /// Returns an error if the dimensions are too large or if the backend doesn't support this
+    /// format as a storage image.
+    #[inline]
+    pub fn new<'a, I>(device: &Arc<Device>, dimensions: Dimensions, format: F, queue_families: I)
+                      -> Result<Arc<StorageImage<F>>, ImageCreationError>
+        where F: FormatDesc, I: IntoIterator<Item = QueueFamily<'a>>
+    {
+        let usage = Usage {
+            transfer_source: true,
+            transfer_dest: true,
+            storage: true,
+            .. Usage::none()
+        };
+
+        StorageImage::with_usage(device, dimensions, format, usage, queue_families)
+    }
+
+    /// Same as `new`, but with a specific usage.
+    ///
+    /// The `storage` usage is always enabled.
+    pub fn with_usage<'a, I>(device: &Arc<Device>, dimensions: Dimensions, format: F,
+                             usage: Usage, queue_families: I)
+                             -> Result<Arc<StorageImage<F>>, ImageCreationError>
+        where F: FormatDesc, I: IntoIterator<Item = QueueFamily<'a>>
+    {
+        let usage = Usage {
+            storage: true,
+            .. usage
+        };
+
+        let queue_families = queue_families.into_iter().map(|f| f.id())
+                                           .collect::<SmallVec<[u32; 4]>>();
+
+        let (image, mem_reqs) = unsafe {
+            let sharing = if queue_families.len() >= 2 {
+                Sharing::Concurrent(queue_families.iter().cloned())
+            } else {
+                Sharing::Exclusive
+            };
+
+            try!(UnsafeImage::new_checked(device, &usage, format.format(), dimensions, 1, 1,
+                                          sharing, false, false, ImageCreateFlags::none()))
+        };
+
+        let pool = device.standard_pool();
+        let mem_ty = pool.choose_memory_type(&mem_reqs, MemoryTypePreference::DeviceLocal)
+                         .unwrap();
+
+        let mem = try!(MemoryPool::alloc(&pool, mem_ty,
+                                         mem_reqs.size, mem_reqs.alignment, AllocLayout::Optimal));
+        debug_assert!((mem.offset() % mem_reqs.alignment) == 0);
+        unsafe { try!(image.bind_allocated_memory(mem.memory(), mem.offset())); }
+
+        let view = unsafe { try!(UnsafeImageView::whole(&image)) };
+        let layouts = LayoutTracker::new(image.mipmap_levels(), image.dimensions().array_layers(),
+                                         Layout::Undefined);
+
+        Ok(Arc::new(StorageImage {
+            image: image,
+            view: view,
+            memory: mem,
+            format: format,
+            queue_families: queue_families,
+            guarded: Mutex::new(Guarded {
+                layouts: layouts,
+                latest_submission: None,
+            }),
+        }))
+    }
+}
+
+impl<F, A> StorageImage<F, A> where A: MemoryPool {
+    /// Returns the dimensions of the image.
+    #[inline]
+    pub fn dimensions(&self) -> Dimensions {
+        self.image.dimensions()
+    }
+
+    /// Returns the queue families this image can be used on.
+    // TODO: use a custom iterator
+    #[inline]
+    pub fn queue_families(&self) -> Vec<QueueFamily> {
+        self.queue_families.iter().map(|&num| {
+            self.image.device().physical_device().queue_family_by_id(num).unwrap()
+        }).collect()
+    }
+}
+
+unsafe impl<F, A> Image for StorageImage<F, A> where F: 'static + Send + Sync, A: MemoryPool {
+    #[inline]
+    fn inner_image(&self) -> &UnsafeImage {
+        &self.image
+    }
+
+    #[inline]
+    fn blocks(&self, _: Range<u32>, _: Range<u32>) -> Vec<(u32, u32)> {
+        vec![(0, 0)]
+    }
+
+    #[inline]
+    fn block_mipmap_levels_range(&self, _: (u32, u32)) -> Range<u32> {
+        0 .. 1
+    }
+
+    #[inline]
+    fn block_array_layers_range(&self, _: (u32, u32)) -> Range<u32> {
+        0 .. self.image.dimensions().array_layers()
+    }
+
+    #[inline]
+    fn initial_layout(&self, _: (u32, u32), _: Layout) -> (Layout, bool, bool) {
+        (Layout::General, false, false)
+    }
+
+    #[inline]
+    fn final_layout(&self, _: (u32, u32), _: Layout) -> (Layout, bool, bool) {
+        (Layout::General, false, false)
+    }
+
+    #[inline]
+    fn needs_fence(&self, _: &mut Iterator<Item = AccessRange>) -> Option<bool> {
+        Some(false)
+    }
+
+    unsafe fn gpu_access(&self, _: &mut Iterator<Item = AccessRange>,
+                         submission: &Arc<Submission>) -> GpuAccessResult
+    {
+        if !self.queue_families.is_empty() {
+            let family = submission.queue().family().id();
+            assert!(self.queue_families.contains(&family),
+                    "The storage image can't be used by queue family {}", family);
+        }
+
+        let mut guarded = self.guarded.lock().unwrap();
+
+        // Since shaders can both read and write the image, every submission depends on the
+        // previous one.
+        let dependency = mem::replace(&mut guarded.latest_submission,
+                                      Some(Arc::downgrade(submission)));
+        let dependency = dependency.and_then(|d| d.upgrade());
+
+        let levels = self.image.mipmap_levels();
+        let layers = self.image.dimensions().array_layers();
+        let transition = guarded.layouts.transition(0 .. levels, 0 .. layers, Layout::General)
+                                .into_iter()
+                                .map(|t| Transition { block: (0, 0), from: t.from, to: t.to })
+                                .collect();
+
+        GpuAccessResult {
+            dependencies: if let Some(dependency) = dependency {
+                vec![dependency]
+            } else {
+                vec![]
+            },
+            additional_wait_semaphore: None,
+            additional_signal_semaphore: None,
+            before_transitions: transition,
+            after_transitions: vec![],
+        }
+    }
+}
+
+unsafe impl<F, A> ImageClearValue<F::ClearValue> for StorageImage<F, A>
+    where F: FormatDesc + 'static + Send + Sync, A: MemoryPool
+{
+    #[inline]
+    fn decode(&self, value: F::ClearValue) -> Option<ClearValue> {
+        self.format.try_decode_clear_value(value)
+    }
+}
+
+unsafe impl<P, F, A> ImageContent<P> for StorageImage<F, A>
+    where F: 'static + Send + Sync, A: MemoryPool
+{
+    #[inline]
+    fn matches_format(&self) -> bool {
+        Image::format(self).accepts_pixel_size(mem::size_of::<P>())
+    }
+}
+
+unsafe impl<F, A> ImageView for StorageImage<F, A>
+    where F: 'static + Send + Sync, A: MemoryPool
+{
+    #[inline]
+    fn parent(&self) -> &Image {
+        self
+    }
+
+    #[inline]
+    fn parent_arc(me: &Arc<Self>) -> Arc<Image> where Self: Sized {
+        me.clone() as Arc<_>
+    }
+
+    #[inline]
+    fn blocks(&self) -> Vec<(u32, u32)> {
+        vec![(0, 0)]
+    }
+
+    #[inline]
+    fn inner_view(&self) -> &UnsafeImageView {
+        &self.view
+    }
+
+    #[inline]
+    fn descriptor_set_storage_image_layout(&self) -> Layout {
+        Layout::General
+    }
+
+    #[inline]
+    fn descriptor_set_combined_image_sampler_layout(&self) -> Layout {
+        Layout::General
+    }
+
+    #[inline]
+    fn descriptor_set_sampled_image_layout(&self) -> Layout {
+        Layout::General
+    }
+
+    #[inline]
+    fn descriptor_set_input_attachment_layout(&self) -> Layout {
+        Layout::General
+    }
+
+    #[inline]
+    fn identity_swizzle(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use buffer::BufferUsage;
+    use buffer::CpuAccessibleBuffer;
+    use command_buffer::CommandBufferPool;
+    use command_buffer::PrimaryCommandBufferBuilder;
+    use command_buffer::submit;
+    use format::R8G8B8A8Unorm;
+    use image::sys::Dimensions;
+    use super::StorageImage;
+
+    #[test]
+    fn create() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let _img = StorageImage::new(&device, Dimensions::Dim2d { width: 32, height: 32 },
+                                     R8G8B8A8Unorm, Some(queue.family())).unwrap();
+    }
+
+    #[test]
+    fn clear_and_read_back() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let image = StorageImage::new(&device, Dimensions::Dim2d { width: 4, height: 4 },
+                                      R8G8B8A8Unorm, Some(queue.family())).unwrap();
+
+        let usage = BufferUsage { transfer_dest: true, .. BufferUsage::none() };
+        let dest = CpuAccessibleBuffer::<[[u8; 4]]>::array(&device, 16, &usage,
+                                                            Some(queue.family())).unwrap();
+
+        let pool = CommandBufferPool::new(&device, &queue.family());
+        let cb = PrimaryCommandBufferBuilder::new(&pool)
+                    .clear_color_image(&image, [0.0, 1.0, 0.0, 1.0])
+                    .copy_color_image_to_buffer(&dest, &image, 0, 0 .. 1, [0, 0, 0], [4, 4, 1])
+                    .build();
+        submit(&cb, &queue).unwrap();
+
+        let content = dest.read(Duration::new(1, 0)).unwrap();
+        for texel in content.iter() {
+            assert_eq!(*texel, [0, 255, 0, 255]);
+        }
+    }
+
+    #[test]
+    fn create_all_dimensions() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let dimensions = [
+            Dimensions::Dim1d { width: 32 },
+            Dimensions::Dim1dArray { width: 32, array_layers: 4 },
+            Dimensions::Dim2d { width: 32, height: 32 },
+            Dimensions::Dim2dArray { width: 32, height: 32, array_layers: 4 },
+            Dimensions::Dim3d { width: 16, height: 16, depth: 16 },
+        ];
+
+        for &dims in dimensions.iter() {
+            let img = StorageImage::new(&device, dims, R8G8B8A8Unorm,
+                                        Some(queue.family())).unwrap();
+            assert_eq!(img.dimensions(), dims);
+        }
+    }
+}
@@ -14,10 +14,13 @@ use std::sync::Mutex;
 use std::sync::Weak;
 
 use command_buffer::Submission;
+use format::ClearValue;
 use format::Format;
+use format::FormatDesc;
 use image::traits::AccessRange;
 use image::traits::GpuAccessResult;
 use image::traits::Image;
+use image::traits::ImageClearValue;
 use image::traits::ImageContent;
 use image::traits::ImageView;
 use image::traits::Transition;
@@ -49,7 +52,7 @@ impl SwapchainImage {
     pub unsafe fn from_raw(image: UnsafeImage, format: Format, swapchain: &Arc<Swapchain>, id: u32)
                            -> Result<Arc<SwapchainImage>, OomError>
     {
-        let view = try!(UnsafeImageView::raw(&image, 0 .. 1, 0 .. 1));
+        let view = try!(UnsafeImageView::whole(&image));
 
         Ok(Arc::new(SwapchainImage {
             image: image,
@@ -124,9 +127,18 @@ unsafe impl Image for SwapchainImage {
         let dependency = mem::replace(&mut guarded.latest_submission, Some(Arc::downgrade(submission)));
         let dependency = dependency.and_then(|d| d.upgrade());
 
-        // TODO: use try!()? - Mixthos
-        let signal = Semaphore::new(submission.queue().device());
-        let wait = self.swapchain.image_semaphore(self.id, signal.clone()).expect("Try to render to a swapchain image that was not acquired first");
+        // When the image was acquired with `acquire_next_image_with_semaphore`, the user
+        // synchronizes the accesses to the image with the presentation engine.
+        let (wait, signal) = if self.swapchain.is_externally_synchronized(self.id) {
+            (None, None)
+        } else {
+            // TODO: use try!()? - Mixthos
+            let signal = Semaphore::new(submission.queue().device());
+            let wait = self.swapchain.image_semaphore(self.id, signal.clone())
+                                     .expect("Try to render to a swapchain image that was not \
+                                              acquired first");
+            (Some(wait), Some(signal))
+        };
 
         if guarded.present_layout {
             return GpuAccessResult {
@@ -135,8 +147,8 @@ unsafe impl Image for SwapchainImage {
                 } else {
                     vec![]
                 },
-                additional_wait_semaphore: Some(wait),
-                additional_signal_semaphore: Some(signal),
+                additional_wait_semaphore: wait,
+                additional_signal_semaphore: signal,
                 before_transitions: vec![],
                 after_transitions: vec![],
             };
@@ -150,8 +162,8 @@ unsafe impl Image for SwapchainImage {
             } else {
                 vec![]
             },
-            additional_wait_semaphore: Some(wait),
-            additional_signal_semaphore: Some(signal),
+            additional_wait_semaphore: wait,
+            additional_signal_semaphore: signal,
             before_transitions: vec![Transition {
                 block: (0, 0),
                 from: Layout::Undefined,
@@ -162,10 +174,17 @@ unsafe impl Image for SwapchainImage {
     }
 }
 
+unsafe impl<T> ImageClearValue<T> for SwapchainImage where T: Into<ClearValue> {
+    #[inline]
+    fn decode(&self, value: T) -> Option<ClearValue> {
+        self.format.try_decode_clear_value(value.into())
+    }
+}
+
 unsafe impl<P> ImageContent<P> for SwapchainImage {
     #[inline]
     fn matches_format(&self) -> bool {
-        true        // FIXME:
+        Image::format(self).accepts_pixel_size(mem::size_of::<P>())
     }
 }
 
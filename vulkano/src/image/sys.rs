@@ -19,14 +19,20 @@ use std::mem;
 use std::ops::Range;
 use std::ptr;
 use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
 use smallvec::SmallVec;
 
 use device::Device;
 use format::Format;
+use format::FormatFeatures;
 use format::FormatTy;
 use image::MipmapsCount;
+use image::Swizzle;
+use instance::PhysicalDevice;
 use memory::DeviceMemory;
 use memory::MemoryRequirements;
+use memory::pool::MemoryPoolError;
 use sync::Sharing;
 
 use Error;
@@ -43,7 +49,7 @@ use vk;
 /// This type is not just unsafe but very unsafe. Don't use it directly.
 ///
 /// - You must manually bind memory to the image with `bind_memory`. The memory must respect the
-///   requirements returned by `new`.
+///   requirements returned by `memory_requirements`.
 /// - The memory that you bind to the image must be manually kept alive.
 /// - The queue family ownership must be manually enforced.
 /// - The usage must be manually enforced.
@@ -60,8 +66,26 @@ pub struct UnsafeImage {
     samples: u32,
     mipmaps: u32,
 
+    // True if the image was created with the cube-compatible flag.
+    cube_compatible: bool,
+
+    // True if the image was created with the mutable format flag.
+    mutable_format: bool,
+
+    // Sparse flags the image was created with.
+    sparse: SparseLevel,
+
+    // True if the image was created with the linear tiling.
+    linear_tiling: bool,
+
     // Features that are supported for this particular format.
-    format_features: vk::FormatFeatureFlagBits,
+    format_features: FormatFeatures,
+
+    // Memory requirements of the image, queried at creation.
+    memory_requirements: MemoryRequirements,
+
+    // True if memory has been bound to the image.
+    memory_bound: AtomicBool,
 
     // `vkDestroyImage` is called only if `needs_destruction` is true.
     needs_destruction: bool,
@@ -74,13 +98,18 @@ impl UnsafeImage {
     ///
     /// - Panicks if one of the dimensions is 0.
     /// - Panicks if the number of mipmaps is 0.
-    /// - Panicks if the number of samples is 0.
+    ///
+    /// See the documentation of `ImageCreateFlags` for the meaning of `flags`.
+    ///
+    /// The dimensions, number of mipmaps and layers and number of samples are not checked
+    /// against the capabilities that the physical device reports for this combination of
+    /// format, type, tiling, usage and flags. Use `new_checked` for that.
     ///
     #[inline]
     pub unsafe fn new<'a, Mi, I>(device: &Arc<Device>, usage: &Usage, format: Format,
                                  dimensions: Dimensions, num_samples: u32, mipmaps: Mi,
                                  sharing: Sharing<I>, linear_tiling: bool,
-                                 preinitialized_layout: bool)
+                                 preinitialized_layout: bool, flags: ImageCreateFlags)
                                  -> Result<(UnsafeImage, MemoryRequirements), ImageCreationError>
         where Mi: Into<MipmapsCount>, I: Iterator<Item = u32>
     {
@@ -90,51 +119,112 @@ impl UnsafeImage {
         };
 
         UnsafeImage::new_impl(device, usage, format, dimensions, num_samples, mipmaps.into(),
-                              sharing, linear_tiling, preinitialized_layout)
+                              sharing, linear_tiling, preinitialized_layout, flags,
+                              SparseLevel::none(), false)
+    }
+
+    /// Same as `new`, except that the parameters are first checked against the capabilities
+    /// that the physical device reports for this exact combination of format, type, tiling,
+    /// usage and flags. An error is returned if they are exceeded.
+    ///
+    /// This requires querying the physical device, which `new` doesn't do.
+    #[inline]
+    pub unsafe fn new_checked<'a, Mi, I>(device: &Arc<Device>, usage: &Usage, format: Format,
+                                         dimensions: Dimensions, num_samples: u32, mipmaps: Mi,
+                                         sharing: Sharing<I>, linear_tiling: bool,
+                                         preinitialized_layout: bool,
+                                         flags: ImageCreateFlags)
+                                         -> Result<(UnsafeImage, MemoryRequirements),
+                                                   ImageCreationError>
+        where Mi: Into<MipmapsCount>, I: Iterator<Item = u32>
+    {
+        let sharing = match sharing {
+            Sharing::Exclusive => (vk::SHARING_MODE_EXCLUSIVE, SmallVec::<[u32; 8]>::new()),
+            Sharing::Concurrent(ids) => (vk::SHARING_MODE_CONCURRENT, ids.collect()),
+        };
+
+        UnsafeImage::new_impl(device, usage, format, dimensions, num_samples, mipmaps.into(),
+                              sharing, linear_tiling, preinitialized_layout, flags,
+                              SparseLevel::none(), true)
+    }
+
+    /// Creates a new sparse image.
+    ///
+    /// Contrary to `new`, no memory must be bound to the image with `bind_memory`. Instead the
+    /// memory must be bound with `Queue::bind_sparse`. If `sparse.sparse_residency` is true,
+    /// the layout of the image in memory can be queried with `sparse_memory_requirements`.
+    ///
+    /// Sparse images always use the optimal tiling.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if `sparse.sparse` is false.
+    ///
+    #[inline]
+    pub unsafe fn new_sparse<'a, Mi, I>(device: &Arc<Device>, usage: &Usage, format: Format,
+                                        dimensions: Dimensions, num_samples: u32, mipmaps: Mi,
+                                        sharing: Sharing<I>, sparse: SparseLevel,
+                                        flags: ImageCreateFlags)
+                                        -> Result<(UnsafeImage, MemoryRequirements),
+                                                  ImageCreationError>
+        where Mi: Into<MipmapsCount>, I: Iterator<Item = u32>
+    {
+        assert!(sparse.sparse, "Can't create a sparse image without enabling sparse binding");
+
+        let sharing = match sharing {
+            Sharing::Exclusive => (vk::SHARING_MODE_EXCLUSIVE, SmallVec::<[u32; 8]>::new()),
+            Sharing::Concurrent(ids) => (vk::SHARING_MODE_CONCURRENT, ids.collect()),
+        };
+
+        UnsafeImage::new_impl(device, usage, format, dimensions, num_samples, mipmaps.into(),
+                              sharing, false, false, flags, sparse, false)
     }
 
     // Non-templated version to avoid inlining and improve compile times.
     unsafe fn new_impl(device: &Arc<Device>, usage: &Usage, format: Format,
                        dimensions: Dimensions, num_samples: u32, mipmaps: MipmapsCount,
                        (sh_mode, sh_indices): (vk::SharingMode, SmallVec<[u32; 8]>),
-                       linear_tiling: bool, preinitialized_layout: bool)
+                       linear_tiling: bool, preinitialized_layout: bool, flags: ImageCreateFlags,
+                       sparse: SparseLevel, check_properties: bool)
                        -> Result<(UnsafeImage, MemoryRequirements), ImageCreationError>
     {
         // TODO: doesn't check that the proper features are enabled
 
+        let cube_compatible = flags.cube_compatible;
+        let mutable_format = flags.mutable_format;
+
         let vk = device.pointers();
         let vk_i = device.instance().pointers();
 
+        // Compressed formats require the corresponding feature to be enabled.
+        if !format.features_enabled(device.enabled_features()) {
+            return Err(ImageCreationError::TextureCompressionFeatureNotEnabled);
+        }
+
         // Checking if image usage conforms to what is supported.
         let format_features = {
-            let physical_device = device.physical_device().internal_object();
-
-            let mut output = mem::uninitialized();
-            vk_i.GetPhysicalDeviceFormatProperties(physical_device, format as u32, &mut output);
-
-            let features = if linear_tiling {
-                output.linearTilingFeatures
-            } else {
-                output.optimalTilingFeatures
-            };
+            let features = format.properties(&device.physical_device())
+                                 .image_features(linear_tiling);
 
-            if features == 0 {
+            if features == FormatFeatures::none() {
                 return Err(ImageCreationError::FormatNotSupported);
             }
 
-            if usage.sampled && (features & vk::FORMAT_FEATURE_SAMPLED_IMAGE_BIT == 0) {
+            if usage.sampled && !features.sampled_image {
                 return Err(ImageCreationError::UnsupportedUsage);
             }
-            if usage.storage && (features & vk::FORMAT_FEATURE_STORAGE_IMAGE_BIT == 0) {
+            if usage.storage && !features.storage_image {
                 return Err(ImageCreationError::UnsupportedUsage);
             }
-            if usage.color_attachment && (features & vk::FORMAT_FEATURE_COLOR_ATTACHMENT_BIT == 0) {
+            if usage.color_attachment && !features.color_attachment {
                 return Err(ImageCreationError::UnsupportedUsage);
             }
-            if usage.depth_stencil_attachment && (features & vk::FORMAT_FEATURE_DEPTH_STENCIL_ATTACHMENT_BIT == 0) {
+            if usage.depth_stencil_attachment && !features.depth_stencil_attachment {
                 return Err(ImageCreationError::UnsupportedUsage);
             }
-            if usage.input_attachment && (features & (vk::FORMAT_FEATURE_COLOR_ATTACHMENT_BIT | vk::FORMAT_FEATURE_DEPTH_STENCIL_ATTACHMENT_BIT) == 0) {
+            if usage.input_attachment &&
+               !(features.color_attachment || features.depth_stencil_attachment)
+            {
                 return Err(ImageCreationError::UnsupportedUsage);
             }
 
@@ -157,44 +247,21 @@ impl UnsafeImage {
             }
         }
 
-        // This function is going to perform various checks and write to `capabilities_error` in
-        // case of error.
-        //
-        // If `capabilities_error` is not `None` after the checks are finished, the function will
-        // check for additional image capabilities (section 31.4 of the specs).
-        let mut capabilities_error = None;
-
-        // Compute the maximum number of mipmaps.
-        // TODO: only compte if necessary?
+        // Compute the maximum number of mipmaps, which is the number of times the largest
+        // dimension can be halved.
         let max_mipmaps = {
-            let smallest_dim: u32 = match dimensions {
-                Dimensions::Dim1d { width } | Dimensions::Dim1dArray { width, .. } => width,
-                Dimensions::Dim2d { width, height } | Dimensions::Dim2dArray { width, height, .. } => {
-                    if width < height { width } else { height }
-                },
-                Dimensions::Dim3d { width, height, depth } => {
-                    if width < height {
-                        if depth < width { depth } else { width }
-                    } else {
-                        if depth < height { depth } else { height }
-                    }
-                },
-            };
-
-            32 - smallest_dim.leading_zeros()
+            let largest_dim = [dimensions.width(), dimensions.height(), dimensions.depth()]
+                                .iter().cloned().max().unwrap();
+            32 - largest_dim.leading_zeros()
         };
 
         // Compute the number of mipmaps.
         let mipmaps = match mipmaps.into() {
             MipmapsCount::Specific(num) => {
-                if num < 1 {
+                if num < 1 || num > max_mipmaps {
                     return Err(ImageCreationError::InvalidMipmapsCount {
                         obtained: num, valid_range: 1 .. max_mipmaps + 1
                     });
-                } else if num > max_mipmaps {
-                    capabilities_error = Some(ImageCreationError::InvalidMipmapsCount {
-                        obtained: num, valid_range: 1 .. max_mipmaps + 1
-                    });
                 }
 
                 num
@@ -203,78 +270,19 @@ impl UnsafeImage {
             MipmapsCount::One => 1,
         };
 
-        // Checking whether the number of samples is supported.
-        if num_samples == 0 {
-            return Err(ImageCreationError::UnsupportedSamplesCount { obtained: num_samples });
-
-        } else if !num_samples.is_power_of_two() {
-            return Err(ImageCreationError::UnsupportedSamplesCount { obtained: num_samples });
-
-        } else {
-            let mut supported_samples = 0x7f;       // all bits up to VK_SAMPLE_COUNT_64_BIT
-
-            if usage.sampled {
-                match format.ty() {
-                    FormatTy::Float | FormatTy::Compressed => {
-                        supported_samples &= device.physical_device().limits()
-                                                   .sampled_image_color_sample_counts();
-                    },
-                    FormatTy::Uint | FormatTy::Sint => {
-                        supported_samples &= device.physical_device().limits()
-                                                   .sampled_image_integer_sample_counts();
-                    },
-                    FormatTy::Depth => {
-                        supported_samples &= device.physical_device().limits()
-                                                   .sampled_image_depth_sample_counts();
-                    },
-                    FormatTy::Stencil => {
-                        supported_samples &= device.physical_device().limits()
-                                                   .sampled_image_stencil_sample_counts();
-                    },
-                    FormatTy::DepthStencil => {
-                        supported_samples &= device.physical_device().limits()
-                                                   .sampled_image_depth_sample_counts();
-                        supported_samples &= device.physical_device().limits()
-                                                   .sampled_image_stencil_sample_counts();
-                    },
-                }
-            }
-
-            if usage.storage {
-                supported_samples &= device.physical_device().limits()
-                                           .storage_image_sample_counts();
-            }
-
-            if usage.color_attachment || usage.depth_stencil_attachment || usage.input_attachment ||
-               usage.transient_attachment
-            {
-                match format.ty() {
-                    FormatTy::Float | FormatTy::Compressed | FormatTy::Uint | FormatTy::Sint => {
-                        supported_samples &= device.physical_device().limits()
-                                                   .framebuffer_color_sample_counts();
-                    },
-                    FormatTy::Depth => {
-                        supported_samples &= device.physical_device().limits()
-                                                   .framebuffer_depth_sample_counts();
-                    },
-                    FormatTy::Stencil => {
-                        supported_samples &= device.physical_device().limits()
-                                                   .framebuffer_stencil_sample_counts();
-                    },
-                    FormatTy::DepthStencil => {
-                        supported_samples &= device.physical_device().limits()
-                                                   .framebuffer_depth_sample_counts();
-                        supported_samples &= device.physical_device().limits()
-                                                   .framebuffer_stencil_sample_counts();
-                    },
-                }
-            }
-
-            if (num_samples & supported_samples) == 0 {
-                let err = ImageCreationError::UnsupportedSamplesCount { obtained: num_samples };
-                capabilities_error = Some(err);
-            }
-        }
+        // Checking whether the number of samples is valid. Whether it is supported for this
+        // specific kind of image is checked below.
+        let sample_count = match SampleCount::from_count(num_samples) {
+            Some(count) => count,
+            None => {
+                let supported = SupportedSampleCounts::for_image(&device.physical_device(),
+                                                                 format, usage);
+                return Err(ImageCreationError::UnsupportedSampleCount {
+                    requested: num_samples,
+                    supported: supported,
+                });
+            },
+        };
 
         // If the `shaderStorageImageMultisample` feature is not enabled and we have
         // `usage_storage` set to true, then the number of samples must be 1.
@@ -291,105 +299,152 @@ impl UnsafeImage {
                     return Err(ImageCreationError::UnsupportedDimensions { dimensions: dimensions });
                 }
                 let extent = vk::Extent3D { width: width, height: 1, depth: 1 };
-                (vk::IMAGE_TYPE_1D, extent, 1)
+                (ImageType::Dim1d, extent, 1)
             },
             Dimensions::Dim1dArray { width, array_layers } => {
                 if width == 0 || array_layers == 0 {
                     return Err(ImageCreationError::UnsupportedDimensions { dimensions: dimensions });
                 }
                 let extent = vk::Extent3D { width: width, height: 1, depth: 1 };
-                (vk::IMAGE_TYPE_1D, extent, array_layers)
+                (ImageType::Dim1d, extent, array_layers)
             },
             Dimensions::Dim2d { width, height } => {
                 if width == 0 || height == 0 {
                     return Err(ImageCreationError::UnsupportedDimensions { dimensions: dimensions });
                 }
                 let extent = vk::Extent3D { width: width, height: height, depth: 1 };
-                (vk::IMAGE_TYPE_2D, extent, 1)
+                (ImageType::Dim2d, extent, 1)
             },
             Dimensions::Dim2dArray { width, height, array_layers } => {
                 if width == 0 || height == 0 || array_layers == 0 {
                     return Err(ImageCreationError::UnsupportedDimensions { dimensions: dimensions });
                 }
                 let extent = vk::Extent3D { width: width, height: height, depth: 1 };
-                (vk::IMAGE_TYPE_2D, extent, array_layers)
+                (ImageType::Dim2d, extent, array_layers)
             },
             Dimensions::Dim3d { width, height, depth } => {
                 if width == 0 || height == 0 || depth == 0 {
                     return Err(ImageCreationError::UnsupportedDimensions { dimensions: dimensions });
                 }
                 let extent = vk::Extent3D { width: width, height: height, depth: depth };
-                (vk::IMAGE_TYPE_3D, extent, 1)
+                (ImageType::Dim3d, extent, 1)
             },
         };
 
-        // Checking the dimensions against the limits.
-        if array_layers > device.physical_device().limits().max_image_array_layers() {
-            let err = ImageCreationError::UnsupportedDimensions { dimensions: dimensions };
-            capabilities_error = Some(err);
+        // Only two-dimensional images can be multisampled.
+        if num_samples > 1 && ty != ImageType::Dim2d {
+            return Err(ImageCreationError::UnsupportedSampleCount {
+                requested: num_samples,
+                supported: SupportedSampleCounts { sample1: true, .. SupportedSampleCounts::none() },
+            });
         }
-        match ty {
-            vk::IMAGE_TYPE_1D => {
-                if extent.width > device.physical_device().limits().max_image_dimension_1d() {
-                    let err = ImageCreationError::UnsupportedDimensions { dimensions: dimensions };
-                    capabilities_error = Some(err);
-                }
-            },
-            vk::IMAGE_TYPE_2D => {
-                let limit = device.physical_device().limits().max_image_dimension_2d();
-                if extent.width > limit || extent.height > limit {
-                    let err = ImageCreationError::UnsupportedDimensions { dimensions: dimensions };
-                    capabilities_error = Some(err);
-                }
-            },
-            vk::IMAGE_TYPE_3D => {
-                let limit = device.physical_device().limits().max_image_dimension_3d();
-                if extent.width > limit || extent.height > limit || extent.depth > limit {
-                    let err = ImageCreationError::UnsupportedDimensions { dimensions: dimensions };
-                    capabilities_error = Some(err);
-                }
-            },
-            _ => unreachable!()
-        };
 
-        let usage = usage.to_usage_bits();
+        // Checking sparse features.
+        if sparse.sparse && !device.enabled_features().sparse_binding {
+            return Err(ImageCreationError::SparseBindingFeatureNotEnabled);
+        }
+        if sparse.sparse_residency {
+            match ty {
+                ImageType::Dim1d => return Err(ImageCreationError::SparseResidency1dImage),
+                ImageType::Dim2d => {
+                    if !device.enabled_features().sparse_residency_image2d {
+                        return Err(ImageCreationError::SparseResidencyImage2dFeatureNotEnabled);
+                    }
+                },
+                ImageType::Dim3d => {
+                    if !device.enabled_features().sparse_residency_image3d {
+                        return Err(ImageCreationError::SparseResidencyImage3dFeatureNotEnabled);
+                    }
+                },
+            }
 
-        // Now that all checks have been performed, if any of the check failed we query the Vulkan
-        // implementation for additional image capabilities.
-        if let Some(capabilities_error) = capabilities_error {
-            let tiling = if linear_tiling {
-                vk::IMAGE_TILING_LINEAR
-            } else {
-                vk::IMAGE_TILING_OPTIMAL
+            let samples_feature = match sample_count {
+                SampleCount::Sample1 => true,
+                SampleCount::Sample2 => device.enabled_features().sparse_residency2_samples,
+                SampleCount::Sample4 => device.enabled_features().sparse_residency4_samples,
+                SampleCount::Sample8 => device.enabled_features().sparse_residency8_samples,
+                SampleCount::Sample16 => device.enabled_features().sparse_residency16_samples,
+                SampleCount::Sample32 | SampleCount::Sample64 => false,
             };
 
-            let mut output = mem::uninitialized();
-            let physical_device = device.physical_device().internal_object();
-            let r = vk_i.GetPhysicalDeviceImageFormatProperties(physical_device, format as u32, ty,
-                                                                tiling, usage, 0 /* TODO */,
-                                                                &mut output);
-
-            match check_errors(r) {
-                Ok(_) => (),
-                Err(Error::FormatNotSupported) => return Err(ImageCreationError::FormatNotSupported),
-                Err(err) => return Err(err.into()),
+            if !samples_feature {
+                return Err(ImageCreationError::SparseResidencySamplesFeatureNotEnabled {
+                    samples: num_samples
+                });
+            }
+        }
+        if sparse.sparse_aliased && !device.enabled_features().sparse_residency_aliased {
+            return Err(ImageCreationError::SparseResidencyAliasedFeatureNotEnabled);
+        }
+
+        // Cube-compatible images must be made of square faces, 6 layers per cube.
+        if cube_compatible {
+            if ty != ImageType::Dim2d || extent.width != extent.height {
+                return Err(ImageCreationError::CubeCompatibleNotSquare);
+            }
+
+            if array_layers % 6 != 0 {
+                return Err(ImageCreationError::CubeCompatibleInvalidArrayLayers {
+                    array_layers: array_layers
+                });
             }
+        }
+
+        // Checking the image against the capabilities of the physical device for this exact
+        // combination of format, type, tiling, usage and flags (section 31.4 of the specs).
+        if check_properties {
+            let properties = {
+                let properties = try!(device.physical_device()
+                                            .image_format_properties(format, ty, linear_tiling,
+                                                                     usage, flags, sparse));
+                match properties {
+                    Some(p) => p,
+                    None => return Err(ImageCreationError::FormatNotSupported),
+                }
+            };
 
-            if extent.width > output.maxExtent.width || extent.height > output.maxExtent.height ||
-               extent.depth > output.maxExtent.depth || mipmaps > output.maxMipLevels ||
-               array_layers > output.maxArrayLayers || (num_samples & output.sampleCounts) == 0
+            if extent.width > properties.max_extent[0] ||
+               extent.height > properties.max_extent[1] ||
+               extent.depth > properties.max_extent[2]
             {
-                return Err(capabilities_error);
+                return Err(ImageCreationError::DimensionsTooLarge {
+                    dimensions: dimensions,
+                    max_extent: properties.max_extent,
+                });
+            }
+
+            if mipmaps > properties.max_mipmap_levels {
+                return Err(ImageCreationError::TooManyMipLevels {
+                    requested: mipmaps,
+                    max: properties.max_mipmap_levels,
+                });
+            }
+
+            if array_layers > properties.max_array_layers {
+                return Err(ImageCreationError::TooManyArrayLayers {
+                    requested: array_layers,
+                    max: properties.max_array_layers,
+                });
+            }
+
+            if !properties.sample_counts.contains(sample_count) {
+                return Err(ImageCreationError::UnsupportedSampleCount {
+                    requested: num_samples,
+                    supported: properties.sample_counts,
+                });
             }
         }
 
+        let flags = sparse.to_flags() | flags.to_flags();
+        let usage = usage.to_usage_bits();
+
         // Everything now ok. Creating the image.
         let image = {
             let infos = vk::ImageCreateInfo {
                 sType: vk::STRUCTURE_TYPE_IMAGE_CREATE_INFO,
                 pNext: ptr::null(),
-                flags: 0,                               // TODO:
-                imageType: ty,
+                flags: flags,
+                imageType: ty as u32,
                 format: format as u32,
                 extent: extent,
                 mipLevels: mipmaps,
@@ -417,11 +472,11 @@ impl UnsafeImage {
             output
         };
 
-        let mem_reqs: vk::MemoryRequirements = {
-            let mut output = mem::uninitialized();
+        let mem_reqs: MemoryRequirements = {
+            let mut output: vk::MemoryRequirements = mem::uninitialized();
             vk.GetImageMemoryRequirements(device.internal_object(), image, &mut output);
             debug_assert!(output.memoryTypeBits != 0);
-            output
+            output.into()
         };
 
         let image = UnsafeImage {
@@ -432,27 +487,33 @@ impl UnsafeImage {
             dimensions: dimensions,
             samples: num_samples,
             mipmaps: mipmaps,
+            cube_compatible: cube_compatible,
+            mutable_format: mutable_format,
+            sparse: sparse,
+            linear_tiling: linear_tiling,
             format_features: format_features,
+            memory_requirements: mem_reqs,
+            memory_bound: AtomicBool::new(false),
             needs_destruction: true,
         };
 
-        Ok((image, mem_reqs.into()))
+        Ok((image, mem_reqs))
     }
 
     /// Creates an image from a raw handle. The image won't be destroyed.
     ///
-    /// This function is for example used at the swapchain's initialization.
+    /// This function is for example used at the swapchain's initialization. No check is
+    /// performed on the parameters.
+    ///
+    /// The image is considered to already have memory bound to it, and its memory requirements
+    /// are all zero.
     pub unsafe fn from_raw(device: &Arc<Device>, handle: u64, usage: u32, format: Format,
                            dimensions: Dimensions, samples: u32, mipmaps: u32)
                            -> UnsafeImage
     {
-        let vk_i = device.instance().pointers();
-        let physical_device = device.physical_device().internal_object();
-
-        let mut output = mem::uninitialized();
-        vk_i.GetPhysicalDeviceFormatProperties(physical_device, format as u32, &mut output);
+        let properties = format.properties(&device.physical_device());
 
-        // TODO: check that usage is correct in regard to `output`?
+        // TODO: check that usage is correct in regard to `properties`?
 
         UnsafeImage {
             device: device.clone(),
@@ -462,31 +523,195 @@ impl UnsafeImage {
             dimensions: dimensions,
             samples: samples,
             mipmaps: mipmaps,
-            format_features: output.optimalTilingFeatures,
+            cube_compatible: false,
+            mutable_format: false,
+            sparse: SparseLevel::none(),
+            linear_tiling: false,
+            format_features: properties.optimal_tiling_features,
+            memory_requirements: MemoryRequirements {
+                size: 0,
+                alignment: 1,
+                memory_type_bits: 0,
+            },
+            memory_bound: AtomicBool::new(true),
             needs_destruction: false,       // TODO: pass as parameter
         }
     }
 
+    /// Returns the memory requirements of the image.
+    ///
+    /// The memory that is bound to the image with `bind_memory` must respect these requirements.
+    #[inline]
+    pub fn memory_requirements(&self) -> MemoryRequirements {
+        self.memory_requirements
+    }
+
+    /// Binds a range of device memory to the image.
+    ///
+    /// The memory type, the alignment of `offset` and the size of the remaining memory are
+    /// checked against the requirements returned by `memory_requirements`. Memory can only be
+    /// bound once.
+    ///
+    /// # Safety
+    ///
+    /// - The memory must be kept alive for as long as the image is in use.
+    /// - The image must not be sparse. The memory of sparse images is bound with
+    ///   `Queue::bind_sparse` instead.
+    ///
     pub unsafe fn bind_memory(&self, memory: &DeviceMemory, offset: usize)
-                                  -> Result<(), OomError>
+                              -> Result<(), ImageMemoryBindError>
     {
         let vk = self.device.pointers();
 
-        // We check for correctness in debug mode.
-        debug_assert!({
-            let mut mem_reqs = mem::uninitialized();
-            vk.GetImageMemoryRequirements(self.device.internal_object(), self.image,
-                                          &mut mem_reqs);
-            mem_reqs.size <= (memory.size() - offset) as u64 &&
-            (offset as u64 % mem_reqs.alignment) == 0 &&
-            mem_reqs.memoryTypeBits & (1 << memory.memory_type().id()) != 0
-        });
+        debug_assert!(!self.sparse.sparse);
+
+        let reqs = &self.memory_requirements;
+
+        if !reqs.prefers(memory.memory_type()) {
+            return Err(ImageMemoryBindError::WrongMemoryType);
+        }
+
+        if offset % reqs.alignment != 0 {
+            return Err(ImageMemoryBindError::UnalignedOffset {
+                offset: offset,
+                alignment: reqs.alignment,
+            });
+        }
+
+        let available = memory.size().saturating_sub(offset);
+        if available < reqs.size {
+            return Err(ImageMemoryBindError::MemoryTooSmall {
+                required: reqs.size,
+                available: available,
+            });
+        }
+
+        if self.memory_bound.swap(true, Ordering::SeqCst) {
+            return Err(ImageMemoryBindError::AlreadyBound);
+        }
+
+        let result = check_errors(vk.BindImageMemory(self.device.internal_object(), self.image,
+                                                     memory.internal_object(),
+                                                     offset as vk::DeviceSize));
+
+        if let Err(err) = result {
+            self.memory_bound.store(false, Ordering::SeqCst);
+            return Err(ImageMemoryBindError::OomError(err.into()));
+        }
 
-        try!(check_errors(vk.BindImageMemory(self.device.internal_object(), self.image,
-                                             memory.internal_object(), offset as vk::DeviceSize)));
         Ok(())
     }
 
+    /// Same as `bind_memory`, but for memory that was allocated according to
+    /// `memory_requirements`. The only error that can then happen is running out of memory.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the memory doesn't respect the requirements of the image.
+    ///
+    /// # Safety
+    ///
+    /// Same as `bind_memory`.
+    ///
+    #[doc(hidden)]
+    #[inline]
+    pub unsafe fn bind_allocated_memory(&self, memory: &DeviceMemory, offset: usize)
+                                        -> Result<(), OomError>
+    {
+        match self.bind_memory(memory, offset) {
+            Ok(()) => Ok(()),
+            Err(ImageMemoryBindError::OomError(err)) => Err(err),
+            Err(err) => panic!("the memory doesn't respect the requirements of the image: {}",
+                               err),
+        }
+    }
+
+    /// Returns the sparse memory requirements of the image, one element per aspect or group of
+    /// aspects.
+    ///
+    /// Always returns an empty list if the image wasn't created with sparse residency.
+    pub fn sparse_memory_requirements(&self) -> Vec<SparseImageMemoryRequirements> {
+        if !self.sparse.sparse_residency {
+            return Vec::new();
+        }
+
+        unsafe {
+            let vk = self.device.pointers();
+
+            let mut num = 0;
+            vk.GetImageSparseMemoryRequirements(self.device.internal_object(), self.image,
+                                                &mut num, ptr::null_mut());
+
+            let mut output = Vec::with_capacity(num as usize);
+            vk.GetImageSparseMemoryRequirements(self.device.internal_object(), self.image,
+                                                &mut num, output.as_mut_ptr());
+            output.set_len(num as usize);
+
+            output.into_iter().map(|r| r.into()).collect()
+        }
+    }
+
+    /// Returns how the given subresource of the image is laid out in memory.
+    ///
+    /// The offsets and pitches are relative to the start of the memory bound to the image. This
+    /// can be used to access the texels of the image directly through a mapping of its memory.
+    ///
+    /// Only images that use the linear tiling have a known layout.
+    pub fn subresource_layout(&self, aspect: ImageAspect, mipmap_level: u32, array_layer: u32)
+                              -> Result<SubresourceLayout, SubresourceLayoutError>
+    {
+        if !self.linear_tiling {
+            return Err(SubresourceLayoutError::OptimalTiling);
+        }
+
+        if mipmap_level >= self.mipmaps {
+            return Err(SubresourceLayoutError::MipmapLevelOutOfRange {
+                obtained: mipmap_level,
+                mipmap_levels: self.mipmaps,
+            });
+        }
+
+        if array_layer >= self.dimensions.array_layers() {
+            return Err(SubresourceLayoutError::ArrayLayerOutOfRange {
+                obtained: array_layer,
+                array_layers: self.dimensions.array_layers(),
+            });
+        }
+
+        let aspects = ImageAspects::from_format(self.format);
+        let aspect_present = match aspect {
+            ImageAspect::Color => aspects.color,
+            ImageAspect::Depth => aspects.depth,
+            ImageAspect::Stencil => aspects.stencil,
+        };
+
+        if !aspect_present {
+            return Err(SubresourceLayoutError::AspectNotPresent { aspect: aspect });
+        }
+
+        let subresource = vk::ImageSubresource {
+            aspectMask: aspect as u32,
+            mipLevel: mipmap_level,
+            arrayLayer: array_layer,
+        };
+
+        let output: vk::SubresourceLayout = unsafe {
+            let vk = self.device.pointers();
+            let mut output = mem::uninitialized();
+            vk.GetImageSubresourceLayout(self.device.internal_object(), self.image,
+                                         &subresource, &mut output);
+            output
+        };
+
+        Ok(output.into())
+    }
+
+    /// Returns the device the image was created with.
+    #[inline]
+    pub fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+
     #[inline]
     pub fn format(&self) -> Format {
         self.format
@@ -507,16 +732,47 @@ impl UnsafeImage {
         self.samples
     }
 
+    /// Returns true if the image uses the linear tiling.
+    #[inline]
+    pub fn linear_tiling(&self) -> bool {
+        self.linear_tiling
+    }
+
+    /// Returns the sparse flags the image was created with.
+    #[inline]
+    pub fn sparse_level(&self) -> SparseLevel {
+        self.sparse
+    }
+
+    /// Returns true if cube and cube array views can be created from this image.
+    #[inline]
+    pub fn cube_compatible(&self) -> bool {
+        self.cube_compatible
+    }
+
+    /// Returns true if views of the image can use a format different from the format of the
+    /// image.
+    #[inline]
+    pub fn mutable_format(&self) -> bool {
+        self.mutable_format
+    }
+
     /// Returns true if the image can be used as a source for blits.
     #[inline]
     pub fn supports_blit_source(&self) -> bool {
-        (self.format_features & vk::FORMAT_FEATURE_BLIT_SRC_BIT) != 0
+        self.format_features.blit_src
     }
 
     /// Returns true if the image can be used as a destination for blits.
     #[inline]
     pub fn supports_blit_destination(&self) -> bool {
-        (self.format_features & vk::FORMAT_FEATURE_BLIT_DST_BIT) != 0
+        self.format_features.blit_dst
+    }
+
+    /// Returns true if the image can be sampled or blitted with linear filtering.
+    #[inline]
+    pub fn supports_linear_filtering(&self) -> bool {
+        self.format_features.sampled_image_filter_linear
     }
 }
 
@@ -550,16 +806,70 @@ pub enum ImageCreationError {
     OomError(OomError),
     /// A wrong number of mipmaps was provided.
     InvalidMipmapsCount { obtained: u32, valid_range: Range<u32> },
-    /// The requeted number of samples is not supported, or is 0.
-    UnsupportedSamplesCount { obtained: u32 },
-    /// The dimensions are too large, or one of the dimensions is 0.
+    /// The requested number of samples is not supported for this format and usage.
+    UnsupportedSampleCount {
+        /// Number of samples that was requested.
+        requested: u32,
+        /// Sample counts that are supported.
+        supported: SupportedSampleCounts,
+    },
+    /// One of the dimensions is 0.
     UnsupportedDimensions { dimensions: Dimensions },
+    /// The dimensions are larger than what the physical device supports for this kind of image.
+    DimensionsTooLarge {
+        /// Dimensions that were requested.
+        dimensions: Dimensions,
+        /// Maximum width, height and depth supported for this kind of image.
+        max_extent: [u32; 3],
+    },
+    /// The number of mipmap levels is larger than what the physical device supports for this
+    /// kind of image.
+    TooManyMipLevels { requested: u32, max: u32 },
+    /// The number of array layers is larger than what the physical device supports for this
+    /// kind of image.
+    TooManyArrayLayers { requested: u32, max: u32 },
     /// The requested format is not supported by the Vulkan implementation.
     FormatNotSupported,
     /// The format is supported, but at least one of the requested usages is not supported.
     UnsupportedUsage,
     /// The `shader_storage_image_multisample` feature must be enabled to create such an image.
     ShaderStorageImageMultisampleFeatureNotEnabled,
+    /// The format is compressed and the corresponding `texture_compression_*` feature is not
+    /// enabled.
+    TextureCompressionFeatureNotEnabled,
+    /// Sparse binding was requested but the corresponding feature wasn't enabled.
+    SparseBindingFeatureNotEnabled,
+    /// Sparse residency was requested for a one-dimensional image.
+    SparseResidency1dImage,
+    /// Sparse residency was requested for a two-dimensional image but the corresponding feature
+    /// wasn't enabled.
+    SparseResidencyImage2dFeatureNotEnabled,
+    /// Sparse residency was requested for a three-dimensional image but the corresponding
+    /// feature wasn't enabled.
+    SparseResidencyImage3dFeatureNotEnabled,
+    /// Sparse residency was requested for a multisampled image but the feature corresponding to
+    /// the number of samples wasn't enabled.
+    SparseResidencySamplesFeatureNotEnabled { samples: u32 },
+    /// Sparse aliasing was requested but the corresponding feature wasn't enabled.
+    SparseResidencyAliasedFeatureNotEnabled,
+    /// The image is cube-compatible but is not two-dimensional with a width equal to its height.
+    CubeCompatibleNotSquare,
+    /// The image is cube-compatible but its number of array layers is not a multiple of 6.
+    CubeCompatibleInvalidArrayLayers { array_layers: u32 },
+    /// Allocating the memory of the image would make the memory used in a heap exceed the budget
+    /// of that heap.
+    BudgetExceeded {
+        /// Index of the heap.
+        heap: u32,
+        /// Number of bytes that were requested.
+        requested: usize,
+        /// Budget of the heap, in bytes.
+        budget: usize,
+        /// Number of bytes of the heap that were already in use.
+        used: usize,
+    },
+    /// The Vulkan implementation returned an error that this operation isn't supposed to return.
+    Unexpected(Error),
 }
 
 impl error::Error for ImageCreationError {
@@ -569,10 +879,16 @@ impl error::Error for ImageCreationError {
             ImageCreationError::OomError(_) => "not enough memory available",
             ImageCreationError::InvalidMipmapsCount { .. } => "a wrong number of mipmaps was \
                                                                provided",
-            ImageCreationError::UnsupportedSamplesCount { .. } => "the requeted number of samples \
-                                                                   is not supported, or is 0",
-            ImageCreationError::UnsupportedDimensions { .. } => "the dimensions are too large, or \
-                                                                 one of the dimensions is 0",
+            ImageCreationError::UnsupportedSampleCount { .. } => "the requested number of samples \
+                                                                  is not supported for this \
+                                                                  format and usage",
+            ImageCreationError::UnsupportedDimensions { .. } => "one of the dimensions is 0",
+            ImageCreationError::DimensionsTooLarge { .. } => "the dimensions are too large for \
+                                                              this kind of image",
+            ImageCreationError::TooManyMipLevels { .. } => "the number of mipmap levels is too \
+                                                            large for this kind of image",
+            ImageCreationError::TooManyArrayLayers { .. } => "the number of array layers is too \
+                                                              large for this kind of image",
             ImageCreationError::FormatNotSupported => "the requested format is not supported by \
                                                        the Vulkan implementation",
             ImageCreationError::UnsupportedUsage => "the format is supported, but at least one \
@@ -581,6 +897,43 @@ impl error::Error for ImageCreationError {
                 "the `shader_storage_image_multisample` feature must be enabled to create such \
                  an image"
             },
+            ImageCreationError::TextureCompressionFeatureNotEnabled => {
+                "the texture compression feature corresponding to the format must be enabled to \
+                 create such an image"
+            },
+            ImageCreationError::SparseBindingFeatureNotEnabled => {
+                "sparse binding was requested but the corresponding feature wasn't enabled"
+            },
+            ImageCreationError::SparseResidency1dImage => {
+                "sparse residency was requested for a one-dimensional image"
+            },
+            ImageCreationError::SparseResidencyImage2dFeatureNotEnabled => {
+                "sparse residency was requested for a two-dimensional image but the \
+                 corresponding feature wasn't enabled"
+            },
+            ImageCreationError::SparseResidencyImage3dFeatureNotEnabled => {
+                "sparse residency was requested for a three-dimensional image but the \
+                 corresponding feature wasn't enabled"
+            },
+            ImageCreationError::SparseResidencySamplesFeatureNotEnabled { .. } => {
+                "sparse residency was requested for a multisampled image but the corresponding \
+                 feature wasn't enabled"
+            },
+            ImageCreationError::SparseResidencyAliasedFeatureNotEnabled => {
+                "sparse aliasing was requested but the corresponding feature wasn't enabled"
+            },
+            ImageCreationError::CubeCompatibleNotSquare => {
+                "a cube-compatible image must be two-dimensional with a width equal to its height"
+            },
+            ImageCreationError::CubeCompatibleInvalidArrayLayers { .. } => {
+                "the number of array layers of a cube-compatible image must be a multiple of 6"
+            },
+            ImageCreationError::BudgetExceeded { .. } => {
+                "allocating the memory of the image would exceed the budget of the heap"
+            },
+            ImageCreationError::Unexpected(_) => {
+                "unexpected error returned by the Vulkan implementation"
+            },
         }
     }
 
@@ -588,6 +941,7 @@ impl error::Error for ImageCreationError {
     fn cause(&self) -> Option<&error::Error> {
         match *self {
             ImageCreationError::OomError(ref err) => Some(err),
+            ImageCreationError::Unexpected(ref err) => Some(err),
             _ => None
         }
     }
@@ -607,13 +961,30 @@ impl From<OomError> for ImageCreationError {
     }
 }
 
+impl From<MemoryPoolError> for ImageCreationError {
+    #[inline]
+    fn from(err: MemoryPoolError) -> ImageCreationError {
+        match err {
+            MemoryPoolError::OomError(err) => ImageCreationError::OomError(err),
+            MemoryPoolError::BudgetExceeded { heap, requested, budget, used } => {
+                ImageCreationError::BudgetExceeded {
+                    heap: heap,
+                    requested: requested,
+                    budget: budget,
+                    used: used,
+                }
+            },
+        }
+    }
+}
+
 impl From<Error> for ImageCreationError {
     #[inline]
     fn from(err: Error) -> ImageCreationError {
         match err {
             err @ Error::OutOfHostMemory => ImageCreationError::OomError(OomError::from(err)),
             err @ Error::OutOfDeviceMemory => ImageCreationError::OomError(OomError::from(err)),
-            _ => panic!("unexpected error: {:?}", err)
+            _ => ImageCreationError::Unexpected(err)
         }
     }
 }
@@ -624,129 +995,308 @@ pub struct UnsafeImageView {
     device: Arc<Device>,
     usage: vk::ImageUsageFlagBits,
     identity_swizzle: bool,
+    swizzle: Swizzle,
     format: Format,
+    mipmap_levels: Range<u32>,
+    array_layers: Range<u32>,
+    dimensions: Dimensions,
+    ty: ViewType,
+    aspects: ImageAspects,
 }
 
 impl UnsafeImageView {
-    /// See the docs of new().
-    pub unsafe fn raw(image: &UnsafeImage, mipmap_levels: Range<u32>, array_layers: Range<u32>)
-                      -> Result<UnsafeImageView, OomError>
-    {
-        let vk = image.device.pointers();
-
-        assert!(mipmap_levels.end > mipmap_levels.start);
-        assert!(mipmap_levels.end <= image.mipmaps);
-        assert!(array_layers.end > array_layers.start);
-        assert!(array_layers.end <= image.dimensions.array_layers());
-
-        let aspect_mask = match image.format.ty() {
-            FormatTy::Float | FormatTy::Uint | FormatTy::Sint | FormatTy::Compressed => {
-                vk::IMAGE_ASPECT_COLOR_BIT
-            },
-            FormatTy::Depth => vk::IMAGE_ASPECT_DEPTH_BIT,
-            FormatTy::Stencil => vk::IMAGE_ASPECT_STENCIL_BIT,
-            FormatTy::DepthStencil => vk::IMAGE_ASPECT_DEPTH_BIT | vk::IMAGE_ASPECT_STENCIL_BIT,
-        };
-
-        let view = {
-            let infos = vk::ImageViewCreateInfo {
-                sType: vk::STRUCTURE_TYPE_IMAGE_VIEW_CREATE_INFO,
-                pNext: ptr::null(),
-                flags: 0,   // reserved
-                image: image.internal_object(),
-                viewType: match (image.dimensions(), array_layers.end - array_layers.start) {
-                    (Dimensions::Dim1d { .. }, _) => vk::IMAGE_VIEW_TYPE_1D,
-                    (Dimensions::Dim1dArray { .. }, 1) => vk::IMAGE_VIEW_TYPE_1D,
-                    (Dimensions::Dim1dArray { .. }, _) => vk::IMAGE_VIEW_TYPE_1D_ARRAY,
-                    (Dimensions::Dim2d { .. }, _) => vk::IMAGE_VIEW_TYPE_2D,
-                    (Dimensions::Dim2dArray { .. }, 1) => vk::IMAGE_VIEW_TYPE_2D,
-                    (Dimensions::Dim2dArray { .. }, _) => vk::IMAGE_VIEW_TYPE_2D_ARRAY,
-                    (Dimensions::Dim3d { .. }, _) => vk::IMAGE_VIEW_TYPE_3D,
-                },      // TODO: cube
-                format: image.format as u32,
-                components: vk::ComponentMapping { r: 0, g: 0, b: 0, a: 0 },     // FIXME:
-                subresourceRange: vk::ImageSubresourceRange {
-                    aspectMask: aspect_mask,
-                    baseMipLevel: mipmap_levels.start,
-                    levelCount: mipmap_levels.end - mipmap_levels.start,
-                    baseArrayLayer: array_layers.start,
-                    layerCount: array_layers.end - array_layers.start,
-                },
-            };
-
-            let mut output = mem::uninitialized();
-            try!(check_errors(vk.CreateImageView(image.device.internal_object(), &infos,
-                                                 ptr::null(), &mut output)));
-            output
-        };
-
-        Ok(UnsafeImageView {
-            view: view,
-            device: image.device.clone(),
-            usage: image.usage,
-            identity_swizzle: true,     // FIXME:
-            format: image.format,
-        })
-    }
-    
-    /// Creates a new view from an image.
+    /// Creates a view that covers all the mipmap levels and array layers of the image, with the
+    /// default swizzle.
     ///
-    /// Note that you must create the view with identity swizzling if you want to use this view
-    /// as a framebuffer attachment.
+    /// Such a view is always valid for the image, so the only error that can happen is running
+    /// out of memory.
     ///
     /// # Panic
     ///
-    /// - Panicks if the device or host ran out of memory.
+    /// - Panicks if the creation of the view fails for another reason.
+    ///
     #[inline]
-    pub unsafe fn new(image: &UnsafeImage, mipmap_levels: Range<u32>, array_layers: Range<u32>)
-                      -> UnsafeImageView
-    {
-        UnsafeImageView::raw(image, mipmap_levels, array_layers).unwrap()
+    pub unsafe fn whole(image: &UnsafeImage) -> Result<UnsafeImageView, OomError> {
+        let mipmap_levels = 0 .. image.mipmap_levels();
+        let array_layers = 0 .. image.dimensions().array_layers();
+
+        match UnsafeImageView::raw(image, mipmap_levels, array_layers, Swizzle::default()) {
+            Ok(view) => Ok(view),
+            Err(ImageViewCreationError::OomError(err)) => Err(err),
+            Err(err) => panic!("failed to create a view of the whole image: {}", err),
+        }
     }
 
+    /// See the docs of new().
+    ///
+    /// The type of the view is deduced from the dimensions of the image and the number of array
+    /// layers. Use `raw_with_type` to create a cube or cube array view. The view covers all the
+    /// aspects of the format of the image.
     #[inline]
-    pub fn format(&self) -> Format {
-        self.format
+    pub unsafe fn raw(image: &UnsafeImage, mipmap_levels: Range<u32>, array_layers: Range<u32>,
+                      swizzle: Swizzle)
+                      -> Result<UnsafeImageView, ImageViewCreationError>
+    {
+        UnsafeImageView::raw_with_aspects(image, mipmap_levels, array_layers, swizzle,
+                                          ImageAspects::from_format(image.format))
+    }
+
+    /// Same as `raw`, except that the aspects covered by the view are explicitly specified.
+    ///
+    /// This is used to create views of only the depth or only the stencil component of an image
+    /// with a depth-stencil format, for example in order to sample it.
+    #[inline]
+    pub unsafe fn raw_with_aspects(image: &UnsafeImage, mipmap_levels: Range<u32>,
+                                   array_layers: Range<u32>, swizzle: Swizzle,
+                                   aspects: ImageAspects)
+                                   -> Result<UnsafeImageView, ImageViewCreationError>
+    {
+        let ty = match (image.dimensions(), array_layers.end.saturating_sub(array_layers.start)) {
+            (Dimensions::Dim1d { .. }, _) => ViewType::Dim1d,
+            (Dimensions::Dim1dArray { .. }, 1) => ViewType::Dim1d,
+            (Dimensions::Dim1dArray { .. }, _) => ViewType::Dim1dArray,
+            (Dimensions::Dim2d { .. }, _) => ViewType::Dim2d,
+            (Dimensions::Dim2dArray { .. }, 1) => ViewType::Dim2d,
+            (Dimensions::Dim2dArray { .. }, _) => ViewType::Dim2dArray,
+            (Dimensions::Dim3d { .. }, _) => ViewType::Dim3d,
+        };
+
+        UnsafeImageView::raw_with_type(image, ty, mipmap_levels, array_layers, swizzle, aspects)
+    }
+
+    /// Same as `raw_with_aspects`, except that the type of the view is explicitly specified.
+    ///
+    /// Cube views must cover exactly 6 array layers, and cube array views must cover a multiple
+    /// of 6 array layers. Both require the image to be cube-compatible, and cube array views
+    /// require the `image_cube_array` feature.
+    #[inline]
+    pub unsafe fn raw_with_type(image: &UnsafeImage, ty: ViewType, mipmap_levels: Range<u32>,
+                                array_layers: Range<u32>, swizzle: Swizzle,
+                                aspects: ImageAspects)
+                                -> Result<UnsafeImageView, ImageViewCreationError>
+    {
+        UnsafeImageView::raw_with_format(image, ty, image.format, mipmap_levels, array_layers,
+                                         swizzle, aspects)
+    }
+
+    /// Same as `raw_with_type`, except that the format of the view is explicitly specified.
+    ///
+    /// If `format` is different from the format of the image, the image must have been created
+    /// with the mutable format flag and both formats must be in the same compatibility class.
+    pub unsafe fn raw_with_format(image: &UnsafeImage, ty: ViewType, format: Format,
+                                  mipmap_levels: Range<u32>, array_layers: Range<u32>,
+                                  swizzle: Swizzle, aspects: ImageAspects)
+                                  -> Result<UnsafeImageView, ImageViewCreationError>
+    {
+        let vk = image.device.pointers();
+
+        if format != image.format {
+            if !image.mutable_format {
+                return Err(ImageViewCreationError::FormatNotMutable);
+            }
+
+            if format.compatibility_class() != image.format.compatibility_class() {
+                return Err(ImageViewCreationError::IncompatibleFormat {
+                    image_format: image.format,
+                    view_format: format,
+                });
+            }
+        }
+
+        // The aspects must be a non-empty subset of the aspects of the format.
+        {
+            let format_aspects = ImageAspects::from_format(image.format);
+            if aspects == ImageAspects::none() ||
+               (aspects.color && !format_aspects.color) ||
+               (aspects.depth && !format_aspects.depth) ||
+               (aspects.stencil && !format_aspects.stencil)
+            {
+                return Err(ImageViewCreationError::InvalidAspects { aspects: aspects });
+            }
+        }
+
+        if mipmap_levels.end <= mipmap_levels.start || mipmap_levels.end > image.mipmaps {
+            return Err(ImageViewCreationError::MipmapLevelsOutOfRange {
+                range: mipmap_levels,
+                image_mipmap_levels: image.mipmaps,
+            });
+        }
+
+        if array_layers.end <= array_layers.start ||
+           array_layers.end > image.dimensions.array_layers()
+        {
+            return Err(ImageViewCreationError::ArrayLayersOutOfRange {
+                range: array_layers,
+                image_array_layers: image.dimensions.array_layers(),
+            });
+        }
+
+        // Checking that the type of the view is compatible with the image.
+        let num_layers = array_layers.end - array_layers.start;
+        let compatible = match (ty, image.dimensions) {
+            (ViewType::Dim1d, Dimensions::Dim1d { .. }) => true,
+            (ViewType::Dim1d, Dimensions::Dim1dArray { .. }) => num_layers == 1,
+            (ViewType::Dim1dArray, Dimensions::Dim1d { .. }) => true,
+            (ViewType::Dim1dArray, Dimensions::Dim1dArray { .. }) => true,
+            (ViewType::Dim2d, Dimensions::Dim2d { .. }) => true,
+            (ViewType::Dim2d, Dimensions::Dim2dArray { .. }) => num_layers == 1,
+            (ViewType::Dim2dArray, Dimensions::Dim2d { .. }) => true,
+            (ViewType::Dim2dArray, Dimensions::Dim2dArray { .. }) => true,
+            (ViewType::Dim3d, Dimensions::Dim3d { .. }) => true,
+            (ViewType::Cubemap, _) | (ViewType::CubemapArray, _) => image.cube_compatible,
+            _ => false
+        };
+
+        if !compatible {
+            return Err(ImageViewCreationError::IncompatibleViewType { ty: ty });
+        }
+
+        match ty {
+            ViewType::Cubemap if num_layers != 6 => {
+                return Err(ImageViewCreationError::InvalidCubeArrayLayers {
+                    array_layers: num_layers
+                });
+            },
+            ViewType::CubemapArray => {
+                if num_layers % 6 != 0 {
+                    return Err(ImageViewCreationError::InvalidCubeArrayLayers {
+                        array_layers: num_layers
+                    });
+                }
+
+                if !image.device.enabled_features().image_cube_array {
+                    return Err(ImageViewCreationError::ImageCubeArrayFeatureNotEnabled);
+                }
+            },
+            _ => ()
+        }
+
+        // Dimensions of the first mipmap level of the view.
+        let dimensions = {
+            let level = image.dimensions.mipmap_dimensions(mipmap_levels.start);
+            let layers = array_layers.end - array_layers.start;
+
+            match image.dimensions {
+                Dimensions::Dim1d { .. } | Dimensions::Dim1dArray { .. } => {
+                    Dimensions::Dim1dArray { width: level[0], array_layers: layers }
+                },
+                Dimensions::Dim2d { .. } | Dimensions::Dim2dArray { .. } => {
+                    Dimensions::Dim2dArray { width: level[0], height: level[1],
+                                             array_layers: layers }
+                },
+                Dimensions::Dim3d { .. } => {
+                    Dimensions::Dim3d { width: level[0], height: level[1], depth: level[2] }
+                },
+            }
+        };
+
+        let view = {
+            let infos = vk::ImageViewCreateInfo {
+                sType: vk::STRUCTURE_TYPE_IMAGE_VIEW_CREATE_INFO,
+                pNext: ptr::null(),
+                flags: 0,   // reserved
+                image: image.internal_object(),
+                viewType: ty as u32,
+                format: format as u32,
+                components: vk::ComponentMapping {
+                    r: swizzle.r as u32,
+                    g: swizzle.g as u32,
+                    b: swizzle.b as u32,
+                    a: swizzle.a as u32,
+                },
+                subresourceRange: vk::ImageSubresourceRange {
+                    aspectMask: aspects.to_aspect_bits(),
+                    baseMipLevel: mipmap_levels.start,
+                    levelCount: mipmap_levels.end - mipmap_levels.start,
+                    baseArrayLayer: array_layers.start,
+                    layerCount: array_layers.end - array_layers.start,
+                },
+            };
+
+            let mut output = mem::uninitialized();
+            try!(check_errors(vk.CreateImageView(image.device.internal_object(), &infos,
+                                                 ptr::null(), &mut output)));
+            output
+        };
+
+        Ok(UnsafeImageView {
+            view: view,
+            device: image.device.clone(),
+            usage: image.usage,
+            identity_swizzle: swizzle.is_identity(),
+            swizzle: swizzle,
+            format: format,
+            mipmap_levels: mipmap_levels,
+            array_layers: array_layers,
+            dimensions: dimensions,
+            ty: ty,
+            aspects: aspects,
+        })
+    }
+
+    /// Creates a new view from an image.
+    ///
+    /// Note that you must create the view with identity swizzling if you want to use this view
+    /// as a framebuffer attachment.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the device or host ran out of memory.
+    /// - Panicks if the mipmap levels or array layers are out of range of the image.
+    #[inline]
+    pub unsafe fn new(image: &UnsafeImage, mipmap_levels: Range<u32>, array_layers: Range<u32>,
+                      swizzle: Swizzle) -> UnsafeImageView
+    {
+        UnsafeImageView::raw(image, mipmap_levels, array_layers, swizzle).unwrap()
     }
 
     #[inline]
-    pub fn usage_transfer_src(&self) -> bool {
-        (self.usage & vk::IMAGE_USAGE_TRANSFER_SRC_BIT) != 0
+    pub fn format(&self) -> Format {
+        self.format
     }
 
+    /// Returns the range of mipmap levels of the image that the view covers.
     #[inline]
-    pub fn usage_transfer_dest(&self) -> bool {
-        (self.usage & vk::IMAGE_USAGE_TRANSFER_DST_BIT) != 0
+    pub fn mipmap_levels(&self) -> Range<u32> {
+        self.mipmap_levels.clone()
     }
 
+    /// Returns the range of array layers of the image that the view covers.
     #[inline]
-    pub fn usage_sampled(&self) -> bool {
-        (self.usage & vk::IMAGE_USAGE_SAMPLED_BIT) != 0
+    pub fn array_layers(&self) -> Range<u32> {
+        self.array_layers.clone()
     }
 
+    /// Returns the mapping of the components of the view.
     #[inline]
-    pub fn usage_storage(&self) -> bool {
-        (self.usage & vk::IMAGE_USAGE_STORAGE_BIT) != 0
+    pub fn swizzle(&self) -> Swizzle {
+        self.swizzle
     }
 
+    /// Returns true if the view doesn't remap any component.
     #[inline]
-    pub fn usage_color_attachment(&self) -> bool {
-        (self.usage & vk::IMAGE_USAGE_COLOR_ATTACHMENT_BIT) != 0
+    pub fn identity_swizzle(&self) -> bool {
+        self.identity_swizzle
     }
 
+    /// Returns the type of the view.
     #[inline]
-    pub fn usage_depth_stencil_attachment(&self) -> bool {
-        (self.usage & vk::IMAGE_USAGE_DEPTH_STENCIL_ATTACHMENT_BIT) != 0
+    pub fn ty(&self) -> ViewType {
+        self.ty
     }
 
+    /// Returns the aspects of the image covered by the view.
     #[inline]
-    pub fn usage_transient_attachment(&self) -> bool {
-        (self.usage & vk::IMAGE_USAGE_TRANSIENT_ATTACHMENT_BIT) != 0
+    pub fn aspects(&self) -> ImageAspects {
+        self.aspects
     }
 
+    /// Returns the dimensions of the first mipmap level of the view.
+    ///
+    /// The number of array layers is the number of layers covered by the view.
     #[inline]
-    pub fn usage_input_attachment(&self) -> bool {
-        (self.usage & vk::IMAGE_USAGE_INPUT_ATTACHMENT_BIT) != 0
+    pub fn dimensions(&self) -> Dimensions {
+        self.dimensions
     }
 }
 
@@ -769,6 +1319,252 @@ impl Drop for UnsafeImageView {
     }
 }
 
+/// Type of an image view.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u32)]
+pub enum ViewType {
+    Dim1d = vk::IMAGE_VIEW_TYPE_1D,
+    Dim1dArray = vk::IMAGE_VIEW_TYPE_1D_ARRAY,
+    Dim2d = vk::IMAGE_VIEW_TYPE_2D,
+    Dim2dArray = vk::IMAGE_VIEW_TYPE_2D_ARRAY,
+    Dim3d = vk::IMAGE_VIEW_TYPE_3D,
+    Cubemap = vk::IMAGE_VIEW_TYPE_CUBE,
+    CubemapArray = vk::IMAGE_VIEW_TYPE_CUBE_ARRAY,
+}
+
+/// Error that can happen when creating an image view.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImageViewCreationError {
+    /// Not enough memory.
+    OomError(OomError),
+    /// The range of mipmap levels is empty or goes beyond the mipmap levels of the image.
+    MipmapLevelsOutOfRange { range: Range<u32>, image_mipmap_levels: u32 },
+    /// The range of array layers is empty or goes beyond the array layers of the image.
+    ArrayLayersOutOfRange { range: Range<u32>, image_array_layers: u32 },
+    /// The type of the view is not compatible with the dimensions of the image, or a cube view
+    /// was requested for an image that is not cube-compatible.
+    IncompatibleViewType { ty: ViewType },
+    /// A cube view doesn't cover 6 array layers, or a cube array view doesn't cover a multiple
+    /// of 6 array layers.
+    InvalidCubeArrayLayers { array_layers: u32 },
+    /// The `image_cube_array` feature must be enabled in order to create cube array views.
+    ImageCubeArrayFeatureNotEnabled,
+    /// The aspects of the view are empty or are not all part of the format of the image.
+    InvalidAspects { aspects: ImageAspects },
+    /// The format of the view is different from the format of the image, but the image wasn't
+    /// created with the mutable format flag.
+    FormatNotMutable,
+    /// The format of the view is not in the same compatibility class as the format of the image.
+    IncompatibleFormat { image_format: Format, view_format: Format },
+}
+
+impl error::Error for ImageViewCreationError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            ImageViewCreationError::OomError(_) => "not enough memory available",
+            ImageViewCreationError::MipmapLevelsOutOfRange { .. } => "the range of mipmap levels \
+                                                                     is out of range of the image",
+            ImageViewCreationError::ArrayLayersOutOfRange { .. } => "the range of array layers is \
+                                                                    out of range of the image",
+            ImageViewCreationError::IncompatibleViewType { .. } => "the type of the view is not \
+                                                                   compatible with the image",
+            ImageViewCreationError::InvalidCubeArrayLayers { .. } => "the number of array layers \
+                                                                     is invalid for a cube view",
+            ImageViewCreationError::ImageCubeArrayFeatureNotEnabled => {
+                "the `image_cube_array` feature must be enabled to create cube array views"
+            },
+            ImageViewCreationError::InvalidAspects { .. } => {
+                "the aspects of the view are empty or are not part of the format of the image"
+            },
+            ImageViewCreationError::FormatNotMutable => {
+                "the format of the view is different from the format of the image, but the image \
+                 wasn't created with the mutable format flag"
+            },
+            ImageViewCreationError::IncompatibleFormat { .. } => {
+                "the format of the view is not compatible with the format of the image"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            ImageViewCreationError::OomError(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for ImageViewCreationError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        try!(write!(fmt, "{}", error::Error::description(self)));
+
+        // The most common mistake is to mix up the sRGB and UNORM versions of a format, in
+        // which case we can point to the right one.
+        if let ImageViewCreationError::IncompatibleFormat { image_format, .. } = *self {
+            let twin = image_format.srgb_equivalent().or(image_format.unorm_equivalent());
+            if let Some(twin) = twin {
+                try!(write!(fmt, " (a view of a {:?} image can use {:?})", image_format, twin));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl From<OomError> for ImageViewCreationError {
+    #[inline]
+    fn from(err: OomError) -> ImageViewCreationError {
+        ImageViewCreationError::OomError(err)
+    }
+}
+
+impl From<Error> for ImageViewCreationError {
+    #[inline]
+    fn from(err: Error) -> ImageViewCreationError {
+        ImageViewCreationError::OomError(OomError::from(err))
+    }
+}
+
+/// Checks whether a copy between a buffer and a region of a mipmap level of an image is valid.
+///
+/// `level_dimensions` are the dimensions of the mipmap level, `offset` and `extent` describe the
+/// region in texels, and `array_layers` is the number of array layers copied. `buffer_offset` and
+/// `buffer_size` are the offset and the size in bytes of the part of the buffer that is accessed.
+///
+/// For compressed formats the offset must be a multiple of the block dimensions, and so must be
+/// the extent unless the region touches the edge of the mipmap level. The buffer offset must be
+/// a multiple of the size of a block, and the buffer must be large enough to contain all the
+/// blocks covered by the region.
+#[inline]
+pub fn check_buffer_image_copy(format: Format, level_dimensions: [u32; 3], offset: [u32; 3],
+                               extent: [u32; 3], array_layers: u32, buffer_offset: usize,
+                               buffer_size: usize) -> Result<(), BufferImageCopyError>
+{
+    check_buffer_image_copy_layout(format, level_dimensions, offset, extent, array_layers,
+                                   buffer_offset, 0, 0, buffer_size)
+}
+
+/// Same as `check_buffer_image_copy`, but for data that isn't tightly packed in the buffer.
+///
+/// `buffer_row_length` and `buffer_image_height` are the number of texels of a row and the number
+/// of rows of an image in the buffer, or 0 if the data is tightly packed in that direction. They
+/// must not be smaller than the extent of the region. The buffer must be large enough to contain
+/// the last block of the region once the padding is taken into account.
+pub fn check_buffer_image_copy_layout(format: Format, level_dimensions: [u32; 3],
+                                      offset: [u32; 3], extent: [u32; 3], array_layers: u32,
+                                      buffer_offset: usize, buffer_row_length: u32,
+                                      buffer_image_height: u32, buffer_size: usize)
+                                      -> Result<(), BufferImageCopyError>
+{
+    for i in 0 .. 3 {
+        let end = offset[i].checked_add(extent[i]);
+        if extent[i] == 0 || end.map_or(true, |end| end > level_dimensions[i]) {
+            return Err(BufferImageCopyError::RegionOutOfRange);
+        }
+    }
+
+    let block_dims = format.block_dimensions();
+
+    for i in 0 .. 2 {
+        if offset[i] % block_dims[i] != 0 {
+            return Err(BufferImageCopyError::UnalignedImageOffset);
+        }
+
+        if extent[i] % block_dims[i] != 0 && offset[i] + extent[i] != level_dimensions[i] {
+            return Err(BufferImageCopyError::UnalignedImageExtent);
+        }
+    }
+
+    if (buffer_row_length != 0 && buffer_row_length < extent[0]) ||
+       (buffer_image_height != 0 && buffer_image_height < extent[1])
+    {
+        return Err(BufferImageCopyError::InvalidBufferLayout);
+    }
+
+    if let Some(block_size) = format.block_size() {
+        if buffer_offset % block_size != 0 {
+            return Err(BufferImageCopyError::UnalignedBufferOffset);
+        }
+
+        let blocks = |texels: u32, block: u32| {
+            (texels as usize + block as usize - 1) / block as usize
+        };
+        let row_length = if buffer_row_length == 0 { extent[0] } else { buffer_row_length };
+        let image_height = if buffer_image_height == 0 { extent[1] } else { buffer_image_height };
+        let row_pitch = blocks(row_length, block_dims[0]) * block_size;
+        let slice_pitch = blocks(image_height, block_dims[1]) * row_pitch;
+        let slices = extent[2] as usize * array_layers as usize;
+
+        // The last row of the last slice doesn't need to contain the padding.
+        let required = (slices - 1) * slice_pitch +
+                       (blocks(extent[1], block_dims[1]) - 1) * row_pitch +
+                       blocks(extent[0], block_dims[0]) * block_size;
+        if buffer_size < required {
+            return Err(BufferImageCopyError::BufferTooSmall {
+                required: required,
+                obtained: buffer_size,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Error that can happen when checking a copy between a buffer and an image.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BufferImageCopyError {
+    /// The region is empty or goes beyond the dimensions of the mipmap level.
+    RegionOutOfRange,
+    /// The offset of the region is not a multiple of the block dimensions of the format.
+    UnalignedImageOffset,
+    /// The extent of the region is not a multiple of the block dimensions of the format and
+    /// doesn't reach the edge of the mipmap level.
+    UnalignedImageExtent,
+    /// The offset within the buffer is not a multiple of the block size of the format.
+    UnalignedBufferOffset,
+    /// The row length or the image height of the data in the buffer is smaller than the extent of
+    /// the region.
+    InvalidBufferLayout,
+    /// The buffer is too small to contain the region.
+    BufferTooSmall { required: usize, obtained: usize },
+}
+
+impl error::Error for BufferImageCopyError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            BufferImageCopyError::RegionOutOfRange => {
+                "the region is out of range of the mipmap level"
+            },
+            BufferImageCopyError::UnalignedImageOffset => {
+                "the offset of the region is not a multiple of the block dimensions"
+            },
+            BufferImageCopyError::UnalignedImageExtent => {
+                "the extent of the region is not a multiple of the block dimensions"
+            },
+            BufferImageCopyError::UnalignedBufferOffset => {
+                "the offset within the buffer is not a multiple of the block size"
+            },
+            BufferImageCopyError::InvalidBufferLayout => {
+                "the row length or image height in the buffer is smaller than the region"
+            },
+            BufferImageCopyError::BufferTooSmall { .. } => {
+                "the buffer is too small to contain the region"
+            },
+        }
+    }
+}
+
+impl fmt::Display for BufferImageCopyError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Dimensions {
     Dim1d { width: u32 },
@@ -801,178 +1597,1498 @@ impl Dimensions {
         }
     }
 
-    #[inline]
-    pub fn width_height(&self) -> [u32; 2] {
-        [self.width(), self.height()]
+    #[inline]
+    pub fn width_height(&self) -> [u32; 2] {
+        [self.width(), self.height()]
+    }
+
+    #[inline]
+    pub fn depth(&self) -> u32 {
+        match *self {
+            Dimensions::Dim1d { .. } => 1,
+            Dimensions::Dim1dArray { .. } => 1,
+            Dimensions::Dim2d { .. } => 1,
+            Dimensions::Dim2dArray { .. } => 1,
+            Dimensions::Dim3d { depth, .. }  => depth,
+        }
+    }
+
+    #[inline]
+    pub fn array_layers(&self) -> u32 {
+        match *self {
+            Dimensions::Dim1d { .. } => 1,
+            Dimensions::Dim1dArray { array_layers, .. } => array_layers,
+            Dimensions::Dim2d { .. } => 1,
+            Dimensions::Dim2dArray { array_layers, .. } => array_layers,
+            Dimensions::Dim3d { .. }  => 1,
+        }
+    }
+
+    /// Returns the width, height and depth of the given mipmap level.
+    ///
+    /// Each level is half the size of the previous one, rounded down, and no dimension goes
+    /// below 1.
+    #[inline]
+    pub fn mipmap_dimensions(&self, level: u32) -> [u32; 3] {
+        let reduce = |d: u32| d.checked_shr(level).map_or(1, |d| if d == 0 { 1 } else { d });
+        [reduce(self.width()), reduce(self.height()), reduce(self.depth())]
+    }
+}
+
+/// Type of an image, ie. its number of dimensions.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u32)]
+pub enum ImageType {
+    Dim1d = vk::IMAGE_TYPE_1D,
+    Dim2d = vk::IMAGE_TYPE_2D,
+    Dim3d = vk::IMAGE_TYPE_3D,
+}
+
+macro_rules! image_usage {
+    ($($(#[$attr:meta])* $field:ident => $accessor:ident, $bit:ident,)+) => (
+        /// Describes how an image is going to be used. This is **not** an optimization.
+        ///
+        /// If you try to use an image in a way that you didn't declare, a panic will happen.
+        ///
+        /// If `transient_attachment` is true, then only `color_attachment`,
+        /// `depth_stencil_attachment` and `input_attachment` can be true as well. The rest must
+        /// be false or an error will be returned when creating the image.
+        ///
+        /// Some methods are provided to build `Usage` structs for some common situations.
+        /// However there is no restriction in the combination of usages that can be enabled.
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+        pub struct Usage {
+            $(
+                $(#[$attr])*
+                pub $field: bool,
+            )+
+        }
+
+        impl Usage {
+            /// Builds a `Usage` with all values set to true. Note that using the returned value
+            /// will produce an error because of `transient_attachment` being true.
+            #[inline]
+            pub fn all() -> Usage {
+                Usage {
+                    $(
+                        $field: true,
+                    )+
+                }
+            }
+
+            /// Builds a `Usage` with all values set to false. Useful as a default value.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// use vulkano::image::ImageUsage;
+            ///
+            /// let _usage = ImageUsage {
+            ///     transfer_dest: true,
+            ///     sampled: true,
+            ///     .. ImageUsage::none()
+            /// };
+            /// ```
+            #[inline]
+            pub fn none() -> Usage {
+                Usage {
+                    $(
+                        $field: false,
+                    )+
+                }
+            }
+
+            #[doc(hidden)]
+            #[inline]
+            pub fn to_usage_bits(&self) -> vk::ImageUsageFlagBits {
+                let mut result = 0;
+                $(
+                    if self.$field { result |= vk::$bit; }
+                )+
+                result
+            }
+
+            #[inline]
+            #[doc(hidden)]
+            pub fn from_bits(val: vk::ImageUsageFlagBits) -> Usage {
+                Usage {
+                    $(
+                        $field: (val & vk::$bit) != 0,
+                    )+
+                }
+            }
+        }
+
+        impl UnsafeImage {
+            $(
+                #[inline]
+                pub fn $accessor(&self) -> bool {
+                    (self.usage & vk::$bit) != 0
+                }
+            )+
+        }
+
+        impl UnsafeImageView {
+            $(
+                #[inline]
+                pub fn $accessor(&self) -> bool {
+                    (self.usage & vk::$bit) != 0
+                }
+            )+
+        }
+    );
+}
+
+image_usage! {
+    /// Can be used a source for transfers. Includes blits.
+    transfer_source => usage_transfer_src, IMAGE_USAGE_TRANSFER_SRC_BIT,
+
+    /// Can be used a destination for transfers. Includes blits.
+    transfer_dest => usage_transfer_dest, IMAGE_USAGE_TRANSFER_DST_BIT,
+
+    /// Can be sampled from a shader.
+    sampled => usage_sampled, IMAGE_USAGE_SAMPLED_BIT,
+
+    /// Can be used as an image storage in a shader.
+    storage => usage_storage, IMAGE_USAGE_STORAGE_BIT,
+
+    /// Can be attached as a color attachment to a framebuffer.
+    color_attachment => usage_color_attachment, IMAGE_USAGE_COLOR_ATTACHMENT_BIT,
+
+    /// Can be attached as a depth, stencil or depth-stencil attachment to a framebuffer.
+    depth_stencil_attachment => usage_depth_stencil_attachment,
+                                IMAGE_USAGE_DEPTH_STENCIL_ATTACHMENT_BIT,
+
+    /// Indicates that this image will only ever be used as a temporary framebuffer attachment.
+    /// As soon as you leave a render pass, the content of transient images becomes undefined.
+    ///
+    /// This is a hint to the Vulkan implementation that it may not need allocate any memory for
+    /// this image if the image can live entirely in some cache.
+    transient_attachment => usage_transient_attachment, IMAGE_USAGE_TRANSIENT_ATTACHMENT_BIT,
+
+    /// Can be used as an input attachment. In other words, you can draw to it in a subpass then
+    /// read from it in a following pass.
+    input_attachment => usage_input_attachment, IMAGE_USAGE_INPUT_ATTACHMENT_BIT,
+}
+
+impl Usage {
+    /// Builds a `Usage` with `color_attachment` set to true and the rest to false.
+    #[inline]
+    pub fn color_attachment() -> Usage {
+        Usage {
+            color_attachment: true,
+            .. Usage::none()
+        }
+    }
+
+    /// Builds a `Usage` with `depth_stencil_attachment` set to true and the rest to false.
+    #[inline]
+    pub fn depth_stencil_attachment() -> Usage {
+        Usage {
+            depth_stencil_attachment: true,
+            .. Usage::none()
+        }
+    }
+
+    /// Builds a `Usage` with `sampled` and `transfer_dest` set to true and the rest to false.
+    ///
+    /// This is the usage of an image that is filled once by a transfer and then sampled.
+    #[inline]
+    pub fn sampled_transfer_dest() -> Usage {
+        Usage {
+            sampled: true,
+            transfer_dest: true,
+            .. Usage::none()
+        }
+    }
+}
+
+/// Describes which aspects of an image a view covers.
+///
+/// Views of images with a depth-stencil format can cover either the depth aspect, the stencil
+/// aspect, or both. Views that are sampled must only cover one of them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ImageAspects {
+    pub color: bool,
+    pub depth: bool,
+    pub stencil: bool,
+}
+
+impl ImageAspects {
+    /// Builds an `ImageAspects` with all fields set to false.
+    #[inline]
+    pub fn none() -> ImageAspects {
+        ImageAspects {
+            color: false,
+            depth: false,
+            stencil: false,
+        }
+    }
+
+    /// Returns all the aspects of the given format.
+    #[inline]
+    pub fn from_format(format: Format) -> ImageAspects {
+        ImageAspects {
+            color: !format.has_depth() && !format.has_stencil(),
+            depth: format.has_depth(),
+            stencil: format.has_stencil(),
+        }
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub fn from_bits(val: vk::ImageAspectFlags) -> ImageAspects {
+        ImageAspects {
+            color: (val & vk::IMAGE_ASPECT_COLOR_BIT) != 0,
+            depth: (val & vk::IMAGE_ASPECT_DEPTH_BIT) != 0,
+            stencil: (val & vk::IMAGE_ASPECT_STENCIL_BIT) != 0,
+        }
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub fn to_aspect_bits(&self) -> vk::ImageAspectFlags {
+        let mut result = 0;
+        if self.color { result |= vk::IMAGE_ASPECT_COLOR_BIT; }
+        if self.depth { result |= vk::IMAGE_ASPECT_DEPTH_BIT; }
+        if self.stencil { result |= vk::IMAGE_ASPECT_STENCIL_BIT; }
+        result
+    }
+}
+
+/// Sparse flags of an image.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SparseLevel {
+    /// The memory of the image is bound with `Queue::bind_sparse`.
+    pub sparse: bool,
+    /// The image can be partially bound to memory.
+    pub sparse_residency: bool,
+    /// Multiple parts of the image, or of other images, can be bound to the same memory.
+    pub sparse_aliased: bool,
+}
+
+impl SparseLevel {
+    #[inline]
+    pub fn none() -> SparseLevel {
+        SparseLevel {
+            sparse: false,
+            sparse_residency: false,
+            sparse_aliased: false,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn to_flags(&self) -> vk::ImageCreateFlagBits {
+        let mut result = 0;
+        if self.sparse { result |= vk::IMAGE_CREATE_SPARSE_BINDING_BIT; }
+        if self.sparse_residency { result |= vk::IMAGE_CREATE_SPARSE_RESIDENCY_BIT; }
+        if self.sparse_aliased { result |= vk::IMAGE_CREATE_SPARSE_ALIASED_BIT; }
+        result
+    }
+}
+
+/// Flags that an image is created with, other than the sparse flags.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ImageCreateFlags {
+    /// The image can be used to create cube and cube array views. It must then be a
+    /// two-dimensional square image whose number of array layers is a multiple of 6.
+    pub cube_compatible: bool,
+    /// Views of the image can use a format different from the format of the image, as long as
+    /// both formats are in the same compatibility class.
+    pub mutable_format: bool,
+}
+
+impl ImageCreateFlags {
+    #[inline]
+    pub fn none() -> ImageCreateFlags {
+        ImageCreateFlags {
+            cube_compatible: false,
+            mutable_format: false,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn to_flags(&self) -> vk::ImageCreateFlagBits {
+        let mut result = 0;
+        if self.cube_compatible { result |= vk::IMAGE_CREATE_CUBE_COMPATIBLE_BIT; }
+        if self.mutable_format { result |= vk::IMAGE_CREATE_MUTABLE_FORMAT_BIT; }
+        result
+    }
+}
+
+/// Describes how the memory of an aspect of a sparse resident image is laid out.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SparseImageMemoryRequirements {
+    /// Aspects of the image that these requirements apply to.
+    pub aspects: ImageAspects,
+    /// Width, height and depth in texels of a sparse block.
+    pub block_extent: [u32; 3],
+    /// If true, there is only one mipmap tail for all the array layers.
+    pub single_mip_tail: bool,
+    /// If true, the first mipmap level whose dimensions are not a multiple of the sparse block
+    /// size starts the mipmap tail.
+    pub aligned_mip_size: bool,
+    /// If true, the block shape doesn't correspond to the standard sparse block shape of the
+    /// format.
+    pub nonstandard_block_size: bool,
+    /// First mipmap level that is part of the mipmap tail.
+    pub mip_tail_first_level: u32,
+    /// Size in bytes of the mipmap tail of each array layer, or of the whole image if
+    /// `single_mip_tail` is true.
+    pub mip_tail_size: usize,
+    /// Offset of the mipmap tail in the opaque binding range of the image.
+    pub mip_tail_offset: usize,
+    /// Offset between the mipmap tails of two consecutive array layers.
+    pub mip_tail_stride: usize,
+}
+
+#[doc(hidden)]
+impl From<vk::SparseImageMemoryRequirements> for SparseImageMemoryRequirements {
+    #[inline]
+    fn from(reqs: vk::SparseImageMemoryRequirements) -> SparseImageMemoryRequirements {
+        let props = &reqs.formatProperties;
+
+        SparseImageMemoryRequirements {
+            aspects: ImageAspects::from_bits(props.aspectMask),
+            block_extent: [props.imageGranularity.width, props.imageGranularity.height,
+                           props.imageGranularity.depth],
+            single_mip_tail: (props.flags & vk::SPARSE_IMAGE_FORMAT_SINGLE_MIPTAIL_BIT) != 0,
+            aligned_mip_size: (props.flags & vk::SPARSE_IMAGE_FORMAT_ALIGNED_MIP_SIZE_BIT) != 0,
+            nonstandard_block_size: (props.flags &
+                                     vk::SPARSE_IMAGE_FORMAT_NONSTANDARD_BLOCK_SIZE_BIT) != 0,
+            mip_tail_first_level: reqs.imageMipTailFirstLod,
+            mip_tail_size: reqs.imageMipTailSize as usize,
+            mip_tail_offset: reqs.imageMipTailOffset as usize,
+            mip_tail_stride: reqs.imageMipTailStride as usize,
+        }
+    }
+}
+
+/// A single aspect of an image.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u32)]
+pub enum ImageAspect {
+    Color = vk::IMAGE_ASPECT_COLOR_BIT,
+    Depth = vk::IMAGE_ASPECT_DEPTH_BIT,
+    Stencil = vk::IMAGE_ASPECT_STENCIL_BIT,
+}
+
+/// Describes how a subresource of a linear image is laid out in memory.
+///
+/// Obtained with `UnsafeImage::subresource_layout`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SubresourceLayout {
+    /// Offset in bytes of the subresource from the start of the memory of the image.
+    pub offset: usize,
+    /// Size in bytes of the subresource.
+    pub size: usize,
+    /// Number of bytes between two consecutive rows of texels.
+    pub row_pitch: usize,
+    /// Number of bytes between two consecutive array layers.
+    pub array_pitch: usize,
+    /// Number of bytes between two consecutive depth slices.
+    pub depth_pitch: usize,
+}
+
+#[doc(hidden)]
+impl From<vk::SubresourceLayout> for SubresourceLayout {
+    #[inline]
+    fn from(layout: vk::SubresourceLayout) -> SubresourceLayout {
+        SubresourceLayout {
+            offset: layout.offset as usize,
+            size: layout.size as usize,
+            row_pitch: layout.rowPitch as usize,
+            array_pitch: layout.arrayPitch as usize,
+            depth_pitch: layout.depthPitch as usize,
+        }
+    }
+}
+
+/// Error that can happen when binding memory to an image.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImageMemoryBindError {
+    /// Not enough memory.
+    OomError(OomError),
+    /// Memory is already bound to the image.
+    AlreadyBound,
+    /// The type of the memory is not one of the types allowed by the requirements of the image.
+    WrongMemoryType,
+    /// The offset is not a multiple of the alignment required by the image.
+    UnalignedOffset { offset: usize, alignment: usize },
+    /// There isn't enough memory after the offset to hold the image.
+    MemoryTooSmall { required: usize, available: usize },
+}
+
+impl error::Error for ImageMemoryBindError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            ImageMemoryBindError::OomError(_) => "not enough memory available",
+            ImageMemoryBindError::AlreadyBound => "memory is already bound to the image",
+            ImageMemoryBindError::WrongMemoryType => {
+                "the type of the memory is not allowed by the requirements of the image"
+            },
+            ImageMemoryBindError::UnalignedOffset { .. } => {
+                "the offset is not a multiple of the alignment required by the image"
+            },
+            ImageMemoryBindError::MemoryTooSmall { .. } => {
+                "there isn't enough memory after the offset to hold the image"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            ImageMemoryBindError::OomError(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for ImageMemoryBindError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<OomError> for ImageMemoryBindError {
+    #[inline]
+    fn from(err: OomError) -> ImageMemoryBindError {
+        ImageMemoryBindError::OomError(err)
+    }
+}
+
+/// Error that can happen when querying the layout of a subresource.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SubresourceLayoutError {
+    /// The image uses the optimal tiling, whose layout is implementation-defined.
+    OptimalTiling,
+    /// The mipmap level is out of range.
+    MipmapLevelOutOfRange { obtained: u32, mipmap_levels: u32 },
+    /// The array layer is out of range.
+    ArrayLayerOutOfRange { obtained: u32, array_layers: u32 },
+    /// The format of the image doesn't have the requested aspect.
+    AspectNotPresent { aspect: ImageAspect },
+}
+
+impl error::Error for SubresourceLayoutError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            SubresourceLayoutError::OptimalTiling => {
+                "the image uses the optimal tiling, whose layout is implementation-defined"
+            },
+            SubresourceLayoutError::MipmapLevelOutOfRange { .. } => {
+                "the mipmap level is out of range"
+            },
+            SubresourceLayoutError::ArrayLayerOutOfRange { .. } => {
+                "the array layer is out of range"
+            },
+            SubresourceLayoutError::AspectNotPresent { .. } => {
+                "the format of the image doesn't have the requested aspect"
+            },
+        }
+    }
+}
+
+impl fmt::Display for SubresourceLayoutError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+/// Number of samples per pixel of an image.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u32)]
+pub enum SampleCount {
+    Sample1 = vk::SAMPLE_COUNT_1_BIT,
+    Sample2 = vk::SAMPLE_COUNT_2_BIT,
+    Sample4 = vk::SAMPLE_COUNT_4_BIT,
+    Sample8 = vk::SAMPLE_COUNT_8_BIT,
+    Sample16 = vk::SAMPLE_COUNT_16_BIT,
+    Sample32 = vk::SAMPLE_COUNT_32_BIT,
+    Sample64 = vk::SAMPLE_COUNT_64_BIT,
+}
+
+impl SampleCount {
+    /// Returns the `SampleCount` that corresponds to a number of samples, or `None` if the
+    /// number is not a valid sample count.
+    #[inline]
+    pub fn from_count(count: u32) -> Option<SampleCount> {
+        match count {
+            1 => Some(SampleCount::Sample1),
+            2 => Some(SampleCount::Sample2),
+            4 => Some(SampleCount::Sample4),
+            8 => Some(SampleCount::Sample8),
+            16 => Some(SampleCount::Sample16),
+            32 => Some(SampleCount::Sample32),
+            64 => Some(SampleCount::Sample64),
+            _ => None
+        }
+    }
+
+    /// Returns the number of samples per pixel.
+    #[inline]
+    pub fn count(&self) -> u32 {
+        // The Vulkan flag of each sample count is equal to the number of samples.
+        *self as u32
+    }
+}
+
+/// List of sample counts that are supported for a kind of image.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SupportedSampleCounts {
+    pub sample1: bool,
+    pub sample2: bool,
+    pub sample4: bool,
+    pub sample8: bool,
+    pub sample16: bool,
+    pub sample32: bool,
+    pub sample64: bool,
+}
+
+impl SupportedSampleCounts {
+    /// Builds a `SupportedSampleCounts` with all fields set to false.
+    #[inline]
+    pub fn none() -> SupportedSampleCounts {
+        SupportedSampleCounts::from_bits(0)
+    }
+
+    /// Builds a `SupportedSampleCounts` with all fields set to true.
+    #[inline]
+    pub fn all() -> SupportedSampleCounts {
+        SupportedSampleCounts::from_bits(0x7f)
+    }
+
+    /// Returns the sample counts that the physical device supports for images of the given
+    /// format and usage, according to the limits of the device.
+    ///
+    /// Note that the Vulkan implementation may support additional sample counts for some
+    /// specific kinds of images.
+    pub fn for_image(physical_device: &PhysicalDevice, format: Format, usage: &Usage)
+                     -> SupportedSampleCounts
+    {
+        let mut supported = 0x7f;       // all bits up to VK_SAMPLE_COUNT_64_BIT
+
+        if usage.sampled {
+            match format.ty() {
+                FormatTy::Float | FormatTy::Compressed => {
+                    supported &= physical_device.limits().sampled_image_color_sample_counts();
+                },
+                FormatTy::Uint | FormatTy::Sint => {
+                    supported &= physical_device.limits().sampled_image_integer_sample_counts();
+                },
+                FormatTy::Depth => {
+                    supported &= physical_device.limits().sampled_image_depth_sample_counts();
+                },
+                FormatTy::Stencil => {
+                    supported &= physical_device.limits().sampled_image_stencil_sample_counts();
+                },
+                FormatTy::DepthStencil => {
+                    supported &= physical_device.limits().sampled_image_depth_sample_counts();
+                    supported &= physical_device.limits().sampled_image_stencil_sample_counts();
+                },
+            }
+        }
+
+        if usage.storage {
+            supported &= physical_device.limits().storage_image_sample_counts();
+        }
+
+        if usage.color_attachment || usage.depth_stencil_attachment || usage.input_attachment ||
+           usage.transient_attachment
+        {
+            match format.ty() {
+                FormatTy::Float | FormatTy::Compressed | FormatTy::Uint | FormatTy::Sint => {
+                    supported &= physical_device.limits().framebuffer_color_sample_counts();
+                },
+                FormatTy::Depth => {
+                    supported &= physical_device.limits().framebuffer_depth_sample_counts();
+                },
+                FormatTy::Stencil => {
+                    supported &= physical_device.limits().framebuffer_stencil_sample_counts();
+                },
+                FormatTy::DepthStencil => {
+                    supported &= physical_device.limits().framebuffer_depth_sample_counts();
+                    supported &= physical_device.limits().framebuffer_stencil_sample_counts();
+                },
+            }
+        }
+
+        SupportedSampleCounts::from_bits(supported)
+    }
+
+    #[inline]
+    #[doc(hidden)]
+    pub fn from_bits(val: u32) -> SupportedSampleCounts {
+        SupportedSampleCounts {
+            sample1: (val & vk::SAMPLE_COUNT_1_BIT) != 0,
+            sample2: (val & vk::SAMPLE_COUNT_2_BIT) != 0,
+            sample4: (val & vk::SAMPLE_COUNT_4_BIT) != 0,
+            sample8: (val & vk::SAMPLE_COUNT_8_BIT) != 0,
+            sample16: (val & vk::SAMPLE_COUNT_16_BIT) != 0,
+            sample32: (val & vk::SAMPLE_COUNT_32_BIT) != 0,
+            sample64: (val & vk::SAMPLE_COUNT_64_BIT) != 0,
+        }
+    }
+
+    /// Returns true if the given sample count is supported.
+    #[inline]
+    pub fn contains(&self, count: SampleCount) -> bool {
+        match count {
+            SampleCount::Sample1 => self.sample1,
+            SampleCount::Sample2 => self.sample2,
+            SampleCount::Sample4 => self.sample4,
+            SampleCount::Sample8 => self.sample8,
+            SampleCount::Sample16 => self.sample16,
+            SampleCount::Sample32 => self.sample32,
+            SampleCount::Sample64 => self.sample64,
+        }
+    }
+
+    /// Returns the highest supported sample count, or `None` if no sample count is supported.
+    #[inline]
+    pub fn max(&self) -> Option<SampleCount> {
+        [SampleCount::Sample64, SampleCount::Sample32, SampleCount::Sample16,
+         SampleCount::Sample8, SampleCount::Sample4, SampleCount::Sample2, SampleCount::Sample1]
+            .iter().cloned().find(|&c| self.contains(c))
+    }
+}
+
+/// Capabilities of a physical device for a specific kind of image.
+///
+/// Obtained with `PhysicalDevice::image_format_properties`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ImageFormatProperties {
+    /// Maximum width, height and depth of an image.
+    pub max_extent: [u32; 3],
+    /// Maximum number of mipmap levels.
+    pub max_mipmap_levels: u32,
+    /// Maximum number of array layers.
+    pub max_array_layers: u32,
+    /// Sample counts that are supported.
+    pub sample_counts: SupportedSampleCounts,
+    /// Maximum size in bytes of an image, including all of its levels and layers.
+    pub max_resource_size: usize,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u32)]
+pub enum Layout {
+    Undefined = vk::IMAGE_LAYOUT_UNDEFINED,
+    General = vk::IMAGE_LAYOUT_GENERAL,
+    ColorAttachmentOptimal = vk::IMAGE_LAYOUT_COLOR_ATTACHMENT_OPTIMAL,
+    DepthStencilAttachmentOptimal = vk::IMAGE_LAYOUT_DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+    DepthStencilReadOnlyOptimal = vk::IMAGE_LAYOUT_DEPTH_STENCIL_READ_ONLY_OPTIMAL,
+    ShaderReadOnlyOptimal = vk::IMAGE_LAYOUT_SHADER_READ_ONLY_OPTIMAL,
+    TransferSrcOptimal = vk::IMAGE_LAYOUT_TRANSFER_SRC_OPTIMAL,
+    TransferDstOptimal = vk::IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL,
+    Preinitialized = vk::IMAGE_LAYOUT_PREINITIALIZED,
+    PresentSrc = vk::IMAGE_LAYOUT_PRESENT_SRC_KHR,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::iter::Empty;
+    use std::u32;
+
+    use super::BufferImageCopyError;
+    use super::Dimensions;
+    use super::ImageAspect;
+    use super::ImageAspects;
+    use super::ImageCreateFlags;
+    use super::ImageCreationError;
+    use super::ImageMemoryBindError;
+    use super::ImageType;
+    use super::ImageViewCreationError;
+    use super::SampleCount;
+    use super::SparseImageMemoryRequirements;
+    use super::SparseLevel;
+    use super::SubresourceLayoutError;
+    use super::SupportedSampleCounts;
+    use super::UnsafeImage;
+    use super::UnsafeImageView;
+    use super::Usage;
+    use super::ViewType;
+    use super::check_buffer_image_copy;
+    use super::check_buffer_image_copy_layout;
+
+    use format::Format;
+    use format::R8G8B8A8Unorm;
+    use image::Image;
+    use image::MipmapsCount;
+    use vk;
+    use image::Swizzle;
+    use image::immutable::ImmutableImage;
+    use memory::DeviceMemory;
+    use memory::pool::AllocLayout;
+    use memory::pool::MemoryPool;
+    use memory::pool::MemoryPoolAlloc;
+    use sync::Sharing;
+
+    #[test]
+    fn create_sampled() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let usage = Usage {
+            sampled: true,
+            .. Usage::none()
+        };
+
+        let (_img, _) = unsafe {
+            UnsafeImage::new(&device, &usage, Format::R8G8B8A8Unorm,
+                             Dimensions::Dim2d { width: 32, height: 32 }, 1, 1,
+                             Sharing::Exclusive::<Empty<_>>, false, false, ImageCreateFlags::none())
+        }.unwrap();
+    }
+
+    #[test]
+    fn create_transient() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let usage = Usage {
+            transient_attachment: true,
+            color_attachment: true,
+            .. Usage::none()
+        };
+
+        let (_img, _) = unsafe {
+            UnsafeImage::new(&device, &usage, Format::R8G8B8A8Unorm,
+                             Dimensions::Dim2d { width: 32, height: 32 }, 1, 1,
+                             Sharing::Exclusive::<Empty<_>>, false, false, ImageCreateFlags::none())
+        }.unwrap();
+    }
+
+    #[test]
+    fn zero_sample() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let usage = Usage {
+            sampled: true,
+            .. Usage::none()
+        };
+
+        let res = unsafe {
+            UnsafeImage::new(&device, &usage, Format::R8G8B8A8Unorm,
+                             Dimensions::Dim2d { width: 32, height: 32 }, 0, 1,
+                             Sharing::Exclusive::<Empty<_>>, false, false, ImageCreateFlags::none())
+        };
+
+        match res {
+            Err(ImageCreationError::UnsupportedSampleCount { .. }) => (),
+            _ => panic!()
+        };
+    }
+
+    #[test]
+    fn non_po2_sample() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let usage = Usage {
+            sampled: true,
+            .. Usage::none()
+        };
+
+        let res = unsafe {
+            UnsafeImage::new(&device, &usage, Format::R8G8B8A8Unorm,
+                             Dimensions::Dim2d { width: 32, height: 32 }, 5, 1,
+                             Sharing::Exclusive::<Empty<_>>, false, false, ImageCreateFlags::none())
+        };
+
+        match res {
+            Err(ImageCreationError::UnsupportedSampleCount { .. }) => (),
+            _ => panic!()
+        };
+    }
+
+    #[test]
+    fn absurd_sample_count() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let usage = Usage {
+            color_attachment: true,
+            .. Usage::none()
+        };
+
+        let res = unsafe {
+            UnsafeImage::new(&device, &usage, Format::R8G8B8A8Unorm,
+                             Dimensions::Dim2d { width: 32, height: 32 }, 128, 1,
+                             Sharing::Exclusive::<Empty<_>>, false, false, ImageCreateFlags::none())
+        };
+
+        match res {
+            Err(ImageCreationError::UnsupportedSampleCount { requested: 128, supported }) => {
+                // Attachments always support at least one and four samples.
+                assert!(supported.sample1);
+                assert!(supported.sample4);
+            },
+            _ => panic!()
+        };
+    }
+
+    #[test]
+    fn sample_counts_from_bits() {
+        let counts = SupportedSampleCounts::from_bits(0x1 | 0x4 | 0x10);
+        assert_eq!(counts, SupportedSampleCounts {
+            sample1: true,
+            sample4: true,
+            sample16: true,
+            .. SupportedSampleCounts::none()
+        });
+
+        assert!(counts.contains(SampleCount::Sample4));
+        assert!(!counts.contains(SampleCount::Sample8));
+        assert_eq!(counts.max(), Some(SampleCount::Sample16));
+        assert_eq!(SupportedSampleCounts::all().max(), Some(SampleCount::Sample64));
+        assert_eq!(SupportedSampleCounts::none().max(), None);
+    }
+
+    #[test]
+    fn sample_count_from_count() {
+        assert_eq!(SampleCount::from_count(8), Some(SampleCount::Sample8));
+        assert_eq!(SampleCount::from_count(8).unwrap().count(), 8);
+        assert_eq!(SampleCount::from_count(0), None);
+        assert_eq!(SampleCount::from_count(3), None);
+        assert_eq!(SampleCount::from_count(128), None);
+    }
+
+    #[test]
+    fn zero_mipmap() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let usage = Usage {
+            sampled: true,
+            .. Usage::none()
+        };
+
+        let res = unsafe {
+            UnsafeImage::new(&device, &usage, Format::R8G8B8A8Unorm,
+                             Dimensions::Dim2d { width: 32, height: 32 }, 1, 0,
+                             Sharing::Exclusive::<Empty<_>>, false, false, ImageCreateFlags::none())
+        };
+
+        match res {
+            Err(ImageCreationError::InvalidMipmapsCount { .. }) => (),
+            _ => panic!()
+        };
+    }
+
+    #[test]
+    #[ignore]       // TODO: AMD card seems to support a u32::MAX number of mipmaps
+    fn mipmaps_too_high() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let usage = Usage {
+            sampled: true,
+            .. Usage::none()
+        };
+
+        let res = unsafe {
+            UnsafeImage::new(&device, &usage, Format::R8G8B8A8Unorm,
+                             Dimensions::Dim2d { width: 32, height: 32 }, 1, u32::MAX,
+                             Sharing::Exclusive::<Empty<_>>, false, false, ImageCreateFlags::none())
+        };
+
+        match res {
+            Err(ImageCreationError::InvalidMipmapsCount { obtained, valid_range }) => {
+                assert_eq!(obtained, u32::MAX);
+                assert_eq!(valid_range.start, 1);
+            },
+            _ => panic!()
+        };
+    }
+
+    #[test]
+    fn log2_mipmaps_non_square() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let usage = Usage {
+            sampled: true,
+            .. Usage::none()
+        };
+
+        // The mipmaps chain goes down to 1x1, so its length depends on the largest dimension.
+        let (image, _) = unsafe {
+            UnsafeImage::new(&device, &usage, Format::R8G8B8A8Unorm,
+                             Dimensions::Dim2d { width: 256, height: 16 }, 1, MipmapsCount::Log2,
+                             Sharing::Exclusive::<Empty<_>>, false, false, ImageCreateFlags::none())
+        }.unwrap();
+
+        assert_eq!(image.mipmap_levels(), 9);
+
+        // An explicit count can't be larger than the one produced by `Log2`.
+        let res = unsafe {
+            UnsafeImage::new(&device, &usage, Format::R8G8B8A8Unorm,
+                             Dimensions::Dim2d { width: 256, height: 16 }, 1, 10,
+                             Sharing::Exclusive::<Empty<_>>, false, false, ImageCreateFlags::none())
+        };
+
+        match res {
+            Err(ImageCreationError::InvalidMipmapsCount { obtained: 10, valid_range }) => {
+                assert_eq!(valid_range, 1 .. 10);
+            },
+            _ => panic!()
+        };
+    }
+
+    #[test]
+    fn shader_storage_image_multisample() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let usage = Usage {
+            storage: true,
+            .. Usage::none()
+        };
+
+        let res = unsafe {
+            UnsafeImage::new(&device, &usage, Format::R8G8B8A8Unorm,
+                             Dimensions::Dim2d { width: 32, height: 32 }, 2, 1,
+                             Sharing::Exclusive::<Empty<_>>, false, false, ImageCreateFlags::none())
+        };
+
+        match res {
+            Err(ImageCreationError::ShaderStorageImageMultisampleFeatureNotEnabled) => (),
+            Err(ImageCreationError::UnsupportedSampleCount { .. }) => (), // unlikely but possible
+            _ => panic!()
+        };
+    }
+
+    #[test]
+    fn compressed_not_color_attachment() {
+        let (device, _) = gfx_dev_and_queue!(texture_compression_astc_ldr);
+
+        let usage = Usage {
+            color_attachment: true,
+            .. Usage::none()
+        };
+
+        let res = unsafe {
+            UnsafeImage::new(&device, &usage, Format::ASTC_5x4UnormBlock,
+                             Dimensions::Dim2d { width: 32, height: 32 }, 1, u32::MAX,
+                             Sharing::Exclusive::<Empty<_>>, false, false, ImageCreateFlags::none())
+        };
+
+        match res {
+            Err(ImageCreationError::FormatNotSupported) => (),
+            Err(ImageCreationError::UnsupportedUsage) => (),
+            _ => panic!()
+        };
+    }
+
+    #[test]
+    fn compressed_feature_not_enabled() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let usage = Usage {
+            sampled: true,
+            .. Usage::none()
+        };
+
+        let res = unsafe {
+            UnsafeImage::new(&device, &usage, Format::BC7UnormBlock,
+                             Dimensions::Dim2d { width: 32, height: 32 }, 1, 1,
+                             Sharing::Exclusive::<Empty<_>>, false, false, ImageCreateFlags::none())
+        };
+
+        match res {
+            Err(ImageCreationError::TextureCompressionFeatureNotEnabled) => (),
+            _ => panic!()
+        };
+    }
+
+    #[test]
+    fn copy_region_uncompressed() {
+        let format = Format::R8G8B8A8Unorm;
+        assert_eq!(check_buffer_image_copy(format, [16, 16, 1], [0, 0, 0], [16, 16, 1], 1, 0,
+                                           1024), Ok(()));
+        assert_eq!(check_buffer_image_copy(format, [16, 16, 1], [3, 5, 0], [7, 9, 1], 1, 0,
+                                           7 * 9 * 4), Ok(()));
+        assert_eq!(check_buffer_image_copy(format, [16, 16, 1], [8, 0, 0], [16, 16, 1], 1, 0,
+                                           2048), Err(BufferImageCopyError::RegionOutOfRange));
+        assert_eq!(check_buffer_image_copy(format, [16, 16, 1], [0, 0, 0], [16, 16, 1], 1, 0, 16),
+                   Err(BufferImageCopyError::BufferTooSmall { required: 1024, obtained: 16 }));
+    }
+
+    #[test]
+    fn copy_region_overflow() {
+        let format = Format::R8G8B8A8Unorm;
+        assert_eq!(check_buffer_image_copy(format, [16, 16, 1], [8, 0, 0], [u32::MAX, 16, 1], 1,
+                                           0, 1024), Err(BufferImageCopyError::RegionOutOfRange));
+    }
+
+    #[test]
+    fn mipmap_dimensions() {
+        let dims = Dimensions::Dim2d { width: 16, height: 4 };
+        assert_eq!(dims.mipmap_dimensions(0), [16, 4, 1]);
+        assert_eq!(dims.mipmap_dimensions(1), [8, 2, 1]);
+        assert_eq!(dims.mipmap_dimensions(3), [2, 1, 1]);
+        assert_eq!(dims.mipmap_dimensions(4), [1, 1, 1]);
+        assert_eq!(dims.mipmap_dimensions(40), [1, 1, 1]);
+    }
+
+    #[test]
+    fn copy_region_compressed() {
+        let format = Format::BC1_RGBAUnormBlock;
+
+        // Whole 16x16 level and 8-bytes aligned offset.
+        assert_eq!(check_buffer_image_copy(format, [16, 16, 1], [0, 0, 0], [16, 16, 1], 1, 8,
+                                           128), Ok(()));
+        // The 2x2 mipmap level is smaller than a block, but is still copied as one block.
+        assert_eq!(check_buffer_image_copy(format, [2, 2, 1], [0, 0, 0], [2, 2, 1], 1, 0, 8),
+                   Ok(()));
+        // A region that touches the edge of a 6x6 level doesn't need to be a multiple of 4.
+        assert_eq!(check_buffer_image_copy(format, [6, 6, 1], [4, 0, 0], [2, 6, 1], 1, 0, 16),
+                   Ok(()));
+
+        assert_eq!(check_buffer_image_copy(format, [16, 16, 1], [2, 0, 0], [4, 4, 1], 1, 0, 8),
+                   Err(BufferImageCopyError::UnalignedImageOffset));
+        assert_eq!(check_buffer_image_copy(format, [16, 16, 1], [0, 0, 0], [6, 4, 1], 1, 0, 16),
+                   Err(BufferImageCopyError::UnalignedImageExtent));
+        assert_eq!(check_buffer_image_copy(format, [16, 16, 1], [0, 0, 0], [4, 4, 1], 1, 4, 8),
+                   Err(BufferImageCopyError::UnalignedBufferOffset));
+        assert_eq!(check_buffer_image_copy(format, [16, 16, 1], [0, 0, 0], [16, 16, 1], 2, 0,
+                                           128),
+                   Err(BufferImageCopyError::BufferTooSmall { required: 256, obtained: 128 }));
+    }
+
+    #[test]
+    fn copy_region_padded() {
+        let format = Format::R8G8B8A8Unorm;
+
+        // Rows of 32 texels, the last row doesn't need its padding.
+        assert_eq!(check_buffer_image_copy_layout(format, [16, 16, 1], [0, 0, 0], [16, 16, 1],
+                                                  1, 0, 32, 0, 15 * 128 + 64), Ok(()));
+        assert_eq!(check_buffer_image_copy_layout(format, [16, 16, 1], [0, 0, 0], [16, 16, 1],
+                                                  1, 0, 32, 0, 15 * 128 + 63),
+                   Err(BufferImageCopyError::BufferTooSmall { required: 1984, obtained: 1983 }));
+        // Two layers of 20 rows.
+        assert_eq!(check_buffer_image_copy_layout(format, [16, 16, 1], [0, 0, 0], [16, 16, 1],
+                                                  2, 0, 0, 20, 20 * 64 + 1024), Ok(()));
+
+        assert_eq!(check_buffer_image_copy_layout(format, [16, 16, 1], [0, 0, 0], [16, 16, 1],
+                                                  1, 0, 8, 0, 4096),
+                   Err(BufferImageCopyError::InvalidBufferLayout));
+        assert_eq!(check_buffer_image_copy_layout(format, [16, 16, 1], [0, 0, 0], [16, 16, 1],
+                                                  1, 0, 0, 15, 4096),
+                   Err(BufferImageCopyError::InvalidBufferLayout));
+
+        // Compressed: rows of 8 blocks of 8 bytes, with 4 blocks copied per row.
+        let format = Format::BC1_RGBAUnormBlock;
+        assert_eq!(check_buffer_image_copy_layout(format, [16, 16, 1], [0, 0, 0], [16, 16, 1],
+                                                  1, 0, 32, 0, 3 * 64 + 32), Ok(()));
+    }
+
+    #[test]
+    fn image_format_properties() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let usage = Usage {
+            sampled: true,
+            .. Usage::none()
+        };
+
+        // This combination is required to be supported by all implementations.
+        let properties = device.physical_device()
+                               .image_format_properties(Format::R8G8B8A8Unorm, ImageType::Dim2d,
+                                                        false, &usage, ImageCreateFlags::none(),
+                                                        SparseLevel::none())
+                               .unwrap().unwrap();
+
+        assert!(properties.max_extent[0] >= 4096);
+        assert!(properties.max_mipmap_levels >= 1);
+        assert!(properties.max_array_layers >= 1);
+        assert!(properties.sample_counts.sample1);
+    }
+
+    #[test]
+    fn format_not_supported() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let usage = Usage {
+            depth_stencil_attachment: true,
+            .. Usage::none()
+        };
+
+        // Depth-stencil formats are practically never supported with linear tiling.
+        let res = unsafe {
+            UnsafeImage::new_checked(&device, &usage, Format::D24Unorm_S8Uint,
+                                     Dimensions::Dim2d { width: 32, height: 32 }, 1, 1,
+                                     Sharing::Exclusive::<Empty<_>>, true, false,
+                                     ImageCreateFlags::none())
+        };
+
+        match res {
+            Err(ImageCreationError::FormatNotSupported) => (),
+            Err(ImageCreationError::UnsupportedUsage) => (),
+            _ => panic!()
+        };
+    }
+
+    #[test]
+    fn dimensions_too_large() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let usage = Usage {
+            sampled: true,
+            .. Usage::none()
+        };
+
+        let res = unsafe {
+            UnsafeImage::new_checked(&device, &usage, Format::R8G8B8A8Unorm,
+                                     Dimensions::Dim2d { width: u32::MAX, height: 32 }, 1, 1,
+                                     Sharing::Exclusive::<Empty<_>>, false, false,
+                                     ImageCreateFlags::none())
+        };
+
+        match res {
+            Err(ImageCreationError::DimensionsTooLarge { dimensions, max_extent }) => {
+                assert_eq!(dimensions, Dimensions::Dim2d { width: u32::MAX, height: 32 });
+                assert!(max_extent[0] < u32::MAX);
+            },
+            _ => panic!()
+        };
+    }
+
+    #[test]
+    fn too_many_array_layers() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let usage = Usage {
+            sampled: true,
+            .. Usage::none()
+        };
+
+        let res = unsafe {
+            UnsafeImage::new_checked(&device, &usage, Format::R8G8B8A8Unorm,
+                                     Dimensions::Dim2dArray { width: 32, height: 32,
+                                                              array_layers: u32::MAX },
+                                     1, 1, Sharing::Exclusive::<Empty<_>>, false, false,
+                                     ImageCreateFlags::none())
+        };
+
+        match res {
+            Err(ImageCreationError::TooManyArrayLayers { requested, .. }) => {
+                assert_eq!(requested, u32::MAX);
+            },
+            _ => panic!()
+        };
+    }
+
+    #[test]
+    fn checked_mutable_format() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let usage = Usage {
+            sampled: true,
+            .. Usage::none()
+        };
+
+        let flags = ImageCreateFlags { mutable_format: true, .. ImageCreateFlags::none() };
+
+        let properties = device.physical_device()
+                               .image_format_properties(Format::R8G8B8A8Unorm, ImageType::Dim2d,
+                                                        false, &usage, flags, SparseLevel::none())
+                               .unwrap().unwrap();
+
+        let (image, _) = unsafe {
+            UnsafeImage::new_checked(&device, &usage, Format::R8G8B8A8Unorm,
+                                     Dimensions::Dim2d { width: 32, height: 32 }, 1, 1,
+                                     Sharing::Exclusive::<Empty<_>>, false, false, flags)
+        }.unwrap();
+
+        assert!(image.mutable_format());
+
+        // The image is checked against the limits reported for mutable-format images.
+        let res = unsafe {
+            UnsafeImage::new_checked(&device, &usage, Format::R8G8B8A8Unorm,
+                                     Dimensions::Dim2dArray {
+                                         width: 32,
+                                         height: 32,
+                                         array_layers: properties.max_array_layers + 1,
+                                     },
+                                     1, 1, Sharing::Exclusive::<Empty<_>>, false, false, flags)
+        };
+
+        match res {
+            Err(ImageCreationError::TooManyArrayLayers { max, .. }) => {
+                assert_eq!(max, properties.max_array_layers);
+            },
+            _ => panic!()
+        };
+    }
+
+    #[test]
+    fn sparse_binding_feature_not_enabled() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let usage = Usage {
+            sampled: true,
+            .. Usage::none()
+        };
+
+        let sparse = SparseLevel { sparse: true, .. SparseLevel::none() };
+
+        let res = unsafe {
+            UnsafeImage::new_sparse(&device, &usage, Format::R8G8B8A8Unorm,
+                                    Dimensions::Dim2d { width: 256, height: 256 }, 1, 1,
+                                    Sharing::Exclusive::<Empty<_>>, sparse,
+                                    ImageCreateFlags::none())
+        };
+
+        match res {
+            Err(ImageCreationError::SparseBindingFeatureNotEnabled) => (),
+            _ => panic!()
+        };
+    }
+
+    #[test]
+    fn sparse_residency_feature_not_enabled() {
+        let (device, _) = gfx_dev_and_queue!(sparse_binding);
+
+        let usage = Usage {
+            sampled: true,
+            .. Usage::none()
+        };
+
+        let sparse = SparseLevel { sparse: true, sparse_residency: true, sparse_aliased: false };
+
+        let res = unsafe {
+            UnsafeImage::new_sparse(&device, &usage, Format::R8G8B8A8Unorm,
+                                    Dimensions::Dim2d { width: 256, height: 256 }, 1, 1,
+                                    Sharing::Exclusive::<Empty<_>>, sparse,
+                                    ImageCreateFlags::none())
+        };
+
+        match res {
+            Err(ImageCreationError::SparseResidencyImage2dFeatureNotEnabled) => (),
+            _ => panic!()
+        };
+    }
+
+    #[test]
+    #[should_panic = "Can't create a sparse image without enabling sparse binding"]
+    fn panic_sparse_without_binding() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let usage = Usage {
+            sampled: true,
+            .. Usage::none()
+        };
+
+        let sparse = SparseLevel { sparse: false, sparse_residency: true, sparse_aliased: false };
+
+        let _ = unsafe {
+            UnsafeImage::new_sparse(&device, &usage, Format::R8G8B8A8Unorm,
+                                    Dimensions::Dim2d { width: 256, height: 256 }, 1, 1,
+                                    Sharing::Exclusive::<Empty<_>>, sparse,
+                                    ImageCreateFlags::none())
+        };
     }
 
-    #[inline]
-    pub fn depth(&self) -> u32 {
-        match *self {
-            Dimensions::Dim1d { .. } => 1,
-            Dimensions::Dim1dArray { .. } => 1,
-            Dimensions::Dim2d { .. } => 1,
-            Dimensions::Dim2dArray { .. } => 1,
-            Dimensions::Dim3d { depth, .. }  => depth,
-        }
-    }
+    #[test]
+    fn sparse_requirements_from_vk() {
+        let reqs = vk::SparseImageMemoryRequirements {
+            formatProperties: vk::SparseImageFormatProperties {
+                aspectMask: vk::IMAGE_ASPECT_COLOR_BIT,
+                imageGranularity: vk::Extent3D { width: 128, height: 128, depth: 1 },
+                flags: vk::SPARSE_IMAGE_FORMAT_SINGLE_MIPTAIL_BIT,
+            },
+            imageMipTailFirstLod: 5,
+            imageMipTailSize: 65536,
+            imageMipTailOffset: 1048576,
+            imageMipTailStride: 0,
+        };
 
-    #[inline]
-    pub fn array_layers(&self) -> u32 {
-        match *self {
-            Dimensions::Dim1d { .. } => 1,
-            Dimensions::Dim1dArray { array_layers, .. } => array_layers,
-            Dimensions::Dim2d { .. } => 1,
-            Dimensions::Dim2dArray { array_layers, .. } => array_layers,
-            Dimensions::Dim3d { .. }  => 1,
-        }
+        let reqs: SparseImageMemoryRequirements = reqs.into();
+        assert_eq!(reqs, SparseImageMemoryRequirements {
+            aspects: ImageAspects { color: true, .. ImageAspects::none() },
+            block_extent: [128, 128, 1],
+            single_mip_tail: true,
+            aligned_mip_size: false,
+            nonstandard_block_size: false,
+            mip_tail_first_level: 5,
+            mip_tail_size: 65536,
+            mip_tail_offset: 1048576,
+            mip_tail_stride: 0,
+        });
     }
-}
 
-/// Describes how an image is going to be used. This is **not** an optimization.
-///
-/// If you try to use an image in a way that you didn't declare, a panic will happen.
-///
-/// If `transient_attachment` is true, then only `color_attachment`, `depth_stencil_attachment`
-/// and `input_attachment` can be true as well. The rest must be false or an error will be returned
-/// when creating the image.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-pub struct Usage {
-    /// Can be used a source for transfers. Includes blits.
-    pub transfer_source: bool,
+    #[test]
+    fn linear_subresource_layout() {
+        let (device, _) = gfx_dev_and_queue!();
 
-    /// Can be used a destination for transfers. Includes blits.
-    pub transfer_dest: bool,
+        let usage = Usage {
+            transfer_source: true,
+            .. Usage::none()
+        };
 
-    /// Can be sampled from a shader.
-    pub sampled: bool,
+        let (img, _) = unsafe {
+            UnsafeImage::new(&device, &usage, Format::R8G8B8A8Unorm,
+                             Dimensions::Dim2d { width: 17, height: 5 }, 1, 1,
+                             Sharing::Exclusive::<Empty<_>>, true, true, ImageCreateFlags::none())
+        }.unwrap();
 
-    /// Can be used as an image storage in a shader.
-    pub storage: bool,
+        assert!(img.linear_tiling());
 
-    /// Can be attached as a color attachment to a framebuffer.
-    pub color_attachment: bool,
+        let layout = img.subresource_layout(ImageAspect::Color, 0, 0).unwrap();
+        assert!(layout.row_pitch >= 17 * 4);
+        assert!(layout.size >= layout.row_pitch * 4 + 17 * 4);
 
-    /// Can be attached as a depth, stencil or depth-stencil attachment to a framebuffer.
-    pub depth_stencil_attachment: bool,
+        match img.subresource_layout(ImageAspect::Color, 1, 0) {
+            Err(SubresourceLayoutError::MipmapLevelOutOfRange { obtained: 1,
+                                                                 mipmap_levels: 1 }) => (),
+            _ => panic!()
+        };
 
-    /// Indicates that this image will only ever be used as a temporary framebuffer attachment.
-    /// As soon as you leave a render pass, the content of transient images becomes undefined.
-    ///
-    /// This is a hint to the Vulkan implementation that it may not need allocate any memory for
-    /// this image if the image can live entirely in some cache.
-    pub transient_attachment: bool,
+        match img.subresource_layout(ImageAspect::Depth, 0, 0) {
+            Err(SubresourceLayoutError::AspectNotPresent { aspect: ImageAspect::Depth }) => (),
+            _ => panic!()
+        };
+    }
 
-    /// Can be used as an input attachment. In other words, you can draw to it in a subpass then
-    /// read from it in a following pass.
-    pub input_attachment: bool,
-}
+    #[test]
+    fn optimal_subresource_layout() {
+        let (device, _) = gfx_dev_and_queue!();
 
-impl Usage {
-    /// Builds a `Usage` with all values set to true. Note that using the returned value will
-    /// produce an error because of `transient_attachment` being true.
-    #[inline]
-    pub fn all() -> Usage {
-        Usage {
-            transfer_source: true,
-            transfer_dest: true,
+        let usage = Usage {
             sampled: true,
-            storage: true,
-            color_attachment: true,
-            depth_stencil_attachment: true,
-            transient_attachment: true,
-            input_attachment: true,
-        }
+            .. Usage::none()
+        };
+
+        let (img, _) = unsafe {
+            UnsafeImage::new(&device, &usage, Format::R8G8B8A8Unorm,
+                             Dimensions::Dim2d { width: 32, height: 32 }, 1, 1,
+                             Sharing::Exclusive::<Empty<_>>, false, false, ImageCreateFlags::none())
+        }.unwrap();
+
+        match img.subresource_layout(ImageAspect::Color, 0, 0) {
+            Err(SubresourceLayoutError::OptimalTiling) => (),
+            _ => panic!()
+        };
     }
 
-    /// Builds a `Usage` with all values set to false. Useful as a default value.
-    ///
-    /// # Example
-    ///
-    /// ```rust
-    /// use vulkano::image::Usage as ImageUsage;
-    ///
-    /// let _usage = ImageUsage {
-    ///     transfer_dest: true,
-    ///     sampled: true,
-    ///     .. ImageUsage::none()
-    /// };
-    /// ```
-    #[inline]
-    pub fn none() -> Usage {
-        Usage {
-            transfer_source: false,
-            transfer_dest: false,
-            sampled: false,
-            storage: false,
-            color_attachment: false,
-            depth_stencil_attachment: false,
-            transient_attachment: false,
-            input_attachment: false,
-        }
+    #[test]
+    fn usage_to_bits() {
+        assert_eq!(Usage::none().to_usage_bits(), 0);
+        assert_eq!(Usage::all().to_usage_bits(), 0xff);
+        assert_eq!(Usage::color_attachment().to_usage_bits(),
+                   vk::IMAGE_USAGE_COLOR_ATTACHMENT_BIT);
+        assert_eq!(Usage::depth_stencil_attachment().to_usage_bits(),
+                   vk::IMAGE_USAGE_DEPTH_STENCIL_ATTACHMENT_BIT);
+        assert_eq!(Usage::sampled_transfer_dest().to_usage_bits(),
+                   vk::IMAGE_USAGE_SAMPLED_BIT | vk::IMAGE_USAGE_TRANSFER_DST_BIT);
     }
 
-    #[doc(hidden)]
-    #[inline]
-    pub fn to_usage_bits(&self) -> vk::ImageUsageFlagBits {
-        let mut result = 0;
-        if self.transfer_source { result |= vk::IMAGE_USAGE_TRANSFER_SRC_BIT; }
-        if self.transfer_dest { result |= vk::IMAGE_USAGE_TRANSFER_DST_BIT; }
-        if self.sampled { result |= vk::IMAGE_USAGE_SAMPLED_BIT; }
-        if self.storage { result |= vk::IMAGE_USAGE_STORAGE_BIT; }
-        if self.color_attachment { result |= vk::IMAGE_USAGE_COLOR_ATTACHMENT_BIT; }
-        if self.depth_stencil_attachment { result |= vk::IMAGE_USAGE_DEPTH_STENCIL_ATTACHMENT_BIT; }
-        if self.transient_attachment { result |= vk::IMAGE_USAGE_TRANSIENT_ATTACHMENT_BIT; }
-        if self.input_attachment { result |= vk::IMAGE_USAGE_INPUT_ATTACHMENT_BIT; }
-        result
+    #[test]
+    fn usage_from_bits() {
+        assert_eq!(Usage::from_bits(0), Usage::none());
+        assert_eq!(Usage::from_bits(0xff), Usage::all());
+        assert_eq!(Usage::from_bits(vk::IMAGE_USAGE_TRANSFER_SRC_BIT |
+                                    vk::IMAGE_USAGE_INPUT_ATTACHMENT_BIT),
+                   Usage { transfer_source: true, input_attachment: true, .. Usage::none() });
+
+        let usage = Usage { storage: true, transient_attachment: true, .. Usage::none() };
+        assert_eq!(Usage::from_bits(usage.to_usage_bits()), usage);
     }
 
-    #[inline]
-    #[doc(hidden)]
-    pub fn from_bits(val: u32) -> Usage {
-        Usage {
-            transfer_source: (val & vk::IMAGE_USAGE_TRANSFER_SRC_BIT) != 0,
-            transfer_dest: (val & vk::IMAGE_USAGE_TRANSFER_DST_BIT) != 0,
-            sampled: (val & vk::IMAGE_USAGE_SAMPLED_BIT) != 0,
-            storage: (val & vk::IMAGE_USAGE_STORAGE_BIT) != 0,
-            color_attachment: (val & vk::IMAGE_USAGE_COLOR_ATTACHMENT_BIT) != 0,
-            depth_stencil_attachment: (val & vk::IMAGE_USAGE_DEPTH_STENCIL_ATTACHMENT_BIT) != 0,
-            transient_attachment: (val & vk::IMAGE_USAGE_TRANSIENT_ATTACHMENT_BIT) != 0,
-            input_attachment: (val & vk::IMAGE_USAGE_INPUT_ATTACHMENT_BIT) != 0,
-        }
+    #[test]
+    fn usage_accessors() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let (img, _) = unsafe {
+            UnsafeImage::new(&device, &Usage::sampled_transfer_dest(), Format::R8G8B8A8Unorm,
+                             Dimensions::Dim2d { width: 32, height: 32 }, 1, 1,
+                             Sharing::Exclusive::<Empty<_>>, false, false, ImageCreateFlags::none())
+        }.unwrap();
+
+        assert!(img.usage_sampled());
+        assert!(img.usage_transfer_dest());
+        assert!(!img.usage_transfer_src());
+        assert!(!img.usage_color_attachment());
     }
-}
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-#[repr(u32)]
-pub enum Layout {
-    Undefined = vk::IMAGE_LAYOUT_UNDEFINED,
-    General = vk::IMAGE_LAYOUT_GENERAL,
-    ColorAttachmentOptimal = vk::IMAGE_LAYOUT_COLOR_ATTACHMENT_OPTIMAL,
-    DepthStencilAttachmentOptimal = vk::IMAGE_LAYOUT_DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
-    DepthStencilReadOnlyOptimal = vk::IMAGE_LAYOUT_DEPTH_STENCIL_READ_ONLY_OPTIMAL,
-    ShaderReadOnlyOptimal = vk::IMAGE_LAYOUT_SHADER_READ_ONLY_OPTIMAL,
-    TransferSrcOptimal = vk::IMAGE_LAYOUT_TRANSFER_SRC_OPTIMAL,
-    TransferDstOptimal = vk::IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL,
-    Preinitialized = vk::IMAGE_LAYOUT_PREINITIALIZED,
-    PresentSrc = vk::IMAGE_LAYOUT_PRESENT_SRC_KHR,
-}
+    #[test]
+    fn transient_forbidden_with_some_usages() {
+        let (device, _) = gfx_dev_and_queue!();
 
-#[cfg(test)]
-mod tests {
-    use std::iter::Empty;
-    use std::u32;
+        let usage = Usage {
+            transient_attachment: true,
+            sampled: true,
+            .. Usage::none()
+        };
 
-    use super::Dimensions;
-    use super::ImageCreationError;
-    use super::UnsafeImage;
-    use super::Usage;
+        let res = unsafe {
+            UnsafeImage::new(&device, &usage, Format::R8G8B8A8Unorm,
+                             Dimensions::Dim2d { width: 32, height: 32 }, 1, 1,
+                             Sharing::Exclusive::<Empty<_>>, false, false, ImageCreateFlags::none())
+        };
 
-    use format::Format;
-    use sync::Sharing;
+        match res {
+            Err(ImageCreationError::UnsupportedUsage) => (),
+            _ => panic!()
+        };
+    }
 
     #[test]
-    fn create_sampled() {
+    fn view_sub_range() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let image = ImmutableImage::with_mipmaps(&device, Dimensions::Dim2dArray {
+            width: 64, height: 64, array_layers: 4
+        }, R8G8B8A8Unorm, 4, Some(queue.family())).unwrap();
+
+        let view = unsafe {
+            UnsafeImageView::raw(image.inner_image(), 3 .. 4, 1 .. 2, Swizzle::default()).unwrap()
+        };
+
+        assert_eq!(view.mipmap_levels(), 3 .. 4);
+        assert_eq!(view.array_layers(), 1 .. 2);
+        assert_eq!(view.dimensions().width_height(), [8, 8]);
+        assert_eq!(view.dimensions().array_layers(), 1);
+    }
+
+    #[test]
+    fn view_out_of_range() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let image = ImmutableImage::with_mipmaps(&device, Dimensions::Dim2dArray {
+            width: 64, height: 64, array_layers: 4
+        }, R8G8B8A8Unorm, 4, Some(queue.family())).unwrap();
+
+        match unsafe { UnsafeImageView::raw(image.inner_image(), 2 .. 5, 0 .. 1,
+                                            Swizzle::default()) } {
+            Err(ImageViewCreationError::MipmapLevelsOutOfRange { .. }) => (),
+            _ => panic!()
+        };
+
+        match unsafe { UnsafeImageView::raw(image.inner_image(), 0 .. 1, 3 .. 5,
+                                            Swizzle::default()) } {
+            Err(ImageViewCreationError::ArrayLayersOutOfRange { .. }) => (),
+            _ => panic!()
+        };
+    }
+
+    #[test]
+    fn incompatible_format_suggestion() {
+        let err = ImageViewCreationError::IncompatibleFormat {
+            image_format: Format::B8G8R8A8Srgb,
+            view_format: Format::R16G16Sfloat,
+        };
+        assert!(format!("{}", err).ends_with("(a view of a B8G8R8A8Srgb image can use \
+                                              B8G8R8A8Unorm)"));
+
+        let err = ImageViewCreationError::IncompatibleFormat {
+            image_format: Format::R32Sfloat,
+            view_format: Format::R16G16B16A16Sfloat,
+        };
+        assert!(!format!("{}", err).contains("can use"));
+    }
+
+    #[test]
+    fn mutable_format_view() {
         let (device, _) = gfx_dev_and_queue!();
 
         let usage = Usage {
@@ -980,32 +3096,63 @@ mod tests {
             .. Usage::none()
         };
 
-        let (_img, _) = unsafe {
+        let (image, _) = unsafe {
             UnsafeImage::new(&device, &usage, Format::R8G8B8A8Unorm,
                              Dimensions::Dim2d { width: 32, height: 32 }, 1, 1,
-                             Sharing::Exclusive::<Empty<_>>, false, false)
+                             Sharing::Exclusive::<Empty<_>>, false, false,
+                             ImageCreateFlags { mutable_format: true, .. ImageCreateFlags::none() })
+        }.unwrap();
+
+        assert!(image.mutable_format());
+
+        let view = unsafe {
+            UnsafeImageView::raw_with_format(&image, ViewType::Dim2d, Format::R8G8B8A8Srgb,
+                                             0 .. 1, 0 .. 1, Swizzle::default(),
+                                             ImageAspects::from_format(Format::R8G8B8A8Srgb))
         }.unwrap();
+
+        assert_eq!(view.format(), Format::R8G8B8A8Srgb);
+
+        match unsafe {
+            UnsafeImageView::raw_with_format(&image, ViewType::Dim2d, Format::R16G16B16A16Sfloat,
+                                             0 .. 1, 0 .. 1, Swizzle::default(),
+                                             ImageAspects::from_format(Format::R16G16B16A16Sfloat))
+        } {
+            Err(ImageViewCreationError::IncompatibleFormat {
+                image_format: Format::R8G8B8A8Unorm,
+                view_format: Format::R16G16B16A16Sfloat,
+            }) => (),
+            _ => panic!()
+        };
     }
 
     #[test]
-    fn create_transient() {
+    fn view_format_not_mutable() {
         let (device, _) = gfx_dev_and_queue!();
 
         let usage = Usage {
-            transient_attachment: true,
-            color_attachment: true,
+            sampled: true,
             .. Usage::none()
         };
 
-        let (_img, _) = unsafe {
+        let (image, _) = unsafe {
             UnsafeImage::new(&device, &usage, Format::R8G8B8A8Unorm,
                              Dimensions::Dim2d { width: 32, height: 32 }, 1, 1,
-                             Sharing::Exclusive::<Empty<_>>, false, false)
+                             Sharing::Exclusive::<Empty<_>>, false, false, ImageCreateFlags::none())
         }.unwrap();
+
+        match unsafe {
+            UnsafeImageView::raw_with_format(&image, ViewType::Dim2d, Format::R8G8B8A8Srgb,
+                                             0 .. 1, 0 .. 1, Swizzle::default(),
+                                             ImageAspects::from_format(Format::R8G8B8A8Srgb))
+        } {
+            Err(ImageViewCreationError::FormatNotMutable) => (),
+            _ => panic!()
+        };
     }
 
     #[test]
-    fn zero_sample() {
+    fn cube_compatible_not_square() {
         let (device, _) = gfx_dev_and_queue!();
 
         let usage = Usage {
@@ -1015,18 +3162,22 @@ mod tests {
 
         let res = unsafe {
             UnsafeImage::new(&device, &usage, Format::R8G8B8A8Unorm,
-                             Dimensions::Dim2d { width: 32, height: 32 }, 0, 1,
-                             Sharing::Exclusive::<Empty<_>>, false, false)
+                             Dimensions::Dim2dArray { width: 32, height: 16, array_layers: 6 },
+                             1, 1, Sharing::Exclusive::<Empty<_>>, false, false,
+                             ImageCreateFlags {
+                                 cube_compatible: true,
+                                 .. ImageCreateFlags::none()
+                             })
         };
 
         match res {
-            Err(ImageCreationError::UnsupportedSamplesCount { .. }) => (),
+            Err(ImageCreationError::CubeCompatibleNotSquare) => (),
             _ => panic!()
         };
     }
 
     #[test]
-    fn non_po2_sample() {
+    fn cube_compatible_wrong_layers() {
         let (device, _) = gfx_dev_and_queue!();
 
         let usage = Usage {
@@ -1036,40 +3187,43 @@ mod tests {
 
         let res = unsafe {
             UnsafeImage::new(&device, &usage, Format::R8G8B8A8Unorm,
-                             Dimensions::Dim2d { width: 32, height: 32 }, 5, 1,
-                             Sharing::Exclusive::<Empty<_>>, false, false)
+                             Dimensions::Dim2dArray { width: 32, height: 32, array_layers: 4 },
+                             1, 1, Sharing::Exclusive::<Empty<_>>, false, false,
+                             ImageCreateFlags {
+                                 cube_compatible: true,
+                                 .. ImageCreateFlags::none()
+                             })
         };
 
         match res {
-            Err(ImageCreationError::UnsupportedSamplesCount { .. }) => (),
+            Err(ImageCreationError::CubeCompatibleInvalidArrayLayers { array_layers: 4 }) => (),
             _ => panic!()
         };
     }
 
     #[test]
-    fn zero_mipmap() {
+    fn multisampled_1d() {
         let (device, _) = gfx_dev_and_queue!();
 
         let usage = Usage {
-            sampled: true,
+            color_attachment: true,
             .. Usage::none()
         };
 
         let res = unsafe {
             UnsafeImage::new(&device, &usage, Format::R8G8B8A8Unorm,
-                             Dimensions::Dim2d { width: 32, height: 32 }, 1, 0,
-                             Sharing::Exclusive::<Empty<_>>, false, false)
+                             Dimensions::Dim1d { width: 32 }, 4, 1,
+                             Sharing::Exclusive::<Empty<_>>, false, false, ImageCreateFlags::none())
         };
 
         match res {
-            Err(ImageCreationError::InvalidMipmapsCount { .. }) => (),
+            Err(ImageCreationError::UnsupportedSampleCount { requested: 4, .. }) => (),
             _ => panic!()
         };
     }
 
     #[test]
-    #[ignore]       // TODO: AMD card seems to support a u32::MAX number of mipmaps
-    fn mipmaps_too_high() {
+    fn dimensions_1d_too_large() {
         let (device, _) = gfx_dev_and_queue!();
 
         let usage = Usage {
@@ -1079,82 +3233,164 @@ mod tests {
 
         let res = unsafe {
             UnsafeImage::new(&device, &usage, Format::R8G8B8A8Unorm,
-                             Dimensions::Dim2d { width: 32, height: 32 }, 1, u32::MAX,
-                             Sharing::Exclusive::<Empty<_>>, false, false)
+                             Dimensions::Dim1d { width: u32::MAX }, 1, 1,
+                             Sharing::Exclusive::<Empty<_>>, false, false, ImageCreateFlags::none())
         };
 
         match res {
-            Err(ImageCreationError::InvalidMipmapsCount { obtained, valid_range }) => {
-                assert_eq!(obtained, u32::MAX);
-                assert_eq!(valid_range.start, 1);
-            },
+            Err(ImageCreationError::UnsupportedDimensions { .. }) => (),
             _ => panic!()
         };
     }
 
     #[test]
-    fn shader_storage_image_multisample() {
+    fn dimensions_3d_too_large() {
         let (device, _) = gfx_dev_and_queue!();
 
         let usage = Usage {
-            storage: true,
+            sampled: true,
             .. Usage::none()
         };
 
         let res = unsafe {
             UnsafeImage::new(&device, &usage, Format::R8G8B8A8Unorm,
-                             Dimensions::Dim2d { width: 32, height: 32 }, 2, 1,
-                             Sharing::Exclusive::<Empty<_>>, false, false)
+                             Dimensions::Dim3d { width: 16, height: 16, depth: u32::MAX }, 1, 1,
+                             Sharing::Exclusive::<Empty<_>>, false, false, ImageCreateFlags::none())
         };
 
         match res {
-            Err(ImageCreationError::ShaderStorageImageMultisampleFeatureNotEnabled) => (),
-            Err(ImageCreationError::UnsupportedSamplesCount { .. }) => (), // unlikely but possible
+            Err(ImageCreationError::UnsupportedDimensions { .. }) => (),
             _ => panic!()
         };
     }
 
     #[test]
-    fn compressed_not_color_attachment() {
+    fn array_layers_too_many() {
         let (device, _) = gfx_dev_and_queue!();
 
         let usage = Usage {
-            color_attachment: true,
+            sampled: true,
             .. Usage::none()
         };
 
         let res = unsafe {
-            UnsafeImage::new(&device, &usage, Format::ASTC_5x4UnormBlock,
-                             Dimensions::Dim2d { width: 32, height: 32 }, 1, u32::MAX,
-                             Sharing::Exclusive::<Empty<_>>, false, false)
+            UnsafeImage::new(&device, &usage, Format::R8G8B8A8Unorm,
+                             Dimensions::Dim2dArray { width: 16, height: 16,
+                                                      array_layers: u32::MAX }, 1, 1,
+                             Sharing::Exclusive::<Empty<_>>, false, false, ImageCreateFlags::none())
         };
 
         match res {
-            Err(ImageCreationError::FormatNotSupported) => (),
-            Err(ImageCreationError::UnsupportedUsage) => (),
+            Err(ImageCreationError::UnsupportedDimensions { .. }) => (),
             _ => panic!()
         };
     }
 
     #[test]
-    fn transient_forbidden_with_some_usages() {
+    fn view_3d_no_array_layers() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let image = ImmutableImage::new(&device, Dimensions::Dim3d {
+            width: 16, height: 16, depth: 16
+        }, R8G8B8A8Unorm, Some(queue.family())).unwrap();
+
+        match unsafe { UnsafeImageView::raw(image.inner_image(), 0 .. 1, 0 .. 2,
+                                            Swizzle::default()) } {
+            Err(ImageViewCreationError::ArrayLayersOutOfRange { .. }) => (),
+            _ => panic!()
+        };
+
+        let aspects = ImageAspects::from_format(image.inner_image().format());
+        match unsafe { UnsafeImageView::raw_with_type(image.inner_image(), ViewType::Dim2dArray,
+                                                      0 .. 1, 0 .. 1, Swizzle::default(),
+                                                      aspects) } {
+            Err(ImageViewCreationError::IncompatibleViewType { .. }) => (),
+            _ => panic!()
+        };
+    }
+
+    #[test]
+    fn bind_pool_memory() {
         let (device, _) = gfx_dev_and_queue!();
 
         let usage = Usage {
-            transient_attachment: true,
+            transfer_dest: true,
             sampled: true,
             .. Usage::none()
         };
 
-        let res = unsafe {
+        let (img, reqs) = unsafe {
             UnsafeImage::new(&device, &usage, Format::R8G8B8A8Unorm,
                              Dimensions::Dim2d { width: 32, height: 32 }, 1, 1,
-                             Sharing::Exclusive::<Empty<_>>, false, false)
+                             Sharing::Exclusive::<Empty<_>>, false, false, ImageCreateFlags::none())
+        }.unwrap();
+
+        assert_eq!(img.memory_requirements().size, reqs.size);
+        assert_eq!(img.memory_requirements().alignment, reqs.alignment);
+        assert_eq!(img.memory_requirements().memory_type_bits, reqs.memory_type_bits);
+
+        let mem_ty = reqs.allowed_types(&device.physical_device()).next().unwrap();
+        let mem = MemoryPool::alloc(&device.standard_pool(), mem_ty, reqs.size, reqs.alignment,
+                                    AllocLayout::Optimal).unwrap();
+
+        unsafe {
+            img.bind_memory(mem.memory(), mem.offset()).unwrap();
+
+            match img.bind_memory(mem.memory(), mem.offset()) {
+                Err(ImageMemoryBindError::AlreadyBound) => (),
+                _ => panic!()
+            };
+        }
+    }
+
+    #[test]
+    fn bind_memory_checks_requirements() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let usage = Usage {
+            transfer_dest: true,
+            sampled: true,
+            .. Usage::none()
         };
 
-        match res {
-            Err(ImageCreationError::UnsupportedUsage) => (),
+        let (img, reqs) = unsafe {
+            UnsafeImage::new(&device, &usage, Format::R8G8B8A8Unorm,
+                             Dimensions::Dim2d { width: 32, height: 32 }, 1, 1,
+                             Sharing::Exclusive::<Empty<_>>, false, false, ImageCreateFlags::none())
+        }.unwrap();
+
+        let mem_ty = reqs.allowed_types(&device.physical_device()).next().unwrap();
+
+        let small = DeviceMemory::alloc(&device, &mem_ty, reqs.size / 2).unwrap();
+        match unsafe { img.bind_memory(&small, 0) } {
+            Err(ImageMemoryBindError::MemoryTooSmall { required, available }) => {
+                assert_eq!(required, reqs.size);
+                assert_eq!(available, reqs.size / 2);
+            },
             _ => panic!()
         };
+
+        if reqs.alignment > 1 {
+            let mem = DeviceMemory::alloc(&device, &mem_ty, reqs.size * 2).unwrap();
+            match unsafe { img.bind_memory(&mem, 1) } {
+                Err(ImageMemoryBindError::UnalignedOffset { offset: 1, .. }) => (),
+                _ => panic!()
+            };
+        }
+
+        let wrong_ty = device.physical_device().memory_types()
+                             .filter(|t| !reqs.prefers(*t))
+                             .next();
+        if let Some(wrong_ty) = wrong_ty {
+            let mem = DeviceMemory::alloc(&device, &wrong_ty, reqs.size).unwrap();
+            match unsafe { img.bind_memory(&mem, 0) } {
+                Err(ImageMemoryBindError::WrongMemoryType) => (),
+                _ => panic!()
+            };
+        }
+
+        // None of the failed attempts count as binding memory.
+        let mem = DeviceMemory::alloc(&device, &mem_ty, reqs.size).unwrap();
+        unsafe { img.bind_memory(&mem, 0).unwrap(); }
     }
 }
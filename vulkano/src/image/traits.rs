@@ -9,11 +9,14 @@
 
 use std::ops::Range;
 use std::sync::Arc;
+use std::sync::Weak;
+use smallvec::SmallVec;
 
 use command_buffer::Submission;
 use format::ClearValue;
 use format::Format;
 use image::sys::Dimensions;
+use image::sys::ImageAspects;
 use image::sys::Layout;
 use image::sys::UnsafeImage;
 use image::sys::UnsafeImageView;
@@ -166,7 +169,10 @@ pub unsafe trait ImageView: 'static + Send + Sync {
     /// This method should check whether the sampler's configuration can be used with the format
     /// of the view.
     // TODO: return a Result
-    fn can_be_sampled(&self, sampler: &Sampler) -> bool { true /* FIXME */ }
+    #[inline]
+    fn can_be_sampled(&self, sampler: &Sampler) -> bool {
+        sampler.supports_format(self.format())
+    }
 
     //fn usable_as_render_pass_attachment(&self, ???) -> Result<(), ???>;
 }
@@ -175,14 +181,115 @@ pub unsafe trait AttachmentImageView: ImageView {
     fn accept(&self, initial_layout: Layout, final_layout: Layout) -> bool;
 }
 
-#[derive(Debug, Clone)]
+/// Access to a range of subresources of an image by a command buffer.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct AccessRange {
+    /// Block that contains the subresources.
     pub block: (u32, u32),
+    /// Mipmap levels that are accessed. Always contained in the mipmap levels of the block.
+    pub mipmap_levels: Range<u32>,
+    /// Array layers that are accessed. Always contained in the array layers of the block.
+    pub array_layers: Range<u32>,
+    /// Aspects that are accessed.
+    pub aspects: ImageAspects,
+    /// True if the subresources are written, false if they are only read.
     pub write: bool,
     pub initial_layout: Layout,
     pub final_layout: Layout,
 }
 
+impl AccessRange {
+    /// Returns true if both accesses cover at least one common subresource, in other words if
+    /// their mipmap levels, array layers and aspects all intersect.
+    #[inline]
+    pub fn overlaps(&self, other: &AccessRange) -> bool {
+        self.mipmap_levels.start < other.mipmap_levels.end &&
+        other.mipmap_levels.start < self.mipmap_levels.end &&
+        self.array_layers.start < other.array_layers.end &&
+        other.array_layers.start < self.array_layers.end &&
+        ((self.aspects.color && other.aspects.color) ||
+         (self.aspects.depth && other.aspects.depth) ||
+         (self.aspects.stencil && other.aspects.stencil))
+    }
+
+    /// Returns true if performing both accesses simultaneously is a hazard, in other words if
+    /// they overlap and at least one of them is a write.
+    #[inline]
+    pub fn conflicts_with(&self, other: &AccessRange) -> bool {
+        (self.write || other.write) && self.overlaps(other)
+    }
+
+    /// Returns true if this access writes all the subresources covered by `other`.
+    #[inline]
+    pub fn covers(&self, other: &AccessRange) -> bool {
+        self.write &&
+        self.mipmap_levels.start <= other.mipmap_levels.start &&
+        self.mipmap_levels.end >= other.mipmap_levels.end &&
+        self.array_layers.start <= other.array_layers.start &&
+        self.array_layers.end >= other.array_layers.end &&
+        (self.aspects.color || !other.aspects.color) &&
+        (self.aspects.depth || !other.aspects.depth) &&
+        (self.aspects.stencil || !other.aspects.stencil)
+    }
+}
+
+/// Keeps track of the subresources accessed by the submissions that use an image, in order to
+/// determine which previous submissions a new submission must wait upon.
+///
+/// Submissions whose accesses don't conflict can run simultaneously, for example a submission
+/// that uploads a mipmap level while another one samples a different level.
+#[derive(Debug)]
+pub struct AccessTracker {
+    submissions: SmallVec<[(Weak<Submission>, SmallVec<[AccessRange; 4]>); 4]>,
+}
+
+impl AccessTracker {
+    /// Builds a tracker with no submission.
+    #[inline]
+    pub fn new() -> AccessTracker {
+        AccessTracker {
+            submissions: SmallVec::new(),
+        }
+    }
+
+    /// Registers the accesses of a new submission and returns the list of previous submissions
+    /// whose accesses conflict with them.
+    pub fn register<I>(&mut self, accesses: I, submission: &Arc<Submission>)
+                       -> Vec<Arc<Submission>>
+        where I: IntoIterator<Item = AccessRange>
+    {
+        let accesses: SmallVec<[AccessRange; 4]> = accesses.into_iter().collect();
+
+        let mut dependencies = Vec::new();
+        let mut remaining = SmallVec::new();
+
+        for (weak, other) in self.submissions.into_iter() {
+            let other_submission = match weak.upgrade() {
+                Some(s) => s,
+                None => continue,
+            };
+
+            let conflict = other.iter().any(|o| accesses.iter().any(|a| a.conflicts_with(o)));
+            if !conflict {
+                remaining.push((weak, other));
+                continue;
+            }
+
+            // If we overwrite everything that the other submission accesses, then any further
+            // access that conflicts with it also conflicts with us and we can forget about it.
+            if !other.iter().all(|o| accesses.iter().any(|a| a.covers(o))) {
+                remaining.push((weak, other));
+            }
+
+            dependencies.push(other_submission);
+        }
+
+        remaining.push((Arc::downgrade(submission), accesses));
+        self.submissions = remaining;
+        dependencies
+    }
+}
+
 pub struct GpuAccessResult {
     pub dependencies: Vec<Arc<Submission>>,
     pub additional_wait_semaphore: Option<Arc<Semaphore>>,
@@ -196,3 +303,72 @@ pub struct Transition {
     pub from: Layout,
     pub to: Layout,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use super::AccessRange;
+    use image::sys::ImageAspects;
+    use image::sys::Layout;
+
+    fn access(mipmap_levels: Range<u32>, array_layers: Range<u32>, write: bool) -> AccessRange {
+        AccessRange {
+            block: (0, 0),
+            mipmap_levels: mipmap_levels,
+            array_layers: array_layers,
+            aspects: ImageAspects { color: true, .. ImageAspects::none() },
+            write: write,
+            initial_layout: Layout::General,
+            final_layout: Layout::General,
+        }
+    }
+
+    #[test]
+    fn disjoint_mipmap_levels() {
+        let upload = access(5 .. 6, 0 .. 1, true);
+        let sample = access(0 .. 1, 0 .. 1, false);
+        assert!(!upload.conflicts_with(&sample));
+        assert!(!sample.conflicts_with(&upload));
+    }
+
+    #[test]
+    fn disjoint_array_layers() {
+        let a = access(0 .. 4, 0 .. 2, true);
+        let b = access(0 .. 4, 2 .. 4, true);
+        assert!(!a.conflicts_with(&b));
+    }
+
+    #[test]
+    fn overlap_in_one_dimension_only() {
+        // Same mipmap levels but different array layers.
+        assert!(!access(0 .. 2, 0 .. 1, true).conflicts_with(&access(0 .. 2, 1 .. 2, true)));
+        // Same array layers but different mipmap levels.
+        assert!(!access(0 .. 1, 0 .. 2, true).conflicts_with(&access(1 .. 2, 0 .. 2, true)));
+        // Both dimensions overlap.
+        assert!(access(0 .. 2, 0 .. 2, true).conflicts_with(&access(1 .. 3, 1 .. 3, false)));
+    }
+
+    #[test]
+    fn full_image() {
+        let full = access(0 .. 6, 0 .. 4, true);
+        assert!(full.conflicts_with(&access(3 .. 4, 2 .. 3, false)));
+        assert!(full.covers(&access(3 .. 4, 2 .. 3, false)));
+        assert!(!access(3 .. 4, 2 .. 3, true).covers(&full));
+        assert!(!access(0 .. 6, 0 .. 4, false).conflicts_with(&access(0 .. 6, 0 .. 4, false)));
+    }
+
+    #[test]
+    fn disjoint_aspects() {
+        let depth = AccessRange {
+            aspects: ImageAspects { depth: true, .. ImageAspects::none() },
+            .. access(0 .. 1, 0 .. 1, true)
+        };
+        let stencil = AccessRange {
+            aspects: ImageAspects { stencil: true, .. ImageAspects::none() },
+            .. access(0 .. 1, 0 .. 1, true)
+        };
+        assert!(!depth.conflicts_with(&stencil));
+        assert!(depth.conflicts_with(&depth));
+    }
+}
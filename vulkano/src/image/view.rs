@@ -0,0 +1,419 @@
+// Copyright (c) 2016 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+//! Views over a part of an existing image.
+//!
+//! All the high-level image types of vulkano are also views over the whole image. If you need a
+//! view that covers only some mipmap levels or some array layers, that remaps the components
+//! of the image, or that views the image as a cube map, you can create a `CustomImageView`.
+
+use std::ops::Range;
+use std::sync::Arc;
+use std::u32;
+
+use image::Swizzle;
+use image::sys::ImageAspects;
+use image::sys::ImageViewCreationError;
+use image::sys::Layout;
+use image::sys::UnsafeImageView;
+use image::sys::ViewType;
+use image::traits::Image;
+use image::traits::ImageView;
+
+/// View over some mipmap levels and array layers of an image.
+#[derive(Debug)]
+pub struct CustomImageView<I> {
+    // The image this view was created from.
+    image: Arc<I>,
+
+    // The inner view.
+    view: UnsafeImageView,
+}
+
+impl<I> CustomImageView<I> where I: Image + ImageView {
+    /// Builds a new view over the given mipmap levels and array layers of an image.
+    ///
+    /// Returns an error if the ranges are empty or are out of range of the image.
+    ///
+    /// Note that a view whose swizzle is not the identity can't be used as a framebuffer
+    /// attachment.
+    pub fn new(image: &Arc<I>, mipmap_levels: Range<u32>, array_layers: Range<u32>,
+               swizzle: Swizzle) -> Result<Arc<CustomImageView<I>>, ImageViewCreationError>
+    {
+        let view = unsafe {
+            try!(UnsafeImageView::raw(image.inner_image(), mipmap_levels, array_layers, swizzle))
+        };
+
+        Ok(Arc::new(CustomImageView {
+            image: image.clone(),
+            view: view,
+        }))
+    }
+
+    /// Same as `new`, except that the type of the view is explicitly specified.
+    pub fn with_type(image: &Arc<I>, ty: ViewType, mipmap_levels: Range<u32>,
+                     array_layers: Range<u32>, swizzle: Swizzle)
+                     -> Result<Arc<CustomImageView<I>>, ImageViewCreationError>
+    {
+        let view = unsafe {
+            let aspects = ImageAspects::from_format(image.inner_image().format());
+            try!(UnsafeImageView::raw_with_type(image.inner_image(), ty, mipmap_levels,
+                                                array_layers, swizzle, aspects))
+        };
+
+        Ok(Arc::new(CustomImageView {
+            image: image.clone(),
+            view: view,
+        }))
+    }
+
+    /// Same as `new`, except that the aspects covered by the view are explicitly specified.
+    ///
+    /// This is used to view only the depth or only the stencil component of an image with a
+    /// depth-stencil format, which is required in order to sample it.
+    pub fn with_aspects(image: &Arc<I>, mipmap_levels: Range<u32>, array_layers: Range<u32>,
+                        aspects: ImageAspects)
+                        -> Result<Arc<CustomImageView<I>>, ImageViewCreationError>
+    {
+        let view = unsafe {
+            try!(UnsafeImageView::raw_with_aspects(image.inner_image(), mipmap_levels,
+                                                   array_layers, Swizzle::default(), aspects))
+        };
+
+        Ok(Arc::new(CustomImageView {
+            image: image.clone(),
+            view: view,
+        }))
+    }
+
+    /// Builds a view over the depth aspect of all the mipmap levels and array layers of an
+    /// image. The format of the image must have a depth component.
+    #[inline]
+    pub fn depth(image: &Arc<I>) -> Result<Arc<CustomImageView<I>>, ImageViewCreationError> {
+        let aspects = ImageAspects { depth: true, .. ImageAspects::none() };
+        let levels = image.inner_image().mipmap_levels();
+        let layers = image.inner_image().dimensions().array_layers();
+        CustomImageView::with_aspects(image, 0 .. levels, 0 .. layers, aspects)
+    }
+
+    /// Builds a view over the stencil aspect of all the mipmap levels and array layers of an
+    /// image. The format of the image must have a stencil component.
+    #[inline]
+    pub fn stencil(image: &Arc<I>) -> Result<Arc<CustomImageView<I>>, ImageViewCreationError> {
+        let aspects = ImageAspects { stencil: true, .. ImageAspects::none() };
+        let levels = image.inner_image().mipmap_levels();
+        let layers = image.inner_image().dimensions().array_layers();
+        CustomImageView::with_aspects(image, 0 .. levels, 0 .. layers, aspects)
+    }
+
+    /// Builds a cube view over all the mipmap levels and the 6 array layers starting at
+    /// `first_layer`.
+    ///
+    /// The image must have been created cube-compatible.
+    #[inline]
+    pub fn cube(image: &Arc<I>, first_layer: u32)
+                -> Result<Arc<CustomImageView<I>>, ImageViewCreationError>
+    {
+        let levels = image.inner_image().mipmap_levels();
+        let layers = try!(layers_range(image, first_layer, 6));
+        CustomImageView::with_type(image, ViewType::Cubemap, 0 .. levels, layers,
+                                   Swizzle::default())
+    }
+
+    /// Builds a cube array view over all the mipmap levels and the given array layers. The
+    /// number of layers must be a multiple of 6.
+    ///
+    /// The image must have been created cube-compatible, and the `image_cube_array` feature must
+    /// be enabled.
+    #[inline]
+    pub fn cube_array(image: &Arc<I>, array_layers: Range<u32>)
+                      -> Result<Arc<CustomImageView<I>>, ImageViewCreationError>
+    {
+        let levels = image.inner_image().mipmap_levels();
+        CustomImageView::with_type(image, ViewType::CubemapArray, 0 .. levels, array_layers,
+                                   Swizzle::default())
+    }
+
+    /// Builds a view over the first mipmap level of a single array layer of an image.
+    ///
+    /// This can be used for example to render to one layer of an array image.
+    #[inline]
+    pub fn layer(image: &Arc<I>, layer: u32)
+                 -> Result<Arc<CustomImageView<I>>, ImageViewCreationError>
+    {
+        let layers = try!(layers_range(image, layer, 1));
+        CustomImageView::new(image, 0 .. 1, layers, Swizzle::default())
+    }
+
+    /// Returns the image this view was created from.
+    #[inline]
+    pub fn image(&self) -> &Arc<I> {
+        &self.image
+    }
+
+    /// Returns the range of mipmap levels covered by this view.
+    #[inline]
+    pub fn mipmap_levels(&self) -> Range<u32> {
+        self.view.mipmap_levels()
+    }
+
+    /// Returns the range of array layers covered by this view.
+    #[inline]
+    pub fn array_layers(&self) -> Range<u32> {
+        self.view.array_layers()
+    }
+
+    /// Returns the mapping of the components of the view.
+    #[inline]
+    pub fn swizzle(&self) -> Swizzle {
+        self.view.swizzle()
+    }
+
+    /// Returns the type of the view.
+    #[inline]
+    pub fn ty(&self) -> ViewType {
+        self.view.ty()
+    }
+
+    /// Returns the aspects of the image covered by the view.
+    #[inline]
+    pub fn aspects(&self) -> ImageAspects {
+        self.view.aspects()
+    }
+}
+
+unsafe impl<I> ImageView for CustomImageView<I> where I: Image + ImageView {
+    #[inline]
+    fn parent(&self) -> &Image {
+        &*self.image
+    }
+
+    #[inline]
+    fn parent_arc(me: &Arc<Self>) -> Arc<Image> where Self: Sized {
+        me.image.clone() as Arc<_>
+    }
+
+    #[inline]
+    fn blocks(&self) -> Vec<(u32, u32)> {
+        Image::blocks(&*self.image, self.view.mipmap_levels(), self.view.array_layers())
+    }
+
+    #[inline]
+    fn inner_view(&self) -> &UnsafeImageView {
+        &self.view
+    }
+
+    #[inline]
+    fn descriptor_set_storage_image_layout(&self) -> Layout {
+        self.image.descriptor_set_storage_image_layout()
+    }
+
+    #[inline]
+    fn descriptor_set_combined_image_sampler_layout(&self) -> Layout {
+        self.image.descriptor_set_combined_image_sampler_layout()
+    }
+
+    #[inline]
+    fn descriptor_set_sampled_image_layout(&self) -> Layout {
+        self.image.descriptor_set_sampled_image_layout()
+    }
+
+    #[inline]
+    fn descriptor_set_input_attachment_layout(&self) -> Layout {
+        self.image.descriptor_set_input_attachment_layout()
+    }
+
+    #[inline]
+    fn identity_swizzle(&self) -> bool {
+        self.view.identity_swizzle()
+    }
+}
+
+// Returns the range of `count` array layers starting at `first`, or an error if the end of the
+// range overflows.
+fn layers_range<I>(image: &Arc<I>, first: u32, count: u32)
+                   -> Result<Range<u32>, ImageViewCreationError>
+    where I: Image
+{
+    match first.checked_add(count) {
+        Some(end) => Ok(first .. end),
+        None => Err(ImageViewCreationError::ArrayLayersOutOfRange {
+            range: first .. u32::MAX,
+            image_array_layers: image.inner_image().dimensions().array_layers(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::u32;
+
+    use format::D16Unorm;
+    use format::Format;
+    use format::R8G8B8A8Unorm;
+    use format::R8Unorm;
+    use framebuffer::Framebuffer;
+    use framebuffer::FramebufferCreationError;
+    use image::ComponentSwizzle;
+    use image::Image;
+    use image::ImageAspects;
+    use image::ImageCreationError;
+    use image::ImageView;
+    use image::ImageViewCreationError;
+    use image::Swizzle;
+    use image::ViewType;
+    use image::attachment::AttachmentImage;
+    use image::immutable::ImmutableImage;
+    use image::sys::Dimensions;
+    use super::CustomImageView;
+
+    #[test]
+    fn swizzled_for_sampling() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let image = ImmutableImage::new(&device, Dimensions::Dim2d { width: 16, height: 16 },
+                                        R8Unorm, Some(queue.family())).unwrap();
+
+        let swizzle = Swizzle {
+            r: ComponentSwizzle::Red,
+            g: ComponentSwizzle::Red,
+            b: ComponentSwizzle::Red,
+            a: ComponentSwizzle::Red,
+        };
+
+        let view = CustomImageView::new(&image, 0 .. 1, 0 .. 1, swizzle).unwrap();
+        assert_eq!(view.swizzle(), swizzle);
+        assert!(!view.identity_swizzle());
+    }
+
+    #[test]
+    fn explicit_identity_swizzle() {
+        let swizzle = Swizzle {
+            r: ComponentSwizzle::Red,
+            g: ComponentSwizzle::Green,
+            b: ComponentSwizzle::Blue,
+            a: ComponentSwizzle::Alpha,
+        };
+
+        assert!(swizzle.is_identity());
+        assert!(Swizzle::default().is_identity());
+    }
+
+    mod example {
+        use format::R8G8B8A8Unorm;
+
+        single_pass_renderpass! {
+            attachments: {
+                color: {
+                    load: Clear,
+                    store: DontCare,
+                    format: R8G8B8A8Unorm,
+                }
+            },
+            pass: {
+                color: [color],
+                depth_stencil: {}
+            }
+        }
+    }
+
+    #[test]
+    fn swizzled_attachment_rejected() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let render_pass = example::CustomRenderPass::new(&device, &example::Formats {
+            color: (R8G8B8A8Unorm, 1)
+        }).unwrap();
+
+        let image = AttachmentImage::new(&device, [64, 64], R8G8B8A8Unorm).unwrap();
+        let swizzle = Swizzle { r: ComponentSwizzle::Blue, b: ComponentSwizzle::Red,
+                                .. Swizzle::default() };
+        let view = CustomImageView::new(&image, 0 .. 1, 0 .. 1, swizzle).unwrap();
+
+        match Framebuffer::new(&render_pass, [64, 64, 1], example::AList { color: &view }) {
+            Err(FramebufferCreationError::AttachmentNotIdentitySwizzled) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn cube_view() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let image = ImmutableImage::cubemap(&device, 32, R8G8B8A8Unorm, 1,
+                                            Some(queue.family())).unwrap();
+        let view = CustomImageView::cube(&image, 0).unwrap();
+        assert_eq!(view.ty(), ViewType::Cubemap);
+        assert_eq!(view.array_layers(), 0 .. 6);
+    }
+
+    #[test]
+    fn cube_view_not_cube_compatible() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let image = ImmutableImage::new(&device, Dimensions::Dim2dArray {
+            width: 32, height: 32, array_layers: 6
+        }, R8G8B8A8Unorm, Some(queue.family())).unwrap();
+
+        match CustomImageView::cube(&image, 0) {
+            Err(ImageViewCreationError::IncompatibleViewType { .. }) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn cube_view_layers_overflow() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let image = ImmutableImage::cubemap(&device, 32, R8G8B8A8Unorm, 1,
+                                            Some(queue.family())).unwrap();
+
+        match CustomImageView::cube(&image, u32::MAX - 2) {
+            Err(ImageViewCreationError::ArrayLayersOutOfRange { image_array_layers: 6, .. }) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn depth_view_of_depth_stencil() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        // Either `D24Unorm_S8Uint` or `D32Sfloat_S8Uint` is guaranteed to be supported.
+        let image = [Format::D24Unorm_S8Uint, Format::D32Sfloat_S8Uint].iter().filter_map(|&f| {
+            match AttachmentImage::new(&device, [64, 64], f) {
+                Ok(img) => Some(img),
+                Err(ImageCreationError::FormatNotSupported) => None,
+                Err(ImageCreationError::UnsupportedUsage) => None,
+                Err(err) => panic!("{:?}", err)
+            }
+        }).next().unwrap();
+
+        assert!(image.inner_image().format().has_depth());
+        assert!(image.inner_image().format().has_stencil());
+        assert_eq!(image.inner_view().aspects(), ImageAspects {
+            depth: true, stencil: true, .. ImageAspects::none()
+        });
+
+        let view = CustomImageView::depth(&image).unwrap();
+        assert_eq!(view.aspects(), ImageAspects { depth: true, .. ImageAspects::none() });
+    }
+
+    #[test]
+    fn stencil_view_of_depth_only() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let image = AttachmentImage::new(&device, [64, 64], D16Unorm).unwrap();
+        assert!(!image.inner_image().format().has_stencil());
+
+        match CustomImageView::stencil(&image) {
+            Err(ImageViewCreationError::InvalidAspects { .. }) => (),
+            _ => panic!()
+        }
+    }
+}
@@ -40,6 +40,7 @@ use instance::Instance;
 
 use check_errors;
 use Error;
+use OomError;
 use VulkanObject;
 use VulkanPointers;
 use vk;
@@ -225,6 +226,10 @@ impl MessageTypes {
 pub enum DebugCallbackCreationError {
     /// The `EXT_debug_report` extension was not enabled.
     MissingExtension,
+    /// Not enough memory.
+    OomError(OomError),
+    /// The Vulkan implementation returned an error that this operation isn't supposed to return.
+    Unexpected(Error),
 }
 
 impl error::Error for DebugCallbackCreationError {
@@ -233,6 +238,19 @@ impl error::Error for DebugCallbackCreationError {
         match *self {
             DebugCallbackCreationError::MissingExtension => "the `EXT_debug_report` extension was \
                                                              not enabled",
+            DebugCallbackCreationError::OomError(_) => "not enough memory available",
+            DebugCallbackCreationError::Unexpected(_) => {
+                "unexpected error returned by the Vulkan implementation"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            DebugCallbackCreationError::OomError(ref err) => Some(err),
+            DebugCallbackCreationError::Unexpected(ref err) => Some(err),
+            _ => None
         }
     }
 }
@@ -244,9 +262,24 @@ impl fmt::Display for DebugCallbackCreationError {
     }
 }
 
+impl From<OomError> for DebugCallbackCreationError {
+    #[inline]
+    fn from(err: OomError) -> DebugCallbackCreationError {
+        DebugCallbackCreationError::OomError(err)
+    }
+}
+
 impl From<Error> for DebugCallbackCreationError {
     #[inline]
     fn from(err: Error) -> DebugCallbackCreationError {
-        panic!("unexpected error: {:?}", err)
+        match err {
+            err @ Error::OutOfHostMemory => {
+                DebugCallbackCreationError::OomError(OomError::from(err))
+            },
+            err @ Error::OutOfDeviceMemory => {
+                DebugCallbackCreationError::OomError(OomError::from(err))
+            },
+            _ => DebugCallbackCreationError::Unexpected(err)
+        }
     }
 }
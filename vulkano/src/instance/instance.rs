@@ -20,6 +20,7 @@ use smallvec::SmallVec;
 
 //use alloc::Alloc;
 use instance::loader;
+use instance::loader::LoadingError;
 use check_errors;
 use Error;
 use OomError;
@@ -28,6 +29,17 @@ use VulkanPointers;
 use vk;
 
 use features::Features;
+use format::Format;
+use format::FormatFeatures;
+use format::FormatProperties;
+use image::ImageCreateFlags;
+use image::ImageFormatProperties;
+use image::ImageType;
+use image::SparseLevel;
+use image::SupportedSampleCounts;
+use image::ImageUsage;
+use memory::MemoryRequirements;
+use memory::MemoryTypePreference;
 use version::Version;
 use instance::InstanceExtensions;
 
@@ -96,7 +108,7 @@ impl Instance {
             extension.as_ptr()
         }).collect::<SmallVec<[_; 32]>>();
 
-        let entry_points = loader::entry_points().unwrap();     // TODO: return proper error
+        let entry_points = try!(loader::entry_points());
 
         // Creating the Vulkan instance.
         let instance = unsafe {
@@ -122,7 +134,7 @@ impl Instance {
 
         // Loading the function pointers of the newly-created instance.
         let vk = {
-            let f = loader::static_functions().unwrap();        // TODO: return proper error
+            let f = try!(loader::static_functions());
             vk::InstancePointers::load(|name| unsafe {
                 mem::transmute(f.GetInstanceProcAddr(instance, name.as_ptr()))
             })
@@ -209,12 +221,7 @@ impl Instance {
     }
 }
 
-impl fmt::Debug for Instance {
-    #[inline]
-    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        write!(fmt, "<Vulkan instance>")
-    }
-}
+impl_debug_handle!(Instance);
 
 unsafe impl VulkanObject for Instance {
     type Object = vk::Instance;
@@ -289,8 +296,10 @@ impl<'a> Default for ApplicationInfo<'a> {
 }
 
 /// Error that can happen when creating an instance.
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum InstanceCreationError {
+    /// Failed to load the Vulkan shared library.
+    LoadingError(LoadingError),
     /// Not enough memory.
     OomError(OomError),
     /// Failed to initialize for an implementation-specific reason.
@@ -301,24 +310,32 @@ pub enum InstanceCreationError {
     ExtensionNotPresent,
     /// The version requested is not supported by the implementation.
     IncompatibleDriver,
+    /// The Vulkan implementation returned an error that this operation isn't supposed to return.
+    Unexpected(Error),
 }
 
 impl error::Error for InstanceCreationError {
     #[inline]
     fn description(&self) -> &str {
         match *self {
+            InstanceCreationError::LoadingError(_) => "failed to load the Vulkan shared library",
             InstanceCreationError::OomError(_) => "not enough memory available",
             InstanceCreationError::InitializationFailed => "initialization failed",
             InstanceCreationError::LayerNotPresent => "layer not present",
             InstanceCreationError::ExtensionNotPresent => "extension not present",
             InstanceCreationError::IncompatibleDriver => "incompatible driver",
+            InstanceCreationError::Unexpected(_) => {
+                "unexpected error returned by the Vulkan implementation"
+            },
         }
     }
 
     #[inline]
     fn cause(&self) -> Option<&error::Error> {
         match *self {
+            InstanceCreationError::LoadingError(ref err) => Some(err),
             InstanceCreationError::OomError(ref err) => Some(err),
+            InstanceCreationError::Unexpected(ref err) => Some(err),
             _ => None
         }
     }
@@ -331,6 +348,13 @@ impl fmt::Display for InstanceCreationError {
     }
 }
 
+impl From<LoadingError> for InstanceCreationError {
+    #[inline]
+    fn from(err: LoadingError) -> InstanceCreationError {
+        InstanceCreationError::LoadingError(err)
+    }
+}
+
 impl From<OomError> for InstanceCreationError {
     #[inline]
     fn from(err: OomError) -> InstanceCreationError {
@@ -348,7 +372,7 @@ impl From<Error> for InstanceCreationError {
             Error::LayerNotPresent => InstanceCreationError::LayerNotPresent,
             Error::ExtensionNotPresent => InstanceCreationError::ExtensionNotPresent,
             Error::IncompatibleDriver => InstanceCreationError::IncompatibleDriver,
-            _ => panic!("unexpected error: {:?}", err)
+            _ => InstanceCreationError::Unexpected(err)
         }
     }
 }
@@ -488,6 +512,30 @@ impl<'a> PhysicalDevice<'a> {
         }
     }
 
+    /// Chooses a memory type for a resource with the given memory requirements.
+    ///
+    /// Returns the first memory type of `memory_types_by_preference`, or `None` if no memory
+    /// type is suitable.
+    #[inline]
+    pub fn choose_memory_type(&self, requirements: &MemoryRequirements,
+                              preferred: MemoryTypePreference) -> Option<MemoryType<'a>>
+    {
+        self.memory_types_by_preference(requirements, preferred).into_iter().next()
+    }
+
+    /// Returns the memory types allowed by the requirements that can be chosen for `preferred`,
+    /// from the most to the least preferred.
+    ///
+    /// See the documentation of `MemoryTypePreference` for the order of each preference.
+    pub fn memory_types_by_preference(&self, requirements: &MemoryRequirements,
+                                      preferred: MemoryTypePreference) -> Vec<MemoryType<'a>>
+    {
+        let types = self.memory_types().map(|t| t.flags()).collect::<Vec<_>>();
+        memory_type_order(&types, requirements, preferred).into_iter()
+            .map(|id| self.memory_type_by_id(id).unwrap())
+            .collect()
+    }
+
     /// Builds an iterator that enumerates all the memory heaps on this physical device.
     #[inline]
     pub fn memory_heaps(&self) -> MemoryHeapsIter<'a> {
@@ -520,6 +568,80 @@ impl<'a> PhysicalDevice<'a> {
         Limits { device: *self }
     }
 
+    /// Queries the capabilities of the physical device for images of the given format, type,
+    /// tiling, usage and create flags. The limits can be different for sparse images, so the
+    /// sparse flags the image is created with must be passed as well.
+    ///
+    /// Returns `Ok(None)` if the combination is not supported at all.
+    pub fn image_format_properties(&self, format: Format, ty: ImageType, linear_tiling: bool,
+                                   usage: &ImageUsage, flags: ImageCreateFlags,
+                                   sparse: SparseLevel)
+                                   -> Result<Option<ImageFormatProperties>, OomError>
+    {
+        let vk = self.instance.pointers();
+
+        let tiling = if linear_tiling {
+            vk::IMAGE_TILING_LINEAR
+        } else {
+            vk::IMAGE_TILING_OPTIMAL
+        };
+
+        let flags = flags.to_flags() | sparse.to_flags();
+
+        let output: vk::ImageFormatProperties = unsafe {
+            let mut output = mem::uninitialized();
+            let r = vk.GetPhysicalDeviceImageFormatProperties(self.internal_object(),
+                                                              format as u32, ty as u32, tiling,
+                                                              usage.to_usage_bits(), flags,
+                                                              &mut output);
+
+            match check_errors(r) {
+                Ok(_) => (),
+                Err(Error::FormatNotSupported) => return Ok(None),
+                Err(err) => return Err(err.into()),
+            }
+
+            output
+        };
+
+        Ok(Some(ImageFormatProperties {
+            max_extent: [output.maxExtent.width, output.maxExtent.height,
+                         output.maxExtent.depth],
+            max_mipmap_levels: output.maxMipLevels,
+            max_array_layers: output.maxArrayLayers,
+            sample_counts: SupportedSampleCounts::from_bits(output.sampleCounts),
+            max_resource_size: output.maxResourceSize as usize,
+        }))
+    }
+
+    /// Queries the features that the physical device supports for the given format.
+    ///
+    /// In debug mode, panicks if the implementation reports fewer features than the minimum
+    /// required by the specifications (see `Format::required_optimal_features`).
+    pub fn format_properties(&self, format: Format) -> FormatProperties {
+        let vk = self.instance.pointers();
+
+        let output: vk::FormatProperties = unsafe {
+            let mut output = mem::uninitialized();
+            vk.GetPhysicalDeviceFormatProperties(self.internal_object(), format as u32,
+                                                 &mut output);
+            output
+        };
+
+        let properties = FormatProperties {
+            linear_tiling_features: FormatFeatures::from_bits(output.linearTilingFeatures),
+            optimal_tiling_features: FormatFeatures::from_bits(output.optimalTilingFeatures),
+            buffer_features: FormatFeatures::from_bits(output.bufferFeatures),
+        };
+
+        debug_assert!(properties.optimal_tiling_features
+                                .is_superset_of(&format.required_optimal_features()),
+                      "The implementation doesn't support the features required by the \
+                       specifications for {:?}", format);
+
+        properties
+    }
+
     /// Returns an opaque number representing the version of the driver of this device.
     #[inline]
     pub fn driver_version(&self) -> u32 {
@@ -560,6 +682,63 @@ unsafe impl<'a> VulkanObject for PhysicalDevice<'a> {
     }
 }
 
+// Orders the memory types whose property flags are `types`. See
+// `PhysicalDevice::memory_types_by_preference`.
+fn memory_type_order(types: &[vk::MemoryPropertyFlags], requirements: &MemoryRequirements,
+                     preferred: MemoryTypePreference) -> Vec<u32>
+{
+    const DEVICE_LOCAL: u32 = vk::MEMORY_PROPERTY_DEVICE_LOCAL_BIT;
+    const HOST_VISIBLE: u32 = vk::MEMORY_PROPERTY_HOST_VISIBLE_BIT;
+    const HOST_COHERENT: u32 = vk::MEMORY_PROPERTY_HOST_COHERENT_BIT;
+    const HOST_CACHED: u32 = vk::MEMORY_PROPERTY_HOST_CACHED_BIT;
+    const LAZY: u32 = vk::MEMORY_PROPERTY_LAZILY_ALLOCATED_BIT;
+
+    // Each level is a pair of the properties that a memory type must have and of the properties
+    // that it must not have, from the most to the least preferred.
+    let levels: &[(u32, u32)] = match preferred {
+        MemoryTypePreference::DeviceLocal => {
+            &[(DEVICE_LOCAL, HOST_VISIBLE), (DEVICE_LOCAL, 0), (0, 0)]
+        },
+        MemoryTypePreference::DeviceLocalOnly => {
+            &[(DEVICE_LOCAL, HOST_VISIBLE), (DEVICE_LOCAL, 0)]
+        },
+        MemoryTypePreference::HostVisibleOnly => {
+            &[(HOST_VISIBLE, DEVICE_LOCAL), (HOST_VISIBLE, 0)]
+        },
+        MemoryTypePreference::PreferDeviceLocalHostVisible { fallback: true } => {
+            &[(DEVICE_LOCAL | HOST_VISIBLE, 0), (HOST_VISIBLE, 0)]
+        },
+        MemoryTypePreference::PreferDeviceLocalHostVisible { fallback: false } => {
+            &[(DEVICE_LOCAL | HOST_VISIBLE, 0)]
+        },
+        MemoryTypePreference::Staging => {
+            &[(HOST_VISIBLE | HOST_COHERENT, DEVICE_LOCAL | HOST_CACHED),
+              (HOST_VISIBLE, DEVICE_LOCAL), (HOST_VISIBLE, 0)]
+        },
+        MemoryTypePreference::HostCached => {
+            &[(HOST_VISIBLE | HOST_CACHED, 0), (HOST_VISIBLE, 0)]
+        },
+        MemoryTypePreference::Transient => {
+            &[(LAZY, 0), (DEVICE_LOCAL, 0), (0, 0)]
+        },
+    };
+
+    let mut order = Vec::with_capacity(types.len());
+
+    for &(with, without) in levels {
+        for (id, &flags) in types.iter().enumerate() {
+            let id = id as u32;
+            if requirements.allows_type_id(id) && (flags & with) == with &&
+               (flags & without) == 0 && !order.contains(&id)
+            {
+                order.push(id);
+            }
+        }
+    }
+
+    order
+}
+
 /// Iterator for all the physical devices available on hardware.
 #[derive(Debug, Clone)]
 pub struct PhysicalDevicesIter<'a> {
@@ -659,6 +838,16 @@ impl<'a> QueueFamily<'a> {
         (self.flags() & vk::QUEUE_SPARSE_BINDING_BIT) != 0
     }
 
+    /// Returns the number of meaningful bits of the timestamps written by queues of this family,
+    /// or `None` if they don't support timestamps.
+    #[inline]
+    pub fn timestamp_valid_bits(&self) -> Option<u32> {
+        match self.physical_device.infos().queue_families[self.id as usize].timestampValidBits {
+            0 => None,
+            bits => Some(bits),
+        }
+    }
+
     /// Internal utility function that returns the flags of this queue family.
     #[inline]
     fn flags(&self) -> u32 {
@@ -1011,6 +1200,140 @@ impl<'a> ExactSizeIterator for MemoryHeapsIter<'a> {}
 #[cfg(test)]
 mod tests {
     use instance;
+    use memory::MemoryRequirements;
+    use memory::MemoryTypePreference;
+    use vk;
+
+    use super::memory_type_order;
+
+    const DEVICE_LOCAL: u32 = vk::MEMORY_PROPERTY_DEVICE_LOCAL_BIT;
+    const HOST_VISIBLE: u32 = vk::MEMORY_PROPERTY_HOST_VISIBLE_BIT;
+    const HOST_COHERENT: u32 = vk::MEMORY_PROPERTY_HOST_COHERENT_BIT;
+    const HOST_CACHED: u32 = vk::MEMORY_PROPERTY_HOST_CACHED_BIT;
+    const LAZY: u32 = vk::MEMORY_PROPERTY_LAZILY_ALLOCATED_BIT;
+
+    // Memory types of a discrete AMD GPU, which has a small heap that is both device-local and
+    // host-visible.
+    const AMD: [u32; 4] = [
+        DEVICE_LOCAL,
+        HOST_VISIBLE | HOST_COHERENT,
+        DEVICE_LOCAL | HOST_VISIBLE | HOST_COHERENT,
+        HOST_VISIBLE | HOST_COHERENT | HOST_CACHED,
+    ];
+
+    // Memory types of a discrete NVIDIA GPU. The first one has no property at all.
+    const NVIDIA: [u32; 4] = [
+        0,
+        DEVICE_LOCAL,
+        HOST_VISIBLE | HOST_COHERENT,
+        HOST_VISIBLE | HOST_COHERENT | HOST_CACHED,
+    ];
+
+    // Memory types of an integrated Intel GPU, where all the memory is device-local.
+    const INTEL: [u32; 2] = [
+        DEVICE_LOCAL | HOST_VISIBLE | HOST_COHERENT,
+        DEVICE_LOCAL | HOST_VISIBLE | HOST_COHERENT | HOST_CACHED,
+    ];
+
+    fn order(types: &[u32], memory_type_bits: u32, preferred: MemoryTypePreference) -> Vec<u32> {
+        let reqs = MemoryRequirements {
+            size: 256,
+            alignment: 1,
+            memory_type_bits: memory_type_bits,
+        };
+        memory_type_order(types, &reqs, preferred)
+    }
+
+    fn choose(types: &[u32], memory_type_bits: u32, preferred: MemoryTypePreference)
+              -> Option<u32>
+    {
+        order(types, memory_type_bits, preferred).first().cloned()
+    }
+
+    #[test]
+    fn memory_type_order_amd() {
+        assert_eq!(order(&AMD, !0, MemoryTypePreference::DeviceLocal), vec![0, 2, 1, 3]);
+        assert_eq!(order(&AMD, !0, MemoryTypePreference::DeviceLocalOnly), vec![0, 2]);
+        assert_eq!(order(&AMD, !0, MemoryTypePreference::HostVisibleOnly), vec![1, 3, 2]);
+        assert_eq!(order(&AMD, !0, MemoryTypePreference::Staging), vec![1, 3, 2]);
+        assert_eq!(order(&AMD, !0, MemoryTypePreference::HostCached), vec![3, 1, 2]);
+
+        let pref = MemoryTypePreference::PreferDeviceLocalHostVisible { fallback: true };
+        assert_eq!(order(&AMD, !0, pref), vec![2, 1, 3]);
+        let pref = MemoryTypePreference::PreferDeviceLocalHostVisible { fallback: false };
+        assert_eq!(order(&AMD, !0, pref), vec![2]);
+    }
+
+    #[test]
+    fn memory_type_order_nvidia() {
+        assert_eq!(order(&NVIDIA, !0, MemoryTypePreference::DeviceLocal), vec![1, 0, 2, 3]);
+        assert_eq!(order(&NVIDIA, !0, MemoryTypePreference::DeviceLocalOnly), vec![1]);
+        assert_eq!(order(&NVIDIA, !0, MemoryTypePreference::HostVisibleOnly), vec![2, 3]);
+        assert_eq!(order(&NVIDIA, !0, MemoryTypePreference::Staging), vec![2, 3]);
+        assert_eq!(order(&NVIDIA, !0, MemoryTypePreference::HostCached), vec![3, 2]);
+        assert_eq!(order(&NVIDIA, !0, MemoryTypePreference::Transient), vec![1, 0, 2, 3]);
+
+        let pref = MemoryTypePreference::PreferDeviceLocalHostVisible { fallback: true };
+        assert_eq!(order(&NVIDIA, !0, pref), vec![2, 3]);
+        let pref = MemoryTypePreference::PreferDeviceLocalHostVisible { fallback: false };
+        assert_eq!(order(&NVIDIA, !0, pref), vec![]);
+    }
+
+    #[test]
+    fn memory_type_order_intel() {
+        assert_eq!(order(&INTEL, !0, MemoryTypePreference::DeviceLocal), vec![0, 1]);
+        assert_eq!(order(&INTEL, !0, MemoryTypePreference::DeviceLocalOnly), vec![0, 1]);
+        assert_eq!(order(&INTEL, !0, MemoryTypePreference::HostVisibleOnly), vec![0, 1]);
+        assert_eq!(order(&INTEL, !0, MemoryTypePreference::Staging), vec![0, 1]);
+        assert_eq!(order(&INTEL, !0, MemoryTypePreference::HostCached), vec![1, 0]);
+
+        let pref = MemoryTypePreference::PreferDeviceLocalHostVisible { fallback: false };
+        assert_eq!(order(&INTEL, !0, pref), vec![0, 1]);
+    }
+
+    #[test]
+    fn memory_type_order_requirements() {
+        assert_eq!(order(&AMD, 0b1010, MemoryTypePreference::DeviceLocal), vec![1, 3]);
+        assert_eq!(order(&AMD, 0b1010, MemoryTypePreference::DeviceLocalOnly), vec![]);
+        assert_eq!(order(&AMD, 0b0101, MemoryTypePreference::HostVisibleOnly), vec![2]);
+        assert_eq!(order(&AMD, 0, MemoryTypePreference::DeviceLocal), vec![]);
+    }
+
+    #[test]
+    fn choose_transient_memory_type() {
+        let types = [HOST_VISIBLE, DEVICE_LOCAL, DEVICE_LOCAL | LAZY];
+        let pref = MemoryTypePreference::Transient;
+
+        assert_eq!(choose(&types, 0b111, pref), Some(2));
+        // Falls back to device-local memory, then to any memory type.
+        assert_eq!(choose(&types, 0b011, pref), Some(1));
+        assert_eq!(choose(&types, 0b001, pref), Some(0));
+        assert_eq!(choose(&types, 0b000, pref), None);
+    }
+
+    #[test]
+    fn choose_device_local_memory_type() {
+        let types = [HOST_VISIBLE, DEVICE_LOCAL | LAZY, DEVICE_LOCAL];
+        let pref = MemoryTypePreference::DeviceLocal;
+
+        assert_eq!(choose(&types, 0b111, pref), Some(1));
+        assert_eq!(choose(&types, 0b101, pref), Some(2));
+        assert_eq!(choose(&types, 0b001, pref), Some(0));
+    }
+
+    #[test]
+    fn choose_host_memory_type() {
+        let types = [DEVICE_LOCAL, HOST_VISIBLE, HOST_VISIBLE | HOST_CACHED];
+
+        let pref = MemoryTypePreference::HostCached;
+        assert_eq!(choose(&types, 0b111, pref), Some(2));
+        assert_eq!(choose(&types, 0b011, pref), Some(1));
+        assert_eq!(choose(&types, 0b001, pref), None);
+
+        let pref = MemoryTypePreference::HostVisibleOnly;
+        assert_eq!(choose(&types, 0b111, pref), Some(1));
+        assert_eq!(choose(&types, 0b001, pref), None);
+    }
 
     #[test]
     fn create_instance() {
@@ -78,7 +78,7 @@ pub fn entry_points() -> Result<&'static vk::EntryPoints, LoadingError> {
 }
 
 /// Error that can happen when loading the Vulkan loader.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum LoadingError {
     /// Failed to load the Vulkan shared library.
     LibraryLoadFailure(String),         // TODO: meh for error type, but this needs changes in shared_library
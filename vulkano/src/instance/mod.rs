@@ -46,6 +46,7 @@
 pub use features::Features;
 pub use self::extensions::DeviceExtensions;
 pub use self::extensions::InstanceExtensions;
+pub use self::extensions::SupportedExtensionsError;
 pub use self::instance::Instance;
 pub use self::instance::InstanceCreationError;
 pub use self::instance::ApplicationInfo;
@@ -62,6 +63,7 @@ pub use self::instance::Limits;
 pub use self::layers::layers_list;
 pub use self::layers::LayerProperties;
 pub use self::layers::LayersIterator;
+pub use self::layers::LayersListError;
 pub use self::loader::LoadingError;
 
 pub mod debug;
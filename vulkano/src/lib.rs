@@ -56,6 +56,26 @@ extern crate shared_library;
 extern crate smallvec;
 extern crate vk_sys as vk;
 
+/// Implements `fmt::Debug` for a type that implements `VulkanObject`. The type name is printed
+/// followed by the raw handle in hexadecimal, which is the way the validation layers show it.
+///
+/// Generic parameters and bounds must be passed the same way as for the `VulkanObject` impl, for
+/// example `impl_debug_handle!(BufferView<F, B> where B: Buffer)`.
+macro_rules! impl_debug_handle {
+    ($name:ident $(<$($param:ident),+>)*) => (
+        impl_debug_handle!($name $(<$($param),+>)* where);
+    );
+
+    ($name:ident $(<$($param:ident),+>)* where $($bounds:tt)*) => (
+        impl $(<$($param),+>)* ::std::fmt::Debug for $name $(<$($param),+>)* where $($bounds)* {
+            #[inline]
+            fn fmt(&self, fmt: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+                write!(fmt, "{}({:#x})", stringify!($name), ::VulkanObject::internal_object(self))
+            }
+        }
+    );
+}
+
 #[macro_use]
 mod tests;
 
@@ -138,6 +158,8 @@ pub enum OomError {
     OutOfHostMemory,
     /// There is no memory available on the device (ie. video memory).
     OutOfDeviceMemory,
+    /// The Vulkan implementation returned an error that this operation isn't supposed to return.
+    Unexpected(Error),
 }
 
 impl error::Error for OomError {
@@ -146,6 +168,15 @@ impl error::Error for OomError {
         match *self {
             OomError::OutOfHostMemory => "no memory available on the host",
             OomError::OutOfDeviceMemory => "no memory available on the graphical device",
+            OomError::Unexpected(_) => "unexpected error returned by the Vulkan implementation",
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            OomError::Unexpected(ref err) => Some(err),
+            _ => None
         }
     }
 }
@@ -163,13 +194,13 @@ impl From<Error> for OomError {
         match err {
             Error::OutOfHostMemory => OomError::OutOfHostMemory,
             Error::OutOfDeviceMemory => OomError::OutOfDeviceMemory,
-            _ => panic!("unexpected error: {:?}", err)
+            _ => OomError::Unexpected(err)
         }
     }
 }
 
 /// All possible success codes returned by any Vulkan function.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(u32)]
 enum Success {
     Success = vk::SUCCESS,
@@ -183,28 +214,87 @@ enum Success {
 
 /// All possible errors returned by any Vulkan function.
 ///
-/// This type is not public. Instead all public error types should implement `From<Error>` and
-/// panic for error code that arent supposed to happen.
-#[derive(Debug, Copy, Clone)]
-#[repr(u32)]
-#[doc(hidden)]      // TODO: this is necessary because of the stupid visibility rules in rustc
+/// All public error types implement `From<Error>`. Error codes that an operation isn't supposed
+/// to return are kept in an `Unexpected` variant of the public error instead of panicking.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Error {
-    OutOfHostMemory = vk::ERROR_OUT_OF_HOST_MEMORY,
-    OutOfDeviceMemory = vk::ERROR_OUT_OF_DEVICE_MEMORY,
-    InitializationFailed = vk::ERROR_INITIALIZATION_FAILED,
-    DeviceLost = vk::ERROR_DEVICE_LOST,
-    MemoryMapFailed = vk::ERROR_MEMORY_MAP_FAILED,
-    LayerNotPresent = vk::ERROR_LAYER_NOT_PRESENT,
-    ExtensionNotPresent = vk::ERROR_EXTENSION_NOT_PRESENT,
-    FeatureNotPresent = vk::ERROR_FEATURE_NOT_PRESENT,
-    IncompatibleDriver = vk::ERROR_INCOMPATIBLE_DRIVER,
-    TooManyObjects = vk::ERROR_TOO_MANY_OBJECTS,
-    FormatNotSupported = vk::ERROR_FORMAT_NOT_SUPPORTED,
-    SurfaceLost = vk::ERROR_SURFACE_LOST_KHR,
-    NativeWindowInUse = vk::ERROR_NATIVE_WINDOW_IN_USE_KHR,
-    OutOfDate = vk::ERROR_OUT_OF_DATE_KHR,
-    IncompatibleDisplay = vk::ERROR_INCOMPATIBLE_DISPLAY_KHR,
-    ValidationFailed = vk::ERROR_VALIDATION_FAILED_EXT,
+    /// A host memory allocation has failed.
+    OutOfHostMemory,
+    /// A device memory allocation has failed.
+    OutOfDeviceMemory,
+    /// Initialization of an object couldn't be completed.
+    InitializationFailed,
+    /// The logical or physical device has been lost.
+    DeviceLost,
+    /// Mapping of a memory object has failed.
+    MemoryMapFailed,
+    /// A requested layer isn't present or couldn't be loaded.
+    LayerNotPresent,
+    /// A requested extension isn't supported.
+    ExtensionNotPresent,
+    /// A requested feature isn't supported.
+    FeatureNotPresent,
+    /// The requested version of Vulkan isn't supported by the driver.
+    IncompatibleDriver,
+    /// Too many objects of this type have already been created.
+    TooManyObjects,
+    /// A requested format isn't supported on this device.
+    FormatNotSupported,
+    /// A pool allocation has failed due to fragmentation of the pool's memory.
+    FragmentedPool,
+    /// The surface is no longer available.
+    SurfaceLost,
+    /// The window is already in use by Vulkan or another API.
+    NativeWindowInUse,
+    /// The surface has changed and the swapchain must be recreated.
+    OutOfDate,
+    /// The display used by a swapchain doesn't use the same presentable image layout.
+    IncompatibleDisplay,
+    /// A validation layer found an error.
+    ValidationFailed,
+    /// A pool allocation has failed because there is no space left in the pool.
+    OutOfPoolMemory,
+    /// An error code that vulkano doesn't know about.
+    Unknown(vk::Result),
+}
+
+impl error::Error for Error {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            Error::OutOfHostMemory => "no memory available on the host",
+            Error::OutOfDeviceMemory => "no memory available on the graphical device",
+            Error::InitializationFailed => "initialization of an object has failed",
+            Error::DeviceLost => "the device has been lost",
+            Error::MemoryMapFailed => "mapping of a memory object has failed",
+            Error::LayerNotPresent => "a requested layer is not present",
+            Error::ExtensionNotPresent => "a requested extension is not supported",
+            Error::FeatureNotPresent => "a requested feature is not supported",
+            Error::IncompatibleDriver => "the requested version of Vulkan is not supported",
+            Error::TooManyObjects => "too many objects of this type have been created",
+            Error::FormatNotSupported => "a requested format is not supported",
+            Error::FragmentedPool => "the pool is too fragmented",
+            Error::SurfaceLost => "the surface is no longer available",
+            Error::NativeWindowInUse => "the window is already in use",
+            Error::OutOfDate => "the surface has changed and the swapchain must be recreated",
+            Error::IncompatibleDisplay => "the display is incompatible with the swapchain",
+            Error::ValidationFailed => "a validation layer found an error",
+            Error::OutOfPoolMemory => "there is no space left in the pool",
+            Error::Unknown(_) => "unknown error code returned by the Vulkan implementation",
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            Error::Unknown(code) => {
+                write!(fmt, "{} ({})", error::Error::description(self), code as i32)
+            },
+            _ => write!(fmt, "{}", error::Error::description(self)),
+        }
+    }
 }
 
 /// Checks whether the result returned correctly.
@@ -227,12 +317,14 @@ fn check_errors(result: vk::Result) -> Result<Success, Error> {
         vk::ERROR_INCOMPATIBLE_DRIVER => Err(Error::IncompatibleDriver),
         vk::ERROR_TOO_MANY_OBJECTS => Err(Error::TooManyObjects),
         vk::ERROR_FORMAT_NOT_SUPPORTED => Err(Error::FormatNotSupported),
+        vk::ERROR_FRAGMENTED_POOL => Err(Error::FragmentedPool),
         vk::ERROR_SURFACE_LOST_KHR => Err(Error::SurfaceLost),
         vk::ERROR_NATIVE_WINDOW_IN_USE_KHR => Err(Error::NativeWindowInUse),
         vk::SUBOPTIMAL_KHR => Ok(Success::Suboptimal),
         vk::ERROR_OUT_OF_DATE_KHR => Err(Error::OutOfDate),
         vk::ERROR_INCOMPATIBLE_DISPLAY_KHR => Err(Error::IncompatibleDisplay),
         vk::ERROR_VALIDATION_FAILED_EXT => Err(Error::ValidationFailed),
-        c => unreachable!("Unexpected error code returned by Vulkan: {}", c)
+        vk::ERROR_OUT_OF_POOL_MEMORY_KHR => Err(Error::OutOfPoolMemory),
+        c => Err(Error::Unknown(c))
     }
 }
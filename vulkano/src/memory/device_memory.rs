@@ -7,6 +7,8 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
+use std::error;
+use std::fmt;
 use std::mem;
 use std::ptr;
 use std::ops::Deref;
@@ -18,6 +20,8 @@ use std::sync::Arc;
 use instance::MemoryType;
 use device::Device;
 use memory::Content;
+use memory::align_range_to_atom;
+use Error;
 use OomError;
 use SafeDeref;
 use VulkanObject;
@@ -40,24 +44,26 @@ impl<D> DeviceMemory<D> where D: SafeDeref<Target = Device> {
     /// Some platforms may have a limit on the maximum size of a single allocation. For example,
     /// certain systems may fail to create allocations with a size greater than or equal to 4GB. 
     ///
+    /// The allocations of each device are counted, and an error is returned without calling
+    /// Vulkan if the `max_memory_allocation_count` limit would be exceeded or if the heap of the
+    /// memory type doesn't have enough space left.
+    ///
     /// # Panic
     ///
     /// - Panicks if `size` is 0.
     /// - Panicks if `memory_type` doesn't belong to the same physical device as `device`.
     ///
-    // TODO: VK_ERROR_TOO_MANY_OBJECTS error
     #[inline]
     pub fn alloc(device: &D, memory_type: &MemoryType, size: usize)
-                 -> Result<DeviceMemory<D>, OomError>
+                 -> Result<DeviceMemory<D>, AllocationError>
         where D: Clone
     {
         assert!(size >= 1);
         assert_eq!(device.physical_device().internal_object(),
                    memory_type.physical_device().internal_object());
 
-        if size > memory_type.heap().size() {
-            return Err(OomError::OutOfDeviceMemory);
-        }
+        let heap_id = memory_type.heap().id();
+        try!(device.reserve_memory(heap_id, size));
 
         let vk = device.pointers();
 
@@ -70,9 +76,15 @@ impl<D> DeviceMemory<D> where D: SafeDeref<Target = Device> {
             };
 
             let mut output = mem::uninitialized();
-            try!(check_errors(vk.AllocateMemory(device.internal_object(), &infos,
-                                                ptr::null(), &mut output)));
-            output
+            match check_errors(vk.AllocateMemory(device.internal_object(), &infos,
+                                                 ptr::null(), &mut output))
+            {
+                Ok(_) => output,
+                Err(err) => {
+                    device.release_memory(heap_id, size);
+                    return Err(err.into());
+                },
+            }
         };
 
         Ok(DeviceMemory {
@@ -91,7 +103,7 @@ impl<D> DeviceMemory<D> where D: SafeDeref<Target = Device> {
     /// - Panicks if the memory type is not host-visible.
     ///
     pub fn alloc_and_map(device: &D, memory_type: &MemoryType, size: usize)
-                         -> Result<MappedDeviceMemory<D>, OomError>
+                         -> Result<MappedDeviceMemory<D>, AllocationError>
         where D: Clone
     {
         let vk = device.pointers();
@@ -133,6 +145,30 @@ impl<D> DeviceMemory<D> where D: SafeDeref<Target = Device> {
     pub fn device(&self) -> &Device {
         &self.device
     }
+
+    /// Returns the number of bytes of this allocation that are currently backed by physical
+    /// memory.
+    ///
+    /// Only lazily-allocated memory can be partially backed. The value can change at any time
+    /// while the memory is used by the GPU.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the memory type of this allocation is not lazily allocated.
+    ///
+    #[inline]
+    pub fn commitment(&self) -> usize {
+        assert!(self.memory_type().is_lazily_allocated(),
+                "The commitment can only be queried for lazily-allocated memory");
+
+        unsafe {
+            let vk = self.device.pointers();
+            let mut output = 0;
+            vk.GetDeviceMemoryCommitment(self.device.internal_object(), self.memory,
+                                         &mut output);
+            output as usize
+        }
+    }
 }
 
 unsafe impl<D> VulkanObject for DeviceMemory<D> where D: SafeDeref<Target = Device> {
@@ -152,6 +188,90 @@ impl<D> Drop for DeviceMemory<D> where D: SafeDeref<Target = Device> {
             let vk = device.pointers();
             vk.FreeMemory(device.internal_object(), self.memory, ptr::null());
         }
+
+        let heap_id = self.memory_type().heap().id();
+        self.device.release_memory(heap_id, self.size);
+    }
+}
+
+/// Error that can happen when allocating device memory.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AllocationError {
+    /// Not enough memory.
+    OomError(OomError),
+
+    /// The maximum number of device memory allocations of the device has been reached.
+    TooManyObjects,
+
+    /// The heap of the memory type doesn't have enough space left for the allocation, according
+    /// to the allocations of the device that are alive.
+    OutOfHeapMemory {
+        /// Index of the heap.
+        heap: u32,
+        /// Number of bytes that were requested.
+        requested: usize,
+        /// Number of bytes that are left in the heap.
+        remaining: usize,
+    },
+}
+
+impl error::Error for AllocationError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            AllocationError::OomError(_) => "not enough memory available",
+            AllocationError::TooManyObjects => {
+                "the maximum number of device memory allocations has been reached"
+            },
+            AllocationError::OutOfHeapMemory { .. } => {
+                "the heap doesn't have enough space left for the allocation"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            AllocationError::OomError(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for AllocationError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<OomError> for AllocationError {
+    #[inline]
+    fn from(err: OomError) -> AllocationError {
+        AllocationError::OomError(err)
+    }
+}
+
+impl From<Error> for AllocationError {
+    #[inline]
+    fn from(err: Error) -> AllocationError {
+        match err {
+            Error::TooManyObjects => AllocationError::TooManyObjects,
+            err => AllocationError::OomError(OomError::from(err)),
+        }
+    }
+}
+
+/// Allocation errors are reported as a lack of device memory by the functions that return an
+/// `OomError`.
+impl From<AllocationError> for OomError {
+    #[inline]
+    fn from(err: AllocationError) -> OomError {
+        match err {
+            AllocationError::OomError(err) => err,
+            AllocationError::TooManyObjects => OomError::OutOfDeviceMemory,
+            AllocationError::OutOfHeapMemory { .. } => OomError::OutOfDeviceMemory,
+        }
     }
 }
 
@@ -184,22 +304,11 @@ impl<D> MappedDeviceMemory<D> where D: SafeDeref<Target = Device> {
     pub unsafe fn read_write<T: ?Sized>(&self, range: Range<usize>) -> CpuAccess<T, D>
         where T: Content + 'static
     {
-        let vk = self.memory.device().pointers();
         let pointer = T::ref_from_ptr((self.pointer as usize + range.start) as *mut _,
                                       range.end - range.start).unwrap();       // TODO: error
 
-        if !self.coherent {
-            let range = vk::MappedMemoryRange {
-                sType: vk::STRUCTURE_TYPE_MAPPED_MEMORY_RANGE,
-                pNext: ptr::null(),
-                memory: self.memory.internal_object(),
-                offset: range.start as u64,
-                size: (range.end - range.start) as u64,
-            };
-
-            // TODO: check result?
-            vk.InvalidateMappedMemoryRanges(self.memory.device().internal_object(), 1, &range);
-        }
+        // TODO: check result?
+        let _ = self.invalidate_range(range.clone());
 
         CpuAccess {
             pointer: pointer,
@@ -208,6 +317,139 @@ impl<D> MappedDeviceMemory<D> where D: SafeDeref<Target = Device> {
             range: range,
         }
     }
+
+    /// Reads the content of a range of the memory.
+    ///
+    /// The range is invalidated before `f` is called, so that the writes of the device are
+    /// visible to the host.
+    ///
+    /// # Safety
+    ///
+    /// Same as `read_write`.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the range is out of the memory or is too small for a `T`.
+    ///
+    pub unsafe fn read<T: ?Sized, F, R>(&self, range: Range<usize>, f: F) -> Result<R, OomError>
+        where T: Content + 'static, F: FnOnce(&T) -> R
+    {
+        let pointer = self.pointer_to::<T>(range.clone());
+        try!(self.invalidate_range(range));
+        Ok(f(&*pointer))
+    }
+
+    /// Writes the content of a range of the memory.
+    ///
+    /// The range is invalidated before `f` is called and flushed afterwards, so that the writes
+    /// of the host are visible to the device.
+    ///
+    /// # Safety
+    ///
+    /// Same as `read_write`.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the range is out of the memory or is too small for a `T`.
+    ///
+    pub unsafe fn write<T: ?Sized, F, R>(&self, range: Range<usize>, f: F) -> Result<R, OomError>
+        where T: Content + 'static, F: FnOnce(&mut T) -> R
+    {
+        let pointer = self.pointer_to::<T>(range.clone());
+        try!(self.invalidate_range(range.clone()));
+        let result = f(&mut *pointer);
+        try!(self.flush_range(range));
+        Ok(result)
+    }
+
+    /// Makes the writes of the host to a range of the memory visible to the device.
+    ///
+    /// The range is extended to multiples of the `non_coherent_atom_size` limit of the physical
+    /// device, without going past the end of the memory. Does nothing if the memory is
+    /// host-coherent, which means that this function can be called unconditionally.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the range is out of the memory.
+    ///
+    pub fn flush_range(&self, range: Range<usize>) -> Result<(), OomError> {
+        let range = match self.non_coherent_range(range) {
+            Some(range) => range,
+            None => return Ok(()),
+        };
+
+        unsafe {
+            let device = self.memory.device();
+            let vk = device.pointers();
+            try!(check_errors(vk.FlushMappedMemoryRanges(device.internal_object(), 1, &range)));
+        }
+
+        Ok(())
+    }
+
+    /// Makes the writes of the device to a range of the memory visible to the host.
+    ///
+    /// The range is extended in the same way as `flush_range`. Does nothing if the memory is
+    /// host-coherent.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the range is out of the memory.
+    ///
+    pub fn invalidate_range(&self, range: Range<usize>) -> Result<(), OomError> {
+        let range = match self.non_coherent_range(range) {
+            Some(range) => range,
+            None => return Ok(()),
+        };
+
+        unsafe {
+            let device = self.memory.device();
+            let vk = device.pointers();
+            try!(check_errors(vk.InvalidateMappedMemoryRanges(device.internal_object(), 1,
+                                                              &range)));
+        }
+
+        Ok(())
+    }
+
+    // Builds the range to flush or invalidate in order to access `range`, or returns `None` if
+    // the memory is coherent.
+    fn non_coherent_range(&self, range: Range<usize>) -> Option<vk::MappedMemoryRange> {
+        let atom_size = self.memory.device().non_coherent_atom_size();
+
+        flush_range(self.coherent, range, atom_size, self.memory.size).map(|range| {
+            vk::MappedMemoryRange {
+                sType: vk::STRUCTURE_TYPE_MAPPED_MEMORY_RANGE,
+                pNext: ptr::null(),
+                memory: self.memory.internal_object(),
+                offset: range.start as u64,
+                size: (range.end - range.start) as u64,
+            }
+        })
+    }
+
+    // Returns a pointer to a range of the memory.
+    fn pointer_to<T: ?Sized>(&self, range: Range<usize>) -> *mut T where T: Content {
+        assert!(range.start <= range.end && range.end <= self.memory.size,
+                "The range is out of the memory");
+        T::ref_from_ptr((self.pointer as usize + range.start) as *mut _,
+                        range.end - range.start).expect("The range is too small for the type")
+    }
+}
+
+// Returns the range of a mapped memory of `size` bytes that must be flushed or invalidated in
+// order to access `range`, or `None` if the memory is coherent or if `range` is empty. See
+// `align_range_to_atom`.
+fn flush_range(coherent: bool, range: Range<usize>, atom_size: usize, size: usize)
+               -> Option<Range<usize>>
+{
+    let range = align_range_to_atom(range, atom_size, size);
+
+    if coherent || range.start == range.end {
+        return None;
+    }
+
+    Some(range)
 }
 
 unsafe impl<D> Send for MappedDeviceMemory<D> where D: SafeDeref<Target = Device> {}
@@ -271,21 +513,8 @@ impl<'a, T: ?Sized + 'a, D: 'a> Drop for CpuAccess<'a, T, D> where D: SafeDeref<
     fn drop(&mut self) {
         // If the memory doesn't have the `coherent` flag, we need to flush the data.
         if !self.coherent {
-            let vk = self.mem.memory().device().pointers();
-
-            let range = vk::MappedMemoryRange {
-                sType: vk::STRUCTURE_TYPE_MAPPED_MEMORY_RANGE,
-                pNext: ptr::null(),
-                memory: self.mem.memory().internal_object(),
-                offset: self.range.start as u64,
-                size: (self.range.end - self.range.start) as u64,
-            };
-
             // TODO: check result?
-            unsafe {
-                vk.FlushMappedMemoryRanges(self.mem.memory().device().internal_object(),
-                                           1, &range);
-            }
+            let _ = self.mem.flush_range(self.range.clone());
         }
     }
 }
@@ -293,7 +522,11 @@ impl<'a, T: ?Sized + 'a, D: 'a> Drop for CpuAccess<'a, T, D> where D: SafeDeref<
 #[cfg(test)]
 mod tests {
     use OomError;
+    use memory::AllocationError;
     use memory::DeviceMemory;
+    use memory::MemoryRequirements;
+    use memory::MemoryTypePreference;
+    use super::flush_range;
 
     #[test]
     fn create() {
@@ -310,6 +543,77 @@ mod tests {
         let _ = DeviceMemory::alloc(&device, &mem_ty, 0);
     }
 
+    #[test]
+    fn map_write_read() {
+        let (device, _) = gfx_dev_and_queue!();
+        let mem_ty = device.physical_device().memory_types().filter(|t| t.is_host_visible())
+                           .next().unwrap();
+        let mem = DeviceMemory::alloc_and_map(&device, &mem_ty, 16).unwrap();
+
+        unsafe {
+            let mut access = mem.read_write::<[u32]>(0 .. 16);
+            access.copy_from_slice(&[1, 2, 3, 4]);
+        }
+
+        unsafe {
+            let access = mem.read_write::<[u32]>(0 .. 16);
+            assert_eq!(&*access, &[1, 2, 3, 4]);
+        }
+
+        unsafe {
+            mem.write(4 .. 12, |data: &mut [u32]| data.copy_from_slice(&[5, 6])).unwrap();
+            let content = mem.read(0 .. 16, |data: &[u32]| data.to_vec()).unwrap();
+            assert_eq!(content, vec![1, 5, 6, 4]);
+        }
+    }
+
+    #[test]
+    fn flush_range_rounding() {
+        assert_eq!(flush_range(false, 0 .. 64, 64, 256), Some(0 .. 64));
+        assert_eq!(flush_range(false, 10 .. 20, 64, 256), Some(0 .. 64));
+        assert_eq!(flush_range(false, 70 .. 130, 64, 256), Some(64 .. 192));
+        assert_eq!(flush_range(false, 16 .. 16, 64, 256), None);
+    }
+
+    #[test]
+    fn flush_range_clamping() {
+        assert_eq!(flush_range(false, 200 .. 250, 64, 250), Some(192 .. 250));
+        assert_eq!(flush_range(false, 100 .. 250, 64, 250), Some(64 .. 250));
+        assert_eq!(flush_range(false, 0 .. 256, 64, 256), Some(0 .. 256));
+    }
+
+    #[test]
+    fn flush_range_coherent() {
+        assert_eq!(flush_range(true, 10 .. 20, 64, 256), None);
+        assert_eq!(flush_range(true, 0 .. 256, 64, 256), None);
+    }
+
+    #[test]
+    #[should_panic = "The range is out of the memory"]
+    fn flush_range_out_of_memory() {
+        let _ = flush_range(false, 200 .. 300, 64, 256);
+    }
+
+    #[test]
+    fn choose_memory_type() {
+        let (device, _) = gfx_dev_and_queue!();
+        let physical = device.physical_device();
+
+        let reqs = MemoryRequirements { size: 16, alignment: 1, memory_type_bits: !0 };
+        let mem_ty = physical.choose_memory_type(&reqs, MemoryTypePreference::HostVisibleOnly)
+                             .unwrap();
+        assert!(mem_ty.is_host_visible());
+
+        // The device-local preference falls back to other memory types.
+        let reqs = MemoryRequirements { memory_type_bits: 1 << mem_ty.id(), .. reqs };
+        let chosen = physical.choose_memory_type(&reqs, MemoryTypePreference::DeviceLocal)
+                             .unwrap();
+        assert_eq!(chosen.id(), mem_ty.id());
+
+        let reqs = MemoryRequirements { memory_type_bits: 0, .. reqs };
+        assert!(physical.choose_memory_type(&reqs, MemoryTypePreference::DeviceLocal).is_none());
+    }
+
     #[test]
     #[cfg(target_pointer_width = "64")]
     fn oom_single() {
@@ -318,7 +622,7 @@ mod tests {
                            .next().unwrap();
     
         match DeviceMemory::alloc(&device, &mem_ty, 0xffffffffffffffff) {
-            Err(OomError::OutOfDeviceMemory) => (),
+            Err(AllocationError::OutOfHeapMemory { .. }) => (),
             _ => panic!()
         }
     }
@@ -333,11 +637,83 @@ mod tests {
     
         for _ in 0 .. 4 {
             match DeviceMemory::alloc(&device, &mem_ty, heap_size / 3) {
-                Err(OomError::OutOfDeviceMemory) => return,     // test succeeded
+                Err(AllocationError::OutOfHeapMemory { .. }) => return,     // test succeeded
+                Err(AllocationError::OomError(OomError::OutOfDeviceMemory)) => return,
                 _ => ()
             }
         }
 
         panic!()
     }
+
+    #[test]
+    fn commitment() {
+        let (device, _) = gfx_dev_and_queue!();
+        let mem_ty = match device.physical_device().memory_types()
+                                 .filter(|m| m.is_lazily_allocated()).next()
+        {
+            Some(m) => m,
+            None => return,
+        };
+
+        let mem = DeviceMemory::alloc(&device, &mem_ty, 4096).unwrap();
+        assert!(mem.commitment() <= 4096);
+    }
+
+    #[test]
+    #[should_panic = "The commitment can only be queried for lazily-allocated memory"]
+    fn commitment_not_lazy() {
+        let (device, _) = gfx_dev_and_queue!();
+        let mem_ty = device.physical_device().memory_types().filter(|m| !m.is_lazily_allocated())
+                           .next().unwrap();
+
+        let mem = DeviceMemory::alloc(&device, &mem_ty, 256).unwrap();
+        mem.commitment();
+    }
+
+    #[test]
+    fn allocation_count() {
+        let (device, _) = gfx_dev_and_queue!();
+        let mem_ty = device.physical_device().memory_types().next().unwrap();
+        let heap_id = mem_ty.heap().id();
+        let heap_sizes = device.physical_device().memory_heaps().map(|h| h.size()).collect();
+        device.override_memory_limits(2, heap_sizes);
+
+        let a = DeviceMemory::alloc(&device, &mem_ty, 256).unwrap();
+        let b = DeviceMemory::alloc(&device, &mem_ty, 128).unwrap();
+        assert_eq!(device.memory_allocation_count(), 2);
+        assert_eq!(device.heap_usage(heap_id), 384);
+
+        match DeviceMemory::alloc(&device, &mem_ty, 256) {
+            Err(AllocationError::TooManyObjects) => (),
+            _ => panic!()
+        }
+
+        drop(a);
+        assert_eq!(device.memory_allocation_count(), 1);
+        assert_eq!(device.heap_usage(heap_id), 128);
+        let _c = DeviceMemory::alloc(&device, &mem_ty, 256).unwrap();
+        drop(b);
+        assert_eq!(device.heap_usage(heap_id), 256);
+    }
+
+    #[test]
+    fn out_of_heap_memory() {
+        let (device, _) = gfx_dev_and_queue!();
+        let mem_ty = device.physical_device().memory_types().next().unwrap();
+        let heap_id = mem_ty.heap().id();
+        let num_heaps = device.physical_device().memory_heaps().len();
+        device.override_memory_limits(16, vec![1024; num_heaps]);
+
+        let _a = DeviceMemory::alloc(&device, &mem_ty, 1000).unwrap();
+        match DeviceMemory::alloc(&device, &mem_ty, 100) {
+            Err(AllocationError::OutOfHeapMemory { heap, requested: 100, remaining: 24 }) => {
+                assert_eq!(heap, heap_id);
+            },
+            _ => panic!()
+        }
+
+        assert_eq!(device.memory_allocation_count(), 1);
+        let _b = DeviceMemory::alloc(&device, &mem_ty, 24).unwrap();
+    }
 }
@@ -9,12 +9,18 @@
 
 //! Memory allocation and memory pools.
 
+use std::cmp;
 use std::mem;
+use std::ops::Range;
 use std::os::raw::c_void;
 use std::slice;
 
+use instance::MemoryType;
+use instance::MemoryTypesIter;
+use instance::PhysicalDevice;
 use vk;
 
+pub use self::device_memory::AllocationError;
 pub use self::device_memory::CpuAccess;
 pub use self::device_memory::DeviceMemory;
 pub use self::device_memory::MappedDeviceMemory;
@@ -22,6 +28,7 @@ pub use self::pool::MemoryPool;
 
 mod device_memory;
 pub mod pool;
+pub mod sparse;
 
 /// Represents requirements expressed by the Vulkan implementation.
 #[derive(Debug, Copy, Clone)]
@@ -38,6 +45,55 @@ pub struct MemoryRequirements {
     pub memory_type_bits: u32,
 }
 
+impl MemoryRequirements {
+    /// Returns an iterator to the memory types of `physical` that are allowed by these
+    /// requirements.
+    #[inline]
+    pub fn allowed_types<'a>(&self, physical: &PhysicalDevice<'a>) -> AllowedMemoryTypesIter<'a> {
+        AllowedMemoryTypesIter {
+            requirements: *self,
+            types: physical.memory_types(),
+        }
+    }
+
+    /// Returns true if `memory_type` is one of the memory types allowed by these requirements.
+    #[inline]
+    pub fn prefers(&self, memory_type: MemoryType) -> bool {
+        self.allows_type_id(memory_type.id())
+    }
+
+    /// Returns true if the memory type whose index is `id` is allowed by these requirements.
+    #[doc(hidden)]
+    #[inline]
+    pub fn allows_type_id(&self, id: u32) -> bool {
+        id < 32 && (self.memory_type_bits & (1 << id)) != 0
+    }
+}
+
+/// Iterator to the memory types allowed by some `MemoryRequirements`.
+///
+/// See `MemoryRequirements::allowed_types`.
+#[derive(Debug, Clone)]
+pub struct AllowedMemoryTypesIter<'a> {
+    requirements: MemoryRequirements,
+    types: MemoryTypesIter<'a>,
+}
+
+impl<'a> Iterator for AllowedMemoryTypesIter<'a> {
+    type Item = MemoryType<'a>;
+
+    #[inline]
+    fn next(&mut self) -> Option<MemoryType<'a>> {
+        let requirements = self.requirements;
+        self.types.find(|t| requirements.allows_type_id(t.id()))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.types.size_hint().1)
+    }
+}
+
 #[doc(hidden)]
 impl From<vk::MemoryRequirements> for MemoryRequirements {
     #[inline]
@@ -50,6 +106,96 @@ impl From<vk::MemoryRequirements> for MemoryRequirements {
     }
 }
 
+/// The kind of memory type to choose for a resource.
+///
+/// Each preference orders the memory types allowed by the requirements of a resource. Memory
+/// types are compared by their properties first, and by their index second.
+///
+/// Some devices have a small heap (usually 256 MB) that is both device-local and host-visible.
+/// It is ideal for data that is written by the host every frame, but bulk data shouldn't be put
+/// there. Apart from `PreferDeviceLocalHostVisible`, the preferences avoid this kind of memory
+/// when something else is available.
+///
+/// See `PhysicalDevice::choose_memory_type`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MemoryTypePreference {
+    /// Memory that is only accessed by the GPU. The order is:
+    ///
+    /// - Device-local memory types that are not host-visible.
+    /// - Device-local memory types.
+    /// - Any memory type.
+    DeviceLocal,
+    /// Same as `DeviceLocal`, except that only device-local memory types can be chosen.
+    DeviceLocalOnly,
+    /// Memory that is written by the host. Only host-visible memory types can be chosen. The
+    /// order is:
+    ///
+    /// - Host-visible memory types that are not device-local.
+    /// - Host-visible memory types.
+    HostVisibleOnly,
+    /// Memory that is written by the host and read by the GPU, for example uniform data that is
+    /// updated every frame. Only host-visible memory types can be chosen. The order is:
+    ///
+    /// - Memory types that are both device-local and host-visible.
+    /// - If `fallback` is true, host-visible memory types. If `fallback` is false, no other
+    ///   memory type can be chosen.
+    PreferDeviceLocalHostVisible {
+        /// Whether host-visible memory types that are not device-local can be chosen.
+        fallback: bool,
+    },
+    /// Memory that is used to transfer data from the host to a device-local resource. Only
+    /// host-visible memory types can be chosen. The order is:
+    ///
+    /// - Host-coherent memory types that are neither device-local nor host-cached.
+    /// - Host-visible memory types that are not device-local.
+    /// - Host-visible memory types.
+    Staging,
+    /// Memory that is read by the host. Only host-visible memory types can be chosen. The order
+    /// is:
+    ///
+    /// - Host-cached memory types.
+    /// - Host-visible memory types.
+    HostCached,
+    /// Memory for images created with the `transient_attachment` usage. The order is:
+    ///
+    /// - Lazily-allocated memory types.
+    /// - Device-local memory types.
+    /// - Any memory type.
+    ///
+    /// Lazily-allocated memory is usually found on tiled GPUs, where transient attachments may
+    /// never need any backing memory.
+    Transient,
+}
+
+// Returns the range of bytes to flush or invalidate in order to access `range` in a non-coherent
+// allocation of `allocation_size` bytes.
+//
+// The range is extended to multiples of `atom_size` (the `non_coherent_atom_size` limit), except
+// at the end of the allocation where it is clamped, as allowed by the specs. An empty range stays
+// empty. The host-visible memory pool aligns the allocations that share a block to `atom_size`,
+// so the extended range never covers another allocation.
+//
+// Panics if `range` is out of the allocation.
+fn align_range_to_atom(range: Range<usize>, atom_size: usize, allocation_size: usize)
+                       -> Range<usize>
+{
+    assert!(range.start <= range.end && range.end <= allocation_size,
+            "The range is out of the memory");
+    debug_assert!(atom_size >= 1, "The atom size must not be 0");
+
+    if range.start == range.end {
+        return range;
+    }
+
+    let start = range.start - range.start % atom_size;
+    let end = match range.end % atom_size {
+        0 => range.end,
+        rem => range.end + (atom_size - rem),
+    };
+
+    start .. cmp::min(end, allocation_size)
+}
+
 /// Trait for types of data that can be mapped.
 // TODO: move to `buffer` module
 pub unsafe trait Content {
@@ -114,3 +260,95 @@ impl<T> !Content for Box<T> {}
 impl<T> !Content for UnsafeCell<T> {}
 
 */
+
+#[cfg(test)]
+mod tests {
+    use memory::MemoryRequirements;
+    use super::align_range_to_atom;
+
+    #[test]
+    fn align_range_aligned() {
+        assert_eq!(align_range_to_atom(0 .. 64, 64, 256), 0 .. 64);
+        assert_eq!(align_range_to_atom(64 .. 192, 64, 256), 64 .. 192);
+        assert_eq!(align_range_to_atom(0 .. 256, 64, 256), 0 .. 256);
+        assert_eq!(align_range_to_atom(3 .. 7, 1, 256), 3 .. 7);
+    }
+
+    #[test]
+    fn align_range_start_not_aligned() {
+        assert_eq!(align_range_to_atom(10 .. 64, 64, 256), 0 .. 64);
+        assert_eq!(align_range_to_atom(127 .. 192, 64, 256), 64 .. 192);
+    }
+
+    #[test]
+    fn align_range_end_not_aligned() {
+        assert_eq!(align_range_to_atom(0 .. 1, 64, 256), 0 .. 64);
+        assert_eq!(align_range_to_atom(64 .. 129, 64, 256), 64 .. 192);
+    }
+
+    #[test]
+    fn align_range_both_not_aligned() {
+        assert_eq!(align_range_to_atom(10 .. 20, 64, 256), 0 .. 64);
+        assert_eq!(align_range_to_atom(70 .. 130, 64, 256), 64 .. 192);
+        assert_eq!(align_range_to_atom(63 .. 65, 64, 256), 0 .. 128);
+    }
+
+    #[test]
+    fn align_range_end_of_allocation() {
+        // The allocation size is not a multiple of the atom size.
+        assert_eq!(align_range_to_atom(200 .. 250, 64, 250), 192 .. 250);
+        assert_eq!(align_range_to_atom(249 .. 250, 64, 250), 192 .. 250);
+        assert_eq!(align_range_to_atom(100 .. 200, 64, 250), 64 .. 250);
+        assert_eq!(align_range_to_atom(0 .. 250, 64, 250), 0 .. 250);
+        assert_eq!(align_range_to_atom(0 .. 10, 64, 10), 0 .. 10);
+    }
+
+    #[test]
+    fn align_range_empty() {
+        assert_eq!(align_range_to_atom(16 .. 16, 64, 256), 16 .. 16);
+        assert_eq!(align_range_to_atom(64 .. 64, 64, 256), 64 .. 64);
+        assert_eq!(align_range_to_atom(256 .. 256, 64, 256), 256 .. 256);
+    }
+
+    #[test]
+    #[should_panic = "The range is out of the memory"]
+    fn align_range_out_of_allocation() {
+        let _ = align_range_to_atom(200 .. 300, 64, 256);
+    }
+
+    #[test]
+    #[should_panic = "The range is out of the memory"]
+    fn align_range_reversed() {
+        let _ = align_range_to_atom(20 .. 10, 64, 256);
+    }
+
+    #[test]
+    fn allowed_type_ids() {
+        let reqs = MemoryRequirements { size: 256, alignment: 16, memory_type_bits: 0b1010 };
+        let allowed = (0 .. 40).filter(|&id| reqs.allows_type_id(id)).collect::<Vec<_>>();
+        assert_eq!(allowed, vec![1, 3]);
+
+        let reqs = MemoryRequirements { memory_type_bits: 0x80000000, .. reqs };
+        let allowed = (0 .. 40).filter(|&id| reqs.allows_type_id(id)).collect::<Vec<_>>();
+        assert_eq!(allowed, vec![31]);
+
+        let reqs = MemoryRequirements { memory_type_bits: 0, .. reqs };
+        assert!((0 .. 40).all(|id| !reqs.allows_type_id(id)));
+    }
+
+    #[test]
+    fn allowed_types() {
+        let (device, _) = gfx_dev_and_queue!();
+        let physical = device.physical_device();
+
+        let reqs = MemoryRequirements { size: 256, alignment: 16, memory_type_bits: !0 };
+        assert_eq!(reqs.allowed_types(&physical).count(), physical.memory_types().len());
+        assert!(physical.memory_types().all(|t| reqs.prefers(t)));
+
+        let first = physical.memory_types().next().unwrap();
+        let reqs = MemoryRequirements { memory_type_bits: 1 << first.id(), .. reqs };
+        let allowed = reqs.allowed_types(&physical).map(|t| t.id()).collect::<Vec<_>>();
+        assert_eq!(allowed, vec![first.id()]);
+        assert!(reqs.prefers(first));
+    }
+}
@@ -7,6 +7,10 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
+use std::cmp;
+use std::error;
+use std::fmt;
+use std::ops::Range;
 use std::sync::Arc;
 
 use instance::MemoryType;
@@ -14,6 +18,7 @@ use memory::DeviceMemory;
 use memory::MappedDeviceMemory;
 use OomError;
 
+pub use self::pool::HeapUsage;
 pub use self::pool::StdMemoryPool;
 pub use self::pool::StdMemoryPoolAlloc;
 pub use self::host_visible::StdHostVisibleMemoryTypePool;
@@ -39,7 +44,66 @@ pub unsafe trait MemoryPool: 'static + Send + Sync {
     /// - Panicks if `alignment` is 0.
     ///
     fn alloc(&Arc<Self>, ty: MemoryType, size: usize, alignment: usize, layout: AllocLayout)
-             -> Result<Self::Alloc, OomError>;
+             -> Result<Self::Alloc, MemoryPoolError>;
+}
+
+/// Error that can happen when allocating memory from a pool.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MemoryPoolError {
+    /// Not enough memory.
+    OomError(OomError),
+
+    /// The allocation would make the memory used in a heap exceed the budget of that heap.
+    BudgetExceeded {
+        /// Index of the heap.
+        heap: u32,
+        /// Number of bytes that were requested.
+        requested: usize,
+        /// Budget of the heap, in bytes.
+        budget: usize,
+        /// Number of bytes of the heap that were already in use.
+        used: usize,
+    },
+}
+
+impl error::Error for MemoryPoolError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            MemoryPoolError::OomError(_) => "not enough memory available",
+            MemoryPoolError::BudgetExceeded { .. } => {
+                "the allocation would exceed the budget of the heap"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            MemoryPoolError::OomError(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for MemoryPoolError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            MemoryPoolError::BudgetExceeded { heap, requested, budget, used } => {
+                write!(fmt, "{} (heap {}, requested {} bytes, budget {} bytes, {} bytes used)",
+                       error::Error::description(self), heap, requested, budget, used)
+            },
+            _ => write!(fmt, "{}", error::Error::description(self)),
+        }
+    }
+}
+
+impl From<OomError> for MemoryPoolError {
+    #[inline]
+    fn from(err: OomError) -> MemoryPoolError {
+        MemoryPoolError::OomError(err)
+    }
 }
 
 /// Object that represents a single allocation. Its destructor should free the chunk.
@@ -65,3 +129,124 @@ pub enum AllocLayout {
     Linear,
     Optimal,
 }
+
+// Returns the size of the blocks of memory that the standard pools allocate from a heap. Requests
+// that are larger than this get their own allocation.
+fn block_size(heap_size: usize) -> usize {
+    const MIN_BLOCK_SIZE: usize = 1024 * 1024;              // 1 MB
+    const MAX_BLOCK_SIZE: usize = 256 * 1024 * 1024;        // 256 MB
+    cmp::max(MIN_BLOCK_SIZE, cmp::min(MAX_BLOCK_SIZE, heap_size / 16))
+}
+
+// Finds some free space for `size` bytes aligned to `alignment` in a block of `block_size`
+// bytes, and marks it as occupied. `occupied` contains the occupied ranges of the block sorted
+// by offset. Returns the offset of the new range, or `None` if there isn't enough space.
+fn insert_range(occupied: &mut Vec<Range<usize>>, block_size: usize, size: usize,
+                alignment: usize) -> Option<usize>
+{
+    #[inline] fn align(val: usize, al: usize) -> usize { al * ((val + al - 1) / al) }
+
+    // Each candidate is the first aligned offset after an occupied range, or the start of the
+    // block.
+    let mut start = 0;
+
+    for i in 0 .. occupied.len() {
+        if start + size <= occupied[i].start {
+            debug_assert!(start % alignment == 0, "Misaligned memory allocation");
+            occupied.insert(i, start .. start + size);
+            return Some(start);
+        }
+
+        start = cmp::max(start, align(occupied[i].end, alignment));
+    }
+
+    if start + size <= block_size {
+        debug_assert!(start % alignment == 0, "Misaligned memory allocation");
+        occupied.push(start .. start + size);
+        return Some(start);
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::block_size;
+    use super::insert_range;
+
+    #[test]
+    fn block_sizes() {
+        assert_eq!(block_size(8 * 1024 * 1024 * 1024), 256 * 1024 * 1024);
+        assert_eq!(block_size(1024 * 1024 * 1024), 64 * 1024 * 1024);
+        assert_eq!(block_size(256 * 1024 * 1024), 16 * 1024 * 1024);
+        assert_eq!(block_size(4 * 1024 * 1024), 1024 * 1024);
+    }
+
+    #[test]
+    fn insert_ranges() {
+        let mut occupied = Vec::new();
+        assert_eq!(insert_range(&mut occupied, 256, 10, 1), Some(0));
+        assert_eq!(insert_range(&mut occupied, 256, 10, 16), Some(16));
+        assert_eq!(insert_range(&mut occupied, 256, 200, 16), Some(32));
+        assert_eq!(insert_range(&mut occupied, 256, 64, 1), None);
+        assert_eq!(occupied, vec![0 .. 10, 16 .. 26, 32 .. 232]);
+
+        // Freeing the first range makes its space available again.
+        occupied.remove(0);
+        assert_eq!(insert_range(&mut occupied, 256, 16, 4), Some(0));
+        assert_eq!(insert_range(&mut occupied, 256, 24, 4), Some(232));
+    }
+
+    #[test]
+    fn mixed_sizes_no_overlap() {
+        const BLOCK_SIZE: usize = 4096;
+        let mut blocks: Vec<Vec<_>> = Vec::new();
+        let mut live = Vec::new();
+
+        for i in 0 .. 2000usize {
+            // Frees one of the live allocations from time to time.
+            if i % 3 == 0 && !live.is_empty() {
+                let (block, offset): (usize, usize) = live.remove((i * 7) % live.len());
+                let pos = blocks[block].iter().position(|r: &::std::ops::Range<usize>| {
+                    r.start == offset
+                }).unwrap();
+                blocks[block].remove(pos);
+            }
+
+            let size = 1 + (i * 37) % 300;
+            let alignment = 1 << (i % 5);
+
+            let mut found = None;
+            for (num, occupied) in blocks.iter_mut().enumerate() {
+                if let Some(offset) = insert_range(occupied, BLOCK_SIZE, size, alignment) {
+                    found = Some((num, offset));
+                    break;
+                }
+            }
+            let (block, offset) = match found {
+                Some(f) => f,
+                None => {
+                    let mut occupied = Vec::new();
+                    let offset = insert_range(&mut occupied, BLOCK_SIZE, size, alignment).unwrap();
+                    blocks.push(occupied);
+                    (blocks.len() - 1, offset)
+                },
+            };
+
+            assert_eq!(offset % alignment, 0);
+            assert!(offset + size <= BLOCK_SIZE);
+            live.push((block, offset));
+        }
+
+        for occupied in blocks.iter() {
+            for pair in occupied.windows(2) {
+                assert!(pair[0].end <= pair[1].start, "{:?} overlaps {:?}", pair[0], pair[1]);
+            }
+        }
+
+        // At most 1334 allocations of at most 300 bytes plus padding are alive at the same time,
+        // and fragmentation must not waste more than half of the blocks.
+        assert!(blocks.len() <= 2 * (1334 * 316) / BLOCK_SIZE + 1,
+                "too many blocks: {}", blocks.len());
+    }
+}
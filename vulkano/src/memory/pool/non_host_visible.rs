@@ -16,6 +16,8 @@ use device::Device;
 use instance::Instance;
 use instance::MemoryType;
 use memory::DeviceMemory;
+use memory::pool::block_size;
+use memory::pool::insert_range;
 use OomError;
 
 /// Memory pool that operates on a given memory type.
@@ -23,8 +25,9 @@ use OomError;
 pub struct StdNonHostVisibleMemoryTypePool {
     device: Arc<Device>,
     memory_type: u32,
-    // TODO: obviously very inefficient
-    occupied: Mutex<Vec<(Arc<DeviceMemory>, Vec<Range<usize>>)>>,
+    // The blocks of memory that have been allocated, with their occupied ranges sorted by offset
+    // and whether they were allocated for a single request.
+    occupied: Mutex<Vec<(Arc<DeviceMemory>, Vec<Range<usize>>, bool)>>,
 }
 
 impl StdNonHostVisibleMemoryTypePool {
@@ -62,56 +65,76 @@ impl StdNonHostVisibleMemoryTypePool {
         assert!(size != 0);
         assert!(alignment != 0);
 
-        #[inline] fn align(val: usize, al: usize) -> usize { al * (1 + (val - 1) / al) }
+        let block_size = block_size(me.memory_type().heap().size());
+
+        // Requests that are larger than a block get their own allocation.
+        if size > block_size {
+            return StdNonHostVisibleMemoryTypePool::alloc_dedicated(me, size);
+        }
 
         // Find a location.
         let mut occupied = me.occupied.lock().unwrap();
 
-        // Try finding an entry in already-allocated chunks.
-        for &mut (ref dev_mem, ref mut entries) in occupied.iter_mut() {
-            // Try find some free space in-between two entries.
-            for i in 0 .. entries.len().saturating_sub(1) {
-                let entry1 = entries[i].clone();
-                let entry1_end = align(entry1.end, alignment);
-                let entry2 = entries[i + 1].clone();
-                if entry1_end + size <= entry2.start {
-                    entries.insert(i + 1, entry1_end .. entry1_end + size);
-                    return Ok(StdNonHostVisibleMemoryTypePoolAlloc {
-                        pool: me.clone(),
-                        memory: dev_mem.clone(),
-                        offset: entry1_end,
-                        size: size,
-                    });
-                }
+        // Try finding some free space in the blocks that are already allocated.
+        for &mut (ref dev_mem, ref mut entries, dedicated) in occupied.iter_mut() {
+            if dedicated {
+                continue;
             }
 
-            // Try append at the end.
-            let last_end = entries.last().map(|e| align(e.end, alignment)).unwrap_or(0);
-            if last_end + size <= dev_mem.size() {
-                entries.push(last_end .. last_end + size);
+            if let Some(offset) = insert_range(entries, dev_mem.size(), size, alignment) {
                 return Ok(StdNonHostVisibleMemoryTypePoolAlloc {
                     pool: me.clone(),
                     memory: dev_mem.clone(),
-                    offset: last_end,
+                    offset: offset,
                     size: size,
+                    dedicated: false,
                 });
             }
         }
 
-        // We need to allocate a new block.
+        // We need to allocate a new block. Blocks are shrunk when the heap is almost full, so
+        // that the last bytes of the heap can still be used.
         let new_block = {
-            const MIN_BLOCK_SIZE: usize = 8 * 1024 * 1024;      // 8 MB
-            let to_alloc = cmp::max(MIN_BLOCK_SIZE, size.next_power_of_two());
+            let remaining = me.device.remaining_heap_memory(me.memory_type().heap().id());
+            let to_alloc = cmp::max(cmp::min(block_size, remaining), size);
             let new_block = try!(DeviceMemory::alloc(&me.device, &me.memory_type(), to_alloc));
             Arc::new(new_block)
         };
 
-        occupied.push((new_block.clone(), vec![0 .. size]));
+        occupied.push((new_block.clone(), vec![0 .. size], false));
         Ok(StdNonHostVisibleMemoryTypePoolAlloc {
             pool: me.clone(),
             memory: new_block,
             offset: 0,
             size: size,
+            dedicated: false,
+        })
+    }
+
+    /// Allocates memory that isn't shared with any other allocation. The memory is given back to
+    /// the driver as soon as the allocation is dropped.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if `size` is 0.
+    ///
+    pub fn alloc_dedicated(me: &Arc<Self>, size: usize)
+                           -> Result<StdNonHostVisibleMemoryTypePoolAlloc, OomError>
+    {
+        assert!(size != 0);
+
+        let memory = {
+            let memory = try!(DeviceMemory::alloc(&me.device, &me.memory_type(), size));
+            Arc::new(memory)
+        };
+
+        me.occupied.lock().unwrap().push((memory.clone(), vec![0 .. size], true));
+        Ok(StdNonHostVisibleMemoryTypePoolAlloc {
+            pool: me.clone(),
+            memory: memory,
+            offset: 0,
+            size: size,
+            dedicated: true,
         })
     }
 
@@ -126,6 +149,13 @@ impl StdNonHostVisibleMemoryTypePool {
     pub fn memory_type(&self) -> MemoryType {
         self.device.physical_device().memory_type_by_id(self.memory_type).unwrap()
     }
+
+    /// Returns the number of bytes of device memory that this pool has allocated.
+    #[inline]
+    pub fn allocated_bytes(&self) -> usize {
+        let occupied = self.occupied.lock().unwrap();
+        occupied.iter().map(|&(ref mem, _, _)| mem.size()).sum()
+    }
 }
 
 #[derive(Debug)]
@@ -134,6 +164,7 @@ pub struct StdNonHostVisibleMemoryTypePoolAlloc {
     memory: Arc<DeviceMemory>,
     offset: usize,
     size: usize,
+    dedicated: bool,
 }
 
 impl StdNonHostVisibleMemoryTypePoolAlloc {
@@ -151,15 +182,27 @@ impl StdNonHostVisibleMemoryTypePoolAlloc {
     pub fn size(&self) -> usize {
         self.size
     }
+
+    /// Returns true if the allocation has its own device memory.
+    #[inline]
+    pub fn is_dedicated(&self) -> bool {
+        self.dedicated
+    }
 }
 
 impl Drop for StdNonHostVisibleMemoryTypePoolAlloc {
     fn drop(&mut self) {
         let mut occupied = self.pool.occupied.lock().unwrap();
 
-        let entries = occupied.iter_mut()
-                              .find(|e| &*e.0 as *const DeviceMemory == &*self.memory).unwrap();
+        let index = occupied.iter()
+                            .position(|e| &*e.0 as *const DeviceMemory == &*self.memory).unwrap();
+
+        occupied[index].1.retain(|e| e.start != self.offset);
 
-        entries.1.retain(|e| e.start != self.offset);
+        // Dedicated blocks are freed along with their only allocation.
+        if occupied[index].2 {
+            debug_assert!(occupied[index].1.is_empty());
+            occupied.remove(index);
+        }
     }
 }
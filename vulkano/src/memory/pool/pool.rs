@@ -19,83 +19,306 @@ use instance::MemoryType;
 use memory::pool::AllocLayout;
 use memory::pool::MemoryPool;
 use memory::pool::MemoryPoolAlloc;
+use memory::pool::MemoryPoolError;
 use memory::pool::StdHostVisibleMemoryTypePool;
 use memory::pool::StdHostVisibleMemoryTypePoolAlloc;
 use memory::pool::StdNonHostVisibleMemoryTypePool;
 use memory::pool::StdNonHostVisibleMemoryTypePoolAlloc;
+use memory::pool::block_size;
 use memory::DeviceMemory;
 use memory::MappedDeviceMemory;
+use memory::MemoryRequirements;
+use memory::MemoryTypePreference;
 use OomError;
 
+/// Standard implementation of `MemoryPool`.
+///
+/// For each memory type, large blocks of memory are allocated from the device and the
+/// allocations are placed inside them, so that the number of device memory allocations stays
+/// low. Requests that are larger than a threshold get their own device memory allocation, which
+/// is given back to the driver as soon as they are freed. By default, the threshold is half of
+/// the size of the blocks of the heap. See `set_dedicated_threshold`.
+///
+/// The pool keeps track of the number of bytes allocated from each heap, which `usage` returns.
+/// Each heap can also have a budget. Allocations that would make the memory used in a heap
+/// exceed its budget return `MemoryPoolError::BudgetExceeded`, and `choose_memory_type` avoids
+/// the heaps that have no room left in their budget.
+///
+/// By default, a device-local heap whose memory types are all host-visible and that is smaller
+/// than another device-local heap has a budget of half of its size. This kind of heap is usually
+/// the small heap that the host can write to directly, and the budget makes large allocations
+/// spill to the bigger heaps.
 #[derive(Debug)]
 pub struct StdMemoryPool {
     device: Arc<Device>,
 
     // For each memory type index, stores the associated pool.
     pools: Mutex<HashMap<(u32, AllocLayout), Pool, BuildHasherDefault<FnvHasher>>>,
+
+    // For each heap, the number of bytes allocated from the pool and the budget.
+    heaps: Mutex<Vec<HeapAccount>>,
+
+    // Size above which allocations are dedicated, or `None` to use the default.
+    dedicated_threshold: Mutex<Option<usize>>,
+}
+
+#[derive(Debug)]
+struct HeapAccount {
+    // Number of bytes of the allocations that are alive.
+    used: usize,
+    // Number of bytes that `used` must not exceed.
+    budget: Option<usize>,
+}
+
+/// Memory usage of a heap by a `StdMemoryPool`.
+///
+/// See `StdMemoryPool::usage`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct HeapUsage {
+    /// Index of the heap.
+    pub heap: u32,
+    /// Number of bytes of the allocations of the pool that are alive.
+    pub used: usize,
+    /// Number of bytes of device memory that the pool has allocated from the heap in order to
+    /// put its allocations in. Always greater than or equal to `used`.
+    pub allocated: usize,
+    /// Size of the heap.
+    pub size: usize,
+    /// Budget of the heap, if any.
+    pub budget: Option<usize>,
 }
 
 impl StdMemoryPool {
     /// Creates a new pool.
     #[inline]
     pub fn new(device: &Arc<Device>) -> Arc<StdMemoryPool> {
-        let cap = device.physical_device().memory_types().len();
+        let physical = device.physical_device();
+        let cap = physical.memory_types().len();
         let hasher = BuildHasherDefault::<FnvHasher>::default();
 
+        let heaps = physical.memory_heaps().map(|heap| {
+            let host_visible_only = physical.memory_types().filter(|t| t.heap().id() == heap.id())
+                                            .all(|t| t.is_host_visible());
+            (heap.size(), heap.is_device_local(), host_visible_only)
+        }).collect::<Vec<_>>();
+
         Arc::new(StdMemoryPool {
             device: device.clone(),
             pools: Mutex::new(HashMap::with_capacity_and_hasher(cap, hasher)),
+            heaps: Mutex::new(default_heap_budgets(&heaps).into_iter().map(|budget| {
+                HeapAccount { used: 0, budget: budget }
+            }).collect()),
+            dedicated_threshold: Mutex::new(None),
         })
     }
+
+    /// Returns the size in bytes above which allocations from the given memory type get their
+    /// own device memory allocation.
+    #[inline]
+    pub fn dedicated_threshold(&self, memory_type: MemoryType) -> usize {
+        match *self.dedicated_threshold.lock().unwrap() {
+            Some(threshold) => threshold,
+            None => block_size(memory_type.heap().size()) / 2,
+        }
+    }
+
+    /// Sets the size in bytes above which allocations get their own device memory allocation.
+    /// Pass `None` to go back to the default, which is half of the size of the blocks that are
+    /// allocated from the heap.
+    ///
+    /// Allocations that are larger than a block are always dedicated, whatever the threshold.
+    #[inline]
+    pub fn set_dedicated_threshold(&self, threshold: Option<usize>) {
+        *self.dedicated_threshold.lock().unwrap() = threshold;
+    }
+
+    /// Returns the memory usage of each heap of the device.
+    pub fn usage(&self) -> Vec<HeapUsage> {
+        let physical = self.device.physical_device();
+        let mut allocated = vec![0; physical.memory_heaps().len()];
+
+        for (&(memory_type, _), pool) in self.pools.lock().unwrap().iter() {
+            let heap_id = physical.memory_type_by_id(memory_type).unwrap().heap().id();
+            allocated[heap_id as usize] += match *pool {
+                Pool::HostVisible(ref pool) => pool.allocated_bytes(),
+                Pool::NonHostVisible(ref pool) => pool.allocated_bytes(),
+            };
+        }
+
+        let heaps = self.heaps.lock().unwrap();
+        physical.memory_heaps().zip(heaps.iter()).map(|(heap, account)| {
+            HeapUsage {
+                heap: heap.id(),
+                used: account.used,
+                allocated: allocated[heap.id() as usize],
+                size: heap.size(),
+                budget: account.budget,
+            }
+        }).collect()
+    }
+
+    /// Returns the budget of a heap, or `None` if the heap has no budget.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if `heap_id` is out of range.
+    ///
+    #[inline]
+    pub fn heap_budget(&self, heap_id: u32) -> Option<usize> {
+        self.heaps.lock().unwrap()[heap_id as usize].budget
+    }
+
+    /// Sets the budget of a heap, in bytes. Pass `None` to remove the budget.
+    ///
+    /// The allocations that are alive are not affected, even if they exceed the new budget.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if `heap_id` is out of range.
+    ///
+    #[inline]
+    pub fn set_heap_budget(&self, heap_id: u32, budget: Option<usize>) {
+        self.heaps.lock().unwrap()[heap_id as usize].budget = budget;
+    }
+
+    /// Chooses a memory type for a resource with the given memory requirements.
+    ///
+    /// Same as `PhysicalDevice::choose_memory_type`, except that memory types whose heap would
+    /// exceed its budget are skipped, as long as there are other memory types to choose from.
+    pub fn choose_memory_type(&self, requirements: &MemoryRequirements,
+                              preferred: MemoryTypePreference) -> Option<MemoryType>
+    {
+        let candidates = self.device.physical_device()
+                             .memory_types_by_preference(requirements, preferred);
+
+        let within_budget = {
+            let heaps = self.heaps.lock().unwrap();
+            candidates.iter().position(|ty| {
+                let account = &heaps[ty.heap().id() as usize];
+                match account.budget {
+                    Some(budget) => account.used + requirements.size <= budget,
+                    None => true,
+                }
+            })
+        };
+
+        candidates.into_iter().nth(within_budget.unwrap_or(0))
+    }
+}
+
+// Returns the default budget of each heap. Each heap is described by its size, whether it is
+// device-local, and whether all of its memory types are host-visible.
+fn default_heap_budgets(heaps: &[(usize, bool, bool)]) -> Vec<Option<usize>> {
+    let largest_device_local = heaps.iter().filter(|&&(_, device_local, _)| device_local)
+                                    .map(|&(size, _, _)| size).max().unwrap_or(0);
+
+    heaps.iter().map(|&(size, device_local, host_visible_only)| {
+        if device_local && host_visible_only && size < largest_device_local {
+            Some(size / 2)
+        } else {
+            None
+        }
+    }).collect()
 }
 
 unsafe impl MemoryPool for StdMemoryPool {
     type Alloc = StdMemoryPoolAlloc;
 
     fn alloc(me: &Arc<Self>, memory_type: MemoryType, size: usize, alignment: usize,
-             layout: AllocLayout) -> Result<StdMemoryPoolAlloc, OomError>
+             layout: AllocLayout) -> Result<StdMemoryPoolAlloc, MemoryPoolError>
     {
-        let mut pools = me.pools.lock().unwrap();
-
-        match pools.entry((memory_type.id(), layout)) {
-            Entry::Occupied(entry) => {
-                match entry.get() {
-                    &Pool::HostVisible(ref pool) => {
-                        let alloc = try!(StdHostVisibleMemoryTypePool::alloc(&pool, size, alignment));
-                        let inner = StdMemoryPoolAllocInner::HostVisible(alloc);
-                        Ok(StdMemoryPoolAlloc { inner: inner })
-                    },
-                    &Pool::NonHostVisible(ref pool) => {
-                        let alloc = try!(StdNonHostVisibleMemoryTypePool::alloc(&pool, size, alignment));
-                        let inner = StdMemoryPoolAllocInner::NonHostVisible(alloc);
-                        Ok(StdMemoryPoolAlloc { inner: inner })
-                    },
-                }
-            },
+        let heap_id = memory_type.heap().id();
+
+        // Checking the budget and counting the allocation are done at once, so that concurrent
+        // allocations can't exceed the budget together.
+        {
+            let mut heaps = me.heaps.lock().unwrap();
+            let account = &mut heaps[heap_id as usize];
 
-            Entry::Vacant(entry) => {
-                match memory_type.is_host_visible() {
-                    true => {
-                        let pool = StdHostVisibleMemoryTypePool::new(&me.device, memory_type);
-                        entry.insert(Pool::HostVisible(pool.clone()));
-                        let alloc = try!(StdHostVisibleMemoryTypePool::alloc(&pool, size, alignment));
-                        let inner = StdMemoryPoolAllocInner::HostVisible(alloc);
-                        Ok(StdMemoryPoolAlloc { inner: inner })
-                    },
-                    false => {
-                        let pool = StdNonHostVisibleMemoryTypePool::new(&me.device, memory_type);
-                        entry.insert(Pool::NonHostVisible(pool.clone()));
-                        let alloc = try!(StdNonHostVisibleMemoryTypePool::alloc(&pool, size, alignment));
-                        let inner = StdMemoryPoolAllocInner::NonHostVisible(alloc);
-                        Ok(StdMemoryPoolAlloc { inner: inner })
-                    },
+            if let Some(budget) = account.budget {
+                if account.used + size > budget {
+                    return Err(MemoryPoolError::BudgetExceeded {
+                        heap: heap_id,
+                        requested: size,
+                        budget: budget,
+                        used: account.used,
+                    });
                 }
+            }
+
+            account.used += size;
+        }
+
+        match StdMemoryPool::alloc_inner(me, memory_type, size, alignment, layout) {
+            Ok(inner) => {
+                Ok(StdMemoryPoolAlloc {
+                    pool: me.clone(),
+                    heap: heap_id,
+                    inner: inner,
+                })
+            },
+            Err(err) => {
+                me.heaps.lock().unwrap()[heap_id as usize].used -= size;
+                Err(err.into())
             },
         }
     }
 }
 
-#[derive(Debug)]
+impl StdMemoryPool {
+    // Allocates memory from the pool of the memory type.
+    fn alloc_inner(me: &Arc<Self>, memory_type: MemoryType, size: usize, alignment: usize,
+                   layout: AllocLayout) -> Result<StdMemoryPoolAllocInner, OomError>
+    {
+        // The lock on the list of pools is only held while looking up the pool of the memory
+        // type, so that allocations from different memory types don't block each other.
+        let pool = {
+            let mut pools = me.pools.lock().unwrap();
+
+            match pools.entry((memory_type.id(), layout)) {
+                Entry::Occupied(entry) => entry.get().clone(),
+                Entry::Vacant(entry) => {
+                    let pool = match memory_type.is_host_visible() {
+                        true => {
+                            let pool = StdHostVisibleMemoryTypePool::new(&me.device, memory_type);
+                            Pool::HostVisible(pool)
+                        },
+                        false => {
+                            let pool = StdNonHostVisibleMemoryTypePool::new(&me.device,
+                                                                            memory_type);
+                            Pool::NonHostVisible(pool)
+                        },
+                    };
+
+                    entry.insert(pool).clone()
+                },
+            }
+        };
+
+        let dedicated = size > me.dedicated_threshold(memory_type);
+
+        Ok(match pool {
+            Pool::HostVisible(ref pool) => {
+                let alloc = if dedicated {
+                    try!(StdHostVisibleMemoryTypePool::alloc_dedicated(pool, size))
+                } else {
+                    try!(StdHostVisibleMemoryTypePool::alloc(pool, size, alignment))
+                };
+                StdMemoryPoolAllocInner::HostVisible(alloc)
+            },
+            Pool::NonHostVisible(ref pool) => {
+                let alloc = if dedicated {
+                    try!(StdNonHostVisibleMemoryTypePool::alloc_dedicated(pool, size))
+                } else {
+                    try!(StdNonHostVisibleMemoryTypePool::alloc(pool, size, alignment))
+                };
+                StdMemoryPoolAllocInner::NonHostVisible(alloc)
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
 enum Pool {
     HostVisible(Arc<StdHostVisibleMemoryTypePool>),
     NonHostVisible(Arc<StdNonHostVisibleMemoryTypePool>),
@@ -103,7 +326,9 @@ enum Pool {
 
 #[derive(Debug)]
 pub struct StdMemoryPoolAlloc {
-    inner: StdMemoryPoolAllocInner
+    pool: Arc<StdMemoryPool>,
+    heap: u32,
+    inner: StdMemoryPoolAllocInner,
 }
 
 impl StdMemoryPoolAlloc {
@@ -114,6 +339,16 @@ impl StdMemoryPoolAlloc {
             StdMemoryPoolAllocInner::HostVisible(ref mem) => mem.size(),
         }
     }
+
+    /// Returns true if the allocation has its own device memory instead of sharing a block with
+    /// other allocations.
+    #[inline]
+    pub fn is_dedicated(&self) -> bool {
+        match self.inner {
+            StdMemoryPoolAllocInner::NonHostVisible(ref mem) => mem.is_dedicated(),
+            StdMemoryPoolAllocInner::HostVisible(ref mem) => mem.is_dedicated(),
+        }
+    }
 }
 
 unsafe impl MemoryPoolAlloc for StdMemoryPoolAlloc {
@@ -142,8 +377,255 @@ unsafe impl MemoryPoolAlloc for StdMemoryPoolAlloc {
     }
 }
 
+impl Drop for StdMemoryPoolAlloc {
+    #[inline]
+    fn drop(&mut self) {
+        let size = self.size();
+        self.pool.heaps.lock().unwrap()[self.heap as usize].used -= size;
+    }
+}
+
 #[derive(Debug)]
 enum StdMemoryPoolAllocInner {
     NonHostVisible(StdNonHostVisibleMemoryTypePoolAlloc),
     HostVisible(StdHostVisibleMemoryTypePoolAlloc),
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::iter;
+
+    use buffer::BufferUsage;
+    use buffer::CpuAccessibleBuffer;
+    use memory::pool::AllocLayout;
+    use memory::pool::MemoryPool;
+    use memory::pool::MemoryPoolAlloc;
+    use memory::pool::MemoryPoolError;
+    use memory::pool::StdMemoryPool;
+    use memory::MemoryRequirements;
+    use memory::MemoryTypePreference;
+    use VulkanObject;
+
+    use super::default_heap_budgets;
+
+    #[test]
+    fn default_budgets() {
+        const MB: usize = 1024 * 1024;
+
+        // Discrete AMD GPU, with a small device-local heap that is host-visible.
+        let heaps = [(8192 * MB, true, false), (16384 * MB, false, true), (256 * MB, true, true)];
+        assert_eq!(default_heap_budgets(&heaps), vec![None, None, Some(128 * MB)]);
+
+        // Discrete NVIDIA GPU.
+        let heaps = [(8192 * MB, true, false), (16384 * MB, false, true)];
+        assert_eq!(default_heap_budgets(&heaps), vec![None, None]);
+
+        // Integrated GPU, where the only heap is both device-local and host-visible.
+        let heaps = [(4096 * MB, true, true)];
+        assert_eq!(default_heap_budgets(&heaps), vec![None]);
+    }
+
+    #[test]
+    fn choose_memory_type_budget() {
+        let (device, _) = gfx_dev_and_queue!();
+        let pool = StdMemoryPool::new(&device);
+
+        let reqs = MemoryRequirements { size: 1024, alignment: 1, memory_type_bits: !0 };
+        let preferred = MemoryTypePreference::DeviceLocal;
+        let candidates = device.physical_device().memory_types_by_preference(&reqs, preferred);
+        let first = pool.choose_memory_type(&reqs, preferred).unwrap();
+        assert_eq!(first.id(), candidates[0].id());
+
+        // Once the budget of the heap of the preferred memory type is exhausted, the next memory
+        // type with another heap is chosen if there is one.
+        pool.set_heap_budget(first.heap().id(), Some(0));
+        assert_eq!(pool.heap_budget(first.heap().id()), Some(0));
+        let chosen = pool.choose_memory_type(&reqs, preferred).unwrap();
+        match candidates.iter().find(|t| t.heap().id() != first.heap().id()) {
+            Some(other) => assert_eq!(chosen.id(), other.id()),
+            None => assert_eq!(chosen.id(), first.id()),
+        }
+    }
+
+    #[test]
+    fn mixed_sizes_no_overlap() {
+        let (device, _) = gfx_dev_and_queue!();
+        let pool = StdMemoryPool::new(&device);
+        let mem_ty = device.physical_device().memory_types().filter(|t| !t.is_lazily_allocated())
+                           .next().unwrap();
+
+        let mut allocs = Vec::new();
+        for i in 0 .. 500usize {
+            // Frees one of the allocations from time to time.
+            if i % 3 == 0 && !allocs.is_empty() {
+                let len = allocs.len();
+                allocs.remove((i * 7) % len);
+            }
+
+            let size = 256 + (i * 4099) % 65536;
+            let alignment = 1 << (i % 9);
+            let alloc = MemoryPool::alloc(&pool, mem_ty, size, alignment, AllocLayout::Linear)
+                                   .unwrap();
+            assert_eq!(alloc.offset() % alignment, 0);
+            allocs.push((alloc, size));
+        }
+
+        for (num, &(ref a, a_size)) in allocs.iter().enumerate() {
+            for &(ref b, b_size) in allocs[num + 1 ..].iter() {
+                if a.memory().internal_object() != b.memory().internal_object() {
+                    continue;
+                }
+
+                assert!(a.offset() + a_size <= b.offset() || b.offset() + b_size <= a.offset());
+            }
+        }
+
+        // Less than 22 MB are allocated at the same time, which fits in a few blocks.
+        let blocks = allocs.iter().map(|&(ref a, _)| a.memory().internal_object())
+                           .collect::<HashSet<_>>();
+        assert!(blocks.len() <= 24, "too many blocks: {}", blocks.len());
+    }
+
+    #[test]
+    fn budget_exceeded() {
+        let (device, _) = gfx_dev_and_queue!();
+        let pool = StdMemoryPool::new(&device);
+        let mem_ty = device.physical_device().memory_types().filter(|t| !t.is_lazily_allocated())
+                           .next().unwrap();
+        let heap = mem_ty.heap().id();
+        pool.set_heap_budget(heap, Some(1024));
+
+        let a = MemoryPool::alloc(&pool, mem_ty, 768, 1, AllocLayout::Linear).unwrap();
+        assert_eq!(pool.usage()[heap as usize].used, 768);
+
+        match MemoryPool::alloc(&pool, mem_ty, 512, 1, AllocLayout::Linear) {
+            Err(MemoryPoolError::BudgetExceeded { heap: h, requested, budget, used }) => {
+                assert_eq!(h, heap);
+                assert_eq!(requested, 512);
+                assert_eq!(budget, 1024);
+                assert_eq!(used, 768);
+            },
+            _ => panic!()
+        }
+
+        // The failed allocation isn't counted.
+        assert_eq!(pool.usage()[heap as usize].used, 768);
+
+        drop(a);
+        assert_eq!(pool.usage()[heap as usize].used, 0);
+        let _b = MemoryPool::alloc(&pool, mem_ty, 512, 1, AllocLayout::Linear).unwrap();
+    }
+
+    #[test]
+    fn budget_exceeded_buffer() {
+        let (device, _) = gfx_dev_and_queue!();
+        let pool = device.standard_pool();
+        for heap in device.physical_device().memory_heaps() {
+            pool.set_heap_budget(heap.id(), Some(0));
+        }
+
+        match CpuAccessibleBuffer::<u32>::new(&device, &BufferUsage::all(), iter::empty()) {
+            Err(MemoryPoolError::BudgetExceeded { budget: 0, .. }) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn non_coherent_allocations_dont_share_atoms() {
+        let (device, _) = gfx_dev_and_queue!();
+        let pool = StdMemoryPool::new(&device);
+        let mem_ty = match device.physical_device().memory_types()
+                                 .filter(|t| t.is_host_visible() && !t.is_host_coherent())
+                                 .next()
+        {
+            Some(t) => t,
+            None => return,     // test ignored
+        };
+
+        let atom_size = device.non_coherent_atom_size();
+        let a = MemoryPool::alloc(&pool, mem_ty, 1, 1, AllocLayout::Linear).unwrap();
+        let b = MemoryPool::alloc(&pool, mem_ty, 1, 1, AllocLayout::Linear).unwrap();
+        assert_eq!(a.memory().internal_object(), b.memory().internal_object());
+        assert_eq!(a.offset() % atom_size, 0);
+        assert_eq!(b.offset() % atom_size, 0);
+        assert!(a.offset() + atom_size <= b.offset() || b.offset() + atom_size <= a.offset());
+    }
+
+    #[test]
+    fn usage() {
+        let (device, _) = gfx_dev_and_queue!();
+        let pool = StdMemoryPool::new(&device);
+        let mem_ty = device.physical_device().memory_types().filter(|t| !t.is_lazily_allocated())
+                           .next().unwrap();
+        let heap = mem_ty.heap().id();
+
+        let usage = pool.usage();
+        assert_eq!(usage.len(), device.physical_device().memory_heaps().len());
+        assert_eq!(usage[heap as usize].heap, heap);
+        assert_eq!(usage[heap as usize].used, 0);
+        assert_eq!(usage[heap as usize].allocated, 0);
+        assert_eq!(usage[heap as usize].size, mem_ty.heap().size());
+
+        let _a = MemoryPool::alloc(&pool, mem_ty, 1000, 1, AllocLayout::Linear).unwrap();
+        let _b = MemoryPool::alloc(&pool, mem_ty, 24, 1, AllocLayout::Optimal).unwrap();
+        let usage = pool.usage();
+        assert_eq!(usage[heap as usize].used, 1024);
+        assert!(usage[heap as usize].allocated >= 1024);
+    }
+
+    #[test]
+    fn dedicated_allocation() {
+        let (device, _) = gfx_dev_and_queue!();
+        let pool = StdMemoryPool::new(&device);
+        let mem_ty = device.physical_device().memory_types().filter(|t| !t.is_lazily_allocated())
+                           .next().unwrap();
+
+        let size = 512 * 1024 * 1024;
+        if size > mem_ty.heap().size() / 2 {
+            return;     // test ignored
+        }
+
+        let alloc = MemoryPool::alloc(&pool, mem_ty, size, 1, AllocLayout::Linear).unwrap();
+        assert!(alloc.is_dedicated());
+        assert_eq!(alloc.offset(), 0);
+        assert_eq!(alloc.memory().size(), size);
+    }
+
+    #[test]
+    fn dedicated_threshold() {
+        let (device, _) = gfx_dev_and_queue!();
+        let pool = StdMemoryPool::new(&device);
+        let mem_ty = device.physical_device().memory_types().filter(|t| !t.is_lazily_allocated())
+                           .next().unwrap();
+        let heap = mem_ty.heap().id() as usize;
+
+        pool.set_dedicated_threshold(Some(4096));
+        assert_eq!(pool.dedicated_threshold(mem_ty), 4096);
+
+        // Allocations under the threshold share a block.
+        let a = MemoryPool::alloc(&pool, mem_ty, 4096, 1, AllocLayout::Linear).unwrap();
+        assert!(!a.is_dedicated());
+        let allocated = pool.usage()[heap].allocated;
+        let b = MemoryPool::alloc(&pool, mem_ty, 4000, 1, AllocLayout::Linear).unwrap();
+        assert!(!b.is_dedicated());
+        assert_eq!(a.memory().internal_object(), b.memory().internal_object());
+        assert_eq!(pool.usage()[heap].allocated, allocated);
+
+        // Allocations over the threshold get their own memory, which is freed along with them.
+        let count = device.memory_allocation_count();
+        let c = MemoryPool::alloc(&pool, mem_ty, 4097, 1, AllocLayout::Linear).unwrap();
+        assert!(c.is_dedicated());
+        assert_eq!(c.offset(), 0);
+        assert_eq!(c.memory().size(), 4097);
+        assert_eq!(pool.usage()[heap].allocated, allocated + 4097);
+        assert_eq!(device.memory_allocation_count(), count + 1);
+
+        drop(c);
+        assert_eq!(pool.usage()[heap].allocated, allocated);
+        assert_eq!(device.memory_allocation_count(), count);
+
+        pool.set_dedicated_threshold(None);
+        assert!(pool.dedicated_threshold(mem_ty) > 4096);
+    }
+}
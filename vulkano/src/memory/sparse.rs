@@ -0,0 +1,534 @@
+// Copyright (c) 2016 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+//! Binding memory to sparse resources.
+//!
+//! The memory of a buffer or of an image that has been created with sparse binding isn't bound
+//! with `bind_memory`. Instead it is bound by submitting a list of binds to a queue with
+//! `Queue::bind_sparse`.
+//!
+//! Each bind associates a range of a resource with a range of a `DeviceMemory`, or removes the
+//! memory of that range if no memory is specified. Images created with sparse residency can
+//! additionally be bound block by block, with the block shape described by
+//! `UnsafeImage::sparse_memory_requirements`.
+//!
+//! The resources and the memory that are passed to `Queue::bind_sparse` are kept alive until
+//! the binding operation has finished.
+
+use std::error;
+use std::fmt;
+use std::io;
+use std::io::Write;
+use std::mem;
+use std::ptr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use buffer::sys::UnsafeBuffer;
+use device::Queue;
+use image::ImageAspects;
+use image::sys::UnsafeImage;
+use memory::DeviceMemory;
+use sync::FenceGuard;
+use sync::FencePool;
+use sync::FenceWaitError;
+
+use check_errors;
+use Error;
+use OomError;
+use SynchronizedVulkanObject;
+use VulkanObject;
+use VulkanPointers;
+use vk;
+
+/// Binds a range of a buffer or an opaque range of an image to memory.
+#[derive(Debug, Clone)]
+pub struct SparseMemoryBind {
+    /// Offset in bytes of the range within the resource.
+    pub resource_offset: usize,
+    /// Size in bytes of the range.
+    pub size: usize,
+    /// Memory to bind and offset within that memory. If `None`, the range is unbound.
+    pub memory: Option<(Arc<DeviceMemory>, usize)>,
+    /// If true, the range is part of the metadata of the image.
+    pub metadata: bool,
+}
+
+impl SparseMemoryBind {
+    #[inline]
+    fn to_vk(&self) -> vk::SparseMemoryBind {
+        let (memory, memory_offset) = match self.memory {
+            Some((ref mem, offset)) => (mem.internal_object(), offset),
+            None => (0, 0),
+        };
+
+        vk::SparseMemoryBind {
+            resourceOffset: self.resource_offset as vk::DeviceSize,
+            size: self.size as vk::DeviceSize,
+            memory: memory,
+            memoryOffset: memory_offset as vk::DeviceSize,
+            flags: if self.metadata { vk::SPARSE_MEMORY_BIND_METADATA_BIT } else { 0 },
+        }
+    }
+}
+
+/// Binds a region of a subresource of a sparse resident image to memory.
+///
+/// The offset and the extent must be multiples of the block extent returned by
+/// `UnsafeImage::sparse_memory_requirements`, except at the edges of the subresource.
+#[derive(Debug, Clone)]
+pub struct SparseImageMemoryBind {
+    /// Aspect of the image to bind. Only one aspect must be set.
+    pub aspects: ImageAspects,
+    /// Mipmap level to bind.
+    pub mipmap_level: u32,
+    /// Array layer to bind.
+    pub array_layer: u32,
+    /// Offset in texels of the region.
+    pub offset: [i32; 3],
+    /// Size in texels of the region.
+    pub extent: [u32; 3],
+    /// Memory to bind and offset within that memory. If `None`, the region is unbound.
+    pub memory: Option<(Arc<DeviceMemory>, usize)>,
+}
+
+impl SparseImageMemoryBind {
+    #[inline]
+    fn to_vk(&self) -> vk::SparseImageMemoryBind {
+        let (memory, memory_offset) = match self.memory {
+            Some((ref mem, offset)) => (mem.internal_object(), offset),
+            None => (0, 0),
+        };
+
+        vk::SparseImageMemoryBind {
+            subresource: vk::ImageSubresource {
+                aspectMask: self.aspects.to_aspect_bits(),
+                mipLevel: self.mipmap_level,
+                arrayLayer: self.array_layer,
+            },
+            offset: vk::Offset3D {
+                x: self.offset[0],
+                y: self.offset[1],
+                z: self.offset[2],
+            },
+            extent: vk::Extent3D {
+                width: self.extent[0],
+                height: self.extent[1],
+                depth: self.extent[2],
+            },
+            memory: memory,
+            memoryOffset: memory_offset as vk::DeviceSize,
+            flags: 0,
+        }
+    }
+}
+
+/// List of sparse binds to submit with `Queue::bind_sparse`.
+pub struct SparseBinds {
+    buffers: Vec<(vk::Buffer, Vec<vk::SparseMemoryBind>)>,
+    images_opaque: Vec<(vk::Image, Vec<vk::SparseMemoryBind>)>,
+    images: Vec<(vk::Image, Vec<vk::SparseImageMemoryBind>)>,
+
+    // Objects that must be kept alive until the binding operation is finished.
+    keep_alive_buffers: Vec<Arc<UnsafeBuffer>>,
+    keep_alive_images: Vec<Arc<UnsafeImage>>,
+    keep_alive_memory: Vec<Arc<DeviceMemory>>,
+}
+
+impl SparseBinds {
+    /// Builds an empty list of binds.
+    #[inline]
+    pub fn new() -> SparseBinds {
+        SparseBinds {
+            buffers: Vec::new(),
+            images_opaque: Vec::new(),
+            images: Vec::new(),
+            keep_alive_buffers: Vec::new(),
+            keep_alive_images: Vec::new(),
+            keep_alive_memory: Vec::new(),
+        }
+    }
+
+    /// Returns true if there is no bind in the list.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.buffers.is_empty() && self.images_opaque.is_empty() && self.images.is_empty()
+    }
+
+    /// Adds binds of ranges of a buffer created with sparse binding.
+    pub fn buffer<I>(mut self, buffer: &Arc<UnsafeBuffer>, binds: I) -> SparseBinds
+        where I: IntoIterator<Item = SparseMemoryBind>
+    {
+        let binds = binds.into_iter().map(|bind| self.memory_bind(bind)).collect();
+        self.buffers.push((buffer.internal_object(), binds));
+        self.keep_alive_buffers.push(buffer.clone());
+        self
+    }
+
+    /// Adds binds of opaque ranges of an image created with sparse binding.
+    ///
+    /// This is the only way to bind the memory of an image that doesn't have sparse residency,
+    /// and the way to bind the mipmap tail and the metadata of an image that has.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the image wasn't created with sparse binding.
+    ///
+    pub fn image_opaque<I>(mut self, image: &Arc<UnsafeImage>, binds: I) -> SparseBinds
+        where I: IntoIterator<Item = SparseMemoryBind>
+    {
+        assert!(image.sparse_level().sparse);
+
+        let binds = binds.into_iter().map(|bind| self.memory_bind(bind)).collect();
+        self.images_opaque.push((image.internal_object(), binds));
+        self.keep_alive_images.push(image.clone());
+        self
+    }
+
+    /// Adds binds of regions of an image created with sparse residency.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the image wasn't created with sparse residency.
+    /// - Panicks if the mipmap level or the array layer of a bind is out of range.
+    ///
+    pub fn image<I>(mut self, image: &Arc<UnsafeImage>, binds: I) -> SparseBinds
+        where I: IntoIterator<Item = SparseImageMemoryBind>
+    {
+        assert!(image.sparse_level().sparse_residency);
+
+        let binds = binds.into_iter().map(|bind| {
+            assert!(bind.mipmap_level < image.mipmap_levels());
+            assert!(bind.array_layer < image.dimensions().array_layers());
+
+            let vk_bind = bind.to_vk();
+            if let Some((memory, _)) = bind.memory {
+                self.keep_alive_memory.push(memory);
+            }
+            vk_bind
+        }).collect();
+
+        self.images.push((image.internal_object(), binds));
+        self.keep_alive_images.push(image.clone());
+        self
+    }
+
+    // Turns a `SparseMemoryBind` into its Vulkan equivalent and keeps its memory alive.
+    #[inline]
+    fn memory_bind(&mut self, bind: SparseMemoryBind) -> vk::SparseMemoryBind {
+        let vk_bind = bind.to_vk();
+        if let Some((memory, _)) = bind.memory {
+            self.keep_alive_memory.push(memory);
+        }
+        vk_bind
+    }
+
+    // Builds the per-resource Vulkan structs. They point to the binds stored in `self`.
+    fn vk_infos(&self) -> VkBindInfos {
+        VkBindInfos {
+            buffers: self.buffers.iter().map(|&(buffer, ref binds)| {
+                vk::SparseBufferMemoryBindInfo {
+                    buffer: buffer,
+                    bindCount: binds.len() as u32,
+                    pBinds: binds.as_ptr(),
+                }
+            }).collect(),
+            images_opaque: self.images_opaque.iter().map(|&(image, ref binds)| {
+                vk::SparseImageOpaqueMemoryBindInfo {
+                    image: image,
+                    bindCount: binds.len() as u32,
+                    pBinds: binds.as_ptr(),
+                }
+            }).collect(),
+            images: self.images.iter().map(|&(image, ref binds)| {
+                vk::SparseImageMemoryBindInfo {
+                    image: image,
+                    bindCount: binds.len() as u32,
+                    pBinds: binds.as_ptr(),
+                }
+            }).collect(),
+        }
+    }
+
+    /// Implementation of `Queue::bind_sparse`.
+    #[doc(hidden)]
+    pub unsafe fn submit(self, queue: &Queue) -> Result<SparseBindSubmission, SparseBindError> {
+        if !queue.family().supports_sparse_binding() {
+            return Err(SparseBindError::SparseBindingNotSupported);
+        }
+
+        let device = queue.device();
+        for buffer in self.keep_alive_buffers.iter() {
+            assert_eq!(&**buffer.device() as *const _, &**device as *const _);
+        }
+        for image in self.keep_alive_images.iter() {
+            assert_eq!(&**image.device() as *const _, &**device as *const _);
+        }
+        for memory in self.keep_alive_memory.iter() {
+            assert_eq!(memory.device() as *const _, &**device as *const _);
+        }
+
+        let fence = try!(FencePool::get(&device.fence_pool()));
+
+        {
+            let vk = device.pointers();
+            let infos = self.vk_infos();
+            let bind_info = infos.bind_sparse_info();
+            let queue = queue.internal_object_guard();
+            try!(check_errors(vk.QueueBindSparse(*queue, 1, &bind_info,
+                                                 fence.internal_object())));
+            fence.mark_submitted();
+        }
+
+        Ok(SparseBindSubmission {
+            fence: fence,
+            keep_alive_buffers: self.keep_alive_buffers,
+            keep_alive_images: self.keep_alive_images,
+            keep_alive_memory: self.keep_alive_memory,
+        })
+    }
+}
+
+impl fmt::Debug for SparseBinds {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "<SparseBinds of {} buffers and {} images>", self.buffers.len(),
+               self.images_opaque.len() + self.images.len())
+    }
+}
+
+// Vulkan structs describing the binds of each resource.
+struct VkBindInfos {
+    buffers: Vec<vk::SparseBufferMemoryBindInfo>,
+    images_opaque: Vec<vk::SparseImageOpaqueMemoryBindInfo>,
+    images: Vec<vk::SparseImageMemoryBindInfo>,
+}
+
+impl VkBindInfos {
+    // Builds the struct to pass to `vkQueueBindSparse`. It points to the vectors of `self`.
+    fn bind_sparse_info(&self) -> vk::BindSparseInfo {
+        vk::BindSparseInfo {
+            sType: vk::STRUCTURE_TYPE_BIND_SPARSE_INFO,
+            pNext: ptr::null(),
+            waitSemaphoreCount: 0,
+            pWaitSemaphores: ptr::null(),
+            bufferBindCount: self.buffers.len() as u32,
+            pBufferBinds: self.buffers.as_ptr(),
+            imageOpaqueBindCount: self.images_opaque.len() as u32,
+            pImageOpaqueBinds: self.images_opaque.as_ptr(),
+            imageBindCount: self.images.len() as u32,
+            pImageBinds: self.images.as_ptr(),
+            signalSemaphoreCount: 0,
+            pSignalSemaphores: ptr::null(),
+        }
+    }
+}
+
+/// Represents a sparse binding operation that has been submitted to a queue.
+///
+/// The resources and the memory of the binds are kept alive until the operation has finished.
+/// Destroying this object blocks until then.
+#[derive(Debug)]
+pub struct SparseBindSubmission {
+    fence: FenceGuard,
+    keep_alive_buffers: Vec<Arc<UnsafeBuffer>>,
+    keep_alive_images: Vec<Arc<UnsafeImage>>,
+    keep_alive_memory: Vec<Arc<DeviceMemory>>,
+}
+
+impl SparseBindSubmission {
+    /// Returns `true` if the binding operation has finished.
+    #[inline]
+    pub fn finished(&self) -> bool {
+        self.fence.ready().unwrap_or(false)     // TODO: what to do in case of error?
+    }
+
+    /// Waits until the binding operation has finished.
+    #[inline]
+    pub fn wait(&self, timeout: Duration) -> Result<(), FenceWaitError> {
+        self.fence.wait(timeout)
+    }
+}
+
+impl Drop for SparseBindSubmission {
+    #[inline]
+    fn drop(&mut self) {
+        match self.fence.wait_forever() {
+            Ok(_) => (),
+            Err(FenceWaitError::DeviceLostError) => (),
+            Err(FenceWaitError::Timeout) => panic!(),       // The driver has some sort of problem.
+            Err(FenceWaitError::OomError(_)) => panic!(),   // What else to do here?
+            Err(FenceWaitError::Unexpected(err)) => {
+                // We don't know whether the binds are still being processed, so the resources
+                // are leaked instead of being destroyed.
+                let _ = writeln!(io::stderr(), "vulkano: failed to wait for the end of a sparse \
+                                                bind, leaking its resources: {}", err);
+                mem::forget(mem::replace(&mut self.keep_alive_buffers, Vec::new()));
+                mem::forget(mem::replace(&mut self.keep_alive_images, Vec::new()));
+                mem::forget(mem::replace(&mut self.keep_alive_memory, Vec::new()));
+            },
+        }
+    }
+}
+
+/// Error that can happen when submitting sparse binds.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SparseBindError {
+    /// Not enough memory.
+    OomError(OomError),
+    /// The family of the queue doesn't support sparse binding operations.
+    SparseBindingNotSupported,
+    /// The device has been lost.
+    DeviceLost,
+    /// The Vulkan implementation returned an error that this operation isn't supposed to return.
+    Unexpected(Error),
+}
+
+impl error::Error for SparseBindError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            SparseBindError::OomError(_) => "not enough memory available",
+            SparseBindError::SparseBindingNotSupported => {
+                "the family of the queue doesn't support sparse binding operations"
+            },
+            SparseBindError::DeviceLost => "the device was lost",
+            SparseBindError::Unexpected(_) => {
+                "unexpected error returned by the Vulkan implementation"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            SparseBindError::OomError(ref err) => Some(err),
+            SparseBindError::Unexpected(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for SparseBindError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<OomError> for SparseBindError {
+    #[inline]
+    fn from(err: OomError) -> SparseBindError {
+        SparseBindError::OomError(err)
+    }
+}
+
+impl From<Error> for SparseBindError {
+    #[inline]
+    fn from(err: Error) -> SparseBindError {
+        match err {
+            err @ Error::OutOfHostMemory => SparseBindError::OomError(OomError::from(err)),
+            err @ Error::OutOfDeviceMemory => SparseBindError::OomError(OomError::from(err)),
+            Error::DeviceLost => SparseBindError::DeviceLost,
+            _ => SparseBindError::Unexpected(err)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::ImageAspects;
+    use vk;
+
+    use super::SparseBinds;
+    use super::SparseImageMemoryBind;
+    use super::SparseMemoryBind;
+
+    #[test]
+    fn memory_bind_to_vk() {
+        let bind = SparseMemoryBind {
+            resource_offset: 65536,
+            size: 131072,
+            memory: None,
+            metadata: true,
+        };
+
+        let vk_bind = bind.to_vk();
+        assert_eq!(vk_bind.resourceOffset, 65536);
+        assert_eq!(vk_bind.size, 131072);
+        assert_eq!(vk_bind.memory, 0);
+        assert_eq!(vk_bind.memoryOffset, 0);
+        assert_eq!(vk_bind.flags, vk::SPARSE_MEMORY_BIND_METADATA_BIT);
+    }
+
+    #[test]
+    fn image_memory_bind_to_vk() {
+        let bind = SparseImageMemoryBind {
+            aspects: ImageAspects { color: true, .. ImageAspects::none() },
+            mipmap_level: 2,
+            array_layer: 3,
+            offset: [128, 64, 0],
+            extent: [128, 128, 1],
+            memory: None,
+        };
+
+        let vk_bind = bind.to_vk();
+        assert_eq!(vk_bind.subresource.aspectMask, vk::IMAGE_ASPECT_COLOR_BIT);
+        assert_eq!(vk_bind.subresource.mipLevel, 2);
+        assert_eq!(vk_bind.subresource.arrayLayer, 3);
+        assert_eq!((vk_bind.offset.x, vk_bind.offset.y, vk_bind.offset.z), (128, 64, 0));
+        assert_eq!((vk_bind.extent.width, vk_bind.extent.height, vk_bind.extent.depth),
+                   (128, 128, 1));
+        assert_eq!(vk_bind.memory, 0);
+        assert_eq!(vk_bind.flags, 0);
+    }
+
+    #[test]
+    fn bind_info_assembly() {
+        let unbind = SparseMemoryBind {
+            resource_offset: 0,
+            size: 65536,
+            memory: None,
+            metadata: false,
+        };
+
+        let mut binds = SparseBinds::new();
+        assert!(binds.is_empty());
+
+        binds.buffers.push((1, vec![unbind.to_vk(), unbind.to_vk()]));
+        binds.buffers.push((2, vec![unbind.to_vk()]));
+        binds.images_opaque.push((3, vec![unbind.to_vk()]));
+        assert!(!binds.is_empty());
+
+        let infos = binds.vk_infos();
+        let bind_info = infos.bind_sparse_info();
+
+        assert_eq!(bind_info.sType, vk::STRUCTURE_TYPE_BIND_SPARSE_INFO);
+        assert_eq!(bind_info.bufferBindCount, 2);
+        assert_eq!(bind_info.imageOpaqueBindCount, 1);
+        assert_eq!(bind_info.imageBindCount, 0);
+        assert_eq!(bind_info.waitSemaphoreCount, 0);
+        assert_eq!(bind_info.signalSemaphoreCount, 0);
+
+        unsafe {
+            let buffers = &*bind_info.pBufferBinds.offset(1);
+            assert_eq!(buffers.buffer, 2);
+            assert_eq!(buffers.bindCount, 1);
+
+            let first = &*bind_info.pBufferBinds;
+            assert_eq!(first.buffer, 1);
+            assert_eq!(first.bindCount, 2);
+            assert_eq!(first.pBinds, binds.buffers[0].1.as_ptr());
+
+            let opaque = &*bind_info.pImageOpaqueBinds;
+            assert_eq!(opaque.image, 3);
+            assert_eq!((*opaque.pBinds).size, 65536);
+        }
+    }
+}
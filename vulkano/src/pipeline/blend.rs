@@ -22,10 +22,17 @@
 
 use vk;
 
+/// Describes how the color outputs of the fragment shader are written to the color attachments
+/// of the subpass.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Blend {
+    /// The logical operation to apply to integer and normalized integer attachments, or `None`
+    /// to disable logic operations.
+    ///
+    /// Using a logic operation requires the `logic_op` feature to be enabled.
     pub logic_op: Option<LogicOp>,
 
+    /// How to blend each color attachment of the subpass.
     pub attachments: AttachmentsBlend,
 
     /// The constant color to use for the `Constant*` blending operation.
@@ -41,19 +48,7 @@ impl Blend {
     pub fn pass_through() -> Blend {
         Blend {
             logic_op: None,
-            attachments: AttachmentsBlend::Collective(AttachmentBlend {
-                enabled: false,
-                color_op: BlendOp::Add,
-                color_src: BlendFactor::Zero,
-                color_dst: BlendFactor::One,
-                alpha_op: BlendOp::Add,
-                alpha_src: BlendFactor::Zero,
-                alpha_dst: BlendFactor::One,
-                mask_red: true,
-                mask_green: true,
-                mask_blue: true,
-                mask_alpha: true,
-            }),
+            attachments: AttachmentsBlend::Collective(AttachmentBlend::pass_through()),
             blend_constants: Some([0.0, 0.0, 0.0, 0.0]),
         }
     }
@@ -63,32 +58,37 @@ impl Blend {
     pub fn alpha_blending() -> Blend {
         Blend {
             logic_op: None,
-            attachments: AttachmentsBlend::Collective(AttachmentBlend {
-                enabled: true,
-                color_op: BlendOp::Add,
-                color_src: BlendFactor::SrcAlpha,
-                color_dst: BlendFactor::OneMinusSrcAlpha,
-                alpha_op: BlendOp::Add,
-                alpha_src: BlendFactor::SrcAlpha,
-                alpha_dst: BlendFactor::OneMinusSrcAlpha,
-                mask_red: true,
-                mask_green: true,
-                mask_blue: true,
-                mask_alpha: true,
-            }),
+            attachments: AttachmentsBlend::Collective(AttachmentBlend::alpha_blending()),
             blend_constants: Some([0.0, 0.0, 0.0, 0.0]),
         }
     }
 }
 
+/// Describes how to blend each color attachment of a subpass.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AttachmentsBlend {
+    /// The same blending is applied to all the color attachments.
     Collective(AttachmentBlend),
+
+    /// One blending state per color attachment, in the same order as the color attachments of
+    /// the subpass. The number of elements must match the number of color attachments.
+    ///
+    /// Using different blending states for different attachments requires the
+    /// `independent_blend` feature to be enabled.
     Individual(Vec<AttachmentBlend>),
 }
 
+/// Describes how the output of the fragment shader is blended with the content of a color
+/// attachment.
+///
+/// When blending is enabled, the color components are computed as
+/// `color_op(src * color_src, dst * color_dst)` and the alpha component as
+/// `alpha_op(src * alpha_src, dst * alpha_dst)`, where `src` is the output of the fragment
+/// shader and `dst` the current content of the attachment.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct AttachmentBlend {
+    /// If `false`, the output of the fragment shader is written as it is and the other
+    /// operations and factors are ignored.
     // TODO: could be automatically determined from the other params
     pub enabled: bool,
 
@@ -100,12 +100,75 @@ pub struct AttachmentBlend {
     pub alpha_src: BlendFactor,
     pub alpha_dst: BlendFactor,
 
+    /// If `false`, the red component of the attachment is left untouched.
     pub mask_red: bool,
+    /// If `false`, the green component of the attachment is left untouched.
     pub mask_green: bool,
+    /// If `false`, the blue component of the attachment is left untouched.
     pub mask_blue: bool,
+    /// If `false`, the alpha component of the attachment is left untouched.
     pub mask_alpha: bool,
 }
 
+impl AttachmentBlend {
+    /// Builds an `AttachmentBlend` where blending is disabled and the output of the fragment
+    /// shader is written directly to the attachment.
+    #[inline]
+    pub fn pass_through() -> AttachmentBlend {
+        AttachmentBlend {
+            enabled: false,
+            color_op: BlendOp::Add,
+            color_src: BlendFactor::Zero,
+            color_dst: BlendFactor::One,
+            alpha_op: BlendOp::Add,
+            alpha_src: BlendFactor::Zero,
+            alpha_dst: BlendFactor::One,
+            mask_red: true,
+            mask_green: true,
+            mask_blue: true,
+            mask_alpha: true,
+        }
+    }
+
+    /// Builds an `AttachmentBlend` where the output of the fragment shader is drawn over the
+    /// content of the attachment according to its alpha value.
+    #[inline]
+    pub fn alpha_blending() -> AttachmentBlend {
+        AttachmentBlend {
+            enabled: true,
+            color_op: BlendOp::Add,
+            color_src: BlendFactor::SrcAlpha,
+            color_dst: BlendFactor::OneMinusSrcAlpha,
+            alpha_op: BlendOp::Add,
+            alpha_src: BlendFactor::SrcAlpha,
+            alpha_dst: BlendFactor::OneMinusSrcAlpha,
+            mask_red: true,
+            mask_green: true,
+            mask_blue: true,
+            mask_alpha: true,
+        }
+    }
+
+    /// Builds an `AttachmentBlend` where the output of the fragment shader is added to the
+    /// content of the attachment.
+    #[inline]
+    pub fn additive() -> AttachmentBlend {
+        AttachmentBlend {
+            enabled: true,
+            color_op: BlendOp::Add,
+            color_src: BlendFactor::One,
+            color_dst: BlendFactor::One,
+            alpha_op: BlendOp::Add,
+            alpha_src: BlendFactor::One,
+            alpha_dst: BlendFactor::One,
+            mask_red: true,
+            mask_green: true,
+            mask_blue: true,
+            mask_alpha: true,
+        }
+    }
+}
+
 #[doc(hidden)]
 impl Into<vk::PipelineColorBlendAttachmentState> for AttachmentBlend {
     #[inline]
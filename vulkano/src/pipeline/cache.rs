@@ -19,13 +19,27 @@
 //! 
 //! Once that is done, you can extract the data from the cache and store it.
 //!
+//! # Thread safety
+//!
+//! Pipelines can be created from multiple threads at the same time with the same cache, and the
+//! data of a cache can be extracted while pipelines are being created. However Vulkan requires
+//! exclusive access to a cache while other caches are merged into it. Each `PipelineCache`
+//! contains a read-write lock for this purpose: merging locks the destination for writing, while
+//! everything else only locks for reading.
+//!
+use std::error;
+use std::fmt;
 use std::mem;
 use std::ptr;
 use std::sync::Arc;
+use std::sync::RwLock;
+use std::sync::RwLockReadGuard;
 
 use device::Device;
 
+use Error;
 use OomError;
+use Success;
 use VulkanObject;
 use VulkanPointers;
 use check_errors;
@@ -35,17 +49,29 @@ use vk;
 pub struct PipelineCache {
     device: Arc<Device>,
     cache: vk::PipelineCache,
+
+    // Locked for writing while other caches are merged into this one, and for reading whenever
+    // the cache is used otherwise.
+    lock: RwLock<()>,
 }
 
 impl PipelineCache {
     /// Builds a new pipeline cache from existing data.
     ///
-    /// The data must have been previously obtained with `get_data`.
-    // TODO: is that unsafe? is it safe to pass garbage data?
+    /// The data must have been previously obtained with `get_data`. The header of the data is
+    /// checked against the physical device, and an error is returned if it is malformed or if
+    /// the data was produced by a different device or driver.
+    ///
+    /// # Safety
+    ///
+    /// - Only the header is validated. The rest of the data must not have been modified since it
+    ///   was returned by `get_data`.
+    ///
     pub unsafe fn with_data(device: &Arc<Device>, initial_data: &[u8])
-                            -> Result<Arc<PipelineCache>, OomError>
+                            -> Result<Arc<PipelineCache>, PipelineCacheCreationError>
     {
-        PipelineCache::new_impl(device, Some(initial_data))
+        try!(check_header(device, initial_data));
+        Ok(try!(PipelineCache::new_impl(device, Some(initial_data))))
     }
 
     /// Builds a new empty pipeline cache.
@@ -76,29 +102,56 @@ impl PipelineCache {
         Ok(Arc::new(PipelineCache {
             device: device.clone(),
             cache: cache,
+            lock: RwLock::new(()),
         }))
     }
 
+    /// Returns the device this cache was created with.
+    #[inline]
+    pub fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+
     /// Merges other pipeline caches into this one.
     ///
+    /// This function blocks until no other thread is using any of the caches involved.
+    ///
     /// # Panic
     ///
     /// - Panicks if `self` is included in the list of other pipelines.
+    /// - Panicks if one of the other caches was not created with the same device.
     ///
-    // FIXME: vkMergePipelineCaches is not thread safe for the destination cache
     pub fn merge<'a, I>(&self, pipelines: I) -> Result<(), OomError>
         where I: IntoIterator<Item = &'a &'a Arc<PipelineCache>>
     {
         unsafe {
             let vk = self.device.pointers();
 
-            let pipelines = pipelines.into_iter().map(|pipeline| {
+            let mut others = pipelines.into_iter().map(|pipeline| {
                 assert!(&***pipeline as *const _ != &*self as *const _);
-                pipeline.cache
-            }).collect::<Vec<_>>();
+                assert_eq!(&*pipeline.device as *const Device, &*self.device as *const Device);
+                &***pipeline
+            }).collect::<Vec<&PipelineCache>>();
+
+            // Locks are always taken in the order of the addresses of the caches, so that two
+            // threads that merge the same caches in opposite directions can't deadlock.
+            others.sort_by_key(|p| *p as *const PipelineCache as usize);
+            others.dedup_by_key(|p| *p as *const PipelineCache as usize);
 
+            let self_addr = self as *const PipelineCache as usize;
+            let mut read_guards = Vec::with_capacity(others.len());
+            let mut write_guard = None;
+            for other in others.iter() {
+                if write_guard.is_none() && *other as *const PipelineCache as usize > self_addr {
+                    write_guard = Some(self.lock.write().unwrap());
+                }
+                read_guards.push(other.lock.read().unwrap());
+            }
+            let _write_guard = write_guard.unwrap_or_else(|| self.lock.write().unwrap());
+
+            let others = others.iter().map(|p| p.cache).collect::<Vec<_>>();
             try!(check_errors(vk.MergePipelineCaches(self.device.internal_object(), self.cache,
-                                                     pipelines.len() as u32, pipelines.as_ptr())));
+                                                     others.len() as u32, others.as_ptr())));
 
             Ok(())
         }
@@ -106,25 +159,83 @@ impl PipelineCache {
 
     /// Obtains the data from the cache.
     ///
-    /// This data can be stored and then reloaded and passed to `PipelineCache::new`.
+    /// This data can be stored and then reloaded and passed to `PipelineCache::with_data`.
     pub fn get_data(&self) -> Result<Vec<u8>, OomError> {
         unsafe {
             let vk = self.device.pointers();
+            let _guard = self.lock.read().unwrap();
+            let device = self.device.internal_object();
 
-            let mut num = 0;
-            try!(check_errors(vk.GetPipelineCacheData(self.device.internal_object(), self.cache,
-                                                      &mut num, ptr::null_mut())));
+            // Pipelines can be created with the cache at the same time, so it can grow between
+            // the moment its size is queried and the moment its data is copied. In that case
+            // `Incomplete` is returned and we try again.
+            loop {
+                let mut num = 0;
+                try!(check_errors(vk.GetPipelineCacheData(device, self.cache, &mut num,
+                                                          ptr::null_mut())));
 
-            let mut data: Vec<u8> = Vec::with_capacity(num as usize);
-            try!(check_errors(vk.GetPipelineCacheData(self.device.internal_object(), self.cache,
-                                                      &mut num, data.as_mut_ptr() as *mut _)));
-            data.set_len(num as usize);
+                let mut data: Vec<u8> = Vec::with_capacity(num as usize);
+                let result = vk.GetPipelineCacheData(device, self.cache, &mut num,
+                                                     data.as_mut_ptr() as *mut _);
 
-            Ok(data)
+                match try!(check_errors(result)) {
+                    Success::Success => {
+                        data.set_len(num as usize);
+                        return Ok(data);
+                    },
+                    Success::Incomplete => (),
+                    _ => unreachable!()
+                }
+            }
         }
     }
+
+    /// Locks the cache for reading. Must be held while the cache is used to create a pipeline.
+    #[doc(hidden)]
+    #[inline]
+    pub fn read_lock(&self) -> RwLockReadGuard<()> {
+        self.lock.read().unwrap()
+    }
+}
+
+// Checks the header of some initial data against the physical device of `device`.
+//
+// The header is made of the length of the header, the version of the header, the vendor ID, the
+// device ID and the pipeline cache UUID. All the fields are stored with the least significant
+// byte first.
+fn check_header(device: &Arc<Device>, data: &[u8]) -> Result<(), PipelineCacheCreationError> {
+    const HEADER_MIN_LEN: usize = 16 + vk::UUID_SIZE as usize;
+
+    if data.len() < HEADER_MIN_LEN {
+        return Err(PipelineCacheCreationError::InvalidHeader);
+    }
+
+    let read_u32 = |offset: usize| {
+        (data[offset] as u32) | ((data[offset + 1] as u32) << 8) |
+        ((data[offset + 2] as u32) << 16) | ((data[offset + 3] as u32) << 24)
+    };
+
+    let header_len = read_u32(0) as usize;
+    if header_len < HEADER_MIN_LEN || header_len > data.len() {
+        return Err(PipelineCacheCreationError::InvalidHeader);
+    }
+
+    if read_u32(4) != vk::PIPELINE_CACHE_HEADER_VERSION_ONE {
+        return Err(PipelineCacheCreationError::InvalidHeader);
+    }
+
+    let physical = device.physical_device();
+    if read_u32(8) != physical.pci_vendor_id() || read_u32(12) != physical.pci_device_id() ||
+       &data[16 .. HEADER_MIN_LEN] != &physical.uuid()[..]
+    {
+        return Err(PipelineCacheCreationError::IncompatibleData);
+    }
+
+    Ok(())
 }
 
+impl_debug_handle!(PipelineCache);
+
 unsafe impl VulkanObject for PipelineCache {
     type Object = vk::PipelineCache;
 
@@ -144,9 +255,94 @@ impl Drop for PipelineCache {
     }
 }
 
+/// Error that can happen when creating a pipeline cache from existing data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PipelineCacheCreationError {
+    /// Not enough memory.
+    OomError(OomError),
+    /// The header of the data is malformed.
+    InvalidHeader,
+    /// The data was produced by a different physical device or driver version.
+    IncompatibleData,
+    /// The Vulkan implementation returned an error that this operation isn't supposed to return.
+    Unexpected(Error),
+}
+
+impl error::Error for PipelineCacheCreationError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            PipelineCacheCreationError::OomError(_) => "not enough memory available",
+            PipelineCacheCreationError::InvalidHeader => "the header of the data is malformed",
+            PipelineCacheCreationError::IncompatibleData => "the data was produced by a \
+                                                            different physical device or driver \
+                                                            version",
+            PipelineCacheCreationError::Unexpected(_) => {
+                "unexpected error returned by the Vulkan implementation"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            PipelineCacheCreationError::OomError(ref err) => Some(err),
+            PipelineCacheCreationError::Unexpected(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for PipelineCacheCreationError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<OomError> for PipelineCacheCreationError {
+    #[inline]
+    fn from(err: OomError) -> PipelineCacheCreationError {
+        PipelineCacheCreationError::OomError(err)
+    }
+}
+
+impl From<Error> for PipelineCacheCreationError {
+    #[inline]
+    fn from(err: Error) -> PipelineCacheCreationError {
+        match err {
+            err @ Error::OutOfHostMemory => {
+                PipelineCacheCreationError::OomError(OomError::from(err))
+            },
+            err @ Error::OutOfDeviceMemory => {
+                PipelineCacheCreationError::OomError(OomError::from(err))
+            },
+            _ => PipelineCacheCreationError::Unexpected(err)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::ffi::CString;
+
+    use descriptor::pipeline_layout::EmptyPipeline;
+    use descriptor::pipeline_layout::EmptyPipelineDesc;
+    use pipeline::ComputePipeline;
     use pipeline::cache::PipelineCache;
+    use pipeline::cache::PipelineCacheCreationError;
+    use pipeline::derivative::PipelineDerivative;
+    use pipeline::shader::ShaderModule;
+    use Error;
+    use OomError;
+
+    #[test]
+    fn creation_error_mapping() {
+        assert_eq!(PipelineCacheCreationError::from(Error::OutOfDeviceMemory),
+                   PipelineCacheCreationError::OomError(OomError::OutOfDeviceMemory));
+        assert_eq!(PipelineCacheCreationError::from(Error::DeviceLost),
+                   PipelineCacheCreationError::Unexpected(Error::DeviceLost));
+    }
 
     #[test]
     #[should_panic]
@@ -155,4 +351,84 @@ mod tests {
         let pipeline = PipelineCache::empty(&device).unwrap();
         pipeline.merge(&[&pipeline]).unwrap();
     }
+
+    #[test]
+    fn data_roundtrip() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let cs = unsafe { ShaderModule::new(&device, &BASIC_CS).unwrap() };
+        let name = CString::new("main").unwrap();
+        let entry_point = unsafe {
+            cs.compute_shader_entry_point::<(), _>(&name, EmptyPipelineDesc)
+        };
+        let layout = EmptyPipeline::new(&device).unwrap();
+
+        let cache = PipelineCache::empty(&device).unwrap();
+        let _ = ComputePipeline::new(&device, &layout, &entry_point, Some(&cache),
+                                     PipelineDerivative::None).unwrap();
+        let data = cache.get_data().unwrap();
+
+        let cache = unsafe { PipelineCache::with_data(&device, &data).unwrap() };
+        let _ = ComputePipeline::new(&device, &layout, &entry_point, Some(&cache),
+                                     PipelineDerivative::None).unwrap();
+    }
+
+    #[test]
+    fn merge() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let cs = unsafe { ShaderModule::new(&device, &BASIC_CS).unwrap() };
+        let name = CString::new("main").unwrap();
+        let entry_point = unsafe {
+            cs.compute_shader_entry_point::<(), _>(&name, EmptyPipelineDesc)
+        };
+        let layout = EmptyPipeline::new(&device).unwrap();
+
+        let cache1 = PipelineCache::empty(&device).unwrap();
+        let cache2 = PipelineCache::empty(&device).unwrap();
+        let _ = ComputePipeline::new(&device, &layout, &entry_point, Some(&cache2),
+                                     PipelineDerivative::None).unwrap();
+
+        cache1.merge(&[&cache2, &cache2]).unwrap();
+        let _ = cache1.get_data().unwrap();
+    }
+
+    #[test]
+    fn corrupted_header() {
+        let (device, _) = gfx_dev_and_queue!();
+        let data = PipelineCache::empty(&device).unwrap().get_data().unwrap();
+
+        let mut bad_version = data.clone();
+        bad_version[4] ^= 0xff;
+        match unsafe { PipelineCache::with_data(&device, &bad_version) } {
+            Err(PipelineCacheCreationError::InvalidHeader) => (),
+            _ => panic!()
+        }
+
+        match unsafe { PipelineCache::with_data(&device, &data[.. 8]) } {
+            Err(PipelineCacheCreationError::InvalidHeader) => (),
+            _ => panic!()
+        }
+
+        let mut bad_uuid = data.clone();
+        bad_uuid[16] ^= 0xff;
+        match unsafe { PipelineCache::with_data(&device, &bad_uuid) } {
+            Err(PipelineCacheCreationError::IncompatibleData) => (),
+            _ => panic!()
+        }
+    }
+
+    /*
+        #version 450
+
+        void main() {
+        }
+    */
+    const BASIC_CS: [u8; 140] = [3, 2, 35, 7, 0, 0, 1, 0, 0, 0, 0, 0, 6, 0, 0, 0, 0, 0, 0, 0, 17, 0,
+                                 2, 0, 1, 0, 0, 0, 14, 0, 3, 0, 0, 0, 0, 0, 1, 0, 0, 0, 15, 0, 5, 0,
+                                 5, 0, 0, 0, 1, 0, 0, 0, 109, 97, 105, 110, 0, 0, 0, 0, 16, 0, 6, 0,
+                                 1, 0, 0, 0, 17, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 19, 0,
+                                 2, 0, 2, 0, 0, 0, 33, 0, 3, 0, 3, 0, 0, 0, 2, 0, 0, 0, 54, 0, 5, 0,
+                                 2, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 248, 0, 2, 0, 4, 0,
+                                 0, 0, 253, 0, 1, 0, 56, 0, 1, 0];
 }
@@ -7,6 +7,7 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
+use std::any::Any;
 use std::error;
 use std::fmt;
 use std::mem;
@@ -14,10 +15,18 @@ use std::ptr;
 use std::sync::Arc;
 
 use descriptor::PipelineLayout;
+use descriptor::descriptor::DescriptorDescTy;
 use descriptor::pipeline_layout::PipelineLayoutDesc;
+use descriptor::pipeline_layout::PipelineLayoutNotSupersetError;
 use descriptor::pipeline_layout::PipelineLayoutSuperset;
+use pipeline::cache::PipelineCache;
+use pipeline::derivative::BasePipeline;
+use pipeline::derivative::PipelineDerivative;
 use pipeline::shader::ComputeShaderEntryPoint;
+use pipeline::shader::EntryPointError;
+use pipeline::shader::ExecutionModel;
 use pipeline::shader::SpecializationConstants;
+use pipeline::shader::specialization_info;
 
 use device::Device;
 use Error;
@@ -35,58 +44,122 @@ pub struct ComputePipeline<Pl> {
     pipeline: vk::Pipeline,
     device: Arc<Device>,
     pipeline_layout: Arc<Pl>,
+    allows_derivatives: bool,
+
+    // The pipeline this pipeline derives from, kept alive as required by Vulkan.
+    base: Option<Arc<Any + Send + Sync>>,
 }
 
 impl<Pl> ComputePipeline<Pl> {
     /// Builds a new `ComputePipeline`.
+    ///
+    /// If `cache` is `Some`, the implementation will look for the pipeline in the cache and add
+    /// it to the cache if it isn't there.
+    ///
+    /// `derivative` indicates whether the pipeline can be used as a base for other pipelines,
+    /// or is a derivative of another compute pipeline.
+    ///
+    /// The values of the specialization constants are the ones passed to the `specialize` method
+    /// of the entry point, if any.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the cache was not created with the same device.
+    /// - Panicks if the base pipeline was not created with the same device or is not a compute
+    ///   pipeline.
+    ///
+    /// # Validation
+    ///
+    /// The pipeline layout must be a superset of the descriptors declared by the shader. Storage
+    /// images whose format isn't specified in the shader require the
+    /// `shader_storage_image_read_without_format` or `shader_storage_image_write_without_format`
+    /// features, depending on whether they are written.
+    ///
     pub fn new<Css, Csl>(device: &Arc<Device>, pipeline_layout: &Arc<Pl>,
-                         shader: &ComputeShaderEntryPoint<Css, Csl>, specialization: &Css) 
+                         shader: &ComputeShaderEntryPoint<Css, Csl>,
+                         cache: Option<&Arc<PipelineCache>>, derivative: PipelineDerivative)
                          -> Result<Arc<ComputePipeline<Pl>>, ComputePipelineCreationError>
         where Pl: PipelineLayout + PipelineLayoutSuperset<Csl>, Csl: PipelineLayoutDesc,
               Css: SpecializationConstants
     {
         let vk = device.pointers();
 
-        // TODO: more details in the error
-        if !PipelineLayoutSuperset::is_superset_of(&**pipeline_layout, shader.layout()) {
-            return Err(ComputePipelineCreationError::IncompatiblePipelineLayout);
+        if let Some(cache) = cache {
+            assert_eq!(&**cache.device() as *const Device, &**device as *const Device);
+        }
+
+        let base = match derivative {
+            PipelineDerivative::DerivativeOf(base) => {
+                assert_eq!(&**base.device() as *const Device, &**device as *const Device);
+                assert!(base.is_compute(), "A compute pipeline can only derive from another \
+                                            compute pipeline");
+                if !base.allows_derivatives() {
+                    return Err(ComputePipelineCreationError::BasePipelineDoesntAllowDerivatives);
+                }
+                Some(base.keep_alive())
+            },
+            _ => None
+        };
+
+        try!(shader.module().check_entry_point(shader.name(), ExecutionModel::Compute));
+
+        try!(PipelineLayoutSuperset::ensure_superset_of(&**pipeline_layout, shader.layout())
+                .map_err(ComputePipelineCreationError::IncompatiblePipelineLayout));
+
+        for set in shader.layout().descriptors_desc() {
+            for desc in set {
+                let image = match desc.ty {
+                    DescriptorDescTy::Image(image) => image,
+                    _ => continue,
+                };
+
+                if image.sampled || image.format.is_some() {
+                    continue;
+                }
+
+                if desc.readonly {
+                    if !device.enabled_features().shader_storage_image_read_without_format {
+                        return Err(ComputePipelineCreationError::
+                                   ShaderStorageImageReadWithoutFormatFeatureNotEnabled);
+                    }
+                } else {
+                    if !device.enabled_features().shader_storage_image_write_without_format {
+                        return Err(ComputePipelineCreationError::
+                                   ShaderStorageImageWriteWithoutFormatFeatureNotEnabled);
+                    }
+                }
+            }
         }
 
         let pipeline = unsafe {
-            let spec_descriptors = <Css as SpecializationConstants>::descriptors();
-            let specialization = vk::SpecializationInfo {
-                mapEntryCount: spec_descriptors.len() as u32,
-                pMapEntries: spec_descriptors.as_ptr() as *const _,
-                dataSize: mem::size_of_val(specialization),
-                pData: specialization as *const Css as *const _,
-            };
+            let specialization = shader.specialization().map(|s| specialization_info(s));
 
             let stage = vk::PipelineShaderStageCreateInfo {
-                sType: vk::STRUCTURE_TYPE_COMPUTE_PIPELINE_CREATE_INFO,
+                sType: vk::STRUCTURE_TYPE_PIPELINE_SHADER_STAGE_CREATE_INFO,
                 pNext: ptr::null(),
                 flags: 0,
                 stage: vk::SHADER_STAGE_COMPUTE_BIT,
                 module: shader.module().internal_object(),
                 pName: shader.name().as_ptr(),
-                pSpecializationInfo: if specialization.dataSize == 0 {
-                    ptr::null()
-                } else {
-                    &specialization
-                },
+                pSpecializationInfo: specialization.as_ref().map(|s| s as *const _)
+                                                   .unwrap_or(ptr::null()),
             };
 
             let infos = vk::ComputePipelineCreateInfo {
                 sType: vk::STRUCTURE_TYPE_COMPUTE_PIPELINE_CREATE_INFO,
                 pNext: ptr::null(),
-                flags: 0,
+                flags: derivative.flags(),
                 stage: stage,
                 layout: PipelineLayout::inner_pipeline_layout(&**pipeline_layout).internal_object(),
-                basePipelineHandle: 0,
-                basePipelineIndex: 0,
+                basePipelineHandle: derivative.base_handle(),
+                basePipelineIndex: -1,
             };
 
+            let _cache_guard = cache.map(|c| c.read_lock());
+            let cache = cache.map(|c| c.internal_object()).unwrap_or(0);
+
             let mut output = mem::uninitialized();
-            try!(check_errors(vk.CreateComputePipelines(device.internal_object(), 0,
+            try!(check_errors(vk.CreateComputePipelines(device.internal_object(), cache,
                                                         1, &infos, ptr::null(), &mut output)));
             output
         };
@@ -95,6 +168,8 @@ impl<Pl> ComputePipeline<Pl> {
             device: device.clone(),
             pipeline: pipeline,
             pipeline_layout: pipeline_layout.clone(),
+            allows_derivatives: derivative.allows_derivatives(),
+            base: base,
         }))
     }
 
@@ -109,8 +184,49 @@ impl<Pl> ComputePipeline<Pl> {
     pub fn layout(&self) -> &Arc<Pl> {
         &self.pipeline_layout
     }
+
+    /// Returns true if other pipelines can be created as derivatives of this pipeline.
+    #[inline]
+    pub fn allows_derivatives(&self) -> bool {
+        self.allows_derivatives
+    }
+
+    /// Returns true if this pipeline was created as a derivative of another pipeline.
+    #[inline]
+    pub fn is_derivative(&self) -> bool {
+        self.base.is_some()
+    }
+}
+
+unsafe impl<Pl> BasePipeline for Arc<ComputePipeline<Pl>> where Pl: Send + Sync + 'static {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+
+    #[inline]
+    fn allows_derivatives(&self) -> bool {
+        self.allows_derivatives
+    }
+
+    #[inline]
+    fn is_compute(&self) -> bool {
+        true
+    }
+
+    #[inline]
+    fn inner_pipeline(&self) -> vk::Pipeline {
+        self.pipeline
+    }
+
+    #[inline]
+    fn keep_alive(&self) -> Arc<Any + Send + Sync> {
+        self.clone() as Arc<_>
+    }
 }
 
+impl_debug_handle!(ComputePipeline<Pl>);
+
 unsafe impl<Pl> VulkanObject for ComputePipeline<Pl> {
     type Object = vk::Pipeline;
 
@@ -136,7 +252,19 @@ pub enum ComputePipelineCreationError {
     /// Not enough memory.
     OomError(OomError),
     /// The pipeline layout is not compatible with what the shader expects.
-    IncompatiblePipelineLayout,
+    IncompatiblePipelineLayout(PipelineLayoutNotSupersetError),
+    /// The entry point of the shader doesn't exist in its module.
+    InvalidEntryPoint(EntryPointError),
+    /// The `shader_storage_image_read_without_format` feature must be enabled in order to read
+    /// from a storage image whose format is unknown to the shader.
+    ShaderStorageImageReadWithoutFormatFeatureNotEnabled,
+    /// The `shader_storage_image_write_without_format` feature must be enabled in order to write
+    /// to a storage image whose format is unknown to the shader.
+    ShaderStorageImageWriteWithoutFormatFeatureNotEnabled,
+    /// The pipeline was created as a derivative of a pipeline that doesn't allow derivatives.
+    BasePipelineDoesntAllowDerivatives,
+    /// The Vulkan implementation returned an error that this operation isn't supposed to return.
+    Unexpected(Error),
 }
 
 impl error::Error for ComputePipelineCreationError {
@@ -144,9 +272,27 @@ impl error::Error for ComputePipelineCreationError {
     fn description(&self) -> &str {
         match *self {
             ComputePipelineCreationError::OomError(_) => "not enough memory available",
-            ComputePipelineCreationError::IncompatiblePipelineLayout => "the pipeline layout is \
-                                                                         not compatible with what \
-                                                                         the shader expects",
+            ComputePipelineCreationError::IncompatiblePipelineLayout(_) => {
+                "the pipeline layout is not compatible with what the shader expects"
+            },
+            ComputePipelineCreationError::InvalidEntryPoint(_) => {
+                "the entry point of the shader doesn't exist in its module"
+            },
+            ComputePipelineCreationError::ShaderStorageImageReadWithoutFormatFeatureNotEnabled => {
+                "the `shader_storage_image_read_without_format` feature must be enabled in order \
+                 to read from a storage image whose format is unknown to the shader"
+            },
+            ComputePipelineCreationError::ShaderStorageImageWriteWithoutFormatFeatureNotEnabled => {
+                "the `shader_storage_image_write_without_format` feature must be enabled in order \
+                 to write to a storage image whose format is unknown to the shader"
+            },
+            ComputePipelineCreationError::BasePipelineDoesntAllowDerivatives => {
+                "the pipeline was created as a derivative of a pipeline that doesn't allow \
+                 derivatives"
+            },
+            ComputePipelineCreationError::Unexpected(_) => {
+                "unexpected error returned by the Vulkan implementation"
+            },
         }
     }
 
@@ -154,6 +300,9 @@ impl error::Error for ComputePipelineCreationError {
     fn cause(&self) -> Option<&error::Error> {
         match *self {
             ComputePipelineCreationError::OomError(ref err) => Some(err),
+            ComputePipelineCreationError::IncompatiblePipelineLayout(ref err) => Some(err),
+            ComputePipelineCreationError::InvalidEntryPoint(ref err) => Some(err),
+            ComputePipelineCreationError::Unexpected(ref err) => Some(err),
             _ => None
         }
     }
@@ -166,6 +315,13 @@ impl fmt::Display for ComputePipelineCreationError {
     }
 }
 
+impl From<EntryPointError> for ComputePipelineCreationError {
+    #[inline]
+    fn from(err: EntryPointError) -> ComputePipelineCreationError {
+        ComputePipelineCreationError::InvalidEntryPoint(err)
+    }
+}
+
 impl From<OomError> for ComputePipelineCreationError {
     #[inline]
     fn from(err: OomError) -> ComputePipelineCreationError {
@@ -183,13 +339,263 @@ impl From<Error> for ComputePipelineCreationError {
             err @ Error::OutOfDeviceMemory => {
                 ComputePipelineCreationError::OomError(OomError::from(err))
             },
-            _ => panic!("unexpected error: {:?}", err)
+            _ => ComputePipelineCreationError::Unexpected(err)
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    // TODO: test for basic creation
+    use std::ffi::CString;
+    use std::iter;
+    use std::option::IntoIter as OptionIntoIter;
+    use std::sync::Arc;
+    use std::vec::IntoIter as VecIntoIter;
+
+    use descriptor::PipelineLayout;
+    use descriptor::descriptor::DescriptorBufferDesc;
+    use descriptor::descriptor::DescriptorDesc;
+    use descriptor::descriptor::DescriptorDescTy;
+    use descriptor::descriptor::DescriptorImageDesc;
+    use descriptor::descriptor::DescriptorImageDescArray;
+    use descriptor::descriptor::DescriptorImageDescDimensions;
+    use descriptor::descriptor::ShaderStages;
+    use descriptor::descriptor_set::UnsafeDescriptorSetLayout;
+    use descriptor::pipeline_layout::EmptyPipeline;
+    use descriptor::pipeline_layout::EmptyPipelineDesc;
+    use descriptor::pipeline_layout::PipelineLayoutDesc;
+    use descriptor::pipeline_layout::PipelineLayoutNotSupersetError;
+    use descriptor::pipeline_layout::UnsafePipelineLayout;
+    use device::Device;
+    use pipeline::ComputePipeline;
+    use pipeline::ComputePipelineCreationError;
+    use pipeline::derivative::PipelineDerivative;
+    use pipeline::shader::ShaderModule;
+
     // TODO: test for pipeline layout error
+
+    specialization_constants! {
+        struct LocalSize {
+            0 => x: u32,
+        }
+    }
+
+    #[test]
+    fn local_size_from_spec_constant() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let cs = unsafe { ShaderModule::new(&device, &SPEC_LOCAL_SIZE_CS).unwrap() };
+        let name = CString::new("main").unwrap();
+        let entry_point = unsafe {
+            cs.compute_shader_entry_point::<LocalSize, _>(&name, EmptyPipelineDesc)
+        };
+        let layout = EmptyPipeline::new(&device).unwrap();
+
+        for &x in &[1, 64] {
+            let constants = LocalSize { x: x };
+            let _ = ComputePipeline::new(&device, &layout, &entry_point.specialize(&constants),
+                                         None, PipelineDerivative::None).unwrap();
+        }
+    }
+
+    #[test]
+    fn derivative() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let cs = unsafe { ShaderModule::new(&device, &SPEC_LOCAL_SIZE_CS).unwrap() };
+        let name = CString::new("main").unwrap();
+        let entry_point = unsafe {
+            cs.compute_shader_entry_point::<LocalSize, _>(&name, EmptyPipelineDesc)
+        };
+        let layout = EmptyPipeline::new(&device).unwrap();
+
+        let base = ComputePipeline::new(&device, &layout,
+                                        &entry_point.specialize(&LocalSize { x: 1 }), None,
+                                        PipelineDerivative::AllowDerivatives).unwrap();
+        assert!(base.allows_derivatives());
+
+        let derivative = ComputePipeline::new(&device, &layout,
+                                              &entry_point.specialize(&LocalSize { x: 64 }), None,
+                                              PipelineDerivative::DerivativeOf(&base)).unwrap();
+        assert!(derivative.is_derivative());
+    }
+
+    #[test]
+    fn derivative_not_allowed() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let cs = unsafe { ShaderModule::new(&device, &SPEC_LOCAL_SIZE_CS).unwrap() };
+        let name = CString::new("main").unwrap();
+        let entry_point = unsafe {
+            cs.compute_shader_entry_point::<LocalSize, _>(&name, EmptyPipelineDesc)
+        };
+        let layout = EmptyPipeline::new(&device).unwrap();
+
+        let base = ComputePipeline::new(&device, &layout,
+                                        &entry_point.specialize(&LocalSize { x: 1 }), None,
+                                        PipelineDerivative::None).unwrap();
+
+        match ComputePipeline::new(&device, &layout, &entry_point.specialize(&LocalSize { x: 64 }),
+                                   None, PipelineDerivative::DerivativeOf(&base))
+        {
+            Err(ComputePipelineCreationError::BasePipelineDoesntAllowDerivatives) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn layout_missing_descriptor() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let cs = unsafe { ShaderModule::new(&device, &SPEC_LOCAL_SIZE_CS).unwrap() };
+        let name = CString::new("main").unwrap();
+        let desc = SingleSetDesc(vec![storage_buffer(0)]);
+        let entry_point = unsafe {
+            cs.compute_shader_entry_point::<LocalSize, _>(&name, desc)
+        };
+        let layout = EmptyPipeline::new(&device).unwrap();
+
+        match ComputePipeline::new(&device, &layout, &entry_point.specialize(&LocalSize { x: 1 }),
+                                   None, PipelineDerivative::None)
+        {
+            Err(ComputePipelineCreationError::IncompatiblePipelineLayout(
+                PipelineLayoutNotSupersetError::MissingDescriptor { set_num: 0, binding_num: 0 }
+            )) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn layout_superset() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let cs = unsafe { ShaderModule::new(&device, &SPEC_LOCAL_SIZE_CS).unwrap() };
+        let name = CString::new("main").unwrap();
+        let desc = SingleSetDesc(vec![storage_buffer(1)]);
+        let entry_point = unsafe {
+            cs.compute_shader_entry_point::<LocalSize, _>(&name, desc)
+        };
+        let layout = SingleSetLayout::new(&device, SingleSetDesc(vec![storage_buffer(0),
+                                                                      storage_buffer(1)]));
+
+        let pipeline = ComputePipeline::new(&device, &layout,
+                                            &entry_point.specialize(&LocalSize { x: 1 }), None,
+                                            PipelineDerivative::None).unwrap();
+        assert_eq!(&**pipeline.layout() as *const _, &*layout as *const _);
+    }
+
+    #[test]
+    fn storage_image_without_format() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let image = DescriptorDesc {
+            binding: 0,
+            ty: DescriptorDescTy::Image(DescriptorImageDesc {
+                sampled: false,
+                dimensions: DescriptorImageDescDimensions::TwoDimensional,
+                format: None,
+                multisampled: false,
+                array_layers: DescriptorImageDescArray::NonArrayed,
+            }),
+            array_count: 1,
+            stages: ShaderStages::compute(),
+            readonly: false,
+            immutable_samplers: None,
+        };
+
+        let cs = unsafe { ShaderModule::new(&device, &SPEC_LOCAL_SIZE_CS).unwrap() };
+        let name = CString::new("main").unwrap();
+        let entry_point = unsafe {
+            cs.compute_shader_entry_point::<LocalSize, _>(&name, SingleSetDesc(vec![image.clone()]))
+        };
+        let layout = SingleSetLayout::new(&device, SingleSetDesc(vec![image]));
+
+        match ComputePipeline::new(&device, &layout, &entry_point.specialize(&LocalSize { x: 1 }),
+                                   None, PipelineDerivative::None)
+        {
+            Err(ComputePipelineCreationError::
+                ShaderStorageImageWriteWithoutFormatFeatureNotEnabled) => (),
+            _ => panic!()
+        }
+    }
+
+    fn storage_buffer(binding: u32) -> DescriptorDesc {
+        DescriptorDesc {
+            binding: binding,
+            ty: DescriptorDescTy::Buffer(DescriptorBufferDesc {
+                dynamic: Some(false),
+                storage: true,
+            }),
+            array_count: 1,
+            stages: ShaderStages::compute(),
+            readonly: false,
+            immutable_samplers: None,
+        }
+    }
+
+    // Description of a layout with a single descriptor set.
+    #[derive(Clone)]
+    struct SingleSetDesc(Vec<DescriptorDesc>);
+
+    unsafe impl PipelineLayoutDesc for SingleSetDesc {
+        type SetsIter = OptionIntoIter<VecIntoIter<DescriptorDesc>>;
+        type DescIter = VecIntoIter<DescriptorDesc>;
+
+        fn descriptors_desc(&self) -> Self::SetsIter {
+            Some(self.0.clone().into_iter()).into_iter()
+        }
+    }
+
+    // Pipeline layout with a single descriptor set.
+    struct SingleSetLayout {
+        inner: UnsafePipelineLayout,
+        desc: SingleSetDesc,
+    }
+
+    impl SingleSetLayout {
+        fn new(device: &Arc<Device>, desc: SingleSetDesc) -> Arc<SingleSetLayout> {
+            let set = UnsafeDescriptorSetLayout::new(device, desc.0.iter().cloned().map(Some));
+            let inner = UnsafePipelineLayout::new(device, Some(&set), iter::empty()).unwrap();
+            Arc::new(SingleSetLayout { inner: inner, desc: desc })
+        }
+    }
+
+    unsafe impl PipelineLayout for SingleSetLayout {
+        fn inner_pipeline_layout(&self) -> &UnsafePipelineLayout {
+            &self.inner
+        }
+    }
+
+    unsafe impl PipelineLayoutDesc for SingleSetLayout {
+        type SetsIter = OptionIntoIter<VecIntoIter<DescriptorDesc>>;
+        type DescIter = VecIntoIter<DescriptorDesc>;
+
+        fn descriptors_desc(&self) -> Self::SetsIter {
+            self.desc.descriptors_desc()
+        }
+    }
+
+    /*
+        #version 450
+
+        layout(local_size_x_id = 0) in;
+
+        void main() {
+        }
+    */
+    const SPEC_LOCAL_SIZE_CS: [u8; 260] = [3, 2, 35, 7, 0, 0, 1, 0, 0, 0, 0, 0, 10, 0, 0, 0, 0, 0,
+                                           0, 0, 17, 0, 2, 0, 1, 0, 0, 0, 14, 0, 3, 0, 0, 0, 0, 0,
+                                           1, 0, 0, 0, 15, 0, 5, 0, 5, 0, 0, 0, 1, 0, 0, 0, 109, 97,
+                                           105, 110, 0, 0, 0, 0, 16, 0, 6, 0, 1, 0, 0, 0, 17, 0, 0,
+                                           0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 71, 0, 4, 0, 2, 0,
+                                           0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 71, 0, 4, 0, 3, 0, 0, 0,
+                                           11, 0, 0, 0, 25, 0, 0, 0, 19, 0, 2, 0, 4, 0, 0, 0, 33, 0,
+                                           3, 0, 5, 0, 0, 0, 4, 0, 0, 0, 21, 0, 4, 0, 6, 0, 0, 0,
+                                           32, 0, 0, 0, 0, 0, 0, 0, 23, 0, 4, 0, 7, 0, 0, 0, 6, 0,
+                                           0, 0, 3, 0, 0, 0, 50, 0, 4, 0, 6, 0, 0, 0, 2, 0, 0, 0, 1,
+                                           0, 0, 0, 43, 0, 4, 0, 6, 0, 0, 0, 8, 0, 0, 0, 1, 0, 0, 0,
+                                           51, 0, 6, 0, 7, 0, 0, 0, 3, 0, 0, 0, 2, 0, 0, 0, 8, 0, 0,
+                                           0, 8, 0, 0, 0, 54, 0, 5, 0, 4, 0, 0, 0, 1, 0, 0, 0, 0, 0,
+                                           0, 0, 5, 0, 0, 0, 248, 0, 2, 0, 9, 0, 0, 0, 253, 0, 1, 0,
+                                           56, 0, 1, 0];
 }
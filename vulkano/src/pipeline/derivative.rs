@@ -0,0 +1,101 @@
+// Copyright (c) 2016 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+//! Creating pipelines as derivatives of other pipelines.
+//!
+//! When you create lots of pipelines that are similar to each other, the Vulkan implementation
+//! may be able to create them faster if you tell it that they are derivatives of a common base
+//! pipeline.
+//!
+//! In order to be used as a base, a pipeline must have been created with
+//! `PipelineDerivative::AllowDerivatives`. You can then create other pipelines with
+//! `PipelineDerivative::DerivativeOf(&base)`. A derivative keeps its base pipeline alive.
+//!
+//! Note that a graphics pipeline can only derive from another graphics pipeline, and a compute
+//! pipeline from another compute pipeline.
+//!
+use std::any::Any;
+use std::sync::Arc;
+
+use device::Device;
+use vk;
+
+/// Whether a pipeline can be used as a base or is a derivative of another pipeline.
+#[derive(Copy, Clone)]
+pub enum PipelineDerivative<'a> {
+    /// The pipeline is neither a base nor a derivative.
+    None,
+    /// Other pipelines can be created as derivatives of this pipeline.
+    AllowDerivatives,
+    /// The pipeline is a derivative of the given pipeline, which must have been created with
+    /// `AllowDerivatives`.
+    DerivativeOf(&'a BasePipeline),
+}
+
+impl<'a> PipelineDerivative<'a> {
+    /// Returns the flags to pass in the create info of the pipeline.
+    #[doc(hidden)]
+    #[inline]
+    pub fn flags(&self) -> vk::PipelineCreateFlags {
+        match *self {
+            PipelineDerivative::None => 0,
+            PipelineDerivative::AllowDerivatives => vk::PIPELINE_CREATE_ALLOW_DERIVATIVES_BIT,
+            PipelineDerivative::DerivativeOf(_) => vk::PIPELINE_CREATE_DERIVATIVE_BIT,
+        }
+    }
+
+    /// Returns the handle of the base pipeline, or 0 if this is not a derivative.
+    #[doc(hidden)]
+    #[inline]
+    pub fn base_handle(&self) -> vk::Pipeline {
+        match *self {
+            PipelineDerivative::DerivativeOf(base) => base.inner_pipeline(),
+            _ => 0,
+        }
+    }
+
+    /// Returns true if this is `AllowDerivatives`.
+    #[inline]
+    pub fn allows_derivatives(&self) -> bool {
+        match *self {
+            PipelineDerivative::AllowDerivatives => true,
+            _ => false,
+        }
+    }
+}
+
+impl<'a> Default for PipelineDerivative<'a> {
+    #[inline]
+    fn default() -> PipelineDerivative<'a> {
+        PipelineDerivative::None
+    }
+}
+
+/// Trait for pipelines that can be the base of a derivative pipeline.
+///
+/// This trait is implemented on `Arc<GraphicsPipeline>` and `Arc<ComputePipeline>`, so that the
+/// derivative can keep its base alive.
+pub unsafe trait BasePipeline {
+    /// Returns the device the pipeline was created with.
+    fn device(&self) -> &Arc<Device>;
+
+    /// Returns true if the pipeline was created with `PipelineDerivative::AllowDerivatives`.
+    fn allows_derivatives(&self) -> bool;
+
+    /// Returns true if this is a compute pipeline, and false if this is a graphics pipeline.
+    fn is_compute(&self) -> bool;
+
+    /// Returns the Vulkan handle of the pipeline.
+    #[doc(hidden)]
+    fn inner_pipeline(&self) -> vk::Pipeline;
+
+    /// Returns an object that keeps the pipeline alive.
+    #[doc(hidden)]
+    fn keep_alive(&self) -> Arc<Any + Send + Sync>;
+}
@@ -7,6 +7,7 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
+use std::any::Any;
 use std::error;
 use std::fmt;
 use std::mem;
@@ -17,9 +18,12 @@ use smallvec::SmallVec;
 
 use device::Device;
 use descriptor::PipelineLayout;
+use descriptor::descriptor::DescriptorDescTy;
 use descriptor::pipeline_layout::PipelineLayoutDesc;
 use descriptor::pipeline_layout::PipelineLayoutSuperset;
 use descriptor::pipeline_layout::EmptyPipeline;
+use descriptor::pipeline_layout::EmptyPipelineDesc;
+use framebuffer::EmptySinglePassRenderPass;
 use framebuffer::RenderPass;
 use framebuffer::RenderPassDesc;
 use framebuffer::RenderPassSubpassInterface;
@@ -32,6 +36,9 @@ use check_errors;
 use vk;
 
 use pipeline::blend::Blend;
+use pipeline::cache::PipelineCache;
+use pipeline::derivative::BasePipeline;
+use pipeline::derivative::PipelineDerivative;
 use pipeline::blend::AttachmentsBlend;
 use pipeline::depth_stencil::Compare;
 use pipeline::depth_stencil::DepthStencil;
@@ -42,6 +49,9 @@ use pipeline::multisample::Multisample;
 use pipeline::raster::DepthBiasControl;
 use pipeline::raster::PolygonMode;
 use pipeline::raster::Rasterization;
+use pipeline::shader::EmptyShaderInterfaceDef;
+use pipeline::shader::EntryPointError;
+use pipeline::shader::ExecutionModel;
 use pipeline::shader::ShaderInterfaceDef;
 use pipeline::shader::ShaderInterfaceDefMatch;
 use pipeline::shader::VertexShaderEntryPoint;
@@ -49,8 +59,13 @@ use pipeline::shader::TessControlShaderEntryPoint;
 use pipeline::shader::TessEvaluationShaderEntryPoint;
 use pipeline::shader::GeometryShaderEntryPoint;
 use pipeline::shader::FragmentShaderEntryPoint;
+use pipeline::shader::SpecializationConstants;
+use pipeline::shader::specialization_info;
 use pipeline::vertex::Definition as VertexDefinition;
+use pipeline::vertex::SingleBufferDefinition;
+use pipeline::vertex::IncompatibleVertexDefinitionError;
 use pipeline::vertex::Vertex;
+use pipeline::viewport::Scissor;
 use pipeline::viewport::ViewportsState;
 
 pub struct GraphicsPipelineParams<'a, Vdef, Vsp, Vi, Vo, Vl, Tcs, Tci, Tco, Tcl, Tes, Tei, Teo,
@@ -70,6 +85,12 @@ pub struct GraphicsPipelineParams<'a, Vdef, Vsp, Vi, Vo, Vl, Tcs, Tci, Tco, Tcl,
     pub blend: Blend,
     pub layout: &'a Arc<L>,
     pub render_pass: Subpass<'a, Rp>,
+    /// If `Some`, the implementation will look for the pipeline in this cache and add it to the
+    /// cache if it isn't there. Must have been created with the same device as the pipeline.
+    pub cache: Option<&'a Arc<PipelineCache>>,
+    /// Whether the pipeline can be used as a base for other pipelines, or is a derivative of
+    /// another graphics pipeline.
+    pub derivative: PipelineDerivative<'a>,
 }
 
 pub struct GraphicsPipelineParamsTess<'a, Tcs, Tci, Tco, Tcl, Tes, Tei, Teo, Tel> {
@@ -77,6 +98,448 @@ pub struct GraphicsPipelineParamsTess<'a, Tcs, Tci, Tco, Tcl, Tes, Tei, Teo, Tel
     pub tessellation_evaluation_shader: TessEvaluationShaderEntryPoint<'a, Tes, Tei, Teo, Tel>,
 }
 
+/// Prototype for a `GraphicsPipeline`.
+///
+/// Created with `GraphicsPipeline::start()`. Each method sets one part of the state of the
+/// pipeline and returns the builder, and `build()` creates the pipeline. The vertex shader,
+/// the fragment shader, the pipeline layout, the viewports and the render pass are mandatory,
+/// while the other states have default values.
+///
+/// # Example
+///
+/// ```ignore
+/// let pipeline = GraphicsPipeline::start()
+///     .vertex_input(SingleBufferDefinition::<Vertex>::new())
+///     .vertex_shader(vs.main_entry_point(), &())
+///     .viewports_dynamic_scissors_irrelevant(1)
+///     .fragment_shader(fs.main_entry_point(), &())
+///     .layout(&pipeline_layout)
+///     .render_pass(Subpass::from(&render_pass, 0).unwrap())
+///     .build(&device)
+///     .unwrap();
+/// ```
+pub struct GraphicsPipelineBuilder<'a, Vdef, Vsp, Vi, Vo, Vl, Tcs, Tci, Tco, Tcl, Tes, Tei, Teo,
+                                   Tel, Gs, Gi, Go, Gl, Fs, Fi, Fo, Fl, L, Rp>
+    where L: 'a, Rp: 'a
+{
+    vertex_input: Vdef,
+    vertex_shader: Option<VertexShaderEntryPoint<'a, Vsp, Vi, Vo, Vl>>,
+    input_assembly: InputAssembly,
+    tessellation: Option<GraphicsPipelineParamsTess<'a, Tcs, Tci, Tco, Tcl, Tes, Tei, Teo, Tel>>,
+    geometry_shader: Option<GeometryShaderEntryPoint<'a, Gs, Gi, Go, Gl>>,
+    viewport: Option<ViewportsState>,
+    raster: Rasterization,
+    multisample: Multisample,
+    fragment_shader: Option<FragmentShaderEntryPoint<'a, Fs, Fi, Fo, Fl>>,
+    depth_stencil: DepthStencil,
+    blend: Blend,
+    layout: Option<&'a Arc<L>>,
+    render_pass: Option<Subpass<'a, Rp>>,
+    cache: Option<&'a Arc<PipelineCache>>,
+    derivative: PipelineDerivative<'a>,
+}
+
+impl<'a, Vdef, Vsp, Vi, Vo, Vl, Tcs, Tci, Tco, Tcl, Tes, Tei, Teo, Tel, Gs, Gi, Go, Gl, Fs, Fi, Fo,
+     Fl, L, Rp>
+    GraphicsPipelineBuilder<'a, Vdef, Vsp, Vi, Vo, Vl, Tcs, Tci, Tco, Tcl, Tes, Tei, Teo, Tel, Gs,
+                            Gi, Go, Gl, Fs, Fi, Fo, Fl, L, Rp>
+{
+    /// Sets the description of the vertex buffers that are going to be used with the pipeline.
+    #[inline]
+    pub fn vertex_input<T>(self, vertex_input: T)
+                           -> GraphicsPipelineBuilder<'a, T, Vsp, Vi, Vo, Vl, Tcs, Tci, Tco, Tcl,
+                                                      Tes, Tei, Teo, Tel, Gs, Gi, Go, Gl, Fs, Fi,
+                                                      Fo, Fl, L, Rp>
+    {
+        GraphicsPipelineBuilder {
+            vertex_input: vertex_input,
+            vertex_shader: self.vertex_shader,
+            input_assembly: self.input_assembly,
+            tessellation: self.tessellation,
+            geometry_shader: self.geometry_shader,
+            viewport: self.viewport,
+            raster: self.raster,
+            multisample: self.multisample,
+            fragment_shader: self.fragment_shader,
+            depth_stencil: self.depth_stencil,
+            blend: self.blend,
+            layout: self.layout,
+            render_pass: self.render_pass,
+            cache: self.cache,
+            derivative: self.derivative,
+        }
+    }
+
+    /// Sets the vertex shader and its specialization constants.
+    #[inline]
+    pub fn vertex_shader<S, I, O, Ly>(self, shader: VertexShaderEntryPoint<'a, S, I, O, Ly>,
+                                      specialization: &'a S)
+                                      -> GraphicsPipelineBuilder<'a, Vdef, S, I, O, Ly, Tcs, Tci,
+                                                                 Tco, Tcl, Tes, Tei, Teo, Tel, Gs,
+                                                                 Gi, Go, Gl, Fs, Fi, Fo, Fl, L, Rp>
+    {
+        GraphicsPipelineBuilder {
+            vertex_input: self.vertex_input,
+            vertex_shader: Some(shader.specialize(specialization)),
+            input_assembly: self.input_assembly,
+            tessellation: self.tessellation,
+            geometry_shader: self.geometry_shader,
+            viewport: self.viewport,
+            raster: self.raster,
+            multisample: self.multisample,
+            fragment_shader: self.fragment_shader,
+            depth_stencil: self.depth_stencil,
+            blend: self.blend,
+            layout: self.layout,
+            render_pass: self.render_pass,
+            cache: self.cache,
+            derivative: self.derivative,
+        }
+    }
+
+    /// Sets the topology of the primitives. Primitive restart is disabled.
+    ///
+    /// The default is `PrimitiveTopology::TriangleList`.
+    #[inline]
+    pub fn input_assembly(mut self, topology: PrimitiveTopology) -> Self {
+        self.input_assembly = InputAssembly {
+            topology: topology,
+            primitive_restart_enable: false,
+        };
+        self
+    }
+
+    /// Enables or disables primitive restart. Only strip and fan topologies support it.
+    #[inline]
+    pub fn primitive_restart(mut self, enabled: bool) -> Self {
+        self.input_assembly.primitive_restart_enable = enabled;
+        self
+    }
+
+    /// Sets the tessellation control and evaluation shaders and their specialization constants.
+    ///
+    /// The topology of the primitives must be a patch list when tessellation shaders are used.
+    #[inline]
+    pub fn tessellation_shaders<Cs, Ci, Co, Cl, Es, Ei, Eo, El>
+                               (self, control: TessControlShaderEntryPoint<'a, Cs, Ci, Co, Cl>,
+                                control_specialization: &'a Cs,
+                                evaluation: TessEvaluationShaderEntryPoint<'a, Es, Ei, Eo, El>,
+                                evaluation_specialization: &'a Es)
+                               -> GraphicsPipelineBuilder<'a, Vdef, Vsp, Vi, Vo, Vl, Cs, Ci, Co, Cl,
+                                                          Es, Ei, Eo, El, Gs, Gi, Go, Gl, Fs, Fi,
+                                                          Fo, Fl, L, Rp>
+    {
+        GraphicsPipelineBuilder {
+            vertex_input: self.vertex_input,
+            vertex_shader: self.vertex_shader,
+            input_assembly: self.input_assembly,
+            tessellation: Some(GraphicsPipelineParamsTess {
+                tessellation_control_shader: control.specialize(control_specialization),
+                tessellation_evaluation_shader: evaluation.specialize(evaluation_specialization),
+            }),
+            geometry_shader: self.geometry_shader,
+            viewport: self.viewport,
+            raster: self.raster,
+            multisample: self.multisample,
+            fragment_shader: self.fragment_shader,
+            depth_stencil: self.depth_stencil,
+            blend: self.blend,
+            layout: self.layout,
+            render_pass: self.render_pass,
+            cache: self.cache,
+            derivative: self.derivative,
+        }
+    }
+
+    /// Sets the geometry shader and its specialization constants.
+    #[inline]
+    pub fn geometry_shader<S, I, O, Ly>(self,
+                                        shader: GeometryShaderEntryPoint<'a, S, I, O, Ly>,
+                                        specialization: &'a S)
+                                        -> GraphicsPipelineBuilder<'a, Vdef, Vsp, Vi, Vo, Vl, Tcs,
+                                                                   Tci, Tco, Tcl, Tes, Tei, Teo,
+                                                                   Tel, S, I, O, Ly, Fs, Fi, Fo, Fl,
+                                                                   L, Rp>
+    {
+        GraphicsPipelineBuilder {
+            vertex_input: self.vertex_input,
+            vertex_shader: self.vertex_shader,
+            input_assembly: self.input_assembly,
+            tessellation: self.tessellation,
+            geometry_shader: Some(shader.specialize(specialization)),
+            viewport: self.viewport,
+            raster: self.raster,
+            multisample: self.multisample,
+            fragment_shader: self.fragment_shader,
+            depth_stencil: self.depth_stencil,
+            blend: self.blend,
+            layout: self.layout,
+            render_pass: self.render_pass,
+            cache: self.cache,
+            derivative: self.derivative,
+        }
+    }
+
+    /// Sets the state of the viewports and scissor boxes.
+    #[inline]
+    pub fn viewports(mut self, viewports: ViewportsState) -> Self {
+        self.viewport = Some(viewports);
+        self
+    }
+
+    /// Uses `num` dynamic viewports, and scissor boxes that cover the whole framebuffer.
+    #[inline]
+    pub fn viewports_dynamic_scissors_irrelevant(self, num: u32) -> Self {
+        self.viewports(ViewportsState::DynamicViewports {
+            scissors: (0 .. num).map(|_| Scissor::irrelevant()).collect(),
+        })
+    }
+
+    /// Sets the rasterization state.
+    ///
+    /// The default is `Rasterization::default()`.
+    #[inline]
+    pub fn rasterization(mut self, raster: Rasterization) -> Self {
+        self.raster = raster;
+        self
+    }
+
+    /// Sets the multisampling state.
+    ///
+    /// The default is `Multisample::disabled()`.
+    #[inline]
+    pub fn multisample(mut self, multisample: Multisample) -> Self {
+        self.multisample = multisample;
+        self
+    }
+
+    /// Sets the fragment shader and its specialization constants.
+    #[inline]
+    pub fn fragment_shader<S, I, O, Ly>(self,
+                                        shader: FragmentShaderEntryPoint<'a, S, I, O, Ly>,
+                                        specialization: &'a S)
+                                        -> GraphicsPipelineBuilder<'a, Vdef, Vsp, Vi, Vo, Vl, Tcs,
+                                                                   Tci, Tco, Tcl, Tes, Tei, Teo,
+                                                                   Tel, Gs, Gi, Go, Gl, S, I, O, Ly,
+                                                                   L, Rp>
+    {
+        GraphicsPipelineBuilder {
+            vertex_input: self.vertex_input,
+            vertex_shader: self.vertex_shader,
+            input_assembly: self.input_assembly,
+            tessellation: self.tessellation,
+            geometry_shader: self.geometry_shader,
+            viewport: self.viewport,
+            raster: self.raster,
+            multisample: self.multisample,
+            fragment_shader: Some(shader.specialize(specialization)),
+            depth_stencil: self.depth_stencil,
+            blend: self.blend,
+            layout: self.layout,
+            render_pass: self.render_pass,
+            cache: self.cache,
+            derivative: self.derivative,
+        }
+    }
+
+    /// Sets the depth and stencil state.
+    ///
+    /// The default is `DepthStencil::disabled()`.
+    #[inline]
+    pub fn depth_stencil(mut self, depth_stencil: DepthStencil) -> Self {
+        self.depth_stencil = depth_stencil;
+        self
+    }
+
+    /// Sets the blending state.
+    ///
+    /// The default is `Blend::pass_through()`.
+    #[inline]
+    pub fn blend(mut self, blend: Blend) -> Self {
+        self.blend = blend;
+        self
+    }
+
+    /// Sets the layout of the descriptors and push constants of the pipeline.
+    #[inline]
+    pub fn layout<Ly>(self, layout: &'a Arc<Ly>)
+                      -> GraphicsPipelineBuilder<'a, Vdef, Vsp, Vi, Vo, Vl, Tcs, Tci, Tco, Tcl, Tes,
+                                                 Tei, Teo, Tel, Gs, Gi, Go, Gl, Fs, Fi, Fo, Fl, Ly,
+                                                 Rp>
+    {
+        GraphicsPipelineBuilder {
+            vertex_input: self.vertex_input,
+            vertex_shader: self.vertex_shader,
+            input_assembly: self.input_assembly,
+            tessellation: self.tessellation,
+            geometry_shader: self.geometry_shader,
+            viewport: self.viewport,
+            raster: self.raster,
+            multisample: self.multisample,
+            fragment_shader: self.fragment_shader,
+            depth_stencil: self.depth_stencil,
+            blend: self.blend,
+            layout: Some(layout),
+            render_pass: self.render_pass,
+            cache: self.cache,
+            derivative: self.derivative,
+        }
+    }
+
+    /// Sets the subpass of the render pass the pipeline is going to be used in.
+    #[inline]
+    pub fn render_pass<R>(self, subpass: Subpass<'a, R>)
+                          -> GraphicsPipelineBuilder<'a, Vdef, Vsp, Vi, Vo, Vl, Tcs, Tci, Tco, Tcl,
+                                                     Tes, Tei, Teo, Tel, Gs, Gi, Go, Gl, Fs, Fi, Fo,
+                                                     Fl, L, R>
+    {
+        GraphicsPipelineBuilder {
+            vertex_input: self.vertex_input,
+            vertex_shader: self.vertex_shader,
+            input_assembly: self.input_assembly,
+            tessellation: self.tessellation,
+            geometry_shader: self.geometry_shader,
+            viewport: self.viewport,
+            raster: self.raster,
+            multisample: self.multisample,
+            fragment_shader: self.fragment_shader,
+            depth_stencil: self.depth_stencil,
+            blend: self.blend,
+            layout: self.layout,
+            render_pass: Some(subpass),
+            cache: self.cache,
+            derivative: self.derivative,
+        }
+    }
+
+    /// Looks for the pipeline in `cache` and adds it to the cache if it isn't there. The cache
+    /// must have been created with the same device as the pipeline.
+    #[inline]
+    pub fn cache(mut self, cache: &'a Arc<PipelineCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Sets whether the pipeline can be used as a base for other pipelines, or is a derivative
+    /// of another graphics pipeline.
+    #[inline]
+    pub fn derivative(mut self, derivative: PipelineDerivative<'a>) -> Self {
+        self.derivative = derivative;
+        self
+    }
+}
+
+impl<'a, Vdef, Vsp, Vi, Vo, Vl, Tcs, Tci, Tco, Tcl, Tes, Tei, Teo, Tel, Gs, Gi, Go, Gl, Fs, Fi, Fo,
+     Fl, L, Rp>
+    GraphicsPipelineBuilder<'a, Vdef, Vsp, Vi, Vo, Vl, Tcs, Tci, Tco, Tcl, Tes, Tei, Teo, Tel, Gs,
+                            Gi, Go, Gl, Fs, Fi, Fo, Fl, L, Rp>
+    where Vdef: VertexDefinition<Vi>,
+          L: PipelineLayout + PipelineLayoutSuperset<Vl> + PipelineLayoutSuperset<Tcl> +
+             PipelineLayoutSuperset<Tel> + PipelineLayoutSuperset<Gl> + PipelineLayoutSuperset<Fl>,
+          Vl: PipelineLayoutDesc, Tcl: PipelineLayoutDesc, Tel: PipelineLayoutDesc,
+          Gl: PipelineLayoutDesc, Fl: PipelineLayoutDesc,
+          Vo: ShaderInterfaceDef,
+          Tci: ShaderInterfaceDefMatch<Vo>,
+          Tco: ShaderInterfaceDef,
+          Tei: ShaderInterfaceDefMatch<Tco>,
+          Teo: ShaderInterfaceDef,
+          Gi: ShaderInterfaceDefMatch<Vo> + ShaderInterfaceDefMatch<Teo>,
+          Go: ShaderInterfaceDef,
+          Fi: ShaderInterfaceDefMatch<Vo> + ShaderInterfaceDefMatch<Teo> +
+              ShaderInterfaceDefMatch<Go>,
+          Fo: ShaderInterfaceDef,
+          Rp: RenderPass + RenderPassDesc + RenderPassSubpassInterface<Fo>,
+          Vsp: SpecializationConstants, Tcs: SpecializationConstants,
+          Tes: SpecializationConstants, Gs: SpecializationConstants, Fs: SpecializationConstants,
+{
+    /// Builds the graphics pipeline.
+    ///
+    /// Returns an error if one of the mandatory parts of the pipeline hasn't been set, or if
+    /// the pipeline isn't valid.
+    pub fn build(self, device: &Arc<Device>)
+                 -> Result<Arc<GraphicsPipeline<Vdef, L, Rp>>, GraphicsPipelineCreationError>
+    {
+        let vertex_shader = match self.vertex_shader {
+            Some(s) => s,
+            None => return Err(GraphicsPipelineCreationError::NoVertexShader),
+        };
+
+        let fragment_shader = match self.fragment_shader {
+            Some(s) => s,
+            None => return Err(GraphicsPipelineCreationError::NoFragmentShader),
+        };
+
+        let layout = match self.layout {
+            Some(l) => l,
+            None => return Err(GraphicsPipelineCreationError::NoPipelineLayout),
+        };
+
+        let render_pass = match self.render_pass {
+            Some(s) => s,
+            None => return Err(GraphicsPipelineCreationError::NoRenderPass),
+        };
+
+        let viewport = match self.viewport {
+            Some(v) => v,
+            None => return Err(GraphicsPipelineCreationError::NoViewport),
+        };
+
+        // The input of each shader stage must match the output of the previous stage.
+        let stages_match = match (&self.tessellation, &self.geometry_shader) {
+            (&None, &None) => fragment_shader.input().matches(vertex_shader.output()),
+            (&Some(ref tess), &None) => {
+                tess_stages_match(&vertex_shader, tess) &&
+                fragment_shader.input().matches(tess.tessellation_evaluation_shader.output())
+            },
+            (&None, &Some(ref gs)) => {
+                gs.input().matches(vertex_shader.output()) &&
+                fragment_shader.input().matches(gs.output())
+            },
+            (&Some(ref tess), &Some(ref gs)) => {
+                tess_stages_match(&vertex_shader, tess) &&
+                gs.input().matches(tess.tessellation_evaluation_shader.output()) &&
+                fragment_shader.input().matches(gs.output())
+            },
+        };
+
+        if !stages_match {
+            return Err(GraphicsPipelineCreationError::ShaderStagesMismatch);
+        }
+
+        GraphicsPipeline::new_inner(device, GraphicsPipelineParams {
+            vertex_input: self.vertex_input,
+            vertex_shader: vertex_shader,
+            input_assembly: self.input_assembly,
+            tessellation: self.tessellation,
+            geometry_shader: self.geometry_shader,
+            viewport: viewport,
+            raster: self.raster,
+            multisample: self.multisample,
+            fragment_shader: fragment_shader,
+            depth_stencil: self.depth_stencil,
+            blend: self.blend,
+            layout: layout,
+            render_pass: render_pass,
+            cache: self.cache,
+            derivative: self.derivative,
+        })
+    }
+}
+
+// Returns true if the input of the tessellation control shader matches the output of the vertex
+// shader, and if the input of the tessellation evaluation shader matches the output of the
+// tessellation control shader.
+fn tess_stages_match<Vsp, Vi, Vo, Vl, Tcs, Tci, Tco, Tcl, Tes, Tei, Teo, Tel>
+                    (vertex_shader: &VertexShaderEntryPoint<Vsp, Vi, Vo, Vl>,
+                     tess: &GraphicsPipelineParamsTess<Tcs, Tci, Tco, Tcl, Tes, Tei, Teo, Tel>)
+                     -> bool
+    where Vo: ShaderInterfaceDef, Tci: ShaderInterfaceDefMatch<Vo>, Tco: ShaderInterfaceDef,
+          Tei: ShaderInterfaceDefMatch<Tco>
+{
+    tess.tessellation_control_shader.input().matches(vertex_shader.output()) &&
+    tess.tessellation_evaluation_shader.input().matches(tess.tessellation_control_shader.output())
+}
+
 ///
 ///
 /// The template parameter contains the descriptor set to use with this pipeline, and the
@@ -91,24 +554,86 @@ pub struct GraphicsPipeline<VertexDefinition, Layout, RenderP> {
 
     vertex_definition: VertexDefinition,
 
-    dynamic_line_width: bool,
-    dynamic_viewport: bool,
-    dynamic_scissor: bool,
-    dynamic_depth_bias: bool,
-    dynamic_depth_bounds: bool,
-    dynamic_stencil_compare_mask: bool,
-    dynamic_stencil_write_mask: bool,
-    dynamic_stencil_reference: bool,
-    dynamic_blend_constants: bool,
+    dynamic_states: DynamicStateFlags,
 
     num_viewports: u32,
+
+    has_geometry_shader: bool,
+
+    allows_derivatives: bool,
+
+    // The pipeline this pipeline derives from, kept alive as required by Vulkan.
+    base: Option<Arc<Any + Send + Sync>>,
+}
+
+/// Describes which parts of the state of a graphics pipeline are dynamic.
+///
+/// A dynamic state is not baked in the pipeline and must instead be set in the command buffer
+/// before drawing, while the other states are fixed when the pipeline is created.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct DynamicStateFlags {
+    pub line_width: bool,
+    pub viewports: bool,
+    pub scissors: bool,
+    pub depth_bias: bool,
+    pub blend_constants: bool,
+    pub depth_bounds: bool,
+    pub stencil_compare_mask: bool,
+    pub stencil_write_mask: bool,
+    pub stencil_reference: bool,
+}
+
+impl DynamicStateFlags {
+    /// Builds a `DynamicStateFlags` where all the states are fixed.
+    #[inline]
+    pub fn none() -> DynamicStateFlags {
+        DynamicStateFlags::default()
+    }
+}
+
+impl GraphicsPipeline<(), (), ()> {
+    /// Starts building a new graphics pipeline. See `GraphicsPipelineBuilder`.
+    #[inline]
+    pub fn start<'a>() -> GraphicsPipelineBuilder<'a, SingleBufferDefinition<()>, (),
+                                                  EmptyShaderInterfaceDef, EmptyShaderInterfaceDef,
+                                                  EmptyPipelineDesc, (), EmptyShaderInterfaceDef,
+                                                  EmptyShaderInterfaceDef, EmptyPipelineDesc, (),
+                                                  EmptyShaderInterfaceDef, EmptyShaderInterfaceDef,
+                                                  EmptyPipelineDesc, (), EmptyShaderInterfaceDef,
+                                                  EmptyShaderInterfaceDef, EmptyPipelineDesc, (),
+                                                  EmptyShaderInterfaceDef, EmptyShaderInterfaceDef,
+                                                  EmptyPipelineDesc, EmptyPipeline,
+                                                  EmptySinglePassRenderPass>
+    {
+        GraphicsPipelineBuilder {
+            vertex_input: SingleBufferDefinition::new(),
+            vertex_shader: None,
+            input_assembly: InputAssembly::triangle_list(),
+            tessellation: None,
+            geometry_shader: None,
+            viewport: None,
+            raster: Rasterization::default(),
+            multisample: Multisample::disabled(),
+            fragment_shader: None,
+            depth_stencil: DepthStencil::disabled(),
+            blend: Blend::pass_through(),
+            layout: None,
+            render_pass: None,
+            cache: None,
+            derivative: PipelineDerivative::None,
+        }
+    }
 }
 
 impl<Vdef, L, Rp> GraphicsPipeline<Vdef, L, Rp>
     where L: PipelineLayout, Rp: RenderPass + RenderPassDesc
 {
     /// Builds a new graphics pipeline object.
+    ///
+    /// This constructor is kept for compatibility and will be removed in the future. Use
+    /// `GraphicsPipeline::start()` instead.
     #[inline]
+    #[deprecated(note = "use GraphicsPipeline::start() instead")]
     pub fn new<'a, Vsp, Vi, Vo, Vl, Fs, Fi, Fo, Fl>
               (device: &Arc<Device>,
                params: GraphicsPipelineParams<'a, Vdef, Vsp, Vi, Vo, Vl, (), (), (), EmptyPipeline,
@@ -122,16 +647,21 @@ impl<Vdef, L, Rp> GraphicsPipeline<Vdef, L, Rp>
               Fo: ShaderInterfaceDef,
               Vo: ShaderInterfaceDef,
               Rp: RenderPassSubpassInterface<Fo>,
+              Vsp: SpecializationConstants, Fs: SpecializationConstants,
     {
-        // TODO: return proper errors
-        assert!(params.fragment_shader.input().matches(params.vertex_shader.output()));
-        GraphicsPipeline::new_inner::<_, _, _, _, (), (), (), EmptyPipeline, (), (), (),
-                                      EmptyPipeline, (), (), (), EmptyPipeline, _, _, _, _>
-                                      (device, params)
+        if !params.fragment_shader.input().matches(params.vertex_shader.output()) {
+            return Err(GraphicsPipelineCreationError::ShaderStagesMismatch);
+        }
+
+        GraphicsPipeline::new_inner(device, params)
     }
 
     /// Builds a new graphics pipeline object with a geometry shader.
+    ///
+    /// This constructor is kept for compatibility and will be removed in the future. Use
+    /// `GraphicsPipeline::start()` instead.
     #[inline]
+    #[deprecated(note = "use GraphicsPipeline::start() instead")]
     pub fn with_geometry_shader<'a, Vsp, Vi, Vo, Vl, Gsp, Gi, Go, Gl, Fs, Fi, Fo, Fl>
               (device: &Arc<Device>,
                params: GraphicsPipelineParams<'a, Vdef, Vsp, Vi, Vo, Vl, (), (), (), EmptyPipeline,
@@ -150,20 +680,30 @@ impl<Vdef, L, Rp> GraphicsPipeline<Vdef, L, Rp>
               Fo: ShaderInterfaceDef,
               Go: ShaderInterfaceDef,
               Rp: RenderPassSubpassInterface<Fo>,
+              Vsp: SpecializationConstants, Gsp: SpecializationConstants,
+              Fs: SpecializationConstants,
     {
-        // TODO: return proper errors
-        if let Some(ref geometry_shader) = params.geometry_shader {
-            assert!(geometry_shader.input().matches(params.vertex_shader.output()));
-            assert!(params.fragment_shader.input().matches(geometry_shader.output()));
-        } else {
-            assert!(params.fragment_shader.input().matches(params.vertex_shader.output()));
+        let stages_match = match params.geometry_shader {
+            Some(ref gs) => {
+                gs.input().matches(params.vertex_shader.output()) &&
+                params.fragment_shader.input().matches(gs.output())
+            },
+            None => params.fragment_shader.input().matches(params.vertex_shader.output()),
+        };
+
+        if !stages_match {
+            return Err(GraphicsPipelineCreationError::ShaderStagesMismatch);
         }
 
         GraphicsPipeline::new_inner(device, params)
     }
 
     /// Builds a new graphics pipeline object with tessellation shaders.
+    ///
+    /// This constructor is kept for compatibility and will be removed in the future. Use
+    /// `GraphicsPipeline::start()` instead.
     #[inline]
+    #[deprecated(note = "use GraphicsPipeline::start() instead")]
     pub fn with_tessellation<'a, Vsp, Vi, Vo, Vl, Tcs, Tci, Tco, Tcl, Tes, Tei, Teo, Tel, Fs, Fi,
                             Fo, Fl>
               (device: &Arc<Device>,
@@ -186,14 +726,19 @@ impl<Vdef, L, Rp> GraphicsPipeline<Vdef, L, Rp>
               Fi: ShaderInterfaceDefMatch<Teo> + ShaderInterfaceDefMatch<Vo>,
               Fo: ShaderInterfaceDef,
               Rp: RenderPassSubpassInterface<Fo>,
+              Vsp: SpecializationConstants, Tcs: SpecializationConstants,
+              Tes: SpecializationConstants, Fs: SpecializationConstants,
     {
-        // TODO: return proper errors
-        if let Some(ref tess) = params.tessellation {
-            assert!(tess.tessellation_control_shader.input().matches(params.vertex_shader.output()));
-            assert!(tess.tessellation_evaluation_shader.input().matches(tess.tessellation_control_shader.output()));
-            assert!(params.fragment_shader.input().matches(tess.tessellation_evaluation_shader.output()));
-        } else {
-            assert!(params.fragment_shader.input().matches(params.vertex_shader.output()));
+        let stages_match = match params.tessellation {
+            Some(ref tess) => {
+                tess_stages_match(&params.vertex_shader, tess) &&
+                params.fragment_shader.input().matches(tess.tessellation_evaluation_shader.output())
+            },
+            None => params.fragment_shader.input().matches(params.vertex_shader.output()),
+        };
+
+        if !stages_match {
+            return Err(GraphicsPipelineCreationError::ShaderStagesMismatch);
         }
 
         GraphicsPipeline::new_inner(device, params)
@@ -217,9 +762,45 @@ impl<Vdef, L, Rp> GraphicsPipeline<Vdef, L, Rp>
               Tcl: PipelineLayoutDesc,
               Tel: PipelineLayoutDesc,
               Rp: RenderPassSubpassInterface<Fo>,
+              Vsp: SpecializationConstants, Tcs: SpecializationConstants,
+              Tes: SpecializationConstants, Gsp: SpecializationConstants,
+              Fs: SpecializationConstants,
     {
         let vk = device.pointers();
 
+        if let Some(cache) = params.cache {
+            assert_eq!(&**cache.device() as *const Device, &**device as *const Device);
+        }
+
+        let base = match params.derivative {
+            PipelineDerivative::DerivativeOf(base) => {
+                assert_eq!(&**base.device() as *const Device, &**device as *const Device);
+                assert!(!base.is_compute(), "A graphics pipeline can only derive from another \
+                                             graphics pipeline");
+                if !base.allows_derivatives() {
+                    return Err(GraphicsPipelineCreationError::BasePipelineDoesntAllowDerivatives);
+                }
+                Some(base.keep_alive())
+            },
+            _ => None
+        };
+
+        // Checking that the entry points exist in their modules.
+        try!(params.vertex_shader.module().check_entry_point(params.vertex_shader.name(),
+                                                             ExecutionModel::Vertex));
+        try!(params.fragment_shader.module().check_entry_point(params.fragment_shader.name(),
+                                                               ExecutionModel::Fragment));
+        if let Some(ref gs) = params.geometry_shader {
+            try!(gs.module().check_entry_point(gs.name(), ExecutionModel::Geometry));
+        }
+        if let Some(ref tess) = params.tessellation {
+            let tcs = &tess.tessellation_control_shader;
+            try!(tcs.module().check_entry_point(tcs.name(), ExecutionModel::TessellationControl));
+            let tes = &tess.tessellation_evaluation_shader;
+            try!(tes.module().check_entry_point(tes.name(),
+                                                ExecutionModel::TessellationEvaluation));
+        }
+
         // Checking that the pipeline layout matches the shader stages.
         // TODO: more details in the errors
         if !PipelineLayoutSuperset::is_superset_of(&**params.layout,
@@ -259,9 +840,43 @@ impl<Vdef, L, Rp> GraphicsPipeline<Vdef, L, Rp>
             return Err(GraphicsPipelineCreationError::FragmentShaderRenderPassIncompatible);
         }
 
+        // Check that the input attachments read by the fragment shader exist in the subpass.
+        {
+            let num_input_attachments = params.render_pass.num_input_attachments();
+            let fs_layout = params.fragment_shader.layout();
+            for (set, descriptors) in fs_layout.descriptors_desc().enumerate() {
+                for desc in descriptors {
+                    let index = match desc.ty {
+                        DescriptorDescTy::InputAttachment { index: Some(index), .. } => index,
+                        _ => continue,
+                    };
+
+                    if index >= num_input_attachments {
+                        return Err(GraphicsPipelineCreationError::MissingInputAttachment {
+                            set: set as u32,
+                            binding: desc.binding,
+                            index: index,
+                        });
+                    }
+                }
+            }
+        }
+
         // Will contain the list of dynamic states. Filled throughout this function.
         let mut dynamic_states: SmallVec<[vk::DynamicState; 8]> = SmallVec::new();
 
+        // Specialization constants of each stage. They must outlive `stages`, which points to them.
+        let vs_spec = params.vertex_shader.specialization().map(|s| specialization_info(s));
+        let fs_spec = params.fragment_shader.specialization().map(|s| specialization_info(s));
+        let gs_spec = params.geometry_shader.as_ref().and_then(|gs| gs.specialization())
+                                            .map(|s| specialization_info(s));
+        let tcs_spec = params.tessellation.as_ref()
+                             .and_then(|t| t.tessellation_control_shader.specialization())
+                             .map(|s| specialization_info(s));
+        let tes_spec = params.tessellation.as_ref()
+                             .and_then(|t| t.tessellation_evaluation_shader.specialization())
+                             .map(|s| specialization_info(s));
+
         // List of shader stages.
         let stages = {
             let mut stages = SmallVec::<[_; 5]>::new();
@@ -273,7 +888,8 @@ impl<Vdef, L, Rp> GraphicsPipeline<Vdef, L, Rp>
                 stage: vk::SHADER_STAGE_VERTEX_BIT,
                 module: params.vertex_shader.module().internal_object(),
                 pName: params.vertex_shader.name().as_ptr(),
-                pSpecializationInfo: ptr::null(),       // TODO:
+                pSpecializationInfo: vs_spec.as_ref().map(|s| s as *const _)
+                                            .unwrap_or(ptr::null()),
             });
 
             stages.push(vk::PipelineShaderStageCreateInfo {
@@ -283,7 +899,8 @@ impl<Vdef, L, Rp> GraphicsPipeline<Vdef, L, Rp>
                 stage: vk::SHADER_STAGE_FRAGMENT_BIT,
                 module: params.fragment_shader.module().internal_object(),
                 pName: params.fragment_shader.name().as_ptr(),
-                pSpecializationInfo: ptr::null(),       // TODO:
+                pSpecializationInfo: fs_spec.as_ref().map(|s| s as *const _)
+                                            .unwrap_or(ptr::null()),
             });
 
             if let Some(ref gs) = params.geometry_shader {
@@ -298,7 +915,8 @@ impl<Vdef, L, Rp> GraphicsPipeline<Vdef, L, Rp>
                     stage: vk::SHADER_STAGE_GEOMETRY_BIT,
                     module: gs.module().internal_object(),
                     pName: gs.name().as_ptr(),
-                    pSpecializationInfo: ptr::null(),       // TODO:
+                    pSpecializationInfo: gs_spec.as_ref().map(|s| s as *const _)
+                                                .unwrap_or(ptr::null()),
                 });
             }
 
@@ -316,7 +934,8 @@ impl<Vdef, L, Rp> GraphicsPipeline<Vdef, L, Rp>
                     stage: vk::SHADER_STAGE_TESSELLATION_CONTROL_BIT,
                     module: tess.tessellation_control_shader.module().internal_object(),
                     pName: tess.tessellation_control_shader.name().as_ptr(),
-                    pSpecializationInfo: ptr::null(),       // TODO:
+                    pSpecializationInfo: tcs_spec.as_ref().map(|s| s as *const _)
+                                                 .unwrap_or(ptr::null()),
                 });
 
                 stages.push(vk::PipelineShaderStageCreateInfo {
@@ -326,7 +945,8 @@ impl<Vdef, L, Rp> GraphicsPipeline<Vdef, L, Rp>
                     stage: vk::SHADER_STAGE_TESSELLATION_EVALUATION_BIT,
                     module: tess.tessellation_evaluation_shader.module().internal_object(),
                     pName: tess.tessellation_evaluation_shader.name().as_ptr(),
-                    pSpecializationInfo: ptr::null(),       // TODO:
+                    pSpecializationInfo: tes_spec.as_ref().map(|s| s as *const _)
+                                                 .unwrap_or(ptr::null()),
                 });
             }
 
@@ -335,7 +955,7 @@ impl<Vdef, L, Rp> GraphicsPipeline<Vdef, L, Rp>
 
         // Vertex bindings.
         let (binding_descriptions, attribute_descriptions) = {
-            let (buffers_iter, attribs_iter) = params.vertex_input.definition(params.vertex_shader.input_definition());
+            let (buffers_iter, attribs_iter) = try!(params.vertex_input.definition(params.vertex_shader.input_definition()));
 
             let mut binding_descriptions = SmallVec::<[_; 8]>::new();
             for (num, stride, rate) in buffers_iter {
@@ -414,6 +1034,12 @@ impl<Vdef, L, Rp> GraphicsPipeline<Vdef, L, Rp>
             });
         }
 
+        if params.input_assembly.topology.has_adjacency() &&
+           !device.enabled_features().geometry_shader
+        {
+            return Err(GraphicsPipelineCreationError::GeometryShaderFeatureNotEnabled);
+        }
+
         // TODO: should check from the tess eval shader instead of the input assembly
         if let Some(ref gs) = params.geometry_shader {
             if !gs.primitives().matches(params.input_assembly.topology) {
@@ -438,6 +1064,9 @@ impl<Vdef, L, Rp> GraphicsPipeline<Vdef, L, Rp>
                 if params.tessellation.is_none() {
                     return Err(GraphicsPipelineCreationError::InvalidPrimitiveTopology);
                 }
+                if vertices_per_patch == 0 {
+                    return Err(GraphicsPipelineCreationError::EmptyTessellationPatch);
+                }
                 if vertices_per_patch > device.physical_device().limits()
                                               .max_tessellation_patch_size()
                 {
@@ -487,6 +1116,10 @@ impl<Vdef, L, Rp> GraphicsPipeline<Vdef, L, Rp>
             },
         };
 
+        if vp_num == 0 {
+            return Err(GraphicsPipelineCreationError::NoViewport);
+        }
+
         if vp_num > 1 && !device.enabled_features().multi_viewport {
             return Err(GraphicsPipelineCreationError::MultiViewportFeatureNotEnabled);
         }
@@ -526,7 +1159,17 @@ impl<Vdef, L, Rp> GraphicsPipeline<Vdef, L, Rp>
 
         if let Some(line_width) = params.raster.line_width {
             if line_width != 1.0 && !device.enabled_features().wide_lines {
-                return Err(GraphicsPipelineCreationError::WideLinesFeatureNotEnabled);
+                return Err(GraphicsPipelineCreationError::WideLinesFeatureNotEnabled {
+                    line_width: line_width,
+                });
+            }
+
+            let range = device.physical_device().limits().line_width_range();
+            if !(line_width >= range[0] && line_width <= range[1]) {
+                return Err(GraphicsPipelineCreationError::LineWidthOutOfRange {
+                    line_width: line_width,
+                    range: range,
+                });
             }
         } else {
             dynamic_states.push(vk::DYNAMIC_STATE_LINE_WIDTH);
@@ -542,7 +1185,9 @@ impl<Vdef, L, Rp> GraphicsPipeline<Vdef, L, Rp>
             },
             DepthBiasControl::Static(bias) => {
                 if bias.clamp != 0.0 && !device.enabled_features().depth_bias_clamp {
-                    return Err(GraphicsPipelineCreationError::DepthBiasClampFeatureNotEnabled);
+                    return Err(GraphicsPipelineCreationError::DepthBiasClampFeatureNotEnabled {
+                        clamp: bias.clamp,
+                    });
                 }
 
                 (vk::TRUE, bias.constant_factor, bias.clamp, bias.slope_factor)
@@ -556,7 +1201,9 @@ impl<Vdef, L, Rp> GraphicsPipeline<Vdef, L, Rp>
         if params.raster.polygon_mode != PolygonMode::Fill &&
            !device.enabled_features().fill_mode_non_solid
         {
-            return Err(GraphicsPipelineCreationError::FillModeNonSolidFeatureNotEnabled);
+            return Err(GraphicsPipelineCreationError::FillModeNonSolidFeatureNotEnabled {
+                polygon_mode: params.raster.polygon_mode,
+            });
         }
 
         let rasterization = vk::PipelineRasterizationStateCreateInfo {
@@ -576,8 +1223,23 @@ impl<Vdef, L, Rp> GraphicsPipeline<Vdef, L, Rp>
         };
 
         assert!(params.multisample.rasterization_samples >= 1);
-        // FIXME: check that rasterization_samples is equal to what's in the renderpass
-        if let Some(s) = params.multisample.sample_shading { assert!(s >= 0.0 && s <= 1.0); }
+        if let Some(s) = params.multisample.sample_shading {
+            assert!(s >= 0.0 && s <= 1.0);
+            if !device.enabled_features().sample_rate_shading {
+                return Err(GraphicsPipelineCreationError::SampleRateShadingFeatureNotEnabled);
+            }
+        }
+        if params.multisample.alpha_to_one && !device.enabled_features().alpha_to_one {
+            return Err(GraphicsPipelineCreationError::AlphaToOneFeatureNotEnabled);
+        }
+        if let Some(expected) = params.render_pass.num_samples() {
+            if params.multisample.rasterization_samples != expected {
+                return Err(GraphicsPipelineCreationError::RasterizationSamplesMismatch {
+                    expected: expected,
+                    obtained: params.multisample.rasterization_samples,
+                });
+            }
+        }
         let multisample = vk::PipelineMultisampleStateCreateInfo {
             sType: vk::STRUCTURE_TYPE_PIPELINE_MULTISAMPLE_STATE_CREATE_INFO,
             pNext: ptr::null(),
@@ -585,7 +1247,7 @@ impl<Vdef, L, Rp> GraphicsPipeline<Vdef, L, Rp>
             rasterizationSamples: params.multisample.rasterization_samples,
             sampleShadingEnable: if params.multisample.sample_shading.is_some() { vk::TRUE } else { vk::FALSE },
             minSampleShading: params.multisample.sample_shading.unwrap_or(1.0),
-            pSampleMask: ptr::null(),   //params.multisample.sample_mask.as_ptr(),     // FIXME:
+            pSampleMask: params.multisample.sample_mask.as_ptr(),
             alphaToCoverageEnable: if params.multisample.alpha_to_coverage { vk::TRUE } else { vk::FALSE },
             alphaToOneEnable: if params.multisample.alpha_to_one { vk::TRUE } else { vk::FALSE },
         };
@@ -651,6 +1313,12 @@ impl<Vdef, L, Rp> GraphicsPipeline<Vdef, L, Rp>
                 return Err(GraphicsPipelineCreationError::NoDepthAttachment);
             }
 
+            if params.depth_stencil.depth_bounds_test != DepthBounds::Disabled &&
+               !params.render_pass.has_depth()
+            {
+                return Err(GraphicsPipelineCreationError::NoDepthAttachment);
+            }
+
             if (!params.depth_stencil.stencil_front.always_keep() ||
                 !params.depth_stencil.stencil_back.always_keep()) &&
                 !params.render_pass.has_stencil()
@@ -709,7 +1377,9 @@ impl<Vdef, L, Rp> GraphicsPipeline<Vdef, L, Rp>
                         return Err(GraphicsPipelineCreationError::MismatchBlendingAttachmentsCount);
                     }
 
-                    if !device.enabled_features().independent_blend {
+                    // Identical states don't require the `independent_blend` feature.
+                    let all_equal = blend.windows(2).all(|w| w[0] == w[1]);
+                    if !all_equal && !device.enabled_features().independent_blend {
                         return Err(GraphicsPipelineCreationError::IndependentBlendFeatureNotEnabled);
                     }
 
@@ -753,7 +1423,7 @@ impl<Vdef, L, Rp> GraphicsPipeline<Vdef, L, Rp>
             let infos = vk::GraphicsPipelineCreateInfo {
                 sType: vk::STRUCTURE_TYPE_GRAPHICS_PIPELINE_CREATE_INFO,
                 pNext: ptr::null(),
-                flags: 0,       // TODO: some flags are available but none are critical
+                flags: params.derivative.flags(),
                 stageCount: stages.len() as u32,
                 pStages: stages.as_ptr(),
                 pVertexInputState: &vertex_input_state,
@@ -769,12 +1439,15 @@ impl<Vdef, L, Rp> GraphicsPipeline<Vdef, L, Rp>
                 layout: PipelineLayout::inner_pipeline_layout(&**params.layout).internal_object(),
                 renderPass: params.render_pass.render_pass().render_pass().internal_object(),
                 subpass: params.render_pass.index(),
-                basePipelineHandle: 0,    // TODO:
-                basePipelineIndex: -1,       // TODO:
+                basePipelineHandle: params.derivative.base_handle(),
+                basePipelineIndex: -1,
             };
 
+            let _cache_guard = params.cache.map(|c| c.read_lock());
+            let cache = params.cache.map(|c| c.internal_object()).unwrap_or(0);
+
             let mut output = mem::uninitialized();
-            try!(check_errors(vk.CreateGraphicsPipelines(device.internal_object(), 0,
+            try!(check_errors(vk.CreateGraphicsPipelines(device.internal_object(), cache,
                                                          1, &infos, ptr::null(), &mut output)));
             output
         };
@@ -789,17 +1462,24 @@ impl<Vdef, L, Rp> GraphicsPipeline<Vdef, L, Rp>
             render_pass: params.render_pass.render_pass().clone(),
             render_pass_subpass: params.render_pass.index(),
 
-            dynamic_line_width: params.raster.line_width.is_none(),
-            dynamic_viewport: params.viewport.dynamic_viewports(),
-            dynamic_scissor: params.viewport.dynamic_scissors(),
-            dynamic_depth_bias: params.raster.depth_bias.is_dynamic(),
-            dynamic_depth_bounds: params.depth_stencil.depth_bounds_test.is_dynamic(),
-            dynamic_stencil_compare_mask: params.depth_stencil.stencil_back.compare_mask.is_none(),
-            dynamic_stencil_write_mask: params.depth_stencil.stencil_back.write_mask.is_none(),
-            dynamic_stencil_reference: params.depth_stencil.stencil_back.reference.is_none(),
-            dynamic_blend_constants: params.blend.blend_constants.is_none(),
+            dynamic_states: DynamicStateFlags {
+                line_width: params.raster.line_width.is_none(),
+                viewports: params.viewport.dynamic_viewports(),
+                scissors: params.viewport.dynamic_scissors(),
+                depth_bias: params.raster.depth_bias.is_dynamic(),
+                blend_constants: params.blend.blend_constants.is_none(),
+                depth_bounds: params.depth_stencil.depth_bounds_test.is_dynamic(),
+                stencil_compare_mask: params.depth_stencil.stencil_back.compare_mask.is_none(),
+                stencil_write_mask: params.depth_stencil.stencil_back.write_mask.is_none(),
+                stencil_reference: params.depth_stencil.stencil_back.reference.is_none(),
+            },
 
             num_viewports: params.viewport.num_viewports(),
+
+            has_geometry_shader: params.geometry_shader.is_some(),
+
+            allows_derivatives: params.derivative.allows_derivatives(),
+            base: base,
         }))
     }
 }
@@ -839,10 +1519,28 @@ impl<Mv, L, Rp> GraphicsPipeline<Mv, L, Rp>
 }
 
 impl<Mv, L, Rp> GraphicsPipeline<Mv, L, Rp> {
+    /// Returns true if other pipelines can be created as derivatives of this pipeline.
+    #[inline]
+    pub fn allows_derivatives(&self) -> bool {
+        self.allows_derivatives
+    }
+
+    /// Returns true if this pipeline was created as a derivative of another pipeline.
+    #[inline]
+    pub fn is_derivative(&self) -> bool {
+        self.base.is_some()
+    }
+
+    /// Returns which parts of the state of this pipeline are dynamic.
+    #[inline]
+    pub fn dynamic_states(&self) -> &DynamicStateFlags {
+        &self.dynamic_states
+    }
+
     /// Returns true if the line width used by this pipeline is dynamic.
     #[inline]
     pub fn has_dynamic_line_width(&self) -> bool {
-        self.dynamic_line_width
+        self.dynamic_states.line_width
     }
 
     /// Returns the number of viewports and scissors of this pipeline.
@@ -851,43 +1549,92 @@ impl<Mv, L, Rp> GraphicsPipeline<Mv, L, Rp> {
         self.num_viewports
     }
 
+    /// Returns true if this pipeline has a geometry shader stage.
+    #[inline]
+    pub fn has_geometry_shader(&self) -> bool {
+        self.has_geometry_shader
+    }
+
     /// Returns true if the viewports used by this pipeline are dynamic.
     #[inline]
     pub fn has_dynamic_viewports(&self) -> bool {
-        self.dynamic_viewport
+        self.dynamic_states.viewports
     }
 
     /// Returns true if the scissors used by this pipeline are dynamic.
     #[inline]
     pub fn has_dynamic_scissors(&self) -> bool {
-        self.dynamic_scissor
+        self.dynamic_states.scissors
+    }
+
+    /// Returns true if the depth bias used by this pipeline is dynamic.
+    #[inline]
+    pub fn has_dynamic_depth_bias(&self) -> bool {
+        self.dynamic_states.depth_bias
+    }
+
+    /// Returns true if the blend constants used by this pipeline are dynamic.
+    #[inline]
+    pub fn has_dynamic_blend_constants(&self) -> bool {
+        self.dynamic_states.blend_constants
     }
 
     /// Returns true if the depth bounds used by this pipeline are dynamic.
     #[inline]
     pub fn has_dynamic_depth_bounds(&self) -> bool {
-        self.dynamic_depth_bounds
+        self.dynamic_states.depth_bounds
     }
 
     /// Returns true if the stencil compare masks used by this pipeline are dynamic.
     #[inline]
     pub fn has_dynamic_stencil_compare_mask(&self) -> bool {
-        self.dynamic_stencil_compare_mask
+        self.dynamic_states.stencil_compare_mask
     }
 
     /// Returns true if the stencil write masks used by this pipeline are dynamic.
     #[inline]
     pub fn has_dynamic_stencil_write_mask(&self) -> bool {
-        self.dynamic_stencil_write_mask
+        self.dynamic_states.stencil_write_mask
     }
 
     /// Returns true if the stencil references used by this pipeline are dynamic.
     #[inline]
     pub fn has_dynamic_stencil_reference(&self) -> bool {
-        self.dynamic_stencil_reference
+        self.dynamic_states.stencil_reference
+    }
+}
+
+unsafe impl<Mv, L, Rp> BasePipeline for Arc<GraphicsPipeline<Mv, L, Rp>>
+    where Mv: Send + Sync + 'static, L: Send + Sync + 'static, Rp: Send + Sync + 'static
+{
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+
+    #[inline]
+    fn allows_derivatives(&self) -> bool {
+        self.allows_derivatives
+    }
+
+    #[inline]
+    fn is_compute(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn inner_pipeline(&self) -> vk::Pipeline {
+        self.pipeline
+    }
+
+    #[inline]
+    fn keep_alive(&self) -> Arc<Any + Send + Sync> {
+        self.clone() as Arc<_>
     }
 }
 
+impl_debug_handle!(GraphicsPipeline<Mv, L, Rp>);
+
 unsafe impl<Mv, L, Rp> VulkanObject for GraphicsPipeline<Mv, L, Rp> {
     type Object = vk::Pipeline;
 
@@ -908,7 +1655,7 @@ impl<Mv, L, Rp> Drop for GraphicsPipeline<Mv, L, Rp> {
 }
 
 /// Error that can happen when creating a graphics pipeline.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum GraphicsPipelineCreationError {
     /// Not enough memory.
     OomError(OomError),
@@ -916,10 +1663,26 @@ pub enum GraphicsPipelineCreationError {
     /// The pipeline layout is not compatible with what the shaders expect.
     IncompatiblePipelineLayout,
 
+    /// The vertex definition is not compatible with the input of the vertex shader.
+    IncompatibleVertexDefinition(IncompatibleVertexDefinitionError),
+
+    /// The entry point of one of the shader stages doesn't exist in its module.
+    InvalidEntryPoint(EntryPointError),
+
     /// The output of the fragment shader is not compatible with what the render pass subpass
     /// expects.
     FragmentShaderRenderPassIncompatible,
 
+    /// The fragment shader reads from an input attachment that doesn't exist in the subpass.
+    MissingInputAttachment {
+        /// Descriptor set of the input attachment in the shader.
+        set: u32,
+        /// Binding of the input attachment in the shader.
+        binding: u32,
+        /// Index of the input attachment requested by the shader.
+        index: u32,
+    },
+
     /// One of the vertex attributes requested by the vertex shader is missing from the vertex
     /// input.
     MissingVertexAttribute {
@@ -980,6 +1743,25 @@ pub enum GraphicsPipelineCreationError {
         obtained: u32
     },
 
+    /// The pipeline must have a vertex shader.
+    NoVertexShader,
+
+    /// The pipeline must have a fragment shader.
+    NoFragmentShader,
+
+    /// The pipeline layout wasn't specified.
+    NoPipelineLayout,
+
+    /// The render pass subpass the pipeline is going to be used in wasn't specified.
+    NoRenderPass,
+
+    /// The pipeline must have at least one viewport and scissor box.
+    NoViewport,
+
+    /// The output interface of a shader stage doesn't match the input interface of the next
+    /// stage.
+    ShaderStagesMismatch,
+
     /// The maximum dimensions of viewports has been exceeded.
     MaxViewportDimensionsExceeded,
 
@@ -987,18 +1769,35 @@ pub enum GraphicsPipelineCreationError {
     ViewportBoundsExceeded,
 
     /// The `wide_lines` feature must be enabled in order to use a line width superior to 1.0.
-    WideLinesFeatureNotEnabled,
+    WideLinesFeatureNotEnabled {
+        /// Line width that was passed.
+        line_width: f32,
+    },
+
+    /// The line width is outside of the range supported by the device.
+    LineWidthOutOfRange {
+        /// Line width that was passed.
+        line_width: f32,
+        /// Minimum and maximum line widths supported by the device.
+        range: [f32; 2],
+    },
 
     /// The `depth_clamp` feature must be enabled in order to use depth clamping.
     DepthClampFeatureNotEnabled,
 
     /// The `depth_bias_clamp` feature must be enabled in order to use a depth bias clamp different
     /// from 0.0.
-    DepthBiasClampFeatureNotEnabled,
+    DepthBiasClampFeatureNotEnabled {
+        /// Depth bias clamp that was passed.
+        clamp: f32,
+    },
 
     /// The `fill_mode_non_solid` feature must be enabled in order to use a polygon mode different
     /// from `Fill`.
-    FillModeNonSolidFeatureNotEnabled,
+    FillModeNonSolidFeatureNotEnabled {
+        /// Polygon mode that was passed.
+        polygon_mode: PolygonMode,
+    },
 
     /// The `depth_bounds` feature must be enabled in order to use depth bounds testing.
     DepthBoundsFeatureNotEnabled,
@@ -1009,7 +1808,8 @@ pub enum GraphicsPipelineCreationError {
     /// The primitives topology does not match what the geometry shader expects.
     TopologyNotMatchingGeometryShader,
 
-    /// The `geometry_shader` feature must be enabled in order to use geometry shaders.
+    /// The `geometry_shader` feature must be enabled in order to use geometry shaders or
+    /// primitive topologies with adjacency.
     GeometryShaderFeatureNotEnabled,
 
     /// The `tessellation_shader` feature must be enabled in order to use tessellation shaders.
@@ -1040,8 +1840,36 @@ pub enum GraphicsPipelineCreationError {
 
     /// The `maxTessellationPatchSize` limit was exceeded.
     MaxTessellationPatchSizeExceeded,
+
+    /// A patch list was requested with 0 vertices per patch.
+    EmptyTessellationPatch,
+
+    /// The `sample_rate_shading` feature must be enabled in order to use sample shading.
+    SampleRateShadingFeatureNotEnabled,
+
+    /// The `alpha_to_one` feature must be enabled in order to use alpha-to-one.
+    AlphaToOneFeatureNotEnabled,
+
+    /// The number of rasterization samples doesn't match the number of samples of the
+    /// attachments of the subpass.
+    RasterizationSamplesMismatch {
+        /// Number of samples of the attachments of the subpass.
+        expected: u32,
+        /// Number of rasterization samples that was passed.
+        obtained: u32,
+    },
+
+    /// The pipeline was created as a derivative of a pipeline that doesn't allow derivatives.
+    BasePipelineDoesntAllowDerivatives,
+
+    /// The Vulkan implementation returned an error that this operation isn't supposed to return.
+    Unexpected(Error),
 }
 
+// Implemented by hand because of the `f32` values carried by some variants. The only values that
+// break `Eq` are NaNs, and an error that carries one is not equal to itself.
+impl Eq for GraphicsPipelineCreationError {}
+
 impl error::Error for GraphicsPipelineCreationError {
     #[inline]
     // TODO: finish
@@ -1051,10 +1879,20 @@ impl error::Error for GraphicsPipelineCreationError {
             GraphicsPipelineCreationError::IncompatiblePipelineLayout => {
                 "the pipeline layout is not compatible with what the shaders expect"
             },
+            GraphicsPipelineCreationError::IncompatibleVertexDefinition(_) => {
+                "the vertex definition is not compatible with the input of the vertex shader"
+            },
+            GraphicsPipelineCreationError::InvalidEntryPoint(_) => {
+                "the entry point of one of the shader stages doesn't exist in its module"
+            },
             GraphicsPipelineCreationError::FragmentShaderRenderPassIncompatible => {
                 "the output of the fragment shader is not compatible with what the render pass \
                  subpass expects"
             },
+            GraphicsPipelineCreationError::MissingInputAttachment { .. } => {
+                "the fragment shader reads from an input attachment that doesn't exist in the \
+                 subpass"
+            },
             GraphicsPipelineCreationError::MissingVertexAttribute { .. } => {
                 "one of the vertex attributes requested by the vertex shader is missing from the \
                  vertex input"
@@ -1083,24 +1921,46 @@ impl error::Error for GraphicsPipelineCreationError {
             GraphicsPipelineCreationError::MaxViewportsExceeded { .. } => {
                 "the maximum number of viewports has been exceeded"
             },
+            GraphicsPipelineCreationError::NoVertexShader => {
+                "the pipeline must have a vertex shader"
+            },
+            GraphicsPipelineCreationError::NoFragmentShader => {
+                "the pipeline must have a fragment shader"
+            },
+            GraphicsPipelineCreationError::NoPipelineLayout => {
+                "the pipeline layout wasn't specified"
+            },
+            GraphicsPipelineCreationError::NoRenderPass => {
+                "the render pass subpass wasn't specified"
+            },
+            GraphicsPipelineCreationError::NoViewport => {
+                "the pipeline must have at least one viewport and scissor box"
+            },
+            GraphicsPipelineCreationError::ShaderStagesMismatch => {
+                "the output interface of a shader stage doesn't match the input interface of the \
+                 next stage"
+            },
             GraphicsPipelineCreationError::MaxViewportDimensionsExceeded => {
                 "the maximum dimensions of viewports has been exceeded"
             },
             GraphicsPipelineCreationError::ViewportBoundsExceeded => {
                 "the minimum or maximum bounds of viewports have been exceeded"
             },
-            GraphicsPipelineCreationError::WideLinesFeatureNotEnabled => {
+            GraphicsPipelineCreationError::WideLinesFeatureNotEnabled { .. } => {
                 "the `wide_lines` feature must be enabled in order to use a line width \
                  superior to 1.0"
             },
+            GraphicsPipelineCreationError::LineWidthOutOfRange { .. } => {
+                "the line width is outside of the range supported by the device"
+            },
             GraphicsPipelineCreationError::DepthClampFeatureNotEnabled => {
                 "the `depth_clamp` feature must be enabled in order to use depth clamping"
             },
-            GraphicsPipelineCreationError::DepthBiasClampFeatureNotEnabled => {
+            GraphicsPipelineCreationError::DepthBiasClampFeatureNotEnabled { .. } => {
                 "the `depth_bias_clamp` feature must be enabled in order to use a depth bias \
                  clamp different from 0.0."
             },
-            GraphicsPipelineCreationError::FillModeNonSolidFeatureNotEnabled => {
+            GraphicsPipelineCreationError::FillModeNonSolidFeatureNotEnabled { .. } => {
                 "the `fill_mode_non_solid` feature must be enabled in order to use a polygon mode \
                  different from `Fill`"
             },
@@ -1114,7 +1974,8 @@ impl error::Error for GraphicsPipelineCreationError {
                 "the primitives topology does not match what the geometry shader expects"
             },
             GraphicsPipelineCreationError::GeometryShaderFeatureNotEnabled => {
-                "the `geometry_shader` feature must be enabled in order to use geometry shaders"
+                "the `geometry_shader` feature must be enabled in order to use geometry shaders \
+                 or primitive topologies with adjacency"
             },
             GraphicsPipelineCreationError::TessellationShaderFeatureNotEnabled => {
                 "the `tessellation_shader` feature must be enabled in order to use tessellation \
@@ -1144,13 +2005,36 @@ impl error::Error for GraphicsPipelineCreationError {
             GraphicsPipelineCreationError::MaxTessellationPatchSizeExceeded => {
                 "the maximum tessellation patch size was exceeded"
             },
-        }
-    }
-
+            GraphicsPipelineCreationError::EmptyTessellationPatch => {
+                "a patch list was requested with 0 vertices per patch"
+            },
+            GraphicsPipelineCreationError::SampleRateShadingFeatureNotEnabled => {
+                "the `sample_rate_shading` feature must be enabled in order to use sample shading"
+            },
+            GraphicsPipelineCreationError::AlphaToOneFeatureNotEnabled => {
+                "the `alpha_to_one` feature must be enabled in order to use alpha-to-one"
+            },
+            GraphicsPipelineCreationError::RasterizationSamplesMismatch { .. } => {
+                "the number of rasterization samples doesn't match the number of samples of the \
+                 attachments of the subpass"
+            },
+            GraphicsPipelineCreationError::BasePipelineDoesntAllowDerivatives => {
+                "the pipeline was created as a derivative of a pipeline that doesn't allow \
+                 derivatives"
+            },
+            GraphicsPipelineCreationError::Unexpected(_) => {
+                "unexpected error returned by the Vulkan implementation"
+            },
+        }
+    }
+
     #[inline]
     fn cause(&self) -> Option<&error::Error> {
         match *self {
             GraphicsPipelineCreationError::OomError(ref err) => Some(err),
+            GraphicsPipelineCreationError::IncompatibleVertexDefinition(ref err) => Some(err),
+            GraphicsPipelineCreationError::InvalidEntryPoint(ref err) => Some(err),
+            GraphicsPipelineCreationError::Unexpected(ref err) => Some(err),
             _ => None
         }
     }
@@ -1170,6 +2054,20 @@ impl From<OomError> for GraphicsPipelineCreationError {
     }
 }
 
+impl From<EntryPointError> for GraphicsPipelineCreationError {
+    #[inline]
+    fn from(err: EntryPointError) -> GraphicsPipelineCreationError {
+        GraphicsPipelineCreationError::InvalidEntryPoint(err)
+    }
+}
+
+impl From<IncompatibleVertexDefinitionError> for GraphicsPipelineCreationError {
+    #[inline]
+    fn from(err: IncompatibleVertexDefinitionError) -> GraphicsPipelineCreationError {
+        GraphicsPipelineCreationError::IncompatibleVertexDefinition(err)
+    }
+}
+
 impl From<Error> for GraphicsPipelineCreationError {
     #[inline]
     fn from(err: Error) -> GraphicsPipelineCreationError {
@@ -1180,34 +2078,65 @@ impl From<Error> for GraphicsPipelineCreationError {
             err @ Error::OutOfDeviceMemory => {
                 GraphicsPipelineCreationError::OomError(OomError::from(err))
             },
-            _ => panic!("unexpected error: {:?}", err)
+            _ => GraphicsPipelineCreationError::Unexpected(err)
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::borrow::Cow;
     use std::ffi::CString;
+    use std::sync::Arc;
+    use std::vec::IntoIter as VecIntoIter;
+    use device::Device;
     use format::Format;
+    use framebuffer::RenderPass;
+    use framebuffer::RenderPassDesc;
+    use framebuffer::RenderPassSubpassInterface;
     use framebuffer::Subpass;
     use descriptor::pipeline_layout::EmptyPipeline;
     use descriptor::pipeline_layout::EmptyPipelineDesc;
     use pipeline::GraphicsPipeline;
     use pipeline::GraphicsPipelineParams;
     use pipeline::GraphicsPipelineCreationError;
+    use pipeline::blend::AttachmentBlend;
+    use pipeline::blend::AttachmentsBlend;
     use pipeline::blend::Blend;
+    use pipeline::blend::LogicOp;
+    use pipeline::depth_stencil::Compare;
+    use pipeline::depth_stencil::DepthBounds;
     use pipeline::depth_stencil::DepthStencil;
+    use pipeline::depth_stencil::Stencil;
+    use pipeline::depth_stencil::StencilOp;
+    use pipeline::derivative::PipelineDerivative;
     use pipeline::input_assembly::InputAssembly;
     use pipeline::input_assembly::PrimitiveTopology;
     use pipeline::multisample::Multisample;
+    use pipeline::raster::CullMode;
+    use pipeline::raster::DepthBias;
+    use pipeline::raster::DepthBiasControl;
+    use pipeline::raster::FrontFace;
+    use pipeline::raster::PolygonMode;
+    use pipeline::raster::Rasterization;
+    use pipeline::shader::GeometryShaderExecutionMode;
     use pipeline::shader::ShaderModule;
     use pipeline::shader::EmptyShaderInterfaceDef;
+    use pipeline::shader::ShaderInterfaceDef;
+    use pipeline::shader::ShaderInterfaceDefEntry;
+    use pipeline::vertex::Definition;
+    use pipeline::vertex::IncompatibleVertexDefinitionError;
     use pipeline::vertex::SingleBufferDefinition;
+    use pipeline::vertex::TwoBuffersDefinition;
+    use pipeline::vertex::Vertex;
+    use pipeline::vertex::VertexMemberInfo;
+    use pipeline::vertex::VertexMemberTy;
     use pipeline::viewport::ViewportsState;
     use pipeline::viewport::Viewport;
     use pipeline::viewport::Scissor;
 
     #[test]
+    #[allow(deprecated)]
     fn create() {
         let (device, _) = gfx_dev_and_queue!();
 
@@ -1240,10 +2169,140 @@ mod tests {
             render_pass: Subpass::from(&simple_rp::CustomRenderPass::new(&device, &{
                 simple_rp::Formats { color: (Format::R8G8B8A8Unorm, 1) }
             }).unwrap(), 0).unwrap(),
+            cache: None,
+            derivative: PipelineDerivative::None,
+        }).unwrap();
+    }
+
+    #[test]
+    fn builder() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let vs = unsafe { ShaderModule::new(&device, &BASIC_VS).unwrap() };
+        let fs = unsafe { ShaderModule::new(&device, &BASIC_FS).unwrap() };
+        let name = CString::new("main").unwrap();
+        let layout = EmptyPipeline::new(&device).unwrap();
+        let render_pass = simple_rp::CustomRenderPass::new(&device, &{
+            simple_rp::Formats { color: (Format::R8G8B8A8Unorm, 1) }
+        }).unwrap();
+
+        let pipeline = GraphicsPipeline::start()
+            .vertex_input(SingleBufferDefinition::<()>::new())
+            .vertex_shader(unsafe {
+                vs.vertex_shader_entry_point::<(), _, _, _>(&name, EmptyShaderInterfaceDef,
+                                                            EmptyShaderInterfaceDef,
+                                                            EmptyPipelineDesc)
+            }, &())
+            .input_assembly(PrimitiveTopology::TriangleStrip)
+            .primitive_restart(true)
+            .viewports_dynamic_scissors_irrelevant(1)
+            .fragment_shader(unsafe {
+                fs.fragment_shader_entry_point::<(), _, _, _>(&name, EmptyShaderInterfaceDef,
+                                                              EmptyShaderInterfaceDef,
+                                                              EmptyPipelineDesc)
+            }, &())
+            .layout(&layout)
+            .render_pass(Subpass::from(&render_pass, 0).unwrap())
+            .build(&device)
+            .unwrap();
+
+        assert_eq!(pipeline.num_viewports(), 1);
+        assert!(pipeline.has_dynamic_viewports());
+        assert!(!pipeline.has_dynamic_scissors());
+    }
+
+    #[test]
+    fn builder_no_vertex_shader() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let fs = unsafe { ShaderModule::new(&device, &BASIC_FS).unwrap() };
+        let name = CString::new("main").unwrap();
+        let layout = EmptyPipeline::new(&device).unwrap();
+        let render_pass = simple_rp::CustomRenderPass::new(&device, &{
+            simple_rp::Formats { color: (Format::R8G8B8A8Unorm, 1) }
         }).unwrap();
+
+        let result = GraphicsPipeline::start()
+            .viewports_dynamic_scissors_irrelevant(1)
+            .fragment_shader(unsafe {
+                fs.fragment_shader_entry_point::<(), _, _, _>(&name, EmptyShaderInterfaceDef,
+                                                              EmptyShaderInterfaceDef,
+                                                              EmptyPipelineDesc)
+            }, &())
+            .layout(&layout)
+            .render_pass(Subpass::from(&render_pass, 0).unwrap())
+            .build(&device);
+
+        match result {
+            Err(GraphicsPipelineCreationError::NoVertexShader) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn builder_no_render_pass() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let vs = unsafe { ShaderModule::new(&device, &BASIC_VS).unwrap() };
+        let fs = unsafe { ShaderModule::new(&device, &BASIC_FS).unwrap() };
+        let name = CString::new("main").unwrap();
+
+        let result = GraphicsPipeline::start()
+            .vertex_shader(unsafe {
+                vs.vertex_shader_entry_point::<(), _, _, _>(&name, EmptyShaderInterfaceDef,
+                                                            EmptyShaderInterfaceDef,
+                                                            EmptyPipelineDesc)
+            }, &())
+            .viewports_dynamic_scissors_irrelevant(1)
+            .fragment_shader(unsafe {
+                fs.fragment_shader_entry_point::<(), _, _, _>(&name, EmptyShaderInterfaceDef,
+                                                              EmptyShaderInterfaceDef,
+                                                              EmptyPipelineDesc)
+            }, &())
+            .layout(&EmptyPipeline::new(&device).unwrap())
+            .build(&device);
+
+        match result {
+            Err(GraphicsPipelineCreationError::NoRenderPass) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn builder_shader_stages_mismatch() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let vs = unsafe { ShaderModule::new(&device, &BASIC_VS).unwrap() };
+        let fs = unsafe { ShaderModule::new(&device, &BASIC_FS).unwrap() };
+        let name = CString::new("main").unwrap();
+        let layout = EmptyPipeline::new(&device).unwrap();
+        let render_pass = simple_render_pass(&device, 1);
+
+        // The fragment shader expects inputs that the vertex shader doesn't write.
+        let result = GraphicsPipeline::start()
+            .vertex_shader(unsafe {
+                vs.vertex_shader_entry_point::<(), _, _, _>(&name, EmptyShaderInterfaceDef,
+                                                            EmptyShaderInterfaceDef,
+                                                            EmptyPipelineDesc)
+            }, &())
+            .viewports_dynamic_scissors_irrelevant(1)
+            .fragment_shader(unsafe {
+                fs.fragment_shader_entry_point::<(), _, _, _>(&name, PositionColorInput,
+                                                              EmptyShaderInterfaceDef,
+                                                              EmptyPipelineDesc)
+            }, &())
+            .layout(&layout)
+            .render_pass(Subpass::from(&render_pass, 0).unwrap())
+            .build(&device);
+
+        match result {
+            Err(GraphicsPipelineCreationError::ShaderStagesMismatch) => (),
+            _ => panic!()
+        }
     }
 
     #[test]
+    #[allow(deprecated)]
     fn bad_primitive_restart() {
         let (device, _) = gfx_dev_and_queue!();
 
@@ -1279,6 +2338,8 @@ mod tests {
             render_pass: Subpass::from(&simple_rp::CustomRenderPass::new(&device, &{
                 simple_rp::Formats { color: (Format::R8G8B8A8Unorm, 1) }
             }).unwrap(), 0).unwrap(),
+            cache: None,
+            derivative: PipelineDerivative::None,
         });
 
         match result {
@@ -1288,6 +2349,147 @@ mod tests {
     }
 
     #[test]
+    fn primitive_restart_strip() {
+        let (device, _) = gfx_dev_and_queue!();
+        let input_assembly = InputAssembly {
+            topology: PrimitiveTopology::TriangleStrip,
+            primitive_restart_enable: true,
+        };
+
+        let states = States { input_assembly: input_assembly, .. Default::default() };
+        let _ = simple_pipeline(&device, states, 1).unwrap();
+    }
+
+    #[test]
+    fn primitive_restart_list() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        for &topology in &[PrimitiveTopology::PointList, PrimitiveTopology::LineList,
+                           PrimitiveTopology::TriangleList]
+        {
+            let input_assembly = InputAssembly {
+                topology: topology,
+                primitive_restart_enable: true,
+            };
+
+            let states = States { input_assembly: input_assembly, .. Default::default() };
+            match simple_pipeline(&device, states, 1) {
+                Err(GraphicsPipelineCreationError::PrimitiveDoesntSupportPrimitiveRestart {
+                    primitive
+                }) => assert_eq!(primitive, topology),
+                _ => panic!()
+            }
+        }
+    }
+
+    #[test]
+    fn primitive_restart_patch_list() {
+        let (device, _) = gfx_dev_and_queue!();
+        let input_assembly = InputAssembly {
+            topology: PrimitiveTopology::PatchList { vertices_per_patch: 3 },
+            primitive_restart_enable: true,
+        };
+
+        let states = States { input_assembly: input_assembly, .. Default::default() };
+        match simple_pipeline(&device, states, 1) {
+            Err(GraphicsPipelineCreationError::PrimitiveDoesntSupportPrimitiveRestart { .. }) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn adjacency_geometry_shader_feature_not_enabled() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        for &topology in &[PrimitiveTopology::LineListWithAdjacency,
+                           PrimitiveTopology::LineStripWithAdjacency,
+                           PrimitiveTopology::TriangleListWithAdjacency,
+                           PrimitiveTopology::TriangleStripWithAdjacency]
+        {
+            let input_assembly = InputAssembly {
+                topology: topology,
+                primitive_restart_enable: false,
+            };
+
+            let states = States { input_assembly: input_assembly, .. Default::default() };
+            match simple_pipeline(&device, states, 1) {
+                Err(GraphicsPipelineCreationError::GeometryShaderFeatureNotEnabled) => (),
+                _ => panic!()
+            }
+        }
+    }
+
+    #[test]
+    fn adjacency_with_feature() {
+        let (device, _) = gfx_dev_and_queue!(geometry_shader);
+        let input_assembly = InputAssembly {
+            topology: PrimitiveTopology::TriangleListWithAdjacency,
+            primitive_restart_enable: false,
+        };
+
+        let states = States { input_assembly: input_assembly, .. Default::default() };
+        let _ = simple_pipeline(&device, states, 1).unwrap();
+    }
+
+    #[test]
+    fn patch_list_without_tessellation_shaders() {
+        let (device, _) = gfx_dev_and_queue!();
+        let input_assembly = InputAssembly {
+            topology: PrimitiveTopology::PatchList { vertices_per_patch: 3 },
+            primitive_restart_enable: false,
+        };
+
+        let states = States { input_assembly: input_assembly, .. Default::default() };
+        match simple_pipeline(&device, states, 1) {
+            Err(GraphicsPipelineCreationError::InvalidPrimitiveTopology) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn derivative() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let states = States {
+            derivative: PipelineDerivative::AllowDerivatives,
+            .. Default::default()
+        };
+        let base = simple_pipeline(&device, states, 1).unwrap();
+        assert!(base.allows_derivatives());
+        assert!(!base.is_derivative());
+
+        let states = States {
+            derivative: PipelineDerivative::DerivativeOf(&base),
+            .. Default::default()
+        };
+        let derivative = simple_pipeline(&device, states, 1).unwrap();
+        assert!(!derivative.allows_derivatives());
+        assert!(derivative.is_derivative());
+
+        // The derivative keeps its base alive.
+        drop(base);
+        drop(derivative);
+    }
+
+    #[test]
+    fn derivative_not_allowed() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let base = simple_pipeline(&device, Default::default(), 1).unwrap();
+        assert!(!base.allows_derivatives());
+
+        let states = States {
+            derivative: PipelineDerivative::DerivativeOf(&base),
+            .. Default::default()
+        };
+        match simple_pipeline(&device, states, 1) {
+            Err(GraphicsPipelineCreationError::BasePipelineDoesntAllowDerivatives) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
     fn multi_viewport_feature() {
         let (device, _) = gfx_dev_and_queue!();
 
@@ -1320,6 +2522,8 @@ mod tests {
             render_pass: Subpass::from(&simple_rp::CustomRenderPass::new(&device, &{
                 simple_rp::Formats { color: (Format::R8G8B8A8Unorm, 1) }
             }).unwrap(), 0).unwrap(),
+            cache: None,
+            derivative: PipelineDerivative::None,
         });
 
         match result {
@@ -1329,6 +2533,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(deprecated)]
     fn max_viewports() {
         let (device, _) = gfx_dev_and_queue!(multi_viewport);
 
@@ -1361,6 +2566,8 @@ mod tests {
             render_pass: Subpass::from(&simple_rp::CustomRenderPass::new(&device, &{
                 simple_rp::Formats { color: (Format::R8G8B8A8Unorm, 1) }
             }).unwrap(), 0).unwrap(),
+            cache: None,
+            derivative: PipelineDerivative::None,
         });
 
         match result {
@@ -1370,6 +2577,69 @@ mod tests {
     }
 
     #[test]
+    fn multi_viewport_fixed() {
+        let (device, _) = gfx_dev_and_queue!(multi_viewport);
+
+        let viewport = Viewport {
+            origin: [0.0, 0.0],
+            dimensions: [256.0, 256.0],
+            depth_range: 0.0 .. 1.0,
+        };
+
+        let viewports = ViewportsState::Fixed {
+            data: vec![
+                (viewport.clone(), Scissor::irrelevant()),
+                (viewport, Scissor::irrelevant()),
+            ],
+        };
+
+        let states = States { viewport: viewports, .. Default::default() };
+        let pipeline = simple_pipeline(&device, states, 1).unwrap();
+        assert_eq!(pipeline.num_viewports(), 2);
+        assert!(!pipeline.has_dynamic_viewports());
+    }
+
+    #[test]
+    fn multi_viewport_dynamic() {
+        let (device, _) = gfx_dev_and_queue!(multi_viewport);
+        let states = States { viewport: ViewportsState::Dynamic { num: 2 }, .. Default::default() };
+        let pipeline = simple_pipeline(&device, states, 1).unwrap();
+        assert_eq!(pipeline.num_viewports(), 2);
+        assert!(pipeline.has_dynamic_viewports());
+        assert!(pipeline.has_dynamic_scissors());
+    }
+
+    #[test]
+    fn multi_viewport_dynamic_scissors_feature() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let viewport = Viewport {
+            origin: [0.0, 0.0],
+            dimensions: [256.0, 256.0],
+            depth_range: 0.0 .. 1.0,
+        };
+
+        let viewports = ViewportsState::DynamicScissors { viewports: vec![viewport; 2] };
+        let states = States { viewport: viewports, .. Default::default() };
+        match simple_pipeline(&device, states, 1) {
+            Err(GraphicsPipelineCreationError::MultiViewportFeatureNotEnabled) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn no_viewport() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let states = States { viewport: ViewportsState::Dynamic { num: 0 }, .. Default::default() };
+        match simple_pipeline(&device, states, 1) {
+            Err(GraphicsPipelineCreationError::NoViewport) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
     fn no_depth_attachment() {
         let (device, _) = gfx_dev_and_queue!();
 
@@ -1402,6 +2672,8 @@ mod tests {
             render_pass: Subpass::from(&simple_rp::CustomRenderPass::new(&device, &{
                 simple_rp::Formats { color: (Format::R8G8B8A8Unorm, 1) }
             }).unwrap(), 0).unwrap(),
+            cache: None,
+            derivative: PipelineDerivative::None,
         });
 
         match result {
@@ -1411,84 +2683,916 @@ mod tests {
     }
 
 
-    mod simple_rp {
-        use format::Format;
+    #[test]
+    fn blend_presets() {
+        let (device, _) = gfx_dev_and_queue!();
 
-        single_pass_renderpass!{
-            attachments: {
-                color: {
-                    load: Clear,
-                    store: Store,
-                    format: Format,
-                }
-            },
-            pass: {
-                color: [color],
-                depth_stencil: {}
-            }
+        let rp = simple_rp::CustomRenderPass::new(&device, &{
+            simple_rp::Formats { color: (Format::R8G8B8A8Unorm, 1) }
+        }).unwrap();
+
+        let presets = [AttachmentBlend::pass_through(), AttachmentBlend::alpha_blending(),
+                       AttachmentBlend::additive()];
+
+        for preset in presets.iter() {
+            let blend = Blend {
+                logic_op: None,
+                attachments: AttachmentsBlend::Collective(preset.clone()),
+                blend_constants: None,
+            };
+
+            let states = States { blend: blend, .. Default::default() };
+            let _ = basic_pipeline(&device, states, Subpass::from(&rp, 0).unwrap()).unwrap();
         }
     }
 
-    /*
-        #version 450
+    #[test]
+    fn independent_blend_not_enabled() {
+        let (device, _) = gfx_dev_and_queue!();
 
-        #extension GL_ARB_separate_shader_objects : enable
-        #extension GL_ARB_shading_language_420pack : enable
+        let rp = two_colors_rp::CustomRenderPass::new(&device, &{
+            two_colors_rp::Formats {
+                color1: (Format::R8G8B8A8Unorm, 1),
+                color2: (Format::R8G8B8A8Unorm, 1),
+            }
+        }).unwrap();
 
-        layout(location = 0) in vec2 position;
+        let blend = Blend {
+            logic_op: None,
+            attachments: AttachmentsBlend::Individual(vec![AttachmentBlend::alpha_blending(),
+                                                           AttachmentBlend::additive()]),
+            blend_constants: None,
+        };
 
-        void main() {
-            gl_Position = vec4(position, 0.0, 1.0);
+        let states = States { blend: blend, .. Default::default() };
+        match basic_pipeline(&device, states, Subpass::from(&rp, 0).unwrap()) {
+            Err(GraphicsPipelineCreationError::IndependentBlendFeatureNotEnabled) => (),
+            _ => panic!()
         }
-    */
-    const BASIC_VS: [u8; 912] = [3, 2, 35, 7, 0, 0, 1, 0, 1, 0, 8, 0, 27, 0, 0, 0, 0, 0, 0, 0, 17,
-                                 0, 2, 0, 1, 0, 0, 0, 17, 0, 2, 0, 32, 0, 0, 0, 17, 0, 2, 0, 33, 0,
-                                 0, 0, 11, 0, 6, 0, 1, 0, 0, 0, 71, 76, 83, 76, 46, 115, 116, 100,
-                                 46, 52, 53, 48, 0, 0, 0, 0, 14, 0, 3, 0, 0, 0, 0, 0, 1, 0, 0, 0,
-                                 15, 0, 7, 0, 0, 0, 0, 0, 4, 0, 0, 0, 109, 97, 105, 110, 0, 0, 0,
-                                 0, 13, 0, 0, 0, 18, 0, 0, 0, 3, 0, 3, 0, 2, 0, 0, 0, 194, 1, 0,
-                                 0, 4, 0, 9, 0, 71, 76, 95, 65, 82, 66, 95, 115, 101, 112, 97,
-                                 114, 97, 116, 101, 95, 115, 104, 97, 100, 101, 114, 95, 111, 98,
-                                 106, 101, 99, 116, 115, 0, 0, 4, 0, 9, 0, 71, 76, 95, 65, 82, 66,
-                                 95, 115, 104, 97, 100, 105, 110, 103, 95, 108, 97, 110, 103, 117,
-                                 97, 103, 101, 95, 52, 50, 48, 112, 97, 99, 107, 0, 5, 0, 4, 0, 4,
-                                 0, 0, 0, 109, 97, 105, 110, 0, 0, 0, 0, 5, 0, 6, 0, 11, 0, 0, 0,
-                                 103, 108, 95, 80, 101, 114, 86, 101, 114, 116, 101, 120, 0, 0, 0,
-                                 0, 6, 0, 6, 0, 11, 0, 0, 0, 0, 0, 0, 0, 103, 108, 95, 80, 111,
-                                 115, 105, 116, 105, 111, 110, 0, 6, 0, 7, 0, 11, 0, 0, 0, 1, 0,
-                                 0, 0, 103, 108, 95, 80, 111, 105, 110, 116, 83, 105, 122, 101, 0,
-                                 0, 0, 0, 6, 0, 7, 0, 11, 0, 0, 0, 2, 0, 0, 0, 103, 108, 95, 67,
-                                 108, 105, 112, 68, 105, 115, 116, 97, 110, 99, 101, 0, 6, 0, 7,
-                                 0, 11, 0, 0, 0, 3, 0, 0, 0, 103, 108, 95, 67, 117, 108, 108, 68,
-                                 105, 115, 116, 97, 110, 99, 101, 0, 5, 0, 3, 0, 13, 0, 0, 0, 0, 0,
-                                 0, 0, 5, 0, 5, 0, 18, 0, 0, 0, 112, 111, 115, 105, 116, 105, 111,
-                                 110, 0, 0, 0, 0, 72, 0, 5, 0, 11, 0, 0, 0, 0, 0, 0, 0, 11, 0, 0,
-                                 0, 0, 0, 0, 0, 72, 0, 5, 0, 11, 0, 0, 0, 1, 0, 0, 0, 11, 0, 0, 0,
-                                 1, 0, 0, 0, 72, 0, 5, 0, 11, 0, 0, 0, 2, 0, 0, 0, 11, 0, 0, 0, 3,
-                                 0, 0, 0, 72, 0, 5, 0, 11, 0, 0, 0, 3, 0, 0, 0, 11, 0, 0, 0, 4, 0,
-                                 0, 0, 71, 0, 3, 0, 11, 0, 0, 0, 2, 0, 0, 0, 71, 0, 4, 0, 18, 0, 0,
-                                 0, 30, 0, 0, 0, 0, 0, 0, 0, 19, 0, 2, 0, 2, 0, 0, 0, 33, 0, 3, 0,
-                                 3, 0, 0, 0, 2, 0, 0, 0, 22, 0, 3, 0, 6, 0, 0, 0, 32, 0, 0, 0, 23,
-                                 0, 4, 0, 7, 0, 0, 0, 6, 0, 0, 0, 4, 0, 0, 0, 21, 0, 4, 0, 8, 0, 0,
-                                 0, 32, 0, 0, 0, 0, 0, 0, 0, 43, 0, 4, 0, 8, 0, 0, 0, 9, 0, 0, 0,
-                                 1, 0, 0, 0, 28, 0, 4, 0, 10, 0, 0, 0, 6, 0, 0, 0, 9, 0, 0, 0, 30,
-                                 0, 6, 0, 11, 0, 0, 0, 7, 0, 0, 0, 6, 0, 0, 0, 10, 0, 0, 0, 10, 0,
-                                 0, 0, 32, 0, 4, 0, 12, 0, 0, 0, 3, 0, 0, 0, 11, 0, 0, 0, 59, 0,
-                                 4, 0, 12, 0, 0, 0, 13, 0, 0, 0, 3, 0, 0, 0, 21, 0, 4, 0, 14, 0,
-                                 0, 0, 32, 0, 0, 0, 1, 0, 0, 0, 43, 0, 4, 0, 14, 0, 0, 0, 15, 0,
-                                 0, 0, 0, 0, 0, 0, 23, 0, 4, 0, 16, 0, 0, 0, 6, 0, 0, 0, 2, 0, 0,
-                                 0, 32, 0, 4, 0, 17, 0, 0, 0, 1, 0, 0, 0, 16, 0, 0, 0, 59, 0, 4,
-                                 0, 17, 0, 0, 0, 18, 0, 0, 0, 1, 0, 0, 0, 43, 0, 4, 0, 6, 0, 0,
-                                 0, 20, 0, 0, 0, 0, 0, 0, 0, 43, 0, 4, 0, 6, 0, 0, 0, 21, 0, 0,
-                                 0, 0, 0, 128, 63, 32, 0, 4, 0, 25, 0, 0, 0, 3, 0, 0, 0, 7, 0, 0,
-                                 0, 54, 0, 5, 0, 2, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0,
-                                 248, 0, 2, 0, 5, 0, 0, 0, 61, 0, 4, 0, 16, 0, 0, 0, 19, 0, 0, 0,
-                                 18, 0, 0, 0, 81, 0, 5, 0, 6, 0, 0, 0, 22, 0, 0, 0, 19, 0, 0, 0,
-                                 0, 0, 0, 0, 81, 0, 5, 0, 6, 0, 0, 0, 23, 0, 0, 0, 19, 0, 0, 0, 1,
-                                 0, 0, 0, 80, 0, 7, 0, 7, 0, 0, 0, 24, 0, 0, 0, 22, 0, 0, 0, 23,
-                                 0, 0, 0, 20, 0, 0, 0, 21, 0, 0, 0, 65, 0, 5, 0, 25, 0, 0, 0, 26,
-                                 0, 0, 0, 13, 0, 0, 0, 15, 0, 0, 0, 62, 0, 3, 0, 26, 0, 0, 0, 24,
-                                 0, 0, 0, 253, 0, 1, 0, 56, 0, 1, 0];
+    }
+
+    #[test]
+    fn independent_blend_identical() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let rp = two_colors_rp::CustomRenderPass::new(&device, &{
+            two_colors_rp::Formats {
+                color1: (Format::R8G8B8A8Unorm, 1),
+                color2: (Format::R8G8B8A8Unorm, 1),
+            }
+        }).unwrap();
+
+        let blend = Blend {
+            logic_op: None,
+            attachments: AttachmentsBlend::Individual(vec![AttachmentBlend::additive(),
+                                                           AttachmentBlend::additive()]),
+            blend_constants: None,
+        };
+
+        let states = States { blend: blend, .. Default::default() };
+        let _ = basic_pipeline(&device, states, Subpass::from(&rp, 0).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn independent_blend_enabled() {
+        let (device, _) = gfx_dev_and_queue!(independent_blend);
+
+        let rp = two_colors_rp::CustomRenderPass::new(&device, &{
+            two_colors_rp::Formats {
+                color1: (Format::R8G8B8A8Unorm, 1),
+                color2: (Format::R8G8B8A8Unorm, 1),
+            }
+        }).unwrap();
+
+        let blend = Blend {
+            logic_op: None,
+            attachments: AttachmentsBlend::Individual(vec![AttachmentBlend::alpha_blending(),
+                                                           AttachmentBlend::additive()]),
+            blend_constants: None,
+        };
+
+        let states = States { blend: blend, .. Default::default() };
+        let _ = basic_pipeline(&device, states, Subpass::from(&rp, 0).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn blend_attachments_count_mismatch() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let rp = simple_rp::CustomRenderPass::new(&device, &{
+            simple_rp::Formats { color: (Format::R8G8B8A8Unorm, 1) }
+        }).unwrap();
+
+        let blend = Blend {
+            logic_op: None,
+            attachments: AttachmentsBlend::Individual(vec![AttachmentBlend::additive(),
+                                                           AttachmentBlend::additive()]),
+            blend_constants: None,
+        };
+
+        let states = States { blend: blend, .. Default::default() };
+        match basic_pipeline(&device, states, Subpass::from(&rp, 0).unwrap()) {
+            Err(GraphicsPipelineCreationError::MismatchBlendingAttachmentsCount) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn logic_op_not_enabled() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let rp = simple_rp::CustomRenderPass::new(&device, &{
+            simple_rp::Formats { color: (Format::R8G8B8A8Unorm, 1) }
+        }).unwrap();
+
+        let blend = Blend {
+            logic_op: Some(LogicOp::Xor),
+            .. Blend::pass_through()
+        };
+
+        let states = States { blend: blend, .. Default::default() };
+        match basic_pipeline(&device, states, Subpass::from(&rp, 0).unwrap()) {
+            Err(GraphicsPipelineCreationError::LogicOpFeatureNotEnabled) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn depth_test() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let rp = depth_rp::CustomRenderPass::new(&device, &{
+            depth_rp::Formats {
+                color: (Format::R8G8B8A8Unorm, 1),
+                depth: (Format::D16Unorm, 1),
+            }
+        }).unwrap();
+
+        let states = States {
+            depth_stencil: DepthStencil::simple_depth_test(),
+            .. Default::default()
+        };
+        let _ = basic_pipeline(&device, states, Subpass::from(&rp, 0).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn depth_bounds_not_enabled() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let rp = depth_rp::CustomRenderPass::new(&device, &{
+            depth_rp::Formats {
+                color: (Format::R8G8B8A8Unorm, 1),
+                depth: (Format::D16Unorm, 1),
+            }
+        }).unwrap();
+
+        let depth_stencil = DepthStencil {
+            depth_bounds_test: DepthBounds::Fixed(0.25 .. 0.75),
+            .. DepthStencil::simple_depth_test()
+        };
+
+        let states = States { depth_stencil: depth_stencil, .. Default::default() };
+        match basic_pipeline(&device, states, Subpass::from(&rp, 0).unwrap()) {
+            Err(GraphicsPipelineCreationError::DepthBoundsFeatureNotEnabled) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn depth_bounds_no_depth_attachment() {
+        let (device, _) = gfx_dev_and_queue!(depth_bounds);
+
+        let rp = simple_rp::CustomRenderPass::new(&device, &{
+            simple_rp::Formats { color: (Format::R8G8B8A8Unorm, 1) }
+        }).unwrap();
+
+        let depth_stencil = DepthStencil {
+            depth_bounds_test: DepthBounds::Dynamic,
+            .. DepthStencil::disabled()
+        };
+
+        let states = States { depth_stencil: depth_stencil, .. Default::default() };
+        match basic_pipeline(&device, states, Subpass::from(&rp, 0).unwrap()) {
+            Err(GraphicsPipelineCreationError::NoDepthAttachment) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn no_stencil_attachment() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let rp = depth_rp::CustomRenderPass::new(&device, &{
+            depth_rp::Formats {
+                color: (Format::R8G8B8A8Unorm, 1),
+                depth: (Format::D16Unorm, 1),
+            }
+        }).unwrap();
+
+        let stencil = Stencil {
+            compare: Compare::Always,
+            pass_op: StencilOp::Replace,
+            .. Default::default()
+        };
+
+        let depth_stencil = DepthStencil {
+            stencil_front: stencil,
+            stencil_back: stencil,
+            .. DepthStencil::simple_depth_test()
+        };
+
+        let states = States { depth_stencil: depth_stencil, .. Default::default() };
+        match basic_pipeline(&device, states, Subpass::from(&rp, 0).unwrap()) {
+            Err(GraphicsPipelineCreationError::NoStencilAttachment) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn multisample_4x() {
+        let (device, _) = gfx_dev_and_queue!();
+        let states = States { multisample: Multisample::samples(4), .. Default::default() };
+        let _ = simple_pipeline(&device, states, 4).unwrap();
+    }
+
+    #[test]
+    fn multisample_samples_mismatch() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let states = States { multisample: Multisample::samples(4), .. Default::default() };
+        match simple_pipeline(&device, states, 1) {
+            Err(GraphicsPipelineCreationError::RasterizationSamplesMismatch {
+                expected: 1, obtained: 4
+            }) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn sample_shading_not_enabled() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let multisample = Multisample {
+            sample_shading: Some(0.5),
+            .. Multisample::samples(4)
+        };
+
+        let states = States { multisample: multisample, .. Default::default() };
+        match simple_pipeline(&device, states, 4) {
+            Err(GraphicsPipelineCreationError::SampleRateShadingFeatureNotEnabled) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn alpha_to_one_not_enabled() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let multisample = Multisample {
+            alpha_to_one: true,
+            .. Multisample::samples(4)
+        };
+
+        let states = States { multisample: multisample, .. Default::default() };
+        match simple_pipeline(&device, states, 4) {
+            Err(GraphicsPipelineCreationError::AlphaToOneFeatureNotEnabled) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn tessellation() {
+        let (device, _) = gfx_dev_and_queue!(tessellation_shader);
+        let topology = PrimitiveTopology::PatchList { vertices_per_patch: 3 };
+        let states = States {
+            stages: Stages::Tessellation,
+            input_assembly: InputAssembly { topology: topology, primitive_restart_enable: false },
+            .. Default::default()
+        };
+        let _ = simple_pipeline(&device, states, 1).unwrap();
+    }
+
+    #[test]
+    fn tessellation_feature_not_enabled() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let topology = PrimitiveTopology::PatchList { vertices_per_patch: 3 };
+        let states = States {
+            stages: Stages::Tessellation,
+            input_assembly: InputAssembly { topology: topology, primitive_restart_enable: false },
+            .. Default::default()
+        };
+        match simple_pipeline(&device, states, 1) {
+            Err(GraphicsPipelineCreationError::TessellationShaderFeatureNotEnabled) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn tessellation_without_patch_list() {
+        let (device, _) = gfx_dev_and_queue!(tessellation_shader);
+
+        let states = States {
+            stages: Stages::Tessellation,
+            input_assembly: InputAssembly {
+                topology: PrimitiveTopology::TriangleList,
+                primitive_restart_enable: false,
+            },
+            .. Default::default()
+        };
+        match simple_pipeline(&device, states, 1) {
+            Err(GraphicsPipelineCreationError::InvalidPrimitiveTopology) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn tessellation_max_patch_size() {
+        let (device, _) = gfx_dev_and_queue!(tessellation_shader);
+        let max = device.physical_device().limits().max_tessellation_patch_size();
+
+        let topology = PrimitiveTopology::PatchList { vertices_per_patch: max + 1 };
+        let states = States {
+            stages: Stages::Tessellation,
+            input_assembly: InputAssembly { topology: topology, primitive_restart_enable: false },
+            .. Default::default()
+        };
+        match simple_pipeline(&device, states, 1) {
+            Err(GraphicsPipelineCreationError::MaxTessellationPatchSizeExceeded) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn tessellation_empty_patch() {
+        let (device, _) = gfx_dev_and_queue!(tessellation_shader);
+
+        let topology = PrimitiveTopology::PatchList { vertices_per_patch: 0 };
+        let states = States {
+            stages: Stages::Tessellation,
+            input_assembly: InputAssembly { topology: topology, primitive_restart_enable: false },
+            .. Default::default()
+        };
+        match simple_pipeline(&device, states, 1) {
+            Err(GraphicsPipelineCreationError::EmptyTessellationPatch) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn patch_list_without_tessellation() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let vs = unsafe { ShaderModule::new(&device, &BASIC_VS).unwrap() };
+        let fs = unsafe { ShaderModule::new(&device, &BASIC_FS).unwrap() };
+
+        let result = GraphicsPipeline::new(&device, GraphicsPipelineParams {
+            vertex_input: SingleBufferDefinition::<()>::new(),
+            vertex_shader: unsafe {
+                vs.vertex_shader_entry_point::<(), _, _, _>(&CString::new("main").unwrap(),
+                                                            EmptyShaderInterfaceDef,
+                                                            EmptyShaderInterfaceDef,
+                                                            EmptyPipelineDesc)
+            },
+            input_assembly: InputAssembly {
+                topology: PrimitiveTopology::PatchList { vertices_per_patch: 3 },
+                primitive_restart_enable: false,
+            },
+            tessellation: None,
+            geometry_shader: None,
+            viewport: ViewportsState::Dynamic { num: 1 },
+            raster: Default::default(),
+            multisample: Multisample::disabled(),
+            fragment_shader: unsafe {
+                fs.fragment_shader_entry_point::<(), _, _, _>(&CString::new("main").unwrap(),
+                                                              EmptyShaderInterfaceDef,
+                                                              EmptyShaderInterfaceDef,
+                                                              EmptyPipelineDesc)
+            },
+            depth_stencil: DepthStencil::disabled(),
+            blend: Blend::pass_through(),
+            layout: &EmptyPipeline::new(&device).unwrap(),
+            render_pass: Subpass::from(&simple_rp::CustomRenderPass::new(&device, &{
+                simple_rp::Formats { color: (Format::R8G8B8A8Unorm, 1) }
+            }).unwrap(), 0).unwrap(),
+            cache: None,
+            derivative: PipelineDerivative::None,
+        });
+
+        match result {
+            Err(GraphicsPipelineCreationError::InvalidPrimitiveTopology) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn geometry_shader() {
+        let (device, _) = gfx_dev_and_queue!(geometry_shader);
+        let states = States {
+            stages: Stages::Geometry(GeometryShaderExecutionMode::Triangles),
+            input_assembly: InputAssembly {
+                topology: PrimitiveTopology::TriangleList,
+                primitive_restart_enable: false,
+            },
+            .. Default::default()
+        };
+        let _ = simple_pipeline(&device, states, 1).unwrap();
+    }
+
+    #[test]
+    fn geometry_shader_feature_not_enabled() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let states = States {
+            stages: Stages::Geometry(GeometryShaderExecutionMode::Triangles),
+            input_assembly: InputAssembly {
+                topology: PrimitiveTopology::TriangleList,
+                primitive_restart_enable: false,
+            },
+            .. Default::default()
+        };
+        match simple_pipeline(&device, states, 1) {
+            Err(GraphicsPipelineCreationError::GeometryShaderFeatureNotEnabled) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn geometry_shader_topology_mismatch() {
+        let (device, _) = gfx_dev_and_queue!(geometry_shader);
+
+        let states = States {
+            stages: Stages::Geometry(GeometryShaderExecutionMode::Triangles),
+            input_assembly: InputAssembly {
+                topology: PrimitiveTopology::LineList,
+                primitive_restart_enable: false,
+            },
+            .. Default::default()
+        };
+        match simple_pipeline(&device, states, 1) {
+            Err(GraphicsPipelineCreationError::TopologyNotMatchingGeometryShader) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn raster_default() {
+        let (device, _) = gfx_dev_and_queue!();
+        let _ = simple_pipeline(&device, Default::default(), 1).unwrap();
+    }
+
+    #[test]
+    fn raster_wide_lines_not_enabled() {
+        let (device, _) = gfx_dev_and_queue!();
+        let raster = Rasterization { line_width: Some(2.0), .. Default::default() };
+
+        let states = States { raster: raster, .. Default::default() };
+        match simple_pipeline(&device, states, 1) {
+            Err(GraphicsPipelineCreationError::WideLinesFeatureNotEnabled { line_width }) => {
+                assert_eq!(line_width, 2.0);
+            },
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn raster_line_width_out_of_range() {
+        let (device, _) = gfx_dev_and_queue!(wide_lines);
+        let max = device.physical_device().limits().line_width_range()[1];
+        let raster = Rasterization { line_width: Some(max + 1.0), .. Default::default() };
+
+        let states = States { raster: raster, .. Default::default() };
+        match simple_pipeline(&device, states, 1) {
+            Err(GraphicsPipelineCreationError::LineWidthOutOfRange { line_width, range }) => {
+                assert_eq!(line_width, max + 1.0);
+                assert_eq!(range[1], max);
+            },
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn raster_depth_clamp_not_enabled() {
+        let (device, _) = gfx_dev_and_queue!();
+        let raster = Rasterization { depth_clamp: true, .. Default::default() };
+
+        let states = States { raster: raster, .. Default::default() };
+        match simple_pipeline(&device, states, 1) {
+            Err(GraphicsPipelineCreationError::DepthClampFeatureNotEnabled) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn raster_depth_bias_clamp_not_enabled() {
+        let (device, _) = gfx_dev_and_queue!();
+        let raster = Rasterization {
+            depth_bias: DepthBiasControl::Static(DepthBias {
+                constant_factor: 1.0,
+                clamp: 0.5,
+                slope_factor: 1.0,
+            }),
+            .. Default::default()
+        };
+
+        let states = States { raster: raster, .. Default::default() };
+        match simple_pipeline(&device, states, 1) {
+            Err(GraphicsPipelineCreationError::DepthBiasClampFeatureNotEnabled { clamp }) => {
+                assert_eq!(clamp, 0.5);
+            },
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn raster_fill_mode_non_solid_not_enabled() {
+        let (device, _) = gfx_dev_and_queue!();
+        let raster = Rasterization { polygon_mode: PolygonMode::Line, .. Default::default() };
+
+        let states = States { raster: raster, .. Default::default() };
+        match simple_pipeline(&device, states, 1) {
+            Err(GraphicsPipelineCreationError::FillModeNonSolidFeatureNotEnabled {
+                polygon_mode: PolygonMode::Line
+            }) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn raster_dynamic_states() {
+        let (device, _) = gfx_dev_and_queue!();
+        let raster = Rasterization {
+            line_width: None,
+            depth_bias: DepthBiasControl::Dynamic,
+            cull_mode: CullMode::Back,
+            front_face: FrontFace::Clockwise,
+            .. Default::default()
+        };
+
+        let states = States { raster: raster, .. Default::default() };
+        let pipeline = simple_pipeline(&device, states, 1).unwrap();
+        assert!(pipeline.has_dynamic_line_width());
+        assert!(pipeline.has_dynamic_depth_bias());
+    }
+
+    // Optional stages of the pipelines built by `pipeline_with_vertex_input`.
+    enum Stages {
+        VertexFragment,
+        Tessellation,
+        // A pass-through geometry shader whose entry point declares the given execution mode.
+        Geometry(GeometryShaderExecutionMode),
+    }
+
+    // States of the pipelines built by `pipeline_with_vertex_input`. The default values
+    // describe a pipeline that all devices support, so that each test only needs to override
+    // the states it's interested in.
+    struct States<'a> {
+        stages: Stages,
+        input_assembly: InputAssembly,
+        viewport: ViewportsState,
+        raster: Rasterization,
+        multisample: Multisample,
+        depth_stencil: DepthStencil,
+        blend: Blend,
+        derivative: PipelineDerivative<'a>,
+    }
+
+    impl<'a> Default for States<'a> {
+        fn default() -> States<'a> {
+            States {
+                stages: Stages::VertexFragment,
+                input_assembly: InputAssembly::triangle_list(),
+                viewport: ViewportsState::Dynamic { num: 1 },
+                raster: Default::default(),
+                multisample: Multisample::disabled(),
+                depth_stencil: DepthStencil::disabled(),
+                blend: Blend::pass_through(),
+                derivative: PipelineDerivative::None,
+            }
+        }
+    }
+
+    // Builds a pipeline without vertex input, against a render pass with a single color
+    // attachment that has `samples` samples.
+    fn simple_pipeline(device: &Arc<Device>, states: States, samples: u32)
+                       -> Result<Arc<GraphicsPipeline<SingleBufferDefinition<()>, EmptyPipeline,
+                                                      simple_rp::CustomRenderPass>>,
+                                 GraphicsPipelineCreationError>
+    {
+        let rp = simple_render_pass(device, samples);
+        basic_pipeline(device, states, Subpass::from(&rp, 0).unwrap())
+    }
+
+    // Builds a render pass with a single color attachment that has `samples` samples.
+    fn simple_render_pass(device: &Arc<Device>, samples: u32) -> Arc<simple_rp::CustomRenderPass> {
+        simple_rp::CustomRenderPass::new(&device, &{
+            simple_rp::Formats { color: (Format::R8G8B8A8Unorm, samples) }
+        }).unwrap()
+    }
+
+    // Builds a pipeline without vertex input.
+    fn basic_pipeline<Rp>(device: &Arc<Device>, states: States, subpass: Subpass<Rp>)
+                          -> Result<Arc<GraphicsPipeline<SingleBufferDefinition<()>, EmptyPipeline,
+                                                         Rp>>, GraphicsPipelineCreationError>
+        where Rp: RenderPass + RenderPassDesc + RenderPassSubpassInterface<EmptyShaderInterfaceDef>
+    {
+        pipeline_with_vertex_input(device, SingleBufferDefinition::<()>::new(),
+                                   EmptyShaderInterfaceDef, states, subpass)
+    }
+
+    // Builds a pipeline with pass-through shaders. `vertex_shader_input` is the input interface
+    // of the vertex shader.
+    fn pipeline_with_vertex_input<V, I, Rp>(device: &Arc<Device>, vertex_input: V,
+                                            vertex_shader_input: I, states: States,
+                                            subpass: Subpass<Rp>)
+                                            -> Result<Arc<GraphicsPipeline<V, EmptyPipeline, Rp>>,
+                                                      GraphicsPipelineCreationError>
+        where V: Definition<I>,
+              Rp: RenderPass + RenderPassDesc + RenderPassSubpassInterface<EmptyShaderInterfaceDef>
+    {
+        let vs = unsafe { ShaderModule::new(&device, &BASIC_VS).unwrap() };
+        let fs = unsafe { ShaderModule::new(&device, &BASIC_FS).unwrap() };
+        let name = CString::new("main").unwrap();
+
+        let layout = EmptyPipeline::new(&device).unwrap();
+
+        let builder = GraphicsPipeline::start()
+            .vertex_input(vertex_input)
+            .vertex_shader(unsafe {
+                vs.vertex_shader_entry_point::<(), _, _, _>(&name, vertex_shader_input,
+                                                            EmptyShaderInterfaceDef,
+                                                            EmptyPipelineDesc)
+            }, &())
+            .input_assembly(states.input_assembly.topology)
+            .primitive_restart(states.input_assembly.primitive_restart_enable)
+            .viewports(states.viewport)
+            .rasterization(states.raster)
+            .multisample(states.multisample)
+            .fragment_shader(unsafe {
+                fs.fragment_shader_entry_point::<(), _, _, _>(&name, EmptyShaderInterfaceDef,
+                                                              EmptyShaderInterfaceDef,
+                                                              EmptyPipelineDesc)
+            }, &())
+            .depth_stencil(states.depth_stencil)
+            .blend(states.blend)
+            .layout(&layout)
+            .render_pass(subpass)
+            .derivative(states.derivative);
+
+        match states.stages {
+            Stages::VertexFragment => builder.build(&device),
+
+            Stages::Tessellation => {
+                let tcs = unsafe { ShaderModule::new(&device, &BASIC_TCS).unwrap() };
+                let tes = unsafe { ShaderModule::new(&device, &BASIC_TES).unwrap() };
+
+                let control = unsafe {
+                    tcs.tess_control_shader_entry_point::<(), _, _, _>(&name,
+                                                                      EmptyShaderInterfaceDef,
+                                                                      EmptyShaderInterfaceDef,
+                                                                      EmptyPipelineDesc)
+                };
+                let evaluation = unsafe {
+                    tes.tess_evaluation_shader_entry_point::<(), _, _, _>(&name,
+                                                                         EmptyShaderInterfaceDef,
+                                                                         EmptyShaderInterfaceDef,
+                                                                         EmptyPipelineDesc)
+                };
+
+                builder.tessellation_shaders(control, &(), evaluation, &()).build(&device)
+            },
+
+            Stages::Geometry(primitives) => {
+                let gs = unsafe { ShaderModule::new(&device, &BASIC_GS).unwrap() };
+                let geometry_shader = unsafe {
+                    gs.geometry_shader_entry_point::<(), _, _, _>(&name, primitives,
+                                                                  EmptyShaderInterfaceDef,
+                                                                  EmptyShaderInterfaceDef,
+                                                                  EmptyPipelineDesc)
+                };
+
+                builder.geometry_shader(geometry_shader, &()).build(&device)
+            },
+        }
+    }
+
+    #[test]
+    fn two_vertex_buffers() {
+        let (device, _) = gfx_dev_and_queue!();
+        let vertex_input = TwoBuffersDefinition::<PositionVertex, ColorVertex>::new();
+        let rp = simple_render_pass(&device, 1);
+        let subpass = Subpass::from(&rp, 0).unwrap();
+        let _ = pipeline_with_vertex_input(&device, vertex_input, PositionColorInput,
+                                           Default::default(), subpass).unwrap();
+    }
+
+    #[test]
+    fn two_vertex_buffers_duplicate_attribute() {
+        let (device, _) = gfx_dev_and_queue!();
+        let vertex_input = TwoBuffersDefinition::<PositionVertex, PositionVertex>::new();
+        let rp = simple_render_pass(&device, 1);
+        let subpass = Subpass::from(&rp, 0).unwrap();
+
+        match pipeline_with_vertex_input(&device, vertex_input, PositionColorInput,
+                                         Default::default(), subpass)
+        {
+            Err(GraphicsPipelineCreationError::IncompatibleVertexDefinition(
+                IncompatibleVertexDefinitionError::DuplicateAttribute { .. })) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn vertex_attribute_missing() {
+        let (device, _) = gfx_dev_and_queue!();
+        let vertex_input = SingleBufferDefinition::<PositionVertex>::new();
+        let rp = simple_render_pass(&device, 1);
+        let subpass = Subpass::from(&rp, 0).unwrap();
+
+        match pipeline_with_vertex_input(&device, vertex_input, PositionColorInput,
+                                         Default::default(), subpass)
+        {
+            Err(GraphicsPipelineCreationError::IncompatibleVertexDefinition(
+                IncompatibleVertexDefinitionError::MissingAttribute { .. })) => (),
+            _ => panic!()
+        }
+    }
+
+    struct PositionVertex {
+        _position: [f32; 2],
+    }
+
+    unsafe impl Vertex for PositionVertex {
+        fn member(name: &str) -> Option<VertexMemberInfo> {
+            if name != "position" {
+                return None;
+            }
+
+            Some(VertexMemberInfo { offset: 0, ty: VertexMemberTy::F32, array_size: 2 })
+        }
+    }
+
+    struct ColorVertex {
+        _color: [f32; 4],
+    }
+
+    unsafe impl Vertex for ColorVertex {
+        fn member(name: &str) -> Option<VertexMemberInfo> {
+            if name != "color" {
+                return None;
+            }
+
+            Some(VertexMemberInfo { offset: 0, ty: VertexMemberTy::F32, array_size: 4 })
+        }
+    }
+
+    // Input of a vertex shader with a `position` and a `color` attributes.
+    struct PositionColorInput;
+
+    unsafe impl ShaderInterfaceDef for PositionColorInput {
+        type Iter = VecIntoIter<ShaderInterfaceDefEntry>;
+
+        fn elements(&self) -> VecIntoIter<ShaderInterfaceDefEntry> {
+            vec![
+                ShaderInterfaceDefEntry {
+                    location: 0 .. 1,
+                    format: Format::R32G32Sfloat,
+                    name: Some(Cow::Borrowed("position")),
+                },
+                ShaderInterfaceDefEntry {
+                    location: 1 .. 2,
+                    format: Format::R32G32B32A32Sfloat,
+                    name: Some(Cow::Borrowed("color")),
+                },
+            ].into_iter()
+        }
+    }
+
+    mod simple_rp {
+        use format::Format;
+
+        single_pass_renderpass!{
+            attachments: {
+                color: {
+                    load: Clear,
+                    store: Store,
+                    format: Format,
+                }
+            },
+            pass: {
+                color: [color],
+                depth_stencil: {}
+            }
+        }
+    }
+
+    mod depth_rp {
+        use format::Format;
+
+        single_pass_renderpass!{
+            attachments: {
+                color: {
+                    load: Clear,
+                    store: Store,
+                    format: Format,
+                },
+                depth: {
+                    load: Clear,
+                    store: DontCare,
+                    format: Format,
+                }
+            },
+            pass: {
+                color: [color],
+                depth_stencil: {depth}
+            }
+        }
+    }
+
+    mod two_colors_rp {
+        use format::Format;
+
+        single_pass_renderpass!{
+            attachments: {
+                color1: {
+                    load: Clear,
+                    store: Store,
+                    format: Format,
+                },
+                color2: {
+                    load: Clear,
+                    store: Store,
+                    format: Format,
+                }
+            },
+            pass: {
+                color: [color1, color2],
+                depth_stencil: {}
+            }
+        }
+    }
+
+    /*
+        #version 450
+
+        #extension GL_ARB_separate_shader_objects : enable
+        #extension GL_ARB_shading_language_420pack : enable
+
+        layout(location = 0) in vec2 position;
+
+        void main() {
+            gl_Position = vec4(position, 0.0, 1.0);
+        }
+    */
+    const BASIC_VS: [u8; 912] = [3, 2, 35, 7, 0, 0, 1, 0, 1, 0, 8, 0, 27, 0, 0, 0, 0, 0, 0, 0, 17,
+                                 0, 2, 0, 1, 0, 0, 0, 17, 0, 2, 0, 32, 0, 0, 0, 17, 0, 2, 0, 33, 0,
+                                 0, 0, 11, 0, 6, 0, 1, 0, 0, 0, 71, 76, 83, 76, 46, 115, 116, 100,
+                                 46, 52, 53, 48, 0, 0, 0, 0, 14, 0, 3, 0, 0, 0, 0, 0, 1, 0, 0, 0,
+                                 15, 0, 7, 0, 0, 0, 0, 0, 4, 0, 0, 0, 109, 97, 105, 110, 0, 0, 0,
+                                 0, 13, 0, 0, 0, 18, 0, 0, 0, 3, 0, 3, 0, 2, 0, 0, 0, 194, 1, 0,
+                                 0, 4, 0, 9, 0, 71, 76, 95, 65, 82, 66, 95, 115, 101, 112, 97,
+                                 114, 97, 116, 101, 95, 115, 104, 97, 100, 101, 114, 95, 111, 98,
+                                 106, 101, 99, 116, 115, 0, 0, 4, 0, 9, 0, 71, 76, 95, 65, 82, 66,
+                                 95, 115, 104, 97, 100, 105, 110, 103, 95, 108, 97, 110, 103, 117,
+                                 97, 103, 101, 95, 52, 50, 48, 112, 97, 99, 107, 0, 5, 0, 4, 0, 4,
+                                 0, 0, 0, 109, 97, 105, 110, 0, 0, 0, 0, 5, 0, 6, 0, 11, 0, 0, 0,
+                                 103, 108, 95, 80, 101, 114, 86, 101, 114, 116, 101, 120, 0, 0, 0,
+                                 0, 6, 0, 6, 0, 11, 0, 0, 0, 0, 0, 0, 0, 103, 108, 95, 80, 111,
+                                 115, 105, 116, 105, 111, 110, 0, 6, 0, 7, 0, 11, 0, 0, 0, 1, 0,
+                                 0, 0, 103, 108, 95, 80, 111, 105, 110, 116, 83, 105, 122, 101, 0,
+                                 0, 0, 0, 6, 0, 7, 0, 11, 0, 0, 0, 2, 0, 0, 0, 103, 108, 95, 67,
+                                 108, 105, 112, 68, 105, 115, 116, 97, 110, 99, 101, 0, 6, 0, 7,
+                                 0, 11, 0, 0, 0, 3, 0, 0, 0, 103, 108, 95, 67, 117, 108, 108, 68,
+                                 105, 115, 116, 97, 110, 99, 101, 0, 5, 0, 3, 0, 13, 0, 0, 0, 0, 0,
+                                 0, 0, 5, 0, 5, 0, 18, 0, 0, 0, 112, 111, 115, 105, 116, 105, 111,
+                                 110, 0, 0, 0, 0, 72, 0, 5, 0, 11, 0, 0, 0, 0, 0, 0, 0, 11, 0, 0,
+                                 0, 0, 0, 0, 0, 72, 0, 5, 0, 11, 0, 0, 0, 1, 0, 0, 0, 11, 0, 0, 0,
+                                 1, 0, 0, 0, 72, 0, 5, 0, 11, 0, 0, 0, 2, 0, 0, 0, 11, 0, 0, 0, 3,
+                                 0, 0, 0, 72, 0, 5, 0, 11, 0, 0, 0, 3, 0, 0, 0, 11, 0, 0, 0, 4, 0,
+                                 0, 0, 71, 0, 3, 0, 11, 0, 0, 0, 2, 0, 0, 0, 71, 0, 4, 0, 18, 0, 0,
+                                 0, 30, 0, 0, 0, 0, 0, 0, 0, 19, 0, 2, 0, 2, 0, 0, 0, 33, 0, 3, 0,
+                                 3, 0, 0, 0, 2, 0, 0, 0, 22, 0, 3, 0, 6, 0, 0, 0, 32, 0, 0, 0, 23,
+                                 0, 4, 0, 7, 0, 0, 0, 6, 0, 0, 0, 4, 0, 0, 0, 21, 0, 4, 0, 8, 0, 0,
+                                 0, 32, 0, 0, 0, 0, 0, 0, 0, 43, 0, 4, 0, 8, 0, 0, 0, 9, 0, 0, 0,
+                                 1, 0, 0, 0, 28, 0, 4, 0, 10, 0, 0, 0, 6, 0, 0, 0, 9, 0, 0, 0, 30,
+                                 0, 6, 0, 11, 0, 0, 0, 7, 0, 0, 0, 6, 0, 0, 0, 10, 0, 0, 0, 10, 0,
+                                 0, 0, 32, 0, 4, 0, 12, 0, 0, 0, 3, 0, 0, 0, 11, 0, 0, 0, 59, 0,
+                                 4, 0, 12, 0, 0, 0, 13, 0, 0, 0, 3, 0, 0, 0, 21, 0, 4, 0, 14, 0,
+                                 0, 0, 32, 0, 0, 0, 1, 0, 0, 0, 43, 0, 4, 0, 14, 0, 0, 0, 15, 0,
+                                 0, 0, 0, 0, 0, 0, 23, 0, 4, 0, 16, 0, 0, 0, 6, 0, 0, 0, 2, 0, 0,
+                                 0, 32, 0, 4, 0, 17, 0, 0, 0, 1, 0, 0, 0, 16, 0, 0, 0, 59, 0, 4,
+                                 0, 17, 0, 0, 0, 18, 0, 0, 0, 1, 0, 0, 0, 43, 0, 4, 0, 6, 0, 0,
+                                 0, 20, 0, 0, 0, 0, 0, 0, 0, 43, 0, 4, 0, 6, 0, 0, 0, 21, 0, 0,
+                                 0, 0, 0, 128, 63, 32, 0, 4, 0, 25, 0, 0, 0, 3, 0, 0, 0, 7, 0, 0,
+                                 0, 54, 0, 5, 0, 2, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0,
+                                 248, 0, 2, 0, 5, 0, 0, 0, 61, 0, 4, 0, 16, 0, 0, 0, 19, 0, 0, 0,
+                                 18, 0, 0, 0, 81, 0, 5, 0, 6, 0, 0, 0, 22, 0, 0, 0, 19, 0, 0, 0,
+                                 0, 0, 0, 0, 81, 0, 5, 0, 6, 0, 0, 0, 23, 0, 0, 0, 19, 0, 0, 0, 1,
+                                 0, 0, 0, 80, 0, 7, 0, 7, 0, 0, 0, 24, 0, 0, 0, 22, 0, 0, 0, 23,
+                                 0, 0, 0, 20, 0, 0, 0, 21, 0, 0, 0, 65, 0, 5, 0, 25, 0, 0, 0, 26,
+                                 0, 0, 0, 13, 0, 0, 0, 15, 0, 0, 0, 62, 0, 3, 0, 26, 0, 0, 0, 24,
+                                 0, 0, 0, 253, 0, 1, 0, 56, 0, 1, 0];
 
     /*
         #version 450
@@ -1525,4 +3629,56 @@ mod tests {
                                  5, 0, 2, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 248, 0, 2,
                                  0, 5, 0, 0, 0, 62, 0, 3, 0, 9, 0, 0, 0, 12, 0, 0, 0, 253, 0, 1,
                                  0, 56, 0, 1, 0];
+
+    /*
+        #version 450
+
+        layout(vertices = 3) out;
+
+        void main() {
+        }
+    */
+    const BASIC_TCS: [u8; 132] = [3, 2, 35, 7, 0, 0, 1, 0, 0, 0, 0, 0, 6, 0, 0, 0, 0, 0, 0, 0, 17,
+                                  0, 2, 0, 3, 0, 0, 0, 14, 0, 3, 0, 0, 0, 0, 0, 1, 0, 0, 0, 15, 0,
+                                  5, 0, 1, 0, 0, 0, 1, 0, 0, 0, 109, 97, 105, 110, 0, 0, 0, 0, 16,
+                                  0, 4, 0, 1, 0, 0, 0, 26, 0, 0, 0, 3, 0, 0, 0, 19, 0, 2, 0, 2, 0,
+                                  0, 0, 33, 0, 3, 0, 3, 0, 0, 0, 2, 0, 0, 0, 54, 0, 5, 0, 2, 0, 0,
+                                  0, 1, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 248, 0, 2, 0, 4, 0, 0, 0,
+                                  253, 0, 1, 0, 56, 0, 1, 0];
+
+    /*
+        #version 450
+
+        layout(triangles, equal_spacing, ccw) in;
+
+        void main() {
+        }
+    */
+    const BASIC_TES: [u8; 152] = [3, 2, 35, 7, 0, 0, 1, 0, 0, 0, 0, 0, 6, 0, 0, 0, 0, 0, 0, 0, 17,
+                                  0, 2, 0, 3, 0, 0, 0, 14, 0, 3, 0, 0, 0, 0, 0, 1, 0, 0, 0, 15, 0,
+                                  5, 0, 2, 0, 0, 0, 1, 0, 0, 0, 109, 97, 105, 110, 0, 0, 0, 0, 16,
+                                  0, 3, 0, 1, 0, 0, 0, 22, 0, 0, 0, 16, 0, 3, 0, 1, 0, 0, 0, 1, 0,
+                                  0, 0, 16, 0, 3, 0, 1, 0, 0, 0, 5, 0, 0, 0, 19, 0, 2, 0, 2, 0, 0,
+                                  0, 33, 0, 3, 0, 3, 0, 0, 0, 2, 0, 0, 0, 54, 0, 5, 0, 2, 0, 0, 0,
+                                  1, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 248, 0, 2, 0, 4, 0, 0, 0, 253,
+                                  0, 1, 0, 56, 0, 1, 0];
+
+    /*
+        #version 450
+
+        layout(triangles) in;
+        layout(triangle_strip, max_vertices = 3) out;
+
+        void main() {
+        }
+    */
+    const BASIC_GS: [u8; 172] = [3, 2, 35, 7, 0, 0, 1, 0, 0, 0, 0, 0, 6, 0, 0, 0, 0, 0, 0, 0, 17, 0,
+                                 2, 0, 2, 0, 0, 0, 14, 0, 3, 0, 0, 0, 0, 0, 1, 0, 0, 0, 15, 0, 5, 0,
+                                 3, 0, 0, 0, 1, 0, 0, 0, 109, 97, 105, 110, 0, 0, 0, 0, 16, 0, 3, 0,
+                                 1, 0, 0, 0, 22, 0, 0, 0, 16, 0, 4, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0,
+                                 0, 0, 16, 0, 3, 0, 1, 0, 0, 0, 29, 0, 0, 0, 16, 0, 4, 0, 1, 0, 0,
+                                 0, 26, 0, 0, 0, 3, 0, 0, 0, 19, 0, 2, 0, 2, 0, 0, 0, 33, 0, 3, 0,
+                                 3, 0, 0, 0, 2, 0, 0, 0, 54, 0, 5, 0, 2, 0, 0, 0, 1, 0, 0, 0, 0, 0,
+                                 0, 0, 3, 0, 0, 0, 248, 0, 2, 0, 4, 0, 0, 0, 253, 0, 1, 0, 56, 0, 1,
+                                 0];
 }
@@ -18,13 +18,16 @@ use vk;
 pub struct InputAssembly {
     /// The type of primitives.
     ///
-    /// Note that some tologies don't support primitive restart.
+    /// Note that some topologies don't support primitive restart.
     pub topology: PrimitiveTopology,
 
     /// If true, then the special index value `0xffff` or `0xffffffff` will tell the GPU that it is
     /// the end of the current primitive. A new primitive will restart at the next index.
     ///
-    /// Note that some tologies don't support primitive restart.
+    /// This only has an effect when drawing with an index buffer. Only the strip and fan
+    /// topologies support primitive restart.
+    ///
+    /// Note that some topologies don't support primitive restart.
     pub primitive_restart_enable: bool,
 }
 
@@ -42,19 +45,40 @@ impl InputAssembly {
 /// Describes how vertices must be grouped together to form primitives.
 ///
 /// Note that some topologies don't support primitive restart.
+///
+/// The topologies with adjacency require the `geometry_shader` feature to be enabled, and
+/// `PatchList` requires tessellation shaders.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum PrimitiveTopology {
+    /// Each vertex is a point.
     PointList,
+    /// Each group of two vertices is a line.
     LineList,
+    /// Each vertex forms a line with the previous vertex.
     LineStrip,
+    /// Each group of three vertices is a triangle.
     TriangleList,
+    /// Each vertex forms a triangle with the two previous vertices.
     TriangleStrip,
+    /// Each vertex forms a triangle with the previous vertex and the first vertex.
     TriangleFan,
+    /// Same as `LineList`, but each line is surrounded by two adjacent vertices that are only
+    /// visible to the geometry shader.
     LineListWithAdjacency,
+    /// Same as `LineStrip`, but with an additional adjacent vertex at each end of the strip.
     LineStripWithAdjacency,
-    TriangleListWithAdjancecy,
+    /// Same as `TriangleList`, but each triangle has three adjacent vertices interleaved with
+    /// its own vertices.
+    TriangleListWithAdjacency,
+    /// Same as `TriangleStrip`, but with adjacent vertices interleaved with the vertices of the
+    /// strip.
     TriangleStripWithAdjacency,
-    PatchList { vertices_per_patch: u32 },
+    /// Each group of `vertices_per_patch` vertices is a patch that is fed to the tessellation
+    /// shaders.
+    PatchList {
+        /// Number of vertices in each patch.
+        vertices_per_patch: u32
+    },
 }
 
 impl Into<vk::PrimitiveTopology> for PrimitiveTopology {
@@ -69,7 +93,7 @@ impl Into<vk::PrimitiveTopology> for PrimitiveTopology {
             PrimitiveTopology::TriangleFan => vk::PRIMITIVE_TOPOLOGY_TRIANGLE_FAN,
             PrimitiveTopology::LineListWithAdjacency => vk::PRIMITIVE_TOPOLOGY_LINE_LIST_WITH_ADJACENCY,
             PrimitiveTopology::LineStripWithAdjacency => vk::PRIMITIVE_TOPOLOGY_LINE_STRIP_WITH_ADJACENCY,
-            PrimitiveTopology::TriangleListWithAdjancecy => vk::PRIMITIVE_TOPOLOGY_TRIANGLE_LIST_WITH_ADJACENCY,
+            PrimitiveTopology::TriangleListWithAdjacency => vk::PRIMITIVE_TOPOLOGY_TRIANGLE_LIST_WITH_ADJACENCY,
             PrimitiveTopology::TriangleStripWithAdjacency => vk::PRIMITIVE_TOPOLOGY_TRIANGLE_STRIP_WITH_ADJACENCY,
             PrimitiveTopology::PatchList { .. } => vk::PRIMITIVE_TOPOLOGY_PATCH_LIST,
         }
@@ -77,6 +101,12 @@ impl Into<vk::PrimitiveTopology> for PrimitiveTopology {
 }
 
 impl PrimitiveTopology {
+    /// Old misspelled name of `TriangleListWithAdjacency`.
+    #[deprecated(note = "Use `PrimitiveTopology::TriangleListWithAdjacency` instead")]
+    #[allow(non_upper_case_globals)]
+    pub const TriangleListWithAdjancecy: PrimitiveTopology =
+        PrimitiveTopology::TriangleListWithAdjacency;
+
     /// Returns true if this primitive topology supports using primitives restart.
     #[inline]
     pub fn supports_primitive_restart(&self) -> bool {
@@ -89,6 +119,19 @@ impl PrimitiveTopology {
             _ => false
         }
     }
+
+    /// Returns true if this primitive topology has adjacency information, which requires the
+    /// `geometry_shader` feature.
+    #[inline]
+    pub fn has_adjacency(&self) -> bool {
+        match *self {
+            PrimitiveTopology::LineListWithAdjacency => true,
+            PrimitiveTopology::LineStripWithAdjacency => true,
+            PrimitiveTopology::TriangleListWithAdjacency => true,
+            PrimitiveTopology::TriangleStripWithAdjacency => true,
+            _ => false
+        }
+    }
 }
 
 /// Trait for types that can be used as indices by the GPU.
@@ -119,3 +162,62 @@ pub enum IndexType {
     U16 = vk::INDEX_TYPE_UINT16,
     U32 = vk::INDEX_TYPE_UINT32,
 }
+
+#[cfg(test)]
+mod tests {
+    use pipeline::input_assembly::PrimitiveTopology;
+    use vk;
+
+    #[test]
+    fn topology_values() {
+        let topologies = [
+            (PrimitiveTopology::PointList, vk::PRIMITIVE_TOPOLOGY_POINT_LIST),
+            (PrimitiveTopology::LineList, vk::PRIMITIVE_TOPOLOGY_LINE_LIST),
+            (PrimitiveTopology::LineStrip, vk::PRIMITIVE_TOPOLOGY_LINE_STRIP),
+            (PrimitiveTopology::TriangleList, vk::PRIMITIVE_TOPOLOGY_TRIANGLE_LIST),
+            (PrimitiveTopology::TriangleStrip, vk::PRIMITIVE_TOPOLOGY_TRIANGLE_STRIP),
+            (PrimitiveTopology::TriangleFan, vk::PRIMITIVE_TOPOLOGY_TRIANGLE_FAN),
+            (PrimitiveTopology::LineListWithAdjacency,
+             vk::PRIMITIVE_TOPOLOGY_LINE_LIST_WITH_ADJACENCY),
+            (PrimitiveTopology::LineStripWithAdjacency,
+             vk::PRIMITIVE_TOPOLOGY_LINE_STRIP_WITH_ADJACENCY),
+            (PrimitiveTopology::TriangleListWithAdjacency,
+             vk::PRIMITIVE_TOPOLOGY_TRIANGLE_LIST_WITH_ADJACENCY),
+            (PrimitiveTopology::TriangleStripWithAdjacency,
+             vk::PRIMITIVE_TOPOLOGY_TRIANGLE_STRIP_WITH_ADJACENCY),
+            (PrimitiveTopology::PatchList { vertices_per_patch: 4 },
+             vk::PRIMITIVE_TOPOLOGY_PATCH_LIST),
+        ];
+
+        for &(topology, value) in topologies.iter() {
+            let obtained: vk::PrimitiveTopology = topology.into();
+            assert_eq!(obtained, value);
+        }
+    }
+
+    #[test]
+    fn primitive_restart_support() {
+        assert!(PrimitiveTopology::LineStrip.supports_primitive_restart());
+        assert!(PrimitiveTopology::TriangleFan.supports_primitive_restart());
+        assert!(PrimitiveTopology::TriangleStripWithAdjacency.supports_primitive_restart());
+        assert!(!PrimitiveTopology::PointList.supports_primitive_restart());
+        assert!(!PrimitiveTopology::TriangleListWithAdjacency.supports_primitive_restart());
+        assert!(!PrimitiveTopology::PatchList { vertices_per_patch: 3 }
+                                   .supports_primitive_restart());
+    }
+
+    #[test]
+    fn adjacency() {
+        assert!(PrimitiveTopology::LineListWithAdjacency.has_adjacency());
+        assert!(PrimitiveTopology::TriangleStripWithAdjacency.has_adjacency());
+        assert!(!PrimitiveTopology::TriangleStrip.has_adjacency());
+        assert!(!PrimitiveTopology::PatchList { vertices_per_patch: 3 }.has_adjacency());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn misspelled_alias() {
+        assert_eq!(PrimitiveTopology::TriangleListWithAdjancecy,
+                   PrimitiveTopology::TriangleListWithAdjacency);
+    }
+}
@@ -22,19 +22,26 @@
 //! steps of pipelines.
 
 pub use self::compute_pipeline::ComputePipeline;
+pub use self::compute_pipeline::ComputePipelineCreationError;
 pub use self::graphics_pipeline::GraphicsPipeline;
+pub use self::graphics_pipeline::GraphicsPipelineBuilder;
 pub use self::graphics_pipeline::GraphicsPipelineParams;
+pub use self::graphics_pipeline::GraphicsPipelineParamsTess;
 pub use self::graphics_pipeline::GraphicsPipelineCreationError;
+pub use self::graphics_pipeline::DynamicStateFlags;
+
+#[macro_use]
+pub mod shader;
 
 mod compute_pipeline;
 mod graphics_pipeline;
 
 pub mod blend;
 pub mod cache;
+pub mod derivative;
 pub mod depth_stencil;
 pub mod input_assembly;
 pub mod multisample;
 pub mod raster;
-pub mod shader;
 pub mod vertex;
 pub mod viewport;
@@ -37,19 +37,52 @@
 /// State of the multisampling.
 ///
 /// See the documentation in this module.
+#[derive(Debug, Copy, Clone)]
 pub struct Multisample {
+    /// Number of samples per pixel. Must be equal to the number of samples of the attachments of
+    /// the subpass, and must be a power of two between 1 and 64.
     pub rasterization_samples: u32,
+
+    /// Bit mask of the samples that are updated. Bit `n` of element `n / 32` corresponds to the
+    /// sample `n`. Samples whose bit is 0 are discarded.
     pub sample_mask: [u32; 4],
+
+    /// Minimum proportion of samples that run the fragment shader, or `None` to disable sample
+    /// shading.
+    ///
+    /// Requires the `sample_rate_shading` feature. The value must be between 0.0 and 1.0.
     pub sample_shading: Option<f32>,
+
+    /// If `true`, the alpha value of the fragment determines which samples are covered.
     pub alpha_to_coverage: bool,
+
+    /// If `true`, the alpha value of all the samples is replaced by 1.0.
+    ///
+    /// Requires the `alpha_to_one` feature.
     pub alpha_to_one: bool,
 }
 
 impl Multisample {
+    /// Disables multisampling. There is one sample per pixel.
     #[inline]
     pub fn disabled() -> Multisample {
+        Multisample::samples(1)
+    }
+
+    /// Uses `samples` samples per pixel, without sample shading, alpha-to-coverage or
+    /// alpha-to-one.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if `samples` is not a power of two between 1 and 64.
+    ///
+    #[inline]
+    pub fn samples(samples: u32) -> Multisample {
+        assert!(samples.is_power_of_two() && samples <= 64,
+                "The number of samples must be a power of two between 1 and 64");
+
         Multisample {
-            rasterization_samples: 1,
+            rasterization_samples: samples,
             sample_mask: [0xffffffff; 4],
             sample_shading: None,
             alpha_to_coverage: false,
@@ -57,3 +90,10 @@ impl Multisample {
         }
     }
 }
+
+impl Default for Multisample {
+    #[inline]
+    fn default() -> Multisample {
+        Multisample::disabled()
+    }
+}
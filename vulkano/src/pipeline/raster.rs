@@ -19,6 +19,8 @@ use vk;
 pub struct Rasterization {
     /// If true, then the depth value of the vertices will be clamped to [0.0 ; 1.0]. If false,
     /// fragments whose depth is outside of this range will be discarded.
+    ///
+    /// Requires the `depth_clamp` feature to be enabled.
     pub depth_clamp: bool,
 
     /// If true, all the fragments will be discarded. This is usually used when your vertex shader
@@ -39,8 +41,11 @@ pub struct Rasterization {
     ///
     /// If you pass `None`, then this state will be considered as dynamic and the line width will
     /// need to be set when you build the command buffer.
+    ///
+    /// Values other than 1.0 require the `wide_lines` feature to be enabled.
     pub line_width: Option<f32>,
 
+    /// Offset to add to the depth value of the fragments of triangles.
     pub depth_bias: DepthBiasControl,
 }
 
@@ -59,10 +64,17 @@ impl Default for Rasterization {
     }
 }
 
+/// Whether and how to add an offset to the depth value of fragments.
+///
+/// This is typically used to avoid artifacts when rendering shadow maps or decals.
 #[derive(Copy, Clone, Debug)]
 pub enum DepthBiasControl {
+    /// No depth bias.
     Disabled,
+    /// Depth bias is enabled, and its value will need to be set when you build the command
+    /// buffer.
     Dynamic,
+    /// Depth bias is enabled with the given value.
     Static(DepthBias),
 }
 
@@ -76,11 +88,20 @@ impl DepthBiasControl {
     }
 }
 
+/// Value of the depth bias.
+///
+/// The offset added to the depth of each fragment is `constant_factor * r + slope_factor * m`,
+/// where `r` is the smallest difference that the depth attachment can represent and `m` is the
+/// maximum depth slope of the polygon.
 #[derive(Copy, Clone, Debug)]
 pub struct DepthBias {
+    /// Constant part of the offset.
     pub constant_factor: f32,
-    /// Requires the `depth_bias_clamp` feature to be enabled.
+    /// Maximum (or minimum if negative) value of the offset, or 0.0 for no clamping.
+    ///
+    /// Values other than 0.0 require the `depth_bias_clamp` feature to be enabled.
     pub clamp: f32,
+    /// Part of the offset that is proportional to the slope of the polygon.
     pub slope_factor: f32,
 }
 
@@ -126,11 +147,17 @@ impl Default for FrontFace {
     }
 }
 
+/// Specifies how polygons are rasterized.
+///
+/// Modes other than `Fill` require the `fill_mode_non_solid` feature to be enabled.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[repr(u32)]
 pub enum PolygonMode {
+    /// The whole surface of the polygons is drawn.
     Fill = vk::POLYGON_MODE_FILL,
+    /// Only the edges of the polygons are drawn, as lines.
     Line = vk::POLYGON_MODE_LINE,
+    /// Only the vertices of the polygons are drawn, as points.
     Point = vk::POLYGON_MODE_POINT,
 }
 
@@ -12,15 +12,21 @@
 //! In Vulkan, shaders are grouped in *shader modules*. Each shader module is built from SPIR-V
 //! code and can contain one or more entry points. Note that for the moment the official
 //! GLSL-to-SPIR-V compiler does not support multiple entry points.
+//!
+//! Each entry point of a module can be used by a different pipeline, or by a different stage of
+//! the same pipeline, without loading the module again.
 //! 
-//! The vulkano library does not provide any functionnality that checks and introspects the SPIR-V
-//! code, therefore the whole shader-related API is unsafe. You are encouraged to use the
-//! `vulkano-shaders` crate that will generate Rust code that wraps around vulkano's shaders API.
+//! The vulkano library only reads the list of entry points from the SPIR-V code and does not
+//! otherwise check or introspect it, therefore the whole shader-related API is unsafe. You are
+//! encouraged to use the `vulkano-shaders` crate that will generate Rust code that wraps around
+//! vulkano's shaders API.
 
 use std::borrow::Cow;
+use std::error;
+use std::ffi::CString;
+use std::fmt;
 use std::iter;
 use std::iter::Empty as EmptyIter;
-use std::marker::PhantomData;
 use std::mem;
 use std::ops::Range;
 use std::ptr;
@@ -45,6 +51,8 @@ use vk;
 pub struct ShaderModule {
     device: Arc<Device>,
     module: vk::ShaderModule,
+    // Entry points declared in the SPIR-V code, or `None` if the code couldn't be parsed.
+    entry_points: Option<Vec<EntryPointDesc>>,
 }
 
 impl ShaderModule {
@@ -81,18 +89,56 @@ impl ShaderModule {
         Ok(Arc::new(ShaderModule {
             device: device.clone(),
             module: module,
+            entry_points: parse_entry_points(spirv),
         }))
     }
 
+    /// Returns the entry points declared in the SPIR-V code of the module.
+    ///
+    /// Returns `None` if the SPIR-V code couldn't be parsed.
+    #[inline]
+    pub fn entry_points(&self) -> Option<&[EntryPointDesc]> {
+        self.entry_points.as_ref().map(|e| &e[..])
+    }
+
+    /// Checks whether the module contains an entry point named `name` for the given stage.
+    ///
+    /// Pipeline creation calls this function for each of its shader stages. Always succeeds if
+    /// the SPIR-V code of the module couldn't be parsed.
+    pub fn check_entry_point(&self, name: &CStr, execution_model: ExecutionModel)
+                             -> Result<(), EntryPointError>
+    {
+        let entry_points = match self.entry_points {
+            Some(ref e) => e,
+            None => return Ok(()),
+        };
+
+        let mut other_model = None;
+
+        for entry_point in entry_points.iter().filter(|e| &*e.name == name) {
+            if entry_point.execution_model == execution_model {
+                return Ok(());
+            }
+            other_model = Some(entry_point.execution_model);
+        }
+
+        match other_model {
+            Some(obtained) => Err(EntryPointError::WrongExecutionModel {
+                expected: execution_model,
+                obtained: obtained,
+            }),
+            None => Err(EntryPointError::Missing),
+        }
+    }
+
     /// Gets access to an entry point contained in this module.
     ///
     /// This is purely a *logical* operation. It returns a struct that *represents* the entry
-    /// point but doesn't actually do anything.
+    /// point but doesn't actually do anything. Whether the entry point exists is checked when
+    /// creating a pipeline with it, if the SPIR-V code of the module could be parsed.
     ///
     /// # Safety
     ///
-    /// - The user must check that the entry point exists in the module, as this is not checked
-    ///   by Vulkan.
     /// - The input, output and layout must correctly describe the input, output and layout used
     ///   by this stage.
     ///
@@ -106,19 +152,16 @@ impl ShaderModule {
             input: input,
             output: output,
             layout: layout,
-            marker: PhantomData,
+            specialization: None,
         }
     }
 
     /// Gets access to an entry point contained in this module.
     ///
-    /// This is purely a *logical* operation. It returns a struct that *represents* the entry
-    /// point but doesn't actually do anything.
+    /// See `vertex_shader_entry_point`.
     ///
     /// # Safety
     ///
-    /// - The user must check that the entry point exists in the module, as this is not checked
-    ///   by Vulkan.
     /// - The input, output and layout must correctly describe the input, output and layout used
     ///   by this stage.
     ///
@@ -132,19 +175,16 @@ impl ShaderModule {
             layout: layout,
             input: input,
             output: output,
-            marker: PhantomData,
+            specialization: None,
         }
     }
 
     /// Gets access to an entry point contained in this module.
     ///
-    /// This is purely a *logical* operation. It returns a struct that *represents* the entry
-    /// point but doesn't actually do anything.
+    /// See `vertex_shader_entry_point`.
     ///
     /// # Safety
     ///
-    /// - The user must check that the entry point exists in the module, as this is not checked
-    ///   by Vulkan.
     /// - The input, output and layout must correctly describe the input, output and layout used
     ///   by this stage.
     ///
@@ -158,19 +198,16 @@ impl ShaderModule {
             layout: layout,
             input: input,
             output: output,
-            marker: PhantomData,
+            specialization: None,
         }
     }
 
     /// Gets access to an entry point contained in this module.
     ///
-    /// This is purely a *logical* operation. It returns a struct that *represents* the entry
-    /// point but doesn't actually do anything.
+    /// See `vertex_shader_entry_point`.
     ///
     /// # Safety
     ///
-    /// - The user must check that the entry point exists in the module, as this is not checked
-    ///   by Vulkan.
     /// - The input, output and layout must correctly describe the input, output and layout used
     ///   by this stage.
     ///
@@ -185,19 +222,16 @@ impl ShaderModule {
             primitives: primitives,
             input: input,
             output: output,
-            marker: PhantomData,
+            specialization: None,
         }
     }
 
     /// Gets access to an entry point contained in this module.
     ///
-    /// This is purely a *logical* operation. It returns a struct that *represents* the entry
-    /// point but doesn't actually do anything.
+    /// See `vertex_shader_entry_point`.
     ///
     /// # Safety
     ///
-    /// - The user must check that the entry point exists in the module, as this is not checked
-    ///   by Vulkan.
     /// - The input, output and layout must correctly describe the input, output and layout used
     ///   by this stage.
     ///
@@ -211,19 +245,16 @@ impl ShaderModule {
             layout: layout,
             input: input,
             output: output,
-            marker: PhantomData,
+            specialization: None,
         }
     }
 
     /// Gets access to an entry point contained in this module.
     ///
-    /// This is purely a *logical* operation. It returns a struct that *represents* the entry
-    /// point but doesn't actually do anything.
+    /// See `vertex_shader_entry_point`.
     ///
     /// # Safety
     ///
-    /// - The user must check that the entry point exists in the module, as this is not checked
-    ///   by Vulkan.
     /// - The layout must correctly describe the layout used by this stage.
     ///
     #[inline]
@@ -234,7 +265,7 @@ impl ShaderModule {
             module: self,
             name: name,
             layout: layout,
-            marker: PhantomData,
+            specialization: None,
         }
     }
 }
@@ -258,6 +289,124 @@ impl Drop for ShaderModule {
     }
 }
 
+/// Stage of a pipeline an entry point of a shader module is meant for.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExecutionModel {
+    Vertex,
+    TessellationControl,
+    TessellationEvaluation,
+    Geometry,
+    Fragment,
+    Compute,
+}
+
+/// Description of an entry point declared in the SPIR-V code of a shader module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryPointDesc {
+    /// Name of the entry point.
+    pub name: CString,
+    /// Stage of the pipeline the entry point is meant for.
+    pub execution_model: ExecutionModel,
+}
+
+/// Error that can happen when looking for an entry point in a shader module.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EntryPointError {
+    /// The module doesn't contain any entry point with this name.
+    Missing,
+    /// The module contains an entry point with this name, but for another stage.
+    WrongExecutionModel {
+        /// Stage the entry point is used for.
+        expected: ExecutionModel,
+        /// Stage the entry point is declared for.
+        obtained: ExecutionModel,
+    },
+}
+
+impl error::Error for EntryPointError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            EntryPointError::Missing => {
+                "the shader module doesn't contain any entry point with this name"
+            },
+            EntryPointError::WrongExecutionModel { .. } => {
+                "the entry point of the shader module is meant for another stage"
+            },
+        }
+    }
+}
+
+impl fmt::Display for EntryPointError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+// Reads the `OpEntryPoint` instructions of SPIR-V code. Returns `None` if the code isn't valid
+// SPIR-V. Entry points of the OpenCL `Kernel` execution model are ignored.
+fn parse_entry_points(spirv: &[u8]) -> Option<Vec<EntryPointDesc>> {
+    if spirv.len() < 20 || (spirv.len() % 4) != 0 {
+        return None;
+    }
+
+    let words = spirv.chunks(4).map(|b| {
+        (b[0] as u32) | ((b[1] as u32) << 8) | ((b[2] as u32) << 16) | ((b[3] as u32) << 24)
+    }).collect::<Vec<_>>();
+
+    let words = match words[0] {
+        0x07230203 => words,
+        0x03022307 => words.into_iter().map(|w| w.swap_bytes()).collect(),
+        _ => return None,
+    };
+
+    let mut entry_points = Vec::new();
+    let mut offset = 5;
+
+    while offset < words.len() {
+        let word_count = (words[offset] >> 16) as usize;
+        let opcode = words[offset] & 0xffff;
+
+        if word_count == 0 || offset + word_count > words.len() {
+            return None;
+        }
+
+        // Entry points are always declared before the first function.
+        if opcode == 54 {       // OpFunction
+            break;
+        }
+
+        if opcode == 15 && word_count >= 4 {       // OpEntryPoint
+            let execution_model = match words[offset + 1] {
+                0 => Some(ExecutionModel::Vertex),
+                1 => Some(ExecutionModel::TessellationControl),
+                2 => Some(ExecutionModel::TessellationEvaluation),
+                3 => Some(ExecutionModel::Geometry),
+                4 => Some(ExecutionModel::Fragment),
+                5 => Some(ExecutionModel::Compute),
+                _ => None,
+            };
+
+            let name = words[offset + 3 .. offset + word_count].iter()
+                            .flat_map(|&w| (0 .. 4).map(move |n| (w >> (n * 8)) as u8))
+                            .take_while(|&c| c != 0)
+                            .collect::<Vec<_>>();
+
+            if let Some(execution_model) = execution_model {
+                entry_points.push(EntryPointDesc {
+                    name: CString::new(name).unwrap(),
+                    execution_model: execution_model,
+                });
+            }
+        }
+
+        offset += word_count;
+    }
+
+    Some(entry_points)
+}
+
 /// Represents the entry point of a vertex shader in a shader module.
 ///
 /// Can be obtained by calling `vertex_shader_entry_point()` on the shader module.
@@ -268,7 +417,7 @@ pub struct VertexShaderEntryPoint<'a, S, I, O, L> {
     input: I,
     layout: L,
     output: O,
-    marker: PhantomData<S>,
+    specialization: Option<&'a S>,
 }
 
 impl<'a, S, I, O, L> VertexShaderEntryPoint<'a, S, I, O, L> {
@@ -284,6 +433,21 @@ impl<'a, S, I, O, L> VertexShaderEntryPoint<'a, S, I, O, L> {
         self.name
     }
 
+    /// Sets the values of the specialization constants of the shader.
+    ///
+    /// Constants that aren't specialized keep the default value written in the shader.
+    #[inline]
+    pub fn specialize(mut self, constants: &'a S) -> Self {
+        self.specialization = Some(constants);
+        self
+    }
+
+    /// Returns the values of the specialization constants, if they were set with `specialize`.
+    #[inline]
+    pub fn specialization(&self) -> Option<&'a S> {
+        self.specialization
+    }
+
     /// Returns the pipeline layout used by the shader stage.
     #[inline]
     pub fn layout(&self) -> &L {
@@ -314,7 +478,7 @@ pub struct TessControlShaderEntryPoint<'a, S, I, O, L> {
     layout: L,
     input: I,
     output: O,
-    marker: PhantomData<S>,
+    specialization: Option<&'a S>,
 }
 
 impl<'a, S, I, O, L> TessControlShaderEntryPoint<'a, S, I, O, L> {
@@ -330,6 +494,21 @@ impl<'a, S, I, O, L> TessControlShaderEntryPoint<'a, S, I, O, L> {
         self.name
     }
 
+    /// Sets the values of the specialization constants of the shader.
+    ///
+    /// Constants that aren't specialized keep the default value written in the shader.
+    #[inline]
+    pub fn specialize(mut self, constants: &'a S) -> Self {
+        self.specialization = Some(constants);
+        self
+    }
+
+    /// Returns the values of the specialization constants, if they were set with `specialize`.
+    #[inline]
+    pub fn specialization(&self) -> Option<&'a S> {
+        self.specialization
+    }
+
     /// Returns the pipeline layout used by the shader stage.
     #[inline]
     pub fn layout(&self) -> &L {
@@ -359,7 +538,7 @@ pub struct TessEvaluationShaderEntryPoint<'a, S, I, O, L> {
     layout: L,
     input: I,
     output: O,
-    marker: PhantomData<S>,
+    specialization: Option<&'a S>,
 }
 
 impl<'a, S, I, O, L> TessEvaluationShaderEntryPoint<'a, S, I, O, L> {
@@ -375,6 +554,21 @@ impl<'a, S, I, O, L> TessEvaluationShaderEntryPoint<'a, S, I, O, L> {
         self.name
     }
 
+    /// Sets the values of the specialization constants of the shader.
+    ///
+    /// Constants that aren't specialized keep the default value written in the shader.
+    #[inline]
+    pub fn specialize(mut self, constants: &'a S) -> Self {
+        self.specialization = Some(constants);
+        self
+    }
+
+    /// Returns the values of the specialization constants, if they were set with `specialize`.
+    #[inline]
+    pub fn specialization(&self) -> Option<&'a S> {
+        self.specialization
+    }
+
     /// Returns the pipeline layout used by the shader stage.
     #[inline]
     pub fn layout(&self) -> &L {
@@ -405,7 +599,7 @@ pub struct GeometryShaderEntryPoint<'a, S, I, O, L> {
     primitives: GeometryShaderExecutionMode,
     input: I,
     output: O,
-    marker: PhantomData<S>,
+    specialization: Option<&'a S>,
 }
 
 impl<'a, S, I, O, L> GeometryShaderEntryPoint<'a, S, I, O, L> {
@@ -421,6 +615,21 @@ impl<'a, S, I, O, L> GeometryShaderEntryPoint<'a, S, I, O, L> {
         self.name
     }
 
+    /// Sets the values of the specialization constants of the shader.
+    ///
+    /// Constants that aren't specialized keep the default value written in the shader.
+    #[inline]
+    pub fn specialize(mut self, constants: &'a S) -> Self {
+        self.specialization = Some(constants);
+        self
+    }
+
+    /// Returns the values of the specialization constants, if they were set with `specialize`.
+    #[inline]
+    pub fn specialization(&self) -> Option<&'a S> {
+        self.specialization
+    }
+
     /// Returns the kind of primitives expected by the geometry shader.
     #[inline]
     pub fn primitives(&self) -> GeometryShaderExecutionMode {
@@ -472,7 +681,7 @@ impl GeometryShaderExecutionMode {
             (GeometryShaderExecutionMode::Triangles, PrimitiveTopology::TriangleStrip) => true,
             (GeometryShaderExecutionMode::Triangles, PrimitiveTopology::TriangleFan) => true,
             (GeometryShaderExecutionMode::TrianglesWithAdjacency,
-             PrimitiveTopology::TriangleListWithAdjancecy) => true,
+             PrimitiveTopology::TriangleListWithAdjacency) => true,
             (GeometryShaderExecutionMode::TrianglesWithAdjacency,
              PrimitiveTopology::TriangleStripWithAdjacency) => true,
             _ => false,
@@ -490,7 +699,7 @@ pub struct FragmentShaderEntryPoint<'a, S, I, O, L> {
     layout: L,
     input: I,
     output: O,
-    marker: PhantomData<S>,
+    specialization: Option<&'a S>,
 }
 
 impl<'a, S, I, O, L> FragmentShaderEntryPoint<'a, S, I, O, L> {
@@ -506,6 +715,21 @@ impl<'a, S, I, O, L> FragmentShaderEntryPoint<'a, S, I, O, L> {
         self.name
     }
 
+    /// Sets the values of the specialization constants of the shader.
+    ///
+    /// Constants that aren't specialized keep the default value written in the shader.
+    #[inline]
+    pub fn specialize(mut self, constants: &'a S) -> Self {
+        self.specialization = Some(constants);
+        self
+    }
+
+    /// Returns the values of the specialization constants, if they were set with `specialize`.
+    #[inline]
+    pub fn specialization(&self) -> Option<&'a S> {
+        self.specialization
+    }
+
     /// Returns the pipeline layout used by the shader stage.
     #[inline]
     pub fn layout(&self) -> &L {
@@ -533,7 +757,7 @@ pub struct ComputeShaderEntryPoint<'a, S, L> {
     module: &'a ShaderModule,
     name: &'a CStr,
     layout: L,
-    marker: PhantomData<S>,
+    specialization: Option<&'a S>,
 }
 
 impl<'a, S, L> ComputeShaderEntryPoint<'a, S, L> {
@@ -549,6 +773,21 @@ impl<'a, S, L> ComputeShaderEntryPoint<'a, S, L> {
         self.name
     }
 
+    /// Sets the values of the specialization constants of the shader.
+    ///
+    /// Constants that aren't specialized keep the default value written in the shader.
+    #[inline]
+    pub fn specialize(mut self, constants: &'a S) -> Self {
+        self.specialization = Some(constants);
+        self
+    }
+
+    /// Returns the values of the specialization constants, if they were set with `specialize`.
+    #[inline]
+    pub fn specialization(&self) -> Option<&'a S> {
+        self.specialization
+    }
+
     /// Returns the pipeline layout used by the shader stage.
     #[inline]
     pub fn layout(&self) -> &L {
@@ -638,6 +877,28 @@ unsafe impl<T, I> ShaderInterfaceDefMatch<I> for T
 
 /// Trait for types that contain specialization data for shaders.
 ///
+/// Specialization constants are constants of a SPIR-V module whose value can be chosen when the
+/// pipeline is created, for example to toggle features or to set the local size of a compute
+/// shader without compiling the shader again. Their values are read from an object of a type
+/// that implements this trait, usually a `#[repr(C)]` struct generated with the
+/// `specialization_constants!` macro:
+///
+/// ```ignore
+/// specialization_constants! {
+///     pub struct LocalSize {
+///         0 => x: u32,
+///         1 => y: u32,
+///     }
+/// }
+///
+/// let constants = LocalSize { x: 64, y: 1 };
+/// let entry_point = shader.main_entry_point().specialize(&constants);
+/// let pipeline = ComputePipeline::new(&device, &layout, &entry_point, None,
+///                                     PipelineDerivative::None).unwrap();
+/// ```
+///
+/// The values are passed to the `specialize` method of the entry point of each shader stage.
+///
 /// It is implemented on `()` for shaders that don't have any specialization constant.
 ///
 /// # Safety
@@ -667,3 +928,264 @@ pub struct SpecializationMapEntry {
     /// Size of the data in bytes.
     pub size: usize,
 }
+
+/// Builds the `VkSpecializationInfo` that points to `constants`.
+///
+/// The returned struct contains raw pointers to `constants` and to its descriptors. It must not
+/// be used after `constants` has been destroyed.
+#[doc(hidden)]
+#[inline]
+pub fn specialization_info<S>(constants: &S) -> vk::SpecializationInfo
+    where S: SpecializationConstants
+{
+    let descriptors = S::descriptors();
+
+    debug_assert!(descriptors.iter().all(|d| {
+        d.offset as usize + d.size <= mem::size_of::<S>()
+    }), "A specialization constant is out of range of its struct");
+
+    vk::SpecializationInfo {
+        mapEntryCount: descriptors.len() as u32,
+        pMapEntries: descriptors.as_ptr() as *const _,
+        dataSize: mem::size_of_val(constants),
+        pData: constants as *const S as *const _,
+    }
+}
+
+/// Declares a `#[repr(C)]` struct that contains the values of specialization constants, and
+/// implements `SpecializationConstants` on it.
+///
+/// Each field is preceded by the ID of the constant in the shader (the `constant_id` layout
+/// qualifier in GLSL). The supported types are `u32`, `i32`, `f32`, `u64`, `i64` and `f64`.
+/// Boolean constants are 32 bits in SPIR-V, so use `u32` for them.
+///
+/// # Example
+///
+/// ```
+/// # #[macro_use] extern crate vulkano;
+/// # fn main() {
+/// specialization_constants! {
+///     pub struct Constants {
+///         0 => local_size_x: u32,
+///         3 => scale: f64,
+///     }
+/// }
+///
+/// let _constants = Constants { local_size_x: 64, scale: 1.5 };
+/// # }
+/// ```
+#[macro_export]
+macro_rules! specialization_constants {
+    (pub struct $name:ident { $($id:expr => $field:ident: $ty:ident),* $(,)* }) => {
+        specialization_constants!(__struct__ [pub] $name { $($id => $field: $ty),* });
+    };
+
+    (struct $name:ident { $($id:expr => $field:ident: $ty:ident),* $(,)* }) => {
+        specialization_constants!(__struct__ [] $name { $($id => $field: $ty),* });
+    };
+
+    (__struct__ [$($vis:tt)*] $name:ident { $($id:expr => $field:ident: $ty:ident),* }) => {
+        #[repr(C)]
+        #[derive(Debug, Copy, Clone)]
+        $($vis)* struct $name {
+            $(
+                pub $field: $ty,
+            )*
+        }
+
+        unsafe impl $crate::pipeline::shader::SpecializationConstants for $name {
+            fn descriptors() -> &'static [$crate::pipeline::shader::SpecializationMapEntry] {
+                use std::mem;
+                use std::sync::Once;
+                use std::sync::ONCE_INIT;
+                use $crate::pipeline::shader::SpecializationMapEntry;
+
+                static INIT: Once = ONCE_INIT;
+                static mut DESCRIPTORS: *const [SpecializationMapEntry] = &[];
+
+                // The offsets are read from the fields of an actual value, because the
+                // alignment of 64-bits types depends on the target.
+                unsafe {
+                    INIT.call_once(|| {
+                        let dummy: $name = mem::zeroed();
+                        let base = &dummy as *const $name as usize;
+
+                        let entries: Vec<SpecializationMapEntry> = vec![$(
+                            SpecializationMapEntry {
+                                constant_id: $id,
+                                offset: (&dummy.$field as *const $ty as usize - base) as u32,
+                                size: mem::size_of::<$ty>(),
+                            }
+                        ),*];
+
+                        DESCRIPTORS = Box::into_raw(entries.into_boxed_slice());
+                    });
+
+                    &*DESCRIPTORS
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+    use std::mem;
+
+    use descriptor::pipeline_layout::EmptyPipeline;
+    use descriptor::pipeline_layout::EmptyPipelineDesc;
+    use pipeline::ComputePipeline;
+    use pipeline::ComputePipelineCreationError;
+    use pipeline::derivative::PipelineDerivative;
+    use pipeline::input_assembly::PrimitiveTopology;
+    use pipeline::shader::EntryPointDesc;
+    use pipeline::shader::EntryPointError;
+    use pipeline::shader::ExecutionModel;
+    use pipeline::shader::GeometryShaderExecutionMode;
+    use pipeline::shader::ShaderModule;
+    use pipeline::shader::SpecializationConstants;
+
+    specialization_constants! {
+        struct Mixed {
+            5 => a: u32,
+            2 => b: f64,
+            7 => c: f32,
+            0 => d: i64,
+        }
+    }
+
+    #[test]
+    fn specialization_offsets() {
+        let descriptors = <Mixed as SpecializationConstants>::descriptors();
+        let ids = descriptors.iter().map(|d| d.constant_id).collect::<Vec<_>>();
+        let offsets = descriptors.iter().map(|d| d.offset).collect::<Vec<_>>();
+        let sizes = descriptors.iter().map(|d| d.size).collect::<Vec<_>>();
+
+        assert_eq!(ids, [5, 2, 7, 0]);
+        assert_eq!(sizes, [4, 8, 4, 8]);
+        assert!(offsets.windows(2).all(|w| w[0] < w[1]));
+        assert!(offsets[3] as usize + sizes[3] <= mem::size_of::<Mixed>());
+    }
+
+    #[test]
+    fn specialization_offsets_match_fields() {
+        let constants = Mixed { a: 0, b: 0.0, c: 0.0, d: 0 };
+        let base = &constants as *const Mixed as usize;
+        let fields = [&constants.a as *const _ as usize, &constants.b as *const _ as usize,
+                      &constants.c as *const _ as usize, &constants.d as *const _ as usize];
+
+        let descriptors = <Mixed as SpecializationConstants>::descriptors();
+        for (desc, field) in descriptors.iter().zip(fields.iter()) {
+            assert_eq!(desc.offset as usize, field - base);
+        }
+    }
+
+    #[test]
+    fn specialization_info() {
+        let constants = Mixed { a: 0, b: 0.0, c: 0.0, d: 0 };
+        let info = super::specialization_info(&constants);
+        assert_eq!(info.mapEntryCount, 4);
+        assert_eq!(info.dataSize, mem::size_of::<Mixed>());
+
+        let empty = super::specialization_info(&());
+        assert_eq!(empty.mapEntryCount, 0);
+        assert_eq!(empty.dataSize, 0);
+    }
+
+    #[test]
+    fn geometry_execution_mode_matches() {
+        assert!(GeometryShaderExecutionMode::Points.matches(PrimitiveTopology::PointList));
+        assert!(GeometryShaderExecutionMode::Lines.matches(PrimitiveTopology::LineStrip));
+        assert!(GeometryShaderExecutionMode::Triangles.matches(PrimitiveTopology::TriangleFan));
+        assert!(GeometryShaderExecutionMode::TrianglesWithAdjacency
+                    .matches(PrimitiveTopology::TriangleStripWithAdjacency));
+
+        assert!(!GeometryShaderExecutionMode::Lines
+                     .matches(PrimitiveTopology::LineListWithAdjacency));
+        assert!(!GeometryShaderExecutionMode::Triangles.matches(PrimitiveTopology::PointList));
+        assert!(!GeometryShaderExecutionMode::Triangles
+                     .matches(PrimitiveTopology::PatchList { vertices_per_patch: 3 }));
+    }
+
+    #[test]
+    fn parse_entry_points() {
+        let entry_points = super::parse_entry_points(&TWO_ENTRY_POINTS_CS).unwrap();
+        assert_eq!(entry_points, vec![
+            EntryPointDesc {
+                name: CString::new("first").unwrap(),
+                execution_model: ExecutionModel::Compute,
+            },
+            EntryPointDesc {
+                name: CString::new("second").unwrap(),
+                execution_model: ExecutionModel::Compute,
+            },
+        ]);
+
+        assert!(super::parse_entry_points(&[0; 24]).is_none());
+        assert!(super::parse_entry_points(&TWO_ENTRY_POINTS_CS[.. 50]).is_none());
+    }
+
+    #[test]
+    fn two_entry_points() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let module = unsafe { ShaderModule::new(&device, &TWO_ENTRY_POINTS_CS).unwrap() };
+        let layout = EmptyPipeline::new(&device).unwrap();
+
+        for name in &["first", "second"] {
+            let name = CString::new(*name).unwrap();
+            assert_eq!(module.check_entry_point(&name, ExecutionModel::Compute), Ok(()));
+
+            let entry_point = unsafe {
+                module.compute_shader_entry_point::<(), _>(&name, EmptyPipelineDesc)
+            };
+            let _ = ComputePipeline::new(&device, &layout, &entry_point, None,
+                                         PipelineDerivative::None).unwrap();
+        }
+    }
+
+    #[test]
+    fn missing_entry_point() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let module = unsafe { ShaderModule::new(&device, &TWO_ENTRY_POINTS_CS).unwrap() };
+        let layout = EmptyPipeline::new(&device).unwrap();
+        let name = CString::new("main").unwrap();
+        let entry_point = unsafe {
+            module.compute_shader_entry_point::<(), _>(&name, EmptyPipelineDesc)
+        };
+
+        match ComputePipeline::new(&device, &layout, &entry_point, None,
+                                   PipelineDerivative::None)
+        {
+            Err(ComputePipelineCreationError::InvalidEntryPoint(EntryPointError::Missing)) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn wrong_execution_model() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let module = unsafe { ShaderModule::new(&device, &TWO_ENTRY_POINTS_CS).unwrap() };
+        let name = CString::new("first").unwrap();
+
+        assert_eq!(module.check_entry_point(&name, ExecutionModel::Vertex),
+                   Err(EntryPointError::WrongExecutionModel {
+                       expected: ExecutionModel::Vertex,
+                       obtained: ExecutionModel::Compute,
+                   }));
+    }
+
+    // Compute shader module with two empty entry points named `first` and `second`.
+    const TWO_ENTRY_POINTS_CS: [u8; 220] = [3, 2, 35, 7, 0, 0, 1, 0, 0, 0, 0, 0, 7, 0, 0, 0, 0,
+        0, 0, 0, 17, 0, 2, 0, 1, 0, 0, 0, 14, 0, 3, 0, 0, 0, 0, 0, 1, 0, 0, 0, 15, 0, 5, 0, 5, 0,
+        0, 0, 1, 0, 0, 0, 102, 105, 114, 115, 116, 0, 0, 0, 15, 0, 5, 0, 5, 0, 0, 0, 2, 0, 0, 0,
+        115, 101, 99, 111, 110, 100, 0, 0, 16, 0, 6, 0, 1, 0, 0, 0, 17, 0, 0, 0, 1, 0, 0, 0, 1, 0,
+        0, 0, 1, 0, 0, 0, 16, 0, 6, 0, 2, 0, 0, 0, 17, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0,
+        19, 0, 2, 0, 3, 0, 0, 0, 33, 0, 3, 0, 4, 0, 0, 0, 3, 0, 0, 0, 54, 0, 5, 0, 3, 0, 0, 0, 1, 0,
+        0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 248, 0, 2, 0, 5, 0, 0, 0, 253, 0, 1, 0, 56, 0, 1, 0, 54, 0, 5,
+        0, 3, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 248, 0, 2, 0, 6, 0, 0, 0, 253, 0, 1, 0,
+        56, 0, 1, 0];
+}
@@ -63,6 +63,8 @@
 //! # }
 //! ```
 //!
+use std::error;
+use std::fmt;
 use std::marker::PhantomData;
 use std::mem;
 use std::option::IntoIter as OptionIntoIter;
@@ -164,8 +166,116 @@ pub unsafe trait Definition<I>: 'static + Send + Sync {
 
     /// Builds the vertex definition to use to link this definition to a vertex shader's input
     /// interface.
-    // TODO: return error if problem
-    fn definition(&self, interface: &I) -> (Self::BuffersIter, Self::AttribsIter);
+    fn definition(&self, interface: &I)
+                  -> Result<(Self::BuffersIter, Self::AttribsIter), IncompatibleVertexDefinitionError>;
+}
+
+/// Error that can happen when the vertex definition doesn't match the input of the vertex shader.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IncompatibleVertexDefinitionError {
+    /// An attribute of the vertex shader is missing in the vertex source.
+    MissingAttribute {
+        /// Name of the missing attribute.
+        attribute: String,
+    },
+
+    /// An attribute of the vertex shader is present in more than one vertex buffer.
+    DuplicateAttribute {
+        /// Name of the attribute.
+        attribute: String,
+    },
+
+    /// Two attributes of the vertex shader use the same location.
+    DuplicateLocation {
+        /// The location used twice.
+        location: u32,
+    },
+
+    /// The format of an attribute of the vertex source doesn't match what the vertex shader
+    /// expects.
+    FormatMismatch {
+        /// Name of the attribute.
+        attribute: String,
+    },
+}
+
+impl error::Error for IncompatibleVertexDefinitionError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            IncompatibleVertexDefinitionError::MissingAttribute { .. } => {
+                "an attribute is missing"
+            },
+            IncompatibleVertexDefinitionError::DuplicateAttribute { .. } => {
+                "an attribute is present in more than one vertex buffer"
+            },
+            IncompatibleVertexDefinitionError::DuplicateLocation { .. } => {
+                "two attributes use the same location"
+            },
+            IncompatibleVertexDefinitionError::FormatMismatch { .. } => {
+                "the format of an attribute does not match"
+            },
+        }
+    }
+}
+
+impl fmt::Display for IncompatibleVertexDefinitionError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+// Builds the list of attributes of a vertex definition from the input interface of a vertex
+// shader. `members` contains the `Vertex::member` function of the vertex type of each binding,
+// in the order of the bindings.
+fn build_attributes<I>(interface: &I, members: &[fn(&str) -> Option<VertexMemberInfo>])
+                       -> Result<Vec<(u32, u32, AttributeInfo)>, IncompatibleVertexDefinitionError>
+    where I: ShaderInterfaceDef
+{
+    let mut attribs: Vec<(u32, u32, AttributeInfo)> = Vec::with_capacity(interface.elements()
+                                                                                  .len());
+
+    for e in interface.elements() {
+        let name = e.name.as_ref().unwrap();
+
+        let mut found = None;
+        for (binding, member) in members.iter().enumerate() {
+            if let Some(infos) = member(name) {
+                if found.is_some() {
+                    return Err(IncompatibleVertexDefinitionError::DuplicateAttribute {
+                        attribute: name.clone().into_owned(),
+                    });
+                }
+                found = Some((infos, binding as u32));
+            }
+        }
+
+        let (infos, binding) = match found {
+            Some(f) => f,
+            None => return Err(IncompatibleVertexDefinitionError::MissingAttribute {
+                attribute: name.clone().into_owned(),
+            }),
+        };
+
+        if !infos.ty.matches(infos.array_size, e.format, e.location.end - e.location.start) {
+            return Err(IncompatibleVertexDefinitionError::FormatMismatch {
+                attribute: name.clone().into_owned(),
+            });
+        }
+
+        let mut offset = infos.offset;
+        for loc in e.location.clone() {
+            if attribs.iter().any(|&(l, _, _)| l == loc) {
+                return Err(IncompatibleVertexDefinitionError::DuplicateLocation { location: loc });
+            }
+
+            attribs.push((loc, binding, AttributeInfo { offset: offset, format: e.format }));
+            offset += e.format.size().unwrap();
+        }
+    }
+
+    Ok(attribs)
 }
 
 /// Extension trait of `Definition`. The `L` parameter is an acceptable vertex source for this
@@ -193,25 +303,12 @@ unsafe impl<T, I> Definition<I> for SingleBufferDefinition<T>
     type BuffersIter = OptionIntoIter<(u32, usize, InputRate)>;
     type AttribsIter = VecIntoIter<(u32, u32, AttributeInfo)>;
 
-    fn definition(&self, interface: &I) -> (Self::BuffersIter, Self::AttribsIter) {
-        let attrib = {
-            let mut attribs = Vec::with_capacity(interface.elements().len());
-            for e in interface.elements() {
-                let infos = <T as Vertex>::member(e.name.as_ref().unwrap()).expect("missing vertex attrib");
-                assert!(infos.ty.matches(infos.array_size, e.format, e.location.end - e.location.start),
-                        "Vertex attribute `{:?}` does not match expected format", e.name);
-
-                let mut offset = infos.offset;
-                for loc in e.location.clone() {
-                    attribs.push((loc, 0, AttributeInfo { offset: offset, format: e.format }));
-                    offset += e.format.size().unwrap();
-                }
-            }
-            attribs
-        }.into_iter();      // TODO: meh
-
+    fn definition(&self, interface: &I)
+                  -> Result<(Self::BuffersIter, Self::AttribsIter), IncompatibleVertexDefinitionError>
+    {
+        let attribs = try!(build_attributes(interface, &[<T as Vertex>::member])).into_iter();
         let buffers = Some((0, mem::size_of::<T>(), InputRate::Vertex)).into_iter();
-        (buffers, attrib)
+        Ok((buffers, attribs))
     }
 }
 
@@ -227,7 +324,14 @@ unsafe impl<'a, B, V> Source<&'a Arc<B>> for SingleBufferDefinition<V>
     }
 }
 
-/// Unstable.
+/// Implementation of `Definition` for two vertex buffers that are both read per vertex.
+///
+/// The attributes of the vertex shader are looked up in both `T` and `U`, and an attribute
+/// must be present in exactly one of them. This is typically used to store the positions in
+/// their own buffer, so that passes that only need the positions don't read the other
+/// attributes.
+///
+/// The number of vertices to draw is the minimum of the lengths of the two buffers.
 // TODO: shouldn't be just `Two` but `Multi`
 pub struct TwoBuffersDefinition<T, U>(pub PhantomData<(T, U)>);
 
@@ -242,34 +346,18 @@ unsafe impl<T, U, I> Definition<I> for TwoBuffersDefinition<T, U>
     type BuffersIter = VecIntoIter<(u32, usize, InputRate)>;
     type AttribsIter = VecIntoIter<(u32, u32, AttributeInfo)>;
 
-    fn definition(&self, interface: &I) -> (Self::BuffersIter, Self::AttribsIter) {
-        let attrib = {
-            let mut attribs = Vec::with_capacity(interface.elements().len());
-            for e in interface.elements() {
-                let (infos, buf_offset) = if let Some(infos) = <T as Vertex>::member(e.name.as_ref().unwrap()) {
-                    (infos, 0)
-                } else if let Some(infos) = <U as Vertex>::member(e.name.as_ref().unwrap()) {
-                    (infos, 1)
-                } else {
-                    panic!("missing vertex attrib")
-                };
-                assert!(infos.ty.matches(infos.array_size, e.format, e.location.end - e.location.start));
-
-                let mut offset = infos.offset;
-                for loc in e.location.clone() {
-                    attribs.push((loc, buf_offset, AttributeInfo { offset: offset, format: e.format }));
-                    offset += e.format.size().unwrap();
-                }
-            }
-            attribs
-        }.into_iter();      // TODO: meh
+    fn definition(&self, interface: &I)
+                  -> Result<(Self::BuffersIter, Self::AttribsIter), IncompatibleVertexDefinitionError>
+    {
+        let attribs = try!(build_attributes(interface, &[<T as Vertex>::member,
+                                                         <U as Vertex>::member])).into_iter();
 
         let buffers = vec![
             (0, mem::size_of::<T>(), InputRate::Vertex),
             (1, mem::size_of::<U>(), InputRate::Vertex)
         ].into_iter();
 
-        (buffers, attrib)
+        Ok((buffers, attribs))
     }
 }
 
@@ -303,34 +391,18 @@ unsafe impl<T, U, I> Definition<I> for OneVertexOneInstanceDefinition<T, U>
     type BuffersIter = VecIntoIter<(u32, usize, InputRate)>;
     type AttribsIter = VecIntoIter<(u32, u32, AttributeInfo)>;
 
-    fn definition(&self, interface: &I) -> (Self::BuffersIter, Self::AttribsIter) {
-        let attrib = {
-            let mut attribs = Vec::with_capacity(interface.elements().len());
-            for e in interface.elements() {
-                let (infos, buf_offset) = if let Some(infos) = <T as Vertex>::member(e.name.as_ref().unwrap()) {
-                    (infos, 0)
-                } else if let Some(infos) = <U as Vertex>::member(e.name.as_ref().unwrap()) {
-                    (infos, 1)
-                } else {
-                    panic!("missing vertex attrib")
-                };
-                assert!(infos.ty.matches(infos.array_size, e.format, e.location.end - e.location.start));
-
-                let mut offset = infos.offset;
-                for loc in e.location.clone() {
-                    attribs.push((loc, buf_offset, AttributeInfo { offset: offset, format: e.format }));
-                    offset += e.format.size().unwrap();
-                }
-            }
-            attribs
-        }.into_iter();      // TODO: meh
+    fn definition(&self, interface: &I)
+                  -> Result<(Self::BuffersIter, Self::AttribsIter), IncompatibleVertexDefinitionError>
+    {
+        let attribs = try!(build_attributes(interface, &[<T as Vertex>::member,
+                                                         <U as Vertex>::member])).into_iter();
 
         let buffers = vec![
             (0, mem::size_of::<T>(), InputRate::Vertex),
             (1, mem::size_of::<U>(), InputRate::Instance)
         ].into_iter();
 
-        (buffers, attrib)
+        Ok((buffers, attribs))
     }
 }
 
@@ -523,3 +595,171 @@ impl_vm_array!(15);
 impl_vm_array!(16);
 impl_vm_array!(32);
 impl_vm_array!(64);
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::vec::IntoIter as VecIntoIter;
+
+    use buffer::BufferUsage;
+    use buffer::CpuAccessibleBuffer;
+    use format::Format;
+    use pipeline::shader::ShaderInterfaceDef;
+    use pipeline::shader::ShaderInterfaceDefEntry;
+    use pipeline::vertex::Definition;
+    use pipeline::vertex::IncompatibleVertexDefinitionError;
+    use pipeline::vertex::InputRate;
+    use pipeline::vertex::OneVertexOneInstanceDefinition;
+    use pipeline::vertex::SingleBufferDefinition;
+    use pipeline::vertex::Source;
+    use pipeline::vertex::TwoBuffersDefinition;
+    use pipeline::vertex::Vertex;
+    use pipeline::vertex::VertexMemberInfo;
+    use pipeline::vertex::VertexMemberTy;
+
+    // Implements `Vertex` for a struct whose members are all `f32` arrays.
+    macro_rules! f32_vertex {
+        ($name:ident { $($member:ident: $len:expr),* }) => (
+            #[allow(dead_code)]
+            struct $name {
+                $($member: [f32; $len]),*
+            }
+
+            unsafe impl Vertex for $name {
+                fn member(name: &str) -> Option<VertexMemberInfo> {
+                    let mut offset = 0;
+                    $(
+                        if name == stringify!($member) {
+                            return Some(VertexMemberInfo {
+                                offset: offset,
+                                ty: VertexMemberTy::F32,
+                                array_size: $len,
+                            });
+                        }
+                        offset += $len * 4;
+                    )*
+                    let _ = offset;
+                    None
+                }
+            }
+        );
+    }
+
+    f32_vertex!(Position { position: 2 });
+    f32_vertex!(Attributes { color: 4, tex_coords: 2 });
+    f32_vertex!(ColorAndPosition { color: 4, position: 2 });
+
+    // Input interface of a vertex shader, as a list of `(name, location, format)`.
+    struct Interface(Vec<(&'static str, u32, Format)>);
+
+    unsafe impl ShaderInterfaceDef for Interface {
+        type Iter = VecIntoIter<ShaderInterfaceDefEntry>;
+
+        fn elements(&self) -> VecIntoIter<ShaderInterfaceDefEntry> {
+            self.0.iter().map(|&(name, location, format)| {
+                ShaderInterfaceDefEntry {
+                    location: location .. location + 1,
+                    format: format,
+                    name: Some(Cow::Borrowed(name)),
+                }
+            }).collect::<Vec<_>>().into_iter()
+        }
+    }
+
+    fn position_and_color() -> Interface {
+        Interface(vec![("position", 0, Format::R32G32Sfloat),
+                       ("color", 1, Format::R32G32B32A32Sfloat)])
+    }
+
+    #[test]
+    fn two_buffers() {
+        let def = TwoBuffersDefinition::<Position, Attributes>::new();
+        let (buffers, attribs) = def.definition(&position_and_color()).unwrap();
+
+        let buffers = buffers.collect::<Vec<_>>();
+        assert_eq!(buffers.len(), 2);
+        match buffers[0] { (0, 8, InputRate::Vertex) => (), _ => panic!() }
+        match buffers[1] { (1, 24, InputRate::Vertex) => (), _ => panic!() }
+
+        let attribs = attribs.collect::<Vec<_>>();
+        assert_eq!(attribs.len(), 2);
+        assert_eq!((attribs[0].0, attribs[0].1, attribs[0].2.offset), (0, 0, 0));
+        assert_eq!((attribs[1].0, attribs[1].1, attribs[1].2.offset), (1, 1, 0));
+    }
+
+    #[test]
+    fn two_buffers_duplicate_attribute() {
+        let def = TwoBuffersDefinition::<Position, ColorAndPosition>::new();
+
+        match def.definition(&position_and_color()) {
+            Err(IncompatibleVertexDefinitionError::DuplicateAttribute { ref attribute }) => {
+                assert_eq!(attribute, "position");
+            },
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn duplicate_location() {
+        let def = TwoBuffersDefinition::<Position, Attributes>::new();
+        let interface = Interface(vec![("position", 0, Format::R32G32Sfloat),
+                                       ("tex_coords", 0, Format::R32G32Sfloat)]);
+
+        match def.definition(&interface) {
+            Err(IncompatibleVertexDefinitionError::DuplicateLocation { location: 0 }) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn missing_attribute() {
+        let def = SingleBufferDefinition::<Position>::new();
+
+        match def.definition(&position_and_color()) {
+            Err(IncompatibleVertexDefinitionError::MissingAttribute { ref attribute }) => {
+                assert_eq!(attribute, "color");
+            },
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn format_mismatch() {
+        let def = SingleBufferDefinition::<Position>::new();
+        let interface = Interface(vec![("position", 0, Format::R32G32B32A32Sfloat)]);
+
+        match def.definition(&interface) {
+            Err(IncompatibleVertexDefinitionError::FormatMismatch { ref attribute }) => {
+                assert_eq!(attribute, "position");
+            },
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn one_vertex_one_instance() {
+        let def = OneVertexOneInstanceDefinition::<Position, Attributes>::new();
+        let (buffers, _) = def.definition(&position_and_color()).unwrap();
+
+        let buffers = buffers.collect::<Vec<_>>();
+        match buffers[0] { (0, 8, InputRate::Vertex) => (), _ => panic!() }
+        match buffers[1] { (1, 24, InputRate::Instance) => (), _ => panic!() }
+    }
+
+    #[test]
+    fn two_buffers_vertex_count() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let usage = BufferUsage { vertex_buffer: true, .. BufferUsage::none() };
+
+        let positions = CpuAccessibleBuffer::<[Position]>::array(&device, 12, &usage,
+                                                                 Some(queue.family())).unwrap();
+        let attributes = CpuAccessibleBuffer::<[Attributes]>::array(&device, 9, &usage,
+                                                                    Some(queue.family())).unwrap();
+
+        let def = TwoBuffersDefinition::<Position, Attributes>::new();
+        let (buffers, vertices, instances) = def.decode((&positions, &attributes));
+        assert_eq!(buffers.len(), 2);
+        assert_eq!(vertices, 9);
+        assert_eq!(instances, 1);
+    }
+}
@@ -33,6 +33,11 @@
 //! If you don't use a geometry shader or use a geometry shader where don't set which viewport to
 //! use, then the first viewport and scissor box will be used.
 //!
+//! Using more than one viewport requires the `multi_viewport` feature to be enabled, and the
+//! number of viewports can't exceed the `max_viewports` limit of the physical device. When the
+//! viewports or scissor boxes are dynamic, the number of values passed when drawing must match
+//! the number of viewports of the pipeline.
+//!
 //! # Dynamic and fixed
 //!
 //! Vulkan allows four different setups:
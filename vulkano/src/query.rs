@@ -13,29 +13,67 @@
 //! represent a collection of queries. Whenever you use a query, you have to specify both the query
 //! pool and the slot id within that query pool.
 
+use std::error;
+use std::fmt;
 use std::mem;
+use std::ops::Range;
 use std::ptr;
+use std::slice;
 use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::u64;
 
 use device::Device;
+use instance::QueueFamily;
 
 use check_errors;
+use Error;
 use OomError;
+use Success;
 use VulkanObject;
 use VulkanPointers;
 use vk;
 
-pub struct OcclusionQueriesPool {
+/// A pool of queries of any type.
+///
+/// This is the low-level wrapper around a `VkQueryPool`. It doesn't keep track of the state of
+/// its queries.
+#[derive(Debug)]
+pub struct UnsafeQueryPool {
     pool: vk::QueryPool,
-    num_slots: u32,
     device: Arc<Device>,
+    num_slots: u32,
+    ty: QueryType,
 }
 
-impl OcclusionQueriesPool {
-    /// See the docs of new().
-    pub fn raw(device: &Arc<Device>, num_slots: u32)
-               -> Result<OcclusionQueriesPool, OomError>
+impl UnsafeQueryPool {
+    /// Builds a new query pool containing `num_slots` queries of type `ty`.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if `num_slots` is 0.
+    /// - Panicks if `ty` is a pipeline statistics type without any counter.
+    ///
+    pub fn new(device: &Arc<Device>, ty: QueryType, num_slots: u32)
+               -> Result<Arc<UnsafeQueryPool>, QueryPoolCreationError>
     {
+        assert!(num_slots >= 1, "A query pool must contain at least one query");
+
+        let statistics = match ty {
+            QueryType::Occlusion | QueryType::Timestamp => 0,
+            QueryType::PipelineStatistics(flags) => {
+                assert!(flags != QueryPipelineStatisticFlags::none(),
+                        "A pipeline statistics query must contain at least one counter");
+
+                if !device.enabled_features().pipeline_statistics_query {
+                    return Err(QueryPoolCreationError::PipelineStatisticsQueryFeatureNotEnabled);
+                }
+
+                flags.into()
+            },
+        };
+
         let vk = device.pointers();
 
         let pool = unsafe {
@@ -43,9 +81,9 @@ impl OcclusionQueriesPool {
                 sType: vk::STRUCTURE_TYPE_QUERY_POOL_CREATE_INFO,
                 pNext: ptr::null(),
                 flags: 0,   // reserved
-                queryType: vk::QUERY_TYPE_OCCLUSION,
+                queryType: ty.into(),
                 queryCount: num_slots,
-                pipelineStatistics: 0,
+                pipelineStatistics: statistics,
             };
 
             let mut output = mem::uninitialized();
@@ -54,10 +92,442 @@ impl OcclusionQueriesPool {
             output
         };
 
-        Ok(OcclusionQueriesPool {
+        Ok(Arc::new(UnsafeQueryPool {
             pool: pool,
-            num_slots: num_slots,
             device: device.clone(),
+            num_slots: num_slots,
+            ty: ty,
+        }))
+    }
+
+    /// Returns the type of the queries of this pool.
+    #[inline]
+    pub fn ty(&self) -> QueryType {
+        self.ty
+    }
+
+    /// Returns the number of queries of this pool.
+    #[inline]
+    pub fn count(&self) -> u32 {
+        self.num_slots
+    }
+
+    /// Returns the device that was used to create this pool.
+    #[inline]
+    pub fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+
+    /// Returns the `count` queries of this pool starting at `first`, or `None` if `count` is 0
+    /// or if the range is out of the pool.
+    #[inline]
+    pub fn queries_range(&self, first: u32, count: u32) -> Option<UnsafeQueriesRange> {
+        if count == 0 {
+            return None;
+        }
+
+        match first.checked_add(count) {
+            Some(end) if end <= self.num_slots => {
+                Some(UnsafeQueriesRange {
+                    pool: self,
+                    first: first,
+                    count: count,
+                })
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the number of values that each query of this pool contains, not counting the
+    /// availability value.
+    #[inline]
+    pub fn values_per_query(&self) -> usize {
+        match self.ty {
+            QueryType::PipelineStatistics(flags) => flags.num_counters(),
+            QueryType::Occlusion | QueryType::Timestamp => 1,
+        }
+    }
+
+    /// Returns the number of bytes that `get_results` writes for each query with these flags.
+    #[inline]
+    pub fn result_stride(&self, flags: QueryResultFlags) -> usize {
+        result_stride(self.values_per_query(), flags)
+    }
+
+    /// Writes the results of the queries of `range` in `destination`, by calling
+    /// `vkGetQueryPoolResults`.
+    ///
+    /// The results of each query take `result_stride(flags)` bytes, and are made of
+    /// `values_per_query()` values optionally followed by the availability value. Each value is
+    /// a `u32`, or a `u64` if `flags.result_64` is true.
+    ///
+    /// Returns `Ok(false)` if `flags.wait` is false and the results of some queries are not
+    /// available yet. The values of these queries are only written if `flags.partial` is true.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if `range` is empty or out of the pool.
+    /// - Panicks if `destination` is too small to contain the results.
+    /// - Panicks if `destination` isn't aligned to the size of a value.
+    /// - Panicks if `flags.partial` is true and this is a timestamp pool.
+    ///
+    /// # Safety
+    ///
+    /// - If `flags.wait` is true, all the queries of `range` must have been written by a command
+    ///   buffer that has been submitted. Otherwise the call can block forever or lose the device.
+    ///
+    pub unsafe fn get_results(&self, range: Range<u32>, destination: &mut [u8],
+                              flags: QueryResultFlags) -> Result<bool, GetResultsError>
+    {
+        let range = self.queries_range(range.start, range.end.saturating_sub(range.start))
+                        .expect("The range of queries is empty or out of the pool");
+        assert!(!flags.partial || self.ty != QueryType::Timestamp,
+                "Partial results can't be used with timestamp queries");
+
+        let stride = self.result_stride(flags);
+        let size = stride * range.count() as usize;
+        assert!(destination.len() >= size, "The destination is too small for the results");
+
+        let alignment = if flags.result_64 { 8 } else { 4 };
+        assert_eq!(destination.as_ptr() as usize % alignment, 0,
+                   "The destination isn't aligned to the size of a value");
+
+        let vk = self.device.pointers();
+        let result = vk.GetQueryPoolResults(self.device.internal_object(), self.pool,
+                                            range.first_index(), range.count(), size,
+                                            destination.as_mut_ptr() as *mut _,
+                                            stride as vk::DeviceSize, flags.into());
+
+        match try!(check_errors(result)) {
+            Success::Success => Ok(true),
+            Success::NotReady => Ok(false),
+            _ => unreachable!()
+        }
+    }
+}
+
+unsafe impl VulkanObject for UnsafeQueryPool {
+    type Object = vk::QueryPool;
+
+    #[inline]
+    fn internal_object(&self) -> vk::QueryPool {
+        self.pool
+    }
+}
+
+impl Drop for UnsafeQueryPool {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe {
+            let vk = self.device.pointers();
+            vk.DestroyQueryPool(self.device.internal_object(), self.pool, ptr::null());
+        }
+    }
+}
+
+/// A range of consecutive queries of an `UnsafeQueryPool`.
+///
+/// The range is guaranteed to be non-empty and within the pool.
+#[derive(Debug, Copy, Clone)]
+pub struct UnsafeQueriesRange<'a> {
+    pool: &'a UnsafeQueryPool,
+    first: u32,
+    count: u32,
+}
+
+impl<'a> UnsafeQueriesRange<'a> {
+    /// Returns the pool the queries belong to.
+    #[inline]
+    pub fn pool(&self) -> &'a UnsafeQueryPool {
+        self.pool
+    }
+
+    /// Returns the index of the first query of the range.
+    #[inline]
+    pub fn first_index(&self) -> u32 {
+        self.first
+    }
+
+    /// Returns the number of queries of the range.
+    #[inline]
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+}
+
+/// Type of the queries of a query pool.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum QueryType {
+    /// Counts the number of samples that pass the depth and stencil tests.
+    Occlusion,
+    /// Records the time at which a pipeline stage has been reached, in device ticks.
+    Timestamp,
+    /// Counts the operations of the pipeline that are enabled in the flags. Requires the
+    /// `pipeline_statistics_query` feature.
+    PipelineStatistics(QueryPipelineStatisticFlags),
+}
+
+#[doc(hidden)]
+impl Into<vk::QueryType> for QueryType {
+    #[inline]
+    fn into(self) -> vk::QueryType {
+        match self {
+            QueryType::Occlusion => vk::QUERY_TYPE_OCCLUSION,
+            QueryType::Timestamp => vk::QUERY_TYPE_TIMESTAMP,
+            QueryType::PipelineStatistics(_) => vk::QUERY_TYPE_PIPELINE_STATISTICS,
+        }
+    }
+}
+
+/// Which counters a pipeline statistics query contains.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct QueryPipelineStatisticFlags {
+    /// Number of vertices processed by the input assembly stage.
+    pub input_assembly_vertices: bool,
+    /// Number of primitives processed by the input assembly stage.
+    pub input_assembly_primitives: bool,
+    /// Number of vertex shader invocations.
+    pub vertex_shader_invocations: bool,
+    /// Number of geometry shader invocations.
+    pub geometry_shader_invocations: bool,
+    /// Number of primitives generated by geometry shader invocations.
+    pub geometry_shader_primitives: bool,
+    /// Number of primitives processed by the clipping stage.
+    pub clipping_invocations: bool,
+    /// Number of primitives output by the clipping stage.
+    pub clipping_primitives: bool,
+    /// Number of fragment shader invocations.
+    pub fragment_shader_invocations: bool,
+    /// Number of patches processed by the tessellation control shader.
+    pub tessellation_control_shader_patches: bool,
+    /// Number of tessellation evaluation shader invocations.
+    pub tessellation_evaluation_shader_invocations: bool,
+    /// Number of compute shader invocations.
+    pub compute_shader_invocations: bool,
+}
+
+impl QueryPipelineStatisticFlags {
+    /// Builds a `QueryPipelineStatisticFlags` with all the counters disabled.
+    #[inline]
+    pub fn none() -> QueryPipelineStatisticFlags {
+        QueryPipelineStatisticFlags {
+            input_assembly_vertices: false,
+            input_assembly_primitives: false,
+            vertex_shader_invocations: false,
+            geometry_shader_invocations: false,
+            geometry_shader_primitives: false,
+            clipping_invocations: false,
+            clipping_primitives: false,
+            fragment_shader_invocations: false,
+            tessellation_control_shader_patches: false,
+            tessellation_evaluation_shader_invocations: false,
+            compute_shader_invocations: false,
+        }
+    }
+
+    /// Builds a `QueryPipelineStatisticFlags` with all the counters enabled.
+    #[inline]
+    pub fn all() -> QueryPipelineStatisticFlags {
+        QueryPipelineStatisticFlags {
+            input_assembly_vertices: true,
+            input_assembly_primitives: true,
+            vertex_shader_invocations: true,
+            geometry_shader_invocations: true,
+            geometry_shader_primitives: true,
+            clipping_invocations: true,
+            clipping_primitives: true,
+            fragment_shader_invocations: true,
+            tessellation_control_shader_patches: true,
+            tessellation_evaluation_shader_invocations: true,
+            compute_shader_invocations: true,
+        }
+    }
+
+    /// Returns the number of counters that are enabled.
+    #[inline]
+    pub fn num_counters(&self) -> usize {
+        let flags: vk::QueryPipelineStatisticFlags = (*self).into();
+        flags.count_ones() as usize
+    }
+}
+
+#[doc(hidden)]
+impl Into<vk::QueryPipelineStatisticFlags> for QueryPipelineStatisticFlags {
+    #[inline]
+    fn into(self) -> vk::QueryPipelineStatisticFlags {
+        let mut result = 0;
+        if self.input_assembly_vertices {
+            result |= vk::QUERY_PIPELINE_STATISTIC_INPUT_ASSEMBLY_VERTICES_BIT;
+        }
+        if self.input_assembly_primitives {
+            result |= vk::QUERY_PIPELINE_STATISTIC_INPUT_ASSEMBLY_PRIMITIVES_BIT;
+        }
+        if self.vertex_shader_invocations {
+            result |= vk::QUERY_PIPELINE_STATISTIC_VERTEX_SHADER_INVOCATIONS_BIT;
+        }
+        if self.geometry_shader_invocations {
+            result |= vk::QUERY_PIPELINE_STATISTIC_GEOMETRY_SHADER_INVOCATIONS_BIT;
+        }
+        if self.geometry_shader_primitives {
+            result |= vk::QUERY_PIPELINE_STATISTIC_GEOMETRY_SHADER_PRIMITIVES_BIT;
+        }
+        if self.clipping_invocations {
+            result |= vk::QUERY_PIPELINE_STATISTIC_CLIPPING_INVOCATIONS_BIT;
+        }
+        if self.clipping_primitives {
+            result |= vk::QUERY_PIPELINE_STATISTIC_CLIPPING_PRIMITIVES_BIT;
+        }
+        if self.fragment_shader_invocations {
+            result |= vk::QUERY_PIPELINE_STATISTIC_FRAGMENT_SHADER_INVOCATIONS_BIT;
+        }
+        if self.tessellation_control_shader_patches {
+            result |= vk::QUERY_PIPELINE_STATISTIC_TESSELLATION_CONTROL_SHADER_PATCHES_BIT;
+        }
+        if self.tessellation_evaluation_shader_invocations {
+            result |= vk::QUERY_PIPELINE_STATISTIC_TESSELLATION_EVALUATION_SHADER_INVOCATIONS_BIT;
+        }
+        if self.compute_shader_invocations {
+            result |= vk::QUERY_PIPELINE_STATISTIC_COMPUTE_SHADER_INVOCATIONS_BIT;
+        }
+        result
+    }
+}
+
+/// Error that can happen when creating a query pool.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum QueryPoolCreationError {
+    /// Not enough memory.
+    OomError(OomError),
+    /// A pipeline statistics pool was requested but the `pipeline_statistics_query` feature
+    /// isn't enabled.
+    PipelineStatisticsQueryFeatureNotEnabled,
+    /// A timestamp pool was requested for a queue family that doesn't support timestamps.
+    TimestampsNotSupported,
+    /// The Vulkan implementation returned an error that this operation isn't supposed to return.
+    Unexpected(Error),
+}
+
+impl error::Error for QueryPoolCreationError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            QueryPoolCreationError::OomError(_) => "not enough memory available",
+            QueryPoolCreationError::PipelineStatisticsQueryFeatureNotEnabled => {
+                "a pipeline statistics pool was requested but the corresponding feature \
+                 wasn't enabled"
+            },
+            QueryPoolCreationError::TimestampsNotSupported => {
+                "a timestamp pool was requested for a queue family that doesn't support \
+                 timestamps"
+            },
+            QueryPoolCreationError::Unexpected(_) => {
+                "unexpected error returned by the Vulkan implementation"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            QueryPoolCreationError::OomError(ref err) => Some(err),
+            QueryPoolCreationError::Unexpected(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for QueryPoolCreationError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<OomError> for QueryPoolCreationError {
+    #[inline]
+    fn from(err: OomError) -> QueryPoolCreationError {
+        QueryPoolCreationError::OomError(err)
+    }
+}
+
+impl From<Error> for QueryPoolCreationError {
+    #[inline]
+    fn from(err: Error) -> QueryPoolCreationError {
+        match err {
+            err @ Error::OutOfHostMemory => QueryPoolCreationError::OomError(OomError::from(err)),
+            err @ Error::OutOfDeviceMemory => {
+                QueryPoolCreationError::OomError(OomError::from(err))
+            },
+            _ => QueryPoolCreationError::Unexpected(err)
+        }
+    }
+}
+
+/// Trait for objects that contain a query pool and that can be used with the query commands of
+/// command buffers.
+///
+/// The methods other than `inner_query_pool` are called by the command buffer builders whenever a
+/// command that uses a query of the pool is recorded, which lets the implementation keep track of
+/// the state of its queries.
+pub unsafe trait QueryPool: 'static + Send + Sync {
+    /// Returns the inner unsafe query pool.
+    fn inner_query_pool(&self) -> &UnsafeQueryPool;
+
+    /// Called when a command that resets the `count` queries starting at `first` is recorded.
+    fn queries_reset(&self, first: u32, count: u32);
+
+    /// Called when a command that begins `query` is recorded.
+    fn query_begun(&self, query: u32);
+
+    /// Called when a command that ends or writes `query` is recorded.
+    fn query_written(&self, query: u32);
+}
+
+unsafe impl QueryPool for UnsafeQueryPool {
+    #[inline]
+    fn inner_query_pool(&self) -> &UnsafeQueryPool {
+        self
+    }
+
+    #[inline]
+    fn queries_reset(&self, _: u32, _: u32) {
+    }
+
+    #[inline]
+    fn query_begun(&self, _: u32) {
+    }
+
+    #[inline]
+    fn query_written(&self, _: u32) {
+    }
+}
+
+/// A pool of occlusion queries.
+///
+/// In debug builds, the pool keeps track of the queries that were reset and written by the
+/// commands recorded in command buffers, and panics if you try to begin a query that was never
+/// reset or to retrieve the result of a query that was never written.
+#[derive(Debug)]
+pub struct OcclusionQueriesPool {
+    inner: Arc<UnsafeQueryPool>,
+    tracker: QueriesTracker,
+}
+
+impl OcclusionQueriesPool {
+    /// See the docs of new().
+    pub fn raw(device: &Arc<Device>, num_slots: u32)
+               -> Result<OcclusionQueriesPool, OomError>
+    {
+        let inner = match UnsafeQueryPool::new(device, QueryType::Occlusion, num_slots) {
+            Ok(pool) => pool,
+            Err(QueryPoolCreationError::OomError(err)) => return Err(err),
+            Err(QueryPoolCreationError::Unexpected(err)) => return Err(OomError::Unexpected(err)),
+            Err(err) => unreachable!("unexpected error: {:?}", err),
+        };
+
+        Ok(OcclusionQueriesPool {
+            inner: inner,
+            tracker: QueriesTracker::new(num_slots),
         })
     }
 
@@ -66,6 +536,7 @@ impl OcclusionQueriesPool {
     /// # Panic
     ///
     /// - Panicks if the device or host ran out of memory.
+    /// - Panicks if `num_slots` is 0.
     ///
     #[inline]
     pub fn new(device: &Arc<Device>, num_slots: u32)
@@ -77,32 +548,1121 @@ impl OcclusionQueriesPool {
     /// Returns the number of slots of that query pool.
     #[inline]
     pub fn num_slots(&self) -> u32 {
-        self.num_slots
+        self.inner.count()
     }
 
     /// Returns the device that was used to create this pool.
     #[inline]
     pub fn device(&self) -> &Arc<Device> {
-        &self.device
+        self.inner.device()
+    }
+
+    /// Retrieves the number of samples that passed the tests for each query of `range`.
+    ///
+    /// If `flags.wait` is true, this function blocks until all the results are available.
+    /// Otherwise `with_availability` is implied and the queries whose result isn't available yet
+    /// are `None`. The width of the results that are requested from the implementation depends on
+    /// `flags.result_64`, but they are always returned as `u64`s.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if `range` is empty or out of the pool.
+    /// - In debug builds, panicks if one of the queries of `range` was never written by a command
+    ///   recorded in a command buffer.
+    ///
+    pub fn get_results(&self, range: Range<u32>, flags: QueryResultFlags)
+                       -> Result<Vec<Option<u64>>, GetResultsError>
+    {
+        let range = self.inner.queries_range(range.start, range.end.saturating_sub(range.start))
+                              .expect("The range of queries is empty or out of the pool");
+        self.tracker.check_written(range.first_index(), range.count());
+
+        let results = try!(read_results(range, flags));
+        Ok(results.into_iter().map(|r| r.map(|values| values[0])).collect())
     }
 }
 
-impl Drop for OcclusionQueriesPool {
-    fn drop(&mut self) {
-        unsafe {
-            let vk = self.device.pointers();
-            vk.DestroyQueryPool(self.device.internal_object(), self.pool, ptr::null());
-        }
+unsafe impl QueryPool for OcclusionQueriesPool {
+    #[inline]
+    fn inner_query_pool(&self) -> &UnsafeQueryPool {
+        &self.inner
+    }
+
+    #[inline]
+    fn queries_reset(&self, first: u32, count: u32) {
+        self.tracker.reset(first, count);
+    }
+
+    #[inline]
+    fn query_begun(&self, query: u32) {
+        self.tracker.begun(query);
+    }
+
+    #[inline]
+    fn query_written(&self, query: u32) {
+        self.tracker.written(query);
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use query::OcclusionQueriesPool;
+/// A pool of timestamp queries whose timestamps are written by the queues of a specific family.
+///
+/// The raw timestamps are expressed in device ticks and only some of their lower bits are
+/// meaningful. This pool masks the timestamps to these bits, and can convert the difference
+/// between two timestamps into a `Duration`.
+///
+/// Like `OcclusionQueriesPool`, the pool keeps track of its queries in debug builds.
+#[derive(Debug)]
+pub struct TimestampQueryPool {
+    inner: Arc<UnsafeQueryPool>,
+    tracker: QueriesTracker,
+    queue_family: u32,
+    valid_bits: u32,
+}
 
-    #[test]
-    fn occlusion_create() {
-        let (device, _) = gfx_dev_and_queue!();
-        let _ = OcclusionQueriesPool::new(&device, 256);
+impl TimestampQueryPool {
+    /// See the docs of new().
+    pub fn raw(device: &Arc<Device>, queue_family: QueueFamily, num_slots: u32)
+               -> Result<TimestampQueryPool, QueryPoolCreationError>
+    {
+        assert_eq!(queue_family.physical_device().internal_object(),
+                   device.physical_device().internal_object(),
+                   "The queue family doesn't belong to the physical device of the device");
+        let valid_bits = match queue_family.timestamp_valid_bits() {
+            Some(bits) => bits,
+            None => return Err(QueryPoolCreationError::TimestampsNotSupported),
+        };
+
+        let inner = try!(UnsafeQueryPool::new(device, QueryType::Timestamp, num_slots));
+
+        Ok(TimestampQueryPool {
+            inner: inner,
+            tracker: QueriesTracker::new(num_slots),
+            queue_family: queue_family.id(),
+            valid_bits: valid_bits,
+        })
+    }
+
+    /// Builds a new query pool whose timestamps will be written by queues of `queue_family`.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the device or host ran out of memory.
+    /// - Panicks if `num_slots` is 0.
+    /// - Panicks if the queue family doesn't support timestamps.
+    ///
+    #[inline]
+    pub fn new(device: &Arc<Device>, queue_family: QueueFamily, num_slots: u32)
+               -> Arc<TimestampQueryPool>
+    {
+        Arc::new(TimestampQueryPool::raw(device, queue_family, num_slots).unwrap())
+    }
+
+    /// Returns the number of slots of that query pool.
+    #[inline]
+    pub fn num_slots(&self) -> u32 {
+        self.inner.count()
+    }
+
+    /// Returns the device that was used to create this pool.
+    #[inline]
+    pub fn device(&self) -> &Arc<Device> {
+        self.inner.device()
+    }
+
+    /// Returns the queue family whose queues write the timestamps of this pool.
+    #[inline]
+    pub fn queue_family(&self) -> QueueFamily {
+        self.inner.device().physical_device().queue_family_by_id(self.queue_family).unwrap()
+    }
+
+    /// Returns the number of meaningful bits of the timestamps.
+    #[inline]
+    pub fn valid_bits(&self) -> u32 {
+        self.valid_bits
+    }
+
+    /// Retrieves the timestamps of the queries of `range`, masked to their valid bits.
+    ///
+    /// See `OcclusionQueriesPool::get_results` for the meaning of the flags.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if `range` is empty or out of the pool.
+    /// - Panicks if `flags.partial` is true.
+    /// - In debug builds, panicks if one of the queries of `range` was never written by a command
+    ///   recorded in a command buffer.
+    ///
+    pub fn get_results(&self, range: Range<u32>, flags: QueryResultFlags)
+                       -> Result<Vec<Option<u64>>, GetResultsError>
+    {
+        assert!(!flags.partial, "Partial results can't be used with timestamp queries");
+
+        let range = self.inner.queries_range(range.start, range.end.saturating_sub(range.start))
+                              .expect("The range of queries is empty or out of the pool");
+        self.tracker.check_written(range.first_index(), range.count());
+
+        let mask = timestamp_mask(self.valid_bits);
+        let results = try!(read_results(range, flags));
+        Ok(results.into_iter().map(|r| r.map(|values| values[0] & mask)).collect())
+    }
+
+    /// Waits for the timestamps of the queries `start` and `end` and returns the time that
+    /// elapsed between them.
+    ///
+    /// The timestamps may have wrapped around between the two queries, in which case the
+    /// difference is computed modulo the range of the valid bits.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if one of the queries is out of the pool.
+    /// - In debug builds, panicks if one of the queries was never written by a command recorded
+    ///   in a command buffer.
+    ///
+    pub fn elapsed(&self, start: u32, end: u32) -> Result<Duration, GetResultsError> {
+        let flags = QueryResultFlags {
+            wait: true,
+            result_64: true,
+            .. QueryResultFlags::default()
+        };
+        let start = try!(self.get_results(start .. start.saturating_add(1), flags))[0].unwrap();
+        let end = try!(self.get_results(end .. end.saturating_add(1), flags))[0].unwrap();
+
+        let period = self.inner.device().physical_device().limits().timestamp_period();
+        Ok(ticks_to_duration(start, end, self.valid_bits, period))
+    }
+}
+
+unsafe impl QueryPool for TimestampQueryPool {
+    #[inline]
+    fn inner_query_pool(&self) -> &UnsafeQueryPool {
+        &self.inner
+    }
+
+    #[inline]
+    fn queries_reset(&self, first: u32, count: u32) {
+        self.tracker.reset(first, count);
+    }
+
+    #[inline]
+    fn query_begun(&self, query: u32) {
+        self.tracker.begun(query);
+    }
+
+    #[inline]
+    fn query_written(&self, query: u32) {
+        self.tracker.written(query);
+    }
+}
+
+/// A pool of pipeline statistics queries.
+///
+/// Requires the `pipeline_statistics_query` feature. Like `OcclusionQueriesPool`, the pool keeps
+/// track of its queries in debug builds.
+#[derive(Debug)]
+pub struct PipelineStatisticsQueryPool {
+    inner: Arc<UnsafeQueryPool>,
+    tracker: QueriesTracker,
+    flags: QueryPipelineStatisticFlags,
+}
+
+impl PipelineStatisticsQueryPool {
+    /// See the docs of new().
+    pub fn raw(device: &Arc<Device>, flags: QueryPipelineStatisticFlags, num_slots: u32)
+               -> Result<PipelineStatisticsQueryPool, QueryPoolCreationError>
+    {
+        let ty = QueryType::PipelineStatistics(flags);
+        let inner = try!(UnsafeQueryPool::new(device, ty, num_slots));
+
+        Ok(PipelineStatisticsQueryPool {
+            inner: inner,
+            tracker: QueriesTracker::new(num_slots),
+            flags: flags,
+        })
+    }
+
+    /// Builds a new query pool whose queries contain the counters enabled in `flags`.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the device or host ran out of memory.
+    /// - Panicks if the `pipeline_statistics_query` feature isn't enabled.
+    /// - Panicks if `num_slots` is 0.
+    /// - Panicks if no counter is enabled in `flags`.
+    ///
+    #[inline]
+    pub fn new(device: &Arc<Device>, flags: QueryPipelineStatisticFlags, num_slots: u32)
+               -> Arc<PipelineStatisticsQueryPool>
+    {
+        Arc::new(PipelineStatisticsQueryPool::raw(device, flags, num_slots).unwrap())
+    }
+
+    /// Returns the number of slots of that query pool.
+    #[inline]
+    pub fn num_slots(&self) -> u32 {
+        self.inner.count()
+    }
+
+    /// Returns the device that was used to create this pool.
+    #[inline]
+    pub fn device(&self) -> &Arc<Device> {
+        self.inner.device()
+    }
+
+    /// Returns the counters that the queries of this pool contain.
+    #[inline]
+    pub fn flags(&self) -> QueryPipelineStatisticFlags {
+        self.flags
+    }
+
+    /// Retrieves the counters of each query of `range`.
+    ///
+    /// Only the counters that were enabled when creating the pool are `Some`. See
+    /// `OcclusionQueriesPool::get_results` for the meaning of the flags.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if `range` is empty or out of the pool.
+    /// - In debug builds, panicks if one of the queries of `range` was never written by a command
+    ///   recorded in a command buffer.
+    ///
+    pub fn get_results(&self, range: Range<u32>, flags: QueryResultFlags)
+                       -> Result<Vec<Option<PipelineStatistics>>, GetResultsError>
+    {
+        let range = self.inner.queries_range(range.start, range.end.saturating_sub(range.start))
+                              .expect("The range of queries is empty or out of the pool");
+        self.tracker.check_written(range.first_index(), range.count());
+
+        let results = try!(read_results(range, flags));
+        Ok(results.into_iter().map(|r| {
+            r.map(|values| PipelineStatistics::decode(&self.flags, &values))
+        }).collect())
+    }
+}
+
+unsafe impl QueryPool for PipelineStatisticsQueryPool {
+    #[inline]
+    fn inner_query_pool(&self) -> &UnsafeQueryPool {
+        &self.inner
+    }
+
+    #[inline]
+    fn queries_reset(&self, first: u32, count: u32) {
+        self.tracker.reset(first, count);
+    }
+
+    #[inline]
+    fn query_begun(&self, query: u32) {
+        self.tracker.begun(query);
+    }
+
+    #[inline]
+    fn query_written(&self, query: u32) {
+        self.tracker.written(query);
+    }
+}
+
+/// Counters of a pipeline statistics query.
+///
+/// Each counter is `None` if it wasn't enabled in the `QueryPipelineStatisticFlags` of the pool.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PipelineStatistics {
+    /// Number of vertices processed by the input assembly stage.
+    pub input_assembly_vertices: Option<u64>,
+    /// Number of primitives processed by the input assembly stage.
+    pub input_assembly_primitives: Option<u64>,
+    /// Number of vertex shader invocations.
+    pub vertex_shader_invocations: Option<u64>,
+    /// Number of geometry shader invocations.
+    pub geometry_shader_invocations: Option<u64>,
+    /// Number of primitives generated by geometry shader invocations.
+    pub geometry_shader_primitives: Option<u64>,
+    /// Number of primitives processed by the clipping stage.
+    pub clipping_invocations: Option<u64>,
+    /// Number of primitives output by the clipping stage.
+    pub clipping_primitives: Option<u64>,
+    /// Number of fragment shader invocations.
+    pub fragment_shader_invocations: Option<u64>,
+    /// Number of patches processed by the tessellation control shader.
+    pub tessellation_control_shader_patches: Option<u64>,
+    /// Number of tessellation evaluation shader invocations.
+    pub tessellation_evaluation_shader_invocations: Option<u64>,
+    /// Number of compute shader invocations.
+    pub compute_shader_invocations: Option<u64>,
+}
+
+impl PipelineStatistics {
+    // Builds a `PipelineStatistics` from the values of a query, which are written in the order
+    // of the bits of the flags.
+    fn decode(flags: &QueryPipelineStatisticFlags, values: &[u64]) -> PipelineStatistics {
+        debug_assert_eq!(values.len(), flags.num_counters());
+
+        let mut values = values.iter().cloned();
+        let mut next = |enabled: bool| if enabled { values.next() } else { None };
+
+        PipelineStatistics {
+            input_assembly_vertices: next(flags.input_assembly_vertices),
+            input_assembly_primitives: next(flags.input_assembly_primitives),
+            vertex_shader_invocations: next(flags.vertex_shader_invocations),
+            geometry_shader_invocations: next(flags.geometry_shader_invocations),
+            geometry_shader_primitives: next(flags.geometry_shader_primitives),
+            clipping_invocations: next(flags.clipping_invocations),
+            clipping_primitives: next(flags.clipping_primitives),
+            fragment_shader_invocations: next(flags.fragment_shader_invocations),
+            tessellation_control_shader_patches: next(flags.tessellation_control_shader_patches),
+            tessellation_evaluation_shader_invocations: {
+                next(flags.tessellation_evaluation_shader_invocations)
+            },
+            compute_shader_invocations: next(flags.compute_shader_invocations),
+        }
+    }
+}
+
+// Returns the mask of the valid bits of timestamps.
+#[inline]
+fn timestamp_mask(valid_bits: u32) -> u64 {
+    if valid_bits >= 64 {
+        u64::MAX
+    } else {
+        (1 << valid_bits) - 1
+    }
+}
+
+// Converts the number of ticks between two masked timestamps into a duration, assuming that
+// the timestamp wrapped around at most once if `end` is inferior to `start`.
+fn ticks_to_duration(start: u64, end: u64, valid_bits: u32, period: f32) -> Duration {
+    let ticks = end.wrapping_sub(start) & timestamp_mask(valid_bits);
+    let nanos = ticks as f64 * period as f64;
+    Duration::new((nanos / 1_000_000_000.0) as u64, (nanos % 1_000_000_000.0) as u32)
+}
+
+/// Flags that control how the results of queries are retrieved.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct QueryResultFlags {
+    /// Wait until the results of all the queries are available.
+    pub wait: bool,
+    /// Write after the values of each query a value that is non-zero if the result is available.
+    pub with_availability: bool,
+    /// Allow returning partial results for queries that are not available yet. Can't be used
+    /// with timestamp queries.
+    pub partial: bool,
+    /// Write the values as 64-bit integers instead of 32-bit integers.
+    pub result_64: bool,
+}
+
+#[doc(hidden)]
+impl Into<vk::QueryResultFlags> for QueryResultFlags {
+    #[inline]
+    fn into(self) -> vk::QueryResultFlags {
+        let mut result = 0;
+        if self.wait {
+            result |= vk::QUERY_RESULT_WAIT_BIT;
+        }
+        if self.with_availability {
+            result |= vk::QUERY_RESULT_WITH_AVAILABILITY_BIT;
+        }
+        if self.partial {
+            result |= vk::QUERY_RESULT_PARTIAL_BIT;
+        }
+        if self.result_64 {
+            result |= vk::QUERY_RESULT_64_BIT;
+        }
+        result
+    }
+}
+
+// Returns the number of bytes written by `vkGetQueryPoolResults` for each query containing
+// `num_values` values.
+#[inline]
+fn result_stride(num_values: usize, flags: QueryResultFlags) -> usize {
+    let slots = num_values + if flags.with_availability { 1 } else { 0 };
+    slots * if flags.result_64 { 8 } else { 4 }
+}
+
+// Retrieves the results of the queries of `range` with `UnsafeQueryPool::get_results`. Returns
+// the values of each query, or `None` if the query isn't available.
+//
+// The availability is always requested if `flags.wait` is false. The caller must have checked
+// that the queries have been written, as required by `UnsafeQueryPool::get_results`.
+fn read_results(range: UnsafeQueriesRange, mut flags: QueryResultFlags)
+                -> Result<Vec<Option<Vec<u64>>>, GetResultsError>
+{
+    if !flags.wait {
+        flags.with_availability = true;
+    }
+
+    let pool = range.pool();
+    let num_values = pool.values_per_query();
+    let count = range.count() as usize;
+
+    // Using a `Vec<u64>` guarantees the alignment of both 32-bit and 64-bit values.
+    let mut data: Vec<u64> = vec![0; (pool.result_stride(flags) * count + 7) / 8];
+
+    {
+        let bytes = unsafe {
+            slice::from_raw_parts_mut(data.as_mut_ptr() as *mut u8, data.len() * 8)
+        };
+        let first = range.first_index();
+        try!(unsafe { pool.get_results(first .. first + range.count(), bytes, flags) });
+    }
+
+    let values = if flags.result_64 {
+        ResultValues::U64(&data)
+    } else {
+        ResultValues::U32(unsafe {
+            slice::from_raw_parts(data.as_ptr() as *const u32, data.len() * 2)
+        })
+    };
+
+    Ok(decode_results(values, count, num_values, flags.with_availability))
+}
+
+// Raw values written by `vkGetQueryPoolResults`.
+#[derive(Debug, Copy, Clone)]
+enum ResultValues<'a> {
+    U32(&'a [u32]),
+    U64(&'a [u64]),
+}
+
+impl<'a> ResultValues<'a> {
+    #[inline]
+    fn get(&self, index: usize) -> u64 {
+        match *self {
+            ResultValues::U32(values) => values[index] as u64,
+            ResultValues::U64(values) => values[index],
+        }
+    }
+}
+
+// Splits the raw values of `count` queries of `num_values` values each, optionally followed by
+// an availability value.
+fn decode_results(values: ResultValues, count: usize, num_values: usize,
+                  with_availability: bool) -> Vec<Option<Vec<u64>>>
+{
+    let slots = num_values + if with_availability { 1 } else { 0 };
+
+    (0 .. count).map(|query| {
+        let base = query * slots;
+        if with_availability && values.get(base + num_values) == 0 {
+            return None;
+        }
+
+        Some((0 .. num_values).map(|n| values.get(base + n)).collect())
+    }).collect()
+}
+
+// Keeps track of the queries that were reset and written by commands recorded in command
+// buffers. Only does something in debug builds.
+#[derive(Debug)]
+struct QueriesTracker {
+    // For each query, whether it was reset and whether it was written.
+    states: Mutex<Vec<(bool, bool)>>,
+}
+
+impl QueriesTracker {
+    #[inline]
+    fn new(num_slots: u32) -> QueriesTracker {
+        let num_slots = if cfg!(debug_assertions) { num_slots as usize } else { 0 };
+
+        QueriesTracker {
+            states: Mutex::new(vec![(false, false); num_slots]),
+        }
+    }
+
+    #[inline]
+    fn reset(&self, first: u32, count: u32) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+
+        let mut states = self.states.lock().unwrap();
+        for state in &mut states[first as usize .. (first + count) as usize] {
+            state.0 = true;
+        }
+    }
+
+    #[inline]
+    fn begun(&self, query: u32) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+
+        let states = self.states.lock().unwrap();
+        assert!(states[query as usize].0, "Query {} must be reset before being begun", query);
+    }
+
+    #[inline]
+    fn written(&self, query: u32) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+
+        let mut states = self.states.lock().unwrap();
+        assert!(states[query as usize].0, "Query {} must be reset before being written", query);
+        states[query as usize].1 = true;
+    }
+
+    #[inline]
+    fn check_written(&self, first: u32, count: u32) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+
+        let states = self.states.lock().unwrap();
+        for query in first .. first + count {
+            assert!(states[query as usize].1, "Query {} was never written by a command", query);
+        }
+    }
+}
+
+/// Error that can happen when retrieving the results of queries.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GetResultsError {
+    /// Not enough memory.
+    OomError(OomError),
+    /// The connection to the device has been lost.
+    DeviceLost,
+    /// The Vulkan implementation returned an error that this operation isn't supposed to return.
+    Unexpected(Error),
+}
+
+impl error::Error for GetResultsError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            GetResultsError::OomError(_) => "not enough memory available",
+            GetResultsError::DeviceLost => "the connection to the device has been lost",
+            GetResultsError::Unexpected(_) => {
+                "unexpected error returned by the Vulkan implementation"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            GetResultsError::OomError(ref err) => Some(err),
+            GetResultsError::Unexpected(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for GetResultsError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<OomError> for GetResultsError {
+    #[inline]
+    fn from(err: OomError) -> GetResultsError {
+        GetResultsError::OomError(err)
+    }
+}
+
+impl From<Error> for GetResultsError {
+    #[inline]
+    fn from(err: Error) -> GetResultsError {
+        match err {
+            err @ Error::OutOfHostMemory => GetResultsError::OomError(OomError::from(err)),
+            err @ Error::OutOfDeviceMemory => GetResultsError::OomError(OomError::from(err)),
+            Error::DeviceLost => GetResultsError::DeviceLost,
+            _ => GetResultsError::Unexpected(err)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::slice;
+    use std::time::Duration;
+
+    use buffer::BufferUsage;
+    use buffer::CpuAccessibleBuffer;
+    use command_buffer::CommandBufferPool;
+    use command_buffer::PrimaryCommandBufferBuilder;
+    use command_buffer::submit;
+    use format::R8G8B8A8Unorm;
+    use framebuffer::Framebuffer;
+    use image::attachment::AttachmentImage;
+    use query::GetResultsError;
+    use query::OcclusionQueriesPool;
+    use query::PipelineStatistics;
+    use query::PipelineStatisticsQueryPool;
+    use query::QueryPipelineStatisticFlags;
+    use query::QueryPoolCreationError;
+    use query::QueryResultFlags;
+    use query::QueryType;
+    use query::ResultValues;
+    use query::TimestampQueryPool;
+    use query::UnsafeQueryPool;
+    use query::decode_results;
+    use query::result_stride;
+    use query::ticks_to_duration;
+    use query::timestamp_mask;
+    use sync::PipelineStages;
+    use Error;
+    use OomError;
+
+    #[test]
+    fn results_error_mapping() {
+        assert_eq!(GetResultsError::from(Error::OutOfDeviceMemory),
+                   GetResultsError::OomError(OomError::OutOfDeviceMemory));
+        assert_eq!(GetResultsError::from(Error::DeviceLost), GetResultsError::DeviceLost);
+        assert_eq!(GetResultsError::from(Error::Unknown(-5i32 as u32)),
+                   GetResultsError::Unexpected(Error::Unknown(-5i32 as u32)));
+    }
+
+    #[test]
+    fn creation_error_mapping() {
+        assert_eq!(QueryPoolCreationError::from(Error::OutOfHostMemory),
+                   QueryPoolCreationError::OomError(OomError::OutOfHostMemory));
+        assert_eq!(QueryPoolCreationError::from(Error::DeviceLost),
+                   QueryPoolCreationError::Unexpected(Error::DeviceLost));
+    }
+
+    #[test]
+    fn occlusion_create() {
+        let (device, _) = gfx_dev_and_queue!();
+        let _ = OcclusionQueriesPool::new(&device, 256);
+    }
+
+    #[test]
+    fn unsafe_occlusion_create() {
+        let (device, _) = gfx_dev_and_queue!();
+        let pool = UnsafeQueryPool::new(&device, QueryType::Occlusion, 256).unwrap();
+        assert_eq!(pool.ty(), QueryType::Occlusion);
+        assert_eq!(pool.count(), 256);
+    }
+
+    #[test]
+    fn unsafe_timestamp_create() {
+        let (device, _) = gfx_dev_and_queue!();
+        let pool = UnsafeQueryPool::new(&device, QueryType::Timestamp, 16).unwrap();
+        assert_eq!(pool.ty(), QueryType::Timestamp);
+        assert_eq!(pool.count(), 16);
+    }
+
+    #[test]
+    fn pipeline_statistics_create() {
+        let (device, _) = gfx_dev_and_queue!(pipeline_statistics_query);
+        let ty = QueryType::PipelineStatistics(QueryPipelineStatisticFlags::all());
+        let pool = UnsafeQueryPool::new(&device, ty, 8).unwrap();
+        assert_eq!(pool.ty(), ty);
+    }
+
+    #[test]
+    fn pipeline_statistics_feature_missing() {
+        let (device, _) = gfx_dev_and_queue!();
+        let ty = QueryType::PipelineStatistics(QueryPipelineStatisticFlags::all());
+        match UnsafeQueryPool::new(&device, ty, 8) {
+            Err(QueryPoolCreationError::PipelineStatisticsQueryFeatureNotEnabled) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn queries_range() {
+        let (device, _) = gfx_dev_and_queue!();
+        let pool = UnsafeQueryPool::new(&device, QueryType::Occlusion, 16).unwrap();
+
+        let range = pool.queries_range(4, 12).unwrap();
+        assert_eq!(range.first_index(), 4);
+        assert_eq!(range.count(), 12);
+        assert!(pool.queries_range(0, 16).is_some());
+        assert!(pool.queries_range(15, 1).is_some());
+
+        assert!(pool.queries_range(4, 13).is_none());
+        assert!(pool.queries_range(16, 1).is_none());
+        assert!(pool.queries_range(2, 0).is_none());
+        assert!(pool.queries_range(0xffffffff, 2).is_none());
+    }
+
+    #[test]
+    fn decode_32bit() {
+        let values = [5, 7, 9];
+        let results = decode_results(ResultValues::U32(&values), 3, 1, false);
+        assert_eq!(results, vec![Some(vec![5]), Some(vec![7]), Some(vec![9])]);
+    }
+
+    #[test]
+    fn decode_64bit() {
+        let values = [0x1_0000_0000, 3];
+        let results = decode_results(ResultValues::U64(&values), 2, 1, false);
+        assert_eq!(results, vec![Some(vec![0x1_0000_0000]), Some(vec![3])]);
+    }
+
+    #[test]
+    fn decode_with_availability() {
+        let values = [5, 1, 0, 0, 9, 1];
+        let results = decode_results(ResultValues::U32(&values), 3, 1, true);
+        assert_eq!(results, vec![Some(vec![5]), None, Some(vec![9])]);
+
+        let values = [1, 2, 1, 3, 4, 0];
+        let results = decode_results(ResultValues::U64(&values), 2, 2, true);
+        assert_eq!(results, vec![Some(vec![1, 2]), None]);
+    }
+
+    mod example {
+        use format::R8G8B8A8Unorm;
+
+        single_pass_renderpass! {
+            attachments: {
+                color: {
+                    load: Clear,
+                    store: Store,
+                    format: R8G8B8A8Unorm,
+                }
+            },
+            pass: {
+                color: [color],
+                depth_stencil: {}
+            }
+        }
+    }
+
+    #[test]
+    fn occlusion_results_wait() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let render_pass = example::CustomRenderPass::new(&device, &example::Formats {
+            color: (R8G8B8A8Unorm, 1),
+        }).unwrap();
+        let image = AttachmentImage::new(&device, [4, 4], R8G8B8A8Unorm).unwrap();
+        let framebuffer = Framebuffer::new(&render_pass, [4, 4, 1], example::AList {
+            color: &image,
+        }).unwrap();
+
+        let pool = OcclusionQueriesPool::new(&device, 4);
+
+        let cb_pool = CommandBufferPool::new(&device, &queue.family());
+        let cb = PrimaryCommandBufferBuilder::new(&cb_pool)
+                    .reset_query_pool(&pool, 0 .. 4)
+                    .draw_inline(&render_pass, &framebuffer, example::ClearValues {
+                        color: [0.0, 0.0, 0.0, 1.0]
+                    })
+                    .begin_query(&pool, 1, false)
+                    .end_query(&pool, 1)
+                    .draw_end()
+                    .begin_query(&pool, 2, false)
+                    .end_query(&pool, 2)
+                    .build();
+        submit(&cb, &queue).unwrap();
+
+        // Nothing was drawn, so no sample passed the tests.
+        let flags = QueryResultFlags { wait: true, .. QueryResultFlags::default() };
+        assert_eq!(pool.get_results(1 .. 3, flags).unwrap(), vec![Some(0), Some(0)]);
+
+        let flags = QueryResultFlags { result_64: true, .. flags };
+        assert_eq!(pool.get_results(1 .. 3, flags).unwrap(), vec![Some(0), Some(0)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "All the queries begun in a subpass must be ended before the end of \
+                               the subpass")]
+    fn query_not_ended_in_subpass() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let render_pass = example::CustomRenderPass::new(&device, &example::Formats {
+            color: (R8G8B8A8Unorm, 1),
+        }).unwrap();
+        let image = AttachmentImage::new(&device, [4, 4], R8G8B8A8Unorm).unwrap();
+        let framebuffer = Framebuffer::new(&render_pass, [4, 4, 1], example::AList {
+            color: &image,
+        }).unwrap();
+
+        let pool = OcclusionQueriesPool::new(&device, 4);
+
+        let cb_pool = CommandBufferPool::new(&device, &queue.family());
+        let _ = PrimaryCommandBufferBuilder::new(&cb_pool)
+                    .reset_query_pool(&pool, 0 .. 4)
+                    .draw_inline(&render_pass, &framebuffer, example::ClearValues {
+                        color: [0.0, 0.0, 0.0, 1.0]
+                    })
+                    .begin_query(&pool, 1, false)
+                    .draw_end();
+    }
+
+    #[test]
+    #[should_panic(expected = "The query must be ended in the subpass it was begun in")]
+    fn query_ended_in_render_pass() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let render_pass = example::CustomRenderPass::new(&device, &example::Formats {
+            color: (R8G8B8A8Unorm, 1),
+        }).unwrap();
+        let image = AttachmentImage::new(&device, [4, 4], R8G8B8A8Unorm).unwrap();
+        let framebuffer = Framebuffer::new(&render_pass, [4, 4, 1], example::AList {
+            color: &image,
+        }).unwrap();
+
+        let pool = OcclusionQueriesPool::new(&device, 4);
+
+        let cb_pool = CommandBufferPool::new(&device, &queue.family());
+        let _ = PrimaryCommandBufferBuilder::new(&cb_pool)
+                    .reset_query_pool(&pool, 0 .. 4)
+                    .begin_query(&pool, 1, false)
+                    .draw_inline(&render_pass, &framebuffer, example::ClearValues {
+                        color: [0.0, 0.0, 0.0, 1.0]
+                    })
+                    .end_query(&pool, 1);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Query 0 was never written by a command")]
+    fn occlusion_never_written() {
+        let (device, _) = gfx_dev_and_queue!();
+        let pool = OcclusionQueriesPool::new(&device, 4);
+        let flags = QueryResultFlags { wait: true, .. QueryResultFlags::default() };
+        let _ = pool.get_results(0 .. 1, flags);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Query 0 must be reset before being begun")]
+    fn occlusion_begin_without_reset() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let pool = OcclusionQueriesPool::new(&device, 4);
+        let cb_pool = CommandBufferPool::new(&device, &queue.family());
+        let _ = PrimaryCommandBufferBuilder::new(&cb_pool).begin_query(&pool, 0, false);
+    }
+
+    #[test]
+    fn timestamp_masks() {
+        assert_eq!(timestamp_mask(64), 0xffffffffffffffff);
+        assert_eq!(timestamp_mask(36), 0xfffffffff);
+        assert_eq!(timestamp_mask(1), 1);
+    }
+
+    #[test]
+    fn timestamp_duration() {
+        assert_eq!(ticks_to_duration(100, 350, 64, 1.0), Duration::new(0, 250));
+        assert_eq!(ticks_to_duration(0, 3_000_000_000, 64, 1.0), Duration::new(3, 0));
+        assert_eq!(ticks_to_duration(10, 20, 32, 80.0), Duration::new(0, 800));
+        assert_eq!(ticks_to_duration(5, 5, 48, 1.0), Duration::new(0, 0));
+    }
+
+    #[test]
+    fn timestamp_wraparound() {
+        // The timestamps wrapped around between `start` and `end`.
+        assert_eq!(ticks_to_duration(0xfff0, 0x10, 16, 1.0), Duration::new(0, 0x20));
+        assert_eq!(ticks_to_duration(0xfffffffffffffffe, 1, 64, 2.0), Duration::new(0, 6));
+    }
+
+    #[test]
+    fn timestamp_elapsed() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        if queue.family().timestamp_valid_bits().is_none() {
+            return;
+        }
+
+        let usage = BufferUsage { transfer_dest: true, .. BufferUsage::none() };
+        let buffer = CpuAccessibleBuffer::<[u32]>::array(&device, 1024, &usage,
+                                                         Some(queue.family())).unwrap();
+
+        let pool = TimestampQueryPool::new(&device, queue.family(), 2);
+        let top = PipelineStages { top_of_pipe: true, .. PipelineStages::none() };
+        let bottom = PipelineStages { bottom_of_pipe: true, .. PipelineStages::none() };
+
+        let cb_pool = CommandBufferPool::new(&device, &queue.family());
+        let cb = unsafe {
+            PrimaryCommandBufferBuilder::new(&cb_pool)
+                .reset_query_pool(&pool, 0 .. 2)
+                .write_timestamp(&pool, top, 0)
+                .fill_buffer(&buffer, 0, 4096, 0x12345678)
+                .write_timestamp(&pool, bottom, 1)
+                .build()
+        };
+        submit(&cb, &queue).unwrap();
+
+        let elapsed = pool.elapsed(0, 1).unwrap();
+        assert!(elapsed < Duration::new(10, 0), "Unexpected duration: {:?}", elapsed);
+
+        let flags = QueryResultFlags { wait: true, .. QueryResultFlags::default() };
+        let mask = timestamp_mask(pool.valid_bits());
+        for result in pool.get_results(0 .. 2, flags).unwrap() {
+            assert_eq!(result.unwrap() & !mask, 0);
+        }
+    }
+
+    #[test]
+    fn statistics_decode_all() {
+        let flags = QueryPipelineStatisticFlags::all();
+        assert_eq!(flags.num_counters(), 11);
+
+        let values = (1 .. 12).collect::<Vec<u64>>();
+        let stats = PipelineStatistics::decode(&flags, &values);
+        assert_eq!(stats.input_assembly_vertices, Some(1));
+        assert_eq!(stats.input_assembly_primitives, Some(2));
+        assert_eq!(stats.vertex_shader_invocations, Some(3));
+        assert_eq!(stats.geometry_shader_invocations, Some(4));
+        assert_eq!(stats.geometry_shader_primitives, Some(5));
+        assert_eq!(stats.clipping_invocations, Some(6));
+        assert_eq!(stats.clipping_primitives, Some(7));
+        assert_eq!(stats.fragment_shader_invocations, Some(8));
+        assert_eq!(stats.tessellation_control_shader_patches, Some(9));
+        assert_eq!(stats.tessellation_evaluation_shader_invocations, Some(10));
+        assert_eq!(stats.compute_shader_invocations, Some(11));
+    }
+
+    #[test]
+    fn statistics_decode_subset() {
+        let flags = QueryPipelineStatisticFlags {
+            vertex_shader_invocations: true,
+            clipping_primitives: true,
+            compute_shader_invocations: true,
+            .. QueryPipelineStatisticFlags::none()
+        };
+        assert_eq!(flags.num_counters(), 3);
+
+        let stats = PipelineStatistics::decode(&flags, &[30, 70, 110]);
+        assert_eq!(stats, PipelineStatistics {
+            input_assembly_vertices: None,
+            input_assembly_primitives: None,
+            vertex_shader_invocations: Some(30),
+            geometry_shader_invocations: None,
+            geometry_shader_primitives: None,
+            clipping_invocations: None,
+            clipping_primitives: Some(70),
+            fragment_shader_invocations: None,
+            tessellation_control_shader_patches: None,
+            tessellation_evaluation_shader_invocations: None,
+            compute_shader_invocations: Some(110),
+        });
+    }
+
+    #[test]
+    fn statistics_decode_single() {
+        let flags = QueryPipelineStatisticFlags {
+            fragment_shader_invocations: true,
+            .. QueryPipelineStatisticFlags::none()
+        };
+
+        let stats = PipelineStatistics::decode(&flags, &[42]);
+        assert_eq!(stats.fragment_shader_invocations, Some(42));
+        assert_eq!(stats.vertex_shader_invocations, None);
+        assert_eq!(stats.compute_shader_invocations, None);
+    }
+
+    #[test]
+    fn statistics_decode_results() {
+        // Two queries of two counters with availability, the second one not available.
+        let flags = QueryPipelineStatisticFlags {
+            input_assembly_primitives: true,
+            tessellation_control_shader_patches: true,
+            .. QueryPipelineStatisticFlags::none()
+        };
+
+        let values = [4, 8, 1, 0, 0, 0];
+        let results = decode_results(ResultValues::U32(&values), 2, flags.num_counters(), true);
+        let stats = results.into_iter().map(|r| {
+            r.map(|values| PipelineStatistics::decode(&flags, &values))
+        }).collect::<Vec<_>>();
+
+        assert_eq!(stats[0].unwrap().input_assembly_primitives, Some(4));
+        assert_eq!(stats[0].unwrap().tessellation_control_shader_patches, Some(8));
+        assert_eq!(stats[1], None);
+    }
+
+    #[test]
+    #[should_panic(expected = "A pipeline statistics query must contain at least one counter")]
+    fn statistics_no_counter() {
+        let (device, _) = gfx_dev_and_queue!(pipeline_statistics_query);
+        let _ = PipelineStatisticsQueryPool::new(&device, QueryPipelineStatisticFlags::none(), 4);
+    }
+
+    #[test]
+    fn statistics_pool_flags() {
+        let (device, _) = gfx_dev_and_queue!(pipeline_statistics_query);
+        let flags = QueryPipelineStatisticFlags {
+            vertex_shader_invocations: true,
+            .. QueryPipelineStatisticFlags::none()
+        };
+        let pool = PipelineStatisticsQueryPool::new(&device, flags, 4);
+        assert_eq!(pool.flags(), flags);
+        assert_eq!(pool.num_slots(), 4);
+    }
+
+    #[test]
+    fn stride_all_flags() {
+        for bits in 0 .. 16u32 {
+            let flags = QueryResultFlags {
+                wait: bits & 1 != 0,
+                with_availability: bits & 2 != 0,
+                partial: bits & 4 != 0,
+                result_64: bits & 8 != 0,
+            };
+
+            let width = if flags.result_64 { 8 } else { 4 };
+            let availability = if flags.with_availability { 1 } else { 0 };
+
+            for &num_values in &[1, 3, 11] {
+                let stride = result_stride(num_values, flags);
+                assert_eq!(stride, (num_values + availability) * width, "{:?}", flags);
+                assert_eq!(stride % width, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn stride_examples() {
+        let flags = QueryResultFlags::default();
+        assert_eq!(result_stride(1, flags), 4);
+
+        let flags = QueryResultFlags { with_availability: true, .. QueryResultFlags::default() };
+        assert_eq!(result_stride(1, flags), 8);
+
+        let flags = QueryResultFlags { result_64: true, .. flags };
+        assert_eq!(result_stride(1, flags), 16);
+        assert_eq!(result_stride(11, flags), 96);
+    }
+
+    #[test]
+    fn unsafe_results_size() {
+        let (device, _) = gfx_dev_and_queue!();
+        let pool = UnsafeQueryPool::new(&device, QueryType::Occlusion, 8).unwrap();
+        assert_eq!(pool.values_per_query(), 1);
+
+        let flags = QueryResultFlags { result_64: true, .. QueryResultFlags::default() };
+        assert_eq!(pool.result_stride(flags), 8);
+    }
+
+    #[test]
+    #[should_panic(expected = "The destination is too small for the results")]
+    fn unsafe_results_destination_too_small() {
+        let (device, _) = gfx_dev_and_queue!();
+        let pool = UnsafeQueryPool::new(&device, QueryType::Occlusion, 8).unwrap();
+
+        let flags = QueryResultFlags { wait: true, .. QueryResultFlags::default() };
+        let mut destination = [0u32; 3];
+        let destination = unsafe {
+            slice::from_raw_parts_mut(destination.as_mut_ptr() as *mut u8, 12)
+        };
+        let _ = unsafe { pool.get_results(0 .. 4, destination, flags) };
+    }
+
+    #[test]
+    fn unsafe_results_not_ready() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let pool = UnsafeQueryPool::new(&device, QueryType::Occlusion, 4).unwrap();
+
+        // Resetting the queries makes them unavailable.
+        let cb_pool = CommandBufferPool::new(&device, &queue.family());
+        let cb = PrimaryCommandBufferBuilder::new(&cb_pool)
+                    .reset_query_pool(&pool, 0 .. 4)
+                    .build();
+        submit(&cb, &queue).unwrap().wait(Duration::new(10, 0)).unwrap();
+
+        let mut destination = [0u32; 4];
+        let destination = unsafe {
+            slice::from_raw_parts_mut(destination.as_mut_ptr() as *mut u8, 16)
+        };
+        let result = unsafe {
+            pool.get_results(0 .. 4, destination, QueryResultFlags::default())
+        };
+        assert_eq!(result, Ok(false));
     }
 }
@@ -19,6 +19,7 @@ use std::ptr;
 use std::sync::Arc;
 
 use device::Device;
+use format::Format;
 use Error;
 use OomError;
 use VulkanObject;
@@ -30,6 +31,15 @@ use vk;
 pub struct Sampler {
     sampler: vk::Sampler,
     device: Arc<Device>,
+    // The parameters below are only kept for debugging purposes.
+    mag_filter: Filter,
+    min_filter: Filter,
+    mipmap_mode: MipmapMode,
+    address_modes: [SamplerAddressMode; 3],
+    mip_lod_bias: f32,
+    max_anisotropy: f32,
+    lod: [f32; 2],
+    unnormalized: bool,
 }
 
 // TODO: what's the story with VK_KHR_mirror_clamp_to_edge? Is it an extension or is it core?
@@ -42,6 +52,9 @@ impl Sampler {
     /// - Panicks if `max_anisotropy < 1.0`.
     /// - Panicks if `min_lod > max_lod`.
     ///
+    /// If `mag_filter`, `min_filter` or `mipmap_mode` is linear, the sampler can only be used
+    /// with image formats that support linear filtering. See `supports_format`.
+    ///
     pub fn new(device: &Arc<Device>, mag_filter: Filter, min_filter: Filter,
                mipmap_mode: MipmapMode, address_u: SamplerAddressMode,
                address_v: SamplerAddressMode, address_w: SamplerAddressMode, mip_lod_bias: f32,
@@ -108,6 +121,14 @@ impl Sampler {
         Ok(Arc::new(Sampler {
             sampler: sampler,
             device: device.clone(),
+            mag_filter: mag_filter,
+            min_filter: min_filter,
+            mipmap_mode: mipmap_mode,
+            address_modes: [address_u, address_v, address_w],
+            mip_lod_bias: mip_lod_bias,
+            max_anisotropy: max_anisotropy,
+            lod: [min_lod, max_lod],
+            unnormalized: false,
         }))
     }
 
@@ -158,8 +179,51 @@ impl Sampler {
         Ok(Arc::new(Sampler {
             sampler: sampler,
             device: device.clone(),
+            mag_filter: filter,
+            min_filter: filter,
+            mipmap_mode: MipmapMode::Nearest,
+            address_modes: [address_u.into(), address_v.into(), SamplerAddressMode::ClampToEdge],
+            mip_lod_bias: 0.0,
+            max_anisotropy: 0.0,
+            lod: [0.0, 0.0],
+            unnormalized: true,
         }))
     }
+
+    /// Returns the device this sampler was created with.
+    #[inline]
+    pub fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+
+    /// Returns true if the sampler uses linear filtering, either between texels or between
+    /// mipmaps.
+    #[inline]
+    pub fn uses_linear_filtering(&self) -> bool {
+        self.mag_filter == Filter::Linear || self.min_filter == Filter::Linear ||
+        self.mipmap_mode == MipmapMode::Linear
+    }
+
+    /// Returns true if the sampler can be used to sample images of the given format.
+    ///
+    /// Samplers that use linear filtering can only be used with formats that support it.
+    #[inline]
+    pub fn supports_format(&self, format: Format) -> bool {
+        !self.uses_linear_filtering() ||
+        format.can_be_sampled_linear(&self.device.physical_device())
+    }
+}
+
+impl fmt::Debug for Sampler {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "Sampler({:#x}) {{ mag_filter: {:?}, min_filter: {:?}, mipmap_mode: {:?}, \
+                     address_modes: {:?}, mip_lod_bias: {:?}, max_anisotropy: {:?}, lod: {:?}, \
+                     unnormalized: {:?} }}",
+               self.sampler, self.mag_filter, self.min_filter, self.mipmap_mode,
+               self.address_modes, self.mip_lod_bias, self.max_anisotropy, self.lod,
+               self.unnormalized)
+    }
 }
 
 unsafe impl VulkanObject for Sampler {
@@ -223,6 +287,16 @@ pub enum UnnormalizedSamplerAddressMode {
     ClampToBorder = vk::SAMPLER_ADDRESS_MODE_CLAMP_TO_BORDER,
 }
 
+impl From<UnnormalizedSamplerAddressMode> for SamplerAddressMode {
+    #[inline]
+    fn from(mode: UnnormalizedSamplerAddressMode) -> SamplerAddressMode {
+        match mode {
+            UnnormalizedSamplerAddressMode::ClampToEdge => SamplerAddressMode::ClampToEdge,
+            UnnormalizedSamplerAddressMode::ClampToBorder => SamplerAddressMode::ClampToBorder,
+        }
+    }
+}
+
 /// Error that can happen when creating an instance.
 #[derive(Clone, Debug, PartialEq)]
 pub enum SamplerCreationError {
@@ -242,6 +316,9 @@ pub enum SamplerCreationError {
 
     /// The requested mip lod bias exceeds the device's limits.
     MipLodBiasLimitExceeded { requested: f32, maximum: f32 },
+
+    /// The Vulkan implementation returned an error that this operation isn't supposed to return.
+    Unexpected(Error),
 }
 
 impl error::Error for SamplerCreationError {
@@ -254,6 +331,9 @@ impl error::Error for SamplerCreationError {
                                                                          feature is not enabled",
             SamplerCreationError::AnisotropyLimitExceeded { .. } => "anisotropy limit exceeded",
             SamplerCreationError::MipLodBiasLimitExceeded { .. } => "mip lod bias limit exceeded",
+            SamplerCreationError::Unexpected(_) => {
+                "unexpected error returned by the Vulkan implementation"
+            },
         }
     }
 
@@ -261,6 +341,7 @@ impl error::Error for SamplerCreationError {
     fn cause(&self) -> Option<&error::Error> {
         match *self {
             SamplerCreationError::OomError(ref err) => Some(err),
+            SamplerCreationError::Unexpected(ref err) => Some(err),
             _ => None
         }
     }
@@ -287,13 +368,14 @@ impl From<Error> for SamplerCreationError {
             err @ Error::OutOfHostMemory => SamplerCreationError::OomError(OomError::from(err)),
             err @ Error::OutOfDeviceMemory => SamplerCreationError::OomError(OomError::from(err)),
             Error::TooManyObjects => SamplerCreationError::TooManyObjects,
-            _ => panic!("unexpected error: {:?}", err)
+            _ => SamplerCreationError::Unexpected(err)
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use format::Format;
     use sampler;
 
     #[test]
@@ -358,6 +440,32 @@ mod tests {
         }
     }
 
+    #[test]
+    fn supports_format() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let linear = sampler::Sampler::new(&device, sampler::Filter::Linear,
+                                           sampler::Filter::Nearest,
+                                           sampler::MipmapMode::Nearest,
+                                           sampler::SamplerAddressMode::Repeat,
+                                           sampler::SamplerAddressMode::Repeat,
+                                           sampler::SamplerAddressMode::Repeat, 1.0, 1.0,
+                                           0.0, 2.0).unwrap();
+        assert!(linear.uses_linear_filtering());
+        // Linear filtering of this format is mandatory.
+        assert!(linear.supports_format(Format::R8G8B8A8Unorm));
+
+        let nearest = sampler::Sampler::new(&device, sampler::Filter::Nearest,
+                                            sampler::Filter::Nearest,
+                                            sampler::MipmapMode::Nearest,
+                                            sampler::SamplerAddressMode::Repeat,
+                                            sampler::SamplerAddressMode::Repeat,
+                                            sampler::SamplerAddressMode::Repeat, 1.0, 1.0,
+                                            0.0, 2.0).unwrap();
+        assert!(!nearest.uses_linear_filtering());
+        assert!(nearest.supports_format(Format::R32G32B32A32Sfloat));
+    }
+
     #[test]
     fn anisotropy_limit() {
         let (device, queue) = gfx_dev_and_queue!(sampler_anisotropy);
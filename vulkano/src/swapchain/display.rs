@@ -12,7 +12,9 @@
 //! As far as the author knows, no existing device supports these features. Therefore the code here
 //! is mostly a draft and needs rework in both the API and the implementation.
 
+use std::error;
 use std::ffi::CStr;
+use std::fmt;
 use std::ptr;
 use std::sync::Arc;
 use std::vec::IntoIter;
@@ -21,6 +23,7 @@ use instance::Instance;
 use instance::PhysicalDevice;
 
 use check_errors;
+use Error;
 use OomError;
 use VulkanObject;
 use VulkanPointers;
@@ -40,10 +43,14 @@ pub struct DisplayPlane {
 
 impl DisplayPlane {
     /// See the docs of enumerate().
-    pub fn enumerate_raw(device: &PhysicalDevice) -> Result<IntoIter<DisplayPlane>, OomError> {
+    pub fn enumerate_raw(device: &PhysicalDevice)
+                         -> Result<IntoIter<DisplayPlane>, DisplayEnumerationError>
+    {
         let vk = device.instance().pointers();
 
-        assert!(device.instance().loaded_extensions().khr_display);     // TODO: return error instead
+        if !device.instance().loaded_extensions().khr_display {
+            return Err(DisplayEnumerationError::MissingExtension { name: "VK_KHR_display" });
+        }
 
         let num = unsafe {
             let mut num: u32 = 0;
@@ -62,32 +69,37 @@ impl DisplayPlane {
             planes
         };
 
-        Ok(planes.into_iter().enumerate().map(|(index, prop)| {
+        let mut output = Vec::with_capacity(planes.len());
+
+        for (index, prop) in planes.into_iter().enumerate() {
             let num = unsafe {
                 let mut num: u32 = 0;
-                check_errors(vk.GetDisplayPlaneSupportedDisplaysKHR(device.internal_object(), index as u32,
-                                                                    &mut num, ptr::null_mut())).unwrap();       // TODO: shouldn't unwrap
+                try!(check_errors(vk.GetDisplayPlaneSupportedDisplaysKHR(device.internal_object(),
+                                                                         index as u32, &mut num,
+                                                                         ptr::null_mut())));
                 num
             };
 
             let supported_displays: Vec<vk::DisplayKHR> = unsafe {
                 let mut displays = Vec::with_capacity(num as usize);
                 let mut num = num;
-                check_errors(vk.GetDisplayPlaneSupportedDisplaysKHR(device.internal_object(),
-                                                                    index as u32, &mut num,
-                                                                    displays.as_mut_ptr())).unwrap();       // TODO: shouldn't unwrap
+                try!(check_errors(vk.GetDisplayPlaneSupportedDisplaysKHR(device.internal_object(),
+                                                                         index as u32, &mut num,
+                                                                         displays.as_mut_ptr())));
                 displays.set_len(num as usize);
                 displays
             };
 
-            DisplayPlane {
+            output.push(DisplayPlane {
                 instance: device.instance().clone(),
                 physical_device: device.index(),
                 index: index as u32,
                 properties: prop,
                 supported_displays: supported_displays,
-            }
-        }).collect::<Vec<_>>().into_iter())
+            });
+        }
+
+        Ok(output.into_iter())
     }
     
     /// Enumerates all the display planes that are available on a given physical device.
@@ -95,6 +107,7 @@ impl DisplayPlane {
     /// # Panic
     ///
     /// - Panicks if the device or host ran out of memory.
+    /// - Panicks if the `VK_KHR_display` extension wasn't enabled on the instance.
     ///
     // TODO: move iterator creation here from raw constructor?
     #[inline]
@@ -130,9 +143,14 @@ pub struct Display {
 
 impl Display {
     /// See the docs of enumerate().
-    pub fn enumerate_raw(device: &PhysicalDevice) -> Result<IntoIter<Display>, OomError> {
+    pub fn enumerate_raw(device: &PhysicalDevice)
+                         -> Result<IntoIter<Display>, DisplayEnumerationError>
+    {
         let vk = device.instance().pointers();
-        assert!(device.instance().loaded_extensions().khr_display);     // TODO: return error instead
+
+        if !device.instance().loaded_extensions().khr_display {
+            return Err(DisplayEnumerationError::MissingExtension { name: "VK_KHR_display" });
+        }
 
         let num = unsafe {
             let mut num = 0;
@@ -165,6 +183,7 @@ impl Display {
     /// # Panic
     ///
     /// - Panicks if the device or host ran out of memory.
+    /// - Panicks if the `VK_KHR_display` extension wasn't enabled on the instance.
     ///
     // TODO: move iterator creation here from raw constructor?
     #[inline]
@@ -238,6 +257,15 @@ impl Display {
     }
 }
 
+impl fmt::Debug for Display {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        // Not using `name()`, as formatting shouldn't panic because of a weird display name.
+        let name = unsafe { CStr::from_ptr(self.properties.displayName).to_string_lossy() };
+        write!(fmt, "Display({:#x}) {{ name: {:?}, physical_resolution: {:?} }}",
+               self.properties.display, name, self.physical_resolution())
+    }
+}
+
 unsafe impl VulkanObject for Display {
     type Object = vk::DisplayKHR;
 
@@ -306,6 +334,8 @@ impl DisplayMode {
     }
 }
 
+impl_debug_handle!(DisplayMode);
+
 unsafe impl VulkanObject for DisplayMode {
     type Object = vk::DisplayModeKHR;
 
@@ -314,3 +344,106 @@ unsafe impl VulkanObject for DisplayMode {
         self.display_mode
     }
 }
+
+/// Error that can happen when enumerating displays or display planes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DisplayEnumerationError {
+    /// Not enough memory.
+    OomError(OomError),
+
+    /// The extension required for this function was not enabled.
+    MissingExtension { name: &'static str },
+
+    /// The Vulkan implementation returned an error that this operation isn't supposed to return.
+    Unexpected(Error),
+}
+
+impl error::Error for DisplayEnumerationError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            DisplayEnumerationError::OomError(_) => "not enough memory available",
+            DisplayEnumerationError::MissingExtension { .. } => "the extension required for this \
+                                                                 function was not enabled",
+            DisplayEnumerationError::Unexpected(_) => {
+                "unexpected error returned by the Vulkan implementation"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            DisplayEnumerationError::OomError(ref err) => Some(err),
+            DisplayEnumerationError::Unexpected(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for DisplayEnumerationError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<OomError> for DisplayEnumerationError {
+    #[inline]
+    fn from(err: OomError) -> DisplayEnumerationError {
+        DisplayEnumerationError::OomError(err)
+    }
+}
+
+impl From<Error> for DisplayEnumerationError {
+    #[inline]
+    fn from(err: Error) -> DisplayEnumerationError {
+        match err {
+            err @ Error::OutOfHostMemory => DisplayEnumerationError::OomError(OomError::from(err)),
+            err @ Error::OutOfDeviceMemory => {
+                DisplayEnumerationError::OomError(OomError::from(err))
+            },
+            _ => DisplayEnumerationError::Unexpected(err)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use instance::PhysicalDevice;
+    use swapchain::display::Display;
+    use swapchain::display::DisplayEnumerationError;
+    use swapchain::display::DisplayPlane;
+
+    #[test]
+    fn displays_missing_extension() {
+        let instance = instance!();
+        let physical = match PhysicalDevice::enumerate(&instance).next() {
+            Some(p) => p,
+            None => return
+        };
+
+        match Display::enumerate_raw(&physical) {
+            Err(DisplayEnumerationError::MissingExtension { name }) => {
+                assert_eq!(name, "VK_KHR_display");
+            },
+            _ => panic!("Expected a missing extension error")
+        }
+    }
+
+    #[test]
+    fn display_planes_missing_extension() {
+        let instance = instance!();
+        let physical = match PhysicalDevice::enumerate(&instance).next() {
+            Some(p) => p,
+            None => return
+        };
+
+        match DisplayPlane::enumerate_raw(&physical) {
+            Err(DisplayEnumerationError::MissingExtension { name }) => {
+                assert_eq!(name, "VK_KHR_display");
+            },
+            _ => panic!("Expected a missing extension error")
+        }
+    }
+}
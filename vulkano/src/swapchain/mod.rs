@@ -37,6 +37,9 @@
 //! - `VK_KHR_android_surface`
 //! - `VK_KHR_win32_surface`
 //!
+//! The `win` module returns the surface extensions to enable on the current platform, which is
+//! all that's needed in order to create surfaces from the windows of any windowing library.
+//!
 
 pub use self::surface::Capabilities;
 pub use self::surface::Surface;
@@ -52,3 +55,4 @@ pub use self::swapchain::PresentError;
 pub mod display;
 mod surface;
 mod swapchain;
+pub mod win;
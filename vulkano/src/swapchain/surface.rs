@@ -15,7 +15,7 @@ use std::sync::Arc;
 
 use format::Format;
 use format::FormatDesc;
-use image::Usage as ImageUsage;
+use image::ImageUsage;
 use instance::Instance;
 use instance::PhysicalDevice;
 use instance::QueueFamily;
@@ -32,7 +32,6 @@ use vk;
 /// Represents a surface on the screen.
 ///
 /// Creating a `Surface` is platform-specific.
-#[derive(Debug)]
 pub struct Surface {
     instance: Arc<Instance>,
     surface: vk::SurfaceKHR,
@@ -419,6 +418,8 @@ impl Surface {
     }
 }
 
+impl_debug_handle!(Surface);
+
 unsafe impl VulkanObject for Surface {
     type Object = vk::SurfaceKHR;
 
@@ -446,6 +447,9 @@ pub enum SurfaceCreationError {
 
     /// The extension required for this function was not enabled.
     MissingExtension { name: &'static str },
+
+    /// The Vulkan implementation returned an error that this operation isn't supposed to return.
+    Unexpected(Error),
 }
 
 impl error::Error for SurfaceCreationError {
@@ -455,6 +459,9 @@ impl error::Error for SurfaceCreationError {
             SurfaceCreationError::OomError(_) => "not enough memory available",
             SurfaceCreationError::MissingExtension { .. } => "the extension required for this \
                                                               function was not enabled",
+            SurfaceCreationError::Unexpected(_) => {
+                "unexpected error returned by the Vulkan implementation"
+            },
         }
     }
 
@@ -462,6 +469,7 @@ impl error::Error for SurfaceCreationError {
     fn cause(&self) -> Option<&error::Error> {
         match *self {
             SurfaceCreationError::OomError(ref err) => Some(err),
+            SurfaceCreationError::Unexpected(ref err) => Some(err),
             _ => None
         }
     }
@@ -487,7 +495,7 @@ impl From<Error> for SurfaceCreationError {
         match err {
             err @ Error::OutOfHostMemory => SurfaceCreationError::OomError(OomError::from(err)),
             err @ Error::OutOfDeviceMemory => SurfaceCreationError::OomError(OomError::from(err)),
-            _ => panic!("unexpected error: {:?}", err)
+            _ => SurfaceCreationError::Unexpected(err)
         }
     }
 }
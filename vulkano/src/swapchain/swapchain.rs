@@ -54,7 +54,7 @@ pub struct Swapchain {
     /// acquire an image before it is presented.
     semaphores_pool: MsQueue<Arc<Semaphore>>,
 
-    images_semaphores: Mutex<Vec<Option<Arc<Semaphore>>>>,
+    images_semaphores: Mutex<Vec<ImageSemaphore>>,
 
     // If true, that means we have used this swapchain to recreate a new swapchain. The current
     // swapchain can no longer be used for anything except presenting already-acquired images.
@@ -207,7 +207,7 @@ impl Swapchain {
         {
             let mut semaphores = swapchain.images_semaphores.lock().unwrap();
             for _ in 0 .. images.len() {
-                semaphores.push(None);
+                semaphores.push(ImageSemaphore::NotAcquired);
             }
         }
 
@@ -228,40 +228,72 @@ impl Swapchain {
     /// If you try to draw on an image without acquiring it first, the execution will block. (TODO
     /// behavior may change).
     pub fn acquire_next_image(&self, timeout: Duration) -> Result<usize, AcquireError> {
-        unsafe {
-            let stale = self.stale.lock().unwrap();
-            if *stale {
-                return Err(AcquireError::OutOfDate);
-            }
+        let semaphore = self.semaphores_pool.try_pop().expect("Failed to obtain a semaphore from \
+                                                               the swapchain semaphores pool");
+
+        match unsafe { self.acquire_raw(timeout, semaphore.internal_object()) } {
+            Ok(id) => {
+                let mut images_semaphores = self.images_semaphores.lock().unwrap();
+                images_semaphores[id] = ImageSemaphore::Managed(semaphore);
+                Ok(id)
+            },
+            Err(err) => {
+                // The semaphore isn't signaled if the acquisition failed.
+                self.semaphores_pool.push(semaphore);
+                Err(err)
+            },
+        }
+    }
 
-            let vk = self.device.pointers();
+    /// Same as `acquire_next_image`, except that `semaphore` is signaled when the image is ready
+    /// to be drawn upon, instead of the swapchain handling the synchronization itself.
+    ///
+    /// The image must then be presented with `present_with_semaphore`.
+    ///
+    /// # Safety
+    ///
+    /// - `semaphore` must be unsignaled and must not be waited upon by a pending operation.
+    /// - The first submission that accesses the image must wait upon `semaphore`.
+    ///
+    pub unsafe fn acquire_next_image_with_semaphore(&self, timeout: Duration,
+                                                    semaphore: &Semaphore)
+                                                    -> Result<usize, AcquireError>
+    {
+        let id = try!(self.acquire_raw(timeout, semaphore.internal_object()));
+        let mut images_semaphores = self.images_semaphores.lock().unwrap();
+        images_semaphores[id] = ImageSemaphore::External;
+        Ok(id)
+    }
 
-            let semaphore = self.semaphores_pool.try_pop().expect("Failed to obtain a semaphore \
-                                                                   from the swapchain semaphores \
-                                                                   pool");
-
-            let timeout_ns = timeout.as_secs().saturating_mul(1_000_000_000)
-                                              .saturating_add(timeout.subsec_nanos() as u64);
-
-            let mut out = mem::uninitialized();
-            let r = try!(check_errors(vk.AcquireNextImageKHR(self.device.internal_object(),
-                                                             self.swapchain, timeout_ns,
-                                                             semaphore.internal_object(), 0,     // TODO: timeout
-                                                             &mut out)));
-
-            let id = match r {
-                Success::Success => out as usize,
-                Success::Suboptimal => out as usize,        // TODO: give that info to the user
-                Success::NotReady => return Err(AcquireError::Timeout),
-                Success::Timeout => return Err(AcquireError::Timeout),
-                s => panic!("unexpected success value: {:?}", s)
-            };
+    // Acquires an image and signals `semaphore` when it is ready.
+    unsafe fn acquire_raw(&self, timeout: Duration, semaphore: vk::Semaphore)
+                          -> Result<usize, AcquireError>
+    {
+        let stale = self.stale.lock().unwrap();
+        if *stale {
+            return Err(AcquireError::OutOfDate);
+        }
 
-            let mut images_semaphores = self.images_semaphores.lock().unwrap();
-            images_semaphores[id] = Some(semaphore);
+        let vk = self.device.pointers();
 
-            Ok(id)
-        }
+        let timeout_ns = timeout.as_secs().saturating_mul(1_000_000_000)
+                                          .saturating_add(timeout.subsec_nanos() as u64);
+
+        let mut out = mem::uninitialized();
+        let r = try!(check_errors(vk.AcquireNextImageKHR(self.device.internal_object(),
+                                                         self.swapchain, timeout_ns,
+                                                         semaphore, 0,     // TODO: timeout
+                                                         &mut out)));
+
+        let id = match r {
+            Success::Success => out as usize,
+            Success::Suboptimal => out as usize,        // TODO: give that info to the user
+            Success::NotReady => return Err(AcquireError::Timeout),
+            Success::Timeout => return Err(AcquireError::Timeout),
+            s => panic!("unexpected success value: {:?}", s)
+        };
+
+        Ok(id)
     }
 
     /// Presents an image on the screen.
@@ -271,39 +303,86 @@ impl Swapchain {
     ///
     /// The actual behavior depends on the present mode that you passed when creating the
     /// swapchain.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the image was acquired with `acquire_next_image_with_semaphore`.
+    ///
     pub fn present(&self, queue: &Arc<Queue>, index: usize) -> Result<(), PresentError> {
-        let vk = self.device.pointers();
-
         let wait_semaphore = {
             let mut images_semaphores = self.images_semaphores.lock().unwrap();
-            images_semaphores[index].take().expect("Trying to present an image that was \
-                                                    not acquired")
+            match mem::replace(&mut images_semaphores[index], ImageSemaphore::NotAcquired) {
+                ImageSemaphore::Managed(semaphore) => semaphore,
+                ImageSemaphore::NotAcquired => {
+                    panic!("Trying to present an image that was not acquired")
+                },
+                ImageSemaphore::External => {
+                    panic!("Images acquired with acquire_next_image_with_semaphore must be \
+                            presented with present_with_semaphore")
+                },
+            }
         };
 
         // FIXME: the semaphore may be destroyed ; need to return it
+        try!(unsafe { self.present_raw(queue, index, wait_semaphore.internal_object()) });
 
-        unsafe {
-            let mut result = mem::uninitialized();
+        self.semaphores_pool.push(wait_semaphore);
+        Ok(())
+    }
 
-            let queue = queue.internal_object_guard();
-            let index = index as u32;
+    /// Same as `present`, but for images that were acquired with
+    /// `acquire_next_image_with_semaphore`. The presentation waits upon `semaphore`.
+    ///
+    /// # Safety
+    ///
+    /// - `semaphore` must be signaled by a submission that has been made, and that accesses the
+    ///   image for the last time before it is presented.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the image wasn't acquired with `acquire_next_image_with_semaphore`.
+    ///
+    pub unsafe fn present_with_semaphore(&self, queue: &Arc<Queue>, index: usize,
+                                         semaphore: &Semaphore) -> Result<(), PresentError>
+    {
+        {
+            let mut images_semaphores = self.images_semaphores.lock().unwrap();
+            match images_semaphores[index] {
+                ImageSemaphore::External => (),
+                _ => panic!("Only images acquired with acquire_next_image_with_semaphore can be \
+                             presented with present_with_semaphore"),
+            }
+            images_semaphores[index] = ImageSemaphore::NotAcquired;
+        }
 
-            let infos = vk::PresentInfoKHR {
-                sType: vk::STRUCTURE_TYPE_PRESENT_INFO_KHR,
-                pNext: ptr::null(),
-                waitSemaphoreCount: 1,
-                pWaitSemaphores: &wait_semaphore.internal_object(),
-                swapchainCount: 1,
-                pSwapchains: &self.swapchain,
-                pImageIndices: &index,
-                pResults: &mut result,
-            };
+        self.present_raw(queue, index, semaphore.internal_object())
+    }
 
-            try!(check_errors(vk.QueuePresentKHR(*queue, &infos)));
-            //try!(check_errors(result));       // TODO: AMD driver doesn't seem to write the result
-        }
+    // Presents an image after `semaphore` is signaled.
+    unsafe fn present_raw(&self, queue: &Arc<Queue>, index: usize, semaphore: vk::Semaphore)
+                          -> Result<(), PresentError>
+    {
+        let vk = self.device.pointers();
+
+        let mut result = mem::uninitialized();
+
+        let queue = queue.internal_object_guard();
+        let index = index as u32;
+
+        let infos = vk::PresentInfoKHR {
+            sType: vk::STRUCTURE_TYPE_PRESENT_INFO_KHR,
+            pNext: ptr::null(),
+            waitSemaphoreCount: 1,
+            pWaitSemaphores: &semaphore,
+            swapchainCount: 1,
+            pSwapchains: &self.swapchain,
+            pImageIndices: &index,
+            pResults: &mut result,
+        };
+
+        try!(check_errors(vk.QueuePresentKHR(*queue, &infos)));
+        //try!(check_errors(result));       // TODO: AMD driver doesn't seem to write the result
 
-        self.semaphores_pool.push(wait_semaphore);
         Ok(())
     }
 
@@ -321,10 +400,38 @@ impl Swapchain {
     #[inline]
     pub fn image_semaphore(&self, id: u32, semaphore: Arc<Semaphore>) -> Option<Arc<Semaphore>> {
         let mut semaphores = self.images_semaphores.lock().unwrap();
-        mem::replace(&mut semaphores[id as usize], Some(semaphore))
+        match semaphores[id as usize] {
+            ImageSemaphore::Managed(ref mut current) => Some(mem::replace(current, semaphore)),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the image was acquired with `acquire_next_image_with_semaphore`.
+    #[doc(hidden)]
+    #[inline]
+    pub fn is_externally_synchronized(&self, id: u32) -> bool {
+        let semaphores = self.images_semaphores.lock().unwrap();
+        match semaphores[id as usize] {
+            ImageSemaphore::External => true,
+            _ => false,
+        }
     }
 }
 
+// Synchronization state of an image of the swapchain.
+enum ImageSemaphore {
+    // The image isn't acquired.
+    NotAcquired,
+
+    // The image was acquired with `acquire_next_image`. The next access to the image waits upon
+    // this semaphore and replaces it with a semaphore that the access signals.
+    Managed(Arc<Semaphore>),
+
+    // The image was acquired with `acquire_next_image_with_semaphore`. The user synchronizes
+    // the accesses to the image with the presentation engine.
+    External,
+}
+
 impl Drop for Swapchain {
     #[inline]
     fn drop(&mut self) {
@@ -354,6 +461,9 @@ pub enum AcquireError {
     /// The surface has changed in a way that makes the swapchain unusable. You must query the
     /// surface's new properties and recreate a new swapchain if you want to continue drawing.
     OutOfDate,
+
+    /// The Vulkan implementation returned an error that this operation isn't supposed to return.
+    Unexpected(Error),
 }
 
 impl error::Error for AcquireError {
@@ -365,6 +475,7 @@ impl error::Error for AcquireError {
             AcquireError::Timeout => "no image is available for acquiring yet",
             AcquireError::SurfaceLost => "the surface of this swapchain is no longer valid",
             AcquireError::OutOfDate => "the swapchain needs to be recreated",
+            AcquireError::Unexpected(_) => "unexpected error returned by the Vulkan implementation",
         }
     }
 
@@ -372,6 +483,7 @@ impl error::Error for AcquireError {
     fn cause(&self) -> Option<&error::Error> {
         match *self {
             AcquireError::OomError(ref err) => Some(err),
+            AcquireError::Unexpected(ref err) => Some(err),
             _ => None
         }
     }
@@ -393,7 +505,7 @@ impl From<Error> for AcquireError {
             Error::DeviceLost => AcquireError::DeviceLost,
             Error::SurfaceLost => AcquireError::SurfaceLost,
             Error::OutOfDate => AcquireError::OutOfDate,
-            _ => panic!("unexpected error: {:?}", err)
+            _ => AcquireError::Unexpected(err)
         }
     }
 }
@@ -414,6 +526,9 @@ pub enum PresentError {
     /// The surface has changed in a way that makes the swapchain unusable. You must query the
     /// surface's new properties and recreate a new swapchain if you want to continue drawing.
     OutOfDate,
+
+    /// The Vulkan implementation returned an error that this operation isn't supposed to return.
+    Unexpected(Error),
 }
 
 impl error::Error for PresentError {
@@ -424,6 +539,7 @@ impl error::Error for PresentError {
             PresentError::DeviceLost => "the connection to the device has been lost",
             PresentError::SurfaceLost => "the surface of this swapchain is no longer valid",
             PresentError::OutOfDate => "the swapchain needs to be recreated",
+            PresentError::Unexpected(_) => "unexpected error returned by the Vulkan implementation",
         }
     }
 
@@ -431,6 +547,7 @@ impl error::Error for PresentError {
     fn cause(&self) -> Option<&error::Error> {
         match *self {
             PresentError::OomError(ref err) => Some(err),
+            PresentError::Unexpected(ref err) => Some(err),
             _ => None
         }
     }
@@ -452,7 +569,7 @@ impl From<Error> for PresentError {
             Error::DeviceLost => PresentError::DeviceLost,
             Error::SurfaceLost => PresentError::SurfaceLost,
             Error::OutOfDate => PresentError::OutOfDate,
-            _ => panic!("unexpected error: {:?}", err)
+            _ => PresentError::Unexpected(err)
         }
     }
 }
@@ -0,0 +1,121 @@
+// Copyright (c) 2016 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+//! Integration point for windowing libraries.
+//!
+//! Any windowing library (winit, GLFW, SDL2, custom platform code, ...) needs the same two
+//! things in order to be used with vulkano:
+//!
+//! - Enabling the surface extensions of the current platform when creating the `Instance`. This
+//!   is what `required_extensions()` returns.
+//! - Creating a `Surface` from the native handles of the window, with one of the raw
+//!   constructors of `Surface`:
+//!
+//!     - `Surface::from_xlib` and `Surface::from_xcb` for X11.
+//!     - `Surface::from_wayland` for Wayland.
+//!     - `Surface::from_mir` for Mir.
+//!     - `Surface::from_hwnd` for Win32.
+//!     - `Surface::from_anativewindow` for Android.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use vulkano::instance::Instance;
+//! use vulkano::swapchain::win;
+//!
+//! let instance = Instance::new(None, &win::required_extensions(), None).unwrap();
+//! // Create the window with your library of choice, then pass its native handles to one of the
+//! // `win::Surface::from_*` constructors.
+//! ```
+
+use instance::InstanceExtensions;
+use instance::SupportedExtensionsError;
+
+pub use swapchain::Surface;
+pub use swapchain::SurfaceCreationError;
+
+/// See the docs of required_extensions().
+pub fn required_extensions_raw() -> Result<InstanceExtensions, SupportedExtensionsError> {
+    let supported = try!(InstanceExtensions::supported_by_core_raw());
+    Ok(supported.intersection(&platform_extensions()))
+}
+
+/// Returns the surface extensions of the current platform that are supported by the Vulkan
+/// implementation.
+///
+/// Pass the returned list when creating the `Instance` so that surfaces can then be created from
+/// the windows of the platform.
+///
+/// # Panic
+///
+/// - Panicks if the Vulkan shared library couldn't be loaded.
+/// - Panicks if the host ran out of memory.
+///
+#[inline]
+pub fn required_extensions() -> InstanceExtensions {
+    required_extensions_raw().unwrap()
+}
+
+// Returns the list of surface extensions that can be used on the target platform.
+fn platform_extensions() -> InstanceExtensions {
+    let mut extensions = InstanceExtensions {
+        khr_surface: true,
+        .. InstanceExtensions::none()
+    };
+
+    if cfg!(target_os = "android") {
+        extensions.khr_android_surface = true;
+    } else if cfg!(windows) {
+        extensions.khr_win32_surface = true;
+    } else if cfg!(all(unix, not(target_os = "macos"), not(target_os = "ios"))) {
+        extensions.khr_xlib_surface = true;
+        extensions.khr_xcb_surface = true;
+        extensions.khr_wayland_surface = true;
+        extensions.khr_mir_surface = true;
+    }
+
+    extensions
+}
+
+#[cfg(test)]
+mod tests {
+    use instance::Instance;
+    use swapchain::win;
+
+    #[test]
+    fn platform_extensions() {
+        let extensions = win::platform_extensions();
+        assert!(extensions.khr_surface);
+        assert!(!extensions.khr_display);
+        assert!(!extensions.ext_debug_report);
+
+        assert_eq!(extensions.khr_win32_surface, cfg!(windows));
+        assert_eq!(extensions.khr_android_surface, cfg!(target_os = "android"));
+        assert_eq!(extensions.khr_xlib_surface,
+                   cfg!(all(unix, not(target_os = "android"), not(target_os = "macos"),
+                            not(target_os = "ios"))));
+    }
+
+    #[test]
+    fn headless_instance() {
+        let extensions = match win::required_extensions_raw() {
+            Ok(e) => e,
+            Err(_) => return
+        };
+
+        assert_eq!(extensions.intersection(&win::platform_extensions()), extensions);
+
+        let instance = match Instance::new(None, &extensions, None) {
+            Ok(i) => i,
+            Err(_) => return
+        };
+
+        assert_eq!(instance.loaded_extensions(), &extensions);
+    }
+}
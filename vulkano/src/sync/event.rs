@@ -28,10 +28,25 @@ use vk;
 /// event to be signaled, in order to avoid interfering with progress of other clients of the GPU.
 /// If the event isn't signaled within these limits, results are undefined and may include
 /// device loss.
-#[derive(Debug)]
+///
+/// # Hazards
+///
+/// An event can be set and reset both from the host (with the methods of this struct) and from
+/// the device (with commands in a command buffer). The methods of `Event` only take `&self`, but
+/// the state of the event is shared between the host and the device:
+///
+/// - Calling `set` or `reset` while a command buffer that sets or resets the same event is
+///   executing leads to a race, and the final state of the event is unspecified.
+/// - The value returned by `signaled` is only a snapshot. If a command buffer that modifies the
+///   event is still executing, the state may have changed by the time the function returns.
+///   Wait for the fence of the submission before relying on the state of the event.
 pub struct Event {
     device: Arc<Device>,
-    event: Mutex<vk::Event>,
+    // The handle never changes, so it can be read without locking.
+    event: vk::Event,
+    // Holds the same handle, and is locked while calling functions that require the event to
+    // be externally synchronized.
+    sync: Mutex<vk::Event>,
 }
 
 impl Event {
@@ -56,7 +71,8 @@ impl Event {
 
         Ok(Event {
             device: device.clone(),
-            event: Mutex::new(event),
+            event: event,
+            sync: Mutex::new(event),
         })
     }
     
@@ -72,13 +88,14 @@ impl Event {
     }
 
     /// Returns true if the event is signaled.
+    ///
+    /// See the documentation of `Event` for the rules regarding accesses from the device.
     #[inline]
     pub fn signaled(&self) -> Result<bool, OomError> {
         unsafe {
             let vk = self.device.pointers();
-            let event = self.event.lock().unwrap();
             let result = try!(check_errors(vk.GetEventStatus(self.device.internal_object(),
-                                                             *event)));
+                                                             self.event)));
             match result {
                 Success::EventSet => Ok(true),
                 Success::EventReset => Ok(false),
@@ -92,7 +109,7 @@ impl Event {
     pub fn set_raw(&self) -> Result<(), OomError> {
         unsafe {
             let vk = self.device.pointers();
-            let event = self.event.lock().unwrap();
+            let event = self.sync.lock().unwrap();
             try!(check_errors(vk.SetEvent(self.device.internal_object(), *event)).map(|_| ()));
             Ok(())
         }
@@ -102,6 +119,8 @@ impl Event {
     ///
     /// If a command buffer is waiting on this event, it is then unblocked.
     ///
+    /// This must not be done while a command buffer that sets or resets this event is executing.
+    ///
     /// # Panic
     ///
     /// - Panicks if the device or host ran out of memory.
@@ -116,7 +135,7 @@ impl Event {
     pub fn reset_raw(&self) -> Result<(), OomError> {
         unsafe {
             let vk = self.device.pointers();
-            let event = self.event.lock().unwrap();
+            let event = self.sync.lock().unwrap();
             try!(check_errors(vk.ResetEvent(self.device.internal_object(), *event)).map(|_| ()));
             Ok(())
         }
@@ -124,6 +143,8 @@ impl Event {
 
     /// Changes the `Event` to the unsignaled state.
     ///
+    /// This must not be done while a command buffer that sets or resets this event is executing.
+    ///
     /// # Panic
     ///
     /// - Panicks if the device or host ran out of memory.
@@ -134,12 +155,23 @@ impl Event {
     }
 }
 
+impl_debug_handle!(Event);
+
+unsafe impl VulkanObject for Event {
+    type Object = vk::Event;
+
+    #[inline]
+    fn internal_object(&self) -> vk::Event {
+        self.event
+    }
+}
+
 unsafe impl SynchronizedVulkanObject for Event {
     type Object = vk::Event;
 
     #[inline]
     fn internal_object_guard(&self) -> MutexGuard<vk::Event> {
-        self.event.lock().unwrap()
+        self.sync.lock().unwrap()
     }
 }
 
@@ -148,8 +180,7 @@ impl Drop for Event {
     fn drop(&mut self) {
         unsafe {
             let vk = self.device.pointers();
-            let event = self.event.lock().unwrap();
-            vk.DestroyEvent(self.device.internal_object(), *event, ptr::null());
+            vk.DestroyEvent(self.device.internal_object(), self.event, ptr::null());
         }
     }
 }
@@ -186,4 +217,17 @@ mod tests {
         event.reset();
         assert!(!event.signaled().unwrap());
     }
+
+    #[test]
+    fn event_raw_set_reset() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let event = Event::raw(&device).unwrap();
+        event.set_raw().unwrap();
+        event.set_raw().unwrap();
+        assert!(event.signaled().unwrap());
+
+        event.reset_raw().unwrap();
+        assert!(!event.signaled().unwrap());
+    }
 }
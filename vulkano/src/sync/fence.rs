@@ -15,6 +15,7 @@ use std::sync::Arc;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
 use std::time::Duration;
+use std::u64;
 use smallvec::SmallVec;
 
 use device::Device;
@@ -32,7 +33,6 @@ use vk;
 /// When a command buffer accesses a ressource, you have to ensure that the CPU doesn't access
 /// the same ressource simultaneously (except for concurrent reads). Therefore in order to know
 /// when the CPU can access a ressource again, a fence has to be used.
-#[derive(Debug)]
 pub struct Fence<D = Arc<Device>> where D: SafeDeref<Target = Device> {
     fence: vk::Fence,
 
@@ -42,6 +42,10 @@ pub struct Fence<D = Arc<Device>> where D: SafeDeref<Target = Device> {
     // This variable exists so that we don't need to call `vkGetFenceStatus` or `vkWaitForFences`
     // multiple times.
     signaled: AtomicBool,
+
+    // True if the fence has been submitted to a queue and we don't know yet whether it is
+    // signaled. Only used to detect invalid resets.
+    submitted: AtomicBool,
 }
 
 impl<D> Fence<D> where D: SafeDeref<Target = Device> {
@@ -76,6 +80,10 @@ impl<D> Fence<D> where D: SafeDeref<Target = Device> {
 
     /// Builds a new fence already in the "signaled" state.
     ///
+    /// This is useful when you need a fence that stands for an operation that has already
+    /// completed, for example when waiting for the "previous frame" before the first frame has
+    /// been submitted. Calling `ready()` or `wait()` on this fence returns immediately.
+    ///
     /// # Panic
     ///
     /// - Panicks if the device or host ran out of memory.
@@ -109,6 +117,7 @@ impl<D> Fence<D> where D: SafeDeref<Target = Device> {
             fence: fence,
             device: device.clone(),
             signaled: AtomicBool::new(signaled),
+            submitted: AtomicBool::new(false),
         })
     }
 
@@ -136,23 +145,30 @@ impl<D> Fence<D> where D: SafeDeref<Target = Device> {
     /// timeout has elapsed.
     ///
     /// Returns `Ok` if the fence is now signaled. Returns `Err` if the timeout was reached instead.
+    ///
+    /// A timeout that is too large to be represented as a `u64` number of nanoseconds (more
+    /// than about 584 years) means "wait forever". A timeout of zero doesn't block and only
+    /// checks whether the fence is signaled, similar to `ready()`.
     pub fn wait(&self, timeout: Duration) -> Result<(), FenceWaitError> {
         unsafe {
             if self.signaled.load(Ordering::Relaxed) { return Ok(()); }
 
-            let timeout_ns = timeout.as_secs().saturating_mul(1_000_000_000)
-                                              .saturating_add(timeout.subsec_nanos() as u64);
-
+            let timeout_ns = timeout_nanoseconds(timeout);
             let vk = self.device.pointers();
-            let r = try!(check_errors(vk.WaitForFences(self.device.internal_object(), 1,
-                                                       &self.fence, vk::TRUE, timeout_ns)));
+
+            let r = if timeout_ns == 0 {
+                try!(check_errors(vk.GetFenceStatus(self.device.internal_object(), self.fence)))
+            } else {
+                try!(check_errors(vk.WaitForFences(self.device.internal_object(), 1,
+                                                   &self.fence, vk::TRUE, timeout_ns)))
+            };
 
             match r {
                 Success::Success => {
                     self.signaled.store(true, Ordering::Relaxed);
                     Ok(())
                 },
-                Success::Timeout => {
+                Success::Timeout | Success::NotReady => {
                     Err(FenceWaitError::Timeout)
                 },
                 _ => unreachable!()
@@ -160,17 +176,46 @@ impl<D> Fence<D> where D: SafeDeref<Target = Device> {
         }
     }
 
+    /// Waits until the fence is signaled, without any timeout.
+    ///
+    /// Returns `Err` only if the device was lost or if an out of memory error happened.
+    #[inline]
+    pub fn wait_forever(&self) -> Result<(), FenceWaitError> {
+        self.wait(Duration::new(u64::MAX, 999_999_999))
+    }
+
     /// Waits for multiple fences at once.
     ///
+    /// Same as `wait_multiple` with `wait_all` set to true.
+    ///
     /// # Panic
     ///
     /// Panicks if not all fences belong to the same device.
+    #[inline]
     pub fn multi_wait<'a, I>(iter: I, timeout: Duration) -> Result<(), FenceWaitError>
         where I: IntoIterator<Item = &'a Fence<D>>, D: 'a
+    {
+        Fence::wait_multiple(iter, true, timeout)
+    }
+
+    /// Waits until either all the fences or at least one of them are signaled, or at least until
+    /// the timeout has elapsed.
+    ///
+    /// If `wait_all` is true, returns `Ok` once all the fences are signaled. Otherwise returns
+    /// `Ok` once at least one of them is signaled. Returns `Err` if the timeout was reached
+    /// instead. Returns `Ok` immediately if the list is empty.
+    ///
+    /// # Panic
+    ///
+    /// Panicks if not all fences belong to the same device.
+    pub fn wait_multiple<'a, I>(iter: I, wait_all: bool, timeout: Duration)
+                                -> Result<(), FenceWaitError>
+        where I: IntoIterator<Item = &'a Fence<D>>, D: 'a
     {
         let mut device: Option<&Device> = None;
+        let mut any_signaled = false;
 
-        let fences: SmallVec<[vk::Fence; 8]> = iter.into_iter().filter_map(|fence| {
+        let fences: SmallVec<[&Fence<D>; 8]> = iter.into_iter().filter(|fence| {
             match &mut device {
                 dev @ &mut None => *dev = Some(&*fence.device),
                 &mut Some(ref dev) if &**dev as *const Device == &*fence.device as *const Device => {},
@@ -179,73 +224,163 @@ impl<D> Fence<D> where D: SafeDeref<Target = Device> {
             };
 
             if fence.signaled.load(Ordering::Relaxed) {
-                None
+                any_signaled = true;
+                false
             } else {
-                Some(fence.fence)
+                true
             }
         }).collect();
 
-        let timeout_ns = timeout.as_secs().saturating_mul(1_000_000_000)
-                                          .saturating_add(timeout.subsec_nanos() as u64);
-
-        let r = if let Some(device) = device {
-            unsafe {
-                let vk = device.pointers();
-                try!(check_errors(vk.WaitForFences(device.internal_object(), fences.len() as u32,
-                                                   fences.as_ptr(), vk::TRUE, timeout_ns)))
-            }
-        } else {
+        if fences.is_empty() || (!wait_all && any_signaled) {
             return Ok(());
+        }
+
+        let raw_fences: SmallVec<[vk::Fence; 8]> = fences.iter().map(|f| f.fence).collect();
+
+        let timeout_ns = timeout_nanoseconds(timeout);
+
+        let r = unsafe {
+            let device = device.unwrap();
+            let vk = device.pointers();
+            try!(check_errors(vk.WaitForFences(device.internal_object(),
+                                               raw_fences.len() as u32, raw_fences.as_ptr(),
+                                               if wait_all { vk::TRUE } else { vk::FALSE },
+                                               timeout_ns)))
         };
 
         match r {
-            Success::Success => Ok(()),
+            Success::Success => {
+                if wait_all {
+                    for fence in fences.iter() {
+                        fence.signaled.store(true, Ordering::Relaxed);
+                    }
+                }
+                Ok(())
+            },
             Success::Timeout => Err(FenceWaitError::Timeout),
             _ => unreachable!()
         }
     }
 
-    /// Resets the fence.
+    /// Waits until at least one of the fences is signaled, or at least until the timeout has
+    /// elapsed.
+    ///
+    /// Returns the index within the list of a fence that is signaled. If multiple fences are
+    /// signaled, any of them can be returned.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the list is empty.
+    /// - Panicks if not all fences belong to the same device.
+    ///
+    pub fn wait_any<'a, I>(iter: I, timeout: Duration) -> Result<usize, FenceWaitError>
+        where I: IntoIterator<Item = &'a Fence<D>>, D: 'a
+    {
+        let fences: SmallVec<[&Fence<D>; 8]> = iter.into_iter().collect();
+        assert!(!fences.is_empty(), "Tried to wait for any fence of an empty list");
+
+        try!(Fence::wait_multiple(fences.iter().cloned(), false, timeout));
+
+        // `vkWaitForFences` doesn't tell which fence is signaled, so we have to query them.
+        for (index, fence) in fences.iter().enumerate() {
+            if try!(fence.ready()) {
+                return Ok(index);
+            }
+        }
+
+        // The fence that was signaled has been reset in the meantime by someone else.
+        Err(FenceWaitError::Timeout)
+    }
+
+    /// Resets the fence, so that it can be used again in a submission.
+    ///
+    /// Returns `FenceResetError::InUse` if the fence was submitted to a queue and isn't signaled
+    /// yet, as it is still in use by a submission that hasn't finished.
     // FIXME: must synchronize the fence
     #[inline]
-    pub fn reset(&self) {
+    pub fn reset(&self) -> Result<(), FenceResetError> {
+        if self.submitted.load(Ordering::Relaxed) && !try!(self.ready()) {
+            return Err(FenceResetError::InUse);
+        }
+
         unsafe {
             let vk = self.device.pointers();
-            vk.ResetFences(self.device.internal_object(), 1, &self.fence);
+            try!(check_errors(vk.ResetFences(self.device.internal_object(), 1, &self.fence)));
             self.signaled.store(false, Ordering::Relaxed);
+            self.submitted.store(false, Ordering::Relaxed);
+            Ok(())
         }
     }
 
-    /// Resets multiple fences at once.
+    /// Resets multiple fences at once, with a single call to the driver.
+    ///
+    /// The same rules as `reset` apply to each fence. If one of the fences is still in use,
+    /// `FenceResetError::InUse` is returned and none of the fences is reset.
     ///
     /// # Panic
     ///
-    /// Panicks if not all fences belong to the same device.
-    pub fn multi_reset<'a, I>(iter: I)
+    /// - Panicks if not all fences belong to the same device.
+    ///
+    pub fn multi_reset<'a, I>(iter: I) -> Result<(), FenceResetError>
         where I: IntoIterator<Item = &'a Fence<D>>, D: 'a
     {
         let mut device: Option<&Device> = None;
 
-        let fences: SmallVec<[vk::Fence; 8]> = iter.into_iter().map(|fence| {
+        let fences: SmallVec<[&Fence<D>; 8]> = iter.into_iter().map(|fence| {
             match &mut device {
                 dev @ &mut None => *dev = Some(&*fence.device),
                 &mut Some(ref dev) if &**dev as *const Device == &*fence.device as *const Device => {},
                 _ => panic!("Tried to reset multiple fences that didn't belong to the same device"),
             };
 
-            fence.signaled.store(false, Ordering::Relaxed);
-            fence.fence
+            fence
         }).collect();
 
-        if let Some(device) = device {
-            unsafe {
-                let vk = device.pointers();
-                vk.ResetFences(device.internal_object(), fences.len() as u32, fences.as_ptr());
+        for fence in fences.iter() {
+            if fence.submitted.load(Ordering::Relaxed) && !try!(fence.ready()) {
+                return Err(FenceResetError::InUse);
             }
         }
+
+        let device = match device {
+            Some(d) => d,
+            None => return Ok(()),
+        };
+
+        let raw_fences: SmallVec<[vk::Fence; 8]> = fences.iter().map(|f| f.fence).collect();
+
+        unsafe {
+            let vk = device.pointers();
+            try!(check_errors(vk.ResetFences(device.internal_object(), raw_fences.len() as u32,
+                                             raw_fences.as_ptr())));
+        }
+
+        for fence in fences.iter() {
+            fence.signaled.store(false, Ordering::Relaxed);
+            fence.submitted.store(false, Ordering::Relaxed);
+        }
+
+        Ok(())
+    }
+
+    /// Marks the fence as submitted to a queue. Must be called after the fence has been passed
+    /// to a queue submission.
+    #[doc(hidden)]
+    #[inline]
+    pub fn mark_submitted(&self) {
+        self.submitted.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns true if the fence has been submitted to a queue and hasn't been reset since then.
+    #[doc(hidden)]
+    #[inline]
+    pub fn is_submitted(&self) -> bool {
+        self.submitted.load(Ordering::Relaxed)
     }
 }
 
+impl_debug_handle!(Fence<D> where D: SafeDeref<Target = Device>);
+
 unsafe impl<D> VulkanObject for Fence<D> where D: SafeDeref<Target = Device> {
     type Object = vk::Fence;
 
@@ -265,6 +400,14 @@ impl<D> Drop for Fence<D> where D: SafeDeref<Target = Device> {
     }
 }
 
+// Converts a `Duration` to the number of nanoseconds expected by `vkWaitForFences`. Durations
+// that don't fit in a `u64` saturate to `u64::MAX`, which Vulkan treats as an infinite timeout.
+#[inline]
+fn timeout_nanoseconds(timeout: Duration) -> u64 {
+    timeout.as_secs().saturating_mul(1_000_000_000)
+                     .saturating_add(timeout.subsec_nanos() as u64)
+}
+
 /// Error that can be returned when waiting on a fence.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum FenceWaitError {
@@ -276,6 +419,9 @@ pub enum FenceWaitError {
 
     /// The device has been lost.
     DeviceLostError,
+
+    /// The Vulkan implementation returned an error that this operation isn't supposed to return.
+    Unexpected(Error),
 }
 
 impl error::Error for FenceWaitError {
@@ -285,6 +431,9 @@ impl error::Error for FenceWaitError {
             FenceWaitError::OomError(_) => "no memory available",
             FenceWaitError::Timeout => "the timeout has been reached",
             FenceWaitError::DeviceLostError => "the device was lost",
+            FenceWaitError::Unexpected(_) => {
+                "unexpected error returned by the Vulkan implementation"
+            },
         }
     }
 
@@ -292,6 +441,7 @@ impl error::Error for FenceWaitError {
     fn cause(&self) -> Option<&error::Error> {
         match *self {
             FenceWaitError::OomError(ref err) => Some(err),
+            FenceWaitError::Unexpected(ref err) => Some(err),
             _ => None
         }
     }
@@ -304,6 +454,13 @@ impl fmt::Display for FenceWaitError {
     }
 }
 
+impl From<OomError> for FenceWaitError {
+    #[inline]
+    fn from(err: OomError) -> FenceWaitError {
+        FenceWaitError::OomError(err)
+    }
+}
+
 impl From<Error> for FenceWaitError {
     #[inline]
     fn from(err: Error) -> FenceWaitError {
@@ -311,15 +468,77 @@ impl From<Error> for FenceWaitError {
             Error::OutOfHostMemory => FenceWaitError::OomError(From::from(err)),
             Error::OutOfDeviceMemory => FenceWaitError::OomError(From::from(err)),
             Error::DeviceLost => FenceWaitError::DeviceLostError,
-            _ => panic!("Unexpected error value: {}", err as i32)
+            _ => FenceWaitError::Unexpected(err)
+        }
+    }
+}
+
+/// Error that can be returned when resetting a fence.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FenceResetError {
+    /// Not enough memory to reset the fence.
+    OomError(OomError),
+
+    /// The fence was submitted to a queue and isn't signaled yet.
+    InUse,
+}
+
+impl error::Error for FenceResetError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            FenceResetError::OomError(_) => "no memory available",
+            FenceResetError::InUse => "the fence is still in use by a submission",
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            FenceResetError::OomError(ref err) => Some(err),
+            _ => None
         }
     }
 }
 
+impl fmt::Display for FenceResetError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<OomError> for FenceResetError {
+    #[inline]
+    fn from(err: OomError) -> FenceResetError {
+        FenceResetError::OomError(err)
+    }
+}
+
+impl From<Error> for FenceResetError {
+    #[inline]
+    fn from(err: Error) -> FenceResetError {
+        FenceResetError::OomError(From::from(err))
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
     use std::time::Duration;
+    use std::u64;
+
+    use buffer::BufferUsage;
+    use buffer::CpuAccessibleBuffer;
+    use command_buffer::CommandBufferPool;
+    use command_buffer::PrimaryCommandBufferBuilder;
+    use command_buffer::submit;
+    use command_buffer::submit_with;
     use sync::Fence;
+    use sync::FencePool;
+    use sync::FenceResetError;
+    use sync::FenceWaitError;
+    use super::timeout_nanoseconds;
 
     #[test]
     #[ignore]       // TODO: fails on AMD + Windows
@@ -346,16 +565,59 @@ mod tests {
         fence.wait(Duration::new(0, 10)).unwrap();
     }
 
+    #[test]
+    fn fence_signaled_raw() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let fence = Fence::signaled_raw(&device).unwrap();
+        assert!(fence.ready().unwrap());
+        fence.wait(Duration::new(0, 0)).unwrap();
+        assert!(!fence.is_submitted());
+    }
+
     #[test]
     #[ignore]       // TODO: fails on AMD + Windows
     fn fence_reset() {
         let (device, _) = gfx_dev_and_queue!();
 
         let fence = Fence::signaled(&device);
-        fence.reset();
+        fence.reset().unwrap();
         assert!(!fence.ready().unwrap());
     }
 
+    #[test]
+    fn zero_timeout_does_not_block() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let fence = Fence::new(&device);
+        assert_eq!(fence.wait(Duration::new(0, 0)), Err(FenceWaitError::Timeout));
+
+        let fence = Fence::signaled(&device);
+        assert_eq!(fence.wait(Duration::new(0, 0)), Ok(()));
+    }
+
+    #[test]
+    fn wait_forever_signaled() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let fence = Fence::signaled(&device);
+        fence.wait_forever().unwrap();
+    }
+
+    #[test]
+    fn timeout_conversion() {
+        assert_eq!(timeout_nanoseconds(Duration::new(0, 0)), 0);
+        assert_eq!(timeout_nanoseconds(Duration::new(3, 25)), 3_000_000_025);
+        assert_eq!(timeout_nanoseconds(Duration::new(18_446_744_073, 709_551_615)), u64::MAX);
+    }
+
+    #[test]
+    fn timeout_conversion_saturates() {
+        assert_eq!(timeout_nanoseconds(Duration::new(18_446_744_073, 709_551_616)), u64::MAX);
+        assert_eq!(timeout_nanoseconds(Duration::new(u64::MAX / 2, 0)), u64::MAX);
+        assert_eq!(timeout_nanoseconds(Duration::new(u64::MAX, 999_999_999)), u64::MAX);
+    }
+
     #[test]
     #[should_panic = "Tried to wait for multiple fences that didn't belong to the same device"]
     fn multiwait_different_devices() {
@@ -368,6 +630,150 @@ mod tests {
         let _ = Fence::multi_wait([&*fence1, &*fence2].iter().cloned(), Duration::new(0, 10));
     }
 
+    #[test]
+    fn wait_multiple_all() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let fence1 = Fence::signaled(&device);
+        let fence2 = Fence::signaled(&device);
+        Fence::wait_multiple([&*fence1, &*fence2].iter().cloned(), true,
+                             Duration::new(0, 10)).unwrap();
+
+        let unsignaled = Fence::new(&device);
+        match Fence::wait_multiple([&*fence1, &*unsignaled].iter().cloned(), true,
+                                   Duration::new(0, 10))
+        {
+            Err(FenceWaitError::Timeout) => (),
+            _ => panic!()
+        };
+    }
+
+    #[test]
+    fn wait_multiple_any() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let unsignaled1 = Fence::new(&device);
+        let unsignaled2 = Fence::new(&device);
+        let signaled = Fence::signaled(&device);
+
+        Fence::wait_multiple([&*unsignaled1, &*signaled].iter().cloned(), false,
+                             Duration::new(0, 10)).unwrap();
+
+        let index = Fence::wait_any([&*unsignaled1, &*unsignaled2, &*signaled].iter().cloned(),
+                                    Duration::new(0, 10)).unwrap();
+        assert_eq!(index, 2);
+
+        match Fence::wait_any([&*unsignaled1, &*unsignaled2].iter().cloned(),
+                              Duration::new(0, 10))
+        {
+            Err(FenceWaitError::Timeout) => (),
+            _ => panic!()
+        };
+    }
+
+    #[test]
+    fn wait_multiple_submissions() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let pool = CommandBufferPool::new(&device, &queue.family());
+        let usage = BufferUsage { transfer_dest: true, .. BufferUsage::none() };
+        let buffer1 = CpuAccessibleBuffer::<u32>::new(&device, &usage,
+                                                      Some(queue.family())).unwrap();
+        let buffer2 = CpuAccessibleBuffer::<u32>::new(&device, &usage,
+                                                      Some(queue.family())).unwrap();
+
+        let cb1 = PrimaryCommandBufferBuilder::new(&pool).update_buffer(&buffer1, &1).build();
+        let cb2 = PrimaryCommandBufferBuilder::new(&pool).update_buffer(&buffer2, &2).build();
+
+        let submission1 = submit(&cb1, &queue).unwrap();
+        let submission2 = submit(&cb2, &queue).unwrap();
+
+        let fences = [&**submission1.fence(), &**submission2.fence()];
+        Fence::wait_any(fences.iter().cloned(), Duration::new(5, 0)).unwrap();
+        Fence::wait_multiple(fences.iter().cloned(), true, Duration::new(5, 0)).unwrap();
+        assert!(submission1.finished() && submission2.finished());
+    }
+
+    #[test]
+    fn multi_reset() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let fence1 = Fence::signaled(&device);
+        let fence2 = Fence::signaled(&device);
+
+        Fence::multi_reset([&*fence1, &*fence2].iter().cloned()).unwrap();
+        assert!(!fence1.ready().unwrap());
+        assert!(!fence2.ready().unwrap());
+    }
+
+    #[test]
+    fn multi_reset_submitted_fences() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let pool = CommandBufferPool::new(&device, &queue.family());
+        let usage = BufferUsage { transfer_dest: true, .. BufferUsage::none() };
+        let buffer = CpuAccessibleBuffer::<u32>::new(&device, &usage,
+                                                     Some(queue.family())).unwrap();
+
+        let cb1 = PrimaryCommandBufferBuilder::new(&pool).update_buffer(&buffer, &1).build();
+        let cb2 = PrimaryCommandBufferBuilder::new(&pool).update_buffer(&buffer, &2).build();
+
+        // The fences are kept alive by this test, so that they aren't returned to the pool
+        // when the submissions are destroyed.
+        let fence_pool = FencePool::new(&device);
+        let fence1 = Arc::new(Fence::raw(&device).unwrap());
+        let fence2 = Arc::new(Fence::raw(&device).unwrap());
+
+        let fences = [&*fence1, &*fence2];
+
+        {
+            let guard1 = FencePool::wrap(&fence_pool, fence1.clone());
+            let guard2 = FencePool::wrap(&fence_pool, fence2.clone());
+            let _submission1 = submit_with(&cb1, &queue, guard1, &[], &[]).unwrap();
+            let _submission2 = submit_with(&cb2, &queue, guard2, &[], &[]).unwrap();
+            Fence::wait_multiple(fences.iter().cloned(), true, Duration::new(5, 0)).unwrap();
+        }
+
+        Fence::multi_reset(fences.iter().cloned()).unwrap();
+        assert!(!fence1.ready().unwrap());
+        assert!(!fence2.ready().unwrap());
+
+        // The fence can be used by another submission once it has been reset.
+        let guard = FencePool::wrap(&fence_pool, fence1.clone());
+        let _submission = submit_with(&cb1, &queue, guard, &[], &[]).unwrap();
+        fence1.wait(Duration::new(5, 0)).unwrap();
+    }
+
+    #[test]
+    fn reset_in_use() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let fence = Fence::new(&device);
+        fence.mark_submitted();
+
+        match fence.reset() {
+            Err(FenceResetError::InUse) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn multi_reset_in_use() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let fence1 = Fence::signaled(&device);
+        let fence2 = Fence::new(&device);
+        fence2.mark_submitted();
+
+        match Fence::multi_reset([&*fence1, &*fence2].iter().cloned()) {
+            Err(FenceResetError::InUse) => (),
+            _ => panic!()
+        }
+
+        // None of the fences has been reset.
+        assert!(fence1.ready().unwrap());
+    }
+
     #[test]
     #[should_panic = "Tried to reset multiple fences that didn't belong to the same device"]
     fn multireset_different_devices() {
@@ -0,0 +1,211 @@
+// Copyright (c) 2016 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::mem;
+use std::ops::Deref;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use device::Device;
+use sync::Fence;
+use sync::FenceResetError;
+use OomError;
+
+/// Pool of fences that can be reused.
+///
+/// Submissions that are waited upon once and then thrown away don't need a brand new fence
+/// every time. Instead a fence can be taken from the pool with `get`, which returns a
+/// `FenceGuard`. When the guard is destroyed, the fence is returned to the pool.
+///
+/// Each device has a default pool that you can access with `Device::fence_pool()`.
+#[derive(Debug)]
+pub struct FencePool {
+    device: Arc<Device>,
+
+    // Fences that can be reused. They may be signaled, and are reset before being handed out.
+    free: Mutex<Vec<Fence>>,
+}
+
+impl FencePool {
+    /// Creates a new empty pool.
+    #[inline]
+    pub fn new(device: &Arc<Device>) -> Arc<FencePool> {
+        Arc::new(FencePool {
+            device: device.clone(),
+            free: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Returns the device this pool belongs to.
+    #[inline]
+    pub fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+
+    /// Takes an unsignaled fence from the pool, or creates a new one if no fence is available.
+    pub fn get(me: &Arc<Self>) -> Result<FenceGuard, OomError> {
+        let fence = me.free.lock().unwrap().pop();
+
+        let fence = match fence {
+            Some(fence) => {
+                match fence.reset() {
+                    Ok(()) => fence,
+                    Err(FenceResetError::OomError(err)) => return Err(err),
+                    Err(FenceResetError::InUse) => {
+                        // Fences are only returned to the pool once they are signaled, so this
+                        // shouldn't happen. The fence is put back and a new one is created.
+                        me.free.lock().unwrap().push(fence);
+                        try!(Fence::raw(&me.device))
+                    },
+                }
+            },
+            None => try!(Fence::raw(&me.device)),
+        };
+
+        Ok(FenceGuard {
+            fence: Some(Arc::new(fence)),
+            pool: me.clone(),
+        })
+    }
+
+    /// Wraps a fence that wasn't taken from the pool in a `FenceGuard`, so that it can be used
+    /// for a submission.
+    ///
+    /// The fence must be unsignaled. If the guard holds the last reference to the fence when it
+    /// is destroyed, the fence is added to the pool.
+    #[doc(hidden)]
+    #[inline]
+    pub fn wrap(me: &Arc<Self>, fence: Arc<Fence>) -> FenceGuard {
+        FenceGuard {
+            fence: Some(fence),
+            pool: me.clone(),
+        }
+    }
+
+    /// Returns the number of fences that are ready to be reused.
+    #[inline]
+    pub fn num_free(&self) -> usize {
+        self.free.lock().unwrap().len()
+    }
+}
+
+/// A fence that has been taken from a `FencePool`.
+///
+/// When the guard is destroyed, the fence is returned to the pool:
+///
+/// - If the fence has been submitted to a queue and isn't signaled yet, the destructor blocks
+///   until the fence is signaled. Destroying or resetting a fence that is in use by the GPU
+///   isn't allowed, so this is the only way to reuse it.
+/// - If the `Arc` returned by `fence()` has been cloned and is still alive, the fence isn't
+///   returned to the pool and is destroyed normally when the last clone is dropped.
+#[derive(Debug)]
+pub struct FenceGuard {
+    // Always `Some`, except during the destructor.
+    fence: Option<Arc<Fence>>,
+    pool: Arc<FencePool>,
+}
+
+impl FenceGuard {
+    /// Returns the fence.
+    #[inline]
+    pub fn fence(&self) -> &Arc<Fence> {
+        self.fence.as_ref().unwrap()
+    }
+
+    /// Returns the pool the fence belongs to.
+    #[inline]
+    pub fn pool(&self) -> &Arc<FencePool> {
+        &self.pool
+    }
+}
+
+impl Deref for FenceGuard {
+    type Target = Fence;
+
+    #[inline]
+    fn deref(&self) -> &Fence {
+        self.fence.as_ref().unwrap()
+    }
+}
+
+impl Drop for FenceGuard {
+    fn drop(&mut self) {
+        let fence = match Arc::try_unwrap(self.fence.take().unwrap()) {
+            Ok(fence) => fence,
+            Err(_) => return,
+        };
+
+        if fence.is_submitted() && !fence.ready().unwrap_or(false) {
+            if fence.wait_forever().is_err() {
+                // The fence may still be in use, so we can neither reuse nor destroy it.
+                mem::forget(fence);
+                return;
+            }
+        }
+
+        self.pool.free.lock().unwrap().push(fence);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use buffer::BufferUsage;
+    use buffer::CpuAccessibleBuffer;
+    use command_buffer::CommandBufferPool;
+    use command_buffer::PrimaryCommandBufferBuilder;
+    use command_buffer::submit;
+    use sync::FencePool;
+
+    #[test]
+    fn fence_reused() {
+        let (device, _) = gfx_dev_and_queue!();
+        let pool = FencePool::new(&device);
+
+        drop(FencePool::get(&pool).unwrap());
+        assert_eq!(pool.num_free(), 1);
+
+        let fence = FencePool::get(&pool).unwrap();
+        assert_eq!(pool.num_free(), 0);
+        assert!(!fence.ready().unwrap());
+    }
+
+    #[test]
+    fn cloned_fence_not_reused() {
+        let (device, _) = gfx_dev_and_queue!();
+        let pool = FencePool::new(&device);
+
+        let fence = FencePool::get(&pool).unwrap();
+        let _clone = fence.fence().clone();
+        drop(fence);
+        assert_eq!(pool.num_free(), 0);
+    }
+
+    #[test]
+    fn repeated_submissions() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb_pool = CommandBufferPool::new(&device, &queue.family());
+        let usage = BufferUsage { transfer_dest: true, .. BufferUsage::none() };
+        let buffer = CpuAccessibleBuffer::<u32>::new(&device, &usage,
+                                                     Some(queue.family())).unwrap();
+
+        for i in 0 .. 8 {
+            let cb = PrimaryCommandBufferBuilder::new(&cb_pool).update_buffer(&buffer, &i).build();
+            let submission = submit(&cb, &queue).unwrap();
+            submission.wait(Duration::new(5, 0)).unwrap();
+
+            // The submission took the only free fence, if any, and gives it back when dropped.
+            assert_eq!(device.fence_pool().num_free(), 0);
+            drop(submission);
+            assert_eq!(device.fence_pool().num_free(), 1);
+        }
+    }
+}
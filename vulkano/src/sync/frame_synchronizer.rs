@@ -0,0 +1,448 @@
+// Copyright (c) 2016 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::mem;
+use std::sync::Arc;
+use std::time::Duration;
+
+use command_buffer::PrimaryCommandBuffer;
+use command_buffer::Submission;
+use command_buffer::submit_with;
+use device::Device;
+use device::Queue;
+use swapchain::AcquireError;
+use swapchain::PresentError;
+use swapchain::Swapchain;
+use sync::Fence;
+use sync::FencePool;
+use sync::FenceResetError;
+use sync::FenceWaitError;
+use sync::PipelineStages;
+use sync::Semaphore;
+use OomError;
+
+/// Limits the number of frames that the GPU can be processing at the same time, and provides
+/// the synchronization primitives that each frame needs.
+///
+/// When rendering to a swapchain, the CPU usually prepares frame N+1 while the GPU is still
+/// drawing frame N. Without any limit the CPU could get arbitrarily far ahead of the GPU. The
+/// `FrameSynchronizer` keeps one slot per frame in flight. Each slot owns:
+///
+/// - A semaphore that is signaled when the swapchain image of the frame is available.
+/// - A semaphore that is signaled when the rendering of the frame is finished.
+/// - A fence that is signaled when the submission of the frame is finished.
+///
+/// Beginning a frame in a slot waits until the fence of that slot is signaled, in other words
+/// until the previous frame that used the slot has finished executing.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut frames = FrameSynchronizer::new(&device, 2);
+///
+/// loop {
+///     let mut slot = frames.begin_frame(Duration::new(1, 0)).unwrap();
+///     let image_num = slot.acquire_next_image(&swapchain, Duration::new(1, 0)).unwrap();
+///     slot.submit(&command_buffers[image_num], &queue).unwrap();
+///     slot.present(&swapchain, &queue, image_num).unwrap();
+/// }
+/// ```
+#[derive(Debug)]
+pub struct FrameSynchronizer {
+    device: Arc<Device>,
+
+    // One entry for each frame in flight.
+    slots: Vec<SlotState>,
+
+    // Index of the slot that is going to be used by the next call to `begin_frame`.
+    current: usize,
+}
+
+// The synchronization primitives of a slot.
+#[derive(Debug)]
+struct SlotState {
+    image_available: Arc<Semaphore>,
+    render_finished: Arc<Semaphore>,
+    fence: Arc<Fence>,
+
+    // The latest submission that was made with this slot. Signals `fence` when it is finished.
+    submission: Option<Arc<Submission>>,
+
+    // True if `image_available` is going to be signaled by an acquisition, and no submission
+    // has waited upon it yet.
+    image_available_pending: bool,
+
+    // True if `render_finished` is going to be signaled by a submission, and no presentation has
+    // waited upon it yet.
+    render_finished_pending: bool,
+}
+
+impl SlotState {
+    fn new(device: &Arc<Device>) -> Result<SlotState, OomError> {
+        Ok(SlotState {
+            image_available: Arc::new(try!(Semaphore::raw(device))),
+            render_finished: Arc::new(try!(Semaphore::raw(device))),
+            fence: Arc::new(try!(Fence::raw(device))),
+            submission: None,
+            image_available_pending: false,
+            render_finished_pending: false,
+        })
+    }
+}
+
+impl Drop for SlotState {
+    #[inline]
+    fn drop(&mut self) {
+        if self.image_available_pending {
+            // The presentation engine may still signal the semaphore, so it can't be destroyed.
+            mem::forget(self.image_available.clone());
+        }
+    }
+}
+
+impl FrameSynchronizer {
+    /// Builds a new `FrameSynchronizer` that allows `frames_in_flight` frames to be processed
+    /// by the GPU at the same time.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if `frames_in_flight` is 0.
+    /// - Panicks if the device or host ran out of memory.
+    ///
+    #[inline]
+    pub fn new(device: &Arc<Device>, frames_in_flight: usize) -> FrameSynchronizer {
+        FrameSynchronizer::raw(device, frames_in_flight).unwrap()
+    }
+
+    /// Builds a new `FrameSynchronizer` that allows `frames_in_flight` frames to be processed
+    /// by the GPU at the same time.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if `frames_in_flight` is 0.
+    ///
+    pub fn raw(device: &Arc<Device>, frames_in_flight: usize)
+               -> Result<FrameSynchronizer, OomError>
+    {
+        assert!(frames_in_flight >= 1, "A FrameSynchronizer must have at least one frame in flight");
+
+        let mut slots = Vec::with_capacity(frames_in_flight);
+        for _ in 0 .. frames_in_flight {
+            slots.push(try!(SlotState::new(device)));
+        }
+
+        Ok(FrameSynchronizer {
+            device: device.clone(),
+            slots: slots,
+            current: 0,
+        })
+    }
+
+    /// Returns the device this synchronizer was created with.
+    #[inline]
+    pub fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+
+    /// Returns the maximum number of frames that can be processed at the same time.
+    #[inline]
+    pub fn frames_in_flight(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns the index of the slot that is going to be used by the next frame.
+    #[inline]
+    pub fn current_index(&self) -> usize {
+        self.current
+    }
+
+    /// Waits until the slot of the next frame is free, resets its fence, and returns it.
+    ///
+    /// The slot is free once the submission of the last frame that used it has finished
+    /// executing. Returns `Err` if the timeout was reached instead, in which case the slot
+    /// isn't modified and this function can be called again.
+    pub fn begin_frame(&mut self, timeout: Duration) -> Result<FrameSlot, FenceWaitError> {
+        {
+            let slot = &mut self.slots[self.current];
+
+            if slot.submission.is_some() {
+                try!(slot.fence.wait(timeout));
+                // The submission holds a clone of the fence and must be destroyed before the
+                // fence is reset.
+                slot.submission = None;
+                match slot.fence.reset() {
+                    Ok(()) => (),
+                    Err(FenceResetError::OomError(err)) => return Err(err.into()),
+                    // The fence has just been waited upon, therefore it is signaled.
+                    Err(FenceResetError::InUse) => unreachable!(),
+                }
+            }
+
+            if slot.render_finished_pending {
+                // The previous frame was submitted but not presented. Its submission is finished
+                // so the semaphore is signaled, and a new one is needed.
+                slot.render_finished = Arc::new(try!(Semaphore::raw(&self.device)));
+                slot.render_finished_pending = false;
+            }
+
+            if slot.image_available_pending {
+                // An image was acquired but no submission waited upon the semaphore. The
+                // presentation engine may still signal it, so it is leaked instead of destroyed.
+                mem::forget(slot.image_available.clone());
+                slot.image_available = Arc::new(try!(Semaphore::raw(&self.device)));
+                slot.image_available_pending = false;
+            }
+        }
+
+        Ok(FrameSlot {
+            synchronizer: self,
+        })
+    }
+}
+
+/// A frame slot returned by `FrameSynchronizer::begin_frame`.
+///
+/// A frame usually acquires a swapchain image with `acquire_next_image`, submits a command buffer
+/// that draws to it with `submit`, and presents it with `present`. These three functions take
+/// care of the semaphores and of the fence of the slot.
+///
+/// When the slot is destroyed, the synchronizer advances to the next slot.
+#[derive(Debug)]
+pub struct FrameSlot<'a> {
+    synchronizer: &'a mut FrameSynchronizer,
+}
+
+impl<'a> FrameSlot<'a> {
+    /// Returns the index of this slot, between 0 and `frames_in_flight()`.
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.synchronizer.current
+    }
+
+    /// Returns the semaphore that is signaled when the image acquired by `acquire_next_image`
+    /// is available.
+    #[inline]
+    pub fn image_available(&self) -> &Arc<Semaphore> {
+        &self.state().image_available
+    }
+
+    /// Returns the semaphore that is signaled when the submission made by `submit` is finished,
+    /// and that `present` waits upon.
+    #[inline]
+    pub fn render_finished(&self) -> &Arc<Semaphore> {
+        &self.state().render_finished
+    }
+
+    /// Returns the fence that is signaled when the submission made by `submit` is finished.
+    ///
+    /// The fence is reset by `begin_frame`.
+    #[inline]
+    pub fn fence(&self) -> &Arc<Fence> {
+        &self.state().fence
+    }
+
+    /// Acquires an image of the swapchain. The `image_available` semaphore is signaled once the
+    /// image is ready, and the next call to `submit` waits upon it.
+    ///
+    /// The image must then be presented with `present`.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if an image was already acquired or a command buffer was already submitted
+    ///   during this frame.
+    ///
+    pub fn acquire_next_image(&mut self, swapchain: &Swapchain, timeout: Duration)
+                              -> Result<usize, AcquireError>
+    {
+        let state = self.state_mut();
+        assert!(!state.image_available_pending && state.submission.is_none(),
+                "An image can only be acquired once per frame, before the submission");
+
+        let id = try!(unsafe {
+            swapchain.acquire_next_image_with_semaphore(timeout, &state.image_available)
+        });
+        state.image_available_pending = true;
+        Ok(id)
+    }
+
+    /// Submits a command buffer to a queue.
+    ///
+    /// If an image was acquired with `acquire_next_image`, the submission waits for it to be
+    /// available before writing color attachments. The submission signals the `render_finished`
+    /// semaphore and the fence of the slot when it is finished.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if a command buffer was already submitted during this frame.
+    /// - Panicks if the queue doesn't belong to the device of the synchronizer.
+    ///
+    pub fn submit(&mut self, command_buffer: &Arc<PrimaryCommandBuffer>, queue: &Arc<Queue>)
+                  -> Result<(), OomError>
+    {
+        assert_eq!(&**queue.device() as *const Device,
+                   &*self.synchronizer.device as *const Device);
+
+        let state = self.state_mut();
+        assert!(state.submission.is_none(), "Only one submission can be made during a frame");
+
+        let stages = PipelineStages {
+            color_attachment_output: true,
+            .. PipelineStages::none()
+        };
+
+        let mut wait_semaphores = Vec::with_capacity(1);
+        if state.image_available_pending {
+            wait_semaphores.push((state.image_available.clone(), stages));
+        }
+
+        let fence = FencePool::wrap(&queue.device().fence_pool(), state.fence.clone());
+        let submission = try!(submit_with(command_buffer, queue, fence, &wait_semaphores,
+                                          &[state.render_finished.clone()]));
+
+        state.submission = Some(submission);
+        state.image_available_pending = false;
+        state.render_finished_pending = true;
+        Ok(())
+    }
+
+    /// Presents an image acquired with `acquire_next_image` once the submission made with
+    /// `submit` is finished.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if no command buffer was submitted during this frame.
+    /// - Panicks if the image wasn't acquired with `acquire_next_image` during this frame.
+    ///
+    pub fn present(&mut self, swapchain: &Swapchain, queue: &Arc<Queue>, index: usize)
+                   -> Result<(), PresentError>
+    {
+        let state = self.state_mut();
+        assert!(state.render_finished_pending,
+                "A command buffer must be submitted before the image is presented");
+
+        try!(unsafe { swapchain.present_with_semaphore(queue, index, &state.render_finished) });
+        state.render_finished_pending = false;
+        Ok(())
+    }
+
+    #[inline]
+    fn state(&self) -> &SlotState {
+        &self.synchronizer.slots[self.synchronizer.current]
+    }
+
+    #[inline]
+    fn state_mut(&mut self) -> &mut SlotState {
+        &mut self.synchronizer.slots[self.synchronizer.current]
+    }
+}
+
+impl<'a> Drop for FrameSlot<'a> {
+    #[inline]
+    fn drop(&mut self) {
+        let current = self.synchronizer.current;
+        self.synchronizer.current = (current + 1) % self.synchronizer.slots.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use buffer::BufferUsage;
+    use buffer::CpuAccessibleBuffer;
+    use command_buffer::CommandBufferPool;
+    use command_buffer::PrimaryCommandBuffer;
+    use command_buffer::PrimaryCommandBufferBuilder;
+    use sync::FrameSynchronizer;
+
+    #[test]
+    #[should_panic = "A FrameSynchronizer must have at least one frame in flight"]
+    fn zero_frames() {
+        let (device, _) = gfx_dev_and_queue!();
+        let _ = FrameSynchronizer::new(&device, 0);
+    }
+
+    #[test]
+    fn slots_rotate() {
+        let (device, _) = gfx_dev_and_queue!();
+        let mut frames = FrameSynchronizer::new(&device, 3);
+        assert_eq!(frames.frames_in_flight(), 3);
+
+        for i in 0 .. 7 {
+            assert_eq!(frames.current_index(), i % 3);
+            let slot = frames.begin_frame(Duration::new(0, 0)).unwrap();
+            assert_eq!(slot.index(), i % 3);
+        }
+    }
+
+    #[test]
+    fn slots_have_distinct_primitives() {
+        let (device, _) = gfx_dev_and_queue!();
+        let mut frames = FrameSynchronizer::new(&device, 2);
+
+        let (sem1, sem2, fence1) = {
+            let slot = frames.begin_frame(Duration::new(0, 0)).unwrap();
+            assert!(!Arc::ptr_eq(slot.image_available(), slot.render_finished()));
+            (slot.image_available().clone(), slot.render_finished().clone(), slot.fence().clone())
+        };
+
+        let slot = frames.begin_frame(Duration::new(0, 0)).unwrap();
+        assert!(!Arc::ptr_eq(slot.image_available(), &sem1));
+        assert!(!Arc::ptr_eq(slot.render_finished(), &sem2));
+        assert!(!Arc::ptr_eq(slot.fence(), &fence1));
+    }
+
+    #[test]
+    fn fences_gate_reuse() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let mut frames = FrameSynchronizer::new(&device, 2);
+
+        let pool = CommandBufferPool::new(&device, &queue.family());
+        let usage = BufferUsage { transfer_dest: true, .. BufferUsage::none() };
+        let buffer = CpuAccessibleBuffer::<u32>::new(&device, &usage,
+                                                     Some(queue.family())).unwrap();
+
+        let mut command_buffers: Vec<Arc<PrimaryCommandBuffer>> = Vec::new();
+
+        for i in 0 .. 6 {
+            let mut slot = frames.begin_frame(Duration::new(5, 0)).unwrap();
+            assert_eq!(slot.index(), i % 2);
+            assert!(!slot.fence().ready().unwrap());
+
+            // The submission of the previous frame in this slot holds the last other reference
+            // to its command buffer, and has been destroyed once its fence was signaled.
+            if i >= 2 {
+                assert_eq!(Arc::strong_count(&command_buffers[i - 2]), 1);
+            }
+
+            let cb = PrimaryCommandBufferBuilder::new(&pool).update_buffer(&buffer, &(i as u32))
+                                                            .build();
+            slot.submit(&cb, &queue).unwrap();
+            command_buffers.push(cb);
+        }
+    }
+
+    #[test]
+    #[should_panic = "Only one submission can be made during a frame"]
+    fn submit_twice() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let mut frames = FrameSynchronizer::new(&device, 2);
+
+        let pool = CommandBufferPool::new(&device, &queue.family());
+        let usage = BufferUsage { transfer_dest: true, .. BufferUsage::none() };
+        let buffer = CpuAccessibleBuffer::<u32>::new(&device, &usage,
+                                                     Some(queue.family())).unwrap();
+        let cb = PrimaryCommandBufferBuilder::new(&pool).update_buffer(&buffer, &0).build();
+
+        let mut slot = frames.begin_frame(Duration::new(0, 0)).unwrap();
+        slot.submit(&cb, &queue).unwrap();
+        slot.submit(&cb, &queue).unwrap();
+    }
+}
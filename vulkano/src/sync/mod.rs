@@ -24,12 +24,23 @@ use vk;
 
 pub use self::event::Event;
 pub use self::fence::Fence;
+pub use self::fence::FenceResetError;
 pub use self::fence::FenceWaitError;
+pub use self::fence_pool::FenceGuard;
+pub use self::fence_pool::FencePool;
+pub use self::frame_synchronizer::FrameSlot;
+pub use self::frame_synchronizer::FrameSynchronizer;
 pub use self::semaphore::Semaphore;
+pub use self::semaphore::SemaphoreCreationError;
+pub use self::semaphore_pool::SemaphoreGuard;
+pub use self::semaphore_pool::SemaphorePool;
 
 mod event;
 mod fence;
+mod fence_pool;
+mod frame_synchronizer;
 mod semaphore;
+mod semaphore_pool;
 
 /// Base trait for objects that can be used as resources and must be synchronized.
 // TODO: remove
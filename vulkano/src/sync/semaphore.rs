@@ -7,6 +7,8 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
+use std::error;
+use std::fmt;
 use std::mem;
 use std::ptr;
 use std::sync::Arc;
@@ -23,7 +25,11 @@ use vk;
 /// 
 /// It is similar to a fence, except that it is purely on the GPU side. The CPU can't query a
 /// semaphore's status or wait for it to be signaled.
-#[derive(Debug)]
+///
+/// Contrary to `Fence`, there is no way to create a semaphore that is already signaled, as Vulkan
+/// doesn't provide any flag for this. A semaphore always starts unsignaled and can only be
+/// signaled by a queue operation. Requesting a signaled semaphore with `raw_with_state` returns
+/// `SemaphoreCreationError::SignaledNotSupported`.
 pub struct Semaphore<D = Arc<Device>> where D: SafeDeref<Target = Device> {
     semaphore: vk::Semaphore,
     device: D,
@@ -57,6 +63,23 @@ impl<D> Semaphore<D> where D: SafeDeref<Target = Device> {
         })
     }
 
+    /// Same as `raw`, but lets you choose whether the semaphore starts signaled, which makes
+    /// the API symmetric with `Fence`.
+    ///
+    /// Vulkan can't create signaled semaphores, so `SignaledNotSupported` is always returned if
+    /// `signaled` is true.
+    #[inline]
+    pub fn raw_with_state(device: &D, signaled: bool)
+                          -> Result<Semaphore<D>, SemaphoreCreationError>
+        where D: Clone
+    {
+        if signaled {
+            return Err(SemaphoreCreationError::SignaledNotSupported);
+        }
+
+        Ok(try!(Semaphore::raw(device)))
+    }
+
     /// Builds a new semaphore.
     ///
     /// # Panic
@@ -71,6 +94,8 @@ impl<D> Semaphore<D> where D: SafeDeref<Target = Device> {
     }
 }
 
+impl_debug_handle!(Semaphore<D> where D: SafeDeref<Target = Device>);
+
 unsafe impl<D> VulkanObject for Semaphore<D> where D: SafeDeref<Target = Device> {
     type Object = vk::Semaphore;
 
@@ -90,13 +115,74 @@ impl<D> Drop for Semaphore<D> where D: SafeDeref<Target = Device> {
     }
 }
 
+/// Error that can be returned when creating a semaphore.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SemaphoreCreationError {
+    /// Not enough memory to create the semaphore.
+    OomError(OomError),
+
+    /// A signaled semaphore was requested, but Vulkan can only create unsignaled semaphores.
+    SignaledNotSupported,
+}
+
+impl error::Error for SemaphoreCreationError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            SemaphoreCreationError::OomError(_) => "no memory available",
+            SemaphoreCreationError::SignaledNotSupported => {
+                "semaphores can't be created in the signaled state"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            SemaphoreCreationError::OomError(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for SemaphoreCreationError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<OomError> for SemaphoreCreationError {
+    #[inline]
+    fn from(err: OomError) -> SemaphoreCreationError {
+        SemaphoreCreationError::OomError(err)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use sync::Semaphore;
+    use sync::SemaphoreCreationError;
 
     #[test]
     fn semaphore_create() {
         let (device, _) = gfx_dev_and_queue!();
         let _ = Semaphore::new(&device);
     }
+
+    #[test]
+    fn semaphore_create_unsignaled() {
+        let (device, _) = gfx_dev_and_queue!();
+        let _ = Semaphore::raw_with_state(&device, false).unwrap();
+    }
+
+    #[test]
+    fn semaphore_create_signaled() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        match Semaphore::raw_with_state(&device, true) {
+            Err(SemaphoreCreationError::SignaledNotSupported) => (),
+            _ => panic!()
+        }
+    }
 }
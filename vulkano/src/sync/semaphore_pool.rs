@@ -0,0 +1,258 @@
+// Copyright (c) 2016 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::mem;
+use std::ops::Deref;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use command_buffer::Submission;
+use device::Device;
+use sync::Fence;
+use sync::Semaphore;
+use OomError;
+
+/// Pool of semaphores that can be reused.
+///
+/// Creating and destroying semaphores every frame is wasteful. Instead you can get a semaphore
+/// from the pool with `get`, which returns a `SemaphoreGuard`. When the guard is destroyed, the
+/// semaphore is returned to the pool so that it can be reused later.
+///
+/// Since a semaphore can't be reused while it's still in use by the GPU, you can attach a fence
+/// or a submission to the guard with `SemaphoreGuard::defer_until` or
+/// `SemaphoreGuard::defer_until_submission`. In that case the semaphore will only be reused after
+/// the fence has been signaled or the submission has finished.
+///
+/// Each device has a default pool that you can access with `Device::semaphore_pool()`. It is
+/// created the first time it is requested.
+#[derive(Debug)]
+pub struct SemaphorePool {
+    device: Arc<Device>,
+    guarded: Mutex<Guarded>,
+}
+
+#[derive(Debug)]
+struct Guarded {
+    // Semaphores that can be reused immediately.
+    free: Vec<Semaphore>,
+
+    // Semaphores that will be free once the corresponding fence is signaled.
+    pending: Vec<(Arc<Fence>, Semaphore)>,
+}
+
+impl SemaphorePool {
+    /// Creates a new empty pool.
+    #[inline]
+    pub fn new(device: &Arc<Device>) -> Arc<SemaphorePool> {
+        Arc::new(SemaphorePool {
+            device: device.clone(),
+            guarded: Mutex::new(Guarded {
+                free: Vec::new(),
+                pending: Vec::new(),
+            }),
+        })
+    }
+
+    /// Returns the device this pool belongs to.
+    #[inline]
+    pub fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+
+    /// Takes a semaphore from the pool, or creates a new one if no semaphore is available.
+    pub fn get(me: &Arc<Self>) -> Result<SemaphoreGuard, OomError> {
+        let semaphore = {
+            let mut guarded = me.guarded.lock().unwrap();
+            guarded.collect_pending();
+            guarded.free.pop()
+        };
+
+        let semaphore = match semaphore {
+            Some(s) => s,
+            None => try!(Semaphore::raw(&me.device)),
+        };
+
+        Ok(SemaphoreGuard {
+            semaphore: Some(semaphore),
+            pool: me.clone(),
+            fence: None,
+        })
+    }
+
+    /// Returns the number of semaphores that are ready to be reused.
+    ///
+    /// Semaphores whose fence has been signaled in the meantime are counted as well.
+    #[inline]
+    pub fn num_free(&self) -> usize {
+        let mut guarded = self.guarded.lock().unwrap();
+        guarded.collect_pending();
+        guarded.free.len()
+    }
+}
+
+impl Guarded {
+    // Moves the semaphores whose fence is signaled from `pending` to `free`.
+    fn collect_pending(&mut self) {
+        let pending = mem::replace(&mut self.pending, Vec::new());
+
+        for (fence, semaphore) in pending.into_iter() {
+            if fence.ready().unwrap_or(false) {
+                self.free.push(semaphore);
+            } else {
+                self.pending.push((fence, semaphore));
+            }
+        }
+    }
+}
+
+/// A semaphore that has been taken from a `SemaphorePool`.
+///
+/// The semaphore is returned to the pool when the guard is destroyed.
+#[derive(Debug)]
+pub struct SemaphoreGuard {
+    // Always `Some`, except during the destructor.
+    semaphore: Option<Semaphore>,
+    pool: Arc<SemaphorePool>,
+    fence: Option<Arc<Fence>>,
+}
+
+impl SemaphoreGuard {
+    /// Prevents the semaphore from being reused before `fence` is signaled.
+    ///
+    /// Calling this function again replaces the previous fence.
+    #[inline]
+    pub fn defer_until(&mut self, fence: &Arc<Fence>) {
+        self.fence = Some(fence.clone());
+    }
+
+    /// Prevents the semaphore from being reused before `submission` has finished.
+    ///
+    /// You should call this with the submission that uses the semaphore. Calling this function
+    /// again replaces the previous fence or submission.
+    #[inline]
+    pub fn defer_until_submission(&mut self, submission: &Arc<Submission>) {
+        self.fence = Some(submission.fence().clone());
+    }
+
+    /// Returns the pool the semaphore belongs to.
+    #[inline]
+    pub fn pool(&self) -> &Arc<SemaphorePool> {
+        &self.pool
+    }
+}
+
+impl Deref for SemaphoreGuard {
+    type Target = Semaphore;
+
+    #[inline]
+    fn deref(&self) -> &Semaphore {
+        self.semaphore.as_ref().unwrap()
+    }
+}
+
+impl Drop for SemaphoreGuard {
+    fn drop(&mut self) {
+        let semaphore = self.semaphore.take().unwrap();
+        let mut guarded = self.pool.guarded.lock().unwrap();
+
+        match self.fence.take() {
+            Some(ref fence) if !fence.ready().unwrap_or(false) => {
+                guarded.pending.push((fence.clone(), semaphore));
+            },
+            _ => guarded.free.push(semaphore),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use buffer::BufferUsage;
+    use buffer::CpuAccessibleBuffer;
+    use command_buffer::CommandBufferPool;
+    use command_buffer::PrimaryCommandBufferBuilder;
+    use command_buffer::submit;
+    use device::Device;
+    use sync::Fence;
+    use sync::SemaphorePool;
+
+    #[test]
+    fn pool_size_stabilizes() {
+        let (device, _) = gfx_dev_and_queue!();
+        let pool = SemaphorePool::new(&device);
+
+        for _ in 0 .. 16 {
+            let s1 = SemaphorePool::get(&pool).unwrap();
+            let s2 = SemaphorePool::get(&pool).unwrap();
+            let s3 = SemaphorePool::get(&pool).unwrap();
+            drop((s1, s2, s3));
+            assert_eq!(pool.num_free(), 3);
+        }
+    }
+
+    #[test]
+    fn semaphore_reused() {
+        let (device, _) = gfx_dev_and_queue!();
+        let pool = SemaphorePool::new(&device);
+
+        drop(SemaphorePool::get(&pool).unwrap());
+        assert_eq!(pool.num_free(), 1);
+
+        let _semaphore = SemaphorePool::get(&pool).unwrap();
+        assert_eq!(pool.num_free(), 0);
+    }
+
+    #[test]
+    fn deferred_until_fence() {
+        let (device, _) = gfx_dev_and_queue!();
+        let pool = SemaphorePool::new(&device);
+
+        let fence = Fence::new(&device);
+        let mut semaphore = SemaphorePool::get(&pool).unwrap();
+        semaphore.defer_until(&fence);
+        drop(semaphore);
+        assert_eq!(pool.num_free(), 0);
+
+        let signaled = Fence::signaled(&device);
+        let mut semaphore = SemaphorePool::get(&pool).unwrap();
+        semaphore.defer_until(&signaled);
+        drop(semaphore);
+        assert_eq!(pool.num_free(), 1);
+    }
+
+    #[test]
+    fn deferred_until_submission() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let pool = SemaphorePool::new(&device);
+
+        let cb_pool = CommandBufferPool::new(&device, &queue.family());
+        let usage = BufferUsage { transfer_dest: true, .. BufferUsage::none() };
+        let buffer = CpuAccessibleBuffer::<u32>::new(&device, &usage,
+                                                     Some(queue.family())).unwrap();
+        let cb = PrimaryCommandBufferBuilder::new(&cb_pool).update_buffer(&buffer, &1).build();
+        let submission = submit(&cb, &queue).unwrap();
+
+        let mut semaphore = SemaphorePool::get(&pool).unwrap();
+        semaphore.defer_until_submission(&submission);
+        drop(semaphore);
+
+        submission.wait(Duration::new(5, 0)).unwrap();
+        assert_eq!(pool.num_free(), 1);
+    }
+
+    #[test]
+    fn device_pool() {
+        let (device, _) = gfx_dev_and_queue!();
+        let pool = Device::semaphore_pool(&device);
+
+        drop(SemaphorePool::get(&pool).unwrap());
+        assert_eq!(Device::semaphore_pool(&device).num_free(), 1);
+    }
+}
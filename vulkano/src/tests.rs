@@ -65,3 +65,114 @@ macro_rules! gfx_dev_and_queue {
         (device, queues.next().unwrap())
     });
 }
+
+#[test]
+fn debug_shows_handles() {
+    use std::iter;
+
+    use command_buffer::CommandBufferPool;
+    use command_buffer::PrimaryCommandBufferBuilder;
+    use descriptor::descriptor_set::UnsafeDescriptorSetLayout;
+    use descriptor::pipeline_layout::UnsafePipelineLayout;
+    use framebuffer::EmptySinglePassRenderPass;
+    use framebuffer::RenderPass;
+    use pipeline::cache::PipelineCache;
+    use sampler::Filter;
+    use sampler::MipmapMode;
+    use sampler::Sampler;
+    use sampler::SamplerAddressMode;
+    use sync::Fence;
+    use sync::Semaphore;
+
+    let (device, queue) = gfx_dev_and_queue!();
+
+    fn check<T: ?Sized + ::std::fmt::Debug>(object: &T, name: &str) {
+        let formatted = format!("{:?}", object);
+        assert!(formatted.starts_with(&format!("{}(0x", name)),
+                "`{}` isn't formatted as a {} handle", formatted, name);
+    }
+
+    check(&*device.instance(), "Instance");
+    check(&*device, "Device");
+    let sampler = Sampler::new(&device, Filter::Linear, Filter::Linear, MipmapMode::Nearest,
+                               SamplerAddressMode::Repeat, SamplerAddressMode::Repeat,
+                               SamplerAddressMode::Repeat, 0.0, 1.0, 0.0, 0.0).unwrap();
+    check(&*sampler, "Sampler");
+    check(&*PipelineCache::empty(&device).unwrap(), "PipelineCache");
+    let set_layout = UnsafeDescriptorSetLayout::new(&device, iter::empty());
+    check(&*set_layout, "UnsafeDescriptorSetLayout");
+    let pipeline_layout = UnsafePipelineLayout::new(&device, iter::once(&set_layout),
+                                                    iter::empty()).unwrap();
+    check(&pipeline_layout, "UnsafePipelineLayout");
+    check(EmptySinglePassRenderPass::new(&device).render_pass(), "UnsafeRenderPass");
+    let pool = CommandBufferPool::new(&device, &queue.family());
+    check(&*PrimaryCommandBufferBuilder::new(&pool).build(), "PrimaryCommandBuffer");
+    check(&*Fence::new(&device), "Fence");
+    check(&*Semaphore::new(&device), "Semaphore");
+}
+
+#[test]
+fn result_codes_mapping() {
+    use check_errors;
+    use swapchain::AcquireError;
+    use swapchain::PresentError;
+    use sync::FenceWaitError;
+    use Error;
+    use OomError;
+    use Success;
+    use vk;
+
+    let successes = [
+        (vk::SUCCESS, Success::Success),
+        (vk::NOT_READY, Success::NotReady),
+        (vk::TIMEOUT, Success::Timeout),
+        (vk::EVENT_SET, Success::EventSet),
+        (vk::EVENT_RESET, Success::EventReset),
+        (vk::INCOMPLETE, Success::Incomplete),
+        (vk::SUBOPTIMAL_KHR, Success::Suboptimal),
+    ];
+
+    for &(code, success) in successes.iter() {
+        assert_eq!(check_errors(code), Ok(success));
+    }
+
+    let errors = [
+        (vk::ERROR_OUT_OF_HOST_MEMORY, Error::OutOfHostMemory),
+        (vk::ERROR_OUT_OF_DEVICE_MEMORY, Error::OutOfDeviceMemory),
+        (vk::ERROR_INITIALIZATION_FAILED, Error::InitializationFailed),
+        (vk::ERROR_DEVICE_LOST, Error::DeviceLost),
+        (vk::ERROR_MEMORY_MAP_FAILED, Error::MemoryMapFailed),
+        (vk::ERROR_LAYER_NOT_PRESENT, Error::LayerNotPresent),
+        (vk::ERROR_EXTENSION_NOT_PRESENT, Error::ExtensionNotPresent),
+        (vk::ERROR_FEATURE_NOT_PRESENT, Error::FeatureNotPresent),
+        (vk::ERROR_INCOMPATIBLE_DRIVER, Error::IncompatibleDriver),
+        (vk::ERROR_TOO_MANY_OBJECTS, Error::TooManyObjects),
+        (vk::ERROR_FORMAT_NOT_SUPPORTED, Error::FormatNotSupported),
+        (vk::ERROR_FRAGMENTED_POOL, Error::FragmentedPool),
+        (vk::ERROR_SURFACE_LOST_KHR, Error::SurfaceLost),
+        (vk::ERROR_NATIVE_WINDOW_IN_USE_KHR, Error::NativeWindowInUse),
+        (vk::ERROR_OUT_OF_DATE_KHR, Error::OutOfDate),
+        (vk::ERROR_INCOMPATIBLE_DISPLAY_KHR, Error::IncompatibleDisplay),
+        (vk::ERROR_VALIDATION_FAILED_EXT, Error::ValidationFailed),
+        (vk::ERROR_OUT_OF_POOL_MEMORY_KHR, Error::OutOfPoolMemory),
+        (-1000012345i32 as u32, Error::Unknown(-1000012345i32 as u32)),
+    ];
+
+    for &(code, err) in errors.iter() {
+        assert_eq!(check_errors(code), Err(err));
+
+        // None of the conversions are allowed to panic.
+        let _ = OomError::from(err);
+        let _ = FenceWaitError::from(err);
+        let _ = AcquireError::from(err);
+        let _ = PresentError::from(err);
+    }
+
+    assert_eq!(OomError::from(Error::OutOfHostMemory), OomError::OutOfHostMemory);
+    assert_eq!(OomError::from(Error::DeviceLost), OomError::Unexpected(Error::DeviceLost));
+    assert_eq!(AcquireError::from(Error::OutOfDate), AcquireError::OutOfDate);
+    assert_eq!(AcquireError::from(Error::FragmentedPool),
+               AcquireError::Unexpected(Error::FragmentedPool));
+    assert_eq!(format!("{}", Error::Unknown(-1000012345i32 as u32)),
+               "unknown error code returned by the Vulkan implementation (-1000012345)");
+}